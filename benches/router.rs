@@ -0,0 +1,42 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use kayrx::router::{Path, Router};
+
+/// Router with `n` static and `n` dynamic resources
+fn router(n: usize) -> Router<usize> {
+    let mut router = Router::build();
+    for i in 0..n {
+        router.path(format!("/api/v1/resource{}", i).as_str(), i);
+        router.path(format!("/api/v1/resource{}/{{id}}/items", i).as_str(), n + i);
+    }
+    router.finish()
+}
+
+fn recognize(c: &mut Criterion) {
+    for n in &[10usize, 100, 1000] {
+        let router = router(*n);
+        let last_static = format!("/api/v1/resource{}", n - 1);
+        let last_dynamic = format!("/api/v1/resource{}/42/items", n - 1);
+
+        c.bench_function(&format!("static, {} resources", n * 2), |b| {
+            b.iter(|| {
+                let mut path = Path::new(last_static.as_str());
+                black_box(router.recognize(&mut path).is_some())
+            })
+        });
+        c.bench_function(&format!("dynamic, {} resources", n * 2), |b| {
+            b.iter(|| {
+                let mut path = Path::new(last_dynamic.as_str());
+                black_box(router.recognize(&mut path).is_some())
+            })
+        });
+        c.bench_function(&format!("not found, {} resources", n * 2), |b| {
+            b.iter(|| {
+                let mut path = Path::new("/api/v2/unknown");
+                black_box(router.recognize(&mut path).is_some())
+            })
+        });
+    }
+}
+
+criterion_group!(benches, recognize);
+criterion_main!(benches);
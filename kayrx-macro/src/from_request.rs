@@ -0,0 +1,152 @@
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, Ident, Lit, Meta, NestedMeta};
+
+struct Field {
+    member: syn::Member,
+    binding: Ident,
+    ty: syn::Type,
+    default: bool,
+    map_err: Option<syn::Path>,
+}
+
+impl Field {
+    fn new(idx: usize, field: &syn::Field) -> syn::Result<Self> {
+        let member = match field.ident {
+            Some(ref ident) => syn::Member::Named(ident.clone()),
+            None => syn::Member::Unnamed(idx.into()),
+        };
+        let mut default = false;
+        let mut map_err = None;
+
+        for attr in &field.attrs {
+            if !attr.path.is_ident("from_request") {
+                continue;
+            }
+            let list = match attr.parse_meta()? {
+                Meta::List(list) => list,
+                meta => {
+                    return Err(syn::Error::new_spanned(
+                        meta,
+                        "Expected #[from_request(...)] attribute",
+                    ))
+                }
+            };
+            for item in list.nested {
+                match item {
+                    NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("default") => {
+                        default = true;
+                    }
+                    NestedMeta::Meta(Meta::NameValue(ref nv))
+                        if nv.path.is_ident("map_err") =>
+                    {
+                        if let Lit::Str(ref lit) = nv.lit {
+                            map_err = Some(lit.parse()?);
+                        } else {
+                            return Err(syn::Error::new_spanned(
+                                &nv.lit,
+                                "Attribute map_err expects function path",
+                            ));
+                        }
+                    }
+                    item => {
+                        return Err(syn::Error::new_spanned(
+                            item,
+                            "Unknown attribute key is specified; allowed: default and map_err",
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(Field {
+            member,
+            binding: Ident::new(&format!("__field{}", idx), Span::call_site()),
+            ty: field.ty.clone(),
+            default,
+            map_err,
+        })
+    }
+
+    fn extract(&self) -> TokenStream2 {
+        let Field { binding, ty, .. } = self;
+        quote! {
+            let #binding = <#ty as kayrx::web::FromRequest>::from_request(req, payload);
+        }
+    }
+
+    fn resolve(&self) -> TokenStream2 {
+        let Field { binding, ty, .. } = self;
+        let on_error = if self.default {
+            quote! { <#ty as ::std::default::Default>::default() }
+        } else if let Some(ref map_err) = self.map_err {
+            quote! { return Err(#map_err(e, &__req)) }
+        } else {
+            quote! { return Err(e.into()) }
+        };
+        quote! {
+            let #binding = match #binding.await {
+                Ok(item) => item,
+                Err(e) => #on_error,
+            };
+        }
+    }
+}
+
+pub fn derive(input: TokenStream) -> syn::Result<TokenStream> {
+    let input: DeriveInput = syn::parse(input)?;
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => fields.named.iter().collect::<Vec<_>>(),
+            Fields::Unnamed(ref fields) => fields.unnamed.iter().collect(),
+            Fields::Unit => Vec::new(),
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "FromRequest can be derived only for structs",
+            ))
+        }
+    };
+    let fields = fields
+        .into_iter()
+        .enumerate()
+        .map(|(idx, field)| Field::new(idx, field))
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let extract = fields.iter().map(Field::extract);
+    let clone_req = if fields.iter().any(|f| f.map_err.is_some()) {
+        quote! { let __req = req.clone(); }
+    } else {
+        quote! {}
+    };
+    let resolve = fields.iter().map(Field::resolve);
+    let members = fields.iter().map(|f| &f.member);
+    let bindings = fields.iter().map(|f| &f.binding);
+
+    Ok(quote! {
+        impl #impl_generics kayrx::web::FromRequest for #name #ty_generics #where_clause {
+            type Error = kayrx::http::error::Error;
+            type Future = ::std::pin::Pin<Box<dyn ::std::future::Future<Output = Result<Self, Self::Error>>>>;
+            type Config = ();
+
+            fn from_request(
+                req: &kayrx::web::HttpRequest,
+                payload: &mut kayrx::web::dev::Payload,
+            ) -> Self::Future {
+                #(#extract)*
+                #clone_req
+
+                Box::pin(async move {
+                    #(#resolve)*
+                    Ok(#name { #(#members: #bindings),* })
+                })
+            }
+        }
+    }
+    .into())
+}
@@ -8,7 +8,10 @@ use proc_macro::TokenStream;
 use quote::quote;
 use syn::parse_macro_input;
 
+mod from_request;
+mod responder;
 mod route;
+mod routes;
 
 /// Marks async function to be executed by kayrx-fiber system.
 ///
@@ -286,3 +289,107 @@ pub fn patch(args: TokenStream, input: TokenStream) -> TokenStream {
     };
     gen.generate()
 }
+
+/// Derives `FromRequest` for struct, every field is extracted with its own
+/// `FromRequest` implementation.
+///
+/// Field attributes:
+///
+/// - `#[from_request(default)]` - use `Default::default()` if extraction fails
+/// - `#[from_request(map_err = "path::to::func")]` - convert extraction error,
+///   function signature is `fn(<T as FromRequest>::Error, &HttpRequest) -> Error`
+///
+/// ## Usage
+///
+/// ```rust,ignore
+/// use kayrx::web::{self, FromRequest, HttpRequest};
+///
+/// #[derive(FromRequest)]
+/// struct Params {
+///     info: web::Path<(u32, String)>,
+///     query: web::Query<Paging>,
+///     req: HttpRequest,
+/// }
+///
+/// async fn index(params: Params) -> String {
+///     format!("Welcome {}!", (params.info.1))
+/// }
+/// ```
+#[proc_macro_derive(FromRequest, attributes(from_request))]
+pub fn from_request(input: TokenStream) -> TokenStream {
+    match from_request::derive(input) {
+        Ok(gen) => gen,
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Derives `Responder` for struct or enum.
+///
+/// Response body is a `Display` representation of the value, or json if
+/// `json` attribute is set. Status code and content type could be declared
+/// for the type and overridden for every enum variant.
+///
+/// Type attributes:
+///
+/// - `#[responder(status = 201)]` - response status code, default is `200`
+/// - `#[responder(content_type = "text/html")]` - response content type
+/// - `#[responder(json)]` - serialize value with serde json
+/// - `#[responder(error)]` - generate `ResponseError` implementation as well,
+///   default status code is `500`
+///
+/// Variant attributes: `status` and `content_type`.
+///
+/// ## Usage
+///
+/// ```rust,ignore
+/// use derive_more::Display;
+/// use kayrx::web::Responder;
+///
+/// #[derive(Debug, Display, Responder)]
+/// #[responder(error)]
+/// enum ApiError {
+///     #[display(fmt = "user not found")]
+///     #[responder(status = 404)]
+///     NotFound,
+///     #[display(fmt = "internal error")]
+///     Internal,
+/// }
+/// ```
+#[proc_macro_derive(Responder, attributes(responder))]
+pub fn responder(input: TokenStream) -> TokenStream {
+    match responder::derive(input) {
+        Ok(gen) => gen,
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Builds routing table at compile time.
+///
+/// Syntax: `routes! { METHOD "path" => handler, ... }`
+///
+/// Paths without parameters are dispatched with perfect hash table, seed of
+/// the hash function is selected at compile time. Paths with parameters
+/// are sorted by first static segment. Expands to `kayrx::web::RouteTable`
+/// which could be registered with `App::service()`.
+///
+/// Duplicate method and path pair is a compile error.
+///
+/// ## Usage
+///
+/// ```rust,ignore
+/// use kayrx::web::{self, routes, App};
+///
+/// let app = App::new().service(routes! {
+///     GET "/" => index,
+///     GET "/users" => users,
+///     POST "/users" => create_user,
+///     GET "/users/{id}" => user,
+/// });
+/// ```
+#[proc_macro]
+pub fn routes(input: TokenStream) -> TokenStream {
+    match routes::generate(input) {
+        Ok(gen) => gen,
+        Err(err) => err.to_compile_error().into(),
+    }
+}
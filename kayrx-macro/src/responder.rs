@@ -0,0 +1,199 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Lit, Meta, NestedMeta};
+
+#[derive(Default)]
+struct Args {
+    status: Option<u16>,
+    content_type: Option<syn::LitStr>,
+    json: bool,
+    error: bool,
+}
+
+impl Args {
+    fn new(attrs: &[syn::Attribute], top: bool) -> syn::Result<Self> {
+        let mut args = Args::default();
+
+        for attr in attrs {
+            if !attr.path.is_ident("responder") {
+                continue;
+            }
+            let list = match attr.parse_meta()? {
+                Meta::List(list) => list,
+                meta => {
+                    return Err(syn::Error::new_spanned(
+                        meta,
+                        "Expected #[responder(...)] attribute",
+                    ))
+                }
+            };
+            for item in list.nested {
+                match item {
+                    NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.path.is_ident("status") => {
+                        args.status = match nv.lit {
+                            Lit::Int(ref lit) => {
+                                let code = lit.base10_parse::<u16>()?;
+                                if code < 100 || code > 999 {
+                                    return Err(syn::Error::new_spanned(
+                                        lit,
+                                        "Invalid status code",
+                                    ));
+                                }
+                                Some(code)
+                            }
+                            ref lit => {
+                                return Err(syn::Error::new_spanned(
+                                    lit,
+                                    "Attribute status expects integer status code",
+                                ))
+                            }
+                        };
+                    }
+                    NestedMeta::Meta(Meta::NameValue(ref nv))
+                        if nv.path.is_ident("content_type") =>
+                    {
+                        args.content_type = match nv.lit {
+                            Lit::Str(ref lit) => Some(lit.clone()),
+                            ref lit => {
+                                return Err(syn::Error::new_spanned(
+                                    lit,
+                                    "Attribute content_type expects literal string",
+                                ))
+                            }
+                        };
+                    }
+                    NestedMeta::Meta(Meta::Path(ref path)) if top && path.is_ident("json") => {
+                        args.json = true;
+                    }
+                    NestedMeta::Meta(Meta::Path(ref path)) if top && path.is_ident("error") => {
+                        args.error = true;
+                    }
+                    item => {
+                        let msg = if top {
+                            "Unknown attribute key is specified; allowed: status, content_type, json and error"
+                        } else {
+                            "Unknown attribute key is specified; allowed: status and content_type"
+                        };
+                        return Err(syn::Error::new_spanned(item, msg));
+                    }
+                }
+            }
+        }
+        Ok(args)
+    }
+}
+
+fn status(code: u16) -> TokenStream2 {
+    quote! { kayrx::http::StatusCode::from_u16(#code).unwrap() }
+}
+
+fn content_type(ct: &Option<syn::LitStr>) -> TokenStream2 {
+    match ct {
+        Some(ct) => quote! { Some(#ct) },
+        None => quote! { None },
+    }
+}
+
+pub fn derive(input: TokenStream) -> syn::Result<TokenStream> {
+    let input: DeriveInput = syn::parse(input)?;
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let args = Args::new(&input.attrs, true)?;
+    let default_status = args
+        .status
+        .unwrap_or(if args.error { 500 } else { 200 });
+
+    // status code and content type of the response
+    let (status, ct) = match input.data {
+        Data::Enum(ref data) => {
+            let mut status_arms = Vec::new();
+            let mut ct_arms = Vec::new();
+            for variant in &data.variants {
+                let v_args = Args::new(&variant.attrs, false)?;
+                let ident = &variant.ident;
+                let st = status(v_args.status.unwrap_or(default_status));
+                let ct = content_type(if v_args.content_type.is_some() {
+                    &v_args.content_type
+                } else {
+                    &args.content_type
+                });
+                status_arms.push(quote! { #name::#ident { .. } => #st, });
+                ct_arms.push(quote! { #name::#ident { .. } => #ct, });
+            }
+            (
+                quote! { match self { #(#status_arms)* } },
+                quote! { match self { #(#ct_arms)* } },
+            )
+        }
+        Data::Struct(_) => (status(default_status), content_type(&args.content_type)),
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "Responder can not be derived for unions",
+            ))
+        }
+    };
+
+    let body = if args.json {
+        quote! {
+            if let Some(ct) = ct {
+                builder.content_type(ct);
+            }
+            builder.json2(self)
+        }
+    } else {
+        quote! {
+            builder.content_type(ct.unwrap_or("text/plain; charset=utf-8"));
+            builder.body(self.to_string())
+        }
+    };
+
+    let response = if args.error {
+        quote! {
+            impl #impl_generics kayrx::http::error::ResponseError for #name #ty_generics #where_clause {
+                fn status_code(&self) -> kayrx::http::StatusCode {
+                    #status
+                }
+
+                fn error_response(&self) -> kayrx::http::Response {
+                    let ct: Option<&'static str> = #ct;
+                    let mut builder = kayrx::http::Response::build(#status);
+                    #body
+                }
+            }
+        }
+    } else {
+        quote! {
+            impl #impl_generics #name #ty_generics #where_clause {
+                fn __kayrx_response(&self) -> kayrx::http::Response {
+                    let ct: Option<&'static str> = #ct;
+                    let mut builder = kayrx::http::Response::build(#status);
+                    #body
+                }
+            }
+        }
+    };
+
+    let respond = if args.error {
+        quote! { kayrx::http::error::ResponseError::error_response(&self) }
+    } else {
+        quote! { self.__kayrx_response() }
+    };
+
+    Ok(quote! {
+        #response
+
+        impl #impl_generics kayrx::web::Responder for #name #ty_generics #where_clause {
+            type Error = kayrx::http::error::Error;
+            type Future = ::std::pin::Pin<Box<dyn ::std::future::Future<Output = Result<kayrx::http::Response, Self::Error>>>>;
+
+            fn respond_to(self, _: &kayrx::web::HttpRequest) -> Self::Future {
+                let res = #respond;
+                Box::pin(async move { Ok(res) })
+            }
+        }
+    }
+    .into())
+}
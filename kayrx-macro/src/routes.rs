@@ -0,0 +1,182 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Expr, Ident, LitStr, Token};
+
+const METHODS: &[&str] = &[
+    "GET", "POST", "PUT", "DELETE", "HEAD", "OPTIONS", "CONNECT", "PATCH", "TRACE",
+];
+
+struct Entry {
+    method: Ident,
+    path: LitStr,
+    handler: Expr,
+}
+
+impl Parse for Entry {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let method: Ident = input.parse()?;
+        if !METHODS.iter().any(|m| method == m) {
+            return Err(syn::Error::new_spanned(
+                method,
+                "Unknown method; allowed: GET, POST, PUT, DELETE, HEAD, OPTIONS, CONNECT, PATCH and TRACE",
+            ));
+        }
+        let path: LitStr = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let handler: Expr = input.parse()?;
+        Ok(Entry {
+            method,
+            path,
+            handler,
+        })
+    }
+}
+
+struct Routes(Punctuated<Entry, Token![,]>);
+
+impl Parse for Routes {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(Routes(Punctuated::parse_terminated(input)?))
+    }
+}
+
+/// Must be the same as hash function of `kayrx::web::RouteTable`
+fn hash(seed: u64, path: &str) -> u64 {
+    let mut h = 0xcbf2_9ce4_8422_2325 ^ seed;
+    for b in path.bytes() {
+        h ^= u64::from(b);
+        h = h.wrapping_mul(0x0100_0000_01b3);
+    }
+    h
+}
+
+/// Find seed that maps every path to its own slot
+fn perfect_hash(paths: &[&str]) -> (u64, Vec<u16>) {
+    let mut size = paths.len().max(1).next_power_of_two();
+    loop {
+        'seed: for seed in 0..1024u64 {
+            let mut slots = vec![0u16; size];
+            for (idx, path) in paths.iter().enumerate() {
+                let slot = hash(seed, path) as usize & (size - 1);
+                if slots[slot] != 0 {
+                    continue 'seed;
+                }
+                slots[slot] = idx as u16 + 1;
+            }
+            return (seed, slots);
+        }
+        size *= 2;
+    }
+}
+
+fn is_dynamic(path: &str) -> bool {
+    path.contains('{')
+}
+
+/// First segment of the path, `None` if segment contains parameter
+fn first_segment(path: &str) -> Option<&str> {
+    let segment = path.trim_start_matches('/').split('/').next().unwrap_or("");
+    if is_dynamic(segment) {
+        None
+    } else {
+        Some(segment)
+    }
+}
+
+pub fn generate(input: TokenStream) -> syn::Result<TokenStream> {
+    let entries = syn::parse::<Routes>(input)?.0;
+
+    // group routes by path, keep declaration order of paths and methods
+    let mut paths: Vec<(String, Vec<&Entry>)> = Vec::new();
+    for entry in &entries {
+        let path = entry.path.value();
+        if !path.starts_with('/') {
+            return Err(syn::Error::new_spanned(
+                &entry.path,
+                "Path must start with '/'",
+            ));
+        }
+        match paths.iter_mut().find(|p| p.0 == path) {
+            Some(item) => {
+                if item.1.iter().any(|e| e.method == entry.method) {
+                    return Err(syn::Error::new_spanned(
+                        &entry.path,
+                        format!("Duplicate route: {} {}", entry.method, path),
+                    ));
+                }
+                item.1.push(entry);
+            }
+            None => paths.push((path, vec![entry])),
+        }
+    }
+
+    let mut statics: Vec<_> = paths.iter().filter(|p| !is_dynamic(&p.0)).collect();
+    statics.sort_by(|a, b| a.0.cmp(&b.0));
+    if statics.len() > u16::max_value() as usize - 1 {
+        return Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "Too many static routes",
+        ));
+    }
+
+    // dynamic paths with static first segment are sorted by segment,
+    // sort is stable so declaration order is kept within a group
+    let mut dynamics: Vec<_> = paths.iter().filter(|p| is_dynamic(&p.0)).collect();
+    dynamics.sort_by(|a, b| {
+        match (first_segment(&a.0), first_segment(&b.0)) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        }
+    });
+    let mut groups: Vec<(&str, usize, usize)> = Vec::new();
+    for (idx, (path, _)) in dynamics.iter().enumerate() {
+        if let Some(segment) = first_segment(path) {
+            match groups.last_mut() {
+                Some(group) if group.0 == segment => group.2 = idx + 1,
+                _ => groups.push((segment, idx, idx + 1)),
+            }
+        }
+    }
+
+    let static_paths: Vec<&str> = statics.iter().map(|p| p.0.as_str()).collect();
+    let (seed, slots) = perfect_hash(&static_paths);
+
+    let statics = statics.iter().map(|(path, entries)| {
+        let routes = entries.iter().map(|e| route(e));
+        quote! { (#path, vec![#(#routes),*]) }
+    });
+    let dynamics = dynamics.iter().map(|(path, entries)| {
+        let routes = entries.iter().map(|e| route(e));
+        quote! { (#path, vec![#(#routes),*]) }
+    });
+    let groups = groups
+        .iter()
+        .map(|(segment, start, end)| quote! { (#segment, #start, #end) });
+
+    Ok(quote! {
+        kayrx::web::RouteTable::from_parts(
+            #seed,
+            &[#(#slots),*],
+            vec![#(#statics),*],
+            &[#(#groups),*],
+            vec![#(#dynamics),*],
+        )
+    }
+    .into())
+}
+
+fn route(entry: &Entry) -> TokenStream2 {
+    let Entry {
+        method, handler, ..
+    } = entry;
+    quote! {
+        kayrx::web::Route::new()
+            .method(kayrx::http::Method::#method)
+            .to(#handler)
+    }
+}
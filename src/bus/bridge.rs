@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures_util::future::{abortable, select, AbortHandle, Either, FutureExt, LocalBoxFuture};
+use futures_util::stream::{select_all, LocalBoxStream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::Bus;
+use crate::timer::delay_for;
+
+/// Connection to an external broker, i.e. Redis pub/sub or NATS.
+///
+/// Bridge is driven by [`Bridge`](struct.Bridge.html). `recv()` and
+/// `publish()` futures could be in flight at the same time, `recv()`
+/// future is never dropped before completion while connection is
+/// alive. After any error, bridge is reconnected with `connect()`.
+pub trait BusBridge: 'static {
+    /// Open connection to the broker and subscribe to the topics
+    fn connect(&self, topics: &[String]) -> LocalBoxFuture<'static, Result<(), io::Error>>;
+
+    /// Publish message to the broker topic
+    fn publish(&self, topic: &str, payload: Bytes)
+        -> LocalBoxFuture<'static, Result<(), io::Error>>;
+
+    /// Receive next message from the broker.
+    ///
+    /// `None` means connection is closed by the broker.
+    fn recv(&self) -> LocalBoxFuture<'static, Result<Option<(String, Bytes)>, io::Error>>;
+}
+
+type Deliver = Box<dyn Fn(Bus, Bytes, usize) -> LocalBoxFuture<'static, ()>>;
+
+struct Topic {
+    outbound: LocalBoxStream<'static, (String, Bytes)>,
+    inbound: Deliver,
+    origin: usize,
+}
+
+/// Bridge driver of the bus.
+///
+/// Messages that are published to the bridged named topics of the local
+/// bus are sent to the broker, messages received from the broker are
+/// published to the local bus. Messages are encoded as json.
+///
+/// Messages from the broker are delivered locally at least once, driver
+/// waits for capacity in subscriber's queues instead of dropping them.
+/// Local messages are kept in the driver's subscription queue while the
+/// broker is not available, and a message that failed to send is sent
+/// again after reconnect, so the broker could receive duplicates.
+/// Messages received from the broker are not sent back.
+///
+/// ```rust,ignore
+/// use kayrx::bus::{Bridge, Bus};
+///
+/// let bus = Bus::new();
+/// let handle = Bridge::new(bus.clone(), RedisBridge::new("127.0.0.1:6379"))
+///     .topic::<OrderCreated>("orders")
+///     .start();
+/// ```
+pub struct Bridge<B> {
+    bus: Bus,
+    bridge: B,
+    topics: Vec<(String, Topic)>,
+    min_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl<B: BusBridge> Bridge<B> {
+    /// Create bridge driver for the bus
+    pub fn new(bus: Bus, bridge: B) -> Self {
+        Bridge {
+            bus,
+            bridge,
+            topics: Vec::new(),
+            min_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+
+    /// Bridge named topic with messages of type `T`
+    pub fn topic<T>(mut self, name: &str) -> Self
+    where
+        T: Serialize + DeserializeOwned + Clone + Send + 'static,
+    {
+        let sub = self.bus.subscribe_to::<T>(name);
+        let origin = sub.id();
+        let topic = name.to_owned();
+        let outbound = sub
+            .filter_map(move |msg| {
+                let res = match serde_json::to_vec(&msg) {
+                    Ok(payload) => Some((topic.clone(), Bytes::from(payload))),
+                    Err(e) => {
+                        log::error!("Can not encode message for topic {}: {}", topic, e);
+                        None
+                    }
+                };
+                async move { res }
+            })
+            .boxed_local();
+
+        let topic = name.to_owned();
+        let inbound: Deliver = Box::new(move |bus: Bus, payload: Bytes, origin: usize| {
+            let topic = topic.clone();
+            async move {
+                match serde_json::from_slice::<T>(&payload) {
+                    Ok(msg) => {
+                        bus.deliver_to(&topic, msg, origin).await;
+                    }
+                    Err(e) => log::error!("Can not decode message of topic {}: {}", topic, e),
+                }
+            }
+            .boxed_local()
+        });
+
+        self.topics.push((
+            name.to_owned(),
+            Topic {
+                outbound,
+                inbound,
+                origin,
+            },
+        ));
+        self
+    }
+
+    /// Set reconnect backoff, delay is doubled after every failed attempt.
+    ///
+    /// By default backoff is from 100 milliseconds to 30 seconds.
+    pub fn reconnect(mut self, min: Duration, max: Duration) -> Self {
+        self.min_backoff = min;
+        self.max_backoff = std::cmp::max(min, max);
+        self
+    }
+
+    /// Start driver on the current arbiter
+    pub fn start(self) -> BridgeHandle {
+        let (fut, handle) = abortable(self.run());
+        crate::fiber::spawn(fut.map(|_| ()));
+        BridgeHandle(handle)
+    }
+
+    async fn run(self) {
+        let Bridge {
+            bus,
+            bridge,
+            topics,
+            min_backoff,
+            max_backoff,
+        } = self;
+
+        let names: Vec<String> = topics.iter().map(|(name, _)| name.clone()).collect();
+        let mut inbound = HashMap::new();
+        let mut outbound = Vec::new();
+        for (name, topic) in topics {
+            inbound.insert(name, (topic.inbound, topic.origin));
+            outbound.push(topic.outbound);
+        }
+        let mut outbound = select_all(outbound);
+        let mut pending: Option<(String, Bytes)> = None;
+        let mut backoff = min_backoff;
+
+        loop {
+            if let Err(e) = bridge.connect(&names).await {
+                log::warn!("Can not connect bus bridge: {}, retry in {:?}", e, backoff);
+                delay_for(backoff).await;
+                backoff = std::cmp::min(backoff * 2, max_backoff);
+                continue;
+            }
+            backoff = min_backoff;
+
+            let mut recv = bridge.recv();
+            let err = loop {
+                // send message that failed before reconnect
+                if let Some((topic, payload)) = pending.take() {
+                    if let Err(e) = bridge.publish(&topic, payload.clone()).await {
+                        pending = Some((topic, payload));
+                        break e;
+                    }
+                }
+
+                match select(outbound.next(), recv).await {
+                    Either::Left((Some(msg), fut)) => {
+                        pending = Some(msg);
+                        recv = fut;
+                    }
+                    // no bridged topics
+                    Either::Left((None, _)) => return,
+                    Either::Right((Ok(Some((topic, payload))), _)) => {
+                        if let Some((deliver, origin)) = inbound.get(&topic) {
+                            deliver(bus.clone(), payload, *origin).await;
+                        } else {
+                            log::trace!("Message of unknown topic {} is ignored", topic);
+                        }
+                        recv = bridge.recv();
+                    }
+                    Either::Right((Ok(None), _)) => {
+                        break io::Error::new(
+                            io::ErrorKind::ConnectionAborted,
+                            "Connection is closed by broker",
+                        )
+                    }
+                    Either::Right((Err(e), _)) => break e,
+                }
+            };
+            log::warn!("Bus bridge is disconnected: {}", err);
+        }
+    }
+}
+
+impl<B> fmt::Debug for Bridge<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let topics: Vec<_> = self.topics.iter().map(|(name, _)| name).collect();
+        f.debug_struct("Bridge").field("topics", &topics).finish()
+    }
+}
+
+/// Handle of the running bridge driver
+#[derive(Debug, Clone)]
+pub struct BridgeHandle(AbortHandle);
+
+impl BridgeHandle {
+    /// Stop bridge driver, bridge is dropped
+    pub fn stop(&self) {
+        self.0.abort();
+    }
+}
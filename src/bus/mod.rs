@@ -0,0 +1,306 @@
+//! Typed publish/subscribe event bus
+//!
+//! [`Bus`](struct.Bus.html) delivers messages to all subscribers of
+//! a topic. Topic is either a message type or a name, named topics are
+//! typed as well. Every subscriber has bounded queue, if the queue is
+//! full the message is dropped for that subscriber and counted in topic
+//! stats, so a slow subscriber never blocks publishers.
+//!
+//! Bus is cheap to clone and could be shared between workers, handlers,
+//! background jobs and websocket sessions.
+//!
+//! ```rust
+//! use kayrx::bus::Bus;
+//! use kayrx::web::{self, App, HttpResponse};
+//!
+//! #[derive(Clone)]
+//! struct UserCreated(u64);
+//!
+//! async fn create(bus: web::Data<Bus>) -> HttpResponse {
+//!     bus.publish(UserCreated(1));
+//!     HttpResponse::Created().finish()
+//! }
+//!
+//! fn main() {
+//!     let bus = Bus::new();
+//!     let mut events = bus.subscribe::<UserCreated>();
+//!     kayrx::fiber::spawn(async move {
+//!         while let Some(UserCreated(id)) = events.recv().await {
+//!             println!("user {} is created", id);
+//!         }
+//!     });
+//!
+//!     let app = App::new()
+//!         .data(bus.clone())
+//!         .route("/users", web::post().to(create));
+//! }
+//! ```
+use std::any::{type_name, Any, TypeId};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use parking_lot::Mutex;
+
+use crate::krse::sync::mpsc::{self, error::TrySendError};
+
+mod bridge;
+
+pub use self::bridge::{Bridge, BridgeHandle, BusBridge};
+
+/// Default capacity of subscriber's queue
+const CAPACITY: usize = 64;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Key {
+    name: Cow<'static, str>,
+    ty: TypeId,
+}
+
+impl Key {
+    fn typed<T: 'static>() -> Self {
+        Key {
+            name: Cow::Borrowed(type_name::<T>()),
+            ty: TypeId::of::<T>(),
+        }
+    }
+
+    fn named<T: 'static>(name: &str) -> Self {
+        Key {
+            name: Cow::Owned(name.to_owned()),
+            ty: TypeId::of::<T>(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Topic {
+    /// Subscription id and `mpsc::Sender<T>` of the subscribers
+    subscribers: Vec<(usize, Box<dyn Any + Send>)>,
+    stats: TopicStats,
+}
+
+/// Topic counters
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TopicStats {
+    /// Number of active subscribers
+    pub subscribers: usize,
+    /// Number of published messages
+    pub published: u64,
+    /// Number of messages queued for subscribers
+    pub delivered: u64,
+    /// Number of messages dropped because subscriber's queue is full
+    pub dropped: u64,
+}
+
+struct Inner {
+    capacity: usize,
+    next_id: AtomicUsize,
+    topics: Mutex<HashMap<Key, Topic>>,
+}
+
+/// Typed publish/subscribe bus
+#[derive(Clone)]
+pub struct Bus(Arc<Inner>);
+
+impl Default for Bus {
+    fn default() -> Self {
+        Bus::new()
+    }
+}
+
+impl Bus {
+    /// Create bus, subscriber's queue capacity is 64 messages.
+    pub fn new() -> Self {
+        Bus::with_capacity(CAPACITY)
+    }
+
+    /// Create bus with capacity of subscriber's queue
+    pub fn with_capacity(capacity: usize) -> Self {
+        Bus(Arc::new(Inner {
+            capacity: std::cmp::max(capacity, 1),
+            next_id: AtomicUsize::new(0),
+            topics: Mutex::new(HashMap::new()),
+        }))
+    }
+
+    /// Subscribe to messages of type `T`
+    pub fn subscribe<T: Clone + Send + 'static>(&self) -> Subscription<T> {
+        self.add_subscriber(Key::typed::<T>())
+    }
+
+    /// Subscribe to messages of type `T` published to the named topic
+    pub fn subscribe_to<T: Clone + Send + 'static>(&self, topic: &str) -> Subscription<T> {
+        self.add_subscriber(Key::named::<T>(topic))
+    }
+
+    /// Publish message to subscribers of type `T`.
+    ///
+    /// Returns number of subscribers the message is queued for.
+    pub fn publish<T: Clone + Send + 'static>(&self, msg: T) -> usize {
+        self.send(Key::typed::<T>(), msg)
+    }
+
+    /// Publish message to subscribers of the named topic.
+    ///
+    /// Returns number of subscribers the message is queued for.
+    pub fn publish_to<T: Clone + Send + 'static>(&self, topic: &str, msg: T) -> usize {
+        self.send(Key::named::<T>(topic), msg)
+    }
+
+    /// Counters of the topics, typed topics are named after the type.
+    pub fn stats(&self) -> BTreeMap<String, TopicStats> {
+        let mut topics = self.0.topics.lock();
+        let mut stats = BTreeMap::new();
+        for (key, topic) in topics.iter_mut() {
+            topic.stats.subscribers = topic.subscribers.len();
+            let entry: &mut TopicStats = stats.entry(key.name.to_string()).or_default();
+            entry.subscribers += topic.stats.subscribers;
+            entry.published += topic.stats.published;
+            entry.delivered += topic.stats.delivered;
+            entry.dropped += topic.stats.dropped;
+        }
+        stats
+    }
+
+    fn add_subscriber<T: Send + 'static>(&self, key: Key) -> Subscription<T> {
+        let (tx, rx) = mpsc::channel(self.0.capacity);
+        let id = self.0.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut topics = self.0.topics.lock();
+        let topic = topics.entry(key).or_default();
+        topic.subscribers.push((id, Box::new(tx)));
+        topic.stats.subscribers = topic.subscribers.len();
+        Subscription { id, rx }
+    }
+
+    /// Publish message to the named topic, wait for capacity in
+    /// subscriber's queues instead of dropping the message.
+    ///
+    /// Subscription with `origin` id does not receive the message.
+    pub(crate) async fn deliver_to<T: Clone + Send + 'static>(
+        &self,
+        topic: &str,
+        msg: T,
+        origin: usize,
+    ) -> usize {
+        let key = Key::named::<T>(topic);
+        let senders: Vec<mpsc::Sender<T>> = {
+            let mut topics = self.0.topics.lock();
+            let topic = topics.entry(key.clone()).or_default();
+            topic.stats.published += 1;
+            topic
+                .subscribers
+                .iter()
+                .filter(|(id, _)| *id != origin)
+                .filter_map(|(_, tx)| tx.downcast_ref::<mpsc::Sender<T>>().cloned())
+                .collect()
+        };
+
+        let mut delivered = 0;
+        for mut tx in senders {
+            if tx.send(msg.clone()).await.is_ok() {
+                delivered += 1;
+            }
+        }
+        if let Some(topic) = self.0.topics.lock().get_mut(&key) {
+            topic.stats.delivered += delivered;
+        }
+        delivered as usize
+    }
+
+    fn send<T: Clone + Send + 'static>(&self, key: Key, msg: T) -> usize {
+        let mut topics = self.0.topics.lock();
+        let topic = topics.entry(key.clone()).or_default();
+        topic.stats.published += 1;
+
+        let mut delivered = 0;
+        let mut dropped = 0;
+        let mut idx = 0;
+        while idx < topic.subscribers.len() {
+            let tx = topic.subscribers[idx]
+                .1
+                .downcast_mut::<mpsc::Sender<T>>()
+                .unwrap();
+            match tx.try_send(msg.clone()) {
+                Ok(_) => delivered += 1,
+                Err(TrySendError::Full(_)) => dropped += 1,
+                Err(TrySendError::Closed(_)) => {
+                    // subscription is dropped
+                    topic.subscribers.swap_remove(idx);
+                    continue;
+                }
+            }
+            idx += 1;
+        }
+        topic.stats.subscribers = topic.subscribers.len();
+        topic.stats.delivered += delivered;
+        topic.stats.dropped += dropped;
+
+        #[cfg(feature = "telemetry")]
+        {
+            let attrs = [("topic", key.name.as_ref())];
+            crate::telemetry::counter("bus.published", &attrs).add(1);
+            crate::telemetry::counter("bus.delivered", &attrs).add(delivered);
+            if dropped > 0 {
+                log::trace!("Bus subscriber queue is full, topic: {}", key.name);
+                crate::telemetry::counter("bus.dropped", &attrs).add(dropped);
+            }
+        }
+        delivered as usize
+    }
+}
+
+impl fmt::Debug for Bus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bus")
+            .field("capacity", &self.0.capacity)
+            .field("topics", &self.0.topics.lock().len())
+            .finish()
+    }
+}
+
+/// Subscription to a topic.
+///
+/// Messages are received in publishing order. Dropped subscription is
+/// removed from the topic on the next publish.
+pub struct Subscription<T> {
+    id: usize,
+    rx: mpsc::Receiver<T>,
+}
+
+impl<T> Subscription<T> {
+    pub(crate) fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Receive next message
+    pub async fn recv(&mut self) -> Option<T> {
+        self.rx.recv().await
+    }
+
+    /// Receive queued message without waiting
+    pub fn try_recv(&mut self) -> Option<T> {
+        self.rx.try_recv().ok()
+    }
+}
+
+impl<T> Stream for Subscription<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+impl<T> fmt::Debug for Subscription<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Subscription")
+            .field("type", &type_name::<T>())
+            .finish()
+    }
+}
@@ -1,5 +1,5 @@
 use crate::codec::framed::{Fuse, ProjectFuse};
-use crate::codec::Decoder;
+use crate::codec::{Decoder, ReadBufSize};
 
 use crate::krse::io::AsyncRead;
 
@@ -27,6 +27,7 @@ pin_project! {
         eof: bool,
         is_readable: bool,
         buffer: BytesMut,
+        read_size: Option<ReadBufSize>,
     }
 }
 
@@ -94,6 +95,11 @@ impl<T, D> FramedRead<T, D> {
     pub fn read_buffer(&self) -> &BytesMut {
         &self.inner.buffer
     }
+
+    /// Use adaptive read chunk size instead of the fixed one.
+    pub(crate) fn set_read_buf_size(&mut self, size: ReadBufSize) {
+        self.inner.read_size = Some(size);
+    }
 }
 
 impl<T, D> Stream for FramedRead<T, D>
@@ -180,6 +186,7 @@ pub(crate) fn framed_read2<T>(inner: T) -> FramedRead2<T> {
         eof: false,
         is_readable: false,
         buffer: BytesMut::with_capacity(INITIAL_CAPACITY),
+        read_size: None,
     }
 }
 
@@ -193,6 +200,7 @@ pub(crate) fn framed_read2_with_buffer<T>(inner: T, mut buf: BytesMut) -> Framed
         eof: false,
         is_readable: !buf.is_empty(),
         buffer: buf,
+        read_size: None,
     }
 }
 
@@ -269,15 +277,29 @@ where
             // Otherwise, try to read more data and try again. Make sure we've
             // got room for at least one byte to read to ensure that we don't
             // get a spurious 0 that looks like EOF
-            pinned.buffer.reserve(1);
+            let room = match pinned.read_size {
+                Some(size) => size.reserve(&mut pinned.buffer),
+                None => {
+                    pinned.buffer.reserve(1);
+                    0
+                }
+            };
             let bytect = match pinned
                 .inner
                 .as_mut()
                 .poll_read_buf(cx, &mut pinned.buffer)?
             {
                 Poll::Ready(ct) => ct,
-                Poll::Pending => return Poll::Pending,
+                Poll::Pending => {
+                    if let Some(size) = pinned.read_size {
+                        size.release(&mut pinned.buffer);
+                    }
+                    return Poll::Pending;
+                }
             };
+            if let Some(size) = pinned.read_size.as_mut() {
+                size.record(bytect, room);
+            }
             if bytect == 0 {
                 *pinned.eof = true;
             }
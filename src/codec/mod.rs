@@ -13,6 +13,7 @@ mod framed_read;
 mod framed_write;
 mod bytes_codec;
 mod lines_codec;
+mod read_buf;
 pub(crate) mod length_delimited;
 
 pub use self::bytes_codec::BytesCodec;
@@ -25,3 +26,5 @@ pub use self::framed_write::FramedWrite;
 pub use self::length_delimited::{LengthDelimitedCodec, LengthDelimitedCodecError};
 pub use self::lines_codec::{LinesCodec, LinesCodecError};
 
+pub(crate) use self::read_buf::ReadBufSize;
+
@@ -0,0 +1,57 @@
+use std::cmp;
+
+use bytes::BytesMut;
+
+/// Read chunk size that adapts to connection throughput.
+///
+/// Size doubles after every read that fills the reserved space, up to `max`,
+/// and halves after reads that use less than a quarter of it, down to `min`.
+/// Once the size is back at `min`, memory of the empty buffer is released,
+/// so idle connections do not keep large read buffers.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ReadBufSize {
+    min: usize,
+    max: usize,
+    current: usize,
+}
+
+impl ReadBufSize {
+    pub(crate) fn new(min: usize, max: usize) -> Self {
+        let min = cmp::max(min, 1);
+        ReadBufSize {
+            min,
+            max: cmp::max(min, max),
+            current: min,
+        }
+    }
+
+    /// Current read chunk size
+    pub(crate) fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Reserve space for the next read, returns available space
+    pub(crate) fn reserve(&self, buf: &mut BytesMut) -> usize {
+        let remaining = buf.capacity() - buf.len();
+        if remaining < self.current / 2 || remaining == 0 {
+            buf.reserve(self.current - remaining);
+        }
+        buf.capacity() - buf.len()
+    }
+
+    /// Adjust size after `n` bytes are read into `room` bytes of space
+    pub(crate) fn record(&mut self, n: usize, room: usize) {
+        if n >= room {
+            self.current = cmp::min(self.current * 2, self.max);
+        } else if n < self.current / 4 {
+            self.current = cmp::max(self.current / 2, self.min);
+        }
+    }
+
+    /// Release memory of the empty buffer if throughput is low
+    pub(crate) fn release(&self, buf: &mut BytesMut) {
+        if self.current == self.min && buf.is_empty() && buf.capacity() != 0 {
+            *buf = BytesMut::new();
+        }
+    }
+}
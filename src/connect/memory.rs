@@ -0,0 +1,101 @@
+use std::io;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use futures_core::Stream;
+use futures_util::future::{err, ok, Ready};
+
+use crate::krse::io::{duplex, DuplexStream};
+use crate::service::Service;
+
+use super::connect::{Address, Connect, Connection};
+use super::error::ConnectError;
+
+/// Create connected pair of in-memory connector and listener.
+///
+/// Every connection made by the connector creates new `DuplexStream` pair,
+/// one end is returned to the caller and the other one is delivered to the
+/// listener. Address of the connect request is ignored. `capacity` is a
+/// max number of buffered bytes in each direction.
+///
+/// ```rust
+/// use http::Uri;
+/// use kayrx::connect::{self, Connect};
+/// use kayrx::service::Service;
+/// use futures::StreamExt;
+///
+/// #[kayrx::main]
+/// async fn main() {
+///     let (mut connector, mut listener) = connect::memory::<Uri>(1024);
+///
+///     let uri = Uri::from_static("http://localhost/");
+///     let client = connector.call(Connect::new(uri)).await.unwrap();
+///     let server = listener.next().await.unwrap();
+/// }
+/// ```
+pub fn memory<T>(capacity: usize) -> (MemoryConnector<T>, MemoryListener) {
+    let (tx, rx) = unbounded();
+    (
+        MemoryConnector {
+            tx,
+            capacity,
+            _t: PhantomData,
+        },
+        MemoryListener(rx),
+    )
+}
+
+/// In-memory connector service
+#[derive(Debug)]
+pub struct MemoryConnector<T> {
+    tx: UnboundedSender<DuplexStream>,
+    capacity: usize,
+    _t: PhantomData<T>,
+}
+
+impl<T> Clone for MemoryConnector<T> {
+    fn clone(&self) -> Self {
+        MemoryConnector {
+            tx: self.tx.clone(),
+            capacity: self.capacity,
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<T: Address> Service for MemoryConnector<T> {
+    type Request = Connect<T>;
+    type Response = Connection<T, DuplexStream>;
+    type Error = ConnectError;
+    type Future = Ready<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Connect<T>) -> Self::Future {
+        let (client, server) = duplex(self.capacity);
+        if self.tx.unbounded_send(server).is_ok() {
+            ok(Connection::new(client, req.req))
+        } else {
+            err(ConnectError::Io(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                "Memory listener is closed",
+            )))
+        }
+    }
+}
+
+/// Stream of in-memory connections
+#[derive(Debug)]
+pub struct MemoryListener(UnboundedReceiver<DuplexStream>);
+
+impl Stream for MemoryListener {
+    type Item = DuplexStream;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.0).poll_next(cx)
+    }
+}
@@ -8,8 +8,10 @@
 mod connect;
 mod connector;
 mod error;
+mod memory;
 mod resolve;
 mod service;
+mod unix;
 pub mod ssl;
 
 mod uri;
@@ -29,8 +31,10 @@ pub mod resolver {
 pub use self::connect::{Address, Connect, Connection};
 pub use self::connector::{TcpConnector, TcpConnectorFactory};
 pub use self::error::ConnectError;
+pub use self::memory::{memory, MemoryConnector, MemoryListener};
 pub use self::resolve::{Resolver, ResolverFactory};
 pub use self::service::{ConnectService, ConnectServiceFactory, TcpConnectService};
+pub use self::unix::UnixConnector;
 
 pub fn start_resolver(cfg: ResolverConfig, opts: ResolverOpts) -> AsyncResolver {
     let (resolver, bg) = AsyncResolver::new(cfg, opts);
@@ -0,0 +1,79 @@
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use futures_util::future::{FutureExt, LocalBoxFuture};
+
+use crate::krse::net::UnixStream;
+use crate::service::Service;
+
+use super::connect::{Address, Connect, Connection};
+use super::error::ConnectError;
+
+/// Unix domain socket connector service.
+///
+/// Every connection is opened to the socket path, host and address of
+/// the connect request are ignored, so the request keeps its logical
+/// host, i.e. for `Host` header and connection pooling.
+///
+/// ```rust,no_run
+/// use http::Uri;
+/// use kayrx::connect::{Connect, UnixConnector};
+/// use kayrx::service::Service;
+///
+/// #[kayrx::main]
+/// async fn main() {
+///     let mut connector = UnixConnector::new("/var/run/docker.sock");
+///
+///     let uri = Uri::from_static("http://docker/containers/json");
+///     let conn = connector.call(Connect::new(uri)).await.unwrap();
+/// }
+/// ```
+#[derive(Debug)]
+pub struct UnixConnector<T> {
+    path: Rc<PathBuf>,
+    _t: PhantomData<T>,
+}
+
+impl<T> UnixConnector<T> {
+    /// Create connector of the socket path
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        UnixConnector {
+            path: Rc::new(path.as_ref().to_owned()),
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for UnixConnector<T> {
+    fn clone(&self) -> Self {
+        UnixConnector {
+            path: self.path.clone(),
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<T: Address + 'static> Service for UnixConnector<T> {
+    type Request = Connect<T>;
+    type Response = Connection<T, UnixStream>;
+    type Error = ConnectError;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Connect<T>) -> Self::Future {
+        let path = self.path.clone();
+        async move {
+            let io = UnixStream::connect(path.as_ref()).await.map_err(|e| {
+                trace!("Unix socket connect error {:?}: {}", path, e);
+                ConnectError::Io(e)
+            })?;
+            Ok(Connection::new(io, req.req))
+        }
+        .boxed_local()
+    }
+}
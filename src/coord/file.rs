@@ -0,0 +1,73 @@
+use std::cell::RefCell;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use futures_util::future::{ready, FutureExt, LocalBoxFuture};
+
+use super::Lease;
+
+/// Lease backed by advisory file lock.
+///
+/// Lock is held until the lease is released or process exits, ttl is
+/// not used. Lock is visible to processes on the same host, locks on
+/// network file systems are not reliable.
+#[derive(Debug)]
+pub struct FileLease {
+    path: PathBuf,
+    file: RefCell<Option<File>>,
+}
+
+impl FileLease {
+    /// Create lease of the lock file, file is created if it does not exist
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        FileLease {
+            path: path.as_ref().to_owned(),
+            file: RefCell::new(None),
+        }
+    }
+
+    fn lock(&self) -> io::Result<bool> {
+        if self.file.borrow().is_some() {
+            return Ok(true);
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .open(&self.path)?;
+        let res = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+        if res == 0 {
+            *self.file.borrow_mut() = Some(file);
+            return Ok(true);
+        }
+
+        let err = io::Error::last_os_error();
+        if err.kind() == io::ErrorKind::WouldBlock {
+            Ok(false)
+        } else {
+            Err(err)
+        }
+    }
+
+    fn unlock(&self) -> io::Result<()> {
+        if let Some(file) = self.file.borrow_mut().take() {
+            if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_UN) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Lease for FileLease {
+    fn acquire(&self, _: Duration) -> LocalBoxFuture<'static, Result<bool, io::Error>> {
+        ready(self.lock()).boxed_local()
+    }
+
+    fn release(&self) -> LocalBoxFuture<'static, Result<(), io::Error>> {
+        ready(self.unlock()).boxed_local()
+    }
+}
@@ -0,0 +1,92 @@
+use std::io;
+use std::time::Duration;
+
+use futures_util::future::{FutureExt, LocalBoxFuture};
+use serde_json::json;
+
+use super::Lease;
+use crate::http::StatusCode;
+use crate::web::client::Client;
+
+/// Lease held in an external http service.
+///
+/// Lease is acquired or extended with `PUT` request to the lease url with
+/// json body `{"holder": "<id>", "ttl": <milliseconds>}`. Service responds
+/// with any successful status if lease is granted to the holder and with
+/// `409 Conflict` if it is held by another holder. Lease is released with
+/// `DELETE` request to the same url with json body `{"holder": "<id>"}`.
+///
+/// Holder id is random by default.
+#[derive(Clone)]
+pub struct HttpLease {
+    client: Client,
+    url: String,
+    holder: String,
+}
+
+impl HttpLease {
+    /// Create lease of the url
+    pub fn new(url: &str) -> Self {
+        HttpLease {
+            client: Client::default(),
+            url: url.to_owned(),
+            holder: format!("{:016x}", rand::random::<u64>()),
+        }
+    }
+
+    /// Set holder id, i.e. host name of the instance
+    pub fn holder(mut self, holder: &str) -> Self {
+        self.holder = holder.to_owned();
+        self
+    }
+
+    /// Set http client that is used for lease requests
+    pub fn client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+}
+
+fn error<E: std::fmt::Display>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err.to_string())
+}
+
+impl Lease for HttpLease {
+    fn acquire(&self, ttl: Duration) -> LocalBoxFuture<'static, Result<bool, io::Error>> {
+        let body = json!({"holder": self.holder, "ttl": ttl.as_millis() as u64});
+        self.client
+            .put(&self.url)
+            .send_json(&body)
+            .map(|res| match res {
+                Ok(res) if res.status().is_success() => Ok(true),
+                Ok(res) if res.status() == StatusCode::CONFLICT => Ok(false),
+                Ok(res) => Err(error(format!("Lease service responded {}", res.status()))),
+                Err(e) => Err(error(e)),
+            })
+            .boxed_local()
+    }
+
+    fn release(&self) -> LocalBoxFuture<'static, Result<(), io::Error>> {
+        let body = json!({"holder": self.holder});
+        self.client
+            .delete(&self.url)
+            .send_json(&body)
+            .map(|res| match res {
+                Ok(res) if res.status().is_success() => Ok(()),
+                // lease is expired and taken by another holder
+                Ok(res) if res.status() == StatusCode::CONFLICT => Ok(()),
+                Ok(res) => Err(error(format!("Lease service responded {}", res.status()))),
+                Err(e) => Err(error(e)),
+            })
+            .boxed_local()
+    }
+}
+
+impl std::fmt::Debug for HttpLease {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpLease")
+            .field("url", &self.url)
+            .field("holder", &self.holder)
+            .finish()
+    }
+}
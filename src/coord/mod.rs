@@ -0,0 +1,215 @@
+//! Single instance coordination of background tasks
+//!
+//! [`Lease`](trait.Lease.html) is a time limited exclusive right that is
+//! held by at most one instance of a fleet. [`Singleton`](struct.Singleton.html)
+//! runs a periodic task only on the instance that holds the lease, other
+//! instances skip their ticks until the holder stops renewing it.
+//!
+//! Two lease implementations are provided:
+//!
+//! * [`FileLease`](struct.FileLease.html) - advisory file lock, for
+//!   instances on the same host.
+//! * [`HttpLease`](struct.HttpLease.html) - lease held in an external
+//!   http service.
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use kayrx::coord::{HttpLease, Singleton};
+//!
+//! #[kayrx::main]
+//! async fn main() {
+//!     let lease = HttpLease::new("http://coordinator:8080/leases/cleanup");
+//!     let handle = Singleton::new(lease, Duration::from_secs(60), || async {
+//!         println!("cleanup expired sessions");
+//!     })
+//!     .start();
+//!
+//!     // stop task and release the lease
+//!     handle.stop().await;
+//! }
+//! ```
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::rc::Rc;
+use std::time::Duration;
+
+use futures_util::future::{abortable, select, AbortHandle, FutureExt, LocalBoxFuture};
+
+use crate::timer::{self, delay_for, MissedTickBehavior};
+
+#[cfg(unix)]
+mod file;
+mod http;
+
+#[cfg(unix)]
+pub use self::file::FileLease;
+pub use self::http::HttpLease;
+
+/// Exclusive time limited lease
+pub trait Lease: 'static {
+    /// Acquire lease or extend held lease for `ttl`.
+    ///
+    /// Returns `false` if lease is held by another instance.
+    fn acquire(&self, ttl: Duration) -> LocalBoxFuture<'static, Result<bool, io::Error>>;
+
+    /// Release held lease, so another instance could acquire it without
+    /// waiting for expiration
+    fn release(&self) -> LocalBoxFuture<'static, Result<(), io::Error>>;
+}
+
+type Task = Box<dyn Fn() -> LocalBoxFuture<'static, ()>>;
+
+/// Periodic task that runs on a single instance.
+///
+/// On every tick driver acquires the lease, task runs only if the lease
+/// is acquired. Lease ttl is longer than the interval, so the holder
+/// extends its lease on the next tick and stays the leader while it is
+/// alive. While task is running, the lease is renewed every third of ttl,
+/// if renewal fails the task is cancelled.
+pub struct Singleton {
+    lease: Rc<dyn Lease>,
+    task: Task,
+    interval: Duration,
+    ttl: Duration,
+}
+
+impl Singleton {
+    /// Create singleton task that runs every `interval`
+    pub fn new<L, F, R>(lease: L, interval: Duration, task: F) -> Self
+    where
+        L: Lease,
+        F: Fn() -> R + 'static,
+        R: Future<Output = ()> + 'static,
+    {
+        Singleton {
+            lease: Rc::new(lease),
+            task: Box::new(move || task().boxed_local()),
+            interval,
+            ttl: interval * 3,
+        }
+    }
+
+    /// Set lease ttl, by default three intervals.
+    ///
+    /// Ttl is time after which another instance takes over the task if
+    /// current leader is gone. Ttl shorter than interval is ignored.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = std::cmp::max(ttl, self.interval);
+        self
+    }
+
+    /// Start task driver on the current arbiter
+    pub fn start(self) -> SingletonHandle {
+        let lease = self.lease.clone();
+        let (fut, handle) = abortable(self.run());
+        crate::fiber::spawn(fut.map(|_| ()));
+        SingletonHandle { lease, handle }
+    }
+
+    /// Run task driver, i.e. as a background job of application module.
+    ///
+    /// Lease is not released if the future is dropped, it expires after
+    /// ttl.
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use kayrx::coord::{FileLease, Singleton};
+    /// use kayrx::web::{Module, ModuleConfig};
+    ///
+    /// struct Cleanup;
+    ///
+    /// impl Module for Cleanup {
+    ///     fn name(&self) -> &str {
+    ///         "cleanup"
+    ///     }
+    ///
+    ///     fn configure(&self, cfg: &mut ModuleConfig) {
+    ///         // job is started by every worker, task runs on one of them
+    ///         cfg.background(|| {
+    ///             let lease = FileLease::new("/tmp/cleanup.lock");
+    ///             Singleton::new(lease, Duration::from_secs(60), || async {}).run()
+    ///         });
+    ///     }
+    /// }
+    /// ```
+    pub async fn run(self) {
+        let Singleton {
+            lease,
+            task,
+            interval,
+            ttl,
+        } = self;
+
+        let mut ticks = timer::interval(interval);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        loop {
+            ticks.tick().await;
+            match lease.acquire(ttl).await {
+                Ok(true) => {
+                    let renew = renew(lease.clone(), ttl).boxed_local();
+                    let _ = select(task(), renew).await;
+                }
+                Ok(false) => log::trace!("Lease is held by another instance, task is skipped"),
+                Err(e) => log::warn!("Can not acquire lease: {}", e),
+            }
+        }
+    }
+}
+
+/// Renew lease until renewal fails
+async fn renew(lease: Rc<dyn Lease>, ttl: Duration) {
+    loop {
+        delay_for(ttl / 3).await;
+        match lease.acquire(ttl).await {
+            Ok(true) => (),
+            Ok(false) => {
+                log::warn!("Lease is taken over by another instance, task is cancelled");
+                return;
+            }
+            Err(e) => {
+                log::warn!("Can not renew lease: {}, task is cancelled", e);
+                return;
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Singleton {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Singleton")
+            .field("interval", &self.interval)
+            .field("ttl", &self.ttl)
+            .finish()
+    }
+}
+
+/// Handle of the running singleton task
+#[derive(Clone)]
+pub struct SingletonHandle {
+    lease: Rc<dyn Lease>,
+    handle: AbortHandle,
+}
+
+impl SingletonHandle {
+    /// Stop task driver and release the lease.
+    ///
+    /// Running task is cancelled.
+    pub fn stop(&self) -> LocalBoxFuture<'static, ()> {
+        self.handle.abort();
+        self.lease
+            .release()
+            .map(|res| {
+                if let Err(e) = res {
+                    log::warn!("Can not release lease: {}", e);
+                }
+            })
+            .boxed_local()
+    }
+}
+
+impl fmt::Debug for SingletonHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SingletonHandle").finish()
+    }
+}
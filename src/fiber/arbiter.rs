@@ -101,7 +101,8 @@ impl Arbiter {
         let handle = thread::Builder::new()
             .name(name.clone())
             .spawn(move || {
-                let mut rt = Runtime::new().expect("Can not create Runtime");
+                let mut rt = Runtime::with_blocking(sys.blocking_config())
+                    .expect("Can not create Runtime");
                 let arb = Arbiter::with_sender(arb_tx);
 
                 let (stop, stop_rx) = channel();
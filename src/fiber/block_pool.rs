@@ -24,7 +24,7 @@ pub(crate) fn create_blocking_pool(
     io: &io::Handle,
     timer: &timer::Handle,
     clock: &timer::Clock,
-    thread_cap: usize,
+    config: BlockingConfig,
 ) -> BlockingPool {
     BlockingPool::new(
         builder,
@@ -32,7 +32,26 @@ pub(crate) fn create_blocking_pool(
         io,
         timer,
         clock,
-        thread_cap)
+        config)
+}
+
+/// Blocking pool limits
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BlockingConfig {
+    /// Maximum number of threads
+    pub(crate) max_threads: usize,
+
+    /// Idle threads exit after this timeout
+    pub(crate) keep_alive: Duration,
+}
+
+impl Default for BlockingConfig {
+    fn default() -> Self {
+        BlockingConfig {
+            max_threads: 512,
+            keep_alive: KEEP_ALIVE,
+        }
+    }
 }
 
 pub struct BlockingPool {
@@ -78,6 +97,8 @@ struct Inner {
 
     thread_cap: usize,
 
+    /// Idle threads exit after this timeout
+    keep_alive: Duration,
 }
 
 struct Shared {
@@ -167,7 +188,7 @@ impl BlockingPool {
         io: &io::Handle,
         timer: &timer::Handle,
         clock: &timer::Clock,
-        thread_cap: usize,
+        config: BlockingConfig,
     ) -> BlockingPool {
         let (shutdown_tx, shutdown_rx) = channel();
 
@@ -191,7 +212,8 @@ impl BlockingPool {
                     io_handle: io.clone(),
                     timer_handle: timer.clone(),
                     clock: clock.clone(),
-                    thread_cap,
+                    thread_cap: config.max_threads,
+                    keep_alive: config.keep_alive,
                 }),
             },
             shutdown_rx,
@@ -358,7 +380,7 @@ impl Inner {
             shared.num_idle += 1;
 
             while !shared.shutdown {
-                let lock_result = self.condvar.wait_timeout(shared, KEEP_ALIVE).unwrap();
+                let lock_result = self.condvar.wait_timeout(shared, self.keep_alive).unwrap();
 
                 shared = lock_result.0;
                 let timeout_result = lock_result.1;
@@ -6,9 +6,12 @@ use futures_util::future::{lazy, FutureExt};
 use futures_core::Future;
 use std::fmt;
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::fiber::handle::Handle;
 use crate::fiber::{block_pool, Spawner};
+use crate::fiber::block_pool::BlockingConfig;
+use crate::fiber::diagnostics::{self, RuntimeInfo};
 use crate::krse::thread::ParkThread;
 use crate::fiber::arbiter::{Arbiter, SystemArbiter};
 use crate::fiber::runtime::{Runtime, Callback, Kind, RuntimeInner};
@@ -28,6 +31,12 @@ pub struct Builder {
 
     /// Whether the Arbiter will stop the whole System on uncaught panic. Defaults to false.
     stop_on_panic: bool,
+
+    /// Blocking thread pool limits.
+    blocking: BlockingConfig,
+
+    /// Whether the runtime clock starts paused. Defaults to false.
+    paused_clock: bool,
 }
 
 impl Builder {
@@ -35,6 +44,8 @@ impl Builder {
         Builder {
             name: Cow::Borrowed("fiber"),
             stop_on_panic: false,
+            blocking: BlockingConfig::default(),
+            paused_clock: false,
         }
     }
 
@@ -53,6 +64,37 @@ impl Builder {
         self
     }
 
+    /// Sets the maximum number of threads of the blocking thread pool used
+    /// by `fiber::spawn_blocking()`.
+    ///
+    /// Each arbiter of the System has its own pool. Once the limit is
+    /// reached, blocking functions are queued until a thread is free.
+    ///
+    /// Defaults to 512.
+    pub fn max_blocking_threads(mut self, val: usize) -> Self {
+        assert_ne!(val, 0, "Thread limit cannot be zero");
+        self.blocking.max_threads = val;
+        self
+    }
+
+    /// Sets how long an idle blocking thread is kept before it exits.
+    ///
+    /// Defaults to 10 seconds.
+    pub fn blocking_keep_alive(mut self, val: Duration) -> Self {
+        self.blocking.keep_alive = val;
+        self
+    }
+
+    /// Start the runtime with paused clock.
+    ///
+    /// Clock is frozen from the moment runtime is created, so timers and
+    /// `Instant::now()` never observe wall time.
+    #[cfg(feature = "sim")]
+    pub(crate) fn paused_clock(mut self) -> Self {
+        self.paused_clock = true;
+        self
+    }
+
     /// Create new System.
     ///
     /// This method panics if it can not create kayrx runtime
@@ -77,11 +119,27 @@ impl Builder {
         self.create_runtime(f).run()
     }
 
+    fn register_diagnostics(&self) {
+        diagnostics::register_runtime(RuntimeInfo {
+            system: self.name.to_string(),
+            cpus: num_cpus::get(),
+            max_blocking_threads: self.blocking.max_threads,
+            blocking_keep_alive: self.blocking.keep_alive,
+            stop_on_panic: self.stop_on_panic,
+        });
+    }
+
     fn create_async_runtime(self, local: &LocalSet) -> AsyncSystemRunner {
         let (stop_tx, stop) = channel();
         let (sys_sender, sys_receiver) = unbounded();
 
-        let system = System::construct(sys_sender, Arbiter::new_system(), self.stop_on_panic);
+        self.register_diagnostics();
+        let system = System::construct(
+            sys_sender,
+            Arbiter::new_system(),
+            self.stop_on_panic,
+            self.blocking,
+        );
 
         // system arbiter
         let arb = SystemArbiter::new(stop_tx, sys_receiver);
@@ -99,12 +157,18 @@ impl Builder {
         let (stop_tx, stop) = channel();
         let (sys_sender, sys_receiver) = unbounded();
 
-        let system = System::construct(sys_sender, Arbiter::new_system(), self.stop_on_panic);
+        self.register_diagnostics();
+        let system = System::construct(
+            sys_sender,
+            Arbiter::new_system(),
+            self.stop_on_panic,
+            self.blocking,
+        );
 
         // system arbiter
         let arb = SystemArbiter::new(stop_tx, sys_receiver);
 
-        let mut rt = Runtime::new().unwrap();
+        let mut rt = Runtime::with_config(self.blocking, self.paused_clock).unwrap();
         rt.spawn(arb);
 
         // init system arbiter and run configuration method
@@ -213,6 +277,12 @@ pub struct BuilderInner {
     /// Cap on thread usage.
     max_threads: usize,
 
+    /// Idle blocking threads exit after this timeout.
+    keep_alive: Duration,
+
+    /// Whether the clock is frozen from the start
+    paused_clock: bool,
+
     /// Name used for threads spawned by the runtime.
     pub thread_name: String,
 
@@ -245,6 +315,11 @@ impl BuilderInner {
 
             max_threads: 512,
 
+            keep_alive: BlockingConfig::default().keep_alive,
+
+            // Clock follows wall time
+            paused_clock: false,
+
             // Default thread name
             thread_name: "kayrx-zone-worker".into(),
 
@@ -286,6 +361,16 @@ impl BuilderInner {
         self
     }
 
+    pub fn keep_alive(&mut self, val: Duration) -> &mut Self {
+        self.keep_alive = val;
+        self
+    }
+
+    pub fn paused_clock(&mut self, val: bool) -> &mut Self {
+        self.paused_clock = val;
+        self
+    }
+
     pub fn thread_name(&mut self, val: impl Into<String>) -> &mut Self {
         self.thread_name = val.into();
         self
@@ -318,7 +403,7 @@ impl BuilderInner {
 
     fn build_basic_runtime(&mut self) -> io::Result<RuntimeInner> {
 
-        let clock = timer::create_clock();
+        let clock = timer::create_clock(self.paused_clock);
 
         // Create I/O driver
         let (io_driver, io_handle) = io_in::create_driver(self.enable_io)?;
@@ -333,7 +418,10 @@ impl BuilderInner {
         let spawner = Spawner::Basic(scheduler.spawner());
 
         // Blocking pool
-        let blocking_pool = block_pool::create_blocking_pool(self, &spawner, &io_handle, &timer_handle, &clock, self.max_threads);
+        let blocking_pool = block_pool::create_blocking_pool(self, &spawner, &io_handle, &timer_handle, &clock, BlockingConfig {
+            max_threads: self.max_threads,
+            keep_alive: self.keep_alive,
+        });
         let blocking_spawner = blocking_pool.spawner().clone();
 
         Ok(RuntimeInner {
@@ -361,6 +449,7 @@ impl fmt::Debug for BuilderInner {
         fmt.debug_struct("Builder")
             .field("core_threads", &self.core_threads)
             .field("max_threads", &self.max_threads)
+            .field("keep_alive", &self.keep_alive)
             .field("thread_name", &self.thread_name)
             .field("thread_stack_size", &self.thread_stack_size)
             .field("after_start", &self.after_start.as_ref().map(|_| "..."))
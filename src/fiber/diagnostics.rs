@@ -0,0 +1,220 @@
+//! Process diagnostics snapshot
+use std::fmt;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+lazy_static::lazy_static! {
+    static ref REGISTRY: Mutex<Registry> = Mutex::new(Registry::default());
+}
+
+#[derive(Default)]
+struct Registry {
+    runtime: Option<RuntimeInfo>,
+    next_id: usize,
+    servers: Vec<(usize, ServerInfo)>,
+    tls: Vec<(String, TlsSummary)>,
+    clients: Vec<ClientInfo>,
+}
+
+/// Structured snapshot of the process configuration.
+///
+/// Snapshot is returned by [`Runtime::diagnostics()`](struct.Runtime.html#method.diagnostics),
+/// it is serializable, so it could be included into support bundles or
+/// exposed by an admin endpoint. `Display` implementation formats a
+/// single line summary.
+#[derive(Clone, Debug, Serialize)]
+pub struct Diagnostics {
+    /// Kayrx version
+    pub version: &'static str,
+    /// Enabled optional features
+    pub features: Vec<&'static str>,
+    /// Last started system
+    pub runtime: Option<RuntimeInfo>,
+    /// Running servers
+    pub servers: Vec<ServerInfo>,
+    /// Distinct configurations of http client connectors
+    pub clients: Vec<ClientInfo>,
+}
+
+/// System runtime configuration
+#[derive(Clone, Debug, Serialize)]
+pub struct RuntimeInfo {
+    /// System name
+    pub system: String,
+    /// Number of logical cpus
+    pub cpus: usize,
+    /// Max number of blocking pool threads per arbiter
+    pub max_blocking_threads: usize,
+    /// Idle blocking thread keep-alive
+    pub blocking_keep_alive: Duration,
+    /// System is stopped on arbiter panic
+    pub stop_on_panic: bool,
+}
+
+/// Server configuration
+#[derive(Clone, Debug, Serialize)]
+pub struct ServerInfo {
+    /// Number of workers
+    pub workers: usize,
+    /// Listen backlog
+    pub backlog: i32,
+    /// Max number of concurrent connections per worker
+    pub max_connections: usize,
+    /// Max number of concurrent tls handshakes per worker
+    pub max_tls_handshakes: usize,
+    /// Graceful shutdown timeout
+    pub shutdown_timeout: Duration,
+    /// Bound sockets
+    pub sockets: Vec<SocketInfo>,
+}
+
+/// Bound socket
+#[derive(Clone, Debug, Serialize)]
+pub struct SocketInfo {
+    /// Socket address
+    pub addr: String,
+    /// Tls configuration of the listener
+    pub tls: Option<TlsSummary>,
+}
+
+/// Tls listener configuration
+#[derive(Clone, Debug, Serialize)]
+pub struct TlsSummary {
+    /// Alpn protocols
+    pub alpn_protocols: Vec<String>,
+    /// Enabled protocol versions
+    pub versions: Vec<String>,
+}
+
+/// Http client connector configuration
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ClientInfo {
+    /// Number of connectors with this configuration
+    pub connectors: usize,
+    /// Max number of connections per host
+    pub limit: usize,
+    /// Connect timeout
+    pub connect_timeout: Duration,
+    /// Max lifetime of pooled connection
+    pub conn_lifetime: Duration,
+    /// Max idle time of pooled connection
+    pub conn_keep_alive: Duration,
+    /// Number of configured proxies
+    pub proxies: usize,
+    /// Number of hosts with pinned certificates
+    pub pinned_hosts: usize,
+}
+
+pub(crate) fn snapshot() -> Diagnostics {
+    let reg = REGISTRY.lock();
+    Diagnostics {
+        version: env!("CARGO_PKG_VERSION"),
+        features: features(),
+        runtime: reg.runtime.clone(),
+        servers: reg.servers.iter().map(|(_, srv)| srv.clone()).collect(),
+        clients: reg.clients.clone(),
+    }
+}
+
+fn features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "cookie") {
+        features.push("cookie");
+    }
+    if cfg!(feature = "protobuf") {
+        features.push("protobuf");
+    }
+    if cfg!(feature = "sim") {
+        features.push("sim");
+    }
+    if cfg!(feature = "telemetry") {
+        features.push("telemetry");
+    }
+    if cfg!(feature = "tower") {
+        features.push("tower");
+    }
+    features
+}
+
+pub(crate) fn register_runtime(info: RuntimeInfo) {
+    REGISTRY.lock().runtime = Some(info);
+}
+
+/// Register started server, tls summaries of the listeners are attached
+/// by socket address. Returns id for `unregister_server()`.
+pub(crate) fn register_server(mut info: ServerInfo) -> usize {
+    let mut reg = REGISTRY.lock();
+    for sock in &mut info.sockets {
+        sock.tls = reg
+            .tls
+            .iter()
+            .find(|(addr, _)| *addr == sock.addr)
+            .map(|(_, tls)| tls.clone());
+    }
+    let id = reg.next_id;
+    reg.next_id += 1;
+    reg.servers.push((id, info));
+    id
+}
+
+pub(crate) fn unregister_server(id: usize) {
+    REGISTRY.lock().servers.retain(|(srv, _)| *srv != id);
+}
+
+/// Register tls configuration of the listener address
+pub(crate) fn register_tls(addr: String, tls: TlsSummary) {
+    let mut reg = REGISTRY.lock();
+    reg.tls.retain(|(item, _)| *item != addr);
+    reg.tls.push((addr, tls));
+}
+
+pub(crate) fn register_client(info: ClientInfo) {
+    let mut reg = REGISTRY.lock();
+    let found = reg.clients.iter_mut().find(|item| {
+        ClientInfo {
+            connectors: item.connectors,
+            ..info.clone()
+        } == **item
+    });
+    match found {
+        Some(item) => item.connectors += info.connectors,
+        None => reg.clients.push(info),
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "kayrx {}", self.version)?;
+        if !self.features.is_empty() {
+            write!(f, " [{}]", self.features.join(","))?;
+        }
+        if let Some(ref rt) = self.runtime {
+            write!(f, ", system: {}, cpus: {}", rt.system, rt.cpus)?;
+        }
+        for srv in &self.servers {
+            write!(
+                f,
+                ", workers: {}, maxconn: {}, listening on",
+                srv.workers, srv.max_connections
+            )?;
+            for sock in &srv.sockets {
+                match sock.tls {
+                    Some(ref tls) => write!(
+                        f,
+                        " {} (tls {})",
+                        sock.addr,
+                        tls.alpn_protocols.join(",")
+                    )?,
+                    None => write!(f, " {}", sock.addr)?,
+                }
+            }
+        }
+        if !self.clients.is_empty() {
+            let connectors: usize = self.clients.iter().map(|c| c.connectors).sum();
+            write!(f, ", client connectors: {}", connectors)?;
+        }
+        Ok(())
+    }
+}
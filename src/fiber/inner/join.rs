@@ -112,6 +112,16 @@ impl JoinError {
         }
     }
 
+    /// Resume the panic of the fiber on the current thread.
+    pub(crate) fn resume(self) -> ! {
+        match self.repr {
+            Repr::Cancelled => panic!("fiber was cancelled"),
+            Repr::Panic(err) => std::panic::resume_unwind(
+                err.into_inner().unwrap_or_else(|err| err.into_inner()),
+            ),
+        }
+    }
+
 }
 
 impl fmt::Display for JoinError {
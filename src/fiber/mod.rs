@@ -2,6 +2,7 @@
 
 pub(crate) mod inner;
 pub(crate) mod block_pool;
+pub(crate) mod diagnostics;
 mod arbiter;
 mod builder;
 mod context;
@@ -17,6 +18,9 @@ mod timer;
 
 pub use self::arbiter::Arbiter;
 pub use self::builder::{Builder, SystemRunner};
+pub use self::diagnostics::{
+    ClientInfo, Diagnostics, RuntimeInfo, ServerInfo, SocketInfo, TlsSummary,
+};
 pub use self::runtime::Runtime;
 pub use self::system::System;
 
@@ -58,6 +62,40 @@ where
     context::spawn(fiber)
 }
 
+/// Run blocking function on the blocking thread pool of the current
+/// runtime and return future that resolves to its result.
+///
+/// Use it for blocking filesystem or CPU heavy code, so it does not stall
+/// the event loop. Threads are started on demand up to the limit set with
+/// `Builder::max_blocking_threads()` and exit after being idle for
+/// `Builder::blocking_keep_alive()`. A panic in `f` is resumed when the
+/// future is polled.
+///
+/// # Panics
+///
+/// This function panics if called outside of the kayrx runtime.
+///
+/// ```rust
+/// #[kayrx::main]
+/// async fn main() {
+///     let sum = kayrx::fiber::spawn_blocking(|| (0..1000u64).sum::<u64>()).await;
+///     assert_eq!(sum, 499_500);
+/// }
+/// ```
+pub fn spawn_blocking<F, R>(f: F) -> impl Future<Output = R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let handle = block_pool::spawn_blocking(f);
+    async move {
+        match handle.await {
+            Ok(res) => res,
+            Err(err) => err.resume(),
+        }
+    }
+}
+
 /// Run fiber  on the Threadpool.
 pub fn run<F, R>(f: F) -> JoinHandle<R>
 where
@@ -2,6 +2,8 @@ use std::future::Future;
 use std::io;
 
 use crate::fiber::{Handle, LocalSet, BuilderInner, JoinHandle, timer, BasicScheduler, BlockingPool};
+use crate::fiber::block_pool::BlockingConfig;
+use crate::fiber::diagnostics::{self, Diagnostics};
 use crate::krse::thread::ParkThread;
 
 /// Single-threaded runtime provides a way to start reactor
@@ -20,9 +22,22 @@ impl Runtime {
     #[allow(clippy::new_ret_no_self)]
     /// Returns a new runtime initialized with default configuration values.
     pub fn new() -> io::Result<Runtime> {
+        Runtime::with_blocking(BlockingConfig::default())
+    }
+
+    /// Returns a new runtime with specified blocking pool limits.
+    pub(crate) fn with_blocking(config: BlockingConfig) -> io::Result<Runtime> {
+        Runtime::with_config(config, false)
+    }
+
+    /// Returns a new runtime with specified blocking pool limits and clock mode.
+    pub(crate) fn with_config(config: BlockingConfig, paused_clock: bool) -> io::Result<Runtime> {
         let rt = BuilderInner::new()
                 .enable_io()
                 .enable_timer()
+                .paused_clock(paused_clock)
+                .max_threads(config.max_threads)
+                .keep_alive(config.keep_alive)
                 .build()?;
 
         Ok(Runtime {
@@ -90,6 +105,22 @@ impl Runtime {
         let res = self.local.block_on(&mut self.rt, f);
         res
     }
+
+    /// Returns snapshot of the process configuration.
+    ///
+    /// Snapshot includes kayrx version and enabled features, configuration
+    /// of the last started system, running servers with their bound sockets
+    /// and tls listeners, and http client connectors.
+    ///
+    /// ```rust
+    /// use kayrx::fiber::Runtime;
+    ///
+    /// let diag = Runtime::diagnostics();
+    /// println!("{}", serde_json::to_string(&diag).unwrap());
+    /// ```
+    pub fn diagnostics() -> Diagnostics {
+        diagnostics::snapshot()
+    }
 }
 
 
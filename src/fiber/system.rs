@@ -8,6 +8,7 @@ use futures_channel::mpsc::UnboundedSender;
 use crate::fiber::local::LocalSet;
 use crate::fiber::arbiter::{Arbiter, SystemCommand};
 use crate::fiber::builder::{Builder, SystemRunner};
+use crate::fiber::block_pool::BlockingConfig;
 
 static SYSTEM_COUNT: AtomicUsize = AtomicUsize::new(0);
 
@@ -18,6 +19,7 @@ pub struct System {
     sys: UnboundedSender<SystemCommand>,
     arbiter: Arbiter,
     stop_on_panic: bool,
+    blocking: BlockingConfig,
 }
 
 thread_local!(
@@ -30,11 +32,13 @@ impl System {
         sys: UnboundedSender<SystemCommand>,
         arbiter: Arbiter,
         stop_on_panic: bool,
+        blocking: BlockingConfig,
     ) -> Self {
         let sys = System {
             sys,
             arbiter,
             stop_on_panic,
+            blocking,
             id: SYSTEM_COUNT.fetch_add(1, Ordering::SeqCst),
         };
         System::set_current(sys.clone());
@@ -128,6 +132,11 @@ impl System {
         self.stop_on_panic
     }
 
+    /// Blocking thread pool limits of arbiters
+    pub(crate) fn blocking_config(&self) -> BlockingConfig {
+        self.blocking
+    }
+
     /// System arbiter
     pub fn arbiter(&self) -> &Arbiter {
         &self.arbiter
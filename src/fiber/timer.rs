@@ -11,7 +11,17 @@ pub(crate) type Clock = timer::Clock;
 pub(crate) type Driver = Either<driver::Driver<io::Driver>, io::Driver>;
 pub(crate) type Handle = Option<driver::Handle>;
 
-pub(crate) fn create_clock() -> Clock {
+#[cfg(feature = "sim")]
+pub(crate) fn create_clock(paused: bool) -> Clock {
+        if paused {
+            Clock::new_frozen()
+        } else {
+            Clock::new()
+        }
+}
+
+#[cfg(not(feature = "sim"))]
+pub(crate) fn create_clock(_paused: bool) -> Clock {
         Clock::new()
 }
 
@@ -0,0 +1,132 @@
+//! Entry points for fuzz targets.
+//!
+//! Every function feeds arbitrary input to one of the internal protocol
+//! decoders and drives it until the input is exhausted. Malformed input must
+//! produce a `DecodeError`, any panic is a bug.
+//!
+//! ```rust,ignore
+//! #![no_main]
+//! use libfuzzer_sys::fuzz_target;
+//!
+//! fuzz_target!(|data: &[u8]| {
+//!     let _ = kayrx::fuzz::h1_request(data);
+//! });
+//! ```
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::{Bytes, BytesMut};
+use derive_more::{Display, From};
+use futures_core::Stream;
+use futures_util::stream;
+use futures_util::task::noop_waker_ref;
+
+use crate::codec::Decoder;
+use crate::http::error::{ParseError, PayloadError};
+use crate::http::h1::decoder::{MessageDecoder, MessageType, PayloadItem, PayloadType};
+use crate::http::header::{self, HeaderMap, HeaderValue};
+use crate::http::{Request, ResponseHead};
+use crate::web::multipart::{Multipart, MultipartError};
+use crate::websocket::{Codec, ProtocolError};
+
+/// Errors produced by decoders on malformed input
+#[derive(Debug, Display, From)]
+pub enum DecodeError {
+    /// Http message head is invalid
+    #[display(fmt = "Http message error: {}", _0)]
+    Parse(ParseError),
+    /// Http message payload is invalid
+    #[display(fmt = "Http payload error: {}", _0)]
+    Payload(io::Error),
+    /// WebSocket frame is invalid
+    #[display(fmt = "WebSocket protocol error: {}", _0)]
+    Protocol(ProtocolError),
+    /// Multipart stream is invalid
+    #[display(fmt = "Multipart error: {}", _0)]
+    Multipart(MultipartError),
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Decode pipelined http/1 requests, returns number of complete messages.
+pub fn h1_request(data: &[u8]) -> Result<usize, DecodeError> {
+    h1_messages::<Request>(data)
+}
+
+/// Decode pipelined http/1 responses, returns number of complete messages.
+pub fn h1_response(data: &[u8]) -> Result<usize, DecodeError> {
+    h1_messages::<ResponseHead>(data)
+}
+
+fn h1_messages<T: MessageType>(data: &[u8]) -> Result<usize, DecodeError> {
+    let mut buf = BytesMut::from(data);
+    let mut decoder = MessageDecoder::<T>::default();
+    let mut count = 0;
+
+    while let Some((_, payload)) = decoder.decode(&mut buf)? {
+        count += 1;
+        let mut payload = match payload {
+            PayloadType::None => continue,
+            PayloadType::Payload(pl) | PayloadType::Stream(pl) => pl,
+        };
+        loop {
+            match payload.decode(&mut buf)? {
+                Some(PayloadItem::Chunk(_)) => (),
+                Some(PayloadItem::Eof) => break,
+                None => return Ok(count),
+            }
+        }
+    }
+    Ok(count)
+}
+
+/// Decode websocket frames, returns number of decoded frames.
+///
+/// `server` selects the side of connection, server side expects
+/// masked frames.
+pub fn ws_frames(data: &[u8], server: bool) -> Result<usize, DecodeError> {
+    let mut buf = BytesMut::from(data);
+    let mut codec = if server {
+        Codec::new()
+    } else {
+        Codec::new().client_mode()
+    };
+    let mut count = 0;
+
+    while codec.decode(&mut buf)?.is_some() {
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Parse multipart body with given boundary, returns number of fields.
+pub fn multipart(boundary: &str, data: &[u8]) -> Result<usize, DecodeError> {
+    let ct = HeaderValue::from_str(&format!("multipart/form-data; boundary={}", boundary))
+        .map_err(|_| MultipartError::ParseContentType)?;
+    let mut headers = HeaderMap::new();
+    headers.insert(header::CONTENT_TYPE, ct);
+
+    let body: Result<Bytes, PayloadError> = Ok(Bytes::copy_from_slice(data));
+    let mut mp = Multipart::new(&headers, stream::iter(Some(body)));
+    let mut cx = Context::from_waker(noop_waker_ref());
+    let mut count = 0;
+
+    loop {
+        let mut field = match Pin::new(&mut mp).poll_next(&mut cx) {
+            Poll::Ready(Some(Ok(field))) => field,
+            Poll::Ready(Some(Err(err))) => return Err(err.into()),
+            Poll::Ready(None) | Poll::Pending => return Ok(count),
+        };
+        count += 1;
+
+        loop {
+            match Pin::new(&mut field).poll_next(&mut cx) {
+                Poll::Ready(Some(Ok(_))) => (),
+                Poll::Ready(Some(Err(err))) => return Err(err.into()),
+                Poll::Ready(None) => break,
+                Poll::Pending => return Ok(count),
+            }
+        }
+    }
+}
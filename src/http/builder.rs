@@ -1,12 +1,16 @@
 use std::marker::PhantomData;
 use std::rc::Rc;
+use std::time::Duration;
 use std::{fmt, net};
 
 use crate::codec::Framed2 as Framed;
 use crate::service::{IntoServiceFactory, Service, ServiceFactory};
 
 use crate::http::body::MessageBody;
-use crate::http::config::{KeepAlive, ServiceConfig};
+use crate::http::config::{
+    ConnLimits, H1Timeouts, H2Settings, KeepAlive, ServiceConfig, WriteRate,
+};
+use crate::http::h2::Reason;
 use crate::http::error::Error;
 use crate::http::h1::{Codec, ExpectHandler, H1Service, UpgradeHandler};
 use crate::http::h2::H2Service;
@@ -25,6 +29,12 @@ pub struct HttpServiceBuilder<T, S, X = ExpectHandler, U = UpgradeHandler<T>> {
     client_disconnect: u64,
     secure: bool,
     local_addr: Option<net::SocketAddr>,
+    conn_limits: ConnLimits,
+    h2_settings: H2Settings,
+    h1_timeouts: H1Timeouts,
+    write_rate: Option<WriteRate>,
+    write_buffer: Option<(usize, usize)>,
+    read_buffer: Option<(usize, usize)>,
     expect: X,
     upgrade: Option<U>,
     on_connect: Option<Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
@@ -46,6 +56,12 @@ where
             client_disconnect: 0,
             secure: false,
             local_addr: None,
+            conn_limits: ConnLimits::default(),
+            h2_settings: H2Settings::default(),
+            h1_timeouts: H1Timeouts::default(),
+            write_rate: None,
+            write_buffer: None,
+            read_buffer: None,
             expect: ExpectHandler,
             upgrade: None,
             on_connect: None,
@@ -116,6 +132,189 @@ where
         self
     }
 
+    /// Set max lifetime of the connection.
+    ///
+    /// After lifetime connection is closed gracefully, http/1 connection
+    /// finishes in-flight request with `Connection: close`, http/2
+    /// connection sends `GOAWAY` and finishes active streams. Clients
+    /// reconnect, so load gets rebalanced across instances behind load
+    /// balancer even with long-lived keep-alive clients.
+    ///
+    /// By default lifetime is not limited.
+    pub fn conn_lifetime(mut self, dur: Duration) -> Self {
+        self.conn_limits.lifetime = Some(dur);
+        self
+    }
+
+    /// Set max idle period of the connection.
+    ///
+    /// Connection without in-flight requests for this period is closed
+    /// gracefully. For http/1 connection it limits keep-alive period,
+    /// for http/2 connection it is the only idle limit.
+    ///
+    /// By default idle period is not limited.
+    pub fn conn_max_idle(mut self, dur: Duration) -> Self {
+        self.conn_limits.max_idle = Some(dur);
+        self
+    }
+
+    /// Set jitter of connection lifetime limits.
+    ///
+    /// Each connection gets its lifetime and max idle period shortened by
+    /// random part of the jitter, i.e. with `0.1` limits are between 90%
+    /// and 100% of configured values. It prevents synchronized closes of
+    /// connections opened at the same time. Value is clamped to `0.0..=1.0`.
+    ///
+    /// By default jitter is set to 0.1.
+    pub fn conn_jitter(mut self, jitter: f64) -> Self {
+        self.conn_limits.jitter = jitter.max(0.0).min(1.0);
+        self
+    }
+
+    pub(crate) fn conn_limits(mut self, limits: ConnLimits) -> Self {
+        self.conn_limits = limits;
+        self
+    }
+
+    /// Set max number of concurrent http/2 streams per connection.
+    ///
+    /// Limit is advertised to the client with `SETTINGS_MAX_CONCURRENT_STREAMS`,
+    /// requests over the limit are rejected according to
+    /// [`h2_overflow_reason`](#method.h2_overflow_reason). It prevents a
+    /// single connection from monopolizing the worker.
+    ///
+    /// By default number of streams is not limited.
+    pub fn h2_max_concurrent_streams(mut self, max: u32) -> Self {
+        self.h2_settings.max_concurrent_streams = Some(max);
+        self
+    }
+
+    /// Set error for http/2 streams over the max concurrent streams limit.
+    ///
+    /// `Reason::REFUSED_STREAM` resets the stream and client could safely
+    /// retry the request. `Reason::ENHANCE_YOUR_CALM` closes the connection
+    /// of misbehaving client with `GOAWAY` frame, in-flight streams are
+    /// finished.
+    ///
+    /// By default `Reason::REFUSED_STREAM` is used.
+    pub fn h2_overflow_reason(mut self, reason: Reason) -> Self {
+        self.h2_settings.overflow_reason = reason;
+        self
+    }
+
+    /// Set initial receive window size (in octets) of http/2 streams.
+    ///
+    /// Window limits amount of request body data client could send before
+    /// the service reads it. By default window size is 65,535.
+    pub fn h2_initial_window_size(mut self, size: u32) -> Self {
+        self.h2_settings.initial_window_size = Some(size);
+        self
+    }
+
+    /// Set initial receive window size (in octets) of http/2 connection.
+    ///
+    /// By default window size is 65,535.
+    pub fn h2_initial_connection_window_size(mut self, size: u32) -> Self {
+        self.h2_settings.initial_connection_window_size = Some(size);
+        self
+    }
+
+    pub(crate) fn h2_settings(mut self, settings: H2Settings) -> Self {
+        self.h2_settings = settings;
+        self
+    }
+
+    /// Set timeout for reading request head.
+    ///
+    /// Timer starts when the first bytes of a request head are received,
+    /// on new and keep-alive connections. If the head is not complete
+    /// within the timeout, client gets 408 (Request Time-out) response
+    /// and connection is closed, so clients that trickle headers can not
+    /// hold the connection. Applies to http/1 connections.
+    ///
+    /// By default only the first request is limited by client timeout.
+    pub fn request_header_timeout(mut self, timeout: Duration) -> Self {
+        self.h1_timeouts.header = Some(timeout);
+        self
+    }
+
+    /// Set timeout for flushing response data.
+    ///
+    /// If buffered response data waits for the client and no data is
+    /// written to the socket within the timeout, connection is dropped with
+    /// `DispatchError::FlushTimeout` error. Applies to http/1 connections.
+    ///
+    /// By default flush timeout is not set.
+    pub fn response_flush_timeout(mut self, timeout: Duration) -> Self {
+        self.h1_timeouts.flush = Some(timeout);
+        self
+    }
+
+    pub(crate) fn h1_timeouts(mut self, timeouts: H1Timeouts) -> Self {
+        self.h1_timeouts = timeouts;
+        self
+    }
+
+    /// Set minimal rate of response writes to the client.
+    ///
+    /// If response data waits for the client and client receives less than
+    /// `bytes_per_sec` on average during `period`, connection is dropped with
+    /// `DispatchError::SlowClient` error, for http/2 the stream is reset.
+    /// It protects server memory from responses buffered for stalled
+    /// clients. With `telemetry` feature slow clients are counted by
+    /// `http.server.slow_clients` counter.
+    ///
+    /// By default write rate is not limited.
+    pub fn min_write_rate(mut self, bytes_per_sec: u64, period: Duration) -> Self {
+        self.write_rate = Some(WriteRate {
+            bytes_per_sec,
+            period,
+        });
+        self
+    }
+
+    pub(crate) fn write_rate(mut self, rate: Option<WriteRate>) -> Self {
+        self.write_rate = rate;
+        self
+    }
+
+    /// Set watermarks of the response write buffer.
+    ///
+    /// Streaming response body is not polled while buffered data reaches
+    /// `high` watermark, polling resumes when socket drains buffer below
+    /// `low` watermark. It applies backpressure to producers that outpace
+    /// the client, including websocket sessions. `low` is clamped to `high`.
+    ///
+    /// By default both watermarks are 32Kb.
+    pub fn write_buffer(mut self, high: usize, low: usize) -> Self {
+        self.write_buffer = Some((high, std::cmp::min(low, high)));
+        self
+    }
+
+    pub(crate) fn write_buffer_limits(mut self, buffer: Option<(usize, usize)>) -> Self {
+        self.write_buffer = buffer;
+        self
+    }
+
+    /// Set min and max size of the socket read chunk.
+    ///
+    /// Read chunk starts at `min` and doubles while reads fill it, up to
+    /// `max`, so connections with sustained throughput do fewer syscalls.
+    /// Chunk shrinks back after small reads and memory of the empty buffer
+    /// is released while connection is idle. `max` is clamped to `min`.
+    /// Applies to http/1 and http/2 connections.
+    ///
+    /// By default min is 4Kb and max is 32Kb.
+    pub fn read_buffer(mut self, min: usize, max: usize) -> Self {
+        self.read_buffer = Some((min, std::cmp::max(min, max)));
+        self
+    }
+
+    pub(crate) fn read_buffer_limits(mut self, buffer: Option<(usize, usize)>) -> Self {
+        self.read_buffer = buffer;
+        self
+    }
+
     /// Provide service for `EXPECT: 100-Continue` support.
     ///
     /// Service get called with request that contains `EXPECT` header.
@@ -135,6 +334,12 @@ where
             client_disconnect: self.client_disconnect,
             secure: self.secure,
             local_addr: self.local_addr,
+            conn_limits: self.conn_limits,
+            h2_settings: self.h2_settings,
+            h1_timeouts: self.h1_timeouts,
+            write_rate: self.write_rate,
+            write_buffer: self.write_buffer,
+            read_buffer: self.read_buffer,
             expect: expect.into_factory(),
             upgrade: self.upgrade,
             on_connect: self.on_connect,
@@ -164,6 +369,12 @@ where
             client_disconnect: self.client_disconnect,
             secure: self.secure,
             local_addr: self.local_addr,
+            conn_limits: self.conn_limits,
+            h2_settings: self.h2_settings,
+            h1_timeouts: self.h1_timeouts,
+            write_rate: self.write_rate,
+            write_buffer: self.write_buffer,
+            read_buffer: self.read_buffer,
             expect: self.expect,
             upgrade: Some(upgrade.into_factory()),
             on_connect: self.on_connect,
@@ -199,7 +410,12 @@ where
             self.client_disconnect,
             self.secure,
             self.local_addr,
-        );
+        )
+        .with_conn_limits(self.conn_limits)
+        .with_h1_timeouts(self.h1_timeouts)
+        .with_write_rate(self.write_rate)
+        .with_write_buffer(self.write_buffer)
+        .with_read_buffer(self.read_buffer);
         H1Service::with_config(cfg, service.into_factory())
             .expect(self.expect)
             .upgrade(self.upgrade)
@@ -222,7 +438,13 @@ where
             self.client_disconnect,
             self.secure,
             self.local_addr,
-        );
+        )
+        .with_conn_limits(self.conn_limits)
+        .with_h2_settings(self.h2_settings)
+        .with_h1_timeouts(self.h1_timeouts)
+        .with_write_rate(self.write_rate)
+        .with_write_buffer(self.write_buffer)
+        .with_read_buffer(self.read_buffer);
         H2Service::with_config(cfg, service.into_factory()).on_connect(self.on_connect)
     }
 
@@ -242,7 +464,13 @@ where
             self.client_disconnect,
             self.secure,
             self.local_addr,
-        );
+        )
+        .with_conn_limits(self.conn_limits)
+        .with_h2_settings(self.h2_settings)
+        .with_h1_timeouts(self.h1_timeouts)
+        .with_write_rate(self.write_rate)
+        .with_write_buffer(self.write_buffer)
+        .with_read_buffer(self.read_buffer);
         HttpService::with_config(cfg, service.into_factory())
             .expect(self.expect)
             .upgrade(self.upgrade)
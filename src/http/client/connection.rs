@@ -1,4 +1,5 @@
 use std::pin::Pin;
+use std::rc::Rc;
 use std::task::{Context, Poll};
 use std::{fmt, io, mem, time};
 
@@ -15,7 +16,9 @@ use crate::http::message::{RequestHeadType, ResponseHead};
 use crate::http::payload::Payload;
 
 use super::error::SendRequestError;
+use super::metrics::Metrics;
 use super::pool::{Acquired, Protocol};
+use super::proxy::Forward;
 use super::{h1proto, h2proto};
 
 pub(crate) enum ConnectionType<Io> {
@@ -42,6 +45,15 @@ pub trait Connection {
 
     /// Send request, returns Response and Framed
     fn open_tunnel<H: Into<RequestHeadType>>(self, head: H) -> Self::TunnelFuture;
+
+    /// Return unused connection to the connection pool.
+    ///
+    /// By default connection is closed.
+    fn release(self)
+    where
+        Self: Sized,
+    {
+    }
 }
 
 pub(crate) trait ConnectionLifetime: AsyncRead + AsyncWrite + 'static {
@@ -58,6 +70,8 @@ pub struct IoConnection<T> {
     io: Option<ConnectionType<T>>,
     created: time::Instant,
     pool: Option<Acquired<T>>,
+    forward: Option<Forward>,
+    metrics: Option<(Rc<dyn Metrics>, String)>,
 }
 
 impl<T> fmt::Debug for IoConnection<T>
@@ -83,9 +97,21 @@ impl<T: AsyncRead + AsyncWrite + Unpin> IoConnection<T> {
             pool,
             created,
             io: Some(io),
+            forward: None,
+            metrics: None,
         }
     }
 
+    /// Send requests in absolute form, connection is opened to http proxy
+    pub(crate) fn set_forward(&mut self, forward: Option<Forward>) {
+        self.forward = forward;
+    }
+
+    /// Report time to first byte of the response for the host
+    pub(crate) fn set_metrics(&mut self, metrics: Rc<dyn Metrics>, host: String) {
+        self.metrics = Some((metrics, host));
+    }
+
     pub(crate) fn into_inner(self) -> (ConnectionType<T>, time::Instant) {
         (self.io.unwrap(), self.created)
     }
@@ -112,15 +138,36 @@ where
         head: H,
         body: B,
     ) -> Self::Future {
-        match self.io.take().unwrap() {
+        let fut = match self.io.take().unwrap() {
             ConnectionType::H1(io) => {
-                h1proto::send_request(io, head.into(), body, self.created, self.pool)
-                    .boxed_local()
+                h1proto::send_request(
+                    io,
+                    head.into(),
+                    body,
+                    self.created,
+                    self.pool,
+                    self.forward,
+                )
+                .boxed_local()
             }
             ConnectionType::H2(io) => {
                 h2proto::send_request(io, head.into(), body, self.created, self.pool)
                     .boxed_local()
             }
+        };
+
+        match self.metrics.take() {
+            None => fut,
+            Some((metrics, host)) => {
+                let start = time::Instant::now();
+                fut.map(move |res| {
+                    if res.is_ok() {
+                        metrics.first_byte(&host, start.elapsed());
+                    }
+                    res
+                })
+                .boxed_local()
+            }
         }
     }
 
@@ -150,6 +197,12 @@ where
             }
         }
     }
+
+    fn release(mut self) {
+        if let (Some(io), Some(mut pool)) = (self.io.take(), self.pool.take()) {
+            pool.release(IoConnection::new(io, self.created, None));
+        }
+    }
 }
 
 #[allow(dead_code)]
@@ -203,6 +256,13 @@ where
                 .boxed_local(),
         }
     }
+
+    fn release(self) {
+        match self {
+            EitherConnection::A(con) => con.release(),
+            EitherConnection::B(con) => con.release(),
+        }
+    }
 }
 
 #[pin_project]
@@ -1,20 +1,30 @@
+use std::collections::HashMap;
 use std::fmt;
+use std::future::Future;
 use std::marker::PhantomData;
-use std::time::Duration;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use futures_util::future::{Either, FutureExt};
 use http::Uri;
 use std::sync::Arc;
 
 use crate::krse::io::{AsyncRead, AsyncWrite};
 use crate::connect::{
-    default_connector, Connect as TcpConnect, Connection as TcpConnection,
+    default_connector, Address, Connect as TcpConnect, Connection as TcpConnection,
+    Resolver, UnixConnector,
 };
-use crate::krse::net::TcpStream;
+use crate::krse::net::{TcpStream, UnixStream};
 use crate::service::{apply_fn, Service};
 use crate::util::timeout::{TimeoutError, TimeoutService};
+use crate::fiber::diagnostics::{self, ClientInfo};
 use super::connection::Connection;
 use super::error::ConnectError;
+use super::metrics::{Metrics, PoolStats};
 use super::pool::{ConnectionPool, Protocol};
-use super::Connect;
+use super::proxy::{self, Proxy, ProxyKind};
+use super::{socks, Connect};
 use crate::connect::ssl::rustls::ClientConfig;
 
 
@@ -42,6 +52,13 @@ pub struct Connector<T, U> {
     conn_keep_alive: Duration,
     disconnect_timeout: Duration,
     limit: usize,
+    min_idle: usize,
+    h2_keep_alive: Option<(Duration, Duration)>,
+    pins: HashMap<String, Vec<[u8; 32]>>,
+    proxies: Vec<Proxy>,
+    metrics: Option<Rc<dyn Metrics>>,
+    stats: PoolStats,
+    resolve: bool,
     #[allow(dead_code)]
     ssl: SslConnector,
     _t: PhantomData<U>,
@@ -81,6 +98,13 @@ impl Connector<(), ()> {
             conn_keep_alive: Duration::from_secs(15),
             disconnect_timeout: Duration::from_millis(3000),
             limit: 100,
+            min_idle: 0,
+            h2_keep_alive: None,
+            pins: HashMap::new(),
+            proxies: Vec::new(),
+            metrics: None,
+            stats: PoolStats::default(),
+            resolve: true,
             _t: PhantomData,
         }
     }
@@ -104,10 +128,42 @@ impl<T, U> Connector<T, U> {
             conn_keep_alive: self.conn_keep_alive,
             disconnect_timeout: self.disconnect_timeout,
             limit: self.limit,
+            min_idle: self.min_idle,
+            h2_keep_alive: self.h2_keep_alive,
+            pins: self.pins,
+            proxies: self.proxies,
+            metrics: self.metrics,
+            stats: self.stats,
+            resolve: true,
             ssl: self.ssl,
             _t: PhantomData,
         }
     }
+
+    /// Connect to the unix domain socket instead of the request host.
+    ///
+    /// All requests use connections to the socket path, request uri
+    /// still defines `Host` header, tls server name and pool of the
+    /// connection. Previously configured proxies are removed.
+    ///
+    /// ```rust,ignore
+    /// use kayrx::http::client::Connector;
+    /// use kayrx::web::client::Client;
+    ///
+    /// let client = Client::build()
+    ///     .connector(Connector::new().unix_socket("/var/run/docker.sock").finish())
+    ///     .finish();
+    /// let res = client.get("http://docker/containers/json").send().await;
+    /// ```
+    pub fn unix_socket<P: AsRef<Path>>(
+        self,
+        path: P,
+    ) -> Connector<UnixConnector<Uri>, UnixStream> {
+        let mut connector = self.connector(UnixConnector::new(path));
+        connector.resolve = false;
+        connector.proxies.clear();
+        connector
+    }
 }
 
 impl<T, U> Connector<T, U>
@@ -132,6 +188,62 @@ where
         self
     }
 
+    /// Route connections through SOCKS5 proxy.
+    ///
+    /// Host names are resolved by the proxy. Requests with isolation label
+    /// (see `ClientRequest::isolation()`) authenticate with the label as
+    /// username and password, so proxies with stream isolation (i.e. Tor's
+    /// `IsolateSOCKSAuth`) use separate circuits for different labels.
+    /// Connections with different labels are never shared by the pool.
+    pub fn socks5_proxy(mut self, addr: SocketAddr) -> Self {
+        self.proxies.push(Proxy::socks5(addr));
+        self
+    }
+
+    /// Route connections through proxy.
+    ///
+    /// Could be called multiple times, i.e. to use different proxies for
+    /// `http` and `https` requests, the first proxy that intercepts the
+    /// request is used. Requests to hosts from proxy's no-proxy list are
+    /// sent directly.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxies.push(proxy);
+        self
+    }
+
+    /// Pin server certificate public keys for the host.
+    ///
+    /// Pins are sha256 digests of the DER-encoded `SubjectPublicKeyInfo`,
+    /// the same values that are used by HPKP `pin-sha256` directive. After
+    /// regular certificate verification, one of the certificates in the
+    /// presented chain must match one of the host's pins, otherwise
+    /// connection fails with `ConnectError::PinMismatch` error. Connections
+    /// to hosts without pins are not affected. Could be called multiple
+    /// times, pins for the same host are accumulated.
+    ///
+    /// ```rust,ignore
+    /// use kayrx::http::client::Connector;
+    ///
+    /// let pin = base64::decode("r/mIkG3eEpVdm+u/ko/cwxzOMo1bk4TyHIlByibiA5E=").unwrap();
+    /// let mut hash = [0u8; 32];
+    /// hash.copy_from_slice(&pin);
+    ///
+    /// let connector = Connector::new()
+    ///      .pin_server_cert("api.example.com", vec![hash])
+    ///      .finish();
+    /// ```
+    pub fn pin_server_cert<H, I>(mut self, host: H, pins: I) -> Self
+    where
+        H: AsRef<str>,
+        I: IntoIterator<Item = [u8; 32]>,
+    {
+        self.pins
+            .entry(host.as_ref().to_ascii_lowercase())
+            .or_insert_with(Vec::new)
+            .extend(pins);
+        self
+    }
+
     /// Set total number of simultaneous connections per type of scheme.
     ///
     /// If limit is 0, the connector has no limit.
@@ -141,6 +253,20 @@ where
         self
     }
 
+    /// Set minimum number of idle connections per host.
+    ///
+    /// Pool keeps at least this many connections open to every host it has
+    /// connected to, connections are opened in background after a connection
+    /// is closed or expired. Use `Client::warmup()` to connect to hosts
+    /// at startup. Idle connections count towards connections limit and
+    /// are still subject to keep-alive and lifetime periods.
+    ///
+    /// By default idle connections are not maintained.
+    pub fn min_idle_per_host(mut self, num: usize) -> Self {
+        self.min_idle = num;
+        self
+    }
+
     /// Set keep-alive period for opened connection.
     ///
     /// Keep-alive period is the period between connection usage. If
@@ -162,6 +288,38 @@ where
         self
     }
 
+    /// Enable keep-alive pings for http/2 connections.
+    ///
+    /// PING frame is sent every `interval`, if peer does not acknowledge it
+    /// within `timeout` connection is closed and evicted from the pool.
+    /// It allows to detect connections silently dropped by NAT or load
+    /// balancers before a request is sent over them.
+    ///
+    /// By default keep-alive pings are disabled.
+    pub fn h2_keep_alive(mut self, interval: Duration, timeout: Duration) -> Self {
+        self.h2_keep_alive = Some((interval, timeout));
+        self
+    }
+
+    /// Report connection events to the metrics.
+    ///
+    /// Dns resolution, tcp connect, tls handshake and time to first byte
+    /// of the response are timed, pool hits and misses are counted. If
+    /// metrics are set, host names are resolved with the default resolver
+    /// before connector is called, so dns time is reported separately
+    /// from connect time.
+    pub fn metrics<M: Metrics + 'static>(mut self, metrics: M) -> Self {
+        self.metrics = Some(Rc::new(metrics));
+        self
+    }
+
+    /// Stats of the connection pools.
+    ///
+    /// Returned handle reflects pools that are created by `finish()`.
+    pub fn stats(&self) -> PoolStats {
+        self.stats.clone()
+    }
+
     /// Set server connection disconnect timeout in milliseconds.
     ///
     /// Defines a timeout for disconnect connection. If a disconnect procedure does not complete
@@ -182,37 +340,68 @@ where
         self,
     ) -> impl Service<Request = Connect, Response = impl Connection, Error = ConnectError>
            + Clone {
+        diagnostics::register_client(ClientInfo {
+            connectors: 1,
+            limit: self.limit,
+            connect_timeout: self.timeout,
+            conn_lifetime: self.conn_lifetime,
+            conn_keep_alive: self.conn_keep_alive,
+            proxies: self.proxies.len(),
+            pinned_hosts: self.pins.len(),
+        });
+
         {
             const H2: &[u8] = b"h2";
             use crate::connect::ssl::rustls::{RustlsConnector, Session};
             use crate::service::{boxed::service, pipeline};
 
+            let ssl = match self.ssl {
+                SslConnector::Rustls(ssl) if !self.pins.is_empty() => {
+                    let mut config = (*ssl).clone();
+                    config.dangerous().set_certificate_verifier(Arc::new(
+                        super::pinning::PinnedCertVerifier::new(self.pins),
+                    ));
+                    SslConnector::Rustls(Arc::new(config))
+                }
+                ssl => ssl,
+            };
+            let proxies = Rc::new(self.proxies);
+            let proxies2 = proxies.clone();
+            let proxies3 = proxies.clone();
+            let metrics = self.metrics;
+            let metrics2 = metrics.clone();
+            let metrics3 = metrics.clone();
+            let metrics4 = metrics.clone();
+            let resolve = self.resolve;
+
             let ssl_service = TimeoutService::new(
                 self.timeout,
                 pipeline(
-                    apply_fn(self.connector.clone(), |msg: Connect, srv| {
-                        srv.call(TcpConnect::new(msg.uri).set_addr(msg.addr))
+                    apply_fn(self.connector.clone(), move |msg: Connect, srv| {
+                        tcp_connect(srv, msg, &proxies, &metrics2, resolve)
                     })
                     .map_err(ConnectError::from),
                 )
-                .and_then(match self.ssl {
+                .and_then(match ssl {
                     SslConnector::Rustls(ssl) => service(
-                        RustlsConnector::service(ssl)
-                            .map_err(ConnectError::from)
-                            .map(|stream| {
-                                let sock = stream.into_parts().0;
-                                let h2 = sock
-                                    .get_ref()
-                                    .1
-                                    .get_alpn_protocol()
-                                    .map(|protos| protos.windows(2).any(|w| w == H2))
-                                    .unwrap_or(false);
-                                if h2 {
-                                    (Box::new(sock) as Box<dyn Io>, Protocol::Http2)
-                                } else {
-                                    (Box::new(sock) as Box<dyn Io>, Protocol::Http1)
-                                }
-                            }),
+                        apply_fn(RustlsConnector::service(ssl), move |conn: TcpConnection<Uri, U>, srv| {
+                            tls_connect(srv, conn, &metrics3)
+                        })
+                        .map_err(ConnectError::from)
+                        .map(|stream| {
+                            let sock = stream.into_parts().0;
+                            let h2 = sock
+                                .get_ref()
+                                .1
+                                .get_alpn_protocol()
+                                .map(|protos| protos.windows(2).any(|w| w == H2))
+                                .unwrap_or(false);
+                            if h2 {
+                                (Box::new(sock) as Box<dyn Io>, Protocol::Http2)
+                            } else {
+                                (Box::new(sock) as Box<dyn Io>, Protocol::Http1)
+                            }
+                        }),
                     ),
                 }),
             )
@@ -223,8 +412,8 @@ where
 
             let tcp_service = TimeoutService::new(
                 self.timeout,
-                apply_fn(self.connector, |msg: Connect, srv| {
-                    srv.call(TcpConnect::new(msg.uri).set_addr(msg.addr))
+                apply_fn(self.connector, move |msg: Connect, srv| {
+                    tcp_connect(srv, msg, &proxies2, &metrics4, resolve)
                 })
                 .map_err(ConnectError::from)
                 .map(|stream| (stream.into_parts().0, Protocol::Http1)),
@@ -241,19 +430,149 @@ where
                     self.conn_keep_alive,
                     None,
                     self.limit,
-                ),
+                    self.min_idle,
+                    self.h2_keep_alive,
+                )
+                .instrument(metrics.clone(), &self.stats),
                 ssl_pool: ConnectionPool::new(
                     ssl_service,
                     self.conn_lifetime,
                     self.conn_keep_alive,
                     Some(self.disconnect_timeout),
                     self.limit,
-                ),
+                    self.min_idle,
+                    self.h2_keep_alive,
+                )
+                .instrument(metrics, &self.stats),
+                proxies: proxies3,
+            }
+        }
+    }
+}
+
+/// Open tcp connection to the host, directly or through proxy
+fn tcp_connect<T, U>(
+    srv: &mut T,
+    msg: Connect,
+    proxies: &[Proxy],
+    metrics: &Option<Rc<dyn Metrics>>,
+    resolve: bool,
+) -> impl Future<Output = Result<TcpConnection<Uri, U>, crate::connect::ConnectError>>
+where
+    T: Service<
+            Request = TcpConnect<Uri>,
+            Response = TcpConnection<Uri, U>,
+            Error = crate::connect::ConnectError,
+        > + Clone
+        + 'static,
+    U: AsyncRead + AsyncWrite + Unpin + 'static,
+{
+    let proxy = proxies.iter().find(|proxy| proxy.intercepts(&msg.uri)).cloned();
+    let req = match proxy {
+        Some(ref proxy) => TcpConnect::new(proxy.uri().clone()).set_addr(proxy.addr()),
+        None => TcpConnect::new(msg.uri.clone()).set_addr(msg.addr),
+    };
+    let host = msg
+        .uri
+        .authority()
+        .map(|authority| authority.as_str().to_owned())
+        .unwrap_or_default();
+    let fut = match metrics {
+        Some(metrics) => Either::Left(timed_connect(
+            srv.clone(),
+            req,
+            host,
+            metrics.clone(),
+            resolve,
+        )),
+        None => Either::Right(srv.call(req)),
+    };
+
+    async move {
+        let proxy = match proxy {
+            Some(proxy) => proxy,
+            None => return fut.await,
+        };
+        let mut io = fut.await?.into_parts().0;
+        let host = Address::host(&msg.uri);
+        let port = Address::port(&msg.uri).unwrap_or(80);
+
+        match proxy.kind() {
+            ProxyKind::Socks5 => {
+                // isolation label is used as credentials, so proxies with
+                // stream isolation use separate circuits for different labels
+                let credentials = match msg.isolation {
+                    Some(ref label) => Some((label.as_str(), label.as_str())),
+                    None => proxy.credentials(),
+                };
+                socks::handshake(&mut io, host, port, credentials).await?;
+            }
+            ProxyKind::Http if proxy.forwards(&msg.uri) => (),
+            ProxyKind::Http => {
+                proxy::tunnel(&mut io, host, port, proxy.authorization()).await?;
             }
         }
+        Ok(TcpConnection::from_parts(io, msg.uri))
     }
 }
 
+/// Resolve host name and open connection, report time of both stages.
+///
+/// Name is not resolved for connectors that do not use host address.
+async fn timed_connect<T, U>(
+    mut srv: T,
+    req: TcpConnect<Uri>,
+    host: String,
+    metrics: Rc<dyn Metrics>,
+    resolve: bool,
+) -> Result<TcpConnection<Uri, U>, crate::connect::ConnectError>
+where
+    T: Service<
+        Request = TcpConnect<Uri>,
+        Response = TcpConnection<Uri, U>,
+        Error = crate::connect::ConnectError,
+    >,
+{
+    let start = Instant::now();
+    let req = if resolve && req.addr.is_none() {
+        let req = Resolver::default().call(req).await?;
+        metrics.dns(&host, start.elapsed());
+        req
+    } else {
+        req
+    };
+
+    let start = Instant::now();
+    let conn = srv.call(req).await?;
+    metrics.connect(&host, start.elapsed());
+    Ok(conn)
+}
+
+/// Perform tls handshake, report handshake time
+fn tls_connect<T, U, R>(
+    srv: &mut T,
+    conn: TcpConnection<Uri, U>,
+    metrics: &Option<Rc<dyn Metrics>>,
+) -> impl Future<Output = Result<R, T::Error>>
+where
+    T: Service<Request = TcpConnection<Uri, U>, Response = R>,
+{
+    let (io, uri) = conn.into_parts();
+    let metrics = match (metrics, uri.authority()) {
+        (Some(metrics), Some(authority)) => {
+            Some((metrics.clone(), authority.as_str().to_owned()))
+        }
+        _ => None,
+    };
+    let start = Instant::now();
+
+    srv.call(TcpConnection::from_parts(io, uri)).map(move |res| {
+        if let (Ok(_), Some((metrics, host))) = (&res, metrics) {
+            metrics.tls_handshake(&host, start.elapsed());
+        }
+        res
+    })
+}
 
 mod connect_impl {
     use std::future::Future;
@@ -266,6 +585,7 @@ mod connect_impl {
 
     use super::*;
     use crate::http::client::connection::EitherConnection;
+    use crate::http::client::proxy::Forward;
 
     pub(crate) struct InnerConnector<T1, T2, Io1, Io2>
     where
@@ -276,6 +596,7 @@ mod connect_impl {
     {
         pub(crate) tcp_pool: ConnectionPool<T1, Io1>,
         pub(crate) ssl_pool: ConnectionPool<T2, Io2>,
+        pub(crate) proxies: Rc<Vec<Proxy>>,
     }
 
     impl<T1, T2, Io1, Io2> Clone for InnerConnector<T1, T2, Io1, Io2>
@@ -291,6 +612,7 @@ mod connect_impl {
             InnerConnector {
                 tcp_pool: self.tcp_pool.clone(),
                 ssl_pool: self.ssl_pool.clone(),
+                proxies: self.proxies.clone(),
             }
         }
     }
@@ -322,10 +644,23 @@ mod connect_impl {
                     fut: self.ssl_pool.call(req),
                     _t: PhantomData,
                 }),
-                _ => Either::Left(InnerConnectorResponseA {
-                    fut: self.tcp_pool.call(req),
-                    _t: PhantomData,
-                }),
+                _ => {
+                    // plain http requests are forwarded by http proxy
+                    let forward = self
+                        .proxies
+                        .iter()
+                        .find(|proxy| proxy.intercepts(&req.uri))
+                        .filter(|proxy| proxy.forwards(&req.uri))
+                        .map(|proxy| Forward {
+                            authorization: proxy.authorization(),
+                        });
+
+                    Either::Left(InnerConnectorResponseA {
+                        fut: self.tcp_pool.call(req),
+                        forward,
+                        _t: PhantomData,
+                    })
+                }
             }
         }
     }
@@ -339,6 +674,7 @@ mod connect_impl {
     {
         #[pin]
         fut: <ConnectionPool<T, Io1> as Service>::Future,
+        forward: Option<Forward>,
         _t: PhantomData<Io2>,
     }
 
@@ -352,10 +688,12 @@ mod connect_impl {
         type Output = Result<EitherConnection<Io1, Io2>, ConnectError>;
 
         fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-            Poll::Ready(
-                ready!(Pin::new(&mut self.get_mut().fut).poll(cx))
-                    .map(EitherConnection::A),
-            )
+            let this = self.get_mut();
+            let forward = &mut this.forward;
+            Poll::Ready(ready!(Pin::new(&mut this.fut).poll(cx)).map(|mut conn| {
+                conn.set_forward(forward.take());
+                EitherConnection::A(conn)
+            }))
         }
     }
 
@@ -39,7 +39,23 @@ pub enum ConnectError {
 
     /// Connection io error
     #[display(fmt = "{}", _0)]
+    #[from(ignore)]
     Io(io::Error),
+
+    /// Server certificate does not match pinned public keys
+    #[display(fmt = "Certificate pin mismatch for {}", _0)]
+    #[from(ignore)]
+    PinMismatch(String),
+
+    /// Adaptive concurrency limit exceeded
+    #[display(fmt = "Connector is overloaded")]
+    Overloaded,
+}
+
+impl From<crate::util::adaptive::Overloaded> for ConnectError {
+    fn from(_: crate::util::adaptive::Overloaded) -> ConnectError {
+        ConnectError::Overloaded
+    }
 }
 
 impl From<crate::connect::ConnectError> for ConnectError {
@@ -49,7 +65,16 @@ impl From<crate::connect::ConnectError> for ConnectError {
             crate::connect::ConnectError::NoRecords => ConnectError::NoRecords,
             crate::connect::ConnectError::InvalidInput => panic!(),
             crate::connect::ConnectError::Unresolverd => ConnectError::Unresolverd,
-            crate::connect::ConnectError::Io(e) => ConnectError::Io(e),
+            crate::connect::ConnectError::Io(e) => e.into(),
+        }
+    }
+}
+
+impl From<io::Error> for ConnectError {
+    fn from(err: io::Error) -> ConnectError {
+        match super::pinning::pin_mismatch(&err) {
+            Some(host) => ConnectError::PinMismatch(host),
+            None => ConnectError::Io(err),
         }
     }
 }
@@ -94,6 +119,9 @@ pub enum SendRequestError {
     TunnelNotSupported,
     /// Error sending request body
     Body(Error),
+    /// Redirect limit is reached
+    #[display(fmt = "Too many redirects")]
+    TooManyRedirects,
 }
 
 /// Convert `SendRequestError` to a server `Response`
@@ -14,13 +14,14 @@ use futures_util::{SinkExt, StreamExt};
 use crate::http::error::PayloadError;
 use crate::http::h1;
 use crate::http::header::HeaderMap;
-use crate::http::header::{IntoHeaderValue, HOST};
+use crate::http::header::{IntoHeaderValue, HOST, PROXY_AUTHORIZATION};
 use crate::http::message::{RequestHeadType, ResponseHead};
 use crate::http::payload::{Payload, PayloadStream};
 
 use super::connection::{ConnectionLifetime, ConnectionType, IoConnection};
 use super::error::{ConnectError, SendRequestError};
 use super::pool::Acquired;
+use super::proxy::Forward;
 use crate::http::body::{BodySize, MessageBody};
 
 pub(crate) async fn send_request<T, B>(
@@ -29,11 +30,25 @@ pub(crate) async fn send_request<T, B>(
     body: B,
     created: time::Instant,
     pool: Option<Acquired<T>>,
+    forward: Option<Forward>,
 ) -> Result<(ResponseHead, Payload), SendRequestError>
 where
     T: AsyncRead + AsyncWrite + Unpin + 'static,
     B: MessageBody,
 {
+    // set proxy credentials
+    if let Some(value) = forward.as_ref().and_then(|f| f.authorization.clone()) {
+        match head {
+            RequestHeadType::Owned(ref mut head) => {
+                head.headers.insert(PROXY_AUTHORIZATION, value)
+            }
+            RequestHeadType::Rc(_, ref mut extra_headers) => {
+                let headers = extra_headers.get_or_insert(HeaderMap::new());
+                headers.insert(PROXY_AUTHORIZATION, value)
+            }
+        }
+    }
+
     // set request host header
     if !head.as_ref().headers.contains_key(HOST)
         && !head.extra_headers().iter().any(|h| h.contains_key(HOST))
@@ -68,7 +83,11 @@ where
     };
 
     // create Framed and send request
-    let mut framed = Framed::new(io, h1::ClientCodec::default());
+    let mut codec = h1::ClientCodec::default();
+    if forward.is_some() {
+        codec.set_absolute_form();
+    }
+    let mut framed = Framed::new(io, codec);
     framed.send((head, body.size()).into()).await?;
 
     // send request body
@@ -0,0 +1,125 @@
+//! Client connection metrics
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::rc::{Rc, Weak};
+use std::time::Duration;
+
+/// Client connection events.
+///
+/// Callbacks are invoked with authority of the request uri, i.e.
+/// `example.com` or `example.com:8443`, so events of a host could be
+/// aggregated with the pool stats. Durations of the failed stages are
+/// not reported. All methods have empty default implementation.
+///
+/// ```rust,ignore
+/// use std::time::Duration;
+/// use kayrx::http::client::{Connector, Metrics};
+///
+/// struct Prometheus;
+///
+/// impl Metrics for Prometheus {
+///     fn connect(&self, host: &str, elapsed: Duration) {
+///         CONNECT_TIME.with_label_values(&[host]).observe(elapsed.as_secs_f64());
+///     }
+/// }
+///
+/// let connector = Connector::new().metrics(Prometheus).finish();
+/// ```
+pub trait Metrics {
+    /// Host name is resolved
+    fn dns(&self, _host: &str, _elapsed: Duration) {}
+
+    /// Tcp connection is established, connections to proxies are included
+    fn connect(&self, _host: &str, _elapsed: Duration) {}
+
+    /// Tls handshake is completed
+    fn tls_handshake(&self, _host: &str, _elapsed: Duration) {}
+
+    /// Response head is received, time is counted from the moment
+    /// request is sent
+    fn first_byte(&self, _host: &str, _elapsed: Duration) {}
+
+    /// Request uses idle connection from the pool
+    fn pool_hit(&self, _host: &str) {}
+
+    /// Pool has no idle connection for the request, new connection is opened
+    fn pool_miss(&self, _host: &str) {}
+}
+
+/// Pooled connections of a host
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct HostStats {
+    /// Number of idle connections
+    pub idle: usize,
+    /// Number of connections that are being opened in background
+    pub opening: usize,
+}
+
+/// Pool state that is visible to `PoolStats`
+pub(crate) trait PoolState {
+    /// Number of acquired connections
+    fn acquired(&self) -> usize;
+
+    /// Add pooled connections to the per host stats
+    fn hosts(&self, stats: &mut BTreeMap<String, HostStats>);
+}
+
+/// Connection pool stats.
+///
+/// Stats handle is returned by `Connector::stats()` and reflects current
+/// state of the pools created by `Connector::finish()`, both plain and
+/// secure connections are counted.
+///
+/// ```rust,ignore
+/// use kayrx::http::client::Connector;
+///
+/// let connector = Connector::new();
+/// let stats = connector.stats();
+/// let client = Client::build().connector(connector.finish()).finish();
+///
+/// for (host, pooled) in stats.hosts() {
+///     IDLE.with_label_values(&[&host]).set(pooled.idle as i64);
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct PoolStats(Rc<RefCell<Vec<Weak<dyn PoolState>>>>);
+
+impl PoolStats {
+    pub(crate) fn register(&self, pool: Weak<dyn PoolState>) {
+        self.0.borrow_mut().push(pool);
+    }
+
+    /// Number of connections that are in use or are being opened
+    pub fn acquired(&self) -> usize {
+        self.0
+            .borrow()
+            .iter()
+            .filter_map(|pool| pool.upgrade())
+            .map(|pool| pool.acquired())
+            .sum()
+    }
+
+    /// Pooled connections by host authority
+    pub fn hosts(&self) -> BTreeMap<String, HostStats> {
+        let mut stats = BTreeMap::new();
+        for pool in self.0.borrow().iter().filter_map(|pool| pool.upgrade()) {
+            pool.hosts(&mut stats);
+        }
+        stats
+    }
+
+    /// Pooled connections of the host
+    pub fn host(&self, authority: &str) -> HostStats {
+        self.hosts().remove(authority).unwrap_or_default()
+    }
+}
+
+impl fmt::Debug for PoolStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PoolStats")
+            .field("acquired", &self.acquired())
+            .field("hosts", &self.hosts())
+            .finish()
+    }
+}
@@ -6,15 +6,24 @@ mod connector;
 mod error;
 mod h1proto;
 mod h2proto;
+mod metrics;
+mod pinning;
 mod pool;
+mod proxy;
+mod socks;
 
 pub use self::connection::Connection;
 pub use self::connector::Connector;
 pub use self::error::{ConnectError, FreezeRequestError, InvalidUrl, SendRequestError};
+pub use self::metrics::{HostStats, Metrics, PoolStats};
 pub use self::pool::Protocol;
+pub use self::proxy::Proxy;
 
 #[derive(Clone)]
 pub struct Connect {
     pub uri: Uri,
     pub addr: Option<std::net::SocketAddr>,
+    /// Stream isolation label, connections with different labels are
+    /// pooled separately and use separate SOCKS5 credentials.
+    pub isolation: Option<String>,
 }
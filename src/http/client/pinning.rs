@@ -0,0 +1,156 @@
+//! SPKI certificate pinning for the client connector
+use std::collections::HashMap;
+use std::io;
+
+use ring::digest::{digest, SHA256};
+use rust_tls::{Certificate, RootCertStore, ServerCertVerified, ServerCertVerifier, TLSError};
+use webpki::DNSNameRef;
+
+const PIN_MISMATCH: &str = "certificate pin mismatch: ";
+
+/// Signature algorithms accepted by rustls default verifier
+static SUPPORTED_SIG_ALGS: &[&webpki::SignatureAlgorithm] = &[
+    &webpki::ECDSA_P256_SHA256,
+    &webpki::ECDSA_P256_SHA384,
+    &webpki::ECDSA_P384_SHA256,
+    &webpki::ECDSA_P384_SHA384,
+    &webpki::RSA_PSS_2048_8192_SHA256_LEGACY_KEY,
+    &webpki::RSA_PSS_2048_8192_SHA384_LEGACY_KEY,
+    &webpki::RSA_PSS_2048_8192_SHA512_LEGACY_KEY,
+    &webpki::RSA_PKCS1_2048_8192_SHA256,
+    &webpki::RSA_PKCS1_2048_8192_SHA384,
+    &webpki::RSA_PKCS1_2048_8192_SHA512,
+    &webpki::RSA_PKCS1_3072_8192_SHA384,
+];
+
+/// Certificate verifier that checks the certificate chain with webpki and
+/// requires one of the certificates in the chain to match host's pins.
+pub(crate) struct PinnedCertVerifier {
+    pins: HashMap<String, Vec<[u8; 32]>>,
+}
+
+impl PinnedCertVerifier {
+    pub(crate) fn new(pins: HashMap<String, Vec<[u8; 32]>>) -> Self {
+        PinnedCertVerifier { pins }
+    }
+}
+
+/// Verify certificate chain against the roots and the dns name, the same
+/// way rustls default verifier does
+fn verify_chain(
+    roots: &RootCertStore,
+    presented_certs: &[Certificate],
+    dns_name: DNSNameRef<'_>,
+) -> Result<ServerCertVerified, TLSError> {
+    let (end_entity, intermediates) = match presented_certs.split_first() {
+        Some(certs) => certs,
+        None => return Err(TLSError::NoCertificatesPresented),
+    };
+    let cert =
+        webpki::EndEntityCert::from(&end_entity.0).map_err(TLSError::WebPKIError)?;
+    let chain: Vec<&[u8]> = intermediates.iter().map(|cert| cert.0.as_ref()).collect();
+    let anchors: Vec<webpki::TrustAnchor<'_>> =
+        roots.roots.iter().map(|root| root.to_trust_anchor()).collect();
+    let now = webpki::Time::try_from(std::time::SystemTime::now())
+        .map_err(|_| TLSError::FailedToGetCurrentTime)?;
+
+    cert.verify_is_valid_tls_server_cert(
+        SUPPORTED_SIG_ALGS,
+        &webpki::TLSServerTrustAnchors(&anchors),
+        &chain,
+        now,
+    )
+    .map_err(TLSError::WebPKIError)?;
+    cert.verify_is_valid_for_dns_name(dns_name)
+        .map_err(TLSError::WebPKIError)?;
+    Ok(ServerCertVerified::assertion())
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        roots: &RootCertStore,
+        presented_certs: &[Certificate],
+        dns_name: DNSNameRef<'_>,
+        ocsp_response: &[u8],
+    ) -> Result<ServerCertVerified, TLSError> {
+        let verified = verify_chain(roots, presented_certs, dns_name)?;
+        if !ocsp_response.is_empty() {
+            log::trace!("Unvalidated OCSP response: {:?}", ocsp_response);
+        }
+
+        let host: &str = dns_name.into();
+        let pins = match self.pins.get(&host.to_ascii_lowercase()) {
+            Some(pins) => pins,
+            None => return Ok(verified),
+        };
+
+        let matched = presented_certs.iter().any(|cert| {
+            spki(&cert.0)
+                .map(|spki| {
+                    let hash = digest(&SHA256, spki);
+                    pins.iter().any(|pin| pin[..] == *hash.as_ref())
+                })
+                .unwrap_or(false)
+        });
+        if matched {
+            Ok(verified)
+        } else {
+            log::warn!("Certificate pin mismatch for {}", host);
+            Err(TLSError::General(format!("{}{}", PIN_MISMATCH, host)))
+        }
+    }
+}
+
+/// Returns host name if io error is caused by pin verification failure
+pub(crate) fn pin_mismatch(err: &io::Error) -> Option<String> {
+    match err.get_ref().and_then(|e| e.downcast_ref::<TLSError>()) {
+        Some(TLSError::General(msg)) if msg.starts_with(PIN_MISMATCH) => {
+            Some(msg[PIN_MISMATCH.len()..].to_owned())
+        }
+        _ => None,
+    }
+}
+
+/// Locate DER-encoded `SubjectPublicKeyInfo` in the X.509 certificate
+fn spki(cert: &[u8]) -> Option<&[u8]> {
+    // Certificate ::= SEQUENCE { tbsCertificate, ... }
+    let (_, cert, _) = der_element(cert, 0x30)?;
+    let (_, mut tbs, _) = der_element(cert, 0x30)?;
+
+    // optional explicit version
+    if tbs.first() == Some(&0xa0) {
+        tbs = der_element(tbs, 0xa0)?.2;
+    }
+    // serialNumber, signature, issuer, validity, subject
+    for tag in &[0x02, 0x30, 0x30, 0x30, 0x30] {
+        tbs = der_element(tbs, *tag)?.2;
+    }
+    der_element(tbs, 0x30).map(|(element, _, _)| element)
+}
+
+/// Parse DER element with expected tag.
+///
+/// Returns whole element, its content and the remaining data.
+fn der_element(data: &[u8], tag: u8) -> Option<(&[u8], &[u8], &[u8])> {
+    if data.len() < 2 || data[0] != tag {
+        return None;
+    }
+    let (len, offset) = if data[1] & 0x80 == 0 {
+        (data[1] as usize, 2)
+    } else {
+        let num = (data[1] & 0x7f) as usize;
+        if num == 0 || num > 4 || data.len() < 2 + num {
+            return None;
+        }
+        let len = data[2..2 + num]
+            .iter()
+            .fold(0usize, |len, b| (len << 8) | *b as usize);
+        (len, 2 + num)
+    };
+    let end = offset.checked_add(len)?;
+    if end > data.len() {
+        return None;
+    }
+    Some((&data[..end], &data[offset..end], &data[end..]))
+}
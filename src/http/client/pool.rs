@@ -1,25 +1,27 @@
 use std::cell::RefCell;
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
 use std::future::Future;
 use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{Context, Poll};
 use std::time::{Duration, Instant};
 use bytes::Bytes;
-use futures_util::future::{poll_fn, FutureExt, LocalBoxFuture};
+use futures_util::future::{poll_fn, select, FutureExt, LocalBoxFuture};
 use fxhash::FxHashMap;
 use http::uri::Authority;
 use indexmap::IndexSet;
 use slab::Slab;
 
 use crate::krse::io::{AsyncRead, AsyncWrite};
-use crate::timer::{delay_for, Delay};
+use crate::timer::{delay_for, timeout, Delay};
 use crate::service::Service;
 use crate::http::h2::client::{handshake, Connection, SendRequest};
+use crate::http::h2::Ping;
 use crate::krse::task::LocalWaker;
 use crate::krse::sync::local::oneshot;
 use super::connection::{ConnectionType, IoConnection};
 use super::error::ConnectError;
+use super::metrics::{HostStats, Metrics, PoolState, PoolStats};
 use super::Connect;
 
 #[derive(Clone, Copy, PartialEq)]
@@ -32,11 +34,16 @@ pub enum Protocol {
 #[derive(Hash, Eq, PartialEq, Clone, Debug)]
 pub(crate) struct Key {
     authority: Authority,
+    isolation: Option<String>,
 }
 
-impl From<Authority> for Key {
-    fn from(authority: Authority) -> Key {
-        Key { authority }
+impl Key {
+    /// Connections with different isolation labels are never shared
+    fn new(connect: &Connect) -> Option<Key> {
+        connect.uri.authority().map(|authority| Key {
+            authority: authority.clone(),
+            isolation: connect.isolation.clone(),
+        })
     }
 }
 
@@ -55,6 +62,8 @@ where
         conn_keep_alive: Duration,
         disconnect_timeout: Option<Duration>,
         limit: usize,
+        min_idle: usize,
+        h2_keep_alive: Option<(Duration, Duration)>,
     ) -> Self {
         ConnectionPool(
             Rc::new(RefCell::new(connector)),
@@ -63,14 +72,31 @@ where
                 conn_keep_alive,
                 disconnect_timeout,
                 limit,
+                min_idle,
+                h2_keep_alive,
                 acquired: 0,
+                hosts: FxHashMap::default(),
+                opening: FxHashMap::default(),
                 waiters: Slab::new(),
                 waiters_queue: IndexSet::new(),
                 available: FxHashMap::default(),
                 waker: LocalWaker::new(),
+                metrics: None,
             })),
         )
     }
+
+    /// Report connection events to metrics and register pool in stats
+    pub(crate) fn instrument(
+        self,
+        metrics: Option<Rc<dyn Metrics>>,
+        stats: &PoolStats,
+    ) -> Self {
+        self.1.borrow_mut().metrics = metrics;
+        let state: Rc<dyn PoolState> = self.1.clone();
+        stats.register(Rc::downgrade(&state));
+        self
+    }
 }
 
 impl<T, Io> Clone for ConnectionPool<T, Io>
@@ -106,14 +132,27 @@ where
 
         let mut connector = self.0.clone();
         let inner = self.1.clone();
+        let metrics = self.1.borrow().metrics.clone().and_then(|metrics| {
+            req.uri
+                .authority()
+                .map(|authority| (metrics, authority.as_str().to_owned()))
+        });
 
         let fut = async move {
-            let key = if let Some(authority) = req.uri.authority() {
-                authority.clone().into()
+            let key = if let Some(key) = Key::new(&req) {
+                key
             } else {
                 return Err(ConnectError::Unresolverd);
             };
 
+            // remember host for maintaining idle connections
+            {
+                let mut inner = inner.borrow_mut();
+                if inner.min_idle > 0 && !inner.hosts.contains_key(&key) {
+                    inner.hosts.insert(key.clone(), req.clone());
+                }
+            }
+
             // acquire connection
             match poll_fn(|cx| Poll::Ready(inner.borrow_mut().acquire(&key, cx))).await {
                 Acquire::Acquired(io, created) => {
@@ -138,7 +177,8 @@ where
                         ))
                     } else {
                         let (snd, connection) = handshake(io).await?;
-                        crate::fiber::spawn(connection.map(|_| ()));
+                        let keep_alive = guard.keep_alive();
+                        crate::fiber::spawn(h2_connection(connection, keep_alive));
                         Ok(IoConnection::new(
                             ConnectionType::H2(snd),
                             Instant::now(),
@@ -161,7 +201,17 @@ where
             }
         };
 
-        fut.boxed_local()
+        match metrics {
+            None => fut.boxed_local(),
+            Some((metrics, host)) => fut
+                .map(move |res| {
+                    res.map(|mut conn| {
+                        conn.set_metrics(metrics, host);
+                        conn
+                    })
+                })
+                .boxed_local(),
+        }
     }
 }
 
@@ -226,6 +276,12 @@ where
     fn consume(mut self) -> Acquired<Io> {
         Acquired(self.key.clone(), self.inner.take())
     }
+
+    fn keep_alive(&self) -> Option<(Duration, Duration)> {
+        self.inner
+            .as_ref()
+            .and_then(|inner| inner.borrow().h2_keep_alive)
+    }
 }
 
 impl<Io> Drop for OpenGuard<Io>
@@ -258,7 +314,11 @@ pub(crate) struct Inner<Io> {
     conn_keep_alive: Duration,
     disconnect_timeout: Option<Duration>,
     limit: usize,
+    min_idle: usize,
+    h2_keep_alive: Option<(Duration, Duration)>,
     acquired: usize,
+    hosts: FxHashMap<Key, Connect>,
+    opening: FxHashMap<Key, usize>,
     available: FxHashMap<Key, VecDeque<AvailableConnection<Io>>>,
     waiters: Slab<
         Option<(
@@ -268,6 +328,7 @@ pub(crate) struct Inner<Io> {
     >,
     waiters_queue: IndexSet<(Key, usize)>,
     waker: LocalWaker,
+    metrics: Option<Rc<dyn Metrics>>,
 }
 
 impl<Io> Inner<Io> {
@@ -279,6 +340,12 @@ impl<Io> Inner<Io> {
         self.acquired -= 1;
     }
 
+    fn opened(&mut self, key: &Key) {
+        if let Some(opening) = self.opening.get_mut(key) {
+            *opening -= 1;
+        }
+    }
+
     fn release_waiter(&mut self, key: &Key, token: usize) {
         self.waiters.remove(token);
         let _ = self.waiters_queue.shift_remove(&(key.clone(), token));
@@ -299,7 +366,7 @@ where
     ) {
         let (tx, rx) = oneshot::channel();
 
-        let key: Key = connect.uri.authority().unwrap().clone().into();
+        let key = Key::new(&connect).unwrap();
         let entry = self.waiters.vacant_entry();
         let token = entry.key();
         entry.insert(Some((connect, tx)));
@@ -348,11 +415,22 @@ where
                             }
                             _ => continue,
                         }
+                    } else if let ConnectionType::H2(ref mut snd) = io {
+                        // connection is closed, i.e. keep-alive ping failed
+                        if let Poll::Ready(Err(_)) = snd.poll_ready(cx) {
+                            continue;
+                        }
+                    }
+                    if let Some(ref metrics) = self.metrics {
+                        metrics.pool_hit(key.authority.as_str());
                     }
                     return Acquire::Acquired(io, conn.created);
                 }
             }
         }
+        if let Some(ref metrics) = self.metrics {
+            metrics.pool_miss(key.authority.as_str());
+        }
         Acquire::Available
     }
 
@@ -380,10 +458,108 @@ where
     }
 
     fn check_availibility(&self) {
-        if !self.waiters_queue.is_empty() && self.acquired < self.limit {
+        if (!self.waiters_queue.is_empty() && self.acquired < self.limit)
+            || self.min_idle > 0
+        {
             self.waker.wake();
         }
     }
+
+    /// Close expired idle connections
+    fn purge(&mut self, key: &Key) {
+        if let Some(connections) = self.available.get_mut(key) {
+            let now = Instant::now();
+            let (keep_alive, lifetime) = (self.conn_keep_alive, self.conn_lifetime);
+            let expired = |conn: &AvailableConnection<Io>| {
+                (now - conn.used) > keep_alive || (now - conn.created) > lifetime
+            };
+
+            let mut idx = 0;
+            while idx < connections.len() {
+                if expired(&connections[idx]) {
+                    let conn = connections.remove(idx).unwrap();
+                    if let Some(timeout) = self.disconnect_timeout {
+                        if let ConnectionType::H1(io) = conn.io {
+                            crate::fiber::spawn(CloseConnection::new(io, timeout))
+                        }
+                    }
+                } else {
+                    idx += 1;
+                }
+            }
+        }
+    }
+
+    /// Number of idle and opening connections
+    fn idle(&self, key: &Key) -> usize {
+        self.available.get(key).map(|c| c.len()).unwrap_or(0)
+            + self.opening.get(key).copied().unwrap_or(0)
+    }
+}
+
+impl<Io> PoolState for RefCell<Inner<Io>> {
+    fn acquired(&self) -> usize {
+        self.try_borrow().map(|inner| inner.acquired).unwrap_or(0)
+    }
+
+    fn hosts(&self, stats: &mut BTreeMap<String, HostStats>) {
+        let inner = match self.try_borrow() {
+            Ok(inner) => inner,
+            Err(_) => return,
+        };
+        for (key, connections) in inner.available.iter().filter(|(_, c)| !c.is_empty()) {
+            let host = stats.entry(key.authority.as_str().to_owned()).or_default();
+            host.idle += connections.len();
+        }
+        for (key, opening) in inner.opening.iter().filter(|(_, num)| **num > 0) {
+            let host = stats.entry(key.authority.as_str().to_owned()).or_default();
+            host.opening += opening;
+        }
+    }
+}
+
+/// Drive h2 connection, optionally checking liveness with PING frames.
+///
+/// Connection is dropped if PING is not acknowledged within timeout.
+async fn h2_connection<Io>(
+    mut connection: Connection<Io, Bytes>,
+    keep_alive: Option<(Duration, Duration)>,
+) where
+    Io: AsyncRead + AsyncWrite + Unpin + 'static,
+{
+    let (interval, ping_timeout) = match keep_alive {
+        Some(keep_alive) => keep_alive,
+        None => {
+            let _ = connection.await;
+            return;
+        }
+    };
+    let mut ping_pong = match connection.ping_pong() {
+        Some(ping_pong) => ping_pong,
+        None => {
+            let _ = connection.await;
+            return;
+        }
+    };
+
+    let pings = async move {
+        loop {
+            delay_for(interval).await;
+            match timeout(ping_timeout, ping_pong.ping(Ping::opaque())).await {
+                Ok(Ok(_)) => (),
+                Ok(Err(err)) => {
+                    log::trace!("H2 keep-alive ping failed: {}", err);
+                    return;
+                }
+                Err(_) => {
+                    log::trace!("H2 keep-alive ping timed out, closing connection");
+                    return;
+                }
+            }
+        }
+    };
+
+    let _ = select(Box::pin(connection), Box::pin(pings)).await;
 }
 
 struct CloseConnection<T> {
@@ -484,6 +660,30 @@ where
             let _ = inner.waiters_queue.swap_remove_index(0);
         }
 
+        // open idle connections
+        if inner.min_idle > 0 {
+            let hosts: Vec<_> = inner
+                .hosts
+                .iter()
+                .map(|(key, connect)| (key.clone(), connect.clone()))
+                .collect();
+            for (key, connect) in hosts {
+                inner.purge(&key);
+                while inner.idle(&key) < inner.min_idle {
+                    if inner.limit > 0 && inner.acquired >= inner.limit {
+                        break;
+                    }
+                    inner.reserve();
+                    *inner.opening.entry(key.clone()).or_insert(0) += 1;
+                    OpenIdleConnection::spawn(
+                        key.clone(),
+                        this.inner.clone(),
+                        this.connector.call(connect.clone()),
+                    );
+                }
+            }
+        }
+
         Poll::Pending
     }
 }
@@ -551,7 +751,11 @@ where
         if let Some(ref mut h2) = this.h2 {
             return match Pin::new(h2).poll(cx) {
                 Poll::Ready(Ok((snd, connection))) => {
-                    crate::fiber::spawn(connection.map(|_| ()));
+                    let keep_alive = this
+                        .inner
+                        .as_ref()
+                        .and_then(|inner| inner.borrow().h2_keep_alive);
+                    crate::fiber::spawn(h2_connection(connection, keep_alive));
                     let rx = this.rx.take().unwrap();
                     let _ = rx.send(Ok(IoConnection::new(
                         ConnectionType::H2(snd),
@@ -598,6 +802,76 @@ where
     }
 }
 
+/// Opens connection and puts it to the pool as idle connection
+struct OpenIdleConnection<Io>
+where
+    Io: AsyncRead + AsyncWrite + Unpin + 'static,
+{
+    key: Key,
+    inner: Option<Rc<RefCell<Inner<Io>>>>,
+}
+
+impl<Io> OpenIdleConnection<Io>
+where
+    Io: AsyncRead + AsyncWrite + Unpin + 'static,
+{
+    fn spawn<F>(key: Key, inner: Rc<RefCell<Inner<Io>>>, fut: F)
+    where
+        F: Future<Output = Result<(Io, Protocol), ConnectError>> + 'static,
+    {
+        let guard = OpenIdleConnection {
+            key,
+            inner: Some(inner),
+        };
+
+        crate::fiber::spawn(async move {
+            let io = match fut.await {
+                Ok((io, Protocol::Http1)) => ConnectionType::H1(io),
+                Ok((io, Protocol::Http2)) => match handshake(io).await {
+                    Ok((snd, connection)) => {
+                        let keep_alive = guard
+                            .inner
+                            .as_ref()
+                            .and_then(|inner| inner.borrow().h2_keep_alive);
+                        crate::fiber::spawn(h2_connection(connection, keep_alive));
+                        ConnectionType::H2(snd)
+                    }
+                    Err(err) => {
+                        log::trace!("Can not open idle connection: {}", err);
+                        return;
+                    }
+                },
+                Err(err) => {
+                    log::trace!("Can not open idle connection: {}", err);
+                    return;
+                }
+            };
+            guard.consume(io);
+        });
+    }
+
+    fn consume(mut self, io: ConnectionType<Io>) {
+        if let Some(inner) = self.inner.take() {
+            let mut inner = inner.as_ref().borrow_mut();
+            inner.opened(&self.key);
+            inner.release_conn(&self.key, io, Instant::now());
+        }
+    }
+}
+
+impl<Io> Drop for OpenIdleConnection<Io>
+where
+    Io: AsyncRead + AsyncWrite + Unpin + 'static,
+{
+    fn drop(&mut self) {
+        if let Some(inner) = self.inner.take() {
+            let mut inner = inner.as_ref().borrow_mut();
+            inner.opened(&self.key);
+            inner.release();
+        }
+    }
+}
+
 pub(crate) struct Acquired<T>(Key, Option<Rc<RefCell<Inner<T>>>>);
 
 impl<T> Acquired<T>
@@ -0,0 +1,116 @@
+//! SOCKS5 proxy handshake (RFC 1928, RFC 1929)
+use std::io;
+use std::net::IpAddr;
+
+use crate::krse::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+const VERSION: u8 = 0x05;
+const NO_AUTH: u8 = 0x00;
+const USERNAME_PASSWORD: u8 = 0x02;
+const CMD_CONNECT: u8 = 0x01;
+const ATYP_IPV4: u8 = 0x01;
+const ATYP_DOMAIN: u8 = 0x03;
+const ATYP_IPV6: u8 = 0x04;
+
+/// Establish tunnel to `host:port` through SOCKS5 proxy.
+///
+/// If credentials are provided, username/password authentication is used.
+/// Host names are resolved by the proxy.
+pub(crate) async fn handshake<Io>(
+    io: &mut Io,
+    host: &str,
+    port: u16,
+    credentials: Option<(&str, &str)>,
+) -> io::Result<()>
+where
+    Io: AsyncRead + AsyncWrite + Unpin,
+{
+    let method = if credentials.is_some() {
+        USERNAME_PASSWORD
+    } else {
+        NO_AUTH
+    };
+    io.write_all(&[VERSION, 1, method]).await?;
+
+    let mut buf = [0u8; 4];
+    io.read_exact(&mut buf[..2]).await?;
+    if buf[0] != VERSION {
+        return Err(error("Invalid SOCKS5 proxy response"));
+    }
+    if buf[1] != method {
+        return Err(error("SOCKS5 authentication method is not supported by proxy"));
+    }
+
+    if let Some((user, password)) = credentials {
+        let (user, password) = (user.as_bytes(), password.as_bytes());
+        if user.is_empty() || user.len() > 255 || password.len() > 255 {
+            return Err(error("SOCKS5 credentials must be 1..255 bytes long"));
+        }
+        let mut req = Vec::with_capacity(3 + user.len() + password.len());
+        req.push(0x01);
+        req.push(user.len() as u8);
+        req.extend_from_slice(user);
+        req.push(password.len() as u8);
+        req.extend_from_slice(password);
+        io.write_all(&req).await?;
+
+        io.read_exact(&mut buf[..2]).await?;
+        if buf[1] != 0 {
+            return Err(error("SOCKS5 authentication failed"));
+        }
+    }
+
+    let mut req = vec![VERSION, CMD_CONNECT, 0];
+    match host.trim_start_matches('[').trim_end_matches(']').parse() {
+        Ok(IpAddr::V4(addr)) => {
+            req.push(ATYP_IPV4);
+            req.extend_from_slice(&addr.octets());
+        }
+        Ok(IpAddr::V6(addr)) => {
+            req.push(ATYP_IPV6);
+            req.extend_from_slice(&addr.octets());
+        }
+        Err(_) => {
+            if host.is_empty() || host.len() > 255 {
+                return Err(error("Invalid host name"));
+            }
+            req.push(ATYP_DOMAIN);
+            req.push(host.len() as u8);
+            req.extend_from_slice(host.as_bytes());
+        }
+    }
+    req.extend_from_slice(&port.to_be_bytes());
+    io.write_all(&req).await?;
+
+    io.read_exact(&mut buf).await?;
+    if buf[0] != VERSION {
+        return Err(error("Invalid SOCKS5 proxy response"));
+    }
+    match buf[1] {
+        0x00 => (),
+        0x02 => return Err(error("SOCKS5 connection not allowed by ruleset")),
+        0x03 => return Err(error("SOCKS5 network unreachable")),
+        0x04 => return Err(error("SOCKS5 host unreachable")),
+        0x05 => return Err(error("SOCKS5 connection refused")),
+        0x06 => return Err(error("SOCKS5 TTL expired")),
+        _ => return Err(error("SOCKS5 proxy failure")),
+    }
+
+    // skip bound address
+    let len = match buf[3] {
+        ATYP_IPV4 => 4,
+        ATYP_IPV6 => 16,
+        ATYP_DOMAIN => {
+            io.read_exact(&mut buf[..1]).await?;
+            buf[0] as usize
+        }
+        _ => return Err(error("Invalid SOCKS5 proxy response")),
+    };
+    let mut addr = vec![0u8; len + 2];
+    io.read_exact(&mut addr).await?;
+    Ok(())
+}
+
+fn error(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, msg)
+}
@@ -1,9 +1,15 @@
 use std::cell::UnsafeCell;
 use std::fmt::Write;
+use std::future::Future;
+use std::pin::Pin;
 use std::rc::Rc;
+use std::task::Context;
 use std::time::Duration;
 use std::{fmt, net};
 
+use crate::codec::ReadBufSize;
+use crate::http::h2::server::Builder as H2Builder;
+use crate::http::h2::Reason;
 use crate::timer::{delay_for, delay_until, Delay, Instant};
 use bytes::BytesMut;
 use futures_util::{future, FutureExt};
@@ -12,6 +18,13 @@ use time::OffsetDateTime;
 // "Sun, 06 Nov 1994 08:49:37 GMT".len()
 const DATE_VALUE_LENGTH: usize = 29;
 
+/// Default high watermark of the write buffer
+const WRITE_BUFFER_HW: usize = 32_768;
+
+/// Default min and max size of the read chunk
+const READ_BUFFER_MIN: usize = 4096;
+const READ_BUFFER_MAX: usize = 32_768;
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 /// Server keep-alive setting
 pub enum KeepAlive {
@@ -39,6 +52,133 @@ impl From<Option<usize>> for KeepAlive {
     }
 }
 
+/// Connection lifetime limits
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ConnLimits {
+    pub(crate) lifetime: Option<Duration>,
+    pub(crate) max_idle: Option<Duration>,
+    pub(crate) jitter: f64,
+}
+
+impl Default for ConnLimits {
+    fn default() -> Self {
+        ConnLimits {
+            lifetime: None,
+            max_idle: None,
+            jitter: 0.1,
+        }
+    }
+}
+
+impl ConnLimits {
+    /// Shorten duration by random part of jitter
+    fn jittered(&self, dur: Duration) -> Duration {
+        if self.jitter > 0.0 {
+            dur.mul_f64(1.0 - rand::random::<f64>() * self.jitter)
+        } else {
+            dur
+        }
+    }
+}
+
+/// Minimal rate of writes to the client
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct WriteRate {
+    pub(crate) bytes_per_sec: u64,
+    pub(crate) period: Duration,
+}
+
+/// Tracks write rate of the response data that waits for the client.
+///
+/// Rate is checked once per period, while data is buffered for the client
+/// during the whole period it must receive at least `bytes_per_sec * period`
+/// bytes. Period restarts when the buffer is empty.
+pub(crate) struct WriteMonitor {
+    rate: WriteRate,
+    timer: Option<Delay>,
+    written: u64,
+}
+
+impl WriteMonitor {
+    /// Data is written to the client
+    pub(crate) fn written(&mut self, n: usize) {
+        self.written += n as u64;
+    }
+
+    /// Check write rate, `pending` is set if data waits for the client.
+    /// Returns `false` if client is too slow.
+    pub(crate) fn poll(&mut self, cx: &mut Context<'_>, pending: bool) -> bool {
+        if !pending {
+            self.timer = None;
+            self.written = 0;
+            return true;
+        }
+
+        let period = self.rate.period;
+        let timer = self.timer.get_or_insert_with(|| delay_for(period));
+        if Pin::new(&mut *timer).poll(cx).is_pending() {
+            return true;
+        }
+
+        let min = self.rate.bytes_per_sec as f64 * period.as_secs_f64();
+        if (self.written as f64) < min {
+            #[cfg(feature = "telemetry")]
+            crate::telemetry::counter("http.server.slow_clients", &[]).add(1);
+            return false;
+        }
+
+        // next period
+        self.written = 0;
+        timer.reset_after(period);
+        let _ = Pin::new(timer).poll(cx);
+        true
+    }
+}
+
+/// Http/1 request head and response flush timeouts
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct H1Timeouts {
+    pub(crate) header: Option<Duration>,
+    pub(crate) flush: Option<Duration>,
+}
+
+/// Http/2 connection settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct H2Settings {
+    pub(crate) max_concurrent_streams: Option<u32>,
+    pub(crate) overflow_reason: Reason,
+    pub(crate) initial_window_size: Option<u32>,
+    pub(crate) initial_connection_window_size: Option<u32>,
+}
+
+impl Default for H2Settings {
+    fn default() -> Self {
+        H2Settings {
+            max_concurrent_streams: None,
+            overflow_reason: Reason::REFUSED_STREAM,
+            initial_window_size: None,
+            initial_connection_window_size: None,
+        }
+    }
+}
+
+impl H2Settings {
+    fn builder(&self) -> H2Builder {
+        let mut builder = H2Builder::new();
+        builder.max_concurrent_streams_reason(self.overflow_reason);
+        if let Some(max) = self.max_concurrent_streams {
+            builder.max_concurrent_streams(max);
+        }
+        if let Some(size) = self.initial_window_size {
+            builder.initial_window_size(size);
+        }
+        if let Some(size) = self.initial_connection_window_size {
+            builder.initial_connection_window_size(size);
+        }
+        builder
+    }
+}
+
 /// Http service configuration
 pub struct ServiceConfig(Rc<Inner>);
 
@@ -49,6 +189,12 @@ struct Inner {
     ka_enabled: bool,
     secure: bool,
     local_addr: Option<std::net::SocketAddr>,
+    conn_limits: ConnLimits,
+    h2: H2Settings,
+    h1_timeouts: H1Timeouts,
+    write_rate: Option<WriteRate>,
+    write_buffer: (usize, usize),
+    read_buffer: (usize, usize),
     timer: DateService,
 }
 
@@ -91,10 +237,68 @@ impl ServiceConfig {
             client_disconnect,
             secure,
             local_addr,
+            conn_limits: ConnLimits::default(),
+            h2: H2Settings::default(),
+            h1_timeouts: H1Timeouts::default(),
+            write_rate: None,
+            write_buffer: (WRITE_BUFFER_HW, WRITE_BUFFER_HW),
+            read_buffer: (READ_BUFFER_MIN, READ_BUFFER_MAX),
             timer: DateService::new(),
         }))
     }
 
+    /// Set connection lifetime limits of the new configuration
+    pub(crate) fn with_conn_limits(mut self, limits: ConnLimits) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Configuration is shared")
+            .conn_limits = limits;
+        self
+    }
+
+    /// Set minimal write rate of the new configuration
+    pub(crate) fn with_write_rate(mut self, rate: Option<WriteRate>) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Configuration is shared")
+            .write_rate = rate;
+        self
+    }
+
+    /// Set http/1 timeouts of the new configuration
+    pub(crate) fn with_h1_timeouts(mut self, timeouts: H1Timeouts) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Configuration is shared")
+            .h1_timeouts = timeouts;
+        self
+    }
+
+    /// Set write buffer watermarks of the new configuration
+    pub(crate) fn with_write_buffer(mut self, buffer: Option<(usize, usize)>) -> Self {
+        if let Some(buffer) = buffer {
+            Rc::get_mut(&mut self.0)
+                .expect("Configuration is shared")
+                .write_buffer = buffer;
+        }
+        self
+    }
+
+    /// Set min and max read chunk size of the new configuration
+    pub(crate) fn with_read_buffer(mut self, buffer: Option<(usize, usize)>) -> Self {
+        if let Some(buffer) = buffer {
+            Rc::get_mut(&mut self.0)
+                .expect("Configuration is shared")
+                .read_buffer = buffer;
+        }
+        self
+    }
+
+    /// Set http/2 settings of the new configuration
+    pub(crate) fn with_h2_settings(mut self, settings: H2Settings) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("Configuration is shared")
+            .h2 = settings;
+        self
+    }
+
     #[inline]
     /// Returns true if connection is secure(https)
     pub fn secure(&self) -> bool {
@@ -171,6 +375,57 @@ impl ServiceConfig {
         }
     }
 
+    /// Max lifetime timer of the new connection, with jitter applied
+    pub(crate) fn conn_lifetime_timer(&self) -> Option<Delay> {
+        // cached date service time could be behind by up to 500ms
+        let limits = &self.0.conn_limits;
+        limits.lifetime.map(|dur| delay_for(limits.jittered(dur)))
+    }
+
+    /// Max idle period of the new connection, with jitter applied
+    pub(crate) fn conn_max_idle(&self) -> Option<Duration> {
+        let limits = &self.0.conn_limits;
+        limits.max_idle.map(|dur| limits.jittered(dur))
+    }
+
+    /// Max time of reading request head
+    pub(crate) fn header_timeout(&self) -> Option<Duration> {
+        self.0.h1_timeouts.header
+    }
+
+    /// Max time buffered response data waits for the client without progress
+    pub(crate) fn flush_timeout(&self) -> Option<Duration> {
+        self.0.h1_timeouts.flush
+    }
+
+    /// High and low watermarks of the write buffer
+    pub(crate) fn write_buffer(&self) -> (usize, usize) {
+        self.0.write_buffer
+    }
+
+    /// Adaptive read chunk size of the new connection
+    pub(crate) fn read_buf_size(&self) -> ReadBufSize {
+        let (min, max) = self.0.read_buffer;
+        ReadBufSize::new(min, max)
+    }
+
+    /// Write rate monitor of the new connection or stream
+    pub(crate) fn write_monitor(&self) -> Option<WriteMonitor> {
+        self.0.write_rate.map(|rate| WriteMonitor {
+            rate,
+            timer: None,
+            written: 0,
+        })
+    }
+
+    /// Handshake builder for the new http/2 connection
+    pub(crate) fn h2_builder(&self) -> H2Builder {
+        let (min, max) = self.0.read_buffer;
+        let mut builder = self.0.h2.builder();
+        builder.read_buffer(min, max);
+        builder
+    }
+
     #[inline]
     pub(crate) fn now(&self) -> Instant {
         self.0.timer.now()
@@ -248,7 +503,7 @@ impl DateServiceInner {
     }
 
     fn update(&self) {
-        let now = Instant::now();
+        let now = crate::timer::recent();
         let date = Date::new();
         *(unsafe { &mut *self.current.get() }) = Some((date, now));
     }
@@ -2,23 +2,139 @@ use std::future::Future;
 use std::io::{self, Write};
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 use brotli2::write::BrotliDecoder;
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use flate2::write::{GzDecoder, ZlibDecoder};
 use futures_core::{ready, Stream};
 
 use super::Writer;
 use crate::http::error::PayloadError;
 use crate::http::header::{ContentEncoding, HeaderMap, CONTENT_ENCODING};
-use crate::util::threadpool::{run, CpuFuture};
+use crate::util::threadpool::{run, BlockingError, CpuFuture};
 
 const INPLACE: usize = 2049;
 
+/// Size of input slices fed to decoder when limits are checked
+const LIMIT_STEP: usize = 512;
+
+/// Ratio is not checked until decoded size reaches this threshold,
+/// small payloads legitimately have high compression ratio
+const RATIO_THRESHOLD: u64 = 65_536;
+
+/// Default maximum size of decoded request payload, 16Mb
+const DEFAULT_MAX_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Limits applied to decompressed payload.
+///
+/// Compressed payloads are controlled by the client, a small request may
+/// inflate to gigabytes. Limits stop decompression with
+/// `PayloadError::DecompressionOverflow` if decoded payload gets larger
+/// than `max_size`, and with `PayloadError::DecompressionLimit` if it
+/// expands more than `max_ratio` times or decoding takes longer than
+/// `timeout`. Decoded output is never buffered beyond `max_size`.
+///
+/// Default limits allow up to 16Mb of decoded payload, ratio and time
+/// are not limited.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecompressLimits {
+    max_size: Option<u64>,
+    max_ratio: Option<u64>,
+    timeout: Option<Duration>,
+}
+
+impl Default for DecompressLimits {
+    fn default() -> Self {
+        DecompressLimits {
+            max_size: Some(DEFAULT_MAX_SIZE),
+            max_ratio: None,
+            timeout: None,
+        }
+    }
+}
+
+impl DecompressLimits {
+    /// Create default limits
+    pub fn new() -> Self {
+        DecompressLimits::default()
+    }
+
+    /// Create limits that do not restrict decompression
+    pub fn unlimited() -> Self {
+        DecompressLimits {
+            max_size: None,
+            max_ratio: None,
+            timeout: None,
+        }
+    }
+
+    /// Set maximum size of decoded payload
+    pub fn max_size(mut self, size: u64) -> Self {
+        self.max_size = Some(size);
+        self
+    }
+
+    /// Set maximum ratio of decoded to encoded payload size.
+    ///
+    /// Ratio is checked once decoded payload exceeds 64Kb.
+    pub fn max_ratio(mut self, ratio: u64) -> Self {
+        self.max_ratio = Some(ratio);
+        self
+    }
+
+    /// Set maximum total time spent decoding payload
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    fn is_unlimited(&self) -> bool {
+        self.max_size.is_none() && self.max_ratio.is_none() && self.timeout.is_none()
+    }
+}
+
+/// Decompression progress, checked against limits
+#[derive(Debug)]
+struct Progress {
+    limits: DecompressLimits,
+    encoded: u64,
+    decoded: u64,
+    elapsed: Duration,
+}
+
+impl Progress {
+    fn new(limits: DecompressLimits) -> Self {
+        Progress {
+            limits,
+            encoded: 0,
+            decoded: 0,
+            elapsed: Duration::from_secs(0),
+        }
+    }
+
+    fn check(&self) -> Result<(), PayloadError> {
+        let limits = &self.limits;
+        if limits.max_size.map(|max| self.decoded > max).unwrap_or(false) {
+            return Err(PayloadError::DecompressionOverflow);
+        }
+        if let Some(ratio) = limits.max_ratio {
+            if self.decoded > RATIO_THRESHOLD && self.decoded > self.encoded.saturating_mul(ratio)
+            {
+                return Err(PayloadError::DecompressionLimit);
+            }
+        }
+        if limits.timeout.map(|max| self.elapsed > max).unwrap_or(false) {
+            return Err(PayloadError::DecompressionLimit);
+        }
+        Ok(())
+    }
+}
+
 pub struct Decoder<S> {
-    decoder: Option<ContentDecoder>,
+    decoder: Option<(ContentDecoder, Progress)>,
     stream: S,
     eof: bool,
-    fut: Option<CpuFuture<(Option<Bytes>, ContentDecoder), io::Error>>,
+    fut: Option<CpuFuture<(Option<Bytes>, (ContentDecoder, Progress)), PayloadError>>,
 }
 
 impl<S> Decoder<S>
@@ -26,6 +142,8 @@ where
     S: Stream<Item = Result<Bytes, PayloadError>>,
 {
     /// Construct a decoder.
+    ///
+    /// Decoded payload is not limited, use `limits()` to restrict it.
     #[inline]
     pub fn new(stream: S, encoding: ContentEncoding) -> Decoder<S> {
         let decoder = match encoding {
@@ -41,13 +159,23 @@ where
             _ => None,
         };
         Decoder {
-            decoder,
+            decoder: decoder
+                .map(|decoder| (decoder, Progress::new(DecompressLimits::unlimited()))),
             stream,
             fut: None,
             eof: false,
         }
     }
 
+    /// Apply limits to decompressed payload.
+    pub fn limits(mut self, limits: DecompressLimits) -> Self {
+        if let Some((ref mut decoder, ref mut progress)) = self.decoder {
+            decoder.writer().set_limit(limits.max_size);
+            progress.limits = limits;
+        }
+        self
+    }
+
     /// Construct decoder based on headers.
     #[inline]
     pub fn from_headers(stream: S, headers: &HeaderMap) -> Decoder<S> {
@@ -80,7 +208,10 @@ where
             if let Some(ref mut fut) = self.fut {
                 let (chunk, decoder) = match ready!(Pin::new(fut).poll(cx)) {
                     Ok(item) => item,
-                    Err(e) => return Poll::Ready(Some(Err(e.into()))),
+                    Err(BlockingError::Error(e)) => return Poll::Ready(Some(Err(e))),
+                    Err(BlockingError::Canceled) => {
+                        return Poll::Ready(Some(Err(PayloadError::Incomplete(None))))
+                    }
                 };
                 self.decoder = Some(decoder);
                 self.fut.take();
@@ -96,17 +227,17 @@ where
             match Pin::new(&mut self.stream).poll_next(cx) {
                 Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
                 Poll::Ready(Some(Ok(chunk))) => {
-                    if let Some(mut decoder) = self.decoder.take() {
+                    if let Some((mut decoder, mut progress)) = self.decoder.take() {
                         if chunk.len() < INPLACE {
-                            let chunk = decoder.feed_data(chunk)?;
-                            self.decoder = Some(decoder);
+                            let chunk = decoder.feed_limited(chunk, &mut progress)?;
+                            self.decoder = Some((decoder, progress));
                             if let Some(chunk) = chunk {
                                 return Poll::Ready(Some(Ok(chunk)));
                             }
                         } else {
                             self.fut = Some(run(move || {
-                                let chunk = decoder.feed_data(chunk)?;
-                                Ok((chunk, decoder))
+                                let chunk = decoder.feed_limited(chunk, &mut progress)?;
+                                Ok((chunk, (decoder, progress)))
                             }));
                         }
                         continue;
@@ -116,11 +247,17 @@ where
                 }
                 Poll::Ready(None) => {
                     self.eof = true;
-                    return if let Some(mut decoder) = self.decoder.take() {
+                    return if let Some((mut decoder, mut progress)) = self.decoder.take() {
                         match decoder.feed_eof() {
-                            Ok(Some(res)) => Poll::Ready(Some(Ok(res))),
+                            Ok(Some(res)) => {
+                                progress.decoded += res.len() as u64;
+                                match progress.check() {
+                                    Ok(()) => Poll::Ready(Some(Ok(res))),
+                                    Err(err) => Poll::Ready(Some(Err(err))),
+                                }
+                            }
                             Ok(None) => Poll::Ready(None),
-                            Err(err) => Poll::Ready(Some(Err(err.into()))),
+                            Err(err) => Poll::Ready(Some(Err(decoder.error(err)))),
                         }
                     } else {
                         Poll::Ready(None)
@@ -140,6 +277,59 @@ enum ContentDecoder {
 }
 
 impl ContentDecoder {
+    /// Feed data to decoder, checking limits.
+    ///
+    /// With limits set, input is fed in small slices so that expansion
+    /// is detected before whole chunk is decoded.
+    fn feed_limited(
+        &mut self,
+        data: Bytes,
+        progress: &mut Progress,
+    ) -> Result<Option<Bytes>, PayloadError> {
+        if progress.limits.is_unlimited() {
+            return self.feed_data(data).map_err(|e| self.error(e));
+        }
+
+        let mut buf = BytesMut::new();
+        for step in data.chunks(LIMIT_STEP) {
+            let start = Instant::now();
+            let chunk = match self.feed_data(Bytes::copy_from_slice(step)) {
+                Ok(chunk) => chunk,
+                Err(e) => return Err(self.error(e)),
+            };
+            if let Some(chunk) = chunk {
+                progress.decoded += chunk.len() as u64;
+                buf.extend_from_slice(&chunk);
+            }
+            progress.encoded += step.len() as u64;
+            progress.elapsed += start.elapsed();
+            progress.check()?;
+        }
+
+        if buf.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(buf.freeze()))
+        }
+    }
+
+    fn writer(&mut self) -> &mut Writer {
+        match self {
+            ContentDecoder::Br(ref mut decoder) => decoder.get_mut(),
+            ContentDecoder::Gzip(ref mut decoder) => decoder.get_mut(),
+            ContentDecoder::Deflate(ref mut decoder) => decoder.get_mut(),
+        }
+    }
+
+    /// Convert decoder error, writer refuses output beyond size limit
+    fn error(&mut self, err: io::Error) -> PayloadError {
+        if self.writer().is_overflow() {
+            PayloadError::DecompressionOverflow
+        } else {
+            err.into()
+        }
+    }
+
     fn feed_eof(&mut self) -> io::Result<Option<Bytes>> {
         match self {
             ContentDecoder::Br(ref mut decoder) => match decoder.flush() {
@@ -6,20 +6,36 @@ use bytes::{Bytes, BytesMut};
 mod decoder;
 mod encoder;
 
-pub use self::decoder::Decoder;
+pub use self::decoder::{DecompressLimits, Decoder};
 pub use self::encoder::Encoder;
 
 pub(self) struct Writer {
     buf: BytesMut,
+    limit: Option<u64>,
+    written: u64,
+    overflow: bool,
 }
 
 impl Writer {
     fn new() -> Writer {
         Writer {
             buf: BytesMut::with_capacity(8192),
+            limit: None,
+            written: 0,
+            overflow: false,
         }
     }
 
+    /// Set maximum number of bytes accepted by writer
+    fn set_limit(&mut self, limit: Option<u64>) {
+        self.limit = limit;
+    }
+
+    /// Writer refused data because of limit
+    fn is_overflow(&self) -> bool {
+        self.overflow
+    }
+
     fn take(&mut self) -> Bytes {
         self.buf.split().freeze()
     }
@@ -27,6 +43,12 @@ impl Writer {
 
 impl io::Write for Writer {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.written + buf.len() as u64;
+        if self.limit.map(|limit| written > limit).unwrap_or(false) {
+            self.overflow = true;
+            return Err(io::Error::new(io::ErrorKind::Other, "Writer limit exceeded"));
+        }
+        self.written = written;
         self.buf.extend_from_slice(buf);
         Ok(buf.len())
     }
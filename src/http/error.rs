@@ -20,6 +20,7 @@ pub use http::Error as HttpError;
 pub(crate) use crate::util::threadpool::BlockingError;
 use crate::codec::{Decoder, Encoder};
 use crate::framed::DispatcherError as FramedDispatcherError;
+use crate::util::adaptive::Overloaded;
 use crate::util::timeout::TimeoutError;
 use crate::http::body::Body;
 use crate::http::helpers::Writer;
@@ -181,6 +182,13 @@ impl<E: ResponseError> ResponseError for TimeoutError<E> {
     }
 }
 
+/// Return `SERVICE_UNAVAILABLE` for `Overloaded`
+impl ResponseError for Overloaded {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
 #[derive(Debug, Display)]
 #[display(fmt = "UnknownError")]
 struct UnitError;
@@ -336,6 +344,12 @@ pub enum PayloadError {
     /// A payload length is unknown.
     #[display(fmt = "A payload length is unknown.")]
     UnknownLength,
+    /// Decompressed payload reached size limit.
+    #[display(fmt = "Decompressed payload reached size limit.")]
+    DecompressionOverflow,
+    /// Payload exceeded decompression ratio or time limit.
+    #[display(fmt = "Payload exceeded decompression limits.")]
+    DecompressionLimit,
     /// Http2 payload error
     #[display(fmt = "{}", _0)]
     Http2Payload(crate::http::h2::Error),
@@ -381,7 +395,9 @@ impl From<BlockingError<io::Error>> for PayloadError {
 impl ResponseError for PayloadError {
     fn status_code(&self) -> StatusCode {
         match *self {
-            PayloadError::Overflow => StatusCode::PAYLOAD_TOO_LARGE,
+            PayloadError::Overflow | PayloadError::DecompressionOverflow => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
             _ => StatusCode::BAD_REQUEST,
         }
     }
@@ -425,6 +441,14 @@ pub enum DispatchError {
     #[display(fmt = "Connection shutdown timeout")]
     DisconnectTimeout,
 
+    /// Client reads response slower than configured minimal write rate.
+    #[display(fmt = "Client write rate is below the minimum")]
+    SlowClient,
+
+    /// Buffered response data is not flushed within the specified timeout.
+    #[display(fmt = "Response flush timeout")]
+    FlushTimeout,
+
     /// Payload is not consumed
     #[display(fmt = "Task is completed but request's payload is not consumed")]
     PayloadIsNotConsumed,
@@ -73,6 +73,12 @@ impl ClientCodec {
         }
     }
 
+    /// Send request uri in absolute form, as required for requests to
+    /// http proxy
+    pub(crate) fn set_absolute_form(&mut self) {
+        self.inner.encoder.absolute_form = true;
+    }
+
     /// Check if request is upgrade
     pub fn upgrade(&self) -> bool {
         self.inner.ctype == ConnectionType::Upgrade
@@ -2,12 +2,13 @@ use std::collections::VecDeque;
 use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Duration;
 use std::{fmt, io, net};
 
 use crate::krse::io::{AsyncRead, AsyncWrite};
-use crate::codec::{Decoder, Encoder};
+use crate::codec::{Decoder, Encoder, ReadBufSize};
 use crate::codec::{Framed2 as Framed, FramedParts2 as FramedParts};
-use crate::timer::{delay_until, Delay, Instant};
+use crate::timer::{delay_for, delay_until, Delay, Instant};
 use crate::service::Service;
 use bitflags::bitflags;
 use bytes::{Buf, BytesMut};
@@ -15,13 +16,15 @@ use log::{error, trace};
 
 use crate::http::body::{Body, BodySize, MessageBody, ResponseBody};
 use crate::http::cloneable::CloneableService;
-use crate::http::config::ServiceConfig;
+use crate::http::config::{ServiceConfig, WriteMonitor};
 use crate::http::error::{DispatchError, Error};
 use crate::http::error::{ParseError, PayloadError};
 use crate::http::helpers::DataFactory;
 use crate::http::httpmessage::HttpMessage;
+use crate::http::message::ConnectionType;
 use crate::http::request::Request;
 use crate::http::response::Response;
+use crate::server::lifecycle::DrainListener;
 
 use super::codec::Codec;
 use super::payload::{Payload, PayloadSender, PayloadStatus};
@@ -40,6 +43,7 @@ bitflags! {
         const READ_DISCONNECT    = 0b0001_0000;
         const WRITE_DISCONNECT   = 0b0010_0000;
         const UPGRADE            = 0b0100_0000;
+        const DRAINING           = 0b1000_0000;
     }
 }
 
@@ -96,6 +100,19 @@ where
 
     ka_expire: Instant,
     ka_timer: Option<Delay>,
+    drain: DrainListener,
+    lifetime: Option<Delay>,
+    max_idle: Option<Duration>,
+    idle_timer: Option<Delay>,
+    header_timeout: Option<Duration>,
+    header_timer: Option<Delay>,
+    flush_timeout: Option<Duration>,
+    flush_timer: Option<Delay>,
+    write_monitor: Option<WriteMonitor>,
+    write_hw: usize,
+    write_lw: usize,
+    write_paused: bool,
+    read_size: ReadBufSize,
 
     pub io: T,
     read_buf: BytesMut,
@@ -192,7 +209,7 @@ where
             stream,
             Codec::new(config.clone()),
             config,
-            BytesMut::with_capacity(HW_BUFFER_SIZE),
+            BytesMut::new(),
             None,
             service,
             expect,
@@ -216,6 +233,7 @@ where
         peer_addr: Option<net::SocketAddr>,
     ) -> Self {
         let keepalive = config.keep_alive_enabled();
+        let (write_hw, write_lw) = config.write_buffer();
         let flags = if keepalive {
             Flags::KEEPALIVE
         } else {
@@ -249,6 +267,19 @@ where
                 peer_addr,
                 ka_expire,
                 ka_timer,
+                drain: DrainListener::new(),
+                lifetime: config.conn_lifetime_timer(),
+                max_idle: config.conn_max_idle(),
+                idle_timer: None,
+                header_timeout: config.header_timeout(),
+                header_timer: None,
+                flush_timeout: config.flush_timeout(),
+                flush_timer: None,
+                write_monitor: config.write_monitor(),
+                write_hw,
+                write_lw,
+                write_paused: false,
+                read_size: config.read_buf_size(),
             }),
         }
     }
@@ -294,6 +325,7 @@ where
     /// false - didnt get whouldblock
     fn poll_flush(&mut self, cx: &mut Context<'_>) -> Result<bool, DispatchError> {
         if self.write_buf.is_empty() {
+            self.flush_timer = None;
             return Ok(false);
         }
 
@@ -311,11 +343,15 @@ where
                 }
                 Poll::Ready(Ok(n)) => {
                     written += n;
+                    if let Some(ref mut monitor) = self.write_monitor {
+                        monitor.written(n);
+                    }
                 }
                 Poll::Pending => {
                     if written > 0 {
                         self.write_buf.advance(written);
                     }
+                    self.update_flush_timer(cx, written > 0);
                     return Ok(true);
                 }
                 Poll::Ready(Err(err)) => return Err(DispatchError::Io(err)),
@@ -326,14 +362,51 @@ where
         } else {
             self.write_buf.advance(written);
         }
+        self.flush_timer = None;
         Ok(false)
     }
 
+    /// Start flush timer if data waits for the client, restart it
+    /// if some data is written
+    fn update_flush_timer(&mut self, cx: &mut Context<'_>, progress: bool) {
+        if let Some(timeout) = self.flush_timeout {
+            if progress || self.flush_timer.is_none() {
+                let mut timer = delay_until(self.codec.config().now() + timeout);
+                let _ = Pin::new(&mut timer).poll(cx);
+                self.flush_timer = Some(timer);
+            }
+        }
+    }
+
+    /// Start request head timer if partial request head is buffered,
+    /// stop it otherwise
+    fn update_header_timer(&mut self, cx: &mut Context<'_>) {
+        if let Some(timeout) = self.header_timeout {
+            let waiting = self.state.is_empty()
+                && self.payload.is_none()
+                && !self.read_buf.is_empty()
+                && !self.flags.intersects(Flags::UPGRADE | Flags::SHUTDOWN);
+
+            if !waiting {
+                self.header_timer = None;
+            } else if self.header_timer.is_none() {
+                let mut timer = delay_until(self.codec.config().now() + timeout);
+                let _ = Pin::new(&mut timer).poll(cx);
+                self.header_timer = Some(timer);
+            }
+        }
+    }
+
     fn send_response(
         &mut self,
-        message: Response<()>,
+        mut message: Response<()>,
         body: ResponseBody<B>,
     ) -> Result<State<S, B, X>, DispatchError> {
+        // worker is shutting down or connection limits are reached,
+        // do not keep connection alive
+        if self.flags.contains(Flags::DRAINING) {
+            message.head_mut().set_connection_type(ConnectionType::Close);
+        }
         self.codec
             .encode(Message::Item((message, body.size())), &mut self.write_buf)
             .map_err(|err| {
@@ -405,7 +478,15 @@ where
                 }
                 State::SendPayload(ref mut stream) => {
                     loop {
-                        if self.write_buf.len() < HW_BUFFER_SIZE {
+                        // stop polling body at high watermark,
+                        // resume when buffer is drained to low watermark
+                        let limit = if self.write_paused {
+                            self.write_lw
+                        } else {
+                            self.write_hw
+                        };
+                        if self.write_buf.len() < limit {
+                            self.write_paused = false;
                             match stream.poll_next(cx) {
                                 Poll::Ready(Some(Ok(item))) => {
                                     self.codec.encode(
@@ -427,6 +508,7 @@ where
                                 Poll::Pending => return Ok(PollResponse::DoNothing),
                             }
                         } else {
+                            self.write_paused = true;
                             return Ok(PollResponse::DrainWriteBuf);
                         }
                         break;
@@ -602,9 +684,89 @@ where
                 self.ka_expire = expire;
             }
         }
+        self.update_header_timer(cx);
         Ok(updated)
     }
 
+    /// Check connection lifetime and max idle period, on expiration
+    /// disable keep-alive. Returns `true` if limit is reached by this call.
+    fn poll_limits(&mut self, cx: &mut Context<'_>) -> bool {
+        if self.flags.contains(Flags::DRAINING) {
+            return false;
+        }
+
+        let mut expired = false;
+        if let Some(ref mut timer) = self.lifetime {
+            if Pin::new(timer).poll(cx).is_ready() {
+                trace!("Connection lifetime is reached, disable keep-alive");
+                expired = true;
+            }
+        }
+
+        if let Some(idle) = self.max_idle {
+            let is_idle = self.state.is_empty()
+                && self.messages.is_empty()
+                && self.payload.is_none()
+                && self.write_buf.is_empty();
+
+            if !is_idle {
+                self.idle_timer = None;
+            } else if !expired {
+                let timer = self.idle_timer.get_or_insert_with(|| delay_for(idle));
+                if Pin::new(timer).poll(cx).is_ready() {
+                    trace!("Connection max idle period is reached, close connection");
+                    expired = true;
+                }
+            }
+        }
+
+        if expired {
+            self.flags.insert(Flags::DRAINING | Flags::STARTED);
+            self.flags.remove(Flags::KEEPALIVE);
+        }
+        expired
+    }
+
+    /// Abort connection if client does not read buffered response data
+    /// with the minimal write rate
+    fn poll_write_rate(&mut self, cx: &mut Context<'_>) -> Result<(), DispatchError> {
+        if let Some(ref mut monitor) = self.write_monitor {
+            if !monitor.poll(cx, !self.write_buf.is_empty()) {
+                trace!("Client write rate is too low, drop connection");
+                return Err(DispatchError::SlowClient);
+            }
+        }
+        Ok(())
+    }
+
+    /// Request head and response flush timers
+    fn poll_timeouts(&mut self, cx: &mut Context<'_>) -> Result<(), DispatchError> {
+        if let Some(ref mut timer) = self.flush_timer {
+            if Pin::new(timer).poll(cx).is_ready() {
+                trace!("Response flush timeout, drop connection");
+                return Err(DispatchError::FlushTimeout);
+            }
+        }
+
+        if let Some(ref mut timer) = self.header_timer {
+            if Pin::new(timer).poll(cx).is_ready() {
+                trace!("Request head timeout, close connection");
+                self.header_timer = None;
+                if let Some(mut payload) = self.payload.take() {
+                    payload.set_error(PayloadError::Incomplete(None));
+                }
+                let _ = self.send_response(
+                    Response::RequestTimeout().finish().drop_body(),
+                    ResponseBody::Other(Body::Empty),
+                );
+                self.flags
+                    .insert(Flags::STARTED | Flags::SHUTDOWN | Flags::READ_DISCONNECT);
+                self.state = State::None;
+            }
+        }
+        Ok(())
+    }
+
     /// keep-alive timer
     fn poll_keepalive(&mut self, cx: &mut Context<'_>) -> Result<(), DispatchError> {
         if self.ka_timer.is_none() {
@@ -716,6 +878,16 @@ where
         match self.as_mut().inner {
             DispatcherState::Normal(ref mut inner) => {
                 inner.poll_keepalive(cx)?;
+                inner.poll_timeouts(cx)?;
+                inner.poll_write_rate(cx)?;
+
+                // graceful shutdown, finish in-flight request and close connection
+                if !inner.flags.contains(Flags::DRAINING) && inner.drain.poll_drain(cx) {
+                    trace!("Worker is draining, disable keep-alive");
+                    inner.flags.insert(Flags::DRAINING | Flags::STARTED);
+                    inner.flags.remove(Flags::KEEPALIVE);
+                }
+                inner.poll_limits(cx);
 
                 if inner.flags.contains(Flags::SHUTDOWN) {
                     if inner.flags.contains(Flags::WRITE_DISCONNECT) {
@@ -738,7 +910,12 @@ where
                     // read socket into a buf
                     let should_disconnect =
                         if !inner.flags.contains(Flags::READ_DISCONNECT) {
-                            read_available(cx, &mut inner.io, &mut inner.read_buf)?
+                            read_available(
+                                cx,
+                                &mut inner.io,
+                                &mut inner.read_buf,
+                                &mut inner.read_size,
+                            )?
                         } else {
                             None
                         };
@@ -816,7 +993,14 @@ where
                         // disconnect if shutdown
                         else if inner.flags.contains(Flags::SHUTDOWN) {
                             self.poll(cx)
+                        }
+                        // connection became idle, check limits
+                        else if inner.poll_limits(cx) {
+                            self.poll(cx)
                         } else {
+                            if inner.messages.is_empty() && inner.payload.is_none() {
+                                inner.read_size.release(&mut inner.read_buf);
+                            }
                             Poll::Pending
                         }
                     } else {
@@ -839,22 +1023,21 @@ fn read_available<T>(
     cx: &mut Context<'_>,
     io: &mut T,
     buf: &mut BytesMut,
+    size: &mut ReadBufSize,
 ) -> Result<Option<bool>, io::Error>
 where
     T: AsyncRead + Unpin,
 {
     let mut read_some = false;
     loop {
-        let remaining = buf.capacity() - buf.len();
-        if remaining < LW_BUFFER_SIZE {
-            buf.reserve(HW_BUFFER_SIZE - remaining);
-        }
+        let room = size.reserve(buf);
 
         match read(cx, io, buf) {
             Poll::Pending => {
                 return if read_some { Ok(Some(false)) } else { Ok(None) };
             }
             Poll::Ready(Ok(n)) => {
+                size.record(n, room);
                 if n == 0 {
                     return Ok(Some(true));
                 } else {
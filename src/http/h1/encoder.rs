@@ -12,7 +12,7 @@ use crate::http::header::map;
 use crate::http::helpers;
 use crate::http::header::{CONNECTION, CONTENT_LENGTH, DATE, TRANSFER_ENCODING};
 use crate::http::{HeaderMap, StatusCode, Version};
-use crate::http::message::{ConnectionType, RequestHeadType};
+use crate::http::message::{ConnectionType, RequestHead, RequestHeadType};
 use crate::http::response::Response;
 
 const AVERAGE_HEADER_SIZE: usize = 30;
@@ -21,6 +21,8 @@ const AVERAGE_HEADER_SIZE: usize = 30;
 pub(crate) struct MessageEncoder<T: MessageType> {
     pub length: BodySize,
     pub te: TransferEncoding,
+    /// Write uri in absolute form, for requests sent to http proxy
+    pub absolute_form: bool,
     _t: PhantomData<T>,
 }
 
@@ -29,6 +31,7 @@ impl<T: MessageType> Default for MessageEncoder<T> {
         MessageEncoder {
             length: BodySize::None,
             te: TransferEncoding::empty(),
+            absolute_form: false,
             _t: PhantomData,
         }
     }
@@ -49,6 +52,10 @@ pub(crate) trait MessageType: Sized {
 
     fn encode_status(&mut self, dst: &mut BytesMut) -> io::Result<()>;
 
+    fn encode_absolute_status(&mut self, dst: &mut BytesMut) -> io::Result<()> {
+        self.encode_status(dst)
+    }
+
     fn encode_headers(
         &mut self,
         dst: &mut BytesMut,
@@ -289,27 +296,45 @@ impl MessageType for RequestHeadType {
 
     fn encode_status(&mut self, dst: &mut BytesMut) -> io::Result<()> {
         let head = self.as_ref();
-        dst.reserve(256 + head.headers.len() * AVERAGE_HEADER_SIZE);
-        write!(
-            Writer(dst),
-            "{} {} {}",
-            head.method,
-            head.uri.path_and_query().map(|u| u.as_str()).unwrap_or("/"),
-            match head.version {
-                Version::HTTP_09 => "HTTP/0.9",
-                Version::HTTP_10 => "HTTP/1.0",
-                Version::HTTP_11 => "HTTP/1.1",
-                Version::HTTP_2 => "HTTP/2.0",
-                Version::HTTP_3 => "HTTP/3.0",
-                _ =>
-                    return Err(io::Error::new(
-                        io::ErrorKind::Other,
-                        "unsupported version"
-                    )),
-            }
-        )
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+        let path = head.uri.path_and_query().map(|u| u.as_str()).unwrap_or("/");
+        encode_request_line(head, path, dst)
     }
+
+    fn encode_absolute_status(&mut self, dst: &mut BytesMut) -> io::Result<()> {
+        let head = self.as_ref();
+        if head.uri.authority().is_some() {
+            encode_request_line(head, &head.uri.to_string(), dst)
+        } else {
+            self.encode_status(dst)
+        }
+    }
+}
+
+fn encode_request_line(
+    head: &RequestHead,
+    target: &str,
+    dst: &mut BytesMut,
+) -> io::Result<()> {
+    dst.reserve(256 + target.len() + head.headers.len() * AVERAGE_HEADER_SIZE);
+    write!(
+        Writer(dst),
+        "{} {} {}",
+        head.method,
+        target,
+        match head.version {
+            Version::HTTP_09 => "HTTP/0.9",
+            Version::HTTP_10 => "HTTP/1.0",
+            Version::HTTP_11 => "HTTP/1.1",
+            Version::HTTP_2 => "HTTP/2.0",
+            Version::HTTP_3 => "HTTP/3.0",
+            _ =>
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "unsupported version"
+                )),
+        }
+    )
+    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
 }
 
 impl<T: MessageType> MessageEncoder<T> {
@@ -353,7 +378,11 @@ impl<T: MessageType> MessageEncoder<T> {
             self.te = TransferEncoding::empty();
         }
 
-        message.encode_status(dst)?;
+        if self.absolute_form {
+            message.encode_absolute_status(dst)?;
+        } else {
+            message.encode_status(dst)?;
+        }
         message.encode_headers(dst, version, length, ctype, config)
     }
 }
@@ -3,7 +3,7 @@ use bytes::{Bytes, BytesMut};
 
 mod client;
 mod codec;
-mod decoder;
+pub(crate) mod decoder;
 mod dispatcher;
 mod encoder;
 mod expect;
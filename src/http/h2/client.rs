@@ -1155,6 +1155,7 @@ where
                 initial_max_send_streams: builder.initial_max_send_streams,
                 reset_stream_duration: builder.reset_stream_duration,
                 reset_stream_max: builder.reset_stream_max,
+                stream_overflow_reason: Reason::REFUSED_STREAM,
                 settings: builder.settings.clone(),
             },
         );
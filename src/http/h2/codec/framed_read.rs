@@ -16,7 +16,7 @@ use std::pin::Pin;
 use std::task::{Context, Poll};
 use crate::krse::io::AsyncRead;
 use crate::codec::FramedRead as InnerFramedRead;
-use crate::codec::{LengthDelimitedCodec, LengthDelimitedCodecError};
+use crate::codec::{LengthDelimitedCodec, LengthDelimitedCodecError, ReadBufSize};
 
 // 16 MB "sane default" taken from golang http2
 const DEFAULT_SETTINGS_MAX_HEADER_LIST_SIZE: usize = 16 << 20;
@@ -323,6 +323,11 @@ impl<T> FramedRead<T> {
         self.inner.decoder_mut().set_max_frame_length(val)
     }
 
+    /// Use adaptive read chunk size
+    pub(crate) fn set_read_buf_size(&mut self, size: ReadBufSize) {
+        self.inner.set_read_buf_size(size)
+    }
+
     /// Update the max header list size setting.
     #[inline]
     pub fn set_max_header_list_size(&mut self, val: usize) {
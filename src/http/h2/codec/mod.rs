@@ -15,7 +15,7 @@ use futures_sink::Sink;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 use crate::krse::io::{AsyncRead, AsyncWrite};
-use crate::codec::length_delimited;
+use crate::codec::{length_delimited, ReadBufSize};
 
 use std::io;
 
@@ -93,6 +93,11 @@ impl<T, B> Codec<T, B> {
         self.inner.set_max_header_list_size(val);
     }
 
+    /// Use adaptive read chunk size for the received frames.
+    pub(crate) fn set_read_buf_size(&mut self, size: ReadBufSize) {
+        self.inner.set_read_buf_size(size);
+    }
+
     /// Get a reference to the inner stream.
     pub fn get_ref(&self) -> &T {
         self.inner.get_ref().get_ref()
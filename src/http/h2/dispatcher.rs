@@ -4,19 +4,24 @@ use std::marker::PhantomData;
 use std::net;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use crate::krse::io::{AsyncRead, AsyncWrite};
-use crate::timer::{Delay, Instant};
+use crate::timer::{delay_until, Delay, Instant};
 use crate::service::Service;
 use bytes::{Bytes, BytesMut};
+use crate::http::h2::push::ServerPush;
 use crate::http::h2::server::{Connection, SendResponse};
-use crate::http::h2::SendStream;
+use crate::http::h2::{Reason, SendStream};
+use futures_channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use futures_core::Stream;
 use http::header::{HeaderValue, CONNECTION, CONTENT_LENGTH, DATE, TRANSFER_ENCODING};
+use http::Uri;
 use log::{error, trace};
 
 use crate::http::body::{BodySize, MessageBody, ResponseBody};
 use crate::http::cloneable::CloneableService;
-use crate::http::config::ServiceConfig;
+use crate::http::config::{ServiceConfig, WriteMonitor};
 use crate::http::error::{DispatchError, Error};
 use crate::http::helpers::DataFactory;
 use crate::http::httpmessage::HttpMessage;
@@ -40,9 +45,17 @@ where
     peer_addr: Option<net::SocketAddr>,
     ka_expire: Instant,
     ka_timer: Option<Delay>,
+    lifetime: Option<Delay>,
+    max_idle: Option<Duration>,
+    idle_timer: Option<Delay>,
+    closing: bool,
+    pushes: (UnboundedSender<Pushed>, UnboundedReceiver<Pushed>),
     _t: PhantomData<B>,
 }
 
+/// Promised request and its stream
+type Pushed = (Request, SendResponse<Bytes>);
+
 impl<T, S, B> Dispatcher<T, S, B>
 where
     T: AsyncRead + AsyncWrite + Unpin,
@@ -77,6 +90,10 @@ where
         };
 
         Dispatcher {
+            lifetime: config.conn_lifetime_timer(),
+            max_idle: config.conn_max_idle(),
+            idle_timer: None,
+            closing: false,
             service,
             config,
             peer_addr,
@@ -84,9 +101,43 @@ where
             on_connect,
             ka_expire,
             ka_timer,
+            pushes: unbounded(),
             _t: PhantomData,
         }
     }
+
+    /// Check connection lifetime and max idle period.
+    /// Returns `true` if limit is reached by this call.
+    fn poll_limits(&mut self, cx: &mut Context<'_>) -> bool {
+        if self.closing {
+            return false;
+        }
+
+        if let Some(ref mut timer) = self.lifetime {
+            if Pin::new(timer).poll(cx).is_ready() {
+                trace!("Connection lifetime is reached, close connection");
+                self.closing = true;
+                return true;
+            }
+        }
+
+        if let Some(idle) = self.max_idle {
+            if self.connection.has_streams() {
+                self.idle_timer = None;
+            } else {
+                let now = self.config.now();
+                let timer = self
+                    .idle_timer
+                    .get_or_insert_with(|| delay_until(now + idle));
+                if Pin::new(timer).poll(cx).is_ready() {
+                    trace!("Connection max idle period is reached, close connection");
+                    self.closing = true;
+                    return true;
+                }
+            }
+        }
+        false
+    }
 }
 
 impl<T, S, B> Future for Dispatcher<T, S, B>
@@ -104,6 +155,30 @@ where
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.get_mut();
 
+        // promised requests are handled like client requests,
+        // except pushes are not allowed on pushed streams
+        while let Poll::Ready(Some((req, res))) =
+            Pin::new(&mut this.pushes.1).poll_next(cx)
+        {
+            crate::fiber::spawn(ServiceResponse::<
+                S::Future,
+                S::Response,
+                S::Error,
+                B,
+            > {
+                state: ServiceResponseState::ServiceCall(
+                    this.service.call(req),
+                    Some(res),
+                ),
+                config: this.config.clone(),
+                buffer: None,
+                push: None,
+                chunk_size: CHUNK_SIZE,
+                write_monitor: this.config.write_monitor(),
+                _t: PhantomData,
+            });
+        }
+
         loop {
             match Pin::new(&mut this.connection).poll_accept(cx) {
                 Poll::Ready(None) => return Poll::Ready(Ok(())),
@@ -117,6 +192,11 @@ where
                     }
 
                     let (parts, body) = req.into_parts();
+                    let push = Push {
+                        uri: parts.uri.clone(),
+                        peer_addr: this.peer_addr,
+                        tx: this.pushes.0.clone(),
+                    };
                     let mut req = Request::with_payload(Payload::<
                         crate::http::payload::PayloadStream,
                     >::H2(
@@ -147,10 +227,20 @@ where
                         ),
                         config: this.config.clone(),
                         buffer: None,
+                        push: Some(push),
+                        chunk_size: CHUNK_SIZE,
+                        write_monitor: this.config.write_monitor(),
                         _t: PhantomData,
                     });
                 }
-                Poll::Pending => return Poll::Pending,
+                Poll::Pending => {
+                    // connection limits, send GOAWAY and finish active streams
+                    if this.poll_limits(cx) {
+                        this.connection.graceful_shutdown();
+                        continue;
+                    }
+                    return Poll::Pending;
+                }
             }
         }
     }
@@ -161,14 +251,59 @@ struct ServiceResponse<F, I, E, B> {
     state: ServiceResponseState<F, B>,
     config: ServiceConfig,
     buffer: Option<Bytes>,
+    push: Option<Push>,
+    chunk_size: usize,
+    write_monitor: Option<WriteMonitor>,
     _t: PhantomData<(I, E)>,
 }
 
+/// Client request info required for server push
+struct Push {
+    uri: Uri,
+    peer_addr: Option<net::SocketAddr>,
+    tx: UnboundedSender<Pushed>,
+}
+
 enum ServiceResponseState<F, B> {
     ServiceCall(F, Option<SendResponse<Bytes>>),
     SendPayload(SendStream<Bytes>, ResponseBody<B>),
 }
 
+impl Push {
+    /// Send push promises and queue promised requests for dispatching
+    fn send(self, send: &mut SendResponse<Bytes>, settings: ServerPush) {
+        for promise in settings.promises {
+            let req = match promise.request(&self.uri) {
+                Some(req) => req,
+                None => {
+                    trace!("Can not push invalid path: {:?}", promise.path);
+                    continue;
+                }
+            };
+            let uri = req.uri().clone();
+            let headers = req.headers().clone();
+
+            let pushed = match send.push_request(req) {
+                Ok(pushed) => pushed,
+                Err(e) => {
+                    trace!("Error sending h2 push promise: {:?}", e);
+                    return;
+                }
+            };
+
+            let mut req: Request = Request::with_payload(Payload::None);
+            let head = req.head_mut();
+            head.uri = uri;
+            head.method = http::Method::GET;
+            head.version = http::Version::HTTP_2;
+            head.headers = headers.into();
+            head.peer_addr = self.peer_addr;
+
+            let _ = self.tx.unbounded_send((req, pushed.into_inner()));
+        }
+    }
+}
+
 impl<F, I, E, B> ServiceResponse<F, I, E, B>
 where
     F: Future<Output = Result<I, E>>,
@@ -254,9 +389,16 @@ where
             ServiceResponseState::ServiceCall(ref mut call, ref mut send) => {
                 match unsafe { Pin::new_unchecked(call) }.poll(cx) {
                     Poll::Ready(Ok(res)) => {
-                        let (res, body) = res.into().replace_body(());
+                        let (mut res, body) = res.into().replace_body(());
 
                         let mut send = send.take().unwrap();
+                        let settings = res.extensions_mut().remove::<ServerPush>();
+                        if let Some(settings) = settings {
+                            *this.chunk_size = settings.chunk_size(CHUNK_SIZE);
+                            if let Some(push) = this.push.take() {
+                                push.send(&mut send, settings);
+                            }
+                        }
                         let mut size = body.size();
                         let h2_res =
                             self.as_mut().prepare_response(res.head(), &mut size);
@@ -313,20 +455,36 @@ where
                 loop {
                     if let Some(ref mut buffer) = this.buffer {
                         match stream.poll_capacity(cx) {
-                            Poll::Pending => return Poll::Pending,
+                            Poll::Pending => {
+                                // client does not update flow control window
+                                if let Some(ref mut monitor) = this.write_monitor {
+                                    if !monitor.poll(cx, true) {
+                                        trace!("Client write rate is too low, reset stream");
+                                        stream.send_reset(Reason::CANCEL);
+                                        return Poll::Ready(());
+                                    }
+                                }
+                                return Poll::Pending;
+                            }
                             Poll::Ready(None) => return Poll::Ready(()),
                             Poll::Ready(Some(Ok(cap))) => {
                                 let len = buffer.len();
                                 let bytes = buffer.split_to(std::cmp::min(cap, len));
+                                if let Some(ref mut monitor) = this.write_monitor {
+                                    monitor.written(bytes.len());
+                                }
 
                                 if let Err(e) = stream.send_data(bytes, false) {
                                     warn!("{:?}", e);
                                     return Poll::Ready(());
                                 } else if !buffer.is_empty() {
-                                    let cap = std::cmp::min(buffer.len(), CHUNK_SIZE);
+                                    let cap = std::cmp::min(buffer.len(), *this.chunk_size);
                                     stream.reserve_capacity(cap);
                                 } else {
                                     this.buffer.take();
+                                    if let Some(ref mut monitor) = this.write_monitor {
+                                        monitor.poll(cx, false);
+                                    }
                                 }
                             }
                             Poll::Ready(Some(Err(e))) => {
@@ -346,7 +504,7 @@ where
                             Poll::Ready(Some(Ok(chunk))) => {
                                 stream.reserve_capacity(std::cmp::min(
                                     chunk.len(),
-                                    CHUNK_SIZE,
+                                    *this.chunk_size,
                                 ));
                                 *this.buffer = Some(chunk);
                             }
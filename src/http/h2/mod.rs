@@ -145,6 +145,7 @@ impl<T, E> PollExt<T, E> for Poll<Option<Result<T, E>>> {
 
 
 mod dispatcher;
+pub(crate) mod push;
 mod service;
 
 pub use self::dispatcher::Dispatcher;
@@ -54,6 +54,7 @@ pub(crate) struct Config {
     pub initial_max_send_streams: usize,
     pub reset_stream_duration: Duration,
     pub reset_stream_max: usize,
+    pub stream_overflow_reason: Reason,
     pub settings: frame::Settings,
 }
 
@@ -91,6 +92,7 @@ where
                 .settings
                 .max_concurrent_streams()
                 .map(|max| max as usize),
+            remote_overflow_reason: config.stream_overflow_reason,
         });
         Connection {
             state: State::Open,
@@ -194,6 +196,11 @@ where
         }
     }
 
+    /// Returns true if connection has active streams
+    pub(crate) fn has_streams(&self) -> bool {
+        self.streams.has_streams()
+    }
+
     pub(crate) fn take_user_pings(&mut self) -> Option<UserPings> {
         self.ping_pong.take_user_pings()
     }
@@ -23,8 +23,7 @@ use self::send::Send;
 use self::state::State;
 use self::store::Store;
 use self::stream::Stream;
-
-use crate::http::h2::frame::{StreamId, StreamIdOverflow};
+use crate::http::h2::frame::{Reason, StreamId, StreamIdOverflow};
 use crate::http::h2::proto::*;
 
 use bytes::Bytes;
@@ -58,4 +57,7 @@ pub struct Config {
 
     /// Maximum number of remote initiated streams
     pub remote_max_initiated: Option<usize>,
+
+    /// Error for remote initiated streams over the maximum
+    pub remote_overflow_reason: Reason,
 }
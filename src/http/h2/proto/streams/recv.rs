@@ -54,6 +54,9 @@ pub(super) struct Recv {
     /// Refused StreamId, this represents a frame that must be sent out.
     refused: Option<StreamId>,
 
+    /// Error for streams over the max concurrent streams limit
+    overflow_reason: Reason,
+
     /// If push promises are allowed to be recevied.
     is_push_enabled: bool,
 }
@@ -108,6 +111,7 @@ impl Recv {
             reset_duration: config.local_reset_duration,
             buffer: Buffer::new(),
             refused: None,
+            overflow_reason: config.remote_overflow_reason,
             is_push_enabled: config.local_push_enabled,
         }
     }
@@ -144,6 +148,10 @@ impl Recv {
         self.next_stream_id = id.next_id();
 
         if !counts.can_inc_num_recv_streams() {
+            if self.overflow_reason != Reason::REFUSED_STREAM {
+                proto_err!(conn: "recv_open: max concurrent streams exceeded; id={:?}", id);
+                return Err(RecvError::Connection(self.overflow_reason));
+            }
             self.refused = Some(id);
             return Ok(None);
         }
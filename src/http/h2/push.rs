@@ -0,0 +1,59 @@
+//! Server push and stream weight settings of the response
+use std::convert::TryFrom;
+
+use http::uri::{PathAndQuery, Uri};
+use http::Method;
+
+use crate::http::header::HeaderMap;
+
+/// Default stream weight, RFC 7540 §5.3.5
+pub(crate) const DEFAULT_WEIGHT: u16 = 16;
+
+/// Server push and priority settings, stored in response extensions.
+///
+/// Settings are used by the HTTP/2 dispatcher only, HTTP/1 dispatcher
+/// ignores them.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ServerPush {
+    pub(crate) promises: Vec<PushPromise>,
+    pub(crate) weight: Option<u16>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct PushPromise {
+    pub(crate) path: String,
+    pub(crate) headers: HeaderMap,
+}
+
+impl ServerPush {
+    /// Size of data chunks, stream gets share of the connection
+    /// proportional to its weight
+    pub(crate) fn chunk_size(&self, default: usize) -> usize {
+        let weight = self.weight.unwrap_or(DEFAULT_WEIGHT) as usize;
+        std::cmp::max(default * weight / DEFAULT_WEIGHT as usize, 1024)
+    }
+}
+
+impl PushPromise {
+    /// Promised `GET` request, `base` is the uri of the client request.
+    pub(crate) fn request(&self, base: &Uri) -> Option<http::Request<()>> {
+        let authority = base.authority()?.clone();
+        let scheme = base.scheme().cloned().unwrap_or(http::uri::Scheme::HTTPS);
+        let path = PathAndQuery::try_from(self.path.as_str()).ok()?;
+        let uri = Uri::builder()
+            .scheme(scheme)
+            .authority(authority)
+            .path_and_query(path)
+            .build()
+            .ok()?;
+
+        let mut req = http::Request::new(());
+        *req.method_mut() = Method::GET;
+        *req.uri_mut() = uri;
+        *req.version_mut() = http::Version::HTTP_2;
+        for (key, value) in self.headers.iter() {
+            req.headers_mut().append(key, value.clone());
+        }
+        Some(req)
+    }
+}
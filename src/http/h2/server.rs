@@ -113,6 +113,7 @@
 //! [`RecvStream`]: ../struct.RecvStream.html
 //! [`SendStream`]: ../struct.SendStream.html
 
+use crate::codec::ReadBufSize;
 use crate::http::h2::codec::{Codec, RecvError, UserError};
 use crate::http::h2::frame::{self, Pseudo, PushPromiseHeaderError, Reason, Settings, StreamId};
 use crate::http::h2::proto::{self, Config, Prioritized};
@@ -238,6 +239,12 @@ pub struct Builder {
 
     /// Initial target window size for new connections.
     initial_target_connection_window_size: Option<u32>,
+
+    /// Error for streams over the max concurrent streams limit.
+    stream_overflow_reason: Reason,
+
+    /// Adaptive read chunk size of the connection.
+    read_buf_size: Option<ReadBufSize>,
 }
 
 /// Send a response back to the client
@@ -368,6 +375,10 @@ where
             codec.set_max_recv_header_list_size(max as usize);
         }
 
+        if let Some(size) = builder.read_buf_size {
+            codec.set_read_buf_size(size);
+        }
+
         // Send initial settings frame.
         codec
             .buffer(builder.settings.clone().into())
@@ -507,6 +518,11 @@ where
         self.connection.go_away_gracefully();
     }
 
+    /// Returns true if connection has active streams.
+    pub(crate) fn has_streams(&self) -> bool {
+        self.connection.has_streams()
+    }
+
     /// Takes a `PingPong` instance from the connection.
     ///
     /// # Note
@@ -576,6 +592,8 @@ impl Builder {
             reset_stream_max: proto::DEFAULT_RESET_STREAM_MAX,
             settings: Settings::default(),
             initial_target_connection_window_size: None,
+            stream_overflow_reason: Reason::REFUSED_STREAM,
+            read_buf_size: None,
         }
     }
 
@@ -767,6 +785,27 @@ impl Builder {
         self
     }
 
+    /// Sets the error for streams opened by the remote over the maximum
+    /// number of concurrent streams.
+    ///
+    /// With the default `REFUSED_STREAM` the stream is reset and the client
+    /// could safely retry the request later. Any other reason, i.e.
+    /// `ENHANCE_YOUR_CALM`, is treated as a connection error and the
+    /// connection is closed with a `GOAWAY` frame.
+    pub fn max_concurrent_streams_reason(&mut self, reason: Reason) -> &mut Self {
+        self.stream_overflow_reason = reason;
+        self
+    }
+
+    /// Sets min and max size of the connection read chunk.
+    ///
+    /// Read chunk grows under sustained throughput and shrinks back when
+    /// connection is idle.
+    pub(crate) fn read_buffer(&mut self, min: usize, max: usize) -> &mut Self {
+        self.read_buf_size = Some(ReadBufSize::new(min, max));
+        self
+    }
+
     /// Sets the maximum number of concurrent locally reset streams.
     ///
     /// When a stream is explicitly reset by either calling
@@ -1093,6 +1132,10 @@ impl<B: Buf> SendPushedResponse<B> {
     pub fn stream_id(&self) -> crate::http::h2::StreamId {
         self.inner.stream_id()
     }
+
+    pub(crate) fn into_inner(self) -> SendResponse<B> {
+        self.inner
+    }
 }
 
 // ===== impl Flush =====
@@ -1219,10 +1262,12 @@ where
                 codec,
                 Config {
                     next_stream_id: 2.into(),
-                    // Server does not need to locally initiate any streams
-                    initial_max_send_streams: 0,
+                    // Pushed streams are locally initiated, their number is
+                    // unlimited until client sends SETTINGS_MAX_CONCURRENT_STREAMS
+                    initial_max_send_streams: usize::MAX,
                     reset_stream_duration: self.builder.reset_stream_duration,
                     reset_stream_max: self.builder.reset_stream_max,
+                    stream_overflow_reason: self.builder.stream_overflow_reason,
                     settings: self.builder.settings.clone(),
                 },
             );
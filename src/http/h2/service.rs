@@ -13,7 +13,7 @@ use crate::service::{
     fn_factory, fn_service, pipeline_factory, IntoServiceFactory, Service,
     ServiceFactory,
 };
-use crate::http::h2::server::{self, Handshake};
+use crate::http::h2::server::Handshake;
 use crate::http::body::MessageBody;
 use crate::http::cloneable::CloneableService;
 use crate::http::config::ServiceConfig;
@@ -264,7 +264,7 @@ where
                 Some(self.cfg.clone()),
                 addr,
                 on_connect,
-                server::handshake(io),
+                self.cfg.h2_builder().handshake(io),
             ),
         }
     }
@@ -391,6 +391,17 @@ impl From<http::HeaderMap> for HeaderMap {
     }
 }
 
+/// Convert HeaderMap to a http::HeaderMap
+impl From<HeaderMap> for http::HeaderMap {
+    fn from(map: HeaderMap) -> http::HeaderMap {
+        let mut new_map = http::HeaderMap::with_capacity(map.len());
+        for (h, v) in map.iter() {
+            new_map.append(h.clone(), v.clone());
+        }
+        new_map
+    }
+}
+
 // This encode set is used for HTTP header values and is defined at
 // https://tools.ietf.org/html/rfc5987#section-3.2
 pub(crate) const HTTP_VALUE: &AsciiSet = &CONTROLS
@@ -24,6 +24,7 @@ pub mod test;
 
 pub use self::builder::HttpServiceBuilder;
 pub use self::config::{KeepAlive, ServiceConfig};
+pub(crate) use self::config::{ConnLimits, H1Timeouts, H2Settings, WriteRate};
 pub use self::extensions::Extensions;
 pub use self::message::{Message, RequestHead, RequestHeadType, ResponseHead};
 pub use self::payload::{Payload, PayloadStream};
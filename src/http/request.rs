@@ -49,6 +49,54 @@ impl From<Message<RequestHead>> for Request<PayloadStream> {
     }
 }
 
+/// Convert `http::Request` to a Request
+///
+/// `http::Extensions` of the request are stored in request extensions and
+/// could be accessed with `req.extensions().get::<http::Extensions>()`.
+/// Requests with stream body could be converted with `req.map(Payload::Stream)`.
+impl<P> From<http::Request<Payload<P>>> for Request<P> {
+    fn from(req: http::Request<Payload<P>>) -> Self {
+        let (parts, payload) = req.into_parts();
+        let mut extensions = parts.extensions;
+
+        let mut head = Message::<RequestHead>::new();
+        head.uri = parts.uri;
+        head.method = parts.method;
+        head.version = parts.version;
+        head.headers = parts.headers.into();
+        head.peer_addr = extensions.remove::<net::SocketAddr>();
+        head.extensions_mut().insert(extensions);
+
+        Request { head, payload }
+    }
+}
+
+/// Convert Request to a `http::Request`
+///
+/// Request extensions are not `Send`, so only `http::Extensions` stored in
+/// request extensions are transferred. Peer address is stored as
+/// `SocketAddr` extension.
+impl<P> From<Request<P>> for http::Request<Payload<P>> {
+    fn from(req: Request<P>) -> Self {
+        let (head, payload) = req.into_parts();
+        let mut extensions = head
+            .extensions_mut()
+            .remove::<http::Extensions>()
+            .unwrap_or_else(http::Extensions::new);
+        if let Some(addr) = head.peer_addr {
+            extensions.insert(addr);
+        }
+
+        let mut req = http::Request::new(payload);
+        *req.uri_mut() = head.uri.clone();
+        *req.method_mut() = head.method.clone();
+        *req.version_mut() = head.version;
+        *req.headers_mut() = head.headers.clone().into();
+        *req.extensions_mut() = extensions;
+        req
+    }
+}
+
 impl Request<PayloadStream> {
     /// Create new Request instance
     pub fn new() -> Request<PayloadStream> {
@@ -206,4 +254,34 @@ mod tests {
         let s = format!("{:?}", req);
         assert!(s.contains("Request HTTP/1.1 GET:/index.html"));
     }
+
+    #[test]
+    fn test_http_conversion() {
+        let addr: net::SocketAddr = "127.0.0.1:8080".parse().unwrap();
+        let mut req = http::Request::builder()
+            .method(Method::POST)
+            .uri("/index.html?q=1")
+            .header(header::CONTENT_TYPE, "text/plain")
+            .body(Payload::<PayloadStream>::None)
+            .unwrap();
+        req.extensions_mut().insert(10u32);
+        req.extensions_mut().insert(addr);
+
+        let req = Request::from(req);
+        assert_eq!(*req.method(), Method::POST);
+        assert_eq!(req.uri().query(), Some("q=1"));
+        assert_eq!(req.peer_addr(), Some(addr));
+        assert!(req.headers().contains_key(header::CONTENT_TYPE));
+        assert_eq!(
+            req.extensions().get::<http::Extensions>().unwrap().get::<u32>(),
+            Some(&10)
+        );
+
+        let req = http::Request::from(req);
+        assert_eq!(*req.method(), Method::POST);
+        assert_eq!(req.uri().path(), "/index.html");
+        assert_eq!(req.headers()[header::CONTENT_TYPE], "text/plain");
+        assert_eq!(req.extensions().get::<u32>(), Some(&10));
+        assert_eq!(req.extensions().get::<net::SocketAddr>(), Some(&addr));
+    }
 }
@@ -19,6 +19,7 @@ use crate::http::extensions::Extensions;
 use crate::http::header::{self, Header,  HeaderName, HeaderValue, IntoHeaderValue};
 use crate::http::{HeaderMap, StatusCode};
 use crate::http::error::{Error, HttpError};
+use crate::http::h2::push::{PushPromise, ServerPush};
 use crate::http::message::{BoxedResponseHead, ConnectionType, ResponseHead};
 
 /// An HTTP Response
@@ -593,6 +594,48 @@ impl ResponseBuilder {
         self
     }
 
+    /// Push resource to the client with HTTP/2 server push.
+    ///
+    /// Client receives promise of `GET` request for `path` with the
+    /// headers, the promised response is produced by the same service as
+    /// the current response. Pushes are silently dropped on HTTP/1
+    /// connections, for pushed responses and if client disabled push.
+    ///
+    /// ```rust
+    /// use kayrx::http::{HeaderMap, Response, StatusCode};
+    ///
+    /// let res = Response::build(StatusCode::OK)
+    ///     .push_promise("/static/app.css", HeaderMap::new())
+    ///     .push_promise("/static/app.js", HeaderMap::new())
+    ///     .finish();
+    /// ```
+    pub fn push_promise<P: Into<String>>(
+        &mut self,
+        path: P,
+        headers: HeaderMap,
+    ) -> &mut Self {
+        if let Some(parts) = parts(&mut self.head, &self.err) {
+            server_push(parts).promises.push(PushPromise {
+                path: path.into(),
+                headers,
+            });
+        }
+        self
+    }
+
+    /// Set HTTP/2 stream weight, `1..=256`, default is `16`.
+    ///
+    /// Response data is sent in chunks proportional to the weight, so
+    /// heavier streams get bigger share of the connection. Weight is
+    /// ignored on HTTP/1 connections.
+    pub fn priority(&mut self, weight: u16) -> &mut Self {
+        if let Some(parts) = parts(&mut self.head, &self.err) {
+            let weight = std::cmp::min(std::cmp::max(weight, 1), 256);
+            server_push(parts).weight = Some(weight);
+        }
+        self
+    }
+
     /// Responses extensions
     #[inline]
     pub fn extensions(&self) -> Ref<'_, Extensions> {
@@ -715,6 +758,15 @@ fn parts<'a>(
     parts.as_mut().map(|r| &mut **r)
 }
 
+fn server_push(head: &ResponseHead) -> RefMut<'_, ServerPush> {
+    RefMut::map(head.extensions.borrow_mut(), |ext| {
+        if !ext.contains::<ServerPush>() {
+            ext.insert(ServerPush::default());
+        }
+        ext.get_mut::<ServerPush>().unwrap()
+    })
+}
+
 /// Convert `Response` to a `ResponseBuilder`. Body get dropped.
 impl<B> From<Response<B>> for ResponseBuilder {
     fn from(res: Response<B>) -> ResponseBuilder {
@@ -890,6 +942,42 @@ impl From<BytesMut> for Response {
     }
 }
 
+/// Convert `http::Response` to a Response
+///
+/// `http::Extensions` of the response are stored in response extensions.
+impl<B> From<http::Response<B>> for Response<B> {
+    fn from(res: http::Response<B>) -> Self {
+        let (parts, body) = res.into_parts();
+
+        let mut res = Response::with_body(parts.status, body);
+        res.head.version = parts.version;
+        res.head.headers = parts.headers.into();
+        res.head.extensions_mut().insert(parts.extensions);
+        res
+    }
+}
+
+/// Convert Response to a `http::Response`
+///
+/// Response extensions are not `Send`, so only `http::Extensions` stored in
+/// response extensions are transferred.
+impl<B> From<Response<B>> for http::Response<ResponseBody<B>> {
+    fn from(res: Response<B>) -> Self {
+        let Response { mut head, body, .. } = res;
+        let extensions = head
+            .extensions_mut()
+            .remove::<http::Extensions>()
+            .unwrap_or_else(http::Extensions::new);
+
+        let mut res = http::Response::new(body);
+        *res.status_mut() = head.status;
+        *res.version_mut() = head.version;
+        *res.headers_mut() = std::mem::replace(&mut head.headers, HeaderMap::new()).into();
+        *res.extensions_mut() = extensions;
+        res
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1131,4 +1219,52 @@ mod tests {
             assert_eq!((cookie.name(), cookie.value()), ("cookie1", "val100"));
         }
     }
+
+    #[test]
+    fn test_http_conversion() {
+        let mut res = http::Response::builder()
+            .status(StatusCode::CREATED)
+            .header(CONTENT_TYPE, "text/plain")
+            .body(Body::from("test"))
+            .unwrap();
+        res.extensions_mut().insert(10u32);
+
+        let res = Response::from(res);
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.body().get_ref(), b"test");
+        assert_eq!(
+            res.headers().get(CONTENT_TYPE).unwrap(),
+            HeaderValue::from_static("text/plain")
+        );
+
+        let res = http::Response::from(res);
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.headers()[CONTENT_TYPE], "text/plain");
+        assert_eq!(res.extensions().get::<u32>(), Some(&10));
+        assert_eq!(res.body().get_ref(), b"test");
+    }
+
+    #[test]
+    fn test_server_push() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_static("text/css"));
+        let res = Response::build(StatusCode::OK)
+            .push_promise("/app.css", headers)
+            .priority(1000)
+            .finish();
+
+        let ext = res.extensions();
+        let push = ext.get::<ServerPush>().unwrap();
+        assert_eq!(push.weight, Some(256));
+        assert_eq!(push.promises.len(), 1);
+
+        let base = http::Uri::from_static("https://example.com/index.html");
+        let req = push.promises[0].request(&base).unwrap();
+        assert_eq!(req.method(), http::Method::GET);
+        assert_eq!(req.uri(), "https://example.com/app.css");
+        assert_eq!(req.headers()[header::ACCEPT], "text/css");
+
+        let base = http::Uri::from_static("/index.html");
+        assert!(push.promises[0].request(&base).is_none());
+    }
 }
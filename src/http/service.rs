@@ -11,7 +11,7 @@ use crate::krse::io::{AsyncRead, AsyncWrite};
 use crate::codec::Framed2 as Framed;
 use crate::krse::net::TcpStream;
 use crate::service::{pipeline_factory, IntoServiceFactory, Service, ServiceFactory};
-use crate::http::h2::server::{self, Handshake};
+use crate::http::h2::server::Handshake;
 use crate::http::body::MessageBody;
 use crate::http::builder::HttpServiceBuilder;
 use crate::http::cloneable::CloneableService;
@@ -218,7 +218,10 @@ mod rustls {
         U::InitError: fmt::Debug,
         <U::Service as Service>::Future: 'static,
     {
-        /// Create openssl based service
+        /// Create rustls based service.
+        ///
+        /// Alpn protocols are set to "h2" and "http/1.1" if config does not
+        /// set them.
         pub fn rustls(
             self,
             mut config: ServerConfig,
@@ -229,8 +232,10 @@ mod rustls {
             Error = SslError<io::Error, DispatchError>,
             InitError = (),
         > {
-            let protos = vec!["h2".to_string().into(), "http/1.1".to_string().into()];
-            config.set_protocols(&protos);
+            if config.alpn_protocols.is_empty() {
+                let protos = vec!["h2".to_string().into(), "http/1.1".to_string().into()];
+                config.set_protocols(&protos);
+            }
 
             pipeline_factory(
                 Acceptor::new(config)
@@ -490,7 +495,7 @@ where
         match proto {
             Protocol::Http2 => HttpServiceHandlerResponse {
                 state: State::H2Handshake(Some((
-                    server::handshake(io),
+                    self.cfg.h2_builder().handshake(io),
                     self.cfg.clone(),
                     self.srv.clone(),
                     on_connect,
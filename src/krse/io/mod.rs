@@ -109,6 +109,7 @@ mod async_buf_read;
 mod async_read;
 mod async_write;
 mod async_seek;
+mod duplex;
 mod stderr;
 mod stdin;
 mod stdout;
@@ -122,6 +123,7 @@ pub use self::async_read::AsyncRead;
 pub use self::async_write::AsyncWrite;
 pub use self::async_buf_read::AsyncBufRead;
 pub use self::async_seek::AsyncSeek;
+pub use self::duplex::{duplex, DuplexStream};
 pub use self::stderr::{stderr, Stderr};
 pub use self::stdin::{stdin, Stdin};
 pub use self::stdout::{stdout, Stdout};
@@ -109,10 +109,12 @@
 
 use crate::krse::cell::CausalCell;
 use crate::krse::task::AtomicWaker;
+use futures_core::stream::Stream;
 
 use std::sync::{Mutex, Arc, Condvar};
 use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, spin_loop_hint};
 use std::fmt;
+use std::pin::Pin;
 use std::ptr;
 use std::sync::atomic::Ordering::SeqCst;
 use std::task::{Context, Poll, Waker};
@@ -863,6 +865,22 @@ where
     }
 }
 
+/// Receiver yields every value sent after subscription, lagged receiver
+/// yields `Err(RecvError::Lagged)` and continues from the oldest retained
+/// value. Stream ends when all senders are dropped.
+impl<T: Clone> Stream for Receiver<T> {
+    type Item = Result<T, RecvError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.poll_recv(cx) {
+            Poll::Ready(Ok(value)) => Poll::Ready(Some(Ok(value))),
+            Poll::Ready(Err(RecvError::Closed)) => Poll::Ready(None),
+            Poll::Ready(Err(err)) => Poll::Ready(Some(Err(err))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
 impl<T> Drop for Receiver<T> {
     fn drop(&mut self) {
         let mut tail = self.shared.tail.lock().unwrap();
@@ -14,10 +14,14 @@ extern crate alloc;
 #[cfg(not(test))] 
 pub use kayrx_macro::main;
 pub use kayrx_macro::test;
+pub mod bus;
 pub mod codec;
 pub mod connect;
+pub mod coord;
 pub mod fiber;
 pub mod framed;
+#[doc(hidden)]
+pub mod fuzz;
 pub mod http;
 pub mod jrpc;
 pub mod krse;
@@ -25,6 +29,10 @@ pub mod router;
 pub mod secure;
 pub mod server;
 pub mod service;
+#[cfg(feature = "sim")]
+pub mod sim;
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
 pub mod timer;
 pub mod web;
 pub mod websocket;
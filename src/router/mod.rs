@@ -17,6 +17,22 @@ pub trait Resource<T: ResourcePath> {
 
 pub trait ResourcePath {
     fn path(&self) -> &str;
+
+    /// Decode matched parameter value, `None` means value is used as is.
+    ///
+    /// `decoding` is resource specific policy that overrides path policy.
+    fn decode_param(
+        &self,
+        _value: &str,
+        _decoding: Option<&PathDecoding>,
+    ) -> Result<Option<String>, DecodeError> {
+        Ok(None)
+    }
+
+    /// Error of path decoding
+    fn decode_error(&self) -> Option<DecodeError> {
+        None
+    }
 }
 
 impl ResourcePath for String {
@@ -133,7 +149,7 @@ array_patterns!(String, 16);
 
 mod url;
 
-pub use self::url::{Quoter, Url};
+pub use self::url::{DecodeError, EncodedSlash, PathDecoding, Quoter, Url};
 
 mod http_support {
     use super::ResourcePath;
@@ -4,12 +4,13 @@ use std::rc::Rc;
 use serde::de;
 
 use crate::router::de::PathDeserializer;
-use crate::router::{Resource, ResourcePath};
+use crate::router::{DecodeError, PathDecoding, Resource, ResourcePath};
 
 #[derive(Debug, Clone, Copy)]
 pub(crate) enum PathItem {
     Static(&'static str),
     Segment(u16, u16),
+    Decoded(u16),
 }
 
 /// Resource path match information
@@ -20,6 +21,8 @@ pub struct Path<T> {
     path: T,
     pub(crate) skip: u16,
     pub(crate) segments: Vec<(Rc<String>, PathItem)>,
+    decoded: Vec<String>,
+    error: Option<DecodeError>,
 }
 
 impl<T: Default> Default for Path<T> {
@@ -28,6 +31,8 @@ impl<T: Default> Default for Path<T> {
             path: T::default(),
             skip: 0,
             segments: Vec::new(),
+            decoded: Vec::new(),
+            error: None,
         }
     }
 }
@@ -38,6 +43,8 @@ impl<T: Clone> Clone for Path<T> {
             path: self.path.clone(),
             skip: self.skip,
             segments: self.segments.clone(),
+            decoded: self.decoded.clone(),
+            error: self.error,
         }
     }
 }
@@ -48,6 +55,8 @@ impl<T: ResourcePath> Path<T> {
             path,
             skip: 0,
             segments: Vec::new(),
+            decoded: Vec::new(),
+            error: None,
         }
     }
 
@@ -81,6 +90,8 @@ impl<T: ResourcePath> Path<T> {
         self.skip = 0;
         self.path = path;
         self.segments.clear();
+        self.decoded.clear();
+        self.error = None;
     }
 
     #[inline]
@@ -88,6 +99,8 @@ impl<T: ResourcePath> Path<T> {
     pub fn reset(&mut self) {
         self.skip = 0;
         self.segments.clear();
+        self.decoded.clear();
+        self.error = None;
     }
 
     #[inline]
@@ -96,12 +109,45 @@ impl<T: ResourcePath> Path<T> {
         self.skip += n;
     }
 
-    pub(crate) fn add(&mut self, name: Rc<String>, value: PathItem) {
+    pub(crate) fn add(
+        &mut self,
+        name: Rc<String>,
+        value: PathItem,
+        decoding: Option<&PathDecoding>,
+    ) {
         match value {
-            PathItem::Static(s) => self.segments.push((name, PathItem::Static(s))),
-            PathItem::Segment(begin, end) => self
-                .segments
-                .push((name, PathItem::Segment(self.skip + begin, self.skip + end))),
+            PathItem::Segment(begin, end) => {
+                let (begin, end) = (self.skip + begin, self.skip + end);
+                let value = &self.path.path()[(begin as usize)..(end as usize)];
+                match self.path.decode_param(value, decoding) {
+                    Ok(Some(decoded)) => {
+                        self.decoded.push(decoded);
+                        let idx = (self.decoded.len() - 1) as u16;
+                        self.segments.push((name, PathItem::Decoded(idx)));
+                    }
+                    Ok(None) => self.segments.push((name, PathItem::Segment(begin, end))),
+                    Err(e) => {
+                        self.error = Some(e);
+                        self.segments.push((name, PathItem::Segment(begin, end)));
+                    }
+                }
+            }
+            item => self.segments.push((name, item)),
+        }
+    }
+
+    /// Error of path or matched parameters decoding.
+    ///
+    /// Request with decoding error is rejected by application router.
+    pub fn decode_error(&self) -> Option<DecodeError> {
+        self.error.or_else(|| self.path.decode_error())
+    }
+
+    fn item(&self, item: PathItem) -> &str {
+        match item {
+            PathItem::Static(s) => s,
+            PathItem::Segment(s, e) => &self.path.path()[(s as usize)..(e as usize)],
+            PathItem::Decoded(idx) => &self.decoded[idx as usize],
         }
     }
 
@@ -127,12 +173,7 @@ impl<T: ResourcePath> Path<T> {
     pub fn get(&self, key: &str) -> Option<&str> {
         for item in self.segments.iter() {
             if key == item.0.as_str() {
-                return match item.1 {
-                    PathItem::Static(ref s) => Some(&s),
-                    PathItem::Segment(s, e) => {
-                        Some(&self.path.path()[(s as usize)..(e as usize)])
-                    }
-                };
+                return Some(self.item(item.1));
             }
         }
         if key == "tail" {
@@ -186,10 +227,7 @@ impl<'a, T: ResourcePath> Iterator for PathIter<'a, T> {
     fn next(&mut self) -> Option<(&'a str, &'a str)> {
         if self.idx < self.params.len() {
             let idx = self.idx;
-            let res = match self.params.segments[idx].1 {
-                PathItem::Static(ref s) => &s,
-                PathItem::Segment(s, e) => &self.params.path.path()[(s as usize)..(e as usize)],
-            };
+            let res = self.params.item(self.params.segments[idx].1);
             self.idx += 1;
             return Some((&self.params.segments[idx].0, res));
         }
@@ -210,10 +248,7 @@ impl<T: ResourcePath> Index<usize> for Path<T> {
     type Output = str;
 
     fn index(&self, idx: usize) -> &str {
-        match self.segments[idx].1 {
-            PathItem::Static(ref s) => &s,
-            PathItem::Segment(s, e) => &self.path.path()[(s as usize)..(e as usize)],
-        }
+        self.item(self.segments[idx].1)
     }
 }
 
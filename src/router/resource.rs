@@ -5,7 +5,7 @@ use std::rc::Rc;
 use regex::{escape, Regex, RegexSet};
 
 use crate::router::path::{Path, PathItem};
-use crate::router::{IntoPattern, Resource, ResourcePath};
+use crate::router::{IntoPattern, PathDecoding, Resource, ResourcePath};
 
 const MAX_DYNAMIC_SEGMENTS: usize = 16;
 
@@ -19,6 +19,7 @@ pub struct ResourceDef {
     name: String,
     pattern: String,
     elements: Vec<PatternElement>,
+    decoding: Option<PathDecoding>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -70,6 +71,7 @@ impl ResourceDef {
                 elements: Vec::new(),
                 name: String::new(),
                 pattern: "".to_owned(),
+                decoding: None,
             }
         }
     }
@@ -104,6 +106,18 @@ impl ResourceDef {
         self.id = id;
     }
 
+    /// Decoding policy of matched parameters, overrides path policy
+    pub fn decoding(&self) -> Option<&PathDecoding> {
+        self.decoding.as_ref()
+    }
+
+    /// Set decoding policy of matched parameters.
+    ///
+    /// Policy is used if path is decoded after matching.
+    pub fn set_decoding(&mut self, decoding: PathDecoding) {
+        self.decoding = Some(decoding);
+    }
+
     /// Parse path pattern and create new `Pattern` instance with custom prefix
     fn with_prefix(path: &str, for_prefix: bool) -> Self {
         let path = path.to_owned();
@@ -132,6 +146,7 @@ impl ResourceDef {
             id: 0,
             name: String::new(),
             pattern: path,
+            decoding: None,
         }
     }
 
@@ -150,6 +165,40 @@ impl ResourceDef {
         &self.pattern
     }
 
+    /// Static path segments that every matching path starts with.
+    ///
+    /// Router uses segments as a key in the routing trie.
+    pub(crate) fn static_segments(&self) -> Vec<&str> {
+        let (literal, complete) = match self.tp {
+            PatternType::Static(ref s) => (s.as_str(), true),
+            PatternType::Prefix(ref s) => (s.as_str(), !s.is_empty() && !s.ends_with('/')),
+            PatternType::Dynamic(..) => {
+                let literal = match self.pattern.find('{') {
+                    Some(idx) => &self.pattern[..idx],
+                    None => {
+                        // `path*` pattern, prefix is used as regex
+                        let literal = self.pattern.trim_end_matches('*');
+                        if literal.chars().any(|c| {
+                            !(c.is_alphanumeric() || c == '/' || c == '-' || c == '_')
+                        }) {
+                            return Vec::new();
+                        }
+                        literal
+                    }
+                };
+                (literal, false)
+            }
+            PatternType::DynamicSet(..) => return Vec::new(),
+        };
+
+        let mut segments: Vec<_> = path_segments(literal).collect();
+        if !complete {
+            // last segment could be continued
+            segments.pop();
+        }
+        segments
+    }
+
     #[inline]
     /// Check if path matchs this pattern?
     pub fn is_match(&self, path: &str) -> bool {
@@ -290,7 +339,7 @@ impl ResourceDef {
                     return false;
                 }
                 for idx in 0..idx {
-                    path.add(names[idx].clone(), segments[idx]);
+                    path.add(names[idx].clone(), segments[idx], self.decoding.as_ref());
                 }
                 path.skip((pos + len) as u16);
                 true
@@ -322,7 +371,7 @@ impl ResourceDef {
                         return false;
                     }
                     for idx in 0..idx {
-                        path.add(names[idx].clone(), segments[idx]);
+                        path.add(names[idx].clone(), segments[idx], self.decoding.as_ref());
                     }
                     path.skip((pos + len) as u16);
                     true
@@ -409,7 +458,7 @@ impl ResourceDef {
 
                 let path = res.resource_path();
                 for idx in 0..idx {
-                    path.add(names[idx].clone(), segments[idx]);
+                    path.add(names[idx].clone(), segments[idx], self.decoding.as_ref());
                 }
                 path.skip((pos + len) as u16);
                 true
@@ -448,7 +497,7 @@ impl ResourceDef {
 
                     let path = res.resource_path();
                     for idx in 0..idx {
-                        path.add(names[idx].clone(), segments[idx]);
+                        path.add(names[idx].clone(), segments[idx], self.decoding.as_ref());
                     }
                     path.skip((pos + len) as u16);
                     true
@@ -617,6 +666,11 @@ impl From<String> for ResourceDef {
     }
 }
 
+/// Split path to segments, leading slashes are ignored
+pub(crate) fn path_segments(path: &str) -> impl Iterator<Item = &str> {
+    path.trim_start_matches('/').split('/')
+}
+
 pub(crate) fn insert_slash(path: &str) -> String {
     let mut path = path.to_owned();
     if !path.is_empty() && !path.starts_with('/') {
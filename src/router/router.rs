@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+
+use crate::router::resource::path_segments;
 use crate::router::{IntoPattern, Resource, ResourceDef, ResourcePath};
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -10,7 +13,52 @@ pub struct ResourceInfo {
 }
 
 /// Resource router.
-pub struct Router<T, U = ()>(Vec<(ResourceDef, T, Option<U>)>);
+///
+/// Resources are indexed with a trie keyed on static path segments,
+/// resource is stored in the node of the static segments its pattern starts
+/// with. Recognition walks the trie along the path and checks only
+/// resources found on the way, in registration order, so matching cost
+/// depends on the path length and not on the number of resources. First
+/// matching resource wins, same as with linear scan.
+pub struct Router<T, U = ()> {
+    resources: Vec<(ResourceDef, T, Option<U>)>,
+    index: Node,
+}
+
+#[derive(Default)]
+struct Node {
+    resources: Vec<usize>,
+    children: HashMap<String, Node>,
+}
+
+impl Node {
+    fn insert(&mut self, segments: &[&str], idx: usize) {
+        match segments.split_first() {
+            Some((segment, rest)) => self
+                .children
+                .entry((*segment).to_owned())
+                .or_insert_with(Node::default)
+                .insert(rest, idx),
+            None => self.resources.push(idx),
+        }
+    }
+
+    /// Collect resources that could match the path
+    fn candidates(&self, path: &str, result: &mut Vec<usize>) {
+        result.extend_from_slice(&self.resources);
+
+        let mut node = self;
+        for segment in path_segments(path) {
+            match node.children.get(segment) {
+                Some(child) => {
+                    result.extend_from_slice(&child.resources);
+                    node = child;
+                }
+                None => break,
+            }
+        }
+    }
+}
 
 impl<T, U> Router<T, U> {
     pub fn build() -> RouterBuilder<T, U> {
@@ -19,17 +67,33 @@ impl<T, U> Router<T, U> {
         }
     }
 
+    /// Find index of the first matching resource
+    fn find<R, P, F>(&self, resource: &mut R, matches: F) -> Option<usize>
+    where
+        F: Fn(&(ResourceDef, T, Option<U>), &mut R) -> bool,
+        R: Resource<P>,
+        P: ResourcePath,
+    {
+        let mut candidates = Vec::new();
+        self.index
+            .candidates(resource.resource_path().path(), &mut candidates);
+        candidates.sort_unstable();
+
+        candidates
+            .into_iter()
+            .find(|idx| matches(&self.resources[*idx], resource))
+    }
+
     pub fn recognize<R, P>(&self, resource: &mut R) -> Option<(&T, ResourceId)>
     where
         R: Resource<P>,
         P: ResourcePath,
     {
-        for item in self.0.iter() {
-            if item.0.match_path(resource.resource_path()) {
-                return Some((&item.1, ResourceId(item.0.id())));
-            }
-        }
-        None
+        let idx = self.find(resource, |item, res| {
+            item.0.match_path(res.resource_path())
+        })?;
+        let item = &self.resources[idx];
+        Some((&item.1, ResourceId(item.0.id())))
     }
 
     pub fn recognize_mut<R, P>(&mut self, resource: &mut R) -> Option<(&mut T, ResourceId)>
@@ -37,12 +101,11 @@ impl<T, U> Router<T, U> {
         R: Resource<P>,
         P: ResourcePath,
     {
-        for item in self.0.iter_mut() {
-            if item.0.match_path(resource.resource_path()) {
-                return Some((&mut item.1, ResourceId(item.0.id())));
-            }
-        }
-        None
+        let idx = self.find(resource, |item, res| {
+            item.0.match_path(res.resource_path())
+        })?;
+        let item = &mut self.resources[idx];
+        Some((&mut item.1, ResourceId(item.0.id())))
     }
 
     pub fn recognize_mut_checked<R, P, F>(
@@ -55,12 +118,11 @@ impl<T, U> Router<T, U> {
         R: Resource<P>,
         P: ResourcePath,
     {
-        for item in self.0.iter_mut() {
-            if item.0.match_path_checked(resource, &check, &item.2) {
-                return Some((&mut item.1, ResourceId(item.0.id())));
-            }
-        }
-        None
+        let idx = self.find(resource, |item, res| {
+            item.0.match_path_checked(res, &check, &item.2)
+        })?;
+        let item = &mut self.resources[idx];
+        Some((&mut item.1, ResourceId(item.0.id())))
     }
 }
 
@@ -95,7 +157,14 @@ impl<T, U> RouterBuilder<T, U> {
 
     /// Finish configuration and create router instance.
     pub fn finish(self) -> Router<T, U> {
-        Router(self.resources)
+        let mut index = Node::default();
+        for (idx, item) in self.resources.iter().enumerate() {
+            index.insert(&item.0.static_segments(), idx);
+        }
+        Router {
+            index,
+            resources: self.resources,
+        }
     }
 }
 
@@ -237,4 +306,49 @@ mod tests {
         assert_eq!(*h, 11);
         assert_eq!(&path["val"], "ttt");
     }
+
+    #[test]
+    fn test_recognizer_registration_order() {
+        let mut router = Router::<usize>::build();
+        router.path("/{name}/index.html", 10);
+        router.path("/name/index.html", 11);
+        router.path(vec!["/set/{val}", "/other/{val}"], 12);
+        router.prefix("/files", 13);
+        router.path("/files/{name}", 14);
+        router.prefix("", 15);
+        let mut router = router.finish();
+
+        // first registered resource wins regardless of trie depth
+        let mut path = Path::new("/name/index.html");
+        let (h, _) = router.recognize_mut(&mut path).unwrap();
+        assert_eq!(*h, 10);
+
+        let mut path = Path::new("/other/value");
+        let (h, _) = router.recognize_mut(&mut path).unwrap();
+        assert_eq!(*h, 12);
+        assert_eq!(&path["val"], "value");
+
+        let mut path = Path::new("/files/name");
+        let (h, _) = router.recognize_mut(&mut path).unwrap();
+        assert_eq!(*h, 13);
+
+        let mut path = Path::new("/filesystem");
+        let (h, _) = router.recognize_mut(&mut path).unwrap();
+        assert_eq!(*h, 15);
+    }
+
+    #[test]
+    fn test_recognizer_checked() {
+        let mut router = Router::<usize, bool>::build();
+        router.path("/name", 10).2 = Some(false);
+        router.path("/{val}", 11);
+        router.path("/name", 12).2 = Some(true);
+        let mut router = router.finish();
+
+        let mut path = Path::new("/name");
+        let (h, _) = router
+            .recognize_mut_checked(&mut path, |_, allowed| allowed.unwrap_or(true))
+            .unwrap();
+        assert_eq!(*h, 11);
+    }
 }
@@ -1,3 +1,5 @@
+use derive_more::Display;
+
 use crate::router::ResourcePath;
 
 #[allow(dead_code)]
@@ -32,28 +34,209 @@ fn set_bit(array: &mut [u8], ch: u8) {
 
 thread_local! {
     static DEFAULT_QUOTER: Quoter = { Quoter::new(b"@:", b"/+") };
+    static SLASH_QUOTER: Quoter = { Quoter::new(b"@:/", b"+") };
+}
+
+/// Handling of percent-encoded slash (`%2F`) in request path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodedSlash {
+    /// Keep `%2F` encoded, it never acts as segment separator. This is default.
+    Keep,
+    /// Decode `%2F` to `/`
+    Decode,
+    /// Reject request that contains `%2F` in path
+    Reject,
+}
+
+/// Path decoding error
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Path contains encoded slash and policy rejects it
+    #[display(fmt = "Encoded slash is not allowed in path")]
+    EncodedSlash,
+    /// Decoded path is not valid utf-8
+    #[display(fmt = "Decoded path is not valid utf-8")]
+    InvalidUtf8,
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Percent-decoding policy of request path.
+///
+/// By default path is decoded before matching, `%2F` stays encoded and
+/// request with invalid utf-8 sequence in decoded path is rejected.
+///
+/// With `decode_after_match()` resources are matched against raw path
+/// and only matched parameter values are decoded, so encoded characters
+/// can not change which resource handles request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathDecoding {
+    after_match: bool,
+    encoded_slash: EncodedSlash,
+    reject_invalid_utf8: bool,
+}
+
+impl Default for PathDecoding {
+    fn default() -> Self {
+        PathDecoding {
+            after_match: false,
+            encoded_slash: EncodedSlash::Keep,
+            reject_invalid_utf8: true,
+        }
+    }
+}
+
+impl PathDecoding {
+    /// Create default decoding policy
+    pub fn new() -> Self {
+        PathDecoding::default()
+    }
+
+    /// Match resources against raw path and decode matched parameters.
+    pub fn decode_after_match(mut self) -> Self {
+        self.after_match = true;
+        self
+    }
+
+    /// Set handling of encoded slash.
+    pub fn encoded_slash(mut self, val: EncodedSlash) -> Self {
+        self.encoded_slash = val;
+        self
+    }
+
+    /// Reject invalid utf-8 sequences, otherwise they are replaced
+    /// with `U+FFFD`. Default is `true`.
+    pub fn reject_invalid_utf8(mut self, val: bool) -> Self {
+        self.reject_invalid_utf8 = val;
+        self
+    }
+
+    /// Check if path is decoded after matching
+    pub fn is_after_match(&self) -> bool {
+        self.after_match
+    }
+
+    /// Decode path, `Ok(None)` means path does not need decoding
+    fn decode_path(&self, path: &str) -> Result<Option<String>, DecodeError> {
+        if !path.contains('%') {
+            return Ok(None);
+        }
+        if self.encoded_slash == EncodedSlash::Reject && has_encoded_slash(path) {
+            return Err(DecodeError::EncodedSlash);
+        }
+        if self.after_match {
+            return Ok(None);
+        }
+        let data = if self.encoded_slash == EncodedSlash::Decode {
+            SLASH_QUOTER.with(|q| q.requote_bytes(path.as_bytes()))
+        } else {
+            DEFAULT_QUOTER.with(|q| q.requote_bytes(path.as_bytes()))
+        };
+        match data {
+            Some(data) => self.to_string(data).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Decode matched parameter value
+    pub(crate) fn decode_param(&self, value: &str) -> Result<Option<String>, DecodeError> {
+        if !value.contains('%') {
+            return Ok(None);
+        }
+        let data = match self.encoded_slash {
+            EncodedSlash::Keep => decode(value.as_bytes(), b"/"),
+            EncodedSlash::Decode => decode(value.as_bytes(), b""),
+            EncodedSlash::Reject => {
+                if has_encoded_slash(value) {
+                    return Err(DecodeError::EncodedSlash);
+                }
+                decode(value.as_bytes(), b"")
+            }
+        };
+        self.to_string(data).map(Some)
+    }
+
+    fn to_string(&self, data: Vec<u8>) -> Result<String, DecodeError> {
+        match String::from_utf8(data) {
+            Ok(s) => Ok(s),
+            Err(_) if self.reject_invalid_utf8 => Err(DecodeError::InvalidUtf8),
+            Err(e) => Ok(String::from_utf8_lossy(e.as_bytes()).into_owned()),
+        }
+    }
+}
+
+fn has_encoded_slash(path: &str) -> bool {
+    path.as_bytes()
+        .windows(3)
+        .any(|w| w[0] == b'%' && w[1] == b'2' && (w[2] == b'f' || w[2] == b'F'))
+}
+
+/// Decode all percent-encoded characters except `protected` ones
+fn decode(val: &[u8], protected: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(val.len());
+    let mut idx = 0;
+    while idx < val.len() {
+        if val[idx] == b'%' && idx + 2 < val.len() {
+            if let Some(ch) = restore_ch(val[idx + 1], val[idx + 2]) {
+                if !protected.contains(&ch) {
+                    buf.push(ch);
+                    idx += 3;
+                    continue;
+                }
+            }
+        }
+        buf.push(val[idx]);
+        idx += 1;
+    }
+    buf
 }
 
 #[derive(Default, Clone, Debug)]
 pub struct Url {
     uri: http::Uri,
     path: Option<String>,
+    decoding: PathDecoding,
+    error: Option<DecodeError>,
 }
 
 impl Url {
     pub fn new(uri: http::Uri) -> Url {
-        let path = DEFAULT_QUOTER.with(|q| q.requote(uri.path().as_bytes()));
+        Url::with_decoding(uri, PathDecoding::default())
+    }
 
-        Url { uri, path }
+    /// Create url and decode path with specified policy
+    pub fn with_decoding(uri: http::Uri, decoding: PathDecoding) -> Url {
+        let (path, error) = match decoding.decode_path(uri.path()) {
+            Ok(path) => (path, None),
+            Err(e) => (None, Some(e)),
+        };
+        Url {
+            uri,
+            path,
+            decoding,
+            error,
+        }
     }
 
     pub fn with_quoter(uri: http::Uri, quoter: &Quoter) -> Url {
         Url {
             path: quoter.requote(uri.path().as_bytes()),
             uri,
+            decoding: PathDecoding::default(),
+            error: None,
         }
     }
 
+    /// Path decoding policy
+    pub fn decoding(&self) -> &PathDecoding {
+        &self.decoding
+    }
+
+    /// Path decoding error, path is not decoded in this case
+    pub fn error(&self) -> Option<DecodeError> {
+        self.error
+    }
+
     pub fn uri(&self) -> &http::Uri {
         &self.uri
     }
@@ -69,13 +252,23 @@ impl Url {
     #[inline]
     pub fn update(&mut self, uri: &http::Uri) {
         self.uri = uri.clone();
-        self.path = DEFAULT_QUOTER.with(|q| q.requote(uri.path().as_bytes()));
+        match self.decoding.decode_path(uri.path()) {
+            Ok(path) => {
+                self.path = path;
+                self.error = None;
+            }
+            Err(e) => {
+                self.path = None;
+                self.error = Some(e);
+            }
+        }
     }
 
     #[inline]
     pub fn update_with_quoter(&mut self, uri: &http::Uri, quoter: &Quoter) {
         self.uri = uri.clone();
         self.path = quoter.requote(uri.path().as_bytes());
+        self.error = None;
     }
 }
 
@@ -84,6 +277,22 @@ impl ResourcePath for Url {
     fn path(&self) -> &str {
         self.path()
     }
+
+    fn decode_param(
+        &self,
+        value: &str,
+        decoding: Option<&PathDecoding>,
+    ) -> Result<Option<String>, DecodeError> {
+        if self.decoding.after_match {
+            decoding.unwrap_or(&self.decoding).decode_param(value)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn decode_error(&self) -> Option<DecodeError> {
+        self.error
+    }
 }
 
 pub struct Quoter {
@@ -122,6 +331,13 @@ impl Quoter {
     }
 
     pub fn requote(&self, val: &[u8]) -> Option<String> {
+        self.requote_bytes(val).map(|data| match String::from_utf8(data) {
+            Ok(s) => s,
+            Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
+        })
+    }
+
+    fn requote_bytes(&self, val: &[u8]) -> Option<Vec<u8>> {
         let mut has_pct = 0;
         let mut pct = [b'%', 0, 0];
         let mut idx = 0;
@@ -170,13 +386,7 @@ impl Quoter {
             idx += 1;
         }
 
-        if let Some(data) = cloned {
-            // Unsafe: we get data from http::Uri, which does utf-8 checks already
-            // this code only decodes valid pct encoded values
-            Some(unsafe { String::from_utf8_unchecked(data) })
-        } else {
-            None
-        }
+        cloned
     }
 }
 
@@ -186,7 +396,7 @@ fn from_hex(v: u8) -> Option<u8> {
         Some(v - 0x30) // ord('0') == 0x30
     } else if v >= b'A' && v <= b'F' {
         Some(v - 0x41 + 10) // ord('A') == 0x41
-    } else if v > b'a' && v <= b'f' {
+    } else if v >= b'a' && v <= b'f' {
         Some(v - 0x61 + 10) // ord('a') == 0x61
     } else {
         None
@@ -225,4 +435,81 @@ mod tests {
         assert!(re.match_path(&mut path));
         assert_eq!(path.get("id").unwrap(), "qwe%rty");
     }
+
+    #[test]
+    fn test_from_hex() {
+        assert_eq!(from_hex(b'a'), Some(10));
+        assert_eq!(from_hex(b'f'), Some(15));
+        assert_eq!(from_hex(b'A'), Some(10));
+        assert_eq!(from_hex(b'g'), None);
+    }
+
+    #[test]
+    fn test_encoded_slash() {
+        let re = ResourceDef::new("/user/{id}/test");
+        let uri = Uri::try_from("/user/a%2Fb/test").unwrap();
+
+        let mut path = Path::new(Url::new(uri.clone()));
+        assert!(re.match_path(&mut path));
+        assert_eq!(path.get("id").unwrap(), "a%2Fb");
+
+        let decoding = PathDecoding::new().encoded_slash(EncodedSlash::Decode);
+        let url = Url::with_decoding(uri.clone(), decoding);
+        assert_eq!(url.path(), "/user/a/b/test");
+        let mut path = Path::new(url);
+        assert!(!re.match_path(&mut path));
+
+        let decoding = PathDecoding::new().encoded_slash(EncodedSlash::Reject);
+        let url = Url::with_decoding(uri, decoding);
+        assert_eq!(url.error(), Some(DecodeError::EncodedSlash));
+        assert_eq!(url.path(), "/user/a%2Fb/test");
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let uri = Uri::try_from("/user/%FF/test").unwrap();
+        let url = Url::new(uri.clone());
+        assert_eq!(url.error(), Some(DecodeError::InvalidUtf8));
+        assert_eq!(url.path(), "/user/%FF/test");
+
+        let decoding = PathDecoding::new().reject_invalid_utf8(false);
+        let url = Url::with_decoding(uri, decoding);
+        assert_eq!(url.error(), None);
+        assert_eq!(url.path(), "/user/\u{FFFD}/test");
+    }
+
+    #[test]
+    fn test_decode_after_match() {
+        let re = ResourceDef::new("/user/{id}/test");
+        let decoding = PathDecoding::new().decode_after_match();
+
+        let uri = Uri::try_from("/user/qwe%25%2Frty/test").unwrap();
+        let mut path = Path::new(Url::with_decoding(uri.clone(), decoding));
+        assert_eq!(path.path(), "/user/qwe%25%2Frty/test");
+        assert!(re.match_path(&mut path));
+        assert_eq!(path.get("id").unwrap(), "qwe%%2Frty");
+        assert_eq!(&path[0], "qwe%%2Frty");
+        assert_eq!(path.decode_error(), None);
+
+        let mut re = ResourceDef::new("/user/{id}/test");
+        re.set_decoding(decoding.encoded_slash(EncodedSlash::Decode));
+        let mut path = Path::new(Url::with_decoding(uri.clone(), decoding));
+        assert!(re.match_path(&mut path));
+        assert_eq!(path.get("id").unwrap(), "qwe%/rty");
+        assert_eq!(path.iter().next(), Some(("id", "qwe%/rty")));
+
+        re.set_decoding(decoding.encoded_slash(EncodedSlash::Reject));
+        let mut path = Path::new(Url::with_decoding(uri, decoding));
+        assert!(re.match_path(&mut path));
+        assert_eq!(path.decode_error(), Some(DecodeError::EncodedSlash));
+
+        let uri = Uri::try_from("/user/%FF/test").unwrap();
+        let mut path = Path::new(Url::with_decoding(uri, decoding));
+        assert_eq!(path.decode_error(), None);
+        assert!(re.match_path(&mut path));
+        assert_eq!(path.decode_error(), Some(DecodeError::InvalidUtf8));
+
+        path.reset();
+        assert_eq!(path.decode_error(), None);
+    }
 }
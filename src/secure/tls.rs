@@ -1,10 +1,18 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
 use std::future::Future;
-use std::io;
+use std::io::{self, BufReader};
 use std::marker::PhantomData;
+use std::path::Path;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime};
+use arc_swap::ArcSwap;
+use futures_core::Stream;
 use futures_util::future::{ok, Ready};
+use futures_util::stream::StreamExt;
 
 pub mod rust_tls {
     pub use rust_tls::*;
@@ -17,6 +25,10 @@ pub use super::rustls::{ServerConfig, Session};
 pub use webpki_roots::TLS_SERVER_ROOTS;
 pub use crate::secure::inner::server::TlsStream;
 
+use super::rustls::internal::pemfile;
+use super::rustls::sign::{self, CertifiedKey};
+use super::rustls::{Certificate, PrivateKey, ResolvesServerCert, SignatureScheme, TLSError};
+use self::webpki::DNSNameRef;
 use crate::krse::io::{AsyncRead, AsyncWrite};
 use crate::service::{Service, ServiceFactory};
 use crate::krse::task::counter::{Counter, CounterGuard};
@@ -121,3 +133,219 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Future for AcceptorServiceFut<T> {
         }
     }
 }
+
+/// Server certificate resolver that selects certificate by SNI server name.
+///
+/// Host names are matched case-insensitive, `*.example.com` name matches
+/// any single label subdomain of `example.com`. Default certificate is
+/// used for unknown names and for clients that do not send SNI extension,
+/// handshake fails if default certificate is not set.
+#[derive(Clone, Default)]
+pub struct SniResolver {
+    hosts: HashMap<String, CertifiedKey>,
+    default: Option<CertifiedKey>,
+}
+
+impl SniResolver {
+    /// Create empty resolver
+    pub fn new() -> Self {
+        SniResolver::default()
+    }
+
+    /// Add certificate chain and private key for the host name
+    pub fn add(
+        &mut self,
+        name: &str,
+        certs: Vec<Certificate>,
+        key: &PrivateKey,
+    ) -> Result<(), TLSError> {
+        let key = certified_key(certs, key)?;
+        self.hosts.insert(name.to_lowercase(), key);
+        Ok(())
+    }
+
+    /// Set default certificate chain and private key
+    pub fn set_default(
+        &mut self,
+        certs: Vec<Certificate>,
+        key: &PrivateKey,
+    ) -> Result<(), TLSError> {
+        self.default = Some(certified_key(certs, key)?);
+        Ok(())
+    }
+
+    fn lookup(&self, name: &str) -> Option<&CertifiedKey> {
+        let name = name.to_lowercase();
+        self.hosts.get(&name).or_else(|| {
+            let idx = name.find('.')?;
+            self.hosts.get(&format!("*{}", &name[idx..]))
+        })
+    }
+}
+
+fn certified_key(certs: Vec<Certificate>, key: &PrivateKey) -> Result<CertifiedKey, TLSError> {
+    let key = sign::any_supported_type(key)
+        .map_err(|_| TLSError::General("Unsupported private key type".to_owned()))?;
+    Ok(CertifiedKey::new(certs, Arc::new(key)))
+}
+
+impl ResolvesServerCert for SniResolver {
+    fn resolve(
+        &self,
+        server_name: Option<DNSNameRef<'_>>,
+        _: &[SignatureScheme],
+    ) -> Option<CertifiedKey> {
+        server_name
+            .and_then(|name| self.lookup(name.into()))
+            .or_else(|| self.default.as_ref())
+            .cloned()
+    }
+}
+
+impl fmt::Debug for SniResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hosts: Vec<_> = self.hosts.keys().collect();
+        f.debug_struct("SniResolver")
+            .field("hosts", &hosts)
+            .field("default", &self.default.is_some())
+            .finish()
+    }
+}
+
+/// Server certificate resolver that could be replaced at runtime.
+///
+/// Resolver is a cheap to clone handle, new resolver is used by the
+/// following handshakes of all workers, established connections are not
+/// affected. Set handle as cert resolver of the server config.
+///
+/// ```rust,no_run
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use kayrx::secure::tls::{rust_tls, CertResolver};
+/// use kayrx::web::{App, HttpServer};
+///
+/// #[kayrx::main]
+/// async fn main() -> std::io::Result<()> {
+///     let resolver = CertResolver::from_files("cert.pem", "key.pem")?;
+///     // pick up renewed certificate
+///     resolver.watch_files("cert.pem", "key.pem", Duration::from_secs(60));
+///
+///     let mut config = rust_tls::ServerConfig::new(rust_tls::NoClientAuth::new());
+///     config.cert_resolver = Arc::new(resolver);
+///
+///     HttpServer::new(|| App::new())
+///         .bind_rustls("0.0.0.0:443", config)?
+///         .run()
+///         .await
+/// }
+/// ```
+#[derive(Clone)]
+pub struct CertResolver(Arc<ArcSwap<Box<dyn ResolvesServerCert>>>);
+
+impl CertResolver {
+    /// Create handle with initial resolver, i.e. `SniResolver`
+    pub fn new<R: ResolvesServerCert + 'static>(resolver: R) -> Self {
+        CertResolver(Arc::new(ArcSwap::from_pointee(Box::new(resolver))))
+    }
+
+    /// Create handle that serves certificate chain and private key from
+    /// pem files for all host names
+    pub fn from_files<P: AsRef<Path>>(cert: P, key: P) -> io::Result<Self> {
+        Ok(CertResolver::new(load_files(cert.as_ref(), key.as_ref())?))
+    }
+
+    /// Replace resolver
+    pub fn set<R: ResolvesServerCert + 'static>(&self, resolver: R) {
+        self.0.store(Arc::new(Box::new(resolver)));
+    }
+
+    /// Replace resolver with every resolver of the stream, i.e. `watch`
+    /// channel receiver.
+    ///
+    /// Stream is polled on the current arbiter until it ends.
+    pub fn watch<S, R>(&self, updates: S)
+    where
+        S: Stream<Item = R> + 'static,
+        R: ResolvesServerCert + 'static,
+    {
+        let this = self.clone();
+        crate::fiber::spawn(updates.for_each(move |resolver| {
+            this.set(resolver);
+            async {}
+        }));
+    }
+
+    /// Reload certificate chain and private key pem files when they are
+    /// modified.
+    ///
+    /// Files are checked every `interval` on the current arbiter, if files
+    /// could not be loaded current certificate stays in use.
+    pub fn watch_files<P: AsRef<Path>>(&self, cert: P, key: P, interval: Duration) {
+        let (cert, key) = (cert.as_ref().to_owned(), key.as_ref().to_owned());
+        let mut modified = files_modified(&cert, &key);
+        let updates = crate::timer::interval(interval).filter_map(move |_| {
+            let res = match files_modified(&cert, &key) {
+                Some(time) if Some(time) != modified => match load_files(&cert, &key) {
+                    Ok(resolver) => {
+                        log::info!("Certificate is reloaded from {:?}", cert);
+                        modified = Some(time);
+                        Some(resolver)
+                    }
+                    Err(e) => {
+                        log::warn!("Can not reload certificate from {:?}: {}", cert, e);
+                        None
+                    }
+                },
+                _ => None,
+            };
+            async move { res }
+        });
+        self.watch(updates);
+    }
+}
+
+impl ResolvesServerCert for CertResolver {
+    fn resolve(
+        &self,
+        server_name: Option<DNSNameRef<'_>>,
+        sigschemes: &[SignatureScheme],
+    ) -> Option<CertifiedKey> {
+        self.0.load().resolve(server_name, sigschemes)
+    }
+}
+
+impl fmt::Debug for CertResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CertResolver").finish()
+    }
+}
+
+/// Latest modification time of the files
+fn files_modified(cert: &Path, key: &Path) -> Option<SystemTime> {
+    let cert = fs::metadata(cert).and_then(|meta| meta.modified()).ok()?;
+    let key = fs::metadata(key).and_then(|meta| meta.modified()).ok()?;
+    Some(std::cmp::max(cert, key))
+}
+
+fn load_files(cert: &Path, key: &Path) -> io::Result<SniResolver> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+    let certs = pemfile::certs(&mut BufReader::new(File::open(cert)?))
+        .map_err(|_| invalid("Can not parse certificate"))?;
+    if certs.is_empty() {
+        return Err(invalid("Certificate is not found"));
+    }
+    let mut keys = pemfile::pkcs8_private_keys(&mut BufReader::new(File::open(key)?))
+        .map_err(|_| invalid("Can not parse private key"))?;
+    if keys.is_empty() {
+        keys = pemfile::rsa_private_keys(&mut BufReader::new(File::open(key)?))
+            .map_err(|_| invalid("Can not parse private key"))?;
+    }
+    let key = keys.pop().ok_or_else(|| invalid("Private key is not found"))?;
+
+    let mut resolver = SniResolver::new();
+    resolver
+        .set_default(certs, &key)
+        .map_err(|e| invalid(&e.to_string()))?;
+    Ok(resolver)
+}
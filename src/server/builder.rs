@@ -1,26 +1,34 @@
+use std::cell::{Cell, RefCell};
 use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::atomic::Ordering;
 use std::task::{Context, Poll};
 use std::time::Duration;
 use std::{io, mem, net};
 use futures_core::{Future, Stream};
 use futures_channel::mpsc::{unbounded, UnboundedReceiver};
 use futures_channel::oneshot;
-use futures_util::future::ready;
-use futures_util::stream::FuturesUnordered;
+use futures_util::future::{join, join_all, LocalBoxFuture, RemoteHandle};
+use futures_util::stream::{FuturesUnordered, LocalBoxStream};
 use futures_util::{ready, FutureExt, StreamExt};
 use log::{error, info};
 use net2::TcpBuilder;
 use num_cpus;
 
 use crate::krse::net::TcpStream;
-use crate::timer::{delay_until, Instant};
+use crate::timer::{delay_until, interval, timeout, Instant};
+use crate::fiber::diagnostics::{self, ServerInfo, SocketInfo};
 use crate::fiber::{spawn, System};
 use crate::server::accept::{AcceptLoop, AcceptNotify, Command};
 use crate::server::config::{ConfiguredService, ServiceConfig};
-use crate::server::server::{Server, ServerCommand};
+use crate::server::gate::{AcceptGate, ServerLoad};
+use crate::server::lifecycle::{self, Hook};
+use crate::server::server::{Server, ServerCommand, ShutdownSignal};
 use crate::server::service::{InternalServiceFactory, ServiceFactory, StreamNewService};
 use crate::server::signal::{Signal, Signals};
 use crate::server::socket::StdListener;
+use crate::server::stream::{StreamAccept, StreamWorkers};
+use crate::server::supervisor::Supervisor;
 use crate::server::worker::{self, Worker, WorkerAvailability, WorkerClient};
 use crate::server::Token;
 
@@ -32,13 +40,32 @@ pub struct ServerBuilder {
     workers: Vec<(usize, WorkerClient)>,
     services: Vec<Box<dyn InternalServiceFactory>>,
     sockets: Vec<(Token, StdListener)>,
+    streams: Vec<(Token, LocalBoxStream<'static, io::Result<net::TcpStream>>)>,
+    stream_workers: StreamWorkers,
+    stream_tasks: Vec<RemoteHandle<()>>,
+    paused: Rc<Cell<bool>>,
+    user_paused: bool,
+    overloaded: bool,
+    gate: Option<AcceptGate>,
+    gate_interval: Option<Duration>,
+    gate_task: Option<RemoteHandle<()>>,
     accept: AcceptLoop,
     exit: bool,
     shutdown_timeout: Duration,
+    supervisor: Option<Supervisor>,
     no_signals: bool,
     cmd: UnboundedReceiver<ServerCommand>,
     server: Server,
     notify: Vec<oneshot::Sender<()>>,
+    on_start: Vec<Hook>,
+    on_stop: Vec<Hook>,
+    starting: Option<LocalBoxFuture<'static, ()>>,
+    background: Vec<LocalBoxFuture<'static, ()>>,
+    background_timeout: Duration,
+    tasks: FuturesUnordered<RemoteHandle<()>>,
+    stopping: Option<oneshot::Sender<()>>,
+    banner: bool,
+    diagnostics: Option<usize>,
 }
 
 impl Default for ServerBuilder {
@@ -51,7 +78,8 @@ impl ServerBuilder {
     /// Create new Server builder instance
     pub fn new() -> ServerBuilder {
         let (tx, rx) = unbounded();
-        let server = Server::new(tx);
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let server = Server::new(tx, ShutdownSignal::new(stop_rx));
 
         ServerBuilder {
             threads: num_cpus::get(),
@@ -59,13 +87,32 @@ impl ServerBuilder {
             workers: Vec::new(),
             services: Vec::new(),
             sockets: Vec::new(),
+            streams: Vec::new(),
+            stream_workers: Rc::new(RefCell::new(Vec::new())),
+            stream_tasks: Vec::new(),
+            paused: Rc::new(Cell::new(false)),
+            user_paused: false,
+            overloaded: false,
+            gate: None,
+            gate_interval: None,
+            gate_task: None,
             accept: AcceptLoop::new(server.clone()),
             backlog: 2048,
             exit: false,
             shutdown_timeout: Duration::from_secs(30),
+            supervisor: None,
             no_signals: false,
             cmd: rx,
             notify: Vec::new(),
+            on_start: Vec::new(),
+            on_stop: Vec::new(),
+            starting: None,
+            background: Vec::new(),
+            background_timeout: Duration::from_secs(5),
+            tasks: FuturesUnordered::new(),
+            stopping: Some(stop_tx),
+            banner: false,
+            diagnostics: None,
             server,
         }
     }
@@ -105,13 +152,25 @@ impl ServerBuilder {
         self
     }
 
+    /// Pause accepting connections while server is overloaded.
+    ///
+    /// See [`AcceptGate`](struct.AcceptGate.html) for details.
+    pub fn accept_gate(mut self, gate: AcceptGate) -> Self {
+        self.gate_interval = Some(gate.get_interval());
+        self.gate = Some(gate);
+        self
+    }
+
     /// Stop fiber system.
     pub fn system_exit(mut self) -> Self {
         self.exit = true;
         self
     }
 
-    /// Disable signal handling
+    /// Disable signal handling.
+    ///
+    /// By default `SIGTERM` and `SIGINT` stop server gracefully, second
+    /// `SIGINT` and `SIGQUIT` stop server immediately.
     pub fn disable_signals(mut self) -> Self {
         self.no_signals = true;
         self
@@ -123,12 +182,89 @@ impl ServerBuilder {
     /// serving requests. Workers still alive after the timeout are force
     /// dropped.
     ///
+    /// During graceful shutdown listeners are closed, http/1 connections
+    /// finish in-flight requests with `Connection: close` and idle
+    /// keep-alive connections are closed immediately.
+    ///
     /// By default shutdown timeout sets to 30 seconds.
     pub fn shutdown_timeout(mut self, sec: u64) -> Self {
         self.shutdown_timeout = Duration::from_secs(sec);
         self
     }
 
+    /// Log diagnostics snapshot line on start.
+    ///
+    /// See [`Runtime::diagnostics()`](../fiber/struct.Runtime.html#method.diagnostics).
+    pub fn startup_banner(mut self) -> Self {
+        self.banner = true;
+        self
+    }
+
+    /// Enable worker supervision.
+    ///
+    /// Supervisor restarts worker services after too many panics or
+    /// readiness errors. By default worker services are restarted only on
+    /// readiness errors, one service at a time.
+    pub fn supervisor(mut self, supervisor: Supervisor) -> Self {
+        self.supervisor = Some(supervisor);
+        self
+    }
+
+    /// Register async function that is executed on server start.
+    ///
+    /// Start hooks are executed one by one in registration order, workers
+    /// start accepting connections after all hooks complete.
+    pub fn on_start<F, R>(mut self, f: F) -> Self
+    where
+        F: Fn() -> R + 'static,
+        R: Future<Output = ()> + 'static,
+    {
+        self.on_start.push(lifecycle::hook(f));
+        self
+    }
+
+    /// Register async function that is executed on server stop.
+    ///
+    /// Stop hooks are executed in reverse registration order after all
+    /// workers have been stopped.
+    pub fn on_stop<F, R>(mut self, f: F) -> Self
+    where
+        F: Fn() -> R + 'static,
+        R: Future<Output = ()> + 'static,
+    {
+        self.on_stop.push(lifecycle::hook(f));
+        self
+    }
+
+    /// Spawn background task attached to the server lifecycle.
+    ///
+    /// Task is spawned on the server's system thread after server start.
+    /// On graceful shutdown server waits for background tasks to complete,
+    /// tasks that are still running after drain timeout get cancelled.
+    /// Use [`shutdown_signal()`](#method.shutdown_signal) to stop tasks
+    /// gracefully. More tasks could be registered at runtime with
+    /// `Server::spawn_background()`.
+    pub fn spawn_background<F>(mut self, fut: F) -> Self
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        self.background.push(fut.boxed_local());
+        self
+    }
+
+    /// Timeout for background tasks completion in seconds.
+    ///
+    /// By default background tasks timeout sets to 5 seconds.
+    pub fn background_timeout(mut self, sec: u64) -> Self {
+        self.background_timeout = Duration::from_secs(sec);
+        self
+    }
+
+    /// Get future that resolves when server starts shutdown process
+    pub fn shutdown_signal(&self) -> ShutdownSignal {
+        self.server.shutdown_signal()
+    }
+
     /// Execute external configuration as part of the server building
     /// process.
     ///
@@ -245,6 +381,37 @@ impl ServerBuilder {
         Ok(self)
     }
 
+    /// Add new service that accepts connections from the stream.
+    ///
+    /// Connections could come from any custom acceptor, i.e. transparent
+    /// proxy socket, userspace tcp stack or test harness. Streams are
+    /// polled on the server's system thread and connections get
+    /// distributed between workers. Accepting stops when stream ends.
+    ///
+    /// Address is used as a local address of the service.
+    pub fn listen_stream<F, N, S>(
+        mut self,
+        name: N,
+        addr: net::SocketAddr,
+        stream: S,
+        factory: F,
+    ) -> io::Result<Self>
+    where
+        F: ServiceFactory<TcpStream>,
+        N: AsRef<str>,
+        S: Stream<Item = io::Result<net::TcpStream>> + 'static,
+    {
+        let token = self.token.next();
+        self.services.push(StreamNewService::create(
+            name.as_ref().to_string(),
+            token,
+            factory,
+            addr,
+        ));
+        self.streams.push((token, stream.boxed_local()));
+        Ok(self)
+    }
+
     /// Spawn new thread and start listening for incoming connections.
     ///
     /// This method spawns new thread and starts new fiber system. Other than
@@ -260,26 +427,19 @@ impl ServerBuilder {
 
     /// Starts processing incoming connections and return server controller.
     pub fn start(mut self) -> Server {
-        if self.sockets.is_empty() {
+        if self.sockets.is_empty() && self.streams.is_empty() {
             panic!("Server should have at least one bound socket");
         } else {
-            info!("Starting {} workers", self.threads);
-
-            // start workers
-            let mut workers = Vec::new();
-            for idx in 0..self.threads {
-                let worker = self.start_worker(idx, self.accept.get_notify());
-                workers.push(worker.clone());
-                self.workers.push((idx, worker));
+            if self.on_start.is_empty() {
+                self.start_workers();
+            } else {
+                // workers get started after start hooks
+                let hooks = mem::replace(&mut self.on_start, Vec::new());
+                self.starting = Some(
+                    async move { lifecycle::run_hooks(hooks.iter()).await }.boxed_local(),
+                );
             }
 
-            // start accept thread
-            for sock in &self.sockets {
-                info!("Starting server on {}", sock.1);
-            }
-            self.accept
-                .start(mem::replace(&mut self.sockets, Vec::new()), workers);
-
             // handle signals
             if !self.no_signals {
                 Signals::start(self.server.clone()).unwrap();
@@ -292,36 +452,166 @@ impl ServerBuilder {
         }
     }
 
+    fn register_diagnostics(&mut self) {
+        let info = ServerInfo {
+            workers: self.threads,
+            backlog: self.backlog,
+            max_connections: worker::max_connections(),
+            max_tls_handshakes: crate::secure::MAX_CONN.load(Ordering::Relaxed),
+            shutdown_timeout: self.shutdown_timeout,
+            sockets: self
+                .sockets
+                .iter()
+                .map(|sock| SocketInfo {
+                    addr: sock.1.local_addr().to_string(),
+                    tls: None,
+                })
+                .collect(),
+        };
+        self.diagnostics = Some(diagnostics::register_server(info));
+        if self.banner {
+            info!("{}", diagnostics::snapshot());
+        }
+    }
+
+    fn start_workers(&mut self) {
+        info!("Starting {} workers", self.threads);
+
+        // start workers
+        let mut workers = Vec::new();
+        for idx in 0..self.threads {
+            let worker = self.start_worker(idx, self.accept.get_notify());
+            workers.push(worker.clone());
+            self.workers.push((idx, worker));
+        }
+
+        // start accept thread
+        for sock in &self.sockets {
+            info!("Starting server on {}", sock.1);
+        }
+        self.register_diagnostics();
+        *self.stream_workers.borrow_mut() = workers.clone();
+        self.accept
+            .start(mem::replace(&mut self.sockets, Vec::new()), workers);
+
+        // start accept gate
+        if let Some(gate) = self.gate.take() {
+            let workers = self.stream_workers.clone();
+            let server = self.server.clone();
+            let (remote, handle) = async move {
+                let mut interval = interval(gate.get_interval());
+                let mut overloaded = false;
+                loop {
+                    interval.tick().await;
+                    let load = ServerLoad::collect(&workers.borrow());
+                    if gate.is_overloaded(&load) != overloaded {
+                        overloaded = !overloaded;
+                        server.overloaded(overloaded);
+                    }
+                }
+            }
+            .remote_handle();
+            spawn(remote);
+            self.gate_task = Some(handle);
+        }
+
+        // start stream acceptors
+        for (token, stream) in mem::replace(&mut self.streams, Vec::new()) {
+            let accept = StreamAccept::new(
+                token,
+                stream,
+                self.stream_workers.clone(),
+                self.paused.clone(),
+                self.server.clone(),
+            );
+            let (remote, handle) = accept.remote_handle();
+            spawn(remote);
+            self.stream_tasks.push(handle);
+        }
+
+        // start background tasks
+        for fut in mem::replace(&mut self.background, Vec::new()) {
+            self.spawn_task(fut);
+        }
+    }
+
+    fn spawn_task<F>(&mut self, fut: F)
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        // dropped handle cancels the task
+        let (remote, handle) = fut.remote_handle();
+        spawn(remote);
+        self.tasks.push(handle);
+    }
+
     fn start_worker(&self, idx: usize, notify: AcceptNotify) -> WorkerClient {
         let avail = WorkerAvailability::new(notify);
         let services: Vec<Box<dyn InternalServiceFactory>> =
             self.services.iter().map(|v| v.clone_factory()).collect();
 
-        Worker::start(idx, services, avail, self.shutdown_timeout)
+        Worker::start(
+            idx,
+            services,
+            avail,
+            self.shutdown_timeout,
+            self.supervisor.clone(),
+            self.gate_interval,
+        )
+    }
+
+    /// Accept connections only if server is not paused and not overloaded
+    fn update_accept(&mut self) {
+        let paused = self.user_paused || self.overloaded;
+        if paused != self.paused.get() {
+            self.paused.set(paused);
+            self.accept
+                .send(if paused { Command::Pause } else { Command::Resume });
+        }
     }
 
     fn handle_cmd(&mut self, item: ServerCommand) {
         match item {
             ServerCommand::Pause(tx) => {
-                self.accept.send(Command::Pause);
+                self.user_paused = true;
+                self.update_accept();
                 let _ = tx.send(());
             }
             ServerCommand::Resume(tx) => {
-                self.accept.send(Command::Resume);
+                self.user_paused = false;
+                self.update_accept();
                 let _ = tx.send(());
             }
+            ServerCommand::Overloaded(overloaded) => {
+                if overloaded {
+                    info!("Server is overloaded, pausing accept");
+                } else {
+                    info!("Server load is back to normal, resuming accept");
+                }
+                self.overloaded = overloaded;
+                self.update_accept();
+            }
             ServerCommand::Signal(sig) => {
                 // Signals support
-                // Handle `SIGINT`, `SIGTERM`, `SIGQUIT` signals and stop fiber system
+                // Handle `SIGINT`, `SIGTERM`, `SIGQUIT` signals and stop fiber system.
+                // `SIGINT` drains connections, second `SIGINT` forces shutdown
                 match sig {
-                    Signal::Int => {
-                        info!("SIGINT received, exiting");
+                    Signal::Int if self.stopping.is_none() => {
+                        info!("SIGINT received during shutdown, exiting");
                         self.exit = true;
                         self.handle_cmd(ServerCommand::Stop {
                             graceful: false,
                             completion: None,
                         })
                     }
+                    Signal::Int => {
+                        info!("SIGINT received, stopping");
+                        self.exit = true;
+                        self.handle_cmd(ServerCommand::Stop {
+                            graceful: true,
+                            completion: None,
+                        })
+                    }
                     Signal::Term => {
                         info!("SIGTERM received, stopping");
                         self.exit = true;
@@ -341,6 +631,9 @@ impl ServerBuilder {
                     _ => (),
                 }
             }
+            ServerCommand::Background(task) => {
+                self.spawn_task(task.0);
+            }
             ServerCommand::Notify(tx) => {
                 self.notify.push(tx);
             }
@@ -350,59 +643,58 @@ impl ServerBuilder {
             } => {
                 let exit = self.exit;
 
-                // stop accept thread
+                // server is not started yet
+                self.starting = None;
+
+                // notify background tasks
+                if let Some(tx) = self.stopping.take() {
+                    let _ = tx.send(());
+                }
+
+                if let Some(id) = self.diagnostics.take() {
+                    diagnostics::unregister_server(id);
+                }
+
+                // stop accept thread and stream acceptors
                 self.accept.send(Command::Stop);
+                self.stream_tasks.clear();
+                self.gate_task = None;
                 let notify = std::mem::replace(&mut self.notify, Vec::new());
-
-                // stop workers
-                if !self.workers.is_empty() && graceful {
-                    spawn(
-                        self.workers
-                            .iter()
-                            .map(move |worker| worker.1.stop(graceful))
-                            .collect::<FuturesUnordered<_>>()
-                            .collect::<Vec<_>>()
-                            .then(move |_| {
-                                if let Some(tx) = completion {
-                                    let _ = tx.send(());
-                                }
-                                for tx in notify {
-                                    let _ = tx.send(());
-                                }
-                                if exit {
-                                    spawn(
-                                        async {
-                                            delay_until(
-                                                Instant::now() + Duration::from_millis(300),
-                                            )
-                                            .await;
-                                            System::current().stop();
-                                        }
-                                        .boxed(),
-                                    );
-                                }
-                                ready(())
-                            }),
-                    )
+                let on_stop = std::mem::replace(&mut self.on_stop, Vec::new());
+
+                // stop workers and wait for background tasks, on timeout
+                // dropped task handles cancel remaining tasks
+                let (workers, tasks) = if graceful {
+                    let workers: Vec<_> = self
+                        .workers
+                        .iter()
+                        .map(move |worker| worker.1.stop(graceful))
+                        .collect();
+                    let tasks = mem::replace(&mut self.tasks, FuturesUnordered::new());
+                    (workers, tasks)
                 } else {
-                    // we need to stop system if server was spawned
-                    if self.exit {
-                        spawn(
-                            delay_until(Instant::now() + Duration::from_millis(300)).then(
-                                |_| {
-                                    System::current().stop();
-                                    ready(())
-                                },
-                            ),
-                        );
-                    }
+                    self.tasks = FuturesUnordered::new();
+                    (Vec::new(), FuturesUnordered::new())
+                };
+                let drain = timeout(self.background_timeout, tasks.collect::<Vec<_>>());
+
+                spawn(async move {
+                    let _ = join(join_all(workers), drain).await;
+
+                    lifecycle::run_hooks(on_stop.iter().rev()).await;
                     if let Some(tx) = completion {
                         let _ = tx.send(());
                     }
                     for tx in notify {
                         let _ = tx.send(());
                     }
-                }
+
+                    // we need to stop system if server was spawned
+                    if exit {
+                        delay_until(Instant::now() + Duration::from_millis(300)).await;
+                        System::current().stop();
+                    }
+                });
             }
             ServerCommand::WorkerFaulted(idx) => {
                 let mut found = false;
@@ -430,6 +722,11 @@ impl ServerBuilder {
 
                     let worker = self.start_worker(new_idx, self.accept.get_notify());
                     self.workers.push((new_idx, worker.clone()));
+                    {
+                        let mut workers = self.stream_workers.borrow_mut();
+                        workers.retain(|worker| worker.idx != idx);
+                        workers.push(worker.clone());
+                    }
                     self.accept.send(Command::Worker(worker));
                 }
             }
@@ -441,6 +738,16 @@ impl Future for ServerBuilder {
     type Output = ();
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(ref mut fut) = self.starting {
+            if fut.as_mut().poll(cx).is_ready() {
+                self.starting = None;
+                self.start_workers();
+            }
+        }
+
+        // cleanup completed background tasks
+        while let Poll::Ready(Some(_)) = Pin::new(&mut self.tasks).poll_next(cx) {}
+
         loop {
             match ready!(Pin::new(&mut self.cmd).poll_next(cx)) {
                 Some(it) => self.as_mut().get_mut().handle_cmd(it),
@@ -0,0 +1,167 @@
+use std::cell::Cell;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crate::server::worker::WorkerClient;
+
+/// Load counters of the worker, shared between worker and server threads
+#[derive(Default)]
+pub(crate) struct WorkerLoad {
+    connections: AtomicUsize,
+    pending: AtomicUsize,
+}
+
+impl WorkerLoad {
+    /// Connection is sent to the worker
+    pub(crate) fn queued(&self) {
+        self.pending.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Worker started connection processing or dropped it
+    pub(crate) fn dequeued(&self) {
+        self.pending.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Store sampled number of connections
+    pub(crate) fn set_connections(&self, num: usize) {
+        self.connections.store(num, Ordering::Relaxed);
+    }
+}
+
+/// Snapshot of the server load
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ServerLoad {
+    connections: usize,
+    pending: usize,
+    workers: usize,
+}
+
+impl ServerLoad {
+    pub(crate) fn collect(workers: &[WorkerClient]) -> ServerLoad {
+        workers.iter().fold(
+            ServerLoad {
+                workers: workers.len(),
+                ..Default::default()
+            },
+            |mut load, worker| {
+                let wload = worker.load();
+                load.connections += wload.connections.load(Ordering::Relaxed);
+                load.pending += wload.pending.load(Ordering::Relaxed);
+                load
+            },
+        )
+    }
+
+    /// Number of connections processed by all workers
+    pub fn connections(&self) -> usize {
+        self.connections
+    }
+
+    /// Number of accepted connections waiting in workers queues
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+
+    /// Number of running workers
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+}
+
+/// Readiness-gated accept.
+///
+/// Gate checks server load periodically and pauses accept loops while
+/// server is overloaded. Connections that could not be served timely stay
+/// in the listener backlog (or get routed to another instance by load
+/// balancer) instead of being accepted and queued by busy workers.
+/// Already established connections are not affected.
+///
+/// Gate state is independent from `Server::pause()`, server accepts
+/// connections only if it is not paused and not overloaded.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use kayrx::server::AcceptGate;
+///
+/// // stop accepting at 10k connections, resume below 8k
+/// let gate = AcceptGate::connections(10_000, 8_000)
+///     .interval(Duration::from_millis(50));
+///
+/// let builder = kayrx::server::new().accept_gate(gate);
+/// ```
+pub struct AcceptGate {
+    check: Box<dyn Fn(&ServerLoad) -> bool>,
+    interval: Duration,
+}
+
+impl AcceptGate {
+    /// Create gate with custom load signal.
+    ///
+    /// Function is called on the server's system thread with current load
+    /// and returns `true` if server is overloaded. Function could also use
+    /// external signals, i.e. queue depth of a downstream service.
+    pub fn new<F>(check: F) -> Self
+    where
+        F: Fn(&ServerLoad) -> bool + 'static,
+    {
+        AcceptGate {
+            check: Box::new(check),
+            interval: Duration::from_millis(100),
+        }
+    }
+
+    /// Pause accept when number of in-flight connections reaches `high`,
+    /// resume when it drops to `low`.
+    pub fn connections(high: usize, low: usize) -> Self {
+        AcceptGate::watermarks(high, low, |load| load.connections())
+    }
+
+    /// Pause accept when number of connections waiting in workers queues
+    /// reaches `high`, resume when it drops to `low`.
+    pub fn pending(high: usize, low: usize) -> Self {
+        AcceptGate::watermarks(high, low, |load| load.pending())
+    }
+
+    fn watermarks<F>(high: usize, low: usize, value: F) -> Self
+    where
+        F: Fn(&ServerLoad) -> usize + 'static,
+    {
+        let low = std::cmp::min(low, high);
+        let overloaded = Cell::new(false);
+
+        AcceptGate::new(move |load| {
+            let value = value(load);
+            if value >= high {
+                overloaded.set(true);
+            } else if value <= low {
+                overloaded.set(false);
+            }
+            overloaded.get()
+        })
+    }
+
+    /// Set load check interval, by default 100 milliseconds.
+    ///
+    /// Connections count is sampled by workers with the same interval.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub(crate) fn get_interval(&self) -> Duration {
+        self.interval
+    }
+
+    pub(crate) fn is_overloaded(&self, load: &ServerLoad) -> bool {
+        (self.check)(load)
+    }
+}
+
+impl fmt::Debug for AcceptGate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AcceptGate")
+            .field("interval", &self.interval)
+            .finish()
+    }
+}
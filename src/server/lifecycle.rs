@@ -0,0 +1,128 @@
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::rc::{Rc, Weak};
+use std::task::{Context, Waker};
+
+use futures_util::future::{FutureExt, LocalBoxFuture};
+
+/// Lifecycle hook, async function executed on start or stop
+pub(crate) type Hook = Box<dyn Fn() -> LocalBoxFuture<'static, ()>>;
+
+/// Worker stop hook
+pub(crate) type StopHook = Box<dyn FnOnce() -> LocalBoxFuture<'static, ()>>;
+
+thread_local! {
+    static STOP_HOOKS: RefCell<Vec<(usize, StopHook)>> = RefCell::new(Vec::new());
+    static NEXT_ID: Cell<usize> = Cell::new(0);
+    static DRAINING: Cell<bool> = Cell::new(false);
+    static DRAIN_LISTENERS: RefCell<Vec<Weak<Cell<Option<Waker>>>>> = RefCell::new(Vec::new());
+}
+
+/// Convert async function to a lifecycle hook
+pub(crate) fn hook<F, R>(f: F) -> Hook
+where
+    F: Fn() -> R + 'static,
+    R: Future<Output = ()> + 'static,
+{
+    Box::new(move || f().boxed_local())
+}
+
+/// Execute hooks one by one in the specified order
+pub(crate) async fn run_hooks<'a, I>(hooks: I)
+where
+    I: IntoIterator<Item = &'a Hook>,
+{
+    for hook in hooks {
+        hook().await;
+    }
+}
+
+/// Register hook that is executed on graceful shutdown of the current worker.
+///
+/// Returns hook id that could be used for hook removal.
+pub(crate) fn register_stop_hook(hook: StopHook) -> usize {
+    let id = NEXT_ID.with(|id| {
+        let next = id.get();
+        id.set(next.wrapping_add(1));
+        next
+    });
+    STOP_HOOKS.with(|hooks| hooks.borrow_mut().push((id, hook)));
+    id
+}
+
+/// Remove registered stop hook
+pub(crate) fn remove_stop_hook(id: usize) -> Option<StopHook> {
+    STOP_HOOKS.with(|hooks| {
+        let mut hooks = hooks.borrow_mut();
+        hooks
+            .iter()
+            .position(|item| item.0 == id)
+            .map(|idx| hooks.remove(idx).1)
+    })
+}
+
+/// Take all stop hooks of the current worker.
+///
+/// Returned future executes hooks in reverse registration order.
+pub(crate) fn take_stop_hooks() -> LocalBoxFuture<'static, ()> {
+    let hooks =
+        STOP_HOOKS.with(|hooks| std::mem::replace(&mut *hooks.borrow_mut(), Vec::new()));
+    async move {
+        for (_, hook) in hooks.into_iter().rev() {
+            hook().await;
+        }
+    }
+    .boxed_local()
+}
+
+/// Start draining of the current worker.
+///
+/// Worker is draining during graceful shutdown, connections finish
+/// in-flight requests and do not accept new ones.
+pub(crate) fn start_draining() {
+    DRAINING.with(|draining| draining.set(true));
+    let listeners =
+        DRAIN_LISTENERS.with(|l| std::mem::replace(&mut *l.borrow_mut(), Vec::new()));
+    for listener in listeners {
+        if let Some(waker) = listener.upgrade().and_then(|w| w.take()) {
+            waker.wake();
+        }
+    }
+}
+
+/// Check if the current worker is draining
+pub(crate) fn is_draining() -> bool {
+    DRAINING.with(|draining| draining.get())
+}
+
+/// Notification about start of worker draining
+pub(crate) struct DrainListener(Rc<Cell<Option<Waker>>>);
+
+impl DrainListener {
+    pub(crate) fn new() -> Self {
+        let waker = Rc::new(Cell::new(None));
+        DRAIN_LISTENERS.with(|listeners| {
+            let mut listeners = listeners.borrow_mut();
+            // remove listeners of closed connections
+            if listeners.len() == listeners.capacity() {
+                listeners.retain(|l| l.strong_count() > 0);
+            }
+            listeners.push(Rc::downgrade(&waker));
+        });
+        DrainListener(waker)
+    }
+
+    /// Returns `true` if worker is draining, otherwise registers
+    /// current task for wake up.
+    pub(crate) fn poll_drain(&self, cx: &mut Context<'_>) -> bool {
+        if is_draining() {
+            true
+        } else {
+            match self.0.take() {
+                Some(waker) if waker.will_wake(cx.waker()) => self.0.set(Some(waker)),
+                _ => self.0.set(Some(cx.waker().clone())),
+            }
+            false
+        }
+    }
+}
@@ -3,16 +3,22 @@
 mod accept;
 mod builder;
 mod config;
+mod gate;
+pub(crate) mod lifecycle;
 mod server;
 mod service;
 mod signal;
 mod socket;
+mod stream;
+mod supervisor;
 mod worker;
 
 pub use self::builder::ServerBuilder;
 pub use self::config::{ServiceConfig, ServiceRuntime};
-pub use self::server::Server;
+pub use self::gate::{AcceptGate, ServerLoad};
+pub use self::server::{Server, ShutdownSignal};
 pub use self::service::ServiceFactory;
+pub use self::supervisor::{Supervisor, WorkerRestart};
 
 #[doc(hidden)]
 pub use self::socket::FromStream;
@@ -1,10 +1,11 @@
 use std::future::Future;
-use std::io;
+use std::{fmt, io};
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
 use futures_channel::mpsc::UnboundedSender;
 use futures_channel::oneshot;
+use futures_util::future::{BoxFuture, Shared};
 use futures_util::FutureExt;
 
 use crate::server::builder::ServerBuilder;
@@ -13,8 +14,11 @@ use crate::server::signal::Signal;
 #[derive(Debug)]
 pub(crate) enum ServerCommand {
     WorkerFaulted(usize),
+    Background(BackgroundTask),
     Pause(oneshot::Sender<()>),
     Resume(oneshot::Sender<()>),
+    /// Accept gate state changed
+    Overloaded(bool),
     Signal(Signal),
     /// Whether to try and shut down gracefully
     Stop {
@@ -25,15 +29,50 @@ pub(crate) enum ServerCommand {
     Notify(oneshot::Sender<()>),
 }
 
+pub(crate) struct BackgroundTask(pub(crate) BoxFuture<'static, ()>);
+
+impl fmt::Debug for BackgroundTask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BackgroundTask").finish()
+    }
+}
+
+/// Future that resolves when server starts shutdown process.
+///
+/// Background tasks could use it to stop gracefully.
+#[derive(Clone)]
+pub struct ShutdownSignal(Shared<oneshot::Receiver<()>>);
+
+impl ShutdownSignal {
+    pub(crate) fn new(rx: oneshot::Receiver<()>) -> Self {
+        ShutdownSignal(rx.shared())
+    }
+}
+
+impl fmt::Debug for ShutdownSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShutdownSignal").finish()
+    }
+}
+
+impl Future for ShutdownSignal {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.0).poll(cx).map(|_| ())
+    }
+}
+
 #[derive(Debug)]
 pub struct Server(
     UnboundedSender<ServerCommand>,
     Option<oneshot::Receiver<()>>,
+    ShutdownSignal,
 );
 
 impl Server {
-    pub(crate) fn new(tx: UnboundedSender<ServerCommand>) -> Self {
-        Server(tx, None)
+    pub(crate) fn new(tx: UnboundedSender<ServerCommand>, signal: ShutdownSignal) -> Self {
+        Server(tx, None, signal)
     }
 
     /// Start server building process
@@ -49,6 +88,28 @@ impl Server {
         let _ = self.0.unbounded_send(ServerCommand::WorkerFaulted(idx));
     }
 
+    pub(crate) fn overloaded(&self, overloaded: bool) {
+        let _ = self.0.unbounded_send(ServerCommand::Overloaded(overloaded));
+    }
+
+    /// Spawn background task on the server's system thread.
+    ///
+    /// On graceful shutdown server waits for background tasks to complete,
+    /// tasks that are still running after drain timeout get cancelled.
+    pub fn spawn_background<F>(&self, fut: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let _ = self
+            .0
+            .unbounded_send(ServerCommand::Background(BackgroundTask(fut.boxed())));
+    }
+
+    /// Get future that resolves when server starts shutdown process
+    pub fn shutdown_signal(&self) -> ShutdownSignal {
+        self.2.clone()
+    }
+
     /// Pause accepting incoming connections
     ///
     /// If socket contains some pending connection, they might be dropped.
@@ -81,7 +142,7 @@ impl Server {
 
 impl Clone for Server {
     fn clone(&self) -> Self {
-        Self(self.0.clone(), None)
+        Self(self.0.clone(), None, self.2.clone())
     }
 }
 
@@ -1,4 +1,5 @@
 use std::marker::PhantomData;
+use std::panic::AssertUnwindSafe;
 use std::net::SocketAddr;
 use std::task::{Context, Poll};
 use std::time::Duration;
@@ -10,6 +11,7 @@ use super::Token;
 use crate::fiber::spawn;
 use crate::service::{self as kayrx, Service, ServiceFactory as KayrxServiceFactory};
 use crate::server::socket::{FromStream, StdStream};
+use crate::server::supervisor;
 use crate::krse::task::counter::CounterGuard;
 
 /// Server message
@@ -79,9 +81,12 @@ where
                 });
 
                 if let Ok(stream) = stream {
-                    let f = self.service.call(stream);
+                    let f = AssertUnwindSafe(self.service.call(stream));
                     spawn(async move {
-                        let _ = f.await;
+                        if f.catch_unwind().await.is_err() {
+                            error!("Connection processing panicked");
+                            supervisor::failed();
+                        }
                         drop(guard);
                     });
                     ok(())
@@ -0,0 +1,150 @@
+use std::cell::{Cell, RefCell};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use std::{io, net};
+
+use futures_core::{Future, Stream};
+use futures_util::stream::LocalBoxStream;
+use log::{error, info};
+
+use crate::server::server::Server;
+use crate::server::socket::{SocketAddr, StdStream};
+use crate::server::worker::{Conn, WorkerClient};
+use crate::server::Token;
+use crate::timer::{delay_for, Delay};
+
+/// Workers shared by all stream acceptors
+pub(crate) type StreamWorkers = Rc<RefCell<Vec<WorkerClient>>>;
+
+/// Accept connections from external stream.
+///
+/// Connections are distributed between available workers the same way as
+/// connections accepted by the accept loop. Acceptor stops polling the
+/// stream while all workers are busy or while server is paused.
+pub(crate) struct StreamAccept {
+    token: Token,
+    stream: LocalBoxStream<'static, io::Result<net::TcpStream>>,
+    workers: StreamWorkers,
+    paused: Rc<Cell<bool>>,
+    srv: Server,
+    next: usize,
+    delay: Option<Delay>,
+}
+
+impl StreamAccept {
+    pub(crate) fn new(
+        token: Token,
+        stream: LocalBoxStream<'static, io::Result<net::TcpStream>>,
+        workers: StreamWorkers,
+        paused: Rc<Cell<bool>>,
+        srv: Server,
+    ) -> Self {
+        StreamAccept {
+            token,
+            stream,
+            workers,
+            paused,
+            srv,
+            next: 0,
+            delay: None,
+        }
+    }
+
+    /// Check if any worker could accept new connection
+    fn available(&self) -> bool {
+        self.workers.borrow().iter().any(|worker| worker.available())
+    }
+
+    fn accept_one(&mut self, mut msg: Conn) {
+        let mut workers = self.workers.borrow_mut();
+        let mut idx = 0;
+        while idx < workers.len() {
+            idx += 1;
+            if self.next >= workers.len() {
+                self.next = 0;
+            }
+            if workers[self.next].available() {
+                match workers[self.next].send(msg) {
+                    Ok(_) => {
+                        self.next = (self.next + 1) % workers.len();
+                        return;
+                    }
+                    Err(tmp) => {
+                        self.srv.worker_faulted(workers[self.next].idx);
+                        msg = tmp;
+                        workers.swap_remove(self.next);
+                        continue;
+                    }
+                }
+            }
+            self.next += 1;
+        }
+
+        // all workers are busy, connection goes to the next one
+        while !workers.is_empty() {
+            if self.next >= workers.len() {
+                self.next = 0;
+            }
+            match workers[self.next].send(msg) {
+                Ok(_) => {
+                    self.next = (self.next + 1) % workers.len();
+                    return;
+                }
+                Err(tmp) => {
+                    self.srv.worker_faulted(workers[self.next].idx);
+                    msg = tmp;
+                    workers.swap_remove(self.next);
+                }
+            }
+        }
+        error!("No workers");
+    }
+}
+
+impl Future for StreamAccept {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        loop {
+            if let Some(ref mut delay) = self.delay {
+                if Pin::new(delay).poll(cx).is_pending() {
+                    return Poll::Pending;
+                }
+                self.delay = None;
+            }
+
+            // there is no readiness notification for external streams,
+            // re-check paused state and workers availability periodically
+            if self.paused.get() || !self.available() {
+                self.delay = Some(delay_for(Duration::from_millis(50)));
+                continue;
+            }
+
+            match Pin::new(&mut self.stream).poll_next(cx) {
+                Poll::Ready(Some(Ok(io))) => {
+                    if let Err(e) = io.set_nonblocking(true) {
+                        error!("Can not set non-blocking mode: {}", e);
+                        continue;
+                    }
+                    let peer = io.peer_addr().ok().map(SocketAddr::Tcp);
+                    let msg = Conn {
+                        io: StdStream::Tcp(io),
+                        token: self.token,
+                        peer,
+                    };
+                    self.accept_one(msg);
+                }
+                Poll::Ready(Some(Err(e))) => {
+                    error!("Error accepting connection: {}", e);
+                }
+                Poll::Ready(None) => {
+                    info!("Connection stream is closed");
+                    return Poll::Ready(());
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
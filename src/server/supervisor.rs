@@ -0,0 +1,211 @@
+use std::cell::Cell;
+use std::fmt;
+use std::sync::Arc;
+use std::task::Context;
+use std::time::Duration;
+
+use crate::krse::task::LocalWaker;
+use crate::timer::Instant;
+
+thread_local! {
+    static FAILURES: Cell<usize> = Cell::new(0);
+    static WAKER: LocalWaker = LocalWaker::new();
+}
+
+/// Record worker failure, i.e. panic in connection processing
+pub(crate) fn failed() {
+    FAILURES.with(|failures| failures.set(failures.get() + 1));
+    // worker could be idle, failures must be checked without new connections
+    WAKER.with(|waker| waker.wake());
+}
+
+/// Take number of failures recorded since last call
+pub(crate) fn take_failures() -> usize {
+    FAILURES.with(|failures| failures.replace(0))
+}
+
+/// Information about scheduled worker restart
+#[derive(Debug, Clone)]
+pub struct WorkerRestart {
+    /// Worker index
+    pub worker: usize,
+    /// Number of failures that triggered restart
+    pub failures: usize,
+    /// Number of consecutive restarts, including this one
+    pub restarts: usize,
+    /// Delay before service factories get re-created
+    pub delay: Duration,
+}
+
+/// Worker supervisor configuration.
+///
+/// Supervisor counts panics in connection processing and service
+/// readiness errors of each worker. When number of failures within the
+/// period reaches the threshold, worker stops accepting connections and
+/// re-creates all its service factories (for http server it re-runs app
+/// factory). Already established connections are not affected.
+///
+/// Restarts are delayed with exponential backoff: delay doubles with every
+/// consecutive restart up to the max backoff, and gets reset once worker
+/// survives the whole period without restart.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use kayrx::server::Supervisor;
+///
+/// let supervisor = Supervisor::new()
+///     .threshold(5)
+///     .period(Duration::from_secs(30))
+///     .backoff(Duration::from_millis(500), Duration::from_secs(30))
+///     .on_restart(|event| {
+///         log::warn!("Restarting worker {}", event.worker);
+///     });
+///
+/// let builder = kayrx::server::new().supervisor(supervisor);
+/// ```
+#[derive(Clone)]
+pub struct Supervisor {
+    threshold: usize,
+    period: Duration,
+    backoff: Duration,
+    max_backoff: Duration,
+    on_restart: Option<Arc<dyn Fn(&WorkerRestart) + Send + Sync>>,
+}
+
+impl Default for Supervisor {
+    fn default() -> Self {
+        Supervisor {
+            threshold: 10,
+            period: Duration::from_secs(60),
+            backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            on_restart: None,
+        }
+    }
+}
+
+impl fmt::Debug for Supervisor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Supervisor")
+            .field("threshold", &self.threshold)
+            .field("period", &self.period)
+            .field("backoff", &self.backoff)
+            .field("max_backoff", &self.max_backoff)
+            .finish()
+    }
+}
+
+impl Supervisor {
+    /// Create supervisor with default settings.
+    ///
+    /// Worker gets restarted after 10 failures within 60 seconds, backoff
+    /// starts with 1 second and is limited to 60 seconds.
+    pub fn new() -> Self {
+        Supervisor::default()
+    }
+
+    /// Set number of failures that triggers worker restart
+    pub fn threshold(mut self, threshold: usize) -> Self {
+        self.threshold = std::cmp::max(threshold, 1);
+        self
+    }
+
+    /// Set period for failures counting
+    pub fn period(mut self, period: Duration) -> Self {
+        self.period = period;
+        self
+    }
+
+    /// Set initial and max restart delay
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.backoff = initial;
+        self.max_backoff = std::cmp::max(initial, max);
+        self
+    }
+
+    /// Set callback that is called on every scheduled restart.
+    ///
+    /// Callback runs on the worker thread.
+    pub fn on_restart<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&WorkerRestart) + Send + Sync + 'static,
+    {
+        self.on_restart = Some(Arc::new(f));
+        self
+    }
+}
+
+/// Per-worker supervision state
+pub(crate) struct Supervision {
+    config: Supervisor,
+    worker: usize,
+    failures: usize,
+    started: Instant,
+    restarts: usize,
+    restarted: Instant,
+}
+
+impl Supervision {
+    pub(crate) fn new(config: Supervisor, worker: usize) -> Self {
+        let now = Instant::now();
+        Supervision {
+            config,
+            worker,
+            failures: 0,
+            started: now,
+            restarts: 0,
+            restarted: now,
+        }
+    }
+
+    /// Collect recorded failures, returns restart delay if threshold is reached
+    pub(crate) fn check(&mut self, cx: &mut Context<'_>) -> Option<Duration> {
+        WAKER.with(|waker| waker.register(cx.waker()));
+        let failures = take_failures();
+        if failures == 0 {
+            return None;
+        }
+
+        let now = Instant::now();
+        if now.saturating_duration_since(self.started) > self.config.period {
+            self.failures = 0;
+            self.started = now;
+        }
+        self.failures += failures;
+        if self.failures < self.config.threshold {
+            return None;
+        }
+
+        if now.saturating_duration_since(self.restarted) > self.config.period {
+            self.restarts = 0;
+        }
+        let delay = self
+            .config
+            .backoff
+            .checked_mul(1 << std::cmp::min(self.restarts, 16) as u32)
+            .map(|delay| std::cmp::min(delay, self.config.max_backoff))
+            .unwrap_or(self.config.max_backoff);
+
+        self.restarts += 1;
+        self.restarted = now + delay;
+        let event = WorkerRestart {
+            worker: self.worker,
+            failures: self.failures,
+            restarts: self.restarts,
+            delay,
+        };
+        self.failures = 0;
+        self.started = self.restarted;
+
+        log::error!(
+            "Worker {} failed {} times, restarting in {:?}",
+            event.worker,
+            event.failures,
+            delay
+        );
+        if let Some(ref on_restart) = self.config.on_restart {
+            on_restart(&event);
+        }
+        Some(delay)
+    }
+}
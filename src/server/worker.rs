@@ -12,8 +12,11 @@ use log::{error, info, trace};
 use crate::fiber::{self, Arbiter};
 use crate::timer::{delay_until, Delay, Instant};
 use crate::server::accept::AcceptNotify;
+use crate::server::gate::WorkerLoad;
+use crate::server::lifecycle;
 use crate::server::service::{BoxedServerService, InternalServiceFactory, ServerMessage};
 use crate::server::socket::{SocketAddr, StdStream};
+use crate::server::supervisor::{self, Supervision, Supervisor};
 use crate::server::Token;
 use crate::krse::task::counter::Counter;
 
@@ -45,6 +48,10 @@ pub fn max_concurrent_connections(num: usize) {
     MAX_CONNS.store(num, Ordering::Relaxed);
 }
 
+pub(crate) fn max_connections() -> usize {
+    MAX_CONNS.load(Ordering::Relaxed)
+}
+
 pub(crate) fn num_connections() -> usize {
     MAX_CONNS_COUNTER.with(|conns| conns.total())
 }
@@ -80,6 +87,7 @@ impl WorkerClient {
     pub fn send(&self, msg: Conn) -> Result<(), Conn> {
         self.tx1
             .unbounded_send(WorkerCommand(msg))
+            .map(|_| self.avail.load.queued())
             .map_err(|msg| msg.into_inner().0)
     }
 
@@ -87,6 +95,10 @@ impl WorkerClient {
         self.avail.available()
     }
 
+    pub(crate) fn load(&self) -> &WorkerLoad {
+        &self.avail.load
+    }
+
     pub fn stop(&self, graceful: bool) -> oneshot::Receiver<bool> {
         let (result, rx) = oneshot::channel();
         let _ = self.tx2.unbounded_send(StopCommand { graceful, result });
@@ -98,6 +110,7 @@ impl WorkerClient {
 pub(crate) struct WorkerAvailability {
     notify: AcceptNotify,
     available: Arc<AtomicBool>,
+    load: Arc<WorkerLoad>,
 }
 
 impl WorkerAvailability {
@@ -105,6 +118,7 @@ impl WorkerAvailability {
         WorkerAvailability {
             notify,
             available: Arc::new(AtomicBool::new(false)),
+            load: Arc::new(WorkerLoad::default()),
         }
     }
 
@@ -133,6 +147,7 @@ pub(crate) struct Worker {
     factories: Vec<Box<dyn InternalServiceFactory>>,
     state: WorkerState,
     shutdown_timeout: time::Duration,
+    supervision: Option<Supervision>,
 }
 
 struct WorkerService {
@@ -164,6 +179,8 @@ impl Worker {
         factories: Vec<Box<dyn InternalServiceFactory>>,
         availability: WorkerAvailability,
         shutdown_timeout: time::Duration,
+        supervisor: Option<Supervisor>,
+        load_interval: Option<time::Duration>,
     ) -> WorkerClient {
         let (tx1, rx) = unbounded();
         let (tx2, rx2) = unbounded();
@@ -172,12 +189,29 @@ impl Worker {
         Arbiter::new().send(
             async move {
                 availability.set(false);
+
+                // sample connections count for accept gate
+                if let Some(interval) = load_interval {
+                    let load = Arc::downgrade(&availability.load);
+                    fiber::spawn(async move {
+                        let mut interval = crate::timer::interval(interval);
+                        loop {
+                            interval.tick().await;
+                            match load.upgrade() {
+                                Some(load) => load.set_connections(num_connections()),
+                                None => return,
+                            }
+                        }
+                    });
+                }
+
                 let mut wrk = MAX_CONNS_COUNTER.with(move |conns| Worker {
                     rx,
                     rx2,
                     availability,
                     factories,
                     shutdown_timeout,
+                    supervision: supervisor.map(|cfg| Supervision::new(cfg, idx)),
                     services: Vec::new(),
                     conns: conns.clone(),
                     state: WorkerState::Unavailable(Vec::new()),
@@ -235,6 +269,9 @@ impl Worker {
                 }
             });
         } else {
+            // notify connections, keep-alive connections get closed
+            lifecycle::start_draining();
+
             let timeout = self.shutdown_timeout;
             self.services.iter_mut().for_each(move |srv| {
                 if srv.status == WorkerServiceStatus::Available {
@@ -249,6 +286,18 @@ impl Worker {
         }
     }
 
+    fn create_services(
+        &self,
+    ) -> LocalBoxFuture<'static, Result<Vec<(Token, BoxedServerService)>, ()>> {
+        let fut: Vec<_> = self.factories.iter().map(|f| f.create()).collect();
+
+        async move {
+            let res: Result<Vec<_>, _> = join_all(fut).await.into_iter().collect();
+            res.map(|items| items.into_iter().flatten().collect())
+        }
+        .boxed_local()
+    }
+
     fn check_readiness(&mut self, cx: &mut Context<'_>) -> Result<bool, (Token, usize)> {
         let mut ready = self.conns.available(cx);
         let mut failed = None;
@@ -282,6 +331,7 @@ impl Worker {
                             "Service {:?} readiness check returned error, restarting",
                             self.factories[srv.factory].name(Token(idx))
                         );
+                        supervisor::failed();
                         failed = Some((Token(idx), srv.factory));
                         srv.status = WorkerServiceStatus::Failed;
                     }
@@ -296,6 +346,18 @@ impl Worker {
     }
 }
 
+/// Execute worker stop hooks and report shutdown result
+fn stopped(result: oneshot::Sender<bool>, success: bool, stop_arbiter: bool) {
+    let hooks = lifecycle::take_stop_hooks();
+    fiber::spawn(async move {
+        hooks.await;
+        let _ = result.send(success);
+        if stop_arbiter {
+            Arbiter::current().stop();
+        }
+    });
+}
+
 enum WorkerState {
     Available,
     Unavailable(Vec<Conn>),
@@ -304,6 +366,8 @@ enum WorkerState {
         Token,
         Pin<Box<dyn Future<Output = Result<Vec<(Token, BoxedServerService)>, ()>>>>,
     ),
+    Backoff(Pin<Box<Delay>>),
+    Recycling(LocalBoxFuture<'static, Result<Vec<(Token, BoxedServerService)>, ()>>),
     Shutdown(
         Pin<Box<Delay>>,
         Pin<Box<Delay>>,
@@ -323,7 +387,7 @@ impl Future for Worker {
             let num = num_connections();
             if num == 0 {
                 info!("Shutting down worker, 0 connections");
-                let _ = result.send(true);
+                stopped(result, true, false);
                 return Poll::Ready(());
             } else if graceful {
                 self.shutdown(false);
@@ -336,24 +400,62 @@ impl Future for Worker {
                         Some(result),
                     );
                 } else {
-                    let _ = result.send(true);
+                    stopped(result, true, false);
                     return Poll::Ready(());
                 }
             } else {
                 info!("Force shutdown worker, {} connections", num);
                 self.shutdown(true);
-                let _ = result.send(false);
+                stopped(result, false, false);
                 return Poll::Ready(());
             }
         }
 
+        // restart all services if supervisor failure threshold is reached
+        if let WorkerState::Available = self.state {
+            if let Some(delay) = self.supervision.as_mut().and_then(|s| s.check(cx)) {
+                self.availability.set(false);
+                self.services
+                    .iter_mut()
+                    .for_each(|srv| srv.status = WorkerServiceStatus::Restarting);
+                self.state =
+                    WorkerState::Backoff(Box::pin(delay_until(Instant::now() + delay)));
+            }
+        }
+
         match self.state {
+            WorkerState::Backoff(ref mut delay) => match delay.as_mut().poll(cx) {
+                Poll::Pending => Poll::Pending,
+                Poll::Ready(_) => {
+                    self.state = WorkerState::Recycling(self.create_services());
+                    self.poll(cx)
+                }
+            },
+            WorkerState::Recycling(ref mut fut) => match fut.as_mut().poll(cx) {
+                Poll::Ready(Ok(services)) => {
+                    for (token, service) in services {
+                        self.services[token.0].created(service);
+                    }
+                    // failures of old services are not relevant anymore
+                    supervisor::take_failures();
+                    info!("Worker services have been restarted");
+                    self.state = WorkerState::Unavailable(Vec::new());
+                    self.poll(cx)
+                }
+                Poll::Ready(Err(_)) => {
+                    error!("Can not restart worker services");
+                    Arbiter::current().stop();
+                    Poll::Ready(())
+                }
+                Poll::Pending => Poll::Pending,
+            },
             WorkerState::Unavailable(ref mut conns) => {
                 let conn = conns.pop();
                 match self.check_readiness(cx) {
                     Ok(true) => {
                         // process requests from wait queue
                         if let Some(conn) = conn {
+                            self.availability.load.dequeued();
                             let guard = self.conns.get();
                             let _ = self.services[conn.token.0]
                                 .service
@@ -416,8 +518,7 @@ impl Future for Worker {
             WorkerState::Shutdown(ref mut t1, ref mut t2, ref mut tx) => {
                 let num = num_connections();
                 if num == 0 {
-                    let _ = tx.take().unwrap().send(true);
-                    Arbiter::current().stop();
+                    stopped(tx.take().unwrap(), true, true);
                     return Poll::Ready(());
                 }
 
@@ -425,9 +526,9 @@ impl Future for Worker {
                 match t2.as_mut().poll(cx) {
                     Poll::Pending => (),
                     Poll::Ready(_) => {
-                        let _ = tx.take().unwrap().send(false);
+                        let tx = tx.take().unwrap();
                         self.shutdown(true);
-                        Arbiter::current().stop();
+                        stopped(tx, false, true);
                         return Poll::Ready(());
                     }
                 }
@@ -451,6 +552,7 @@ impl Future for Worker {
                         Poll::Ready(Some(WorkerCommand(msg))) => {
                             match self.check_readiness(cx) {
                                 Ok(true) => {
+                                    self.availability.load.dequeued();
                                     let guard = self.conns.get();
                                     let _ = self.services[msg.token.0]
                                         .service
@@ -19,6 +19,8 @@ mod map_err;
 mod map_init_err;
 mod pipeline;
 mod then;
+#[cfg(feature = "tower")]
+pub mod tower;
 mod transform;
 mod transform_err;
 
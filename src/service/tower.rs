@@ -0,0 +1,134 @@
+//! Adapters between kayrx and `tower` services.
+//!
+//! Module is available with `tower` feature.
+use std::marker::PhantomData;
+use std::task::{Context, Poll};
+
+use futures_util::future::{ok, Ready};
+use tower_layer::Layer;
+
+use crate::service::{Service, ServiceFactory, Transform};
+
+/// Wrap `tower::Service` as a kayrx service.
+///
+/// Adapter is also a service factory, if tower service is `Clone`,
+/// every new service is a clone of the wrapped service.
+pub fn from_tower<S, R>(service: S) -> TowerService<S, R>
+where
+    S: tower_service::Service<R>,
+{
+    TowerService {
+        service,
+        _t: PhantomData,
+    }
+}
+
+/// Wrap kayrx service as a `tower::Service`.
+pub fn into_tower<S: Service>(service: S) -> KayrxService<S> {
+    KayrxService { service }
+}
+
+/// Use `tower::Layer` as a kayrx transform.
+///
+/// Layer wraps kayrx service converted to `tower::Service`, so layers like
+/// timeouts or concurrency limits could be mounted with `wrap()`.
+pub fn layer<L>(layer: L) -> TowerLayer<L> {
+    TowerLayer { layer }
+}
+
+/// `tower::Service` adapter, created by `from_tower()` function
+pub struct TowerService<S, R> {
+    service: S,
+    _t: PhantomData<R>,
+}
+
+impl<S, R> Clone for TowerService<S, R>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        TowerService {
+            service: self.service.clone(),
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<S, R> Service for TowerService<S, R>
+where
+    S: tower_service::Service<R>,
+{
+    type Request = R;
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: R) -> Self::Future {
+        self.service.call(req)
+    }
+}
+
+impl<S, R> ServiceFactory for TowerService<S, R>
+where
+    S: tower_service::Service<R> + Clone,
+{
+    type Request = R;
+    type Response = S::Response;
+    type Error = S::Error;
+    type Config = ();
+    type Service = TowerService<S, R>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        ok(self.clone())
+    }
+}
+
+/// kayrx service adapter, created by `into_tower()` function
+#[derive(Clone)]
+pub struct KayrxService<S> {
+    service: S,
+}
+
+impl<S: Service> tower_service::Service<S::Request> for KayrxService<S> {
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: S::Request) -> Self::Future {
+        self.service.call(req)
+    }
+}
+
+/// `tower::Layer` adapter, created by `layer()` function
+#[derive(Clone)]
+pub struct TowerLayer<L> {
+    layer: L,
+}
+
+impl<L, S> Transform<S> for TowerLayer<L>
+where
+    S: Service,
+    L: Layer<KayrxService<S>>,
+    L::Service: tower_service::Service<S::Request>,
+{
+    type Request = S::Request;
+    type Response = <L::Service as tower_service::Service<S::Request>>::Response;
+    type Error = <L::Service as tower_service::Service<S::Request>>::Error;
+    type Transform = TowerService<L::Service, S::Request>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(from_tower(self.layer.layer(into_tower(service))))
+    }
+}
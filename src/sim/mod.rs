@@ -0,0 +1,66 @@
+//! Deterministic simulation of time and network.
+//!
+//! Simulation harness combines the paused clock with an in-memory network,
+//! so protocol logic like retries, timeouts or connection pooling could be
+//! tested deterministically and without waiting for real time.
+//!
+//! * `run()` executes a future on a new system with paused clock. When all
+//!   tasks are idle, clock jumps to the next timer deadline, so a test that
+//!   sleeps for an hour completes instantly and always observes the same
+//!   order of events.
+//!
+//! * `Network` is a set of named nodes connected by links. Every link has a
+//!   latency/loss model, data written to a `SimStream` is delivered to the
+//!   peer after link delay. Network randomness is driven by a seed, the same
+//!   seed always produces the same delays.
+//!
+//! Module is available with `sim` feature.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use futures::StreamExt;
+//! use kayrx::krse::io::{AsyncReadExt, AsyncWriteExt};
+//! use kayrx::sim::{self, Link, Network};
+//! use kayrx::timer::Instant;
+//!
+//! sim::run(async {
+//!     let net = Network::new(7);
+//!     net.link("client", "server", Link::new().latency(Duration::from_millis(50)));
+//!
+//!     let mut listener = net.bind("server").unwrap();
+//!     let start = Instant::now();
+//!     let mut client = net.connect("client", "server").await.unwrap();
+//!     let mut server = listener.next().await.unwrap();
+//!
+//!     client.write_all(b"ping").await.unwrap();
+//!     let mut buf = [0u8; 4];
+//!     server.read_exact(&mut buf).await.unwrap();
+//!
+//!     // handshake round trip plus one way delivery
+//!     assert_eq!(start.elapsed(), Duration::from_millis(150));
+//! });
+//! ```
+use std::future::Future;
+
+use crate::fiber::System;
+
+mod net;
+
+pub use self::net::{Link, Network, SimConnector, SimListener, SimStream};
+
+/// Run future in the simulated environment.
+///
+/// Future is executed on a new system with paused clock, the call blocks
+/// until future completes.
+pub fn run<F, R>(fut: F) -> R
+where
+    F: Future<Output = R> + 'static,
+{
+    System::builder()
+        .name("kayrx-sim")
+        .paused_clock()
+        .build()
+        .block_on(fut)
+}
@@ -0,0 +1,579 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::rc::{Rc, Weak};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+use std::{cmp, fmt, io};
+
+use bytes::{Buf, Bytes};
+use futures_channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use futures_core::Stream;
+use futures_util::future::LocalBoxFuture;
+use futures_util::FutureExt;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::connect::{Address, Connect, ConnectError, Connection};
+use crate::krse::io::{AsyncRead, AsyncWrite};
+use crate::service::Service;
+use crate::timer::{delay_for, delay_until, Delay, Instant};
+
+/// Max number of retransmissions of a lost segment
+const MAX_RETRANSMITS: usize = 16;
+
+/// Link latency and loss model.
+///
+/// Every write to a stream is a segment. Segment is delivered after
+/// `latency` plus random jitter, lost segments are retransmitted after
+/// `retransmit` timeout. Delivery is in order, like in tcp.
+#[derive(Clone, Copy, Debug)]
+pub struct Link {
+    latency: Duration,
+    jitter: Duration,
+    loss: f64,
+    retransmit: Duration,
+    capacity: usize,
+}
+
+impl Default for Link {
+    fn default() -> Self {
+        Link {
+            latency: Duration::from_millis(0),
+            jitter: Duration::from_millis(0),
+            loss: 0.0,
+            retransmit: Duration::from_millis(200),
+            capacity: 65_536,
+        }
+    }
+}
+
+impl Link {
+    /// Create link without latency and loss
+    pub fn new() -> Self {
+        Link::default()
+    }
+
+    /// Set one way delivery delay
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Set max random delay added to latency
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Set probability of segment loss, value is clamped to `0.0..=0.99`
+    pub fn loss(mut self, loss: f64) -> Self {
+        self.loss = loss.max(0.0).min(0.99);
+        self
+    }
+
+    /// Set retransmission timeout of lost segments.
+    ///
+    /// By default retransmission timeout is set to 200 milliseconds.
+    pub fn retransmit(mut self, timeout: Duration) -> Self {
+        self.retransmit = timeout;
+        self
+    }
+
+    /// Set max number of bytes in flight in each direction.
+    ///
+    /// By default capacity is set to 64Kb.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = cmp::max(capacity, 1);
+        self
+    }
+}
+
+type LinkKey = (String, String);
+
+fn key(a: &str, b: &str) -> LinkKey {
+    if a <= b {
+        (a.to_owned(), b.to_owned())
+    } else {
+        (b.to_owned(), a.to_owned())
+    }
+}
+
+/// Simulated network.
+///
+/// Network is a set of named nodes, every pair of nodes is connected by a
+/// link. Links that are not configured explicitly use default link. Network
+/// is a cheap handle, clones share the same state.
+#[derive(Clone)]
+pub struct Network(Rc<RefCell<Inner>>);
+
+struct Inner {
+    rng: StdRng,
+    default: Link,
+    links: HashMap<LinkKey, Link>,
+    down: HashSet<LinkKey>,
+    listeners: HashMap<String, UnboundedSender<SimStream>>,
+    conns: Vec<(LinkKey, Weak<RefCell<Pipe>>)>,
+}
+
+impl fmt::Debug for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = self.0.borrow();
+        f.debug_struct("Network")
+            .field("default", &inner.default)
+            .field("links", &inner.links)
+            .field("down", &inner.down)
+            .finish()
+    }
+}
+
+impl Network {
+    /// Create network, `seed` initializes random delays and losses
+    pub fn new(seed: u64) -> Self {
+        Network(Rc::new(RefCell::new(Inner {
+            rng: StdRng::seed_from_u64(seed),
+            default: Link::default(),
+            links: HashMap::new(),
+            down: HashSet::new(),
+            listeners: HashMap::new(),
+            conns: Vec::new(),
+        })))
+    }
+
+    /// Set link model for node pairs without explicit link
+    pub fn default_link(&self, link: Link) -> &Self {
+        self.0.borrow_mut().default = link;
+        self
+    }
+
+    /// Set link model between two nodes, link is symmetric.
+    ///
+    /// New model applies to new connections.
+    pub fn link(&self, a: &str, b: &str, link: Link) -> &Self {
+        self.0.borrow_mut().links.insert(key(a, b), link);
+        self
+    }
+
+    /// Break link between two nodes.
+    ///
+    /// Established connections get reset, new connections time out until
+    /// link is healed.
+    pub fn partition(&self, a: &str, b: &str) {
+        let key = key(a, b);
+        let mut inner = self.0.borrow_mut();
+        inner.down.insert(key.clone());
+        inner.conns.retain(|(k, pipe)| match pipe.upgrade() {
+            Some(pipe) => {
+                if *k == key {
+                    pipe.borrow_mut().reset();
+                    false
+                } else {
+                    true
+                }
+            }
+            None => false,
+        });
+    }
+
+    /// Restore link between two nodes
+    pub fn heal(&self, a: &str, b: &str) {
+        self.0.borrow_mut().down.remove(&key(a, b));
+    }
+
+    /// Start listening for connections to the node.
+    pub fn bind(&self, node: &str) -> io::Result<SimListener> {
+        let mut inner = self.0.borrow_mut();
+        if let Some(tx) = inner.listeners.get(node) {
+            if !tx.is_closed() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    format!("Node {:?} is already bound", node),
+                ));
+            }
+        }
+        let (tx, rx) = unbounded();
+        inner.listeners.insert(node.to_owned(), tx);
+        Ok(SimListener(rx))
+    }
+
+    /// Open connection from one node to another.
+    ///
+    /// Connection is established after link round trip. Connection is
+    /// refused if target node is not bound and times out after
+    /// retransmission timeout if link is partitioned.
+    pub fn connect(
+        &self,
+        from: &str,
+        to: &str,
+    ) -> impl Future<Output = io::Result<SimStream>> {
+        let net = self.clone();
+        let key = key(from, to);
+        let to = to.to_owned();
+
+        async move {
+            let link = net.get_link(&key);
+            if net.0.borrow().down.contains(&key) {
+                delay_for(link.retransmit).await;
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("Link to {:?} is down", to),
+                ));
+            }
+
+            // handshake
+            let rtt = net.delay(&key) + net.delay(&key);
+            delay_for(rtt).await;
+
+            let tx = net.0.borrow().listeners.get(&to).cloned();
+            let (client, server) = net.pair(key, link.capacity);
+            let accepted = match tx {
+                Some(tx) => tx.unbounded_send(server).is_ok(),
+                None => false,
+            };
+            if accepted {
+                Ok(client)
+            } else {
+                Err(io::Error::new(
+                    io::ErrorKind::ConnectionRefused,
+                    format!("Node {:?} is not bound", to),
+                ))
+            }
+        }
+    }
+
+    /// Create connector service for the node.
+    ///
+    /// Connector could be used as a custom connector of the http client,
+    /// host of the request is a target node name.
+    pub fn connector<T>(&self, node: &str) -> SimConnector<T> {
+        SimConnector {
+            net: self.clone(),
+            node: node.to_owned(),
+            _t: PhantomData,
+        }
+    }
+
+    fn get_link(&self, key: &LinkKey) -> Link {
+        let inner = self.0.borrow();
+        inner.links.get(key).copied().unwrap_or(inner.default)
+    }
+
+    /// Sample segment delivery delay
+    fn delay(&self, key: &LinkKey) -> Duration {
+        let link = self.get_link(key);
+        let mut inner = self.0.borrow_mut();
+
+        let mut delay = link.latency;
+        let jitter = link.jitter.as_micros() as u64;
+        if jitter > 0 {
+            delay += Duration::from_micros(inner.rng.gen_range(0, jitter + 1));
+        }
+        if link.loss > 0.0 {
+            let mut n = 0;
+            while n < MAX_RETRANSMITS && inner.rng.gen::<f64>() < link.loss {
+                delay += link.retransmit;
+                n += 1;
+            }
+        }
+        delay
+    }
+
+    fn pair(&self, key: LinkKey, capacity: usize) -> (SimStream, SimStream) {
+        let one = Rc::new(RefCell::new(Pipe::new(capacity)));
+        let two = Rc::new(RefCell::new(Pipe::new(capacity)));
+        {
+            let mut inner = self.0.borrow_mut();
+            inner.conns.retain(|(_, pipe)| pipe.upgrade().is_some());
+            inner.conns.push((key.clone(), Rc::downgrade(&one)));
+            inner.conns.push((key.clone(), Rc::downgrade(&two)));
+        }
+
+        (
+            SimStream {
+                net: self.clone(),
+                key: key.clone(),
+                read: one.clone(),
+                write: two.clone(),
+                delay: None,
+            },
+            SimStream {
+                net: self.clone(),
+                key,
+                read: two,
+                write: one,
+                delay: None,
+            },
+        )
+    }
+}
+
+/// One direction of the simulated connection
+struct Pipe {
+    segments: VecDeque<(Instant, Bytes)>,
+    buffered: usize,
+    capacity: usize,
+    last: Option<Instant>,
+    eof: Option<Instant>,
+    dropped: bool,
+    reset: bool,
+    read_waker: Option<Waker>,
+    write_waker: Option<Waker>,
+}
+
+impl Pipe {
+    fn new(capacity: usize) -> Self {
+        Pipe {
+            segments: VecDeque::new(),
+            buffered: 0,
+            capacity,
+            last: None,
+            eof: None,
+            dropped: false,
+            reset: false,
+            read_waker: None,
+            write_waker: None,
+        }
+    }
+
+    /// Delivery time of the next segment, delivery is in order
+    fn deliver_at(&mut self, delay: Duration) -> Instant {
+        let at = Instant::now() + delay;
+        let at = match self.last {
+            Some(last) if last > at => last,
+            _ => at,
+        };
+        self.last = Some(at);
+        at
+    }
+
+    fn reset(&mut self) {
+        self.reset = true;
+        self.wake();
+    }
+
+    fn wake(&mut self) {
+        if let Some(waker) = self.read_waker.take() {
+            waker.wake();
+        }
+        if let Some(waker) = self.write_waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Simulated connection
+pub struct SimStream {
+    net: Network,
+    key: LinkKey,
+    read: Rc<RefCell<Pipe>>,
+    write: Rc<RefCell<Pipe>>,
+    delay: Option<Delay>,
+}
+
+impl fmt::Debug for SimStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SimStream").field("link", &self.key).finish()
+    }
+}
+
+impl SimStream {
+    /// Wait for the deadline, returns `Ready` if deadline has passed
+    fn poll_deadline(&mut self, cx: &mut Context<'_>, at: Instant) -> Poll<()> {
+        match self.delay {
+            Some(ref delay) if delay.deadline() == at => (),
+            _ => self.delay = Some(delay_until(at)),
+        }
+        let res = Pin::new(self.delay.as_mut().unwrap()).poll(cx);
+        if res.is_ready() {
+            self.delay = None;
+        }
+        res
+    }
+}
+
+impl AsyncRead for SimStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let read = this.read.clone();
+
+        loop {
+            let mut pipe = read.borrow_mut();
+            if pipe.reset {
+                return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()));
+            }
+
+            let now = Instant::now();
+            let next = match pipe.segments.front() {
+                Some((at, _)) if *at <= now => None,
+                Some((at, _)) => Some(*at),
+                None => match pipe.eof {
+                    Some(at) if at <= now => return Poll::Ready(Ok(0)),
+                    Some(at) => Some(at),
+                    None => {
+                        pipe.read_waker = Some(cx.waker().clone());
+                        return Poll::Pending;
+                    }
+                },
+            };
+
+            if let Some(at) = next {
+                // reset could arrive before the deadline
+                pipe.read_waker = Some(cx.waker().clone());
+                drop(pipe);
+                if this.poll_deadline(cx, at).is_pending() {
+                    return Poll::Pending;
+                }
+                continue;
+            }
+
+            // copy all delivered segments
+            let mut n = 0;
+            while n < buf.len() {
+                let len = match pipe.segments.front_mut() {
+                    Some((at, data)) if *at <= now => {
+                        let len = cmp::min(data.len(), buf.len() - n);
+                        buf[n..n + len].copy_from_slice(&data[..len]);
+                        data.advance(len);
+                        len
+                    }
+                    _ => break,
+                };
+                n += len;
+                if pipe.segments.front().map(|s| s.1.is_empty()).unwrap_or(false) {
+                    pipe.segments.pop_front();
+                }
+            }
+            pipe.buffered -= n;
+            if let Some(waker) = pipe.write_waker.take() {
+                waker.wake();
+            }
+            return Poll::Ready(Ok(n));
+        }
+    }
+}
+
+impl AsyncWrite for SimStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let mut pipe = this.write.borrow_mut();
+        if pipe.reset {
+            return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()));
+        }
+        if pipe.dropped || pipe.eof.is_some() {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        if pipe.buffered >= pipe.capacity {
+            pipe.write_waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        let len = cmp::min(buf.len(), pipe.capacity - pipe.buffered);
+        let at = pipe.deliver_at(this.net.delay(&this.key));
+        pipe.segments
+            .push_back((at, Bytes::copy_from_slice(&buf[..len])));
+        pipe.buffered += len;
+        if let Some(waker) = pipe.read_waker.take() {
+            waker.wake();
+        }
+        Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let mut pipe = this.write.borrow_mut();
+        if pipe.eof.is_none() {
+            let at = pipe.deliver_at(this.net.delay(&this.key));
+            pipe.eof = Some(at);
+            if let Some(waker) = pipe.read_waker.take() {
+                waker.wake();
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for SimStream {
+    fn drop(&mut self) {
+        // peer reads buffered data and eof, peer writes fail
+        let _ = Pin::new(&mut *self).poll_shutdown(&mut Context::from_waker(
+            futures_util::task::noop_waker_ref(),
+        ));
+        let mut pipe = self.read.borrow_mut();
+        pipe.dropped = true;
+        if let Some(waker) = pipe.write_waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Stream of connections to the node
+#[derive(Debug)]
+pub struct SimListener(UnboundedReceiver<SimStream>);
+
+impl Stream for SimListener {
+    type Item = SimStream;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.0).poll_next(cx)
+    }
+}
+
+/// Connector service of the simulated network node
+pub struct SimConnector<T> {
+    net: Network,
+    node: String,
+    _t: PhantomData<T>,
+}
+
+impl<T> Clone for SimConnector<T> {
+    fn clone(&self) -> Self {
+        SimConnector {
+            net: self.net.clone(),
+            node: self.node.clone(),
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for SimConnector<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SimConnector").field("node", &self.node).finish()
+    }
+}
+
+impl<T: Address + 'static> Service for SimConnector<T> {
+    type Request = Connect<T>;
+    type Response = Connection<T, SimStream>;
+    type Error = ConnectError;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Connect<T>) -> Self::Future {
+        let fut = self.net.connect(&self.node, req.host());
+        async move {
+            let io = fut.await?;
+            Ok(Connection::new(io, req.req))
+        }
+        .boxed_local()
+    }
+}
@@ -0,0 +1,206 @@
+//! Metric instruments
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use lazy_static::lazy_static;
+
+/// Default histogram bucket bounds, milliseconds
+const DEFAULT_BOUNDS: &[f64] = &[
+    0.0, 5.0, 10.0, 25.0, 50.0, 75.0, 100.0, 250.0, 500.0, 750.0, 1000.0, 2500.0,
+    5000.0, 7500.0, 10000.0,
+];
+
+lazy_static! {
+    static ref REGISTRY: Registry = Registry::new();
+}
+
+type Key = (String, Vec<(String, String)>);
+
+struct Registry {
+    start: SystemTime,
+    metrics: Mutex<HashMap<Key, Instrument>>,
+}
+
+#[derive(Clone)]
+enum Instrument {
+    Counter(Arc<AtomicU64>),
+    Gauge(Arc<AtomicU64>),
+    Histogram(Arc<Mutex<HistogramData>>),
+}
+
+struct HistogramData {
+    count: u64,
+    sum: f64,
+    buckets: Vec<u64>,
+}
+
+/// Collected value of the instrument
+pub(crate) enum Value {
+    Counter(u64),
+    Gauge(f64),
+    Histogram {
+        count: u64,
+        sum: f64,
+        buckets: Vec<u64>,
+        bounds: &'static [f64],
+    },
+}
+
+/// Snapshot of a metric with attributes
+pub(crate) struct Point {
+    pub(crate) name: String,
+    pub(crate) attributes: Vec<(String, String)>,
+    pub(crate) value: Value,
+}
+
+impl Registry {
+    fn new() -> Registry {
+        Registry {
+            start: SystemTime::now(),
+            metrics: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get<F>(&self, name: &str, attributes: &[(&str, &str)], f: F) -> Instrument
+    where
+        F: FnOnce() -> Instrument,
+    {
+        let mut attributes: Vec<_> = attributes
+            .iter()
+            .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+            .collect();
+        attributes.sort();
+
+        self.metrics
+            .lock()
+            .unwrap()
+            .entry((name.to_owned(), attributes))
+            .or_insert_with(f)
+            .clone()
+    }
+}
+
+/// Time of registry creation, start of cumulative metrics
+pub(crate) fn start_time() -> SystemTime {
+    REGISTRY.start
+}
+
+/// Snapshot of all registered metrics
+pub(crate) fn collect() -> Vec<Point> {
+    let metrics = REGISTRY.metrics.lock().unwrap();
+    metrics
+        .iter()
+        .map(|((name, attributes), instrument)| {
+            let value = match instrument {
+                Instrument::Counter(val) => Value::Counter(val.load(Ordering::Relaxed)),
+                Instrument::Gauge(val) => {
+                    Value::Gauge(f64::from_bits(val.load(Ordering::Relaxed)))
+                }
+                Instrument::Histogram(data) => {
+                    let data = data.lock().unwrap();
+                    Value::Histogram {
+                        count: data.count,
+                        sum: data.sum,
+                        buckets: data.buckets.clone(),
+                        bounds: DEFAULT_BOUNDS,
+                    }
+                }
+            };
+            Point {
+                value,
+                name: name.clone(),
+                attributes: attributes.clone(),
+            }
+        })
+        .collect()
+}
+
+/// Monotonic cumulative counter
+#[derive(Clone)]
+pub struct Counter(Arc<AtomicU64>);
+
+impl Counter {
+    /// Increment counter
+    pub fn add(&self, value: u64) {
+        self.0.fetch_add(value, Ordering::Relaxed);
+    }
+}
+
+/// Last value gauge
+#[derive(Clone)]
+pub struct Gauge(Arc<AtomicU64>);
+
+impl Gauge {
+    /// Set current value
+    pub fn set(&self, value: f64) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// Histogram with millisecond buckets from 5ms to 10s
+#[derive(Clone)]
+pub struct Histogram(Arc<Mutex<HistogramData>>);
+
+impl Histogram {
+    /// Record value
+    pub fn record(&self, value: f64) {
+        let mut data = self.0.lock().unwrap();
+        let idx = DEFAULT_BOUNDS
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(DEFAULT_BOUNDS.len());
+        data.buckets[idx] += 1;
+        data.count += 1;
+        data.sum += value;
+    }
+}
+
+/// Get or register counter with the name and attributes.
+///
+/// Instruments with the same name and attributes share the value.
+///
+/// # Panics
+///
+/// Panics if instrument with the name and attributes has different type.
+pub fn counter(name: &str, attributes: &[(&str, &str)]) -> Counter {
+    match REGISTRY.get(name, attributes, || {
+        Instrument::Counter(Arc::new(AtomicU64::new(0)))
+    }) {
+        Instrument::Counter(val) => Counter(val),
+        _ => panic!("Metric {} is not a counter", name),
+    }
+}
+
+/// Get or register gauge with the name and attributes.
+///
+/// # Panics
+///
+/// Panics if instrument with the name and attributes has different type.
+pub fn gauge(name: &str, attributes: &[(&str, &str)]) -> Gauge {
+    match REGISTRY.get(name, attributes, || {
+        Instrument::Gauge(Arc::new(AtomicU64::new(0f64.to_bits())))
+    }) {
+        Instrument::Gauge(val) => Gauge(val),
+        _ => panic!("Metric {} is not a gauge", name),
+    }
+}
+
+/// Get or register histogram with the name and attributes.
+///
+/// # Panics
+///
+/// Panics if instrument with the name and attributes has different type.
+pub fn histogram(name: &str, attributes: &[(&str, &str)]) -> Histogram {
+    match REGISTRY.get(name, attributes, || {
+        Instrument::Histogram(Arc::new(Mutex::new(HistogramData {
+            count: 0,
+            sum: 0.0,
+            buckets: vec![0; DEFAULT_BOUNDS.len() + 1],
+        })))
+    }) {
+        Instrument::Histogram(val) => Histogram(val),
+        _ => panic!("Metric {} is not a histogram", name),
+    }
+}
@@ -0,0 +1,117 @@
+//! Server requests metrics and spans
+use std::task::{Context, Poll};
+use std::time::{Instant, SystemTime};
+
+use futures_util::future::{ok, FutureExt, LocalBoxFuture, Ready};
+
+use crate::http::error::{Error, ResponseError};
+use crate::http::HttpMessage;
+use crate::service::{Service, Transform};
+use crate::web::dev::{ServiceRequest, ServiceResponse};
+use crate::web::trace::TraceContext;
+
+use super::span::{self, SpanData, SpanKind};
+
+/// `Middleware` for recording server metrics and spans.
+///
+/// Every request increments `http.server.requests` counter with `method`
+/// and `status` attributes and records its duration to
+/// `http.server.duration` histogram. Server span is recorded for sampled
+/// traces, register [`Trace`](../web/middleware/struct.Trace.html)
+/// middleware after this one to continue traces of the callers.
+///
+/// ```rust
+/// use kayrx::telemetry::HttpMetrics;
+/// use kayrx::web::{self, middleware, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(HttpMetrics)
+///         .wrap(middleware::Trace::new())
+///         .service(web::resource("/").to(|| HttpResponse::Ok()));
+/// }
+/// ```
+#[derive(Clone, Copy, Default)]
+pub struct HttpMetrics;
+
+impl<S, B> Transform<S> for HttpMetrics
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = HttpMetricsMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(HttpMetricsMiddleware { service })
+    }
+}
+
+pub struct HttpMetricsMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service for HttpMetricsMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let start = Instant::now();
+        let start_time = SystemTime::now();
+        let method = req.method().to_string();
+        let target = req.uri().to_string();
+        let fut = self.service.call(req);
+
+        async move {
+            let res = fut.await;
+            let status = match res {
+                Ok(ref res) => res.status(),
+                Err(ref e) => e.as_response_error().status_code(),
+            };
+            let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+
+            super::counter(
+                "http.server.requests",
+                &[("method", &method), ("status", status.as_str())],
+            )
+            .add(1);
+            super::histogram("http.server.duration", &[("method", &method)])
+                .record(elapsed);
+
+            // context is stored by `Trace` middleware
+            let ctx = match res {
+                Ok(ref res) => res.request().extensions().get::<TraceContext>().cloned(),
+                Err(_) => None,
+            };
+            span::record(SpanData {
+                ctx: ctx.unwrap_or_else(TraceContext::new_root),
+                name: method.clone(),
+                kind: SpanKind::Server,
+                start: start_time,
+                end: SystemTime::now(),
+                attributes: vec![
+                    ("http.method", method),
+                    ("http.target", target),
+                    ("http.status_code", status.as_str().to_owned()),
+                ],
+                error: status.is_server_error(),
+            });
+            res
+        }
+        .boxed_local()
+    }
+}
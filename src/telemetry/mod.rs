@@ -0,0 +1,247 @@
+//! Metrics and traces export with OpenTelemetry protocol.
+//!
+//! Exporter periodically sends collected metrics and finished spans to an
+//! OpenTelemetry collector with OTLP/HTTP JSON encoding, using kayrx http
+//! client on the current system, so no separate runtime is required.
+//!
+//! * Runtime metrics, timer driver statistics of the exporter thread
+//!   (`kayrx.timer.*`), are collected on every export.
+//! * Server metrics and spans are recorded by
+//!   [`HttpMetrics`](struct.HttpMetrics.html) middleware.
+//! * Client spans and `http.client.requests` counter are recorded by
+//!   clients with enabled `ClientBuilder::trace_propagation()`.
+//! * Application metrics could be recorded with [`counter()`](fn.counter.html),
+//!   [`gauge()`](fn.gauge.html) and [`histogram()`](fn.histogram.html).
+//!
+//! Module is available with `telemetry` feature.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use kayrx::telemetry::{HttpMetrics, Telemetry};
+//! use kayrx::web::{self, middleware, App, HttpResponse, HttpServer};
+//!
+//! #[kayrx::main]
+//! async fn main() -> std::io::Result<()> {
+//!     let exporter = Telemetry::new("http://otel-collector:4318")
+//!         .service_name("frontend")
+//!         .resource_attribute("deployment.environment", "prod")
+//!         .interval(Duration::from_secs(15))
+//!         .start();
+//!
+//!     HttpServer::new(|| {
+//!         App::new()
+//!             .wrap(HttpMetrics)
+//!             .wrap(middleware::Trace::new())
+//!             .service(web::resource("/").to(|| HttpResponse::Ok()))
+//!     })
+//!     // export the last batch before exit
+//!     .on_stop(move || exporter.shutdown())
+//!     .bind("127.0.0.1:8080")?
+//!     .run()
+//!     .await
+//! }
+//! ```
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::{Duration, SystemTime};
+
+use futures_channel::oneshot;
+use futures_util::future::{select, Either, FutureExt, LocalBoxFuture};
+use serde_json::Value;
+
+use crate::timer::{self, MissedTickBehavior};
+use crate::web::client::Client;
+
+mod metrics;
+mod middleware;
+mod otlp;
+mod span;
+
+pub use self::metrics::{counter, gauge, histogram, Counter, Gauge, Histogram};
+pub use self::middleware::HttpMetrics;
+pub(crate) use self::span::{record as record_span, SpanData, SpanKind};
+
+/// Telemetry exporter configuration
+pub struct Telemetry {
+    endpoint: String,
+    resource: Vec<(String, String)>,
+    headers: Vec<(String, String)>,
+    interval: Duration,
+    timeout: Duration,
+}
+
+impl Telemetry {
+    /// Configure exporter to the OTLP/HTTP collector, i.e.
+    /// `http://localhost:4318`. Metrics are sent to `/v1/metrics` and
+    /// spans to `/v1/traces` paths of the endpoint.
+    pub fn new(endpoint: &str) -> Telemetry {
+        Telemetry {
+            endpoint: endpoint.trim_end_matches('/').to_owned(),
+            resource: vec![
+                ("service.name".to_owned(), "unknown_service".to_owned()),
+                ("telemetry.sdk.name".to_owned(), "kayrx".to_owned()),
+                (
+                    "telemetry.sdk.version".to_owned(),
+                    env!("CARGO_PKG_VERSION").to_owned(),
+                ),
+            ],
+            headers: Vec::new(),
+            interval: Duration::from_secs(60),
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// Set `service.name` resource attribute
+    pub fn service_name<T: Into<String>>(self, name: T) -> Self {
+        self.resource_attribute("service.name", name)
+    }
+
+    /// Add resource attribute, attribute with the same key is replaced
+    pub fn resource_attribute<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let key = key.into();
+        self.resource.retain(|(k, _)| *k != key);
+        self.resource.push((key, value.into()));
+        self
+    }
+
+    /// Add header to export requests, i.e. collector api key
+    pub fn header<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.headers.push((key.into(), value.into()));
+        self
+    }
+
+    /// Set export interval, by default 60 seconds
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Set export request timeout, by default 10 seconds
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Start exporter on the current system.
+    ///
+    /// # Panics
+    ///
+    /// Panics if system is not running.
+    pub fn start(self) -> Exporter {
+        let mut client = Client::build().timeout(self.timeout);
+        for (key, value) in &self.headers {
+            client = client.header(key.as_str(), value.as_str());
+        }
+
+        let (tx, rx) = oneshot::channel();
+        let exporter = Exporter(Rc::new(Inner {
+            client: client.finish(),
+            endpoint: self.endpoint,
+            resource: self.resource,
+            stop: RefCell::new(Some(tx)),
+            stopped: Cell::new(false),
+        }));
+
+        let period = self.interval;
+        let inner = exporter.0.clone();
+        crate::fiber::spawn(async move {
+            let mut interval = timer::interval_at(timer::Instant::now() + period, period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+            let mut rx = rx;
+            loop {
+                match select(interval.tick().boxed_local(), rx).await {
+                    Either::Left((_, stop)) => {
+                        rx = stop;
+                        inner.export().await;
+                    }
+                    Either::Right(_) => return,
+                }
+            }
+        });
+        exporter
+    }
+}
+
+/// Handle of the running exporter
+#[derive(Clone)]
+pub struct Exporter(Rc<Inner>);
+
+struct Inner {
+    client: Client,
+    endpoint: String,
+    resource: Vec<(String, String)>,
+    stop: RefCell<Option<oneshot::Sender<()>>>,
+    stopped: Cell<bool>,
+}
+
+impl Exporter {
+    /// Export collected metrics and spans now
+    pub fn flush(&self) -> LocalBoxFuture<'static, ()> {
+        let inner = self.0.clone();
+        async move { inner.export().await }.boxed_local()
+    }
+
+    /// Stop periodic export and export the last batch.
+    ///
+    /// Following calls complete immediately.
+    pub fn shutdown(&self) -> LocalBoxFuture<'static, ()> {
+        let inner = self.0.clone();
+        async move {
+            if inner.stopped.replace(true) {
+                return;
+            }
+            if let Some(tx) = inner.stop.borrow_mut().take() {
+                let _ = tx.send(());
+            }
+            inner.export().await;
+        }
+        .boxed_local()
+    }
+}
+
+impl Inner {
+    async fn export(&self) {
+        if let Some(stats) = timer::stats() {
+            gauge("kayrx.timer.entries", &[]).set(stats.entries() as f64);
+            gauge("kayrx.timer.fired", &[]).set(stats.fired() as f64);
+            gauge("kayrx.timer.max_lateness", &[])
+                .set(stats.max_lateness().as_secs_f64() * 1000.0);
+        }
+
+        let points = metrics::collect();
+        if !points.is_empty() {
+            let body = otlp::metrics(
+                &self.resource,
+                &points,
+                metrics::start_time(),
+                SystemTime::now(),
+            );
+            self.send("/v1/metrics", body).await;
+        }
+
+        let spans = span::drain();
+        if !spans.is_empty() {
+            let body = otlp::traces(&self.resource, &spans);
+            self.send("/v1/traces", body).await;
+        }
+    }
+
+    async fn send(&self, path: &str, body: Value) {
+        let url = format!("{}{}", self.endpoint, path);
+        match self.client.post(&url).send_json(&body).await {
+            Ok(res) if res.status().is_success() => (),
+            Ok(res) => log::warn!("Telemetry export to {} failed: {}", url, res.status()),
+            Err(e) => log::warn!("Telemetry export to {} failed: {}", url, e),
+        }
+    }
+}
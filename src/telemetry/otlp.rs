@@ -0,0 +1,141 @@
+//! OTLP/HTTP JSON encoding of metrics and spans
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+
+use super::metrics::{Point, Value as MetricValue};
+use super::span::{SpanData, SpanKind};
+
+/// Cumulative aggregation temporality
+const CUMULATIVE: u8 = 2;
+
+fn nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH)
+        .map(|dur| dur.as_nanos())
+        .unwrap_or(0)
+        .to_string()
+}
+
+fn attributes<K: AsRef<str>>(attrs: &[(K, String)]) -> Value {
+    Value::Array(
+        attrs
+            .iter()
+            .map(|(key, value)| {
+                json!({ "key": key.as_ref(), "value": { "stringValue": value } })
+            })
+            .collect(),
+    )
+}
+
+fn scope() -> Value {
+    json!({ "name": "kayrx", "version": env!("CARGO_PKG_VERSION") })
+}
+
+/// `ExportMetricsServiceRequest`
+pub(crate) fn metrics(
+    resource: &[(String, String)],
+    points: &[Point],
+    start: SystemTime,
+    now: SystemTime,
+) -> Value {
+    let (start, now) = (nanos(start), nanos(now));
+
+    let metrics: Vec<_> = points
+        .iter()
+        .map(|point| {
+            let attrs = attributes(&point.attributes);
+            match point.value {
+                MetricValue::Counter(val) => json!({
+                    "name": point.name,
+                    "sum": {
+                        "dataPoints": [{
+                            "attributes": attrs,
+                            "startTimeUnixNano": start,
+                            "timeUnixNano": now,
+                            "asInt": val.to_string(),
+                        }],
+                        "aggregationTemporality": CUMULATIVE,
+                        "isMonotonic": true,
+                    }
+                }),
+                MetricValue::Gauge(val) => json!({
+                    "name": point.name,
+                    "gauge": {
+                        "dataPoints": [{
+                            "attributes": attrs,
+                            "timeUnixNano": now,
+                            "asDouble": val,
+                        }]
+                    }
+                }),
+                MetricValue::Histogram {
+                    count,
+                    sum,
+                    ref buckets,
+                    bounds,
+                } => json!({
+                    "name": point.name,
+                    "unit": "ms",
+                    "histogram": {
+                        "dataPoints": [{
+                            "attributes": attrs,
+                            "startTimeUnixNano": start,
+                            "timeUnixNano": now,
+                            "count": count.to_string(),
+                            "sum": sum,
+                            "bucketCounts": buckets
+                                .iter()
+                                .map(|c| c.to_string())
+                                .collect::<Vec<_>>(),
+                            "explicitBounds": bounds,
+                        }],
+                        "aggregationTemporality": CUMULATIVE,
+                    }
+                }),
+            }
+        })
+        .collect();
+
+    json!({
+        "resourceMetrics": [{
+            "resource": { "attributes": attributes(resource) },
+            "scopeMetrics": [{ "scope": scope(), "metrics": metrics }],
+        }]
+    })
+}
+
+/// `ExportTraceServiceRequest`
+pub(crate) fn traces(resource: &[(String, String)], spans: &[SpanData]) -> Value {
+    let spans: Vec<_> = spans
+        .iter()
+        .map(|span| {
+            let mut value = json!({
+                "traceId": format!("{:032x}", span.ctx.trace_id()),
+                "spanId": format!("{:016x}", span.ctx.span_id()),
+                "name": span.name,
+                "kind": match span.kind {
+                    SpanKind::Server => 2,
+                    SpanKind::Client => 3,
+                },
+                "startTimeUnixNano": nanos(span.start),
+                "endTimeUnixNano": nanos(span.end),
+                "attributes": attributes(&span.attributes),
+                "status": { "code": if span.error { 2 } else { 0 } },
+            });
+            if let Some(parent) = span.ctx.parent_id() {
+                value["parentSpanId"] = Value::String(format!("{:016x}", parent));
+            }
+            if let Some(state) = span.ctx.trace_state() {
+                value["traceState"] = Value::String(state.to_owned());
+            }
+            value
+        })
+        .collect();
+
+    json!({
+        "resourceSpans": [{
+            "resource": { "attributes": attributes(resource) },
+            "scopeSpans": [{ "scope": scope(), "spans": spans }],
+        }]
+    })
+}
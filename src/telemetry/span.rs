@@ -0,0 +1,51 @@
+//! Finished spans queue
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use lazy_static::lazy_static;
+
+use crate::web::trace::TraceContext;
+
+/// Max number of spans waiting for export, oldest spans are dropped
+const MAX_QUEUED_SPANS: usize = 2048;
+
+lazy_static! {
+    static ref SPANS: Mutex<VecDeque<SpanData>> = Mutex::new(VecDeque::new());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SpanKind {
+    Server,
+    Client,
+}
+
+/// Finished span
+#[derive(Debug, Clone)]
+pub(crate) struct SpanData {
+    pub(crate) ctx: TraceContext,
+    pub(crate) name: String,
+    pub(crate) kind: SpanKind,
+    pub(crate) start: SystemTime,
+    pub(crate) end: SystemTime,
+    pub(crate) attributes: Vec<(&'static str, String)>,
+    pub(crate) error: bool,
+}
+
+/// Queue finished span for export, spans of not sampled traces are dropped
+pub(crate) fn record(span: SpanData) {
+    if !span.ctx.is_sampled() {
+        return;
+    }
+    let mut spans = SPANS.lock().unwrap();
+    if spans.len() >= MAX_QUEUED_SPANS {
+        spans.pop_front();
+        super::counter("kayrx.telemetry.dropped_spans", &[]).add(1);
+    }
+    spans.push_back(span);
+}
+
+/// Take all queued spans
+pub(crate) fn drain() -> Vec<SpanData> {
+    SPANS.lock().unwrap().drain(..).collect()
+}
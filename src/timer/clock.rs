@@ -1,16 +1,23 @@
 //! Source of time abstraction.
 //!
-//! By default, `std::time::Instant::now()` is used. 
+//! By default, `std::time::Instant::now()` is used. With `sim` feature
+//! runtime uses the pausable clock from `clock_util`.
 
 use crate::timer::Instant;
 
+#[cfg(feature = "sim")]
+pub(crate) use self::clock_util::{now, Clock};
+
+#[cfg(not(feature = "sim"))]
 #[derive(Debug, Clone)]
 pub(crate) struct Clock {}
 
+#[cfg(not(feature = "sim"))]
 pub(crate) fn now() -> Instant {
     Instant::from_std(std::time::Instant::now())
 }
 
+#[cfg(not(feature = "sim"))]
 impl Clock {
    pub(crate) fn new() -> Clock {
        Clock {}
@@ -121,19 +128,19 @@ pub mod clock_util {
     ///
     /// Panics if time is not frozen or if called from outside of the kayrx::krse
     /// runtime.
-    // pub async fn advance(duration: Duration) {
-    //     CLOCK.with(|cell| {
-    //         let ptr = match cell.get() {
-    //             Some(ptr) => ptr,
-    //             None => panic!("time cannot be frozen from outside the kayrx::krse runtime"),
-    //         };
+    pub async fn advance(duration: Duration) {
+        CLOCK.with(|cell| {
+            let ptr = match cell.get() {
+                Some(ptr) => ptr,
+                None => panic!("time cannot be frozen from outside the kayrx::krse runtime"),
+            };
 
-    //         let clock = unsafe { &*ptr };
-    //         clock.advance(duration);
-    //     });
+            let clock = unsafe { &*ptr };
+            clock.advance(duration);
+        });
 
-    //     crate::task::yield_now().await;
-    // }
+        crate::fiber::inner::yield_now().await;
+    }
 
     /// Return the current instant, factoring in frozen time.
     pub(crate) fn now() -> Instant {
@@ -165,10 +172,7 @@ pub mod clock_util {
             }
         }
 
-        // TODO: delete this. Some tests rely on this
-        #[cfg(test)]
-        /// Return a new `Clock` instance that uses the current execution context's
-        /// source of time.
+        /// Return a new `Clock` instance that is frozen at the moment of creation.
         pub(crate) fn new_frozen() -> Clock {
             Clock {
                 inner: Arc::new(Inner {
@@ -178,6 +182,11 @@ pub mod clock_util {
             }
         }
 
+        /// Check if time is frozen
+        pub(crate) fn is_paused(&self) -> bool {
+            self.inner.frozen.lock().unwrap().is_some()
+        }
+
         pub(crate) fn advance(&self, duration: Duration) {
             let mut frozen = self.inner.frozen.lock().unwrap();
 
@@ -1,6 +1,8 @@
 use crate::timer::driver::Registration;
+use crate::timer::instant::FAR_FUTURE;
 use crate::timer::{Duration, Instant};
 
+use std::cmp;
 use std::future::Future;
 use std::pin::Pin;
 use std::task::{self, Poll};
@@ -85,6 +87,30 @@ impl Delay {
     pub fn reset(&mut self, deadline: Instant) {
         self.registration.reset(deadline);
     }
+
+    /// Reset the `Delay` instance to complete after `duration` from now.
+    ///
+    /// Durations longer than the timer can handle are capped at
+    /// `Instant::far_future()`, so it is safe to use for idle timers that
+    /// are bumped on every I/O event.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kayrx::timer::{delay_for, Duration};
+    ///
+    /// #[kayrx::main]
+    /// async fn main() {
+    ///     let mut delay = delay_for(Duration::from_secs(60));
+    ///
+    ///     // activity on connection, extend idle timeout
+    ///     delay.reset_after(Duration::from_millis(10));
+    ///     delay.await;
+    /// }
+    /// ```
+    pub fn reset_after(&mut self, duration: Duration) {
+        self.reset(Instant::now() + cmp::min(duration, FAR_FUTURE));
+    }
 }
 
 impl Future for Delay {
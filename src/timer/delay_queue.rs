@@ -153,6 +153,22 @@ pub struct DelayQueue<T> {
 
     /// Instant at which the timer starts
     start: Instant,
+
+    /// Set once the queue is closed
+    closed: Option<ClosePolicy>,
+}
+
+/// Determines how a closed `DelayQueue` yields remaining items.
+///
+/// See [`DelayQueue::close_with`].
+///
+/// [`DelayQueue::close_with`]: struct.DelayQueue.html#method.close_with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClosePolicy {
+    /// Yield all remaining items immediately, in deadline order.
+    Drain,
+    /// Keep yielding remaining items once their deadlines are reached.
+    Wait,
 }
 
 /// An entry in `DelayQueue` that has expired and removed.
@@ -262,6 +278,7 @@ impl<T> DelayQueue<T> {
             delay: None,
             poll: wheel::Poll::new(0),
             start: Instant::now(),
+            closed: None,
         }
     }
 
@@ -286,7 +303,8 @@ impl<T> DelayQueue<T> {
     ///
     /// # Panics
     ///
-    /// This function panics if `when` is too far in the future.
+    /// This function panics if `when` is too far in the future or if the
+    /// queue is closed.
     ///
     /// # Examples
     ///
@@ -313,6 +331,7 @@ impl<T> DelayQueue<T> {
     /// [`Key`]: struct.Key.html
     /// [type]: #
     pub fn insert_at(&mut self, value: T, when: Instant) -> Key {
+        assert!(self.closed.is_none(), "insert into closed DelayQueue");
         assert!(self.slab.len() < MAX_ENTRIES, "max entries exceeded");
 
         // Normalize the deadline. Values cannot be set to expire in the past.
@@ -352,19 +371,107 @@ impl<T> DelayQueue<T> {
         cx: &mut task::Context<'_>,
     ) -> Poll<Option<Result<Expired<T>, Error>>> {
         let item = ready!(self.poll_idx(cx));
-        Poll::Ready(item.map(|result| {
-            result.map(|idx| {
-                let data = self.slab.remove(idx);
-                debug_assert!(data.next.is_none());
-                debug_assert!(data.prev.is_none());
-
-                Expired {
-                    key: Key::new(idx),
-                    data: data.inner,
-                    deadline: self.start + Duration::from_millis(data.when),
-                }
-            })
-        }))
+        Poll::Ready(item.map(|result| result.map(|idx| self.take_expired(idx))))
+    }
+
+    /// Remove and return the next value whose deadline has already been
+    /// reached.
+    ///
+    /// Unlike [`poll_expired`], this function does not register the current
+    /// task for wakeup, so it can be called in a loop to drain all currently
+    /// expired items. `None` is returned once no expired items are left,
+    /// use [`poll_expired`] to wait for the next one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kayrx::timer::{DelayQueue, Duration};
+    ///
+    /// #[kayrx::main]
+    /// async fn main() {
+    ///     let mut delay_queue = DelayQueue::new();
+    ///     delay_queue.insert("foo", Duration::from_secs(0));
+    ///     delay_queue.insert("bar", Duration::from_secs(60));
+    ///
+    ///     kayrx::timer::delay_for(Duration::from_millis(10)).await;
+    ///
+    ///     let mut expired = Vec::new();
+    ///     while let Some(item) = delay_queue.pop_expired() {
+    ///         expired.push(item.into_inner());
+    ///     }
+    ///     assert_eq!(expired, vec!["foo"]);
+    ///     assert_eq!(delay_queue.len(), 1);
+    /// }
+    /// ```
+    ///
+    /// [`poll_expired`]: #method.poll_expired
+    pub fn pop_expired(&mut self) -> Option<Expired<T>> {
+        use self::wheel::Stack;
+
+        if let Some(idx) = self.expired.pop(&mut self.slab) {
+            return Some(self.take_expired(idx));
+        }
+
+        if self.closed == Some(ClosePolicy::Drain) {
+            return self.drain_idx().map(|idx| self.take_expired(idx));
+        }
+
+        let now = crate::timer::ms(Instant::now() - self.start, crate::timer::Round::Down);
+        self.poll = wheel::Poll::new(cmp::max(now, self.wheel.elapsed()));
+        self.wheel
+            .poll(&mut self.poll, &mut self.slab)
+            .map(|idx| self.take_expired(idx))
+    }
+
+    /// Returns the instant at which the next item in the queue expires,
+    /// without removing it.
+    ///
+    /// Returns `None` if the queue is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kayrx::timer::{DelayQueue, Duration, Instant};
+    ///
+    /// #[kayrx::main]
+    /// async fn main() {
+    ///     let mut delay_queue = DelayQueue::new();
+    ///     assert!(delay_queue.peek().is_none());
+    ///
+    ///     let deadline = Instant::now() + Duration::from_secs(5);
+    ///     delay_queue.insert_at("foo", deadline + Duration::from_secs(5));
+    ///     delay_queue.insert_at("bar", deadline);
+    ///
+    ///     assert!(delay_queue.peek().unwrap() >= deadline);
+    ///     assert!(delay_queue.peek().unwrap() < deadline + Duration::from_secs(1));
+    /// }
+    /// ```
+    pub fn peek(&self) -> Option<Instant> {
+        if let Some(idx) = self.expired.head {
+            return Some(self.start + Duration::from_millis(self.slab[idx].when));
+        }
+
+        // slot deadline is rounded down to the slot range, items of the
+        // slot processed next are earlier than items of any other slot
+        let mut next = self.wheel.next_slot()?.head;
+        let mut when = u64::MAX;
+        while let Some(idx) = next {
+            when = cmp::min(when, self.slab[idx].when);
+            next = self.slab[idx].next;
+        }
+        Some(self.start + Duration::from_millis(when))
+    }
+
+    fn take_expired(&mut self, idx: usize) -> Expired<T> {
+        let data = self.slab.remove(idx);
+        debug_assert!(data.next.is_none());
+        debug_assert!(data.prev.is_none());
+
+        Expired {
+            key: Key::new(idx),
+            data: data.inner,
+            deadline: self.start + Duration::from_millis(data.when),
+        }
     }
 
     /// Insert `value` into the queue set to expire after the requested duration
@@ -389,7 +496,7 @@ impl<T> DelayQueue<T> {
     /// # Panics
     ///
     /// This function panics if `timeout` is greater than the maximum supported
-    /// duration.
+    /// duration or if the queue is closed.
     ///
     /// # Examples
     ///
@@ -609,6 +716,61 @@ impl<T> DelayQueue<T> {
         self.delay = None;
     }
 
+    /// Close the queue, remaining items are yielded immediately.
+    ///
+    /// This is the same as `close_with(ClosePolicy::Drain)`. After closing,
+    /// [`poll_expired`] and [`pop_expired`] return the remaining items in
+    /// deadline order without waiting for deadlines and the stream ends
+    /// once the queue is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kayrx::timer::{DelayQueue, Duration};
+    /// use futures::StreamExt;
+    ///
+    /// #[kayrx::main]
+    /// async fn main() {
+    ///     let mut delay_queue = DelayQueue::new();
+    ///     delay_queue.insert("foo", Duration::from_secs(3600));
+    ///     delay_queue.insert("bar", Duration::from_secs(60));
+    ///
+    ///     delay_queue.close();
+    ///
+    ///     let bar = delay_queue.next().await.unwrap().unwrap();
+    ///     assert_eq!(*bar.get_ref(), "bar");
+    ///     let foo = delay_queue.next().await.unwrap().unwrap();
+    ///     assert_eq!(*foo.get_ref(), "foo");
+    ///     assert!(delay_queue.next().await.is_none());
+    /// }
+    /// ```
+    ///
+    /// [`poll_expired`]: #method.poll_expired
+    /// [`pop_expired`]: #method.pop_expired
+    pub fn close(&mut self) {
+        self.close_with(ClosePolicy::Drain)
+    }
+
+    /// Close the queue with specified policy.
+    ///
+    /// Closed queue does not accept new items, `insert` and `insert_at` panic.
+    /// Already queued items can still be removed or reset. Once all items are
+    /// yielded, [`poll_expired`] returns `None`.
+    ///
+    /// [`poll_expired`]: #method.poll_expired
+    pub fn close_with(&mut self, policy: ClosePolicy) {
+        self.closed = Some(policy);
+
+        if policy == ClosePolicy::Drain {
+            self.delay = None;
+        }
+    }
+
+    /// Returns `true` if the queue is closed.
+    pub fn is_closed(&self) -> bool {
+        self.closed.is_some()
+    }
+
     /// Returns the number of elements the queue can hold without reallocating.
     ///
     /// # Examples
@@ -717,6 +879,10 @@ impl<T> DelayQueue<T> {
             return Poll::Ready(expired.map(Ok));
         }
 
+        if self.closed == Some(ClosePolicy::Drain) {
+            return Poll::Ready(self.drain_idx().map(Ok));
+        }
+
         loop {
             if let Some(ref mut delay) = self.delay {
                 if !delay.is_elapsed() {
@@ -725,7 +891,8 @@ impl<T> DelayQueue<T> {
 
                 let now = crate::timer::ms(delay.deadline() - self.start, crate::timer::Round::Down);
 
-                self.poll = wheel::Poll::new(now);
+                // wheel may already be advanced further by `pop_expired`
+                self.poll = wheel::Poll::new(cmp::max(now, self.wheel.elapsed()));
             }
 
             self.delay = None;
@@ -742,6 +909,21 @@ impl<T> DelayQueue<T> {
         }
     }
 
+    /// Returns the index of the next item in the wheel regardless of its
+    /// deadline.
+    fn drain_idx(&mut self) -> Option<usize> {
+        self.delay = None;
+
+        while let Some(poll_at) = self.wheel.poll_at() {
+            self.poll = wheel::Poll::new(cmp::max(poll_at, self.wheel.elapsed()));
+
+            if let Some(idx) = self.wheel.poll(&mut self.poll, &mut self.slab) {
+                return Some(idx);
+            }
+        }
+        None
+    }
+
     fn normalize_deadline(&self, when: Instant) -> u64 {
         let when = if when < self.start {
             0
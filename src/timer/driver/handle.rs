@@ -68,6 +68,11 @@ impl Handle {
         })
     }
 
+    /// Get a handle to the current timer, if there is one.
+    pub(crate) fn try_current() -> Option<Self> {
+        CURRENT_TIMER.with(|current| current.borrow().clone())
+    }
+
     /// Try to return a strong ref to the inner
     pub(crate) fn inner(&self) -> Option<Arc<Inner>> {
         self.inner.upgrade()
@@ -15,16 +15,27 @@ pub(crate) use self::registration::Registration;
 mod stack;
 use self::stack::Stack;
 
-use std::sync::atomic::{AtomicU64, AtomicUsize};
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize};
 use crate::krse::thread::{Park, Unpark};
-use crate::timer::{wheel, Error};
+use crate::timer::{wheel, Error, TimerStats};
 use crate::timer::{Clock, Duration, Instant};
 
-use std::sync::atomic::Ordering::SeqCst;
+use std::cell::Cell;
+use std::sync::atomic::Ordering::{Relaxed, SeqCst};
 use std::sync::Arc;
 use std::usize;
 use std::{cmp, fmt};
 
+thread_local! {
+    /// Instant of the last timer turn on the current thread
+    static RECENT: Cell<Option<Instant>> = Cell::new(None);
+}
+
+/// Returns instant of the last turn of the timer driven by current thread.
+pub(crate) fn recent() -> Option<Instant> {
+    RECENT.with(|recent| recent.get())
+}
+
 /// Time implementation that drives [`Delay`], [`Interval`], and [`Timeout`].
 ///
 /// A `Driver` instance tracks the state necessary for managing time and
@@ -104,6 +115,18 @@ pub(crate) struct Inner {
     /// Head of the "process" linked list.
     process: AtomicStack,
 
+    /// Number of fired timeouts
+    fired: AtomicU64,
+
+    /// Sum of lateness of fired timeouts in milliseconds
+    lateness: AtomicU64,
+
+    /// Max lateness of fired timeouts in milliseconds
+    max_lateness: AtomicU64,
+
+    /// Occupied slots of wheel levels
+    levels: [AtomicU32; wheel::NUM_LEVELS],
+
     /// Unparks the timer thread.
     unpark: Box<dyn Unpark>,
 }
@@ -149,11 +172,12 @@ where
 
     /// Run timer related logic
     fn process(&mut self) {
-        let now = crate::timer::ms(
-            self.clock.now() - self.inner.start,
-            crate::timer::Round::Down,
-        );
+        let now = self.clock.now();
+        RECENT.with(|recent| recent.set(Some(now)));
+
+        let now = crate::timer::ms(now - self.inner.start, crate::timer::Round::Down);
         let mut poll = wheel::Poll::new(now);
+        let (mut fired, mut lateness, mut max_lateness) = (0, 0, 0);
 
         while let Some(entry) = self.wheel.poll(&mut poll, &mut ()) {
             let when = entry.when_internal().expect("invalid internal entry state");
@@ -163,10 +187,27 @@ where
 
             // Track that the entry has been fired
             entry.set_when_internal(None);
+
+            let late = now.saturating_sub(when);
+            fired += 1;
+            lateness += late;
+            max_lateness = cmp::max(max_lateness, late);
         }
 
         // Update the elapsed cache
         self.inner.elapsed.store(self.wheel.elapsed(), SeqCst);
+
+        // Update statistics
+        if fired != 0 {
+            self.inner.fired.fetch_add(fired, Relaxed);
+            self.inner.lateness.fetch_add(lateness, Relaxed);
+            if max_lateness > self.inner.max_lateness.load(Relaxed) {
+                self.inner.max_lateness.store(max_lateness, Relaxed);
+            }
+        }
+        for (slots, val) in self.wheel.occupancy().iter().zip(self.inner.levels.iter()) {
+            val.store(*slots, Relaxed);
+        }
     }
 
     /// Process the entry queue
@@ -194,6 +235,23 @@ where
         }
     }
 
+    /// Park the thread for the duration.
+    ///
+    /// If time is paused, ready events are processed without blocking and
+    /// the clock is advanced by the duration instead, so the next timer
+    /// fires immediately.
+    fn park_for(&mut self, duration: Duration) -> Result<(), T::Error> {
+        #[cfg(feature = "sim")]
+        {
+            if self.clock.is_paused() {
+                self.park.park_timeout(Duration::from_secs(0))?;
+                self.clock.advance(duration);
+                return Ok(());
+            }
+        }
+        self.park.park_timeout(duration)
+    }
+
     fn clear_entry(&mut self, entry: &Arc<Entry>) {
         self.wheel.remove(entry, &mut ());
         entry.set_when_internal(None);
@@ -245,7 +303,7 @@ where
                 let deadline = self.expiration_instant(when);
 
                 if deadline > now {
-                    self.park.park_timeout(deadline - now)?;
+                    self.park_for(deadline - now)?;
                 } else {
                     self.park.park_timeout(Duration::from_secs(0))?;
                 }
@@ -269,13 +327,13 @@ where
                 let deadline = self.expiration_instant(when);
 
                 if deadline > now {
-                    self.park.park_timeout(cmp::min(deadline - now, duration))?;
+                    self.park_for(cmp::min(deadline - now, duration))?;
                 } else {
                     self.park.park_timeout(Duration::from_secs(0))?;
                 }
             }
             None => {
-                self.park.park_timeout(duration)?;
+                self.park_for(duration)?;
             }
         }
 
@@ -310,11 +368,33 @@ impl Inner {
             num: AtomicUsize::new(0),
             elapsed: AtomicU64::new(0),
             process: AtomicStack::new(),
+            fired: AtomicU64::new(0),
+            lateness: AtomicU64::new(0),
+            max_lateness: AtomicU64::new(0),
+            levels: Default::default(),
             start,
             unpark,
         }
     }
 
+    /// Snapshot of timer statistics
+    pub(crate) fn stats(&self) -> TimerStats {
+        let fired = self.fired.load(Relaxed);
+        let lateness = self.lateness.load(Relaxed);
+        let mut levels = [0; wheel::NUM_LEVELS];
+        for (val, slots) in self.levels.iter().zip(levels.iter_mut()) {
+            *slots = val.load(Relaxed);
+        }
+
+        TimerStats {
+            entries: self.num.load(Relaxed),
+            fired,
+            max_lateness: Duration::from_millis(self.max_lateness.load(Relaxed)),
+            mean_lateness: Duration::from_millis(if fired == 0 { 0 } else { lateness / fired }),
+            levels,
+        }
+    }
+
     fn elapsed(&self) -> u64 {
         self.elapsed.load(SeqCst)
     }
@@ -4,6 +4,10 @@ use std::fmt;
 use std::ops;
 use std::time::Duration;
 
+/// Distance of `Instant::far_future()` from now, about two years. Timer
+/// can not handle deadlines further than that.
+pub(crate) const FAR_FUTURE: Duration = Duration::from_secs(86400 * 365 * 2);
+
 /// A measurement of the system clock, useful for talking to
 /// external entities like the file system or other processes.
 #[derive(Clone, Copy, Eq, PartialEq, PartialOrd)]
@@ -25,6 +29,26 @@ impl Instant {
         variant::now()
     }
 
+    /// Returns an instant far in the future.
+    ///
+    /// Useful as a deadline of a timer that should not fire until it is
+    /// reset, it is still accepted by the timer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kayrx::timer::{delay_until, Instant};
+    ///
+    /// #[kayrx::main]
+    /// async fn main() {
+    ///     let mut delay = delay_until(Instant::far_future());
+    ///     assert!(!delay.is_elapsed());
+    /// }
+    /// ```
+    pub fn far_future() -> Instant {
+        Instant::now() + FAR_FUTURE
+    }
+
     /// Create a `kayrx::timer::Instant` from a `std::time::Instant`.
     pub fn from_std(std: std::time::Instant) -> Instant {
         Instant { std }
@@ -180,10 +204,20 @@ impl fmt::Debug for Instant {
     }
 }
 
+#[cfg(not(feature = "sim"))]
 mod variant {
     use super::Instant;
 
     pub(super) fn now() -> Instant {
         Instant::from_std(std::time::Instant::now())
     }
+}
+
+#[cfg(feature = "sim")]
+mod variant {
+    use super::Instant;
+
+    pub(super) fn now() -> Instant {
+        crate::timer::clock::now()
+    }
 }
\ No newline at end of file
@@ -81,6 +81,54 @@ pub fn interval_at(start: Instant, period: Duration) -> Interval {
     Interval {
         delay: delay_until(start),
         period,
+        missed_tick_behavior: MissedTickBehavior::default(),
+    }
+}
+
+/// Ticks that are late by less than this are not considered missed
+const MISSED_TICK_THRESHOLD: Duration = Duration::from_millis(5);
+
+/// Defines the behavior of an [`Interval`](struct.Interval.html) when it
+/// misses a tick, i.e. the task was blocked or did not poll the interval
+/// for longer than `period`.
+///
+/// Examples use interval with `period` of 10ms, started at `0ms`, that was
+/// stalled until `35ms`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissedTickBehavior {
+    /// Ticks as fast as possible until caught up, then keeps the original
+    /// schedule: `35ms, 35ms, 35ms, 40ms, 50ms`.
+    ///
+    /// This is the default behavior.
+    Burst,
+
+    /// Re-anchors the schedule to the time of the late tick:
+    /// `35ms, 45ms, 55ms`.
+    Delay,
+
+    /// Skips missed ticks and continues on the original schedule:
+    /// `35ms, 40ms, 50ms`.
+    Skip,
+}
+
+impl Default for MissedTickBehavior {
+    fn default() -> Self {
+        MissedTickBehavior::Burst
+    }
+}
+
+impl MissedTickBehavior {
+    /// Deadline of the next tick after tick scheduled at `timeout`
+    /// completed late at `now`
+    fn next_timeout(self, timeout: Instant, now: Instant, period: Duration) -> Instant {
+        match self {
+            MissedTickBehavior::Burst => timeout + period,
+            MissedTickBehavior::Delay => now + period,
+            MissedTickBehavior::Skip => {
+                let late = (now - timeout).as_nanos() % period.as_nanos();
+                now + period - Duration::from_nanos(late as u64)
+            }
+        }
     }
 }
 
@@ -92,6 +140,9 @@ pub struct Interval {
 
     /// The duration between values yielded by `Interval`.
     period: Duration,
+
+    /// What to do with missed ticks
+    missed_tick_behavior: MissedTickBehavior,
 }
 
 impl Interval {
@@ -101,15 +152,21 @@ impl Interval {
         ready!(Pin::new(&mut self.delay).poll(cx));
 
         // Get the `now` by looking at the `delay` deadline
-        let now = self.delay.deadline();
+        let timeout = self.delay.deadline();
+        let now = Instant::now();
 
         // The next interval value is `duration` after the one that just
-        // yielded.
-        let next = now + self.period;
+        // yielded, unless the tick is late and missed ticks are not burst.
+        let next = if now > timeout + MISSED_TICK_THRESHOLD {
+            self.missed_tick_behavior
+                .next_timeout(timeout, now, self.period)
+        } else {
+            timeout + self.period
+        };
         self.delay.reset(next);
 
-        // Return the current instant
-        Poll::Ready(now)
+        // Return the scheduled instant
+        Poll::Ready(timeout)
     }
 
     /// Completes when the next instant in the interval has been reached.
@@ -135,6 +192,81 @@ impl Interval {
     pub async fn tick(&mut self) -> Instant {
         poll_fn(|cx| self.poll_tick(cx)).await
     }
+
+    /// Restarts the interval from now, the next tick completes after one
+    /// `period`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kayrx::timer::{self, Duration, Instant};
+    ///
+    /// #[kayrx::main]
+    /// async fn main() {
+    ///     let mut interval = timer::interval(Duration::from_millis(50));
+    ///     interval.tick().await;
+    ///
+    ///     timer::delay_for(Duration::from_millis(20)).await;
+    ///     interval.reset();
+    ///
+    ///     let start = Instant::now();
+    ///     interval.tick().await;
+    ///     // approximately 50ms have elapsed since reset.
+    ///     assert!(start.elapsed() >= Duration::from_millis(40));
+    /// }
+    /// ```
+    pub fn reset(&mut self) {
+        self.delay.reset(Instant::now() + self.period);
+    }
+
+    /// Reschedules the next tick to complete at `at`, following ticks keep
+    /// the configured `period` after it.
+    pub fn reset_at(&mut self, at: Instant) {
+        self.delay.reset(at);
+    }
+
+    /// Changes the duration between ticks.
+    ///
+    /// The tick that is already scheduled is not affected, the new `period`
+    /// applies to the ticks after it, so the phase of the interval is kept.
+    /// Call [`reset`](#method.reset) to apply the new period immediately.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `period` is zero.
+    pub fn set_period(&mut self, period: Duration) {
+        assert!(period > Duration::new(0, 0), "`period` must be non-zero.");
+        self.period = period;
+    }
+
+    /// Returns the duration between ticks.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Sets the behavior of the interval when it misses a tick.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kayrx::timer::{self, Duration, MissedTickBehavior};
+    ///
+    /// #[kayrx::main]
+    /// async fn main() {
+    ///     let mut interval = timer::interval(Duration::from_secs(10));
+    ///     // report metrics every 10 seconds, without catching up
+    ///     interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    ///     interval.tick().await;
+    /// }
+    /// ```
+    pub fn set_missed_tick_behavior(&mut self, behavior: MissedTickBehavior) {
+        self.missed_tick_behavior = behavior;
+    }
+
+    /// Returns the behavior of the interval when it misses a tick.
+    pub fn missed_tick_behavior(&self) -> MissedTickBehavior {
+        self.missed_tick_behavior
+    }
 }
 
 impl futures_core::stream::Stream for Interval {
@@ -59,24 +59,54 @@
 //! ```
 
 pub mod delay_queue;
+pub mod stream;
 
 pub use std::time::Duration;
-pub use clock::clock_util::{pause, resume};
+pub use clock::clock_util::{advance, pause, resume};
 #[doc(inline)]
-pub use delay_queue::DelayQueue;
+pub use delay_queue::{ClosePolicy, DelayQueue};
 pub use delay::{delay_for, delay_until, Delay};
 pub use error::Error;
 pub use self::instant::Instant;
-pub use interval::{interval, interval_at, Interval};
+pub use interval::{interval, interval_at, Interval, MissedTickBehavior};
 #[doc(inline)]
 pub use timeout::{timeout, timeout_at, Elapsed, Timeout};
 pub use throttle::{throttle, Throttle};
+#[doc(inline)]
+pub use stream::StreamTimeoutExt;
+pub use stats::{stats, TimerStats};
+
+/// Returns a coarse "now" cached by the timer.
+///
+/// The value is refreshed every time the timer driver of the current
+/// thread turns, so it could lag behind the real time by the time spent
+/// processing events since the last turn. It is cheap enough for hot paths
+/// like keep-alive checks, where `Instant::now()` per request is
+/// noticeable. Outside of the kayrx runtime `Instant::now()` is returned.
+///
+/// # Examples
+///
+/// ```
+/// use kayrx::timer::{self, Duration, Instant};
+///
+/// #[kayrx::main]
+/// async fn main() {
+///     timer::delay_for(Duration::from_millis(10)).await;
+///
+///     let recent = timer::recent();
+///     assert!(recent <= Instant::now());
+/// }
+/// ```
+pub fn recent() -> Instant {
+    driver::recent().unwrap_or_else(Instant::now)
+}
 
 mod clock;
 mod error;
 mod delay;
 mod instant;
 mod interval;
+mod stats;
 mod throttle;
 mod timeout;
 mod wheel;
@@ -0,0 +1,73 @@
+use crate::timer::driver::Handle;
+use crate::timer::wheel::NUM_LEVELS;
+use crate::timer::Duration;
+
+/// Statistics of the timer driver.
+///
+/// Timers firing later than requested are the early sign of an overloaded
+/// event loop, when the thread is busy the timer driver is not turned in
+/// time. Use [`stats`] to get statistics of the current thread's timer.
+///
+/// [`stats`]: fn.stats.html
+#[derive(Debug, Clone, Copy)]
+pub struct TimerStats {
+    pub(crate) entries: usize,
+    pub(crate) fired: u64,
+    pub(crate) max_lateness: Duration,
+    pub(crate) mean_lateness: Duration,
+    pub(crate) levels: [u32; NUM_LEVELS],
+}
+
+impl TimerStats {
+    /// Number of active timers.
+    pub fn entries(&self) -> usize {
+        self.entries
+    }
+
+    /// Number of fired timers since the timer start.
+    pub fn fired(&self) -> u64 {
+        self.fired
+    }
+
+    /// Max delay between the deadline and the actual fire time.
+    pub fn max_lateness(&self) -> Duration {
+        self.max_lateness
+    }
+
+    /// Mean delay between the deadline and the actual fire time.
+    pub fn mean_lateness(&self) -> Duration {
+        self.mean_lateness
+    }
+
+    /// Number of occupied slots at every level of the timer wheel.
+    ///
+    /// Level 0 slots are 1 millisecond long, every next level slots are
+    /// 64 times longer.
+    pub fn level_occupancy(&self) -> &[u32] {
+        &self.levels
+    }
+}
+
+/// Returns statistics of the timer of the current thread.
+///
+/// Returns `None` if called outside of the kayrx runtime.
+///
+/// # Examples
+///
+/// ```
+/// use kayrx::timer::{self, Duration};
+///
+/// #[kayrx::main]
+/// async fn main() {
+///     timer::delay_for(Duration::from_millis(10)).await;
+///
+///     let stats = timer::stats().unwrap();
+///     assert!(stats.fired() >= 1);
+///     println!("max timer lateness: {:?}", stats.max_lateness());
+/// }
+/// ```
+pub fn stats() -> Option<TimerStats> {
+    Handle::try_current()
+        .and_then(|handle| handle.inner())
+        .map(|inner| inner.stats())
+}
@@ -0,0 +1,123 @@
+//! Stream adapters with time limits.
+//!
+//! See [`StreamTimeoutExt`] documentation for more details.
+//!
+//! [`StreamTimeoutExt`]: trait.StreamTimeoutExt.html
+
+use futures_core::Stream;
+use crate::timer::{Delay, Duration, Elapsed, Instant};
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{self, Poll};
+
+use pin_project_lite::pin_project;
+
+/// An extension trait for `Stream`s that provides time limits.
+pub trait StreamTimeoutExt: Stream {
+    /// Require every item of the stream to arrive before the specified
+    /// duration has elapsed since the previous item.
+    ///
+    /// The deadline starts when the stream is created and resets on every
+    /// item. If the gap between items exceeds the duration, `Err(Elapsed)`
+    /// is yielded once per gap and the stream continues to wait for the next
+    /// item, so caller decides whether to keep reading or drop the stream.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use futures::stream::{self, StreamExt};
+    /// use kayrx::timer::StreamTimeoutExt;
+    ///
+    /// # async fn dox() {
+    /// let mut items = stream::iter(vec![1, 2, 3]).timeout(Duration::from_secs(1));
+    ///
+    /// while let Some(item) = items.next().await {
+    ///     match item {
+    ///         Ok(item) => println!("got {}", item),
+    ///         Err(_) => {
+    ///             println!("no items within 1 second");
+    ///             break;
+    ///         }
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    fn timeout(self, duration: Duration) -> Timeout<Self>
+    where
+        Self: Sized,
+    {
+        Timeout {
+            stream: self,
+            deadline: Delay::new_timeout(Instant::now() + duration, duration),
+            duration,
+            poll_deadline: true,
+        }
+    }
+}
+
+impl<S: Stream + ?Sized> StreamTimeoutExt for S {}
+
+pin_project! {
+    /// Stream returned by the [`timeout`](trait.StreamTimeoutExt.html#method.timeout)
+    /// method.
+    #[derive(Debug)]
+    #[must_use = "streams do nothing unless polled"]
+    pub struct Timeout<S> {
+        #[pin]
+        stream: S,
+        deadline: Delay,
+        duration: Duration,
+        // Set to false after elapsed error, until the next item.
+        poll_deadline: bool,
+    }
+}
+
+impl<S> Timeout<S> {
+    /// Gets a reference to the underlying stream.
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /// Gets a mutable reference to the underlying stream.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    /// Consumes this timeout, returning the underlying stream.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S: Stream> Stream for Timeout<S> {
+    type Item = Result<S::Item, Elapsed>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+
+        if let Poll::Ready(item) = this.stream.poll_next(cx) {
+            if item.is_some() {
+                this.deadline.reset(Instant::now() + *this.duration);
+                *this.poll_deadline = true;
+            }
+            return Poll::Ready(item.map(Ok));
+        }
+
+        if *this.poll_deadline {
+            if let Poll::Ready(()) = Pin::new(this.deadline).poll(cx) {
+                *this.poll_deadline = false;
+                return Poll::Ready(Some(Err(Elapsed::new())));
+            }
+        }
+        Poll::Pending
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.stream.size_hint();
+
+        // every item could be preceded by an elapsed error
+        (lower, upper.and_then(|upper| upper.checked_mul(2)?.checked_add(1)))
+    }
+}
@@ -161,6 +161,12 @@ where
 
 // ===== impl Elapsed =====
 
+impl Elapsed {
+    pub(crate) fn new() -> Self {
+        Elapsed(())
+    }
+}
+
 impl fmt::Display for Elapsed {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         "deadline has elapsed".fmt(fmt)
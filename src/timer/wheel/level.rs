@@ -124,6 +124,11 @@ impl<T: Stack> Level<T> {
 
     /// Finds the slot that needs to be processed next and returns the slot and
     /// `Instant` at which this slot must be processed.
+    /// Number of slots with entries
+    pub(crate) fn occupied_slots(&self) -> u32 {
+        self.occupied.count_ones()
+    }
+
     pub(crate) fn next_expiration(&self, now: u64) -> Option<Expiration> {
         // Use the `occupied` bit field to get the index of the next slot that
         // needs to be processed.
@@ -173,6 +178,10 @@ impl<T: Stack> Level<T> {
         Some(slot)
     }
 
+    pub(crate) fn slot(&self, slot: usize) -> &T {
+        &self.slot[slot]
+    }
+
     pub(crate) fn add_entry(&mut self, when: u64, item: T::Owned, store: &mut T::Store) {
         let slot = slot_for(when, self.level);
 
@@ -40,7 +40,7 @@ pub(crate) struct Wheel<T> {
 /// Number of levels. Each level has 64 slots. By using 6 levels with 64 slots
 /// each, the timer is able to track time up to 2 years into the future with a
 /// precision of 1 millisecond.
-const NUM_LEVELS: usize = 6;
+pub(crate) const NUM_LEVELS: usize = 6;
 
 /// The maximum duration of a delay
 const MAX_DURATION: u64 = 1 << (6 * NUM_LEVELS);
@@ -131,11 +131,26 @@ where
         self.levels[level].remove_entry(when, item, store);
     }
 
+    /// Number of occupied slots at every level
+    pub(crate) fn occupancy(&self) -> [u32; NUM_LEVELS] {
+        let mut res = [0; NUM_LEVELS];
+        for (level, val) in self.levels.iter().zip(res.iter_mut()) {
+            *val = level.occupied_slots();
+        }
+        res
+    }
+
     /// Instant at which to poll
     pub(crate) fn poll_at(&self) -> Option<u64> {
         self.next_expiration().map(|expiration| expiration.deadline)
     }
 
+    /// Slot that is processed next
+    pub(crate) fn next_slot(&self) -> Option<&T> {
+        self.next_expiration()
+            .map(|expiration| self.levels[expiration.level].slot(expiration.slot))
+    }
+
     pub(crate) fn poll(&mut self, poll: &mut Poll, store: &mut T::Store) -> Option<T::Owned> {
         loop {
             if poll.expiration.is_none() {
@@ -0,0 +1,423 @@
+//! Service that adapts its concurrency limit to the observed latency.
+//!
+//! Requests over the current limit are rejected immediately with
+//! `Overloaded` error, so an overloaded downstream is protected without
+//! manually tuned static limits.
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use std::{cmp, fmt};
+use futures_util::future::{ok, Ready};
+
+use crate::service::{IntoService, Service, Transform};
+use crate::timer::Instant;
+
+/// Concurrency limit exceeded error
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Overloaded;
+
+impl fmt::Display for Overloaded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Concurrency limit exceeded")
+    }
+}
+
+/// Algorithm that computes new concurrency limit from latency samples
+pub trait LimitAlgorithm {
+    /// Compute new limit after a request completed.
+    ///
+    /// `rtt` is the latency of the request, `in_flight` is the number of
+    /// requests in flight when it was started and `success` is false if
+    /// the service returned an error.
+    fn update(
+        &mut self,
+        limit: usize,
+        rtt: Duration,
+        in_flight: usize,
+        success: bool,
+    ) -> usize;
+}
+
+/// Additive increase, multiplicative decrease.
+///
+/// Limit grows by `increase` while requests complete successfully within
+/// the latency threshold and is multiplied by `backoff` otherwise.
+#[derive(Debug, Clone)]
+pub struct Aimd {
+    increase: usize,
+    backoff: f64,
+    threshold: Duration,
+}
+
+impl Default for Aimd {
+    fn default() -> Self {
+        Aimd {
+            increase: 1,
+            backoff: 0.9,
+            threshold: Duration::from_secs(1),
+        }
+    }
+}
+
+impl Aimd {
+    /// Set limit increment. By default limit grows by 1.
+    pub fn increase(mut self, increase: usize) -> Self {
+        self.increase = increase;
+        self
+    }
+
+    /// Set backoff ratio, must be in `0.5..1.0` range. By default it is 0.9.
+    pub fn backoff(mut self, backoff: f64) -> Self {
+        assert!(
+            backoff >= 0.5 && backoff < 1.0,
+            "Backoff ratio must be in 0.5..1.0 range"
+        );
+        self.backoff = backoff;
+        self
+    }
+
+    /// Set latency threshold, slower requests are treated as failures.
+    /// By default it is 1 second.
+    pub fn threshold(mut self, threshold: Duration) -> Self {
+        self.threshold = threshold;
+        self
+    }
+}
+
+impl LimitAlgorithm for Aimd {
+    fn update(
+        &mut self,
+        limit: usize,
+        rtt: Duration,
+        in_flight: usize,
+        success: bool,
+    ) -> usize {
+        if !success || rtt > self.threshold {
+            (limit as f64 * self.backoff) as usize
+        } else if in_flight * 2 >= limit {
+            // grow only if the limit is actually used
+            limit + self.increase
+        } else {
+            limit
+        }
+    }
+}
+
+/// Gradient algorithm.
+///
+/// Compares short term latency with the long term average: limit is
+/// reduced proportionally while latency grows (queueing in the downstream)
+/// and grows by a small queue allowance otherwise.
+#[derive(Debug, Clone)]
+pub struct Gradient {
+    smoothing: f64,
+    tolerance: f64,
+    window: usize,
+    long_rtt: Option<f64>,
+}
+
+impl Default for Gradient {
+    fn default() -> Self {
+        Gradient {
+            smoothing: 0.2,
+            tolerance: 1.5,
+            window: 600,
+            long_rtt: None,
+        }
+    }
+}
+
+impl Gradient {
+    /// Set smoothing factor for limit changes, `0.0..=1.0`. By default it is 0.2.
+    pub fn smoothing(mut self, smoothing: f64) -> Self {
+        assert!(smoothing > 0.0 && smoothing <= 1.0);
+        self.smoothing = smoothing;
+        self
+    }
+
+    /// Set tolerated ratio of short term to long term latency before
+    /// the limit is reduced. By default it is 1.5.
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        assert!(tolerance >= 1.0);
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Set number of samples in the long term latency average.
+    /// By default it is 600.
+    pub fn window(mut self, window: usize) -> Self {
+        self.window = cmp::max(window, 1);
+        self
+    }
+}
+
+impl LimitAlgorithm for Gradient {
+    fn update(
+        &mut self,
+        limit: usize,
+        rtt: Duration,
+        in_flight: usize,
+        success: bool,
+    ) -> usize {
+        let rtt = rtt.as_secs_f64();
+        let long_rtt = match self.long_rtt {
+            Some(long_rtt) => {
+                let factor = 2.0 / (self.window as f64 + 1.0);
+                long_rtt * (1.0 - factor) + rtt * factor
+            }
+            None => rtt,
+        };
+        self.long_rtt = Some(long_rtt);
+
+        // do not grow the limit if it is not used
+        if success && in_flight * 2 < limit {
+            return limit;
+        }
+
+        let gradient = if rtt > 0.0 {
+            (self.tolerance * long_rtt / rtt).max(0.5).min(1.0)
+        } else {
+            1.0
+        };
+        let gradient = if success { gradient } else { gradient.min(0.9) };
+        let limit = limit as f64;
+        let new_limit = limit * gradient + limit.sqrt();
+        (limit * (1.0 - self.smoothing) + new_limit * self.smoothing).round() as usize
+    }
+}
+
+/// Adaptive concurrency limit.
+///
+/// Number of requests in flight is limited by a limit that is continuously
+/// adjusted by the `LimitAlgorithm` from the observed latency. Requests over
+/// the limit fail immediately with `Overloaded` error, so the service error
+/// type has to be convertible from it. Both `kayrx::http::Error` (*503
+/// Service Unavailable*) and client's `ConnectError` are, so the transform
+/// could be used as web middleware and with the client connector.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use kayrx::util::adaptive::{Aimd, AdaptiveConcurrency};
+/// use kayrx::web::{self, App, HttpResponse};
+///
+/// let app = App::new().service(
+///     web::resource("/")
+///         .wrap(AdaptiveConcurrency::new(
+///             Aimd::default().threshold(Duration::from_millis(200)),
+///         ))
+///         .to(|| HttpResponse::Ok()),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct AdaptiveConcurrency<A> {
+    algorithm: A,
+    initial: usize,
+    min: usize,
+    max: usize,
+}
+
+impl<A: LimitAlgorithm + Clone> AdaptiveConcurrency<A> {
+    /// Create adaptive limit with provided algorithm
+    pub fn new(algorithm: A) -> Self {
+        AdaptiveConcurrency {
+            algorithm,
+            initial: 20,
+            min: 1,
+            max: 1000,
+        }
+    }
+
+    /// Set initial limit. By default it is 20.
+    pub fn initial_limit(mut self, limit: usize) -> Self {
+        self.initial = limit;
+        self
+    }
+
+    /// Set lower bound of the limit. By default it is 1.
+    pub fn min_limit(mut self, limit: usize) -> Self {
+        self.min = cmp::max(limit, 1);
+        self
+    }
+
+    /// Set upper bound of the limit. By default it is 1000.
+    pub fn max_limit(mut self, limit: usize) -> Self {
+        self.max = limit;
+        self
+    }
+}
+
+impl<S, A> Transform<S> for AdaptiveConcurrency<A>
+where
+    S: Service,
+    S::Error: From<Overloaded>,
+    A: LimitAlgorithm + Clone,
+{
+    type Request = S::Request;
+    type Response = S::Response;
+    type Error = S::Error;
+    type InitError = ();
+    type Transform = AdaptiveConcurrencyService<S, A>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(AdaptiveConcurrencyService::new(self.clone(), service))
+    }
+}
+
+struct State<A> {
+    algorithm: A,
+    limit: usize,
+    min: usize,
+    max: usize,
+    in_flight: usize,
+}
+
+/// Service with adaptive concurrency limit
+pub struct AdaptiveConcurrencyService<S, A> {
+    service: S,
+    state: Rc<RefCell<State<A>>>,
+}
+
+impl<S, A> AdaptiveConcurrencyService<S, A>
+where
+    S: Service,
+    S::Error: From<Overloaded>,
+    A: LimitAlgorithm,
+{
+    pub fn new<U>(cfg: AdaptiveConcurrency<A>, service: U) -> Self
+    where
+        U: IntoService<S>,
+    {
+        let max = cmp::max(cfg.max, cfg.min);
+        AdaptiveConcurrencyService {
+            service: service.into_service(),
+            state: Rc::new(RefCell::new(State {
+                algorithm: cfg.algorithm,
+                limit: cmp::min(cmp::max(cfg.initial, cfg.min), max),
+                min: cfg.min,
+                max,
+                in_flight: 0,
+            })),
+        }
+    }
+
+    /// Current concurrency limit
+    pub fn limit(&self) -> usize {
+        self.state.borrow().limit
+    }
+
+    /// Number of requests in flight
+    pub fn in_flight(&self) -> usize {
+        self.state.borrow().in_flight
+    }
+}
+
+impl<S, A> Clone for AdaptiveConcurrencyService<S, A>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        AdaptiveConcurrencyService {
+            service: self.service.clone(),
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<S, A> Service for AdaptiveConcurrencyService<S, A>
+where
+    S: Service,
+    S::Error: From<Overloaded>,
+    A: LimitAlgorithm,
+{
+    type Request = S::Request;
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = AdaptiveConcurrencyResponse<S, A>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: S::Request) -> Self::Future {
+        let in_flight = {
+            let mut state = self.state.borrow_mut();
+            if state.in_flight >= state.limit {
+                log::trace!("Adaptive concurrency limit exceeded: {}", state.limit);
+                return AdaptiveConcurrencyResponse {
+                    fut: None,
+                    guard: None,
+                };
+            }
+            state.in_flight += 1;
+            state.in_flight
+        };
+
+        AdaptiveConcurrencyResponse {
+            fut: Some(self.service.call(req)),
+            guard: Some(Guard {
+                state: self.state.clone(),
+                start: Instant::now(),
+                in_flight,
+            }),
+        }
+    }
+}
+
+struct Guard<A: LimitAlgorithm> {
+    state: Rc<RefCell<State<A>>>,
+    start: Instant,
+    in_flight: usize,
+}
+
+impl<A: LimitAlgorithm> Guard<A> {
+    fn sample(&self, success: bool) {
+        let mut state = self.state.borrow_mut();
+        let state = &mut *state;
+        let limit =
+            state
+                .algorithm
+                .update(state.limit, self.start.elapsed(), self.in_flight, success);
+        state.limit = cmp::min(cmp::max(limit, state.min), state.max);
+    }
+}
+
+impl<A: LimitAlgorithm> Drop for Guard<A> {
+    fn drop(&mut self) {
+        self.state.borrow_mut().in_flight -= 1;
+    }
+}
+
+#[doc(hidden)]
+#[pin_project::pin_project]
+pub struct AdaptiveConcurrencyResponse<S: Service, A: LimitAlgorithm> {
+    #[pin]
+    fut: Option<S::Future>,
+    guard: Option<Guard<A>>,
+}
+
+impl<S, A> Future for AdaptiveConcurrencyResponse<S, A>
+where
+    S: Service,
+    S::Error: From<Overloaded>,
+    A: LimitAlgorithm,
+{
+    type Output = Result<S::Response, S::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let fut = match this.fut.as_pin_mut() {
+            Some(fut) => fut,
+            None => return Poll::Ready(Err(Overloaded.into())),
+        };
+
+        let res = futures_util::ready!(fut.poll(cx));
+        if let Some(guard) = this.guard.take() {
+            guard.sample(res.is_ok());
+        }
+        Poll::Ready(res)
+    }
+}
@@ -1,10 +1,12 @@
 //! kayrx utils - various helper services
 
 pub(crate) mod linked_list;
+pub mod adaptive;
 pub mod either;
 pub mod inflight;
 pub mod keepalive;
 pub mod order;
+pub mod spool;
 pub mod stream;
 pub mod time;
 pub mod timeout;
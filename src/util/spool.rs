@@ -0,0 +1,260 @@
+//! Buffer that spills large payloads to a temporary file.
+//!
+//! Middlewares and extractors that have to buffer the whole payload before
+//! processing could use `SpoolBuffer` instead of in-memory `BytesMut`.
+//! Payload is kept in memory up to the threshold, the rest is written to a
+//! temporary file on the blocking thread pool. The file is removed when the
+//! buffer or its stream is dropped.
+//!
+//! ```rust
+//! use futures::StreamExt;
+//! use kayrx::util::spool::SpoolBuffer;
+//! use kayrx::http::Error;
+//! use kayrx::web::{types::Payload, HttpResponse};
+//!
+//! async fn index(mut payload: Payload) -> Result<HttpResponse, Error> {
+//!     let mut buf = SpoolBuffer::new(256 * 1024);
+//!     while let Some(chunk) = payload.next().await {
+//!         buf.write(&chunk?).await?;
+//!     }
+//!     // verify payload, then send it back
+//!     Ok(HttpResponse::Ok().streaming(buf.into_stream()))
+//! }
+//! ```
+use std::fs::{self, File, OpenOptions};
+use std::future::Future;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::{env, fmt};
+
+use bytes::{Bytes, BytesMut};
+use futures_core::Stream;
+use futures_util::StreamExt;
+use rand::Rng;
+
+use crate::util::threadpool::{self, BlockingError, CpuFuture};
+
+/// Default size of the in-memory part
+const THRESHOLD: usize = 1_048_576;
+
+/// Size of the file writes and reads
+const CHUNK_SIZE: usize = 65_536;
+
+/// Payload buffer that spills to disk.
+///
+/// Buffer should be discarded after an io error.
+pub struct SpoolBuffer {
+    threshold: usize,
+    dir: PathBuf,
+    mem: BytesMut,
+    file: Option<TempFile>,
+    len: u64,
+}
+
+impl Default for SpoolBuffer {
+    fn default() -> Self {
+        SpoolBuffer::new(THRESHOLD)
+    }
+}
+
+impl SpoolBuffer {
+    /// Create buffer that keeps up to `threshold` bytes in memory.
+    ///
+    /// Temporary file is created in the system temp directory.
+    pub fn new(threshold: usize) -> Self {
+        SpoolBuffer {
+            threshold,
+            dir: env::temp_dir(),
+            mem: BytesMut::new(),
+            file: None,
+            len: 0,
+        }
+    }
+
+    /// Set directory for the temporary file
+    pub fn dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.dir = dir.into();
+        self
+    }
+
+    /// Number of buffered bytes
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Check if buffer is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Check if payload is spilled to disk
+    pub fn is_spilled(&self) -> bool {
+        self.file.is_some()
+    }
+
+    /// Append data to the buffer
+    pub async fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.len += data.len() as u64;
+        self.mem.extend_from_slice(data);
+
+        if self.file.is_none() {
+            if self.mem.len() <= self.threshold {
+                return Ok(());
+            }
+            let dir = self.dir.clone();
+            self.file = Some(blocking(move || TempFile::create(&dir)).await?);
+        }
+        if self.mem.len() >= CHUNK_SIZE {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Write in-memory part to the file
+    async fn flush(&mut self) -> io::Result<()> {
+        let mut tmp = self.file.take().unwrap();
+        let data = self.mem.split().freeze();
+        let tmp = blocking(move || tmp.file.write_all(&data).map(|_| tmp)).await?;
+        self.file = Some(tmp);
+        Ok(())
+    }
+
+    /// Read the whole buffer into memory
+    pub async fn into_bytes(self) -> io::Result<Bytes> {
+        let mut stream = self.into_stream();
+        let mut buf = BytesMut::new();
+        while let Some(chunk) = stream.next().await {
+            buf.extend_from_slice(&chunk?);
+        }
+        Ok(buf.freeze())
+    }
+
+    /// Convert buffer to a stream of chunks, i.e. to use as response body
+    pub fn into_stream(self) -> SpoolStream {
+        SpoolStream {
+            file: self.file,
+            tail: Some(self.mem.freeze()),
+            rewind: true,
+            fut: None,
+        }
+    }
+}
+
+impl fmt::Debug for SpoolBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpoolBuffer")
+            .field("len", &self.len)
+            .field("threshold", &self.threshold)
+            .field("spilled", &self.file.is_some())
+            .finish()
+    }
+}
+
+/// Stream of the buffered payload
+pub struct SpoolStream {
+    file: Option<TempFile>,
+    tail: Option<Bytes>,
+    rewind: bool,
+    fut: Option<CpuFuture<(TempFile, Bytes), io::Error>>,
+}
+
+impl Stream for SpoolStream {
+    type Item = Result<Bytes, io::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(ref mut fut) = self.fut {
+                let res = match Pin::new(fut).poll(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(res) => res,
+                };
+                self.fut = None;
+                match res {
+                    Ok((file, chunk)) => {
+                        // file is removed when it is read to the end
+                        if !chunk.is_empty() {
+                            self.file = Some(file);
+                            return Poll::Ready(Some(Ok(chunk)));
+                        }
+                    }
+                    Err(e) => {
+                        self.tail = None;
+                        return Poll::Ready(Some(Err(io_error(e))));
+                    }
+                }
+            }
+
+            // file content goes first, then data that is not flushed yet
+            if let Some(mut tmp) = self.file.take() {
+                let rewind = std::mem::replace(&mut self.rewind, false);
+                self.fut = Some(threadpool::run(move || {
+                    if rewind {
+                        tmp.file.seek(SeekFrom::Start(0))?;
+                    }
+                    let mut buf = vec![0; CHUNK_SIZE];
+                    let n = tmp.file.read(&mut buf)?;
+                    buf.truncate(n);
+                    Ok((tmp, Bytes::from(buf)))
+                }));
+                continue;
+            }
+
+            return Poll::Ready(self.tail.take().filter(|tail| !tail.is_empty()).map(Ok));
+        }
+    }
+}
+
+/// Temporary file, removed on drop
+struct TempFile {
+    file: File,
+    path: Option<PathBuf>,
+}
+
+impl TempFile {
+    fn create(dir: &Path) -> io::Result<TempFile> {
+        let path = dir.join(format!(
+            "kayrx-spool-{:032x}",
+            rand::thread_rng().gen::<u128>()
+        ));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+
+        // unix allows to unlink open file, it is freed when descriptor
+        // is closed, even if the process is killed
+        let path = if cfg!(unix) {
+            fs::remove_file(&path)?;
+            None
+        } else {
+            Some(path)
+        };
+        Ok(TempFile { file, path })
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if let Some(ref path) = self.path {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+async fn blocking<F, T>(f: F) -> io::Result<T>
+where
+    F: FnOnce() -> io::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    threadpool::run(f).await.map_err(io_error)
+}
+
+fn io_error(err: BlockingError<io::Error>) -> io::Error {
+    match err {
+        BlockingError::Error(e) => e,
+        BlockingError::Canceled => io::Error::new(io::ErrorKind::Other, "Thread pool is gone"),
+    }
+}
@@ -0,0 +1,361 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_util::ready;
+use pin_project::pin_project;
+
+use crate::http::error::{Error, ErrorServiceUnavailable};
+use crate::http::Request;
+use crate::krse::sync::local::oneshot;
+use crate::service::{Service, ServiceFactory};
+use crate::timer::{delay_for, Delay};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static CONTROLLERS: RefCell<HashMap<usize, Rc<Controller>>> = RefCell::new(HashMap::new());
+}
+
+/// Order in which queued requests are admitted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QueueOrder {
+    /// First in, first out. Requests are admitted in arrival order.
+    Fifo,
+    /// Last in, first out. Most recent requests are admitted first, so under
+    /// sustained overload fresh requests are served with low latency while
+    /// old ones time out. If the queue is full the oldest request is rejected.
+    Lifo,
+}
+
+/// Server level admission control.
+///
+/// Bounds the number of requests concurrently processed by each worker,
+/// independently of the number of open connections. Requests over the limit
+/// wait in a bounded queue; requests that could not be queued or that waited
+/// longer than the max queue delay are rejected with
+/// *503 Service Unavailable*.
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use kayrx::web::{self, Admission, App, HttpResponse, HttpServer, QueueOrder};
+///
+/// #[kayrx::main]
+/// async fn main() -> std::io::Result<()> {
+///     HttpServer::new(|| App::new().service(web::resource("/").to(|| HttpResponse::Ok())))
+///         .admission(
+///             Admission::new(256)
+///                 .queue(1024, QueueOrder::Lifo)
+///                 .max_delay(Duration::from_millis(500)),
+///         )
+///         .bind("127.0.0.1:59090")?
+///         .run()
+///         .await
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Admission {
+    id: usize,
+    limit: usize,
+    queue: usize,
+    order: QueueOrder,
+    max_delay: Duration,
+}
+
+impl Admission {
+    /// Allow `limit` concurrently processed requests per worker.
+    ///
+    /// By default queue is disabled and requests over the limit are rejected
+    /// immediately.
+    pub fn new(limit: usize) -> Self {
+        Admission {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            limit,
+            queue: 0,
+            order: QueueOrder::Fifo,
+            max_delay: Duration::from_secs(1),
+        }
+    }
+
+    /// Set max number of queued requests and queueing order.
+    pub fn queue(mut self, size: usize, order: QueueOrder) -> Self {
+        self.queue = size;
+        self.order = order;
+        self
+    }
+
+    /// Set max time a request may wait in the queue.
+    ///
+    /// By default max delay is set to 1 second.
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    fn controller(&self) -> Rc<Controller> {
+        CONTROLLERS.with(|ctrls| {
+            ctrls
+                .borrow_mut()
+                .entry(self.id)
+                .or_insert_with(|| {
+                    Rc::new(Controller {
+                        limit: self.limit,
+                        queue: self.queue,
+                        order: self.order,
+                        max_delay: self.max_delay,
+                        in_flight: Cell::new(0),
+                        waiters: RefCell::new(VecDeque::new()),
+                    })
+                })
+                .clone()
+        })
+    }
+}
+
+/// Per-worker admission state, shared by all listeners of the server
+struct Controller {
+    limit: usize,
+    queue: usize,
+    order: QueueOrder,
+    max_delay: Duration,
+    in_flight: Cell<usize>,
+    waiters: RefCell<VecDeque<oneshot::Sender<Slot>>>,
+}
+
+impl Controller {
+    /// Acquire slot or enqueue the request
+    fn acquire(self: &Rc<Self>) -> Result<Result<Slot, oneshot::Receiver<Slot>>, ()> {
+        if self.in_flight.get() < self.limit {
+            self.in_flight.set(self.in_flight.get() + 1);
+            return Ok(Ok(Slot(Some(self.clone()))));
+        }
+
+        let mut waiters = self.waiters.borrow_mut();
+        waiters.retain(|tx| !tx.is_canceled());
+        if waiters.len() >= self.queue {
+            if self.order == QueueOrder::Fifo || waiters.is_empty() {
+                return Err(());
+            }
+            // reject oldest request, its receiver resolves with error
+            waiters.pop_front();
+        }
+
+        let (tx, rx) = oneshot::channel();
+        waiters.push_back(tx);
+        Ok(Err(rx))
+    }
+
+    /// Pass slot to the next queued request or release it.
+    ///
+    /// Slot is sent to the waiter, so it is released even if the waiter
+    /// goes away before it gets a chance to run.
+    fn release(self: &Rc<Self>) {
+        let mut waiters = self.waiters.borrow_mut();
+        loop {
+            let tx = match self.order {
+                QueueOrder::Fifo => waiters.pop_front(),
+                QueueOrder::Lifo => waiters.pop_back(),
+            };
+            match tx {
+                Some(tx) => match tx.send(Slot(Some(self.clone()))) {
+                    Ok(_) => return,
+                    Err(mut slot) => {
+                        slot.0.take();
+                    }
+                },
+                None => {
+                    self.in_flight.set(self.in_flight.get() - 1);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+struct Slot(Option<Rc<Controller>>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        if let Some(controller) = self.0.take() {
+            controller.release();
+        }
+    }
+}
+
+/// Service factory that applies admission control to the application
+pub(crate) struct AdmissionFactory<T> {
+    factory: T,
+    admission: Option<Admission>,
+}
+
+impl<T> AdmissionFactory<T> {
+    pub(crate) fn new(admission: Option<Admission>, factory: T) -> Self {
+        AdmissionFactory { factory, admission }
+    }
+}
+
+impl<T, B> ServiceFactory for AdmissionFactory<T>
+where
+    T: ServiceFactory<Config = (), Request = Request, Response = B>,
+    T::Error: Into<Error>,
+{
+    type Config = ();
+    type Request = Request;
+    type Response = B;
+    type Error = Error;
+    type InitError = T::InitError;
+    type Service = AdmissionService<T::Service>;
+    type Future = AdmissionFactoryResponse<T>;
+
+    fn new_service(&self, cfg: ()) -> Self::Future {
+        AdmissionFactoryResponse {
+            fut: self.factory.new_service(cfg),
+            controller: self.admission.as_ref().map(|a| a.controller()),
+        }
+    }
+}
+
+#[doc(hidden)]
+#[pin_project]
+pub struct AdmissionFactoryResponse<T: ServiceFactory> {
+    #[pin]
+    fut: T::Future,
+    controller: Option<Rc<Controller>>,
+}
+
+impl<T: ServiceFactory> Future for AdmissionFactoryResponse<T> {
+    type Output = Result<AdmissionService<T::Service>, T::InitError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let service = ready!(this.fut.poll(cx))?;
+        Poll::Ready(Ok(AdmissionService {
+            service: Rc::new(RefCell::new(service)),
+            controller: this.controller.take(),
+        }))
+    }
+}
+
+#[doc(hidden)]
+pub struct AdmissionService<S> {
+    service: Rc<RefCell<S>>,
+    controller: Option<Rc<Controller>>,
+}
+
+impl<S> Service for AdmissionService<S>
+where
+    S: Service<Request = Request>,
+    S::Error: Into<Error>,
+{
+    type Request = Request;
+    type Response = S::Response;
+    type Error = Error;
+    type Future = AdmissionServiceResponse<S>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx).map_err(|e| e.into())
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let controller = match self.controller {
+            Some(ref controller) => controller,
+            None => return AdmissionServiceResponse::admitted(self.service.call(req), None),
+        };
+
+        match controller.acquire() {
+            Ok(Ok(slot)) => {
+                AdmissionServiceResponse::admitted(self.service.call(req), Some(slot))
+            }
+            Ok(Err(rx)) => AdmissionServiceResponse {
+                fut: None,
+                slot: None,
+                queued: Some(Queued {
+                    rx,
+                    delay: delay_for(controller.max_delay),
+                    req,
+                    service: self.service.clone(),
+                }),
+            },
+            Err(_) => AdmissionServiceResponse {
+                fut: None,
+                slot: None,
+                queued: None,
+            },
+        }
+    }
+}
+
+/// Request waiting in the admission queue
+struct Queued<S> {
+    rx: oneshot::Receiver<Slot>,
+    delay: Delay,
+    req: Request,
+    service: Rc<RefCell<S>>,
+}
+
+#[doc(hidden)]
+#[pin_project]
+pub struct AdmissionServiceResponse<S: Service> {
+    #[pin]
+    fut: Option<S::Future>,
+    slot: Option<Slot>,
+    queued: Option<Queued<S>>,
+}
+
+impl<S: Service> AdmissionServiceResponse<S> {
+    fn admitted(fut: S::Future, slot: Option<Slot>) -> Self {
+        AdmissionServiceResponse {
+            fut: Some(fut),
+            slot,
+            queued: None,
+        }
+    }
+}
+
+impl<S> Future for AdmissionServiceResponse<S>
+where
+    S: Service<Request = Request>,
+    S::Error: Into<Error>,
+{
+    type Output = Result<S::Response, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        if let Some(mut queued) = this.queued.take() {
+            match Pin::new(&mut queued.rx).poll(cx) {
+                Poll::Ready(Ok(slot)) => {
+                    *this.slot = Some(slot);
+                    let fut = queued.service.borrow_mut().call(queued.req);
+                    this.fut.set(Some(fut));
+                }
+                Poll::Ready(Err(_)) => return Poll::Ready(Err(overloaded())),
+                Poll::Pending => {
+                    if Pin::new(&mut queued.delay).poll(cx).is_ready() {
+                        return Poll::Ready(Err(overloaded()));
+                    }
+                    *this.queued = Some(queued);
+                    return Poll::Pending;
+                }
+            }
+        }
+
+        match this.fut.as_pin_mut() {
+            Some(fut) => {
+                let res = ready!(fut.poll(cx));
+                this.slot.take();
+                Poll::Ready(res.map_err(|e| e.into()))
+            }
+            None => Poll::Ready(Err(overloaded())),
+        }
+    }
+}
+
+fn overloaded() -> Error {
+    ErrorServiceUnavailable("Server is overloaded")
+}
@@ -6,19 +6,22 @@ use std::rc::Rc;
 
 use crate::http::body::{Body, MessageBody};
 use crate::http::Extensions;
+use crate::server::lifecycle::{self, Hook};
 use crate::service::boxed::{self, BoxServiceFactory};
 use crate::service::{
     apply, apply_fn_factory, IntoServiceFactory, ServiceFactory, Transform,
 };
-use futures_util::future::{FutureExt, LocalBoxFuture};
+use futures_util::future::{ready, FutureExt, LocalBoxFuture};
 
 use crate::web::app_service::{AppEntry, AppInit, AppRoutingFactory};
 use crate::web::config::ServiceConfig;
 use crate::web::data::{Data, DataFactory};
-use crate::web::dev::ResourceDef;
+use crate::web::dev::{PathDecoding, ResourceDef};
 use crate::web::error::Error;
+use crate::web::module::{Module, ModuleConfig};
 use crate::web::resource::Resource;
 use crate::web::route::Route;
+use crate::web::scope::Scope;
 use crate::web::service::{
     AppServiceFactory, HttpServiceFactory, ServiceFactoryWrapper, ServiceRequest,
     ServiceResponse,
@@ -37,8 +40,12 @@ pub struct App<T, B> {
     factory_ref: Rc<RefCell<Option<AppRoutingFactory>>>,
     data: Vec<Box<dyn DataFactory>>,
     data_factories: Vec<FnDataFactory>,
+    on_start: Vec<Hook>,
+    on_stop: Vec<Hook>,
+    modules: Vec<(String, String)>,
     external: Vec<ResourceDef>,
     extensions: Extensions,
+    decoding: PathDecoding,
     _t: PhantomData<B>,
 }
 
@@ -50,11 +57,15 @@ impl App<AppEntry, Body> {
             endpoint: AppEntry::new(fref.clone()),
             data: Vec::new(),
             data_factories: Vec::new(),
+            on_start: Vec::new(),
+            on_stop: Vec::new(),
+            modules: Vec::new(),
             services: Vec::new(),
             default: None,
             factory_ref: fref,
             external: Vec::new(),
             extensions: Extensions::new(),
+            decoding: PathDecoding::default(),
             _t: PhantomData,
         }
     }
@@ -137,6 +148,45 @@ where
         self
     }
 
+    /// Register async function that is executed on application start.
+    ///
+    /// Application instance is constructed for each worker, so hooks are
+    /// executed once per worker. Start hooks are executed one by one in
+    /// registration order before data factories, application starts
+    /// handling requests after all hooks complete.
+    ///
+    /// ```rust
+    /// use kayrx::web::{self, App, HttpResponse};
+    ///
+    /// let app = App::new()
+    ///     .on_start(|| async { log::info!("worker is started") })
+    ///     .on_stop(|| async { log::info!("worker is stopped") })
+    ///     .service(web::resource("/").to(|| HttpResponse::Ok()));
+    /// ```
+    pub fn on_start<F, R>(mut self, f: F) -> Self
+    where
+        F: Fn() -> R + 'static,
+        R: Future<Output = ()> + 'static,
+    {
+        self.on_start.push(lifecycle::hook(f));
+        self
+    }
+
+    /// Register async function that is executed on application stop.
+    ///
+    /// Stop hooks are executed in reverse registration order during graceful
+    /// worker shutdown, before server stop hooks. If worker re-creates
+    /// application instance, stop hooks of the old instance are executed
+    /// in background.
+    pub fn on_stop<F, R>(mut self, f: F) -> Self
+    where
+        F: Fn() -> R + 'static,
+        R: Future<Output = ()> + 'static,
+    {
+        self.on_stop.push(lifecycle::hook(f));
+        self
+    }
+
     /// Set application level arbitrary data item.
     ///
     /// Application data stored with `App::app_data()` method is available
@@ -186,6 +236,86 @@ where
         self
     }
 
+    /// Register module at its default prefix.
+    ///
+    /// Check [`Module`](trait.Module.html) documentation for details.
+    ///
+    /// Panics if module with the same name or the same prefix is already
+    /// registered.
+    pub fn plug<M: Module>(self, module: M) -> Self {
+        let prefix = module.prefix().to_owned();
+        self.plug_at(&prefix, module)
+    }
+
+    /// Register module at the specified prefix.
+    ///
+    /// Empty prefix registers module services at the application root.
+    pub fn plug_at<M: Module>(mut self, prefix: &str, module: M) -> Self {
+        let name = module.name().to_owned();
+        if let Some(item) = self.modules.iter().find(|item| item.0 == name) {
+            panic!("Module {:?} is already registered at {:?}", name, item.1);
+        }
+        if !prefix.is_empty() {
+            if let Some(item) = self.modules.iter().find(|item| item.1 == prefix) {
+                panic!("Prefix {:?} is already used by module {:?}", prefix, item.0);
+            }
+        }
+
+        let mut cfg = ModuleConfig::new();
+        module.configure(&mut cfg);
+        let ModuleConfig {
+            config,
+            middleware,
+            on_start,
+            on_stop,
+            jobs,
+        } = cfg;
+
+        if prefix.is_empty() {
+            if !middleware.is_empty() {
+                panic!("Module {:?} with middleware requires mount prefix", name);
+            }
+            self.data.extend(config.data);
+            self.services.extend(config.services);
+            self.external.extend(config.external);
+        } else {
+            let mut scope = Scope::new(prefix)
+                .configure(move |cfg| {
+                    cfg.data.extend(config.data);
+                    cfg.services.extend(config.services);
+                    cfg.external.extend(config.external);
+                })
+                .boxed();
+            for mw in middleware {
+                scope = mw(scope);
+            }
+            self.services
+                .push(Box::new(ServiceFactoryWrapper::new(scope)));
+        }
+
+        self.on_start.extend(on_start);
+        self.on_stop.extend(on_stop);
+
+        // background jobs are cancelled on stop
+        for job in jobs {
+            let handle = Rc::new(RefCell::new(None));
+            let handle2 = handle.clone();
+            self.on_start.push(lifecycle::hook(move || {
+                let (remote, job_handle) = job().remote_handle();
+                crate::fiber::spawn(remote);
+                *handle2.borrow_mut() = Some(job_handle);
+                ready(())
+            }));
+            self.on_stop.push(lifecycle::hook(move || {
+                handle.borrow_mut().take();
+                ready(())
+            }));
+        }
+
+        self.modules.push((name, prefix.to_owned()));
+        self
+    }
+
     /// Configure route for a specific path.
     ///
     /// This is a simplified version of the `App::service()` method.
@@ -317,6 +447,31 @@ where
         self
     }
 
+    /// Set percent-decoding policy of request path.
+    ///
+    /// By default path is decoded before matching, encoded slash `%2F` is
+    /// kept as is and request with invalid utf-8 in decoded path is rejected
+    /// with `400 Bad Request`. Resources can override decoding of their
+    /// parameters with `Resource::path_decoding()`.
+    ///
+    /// ```rust
+    /// use kayrx::web::{self, dev::{EncodedSlash, PathDecoding}, App, HttpResponse};
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .path_decoding(
+    ///             PathDecoding::new()
+    ///                 .decode_after_match()
+    ///                 .encoded_slash(EncodedSlash::Reject),
+    ///         )
+    ///         .route("/files/{name}", web::get().to(|| HttpResponse::Ok()));
+    /// }
+    /// ```
+    pub fn path_decoding(mut self, decoding: PathDecoding) -> Self {
+        self.decoding = decoding;
+        self
+    }
+
     /// Registers middleware, in the form of a middleware component (type),
     /// that runs during inbound and/or outbound processing in the request
     /// lifecycle (request -> response), modifying request/response as
@@ -374,11 +529,15 @@ where
             endpoint: apply(mw, self.endpoint),
             data: self.data,
             data_factories: self.data_factories,
+            on_start: self.on_start,
+            on_stop: self.on_stop,
+            modules: self.modules,
             services: self.services,
             default: self.default,
             factory_ref: self.factory_ref,
             external: self.external,
             extensions: self.extensions,
+            decoding: self.decoding,
             _t: PhantomData,
         }
     }
@@ -436,11 +595,15 @@ where
             endpoint: apply_fn_factory(self.endpoint, mw),
             data: self.data,
             data_factories: self.data_factories,
+            on_start: self.on_start,
+            on_stop: self.on_stop,
+            modules: self.modules,
             services: self.services,
             default: self.default,
             factory_ref: self.factory_ref,
             external: self.external,
             extensions: self.extensions,
+            decoding: self.decoding,
             _t: PhantomData,
         }
     }
@@ -461,12 +624,15 @@ where
         AppInit {
             data: Rc::new(self.data),
             data_factories: Rc::new(self.data_factories),
+            on_start: Rc::new(self.on_start),
+            on_stop: Rc::new(self.on_stop),
             endpoint: self.endpoint,
             services: Rc::new(RefCell::new(self.services)),
             external: RefCell::new(self.external),
             default: self.default,
             factory_ref: self.factory_ref,
             extensions: RefCell::new(Some(self.extensions)),
+            decoding: self.decoding,
         }
     }
 }
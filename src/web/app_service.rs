@@ -6,7 +6,9 @@ use std::rc::Rc;
 use std::task::{Context, Poll};
 
 use crate::http::{Extensions, Request, Response};
-use crate::router::{Path, ResourceDef, ResourceInfo, Router, Url};
+use crate::fiber::{self, System};
+use crate::router::{Path, PathDecoding, ResourceDef, ResourceInfo, Router, Url};
+use crate::server::lifecycle::{self, Hook};
 use crate::service::boxed::{self, BoxService, BoxServiceFactory};
 use crate::service::{fn_service, Service, ServiceFactory};
 use futures_util::future::{ok, FutureExt, LocalBoxFuture};
@@ -42,10 +44,13 @@ where
     pub(crate) extensions: RefCell<Option<Extensions>>,
     pub(crate) data: Rc<Vec<Box<dyn DataFactory>>>,
     pub(crate) data_factories: Rc<Vec<FnDataFactory>>,
+    pub(crate) on_start: Rc<Vec<Hook>>,
+    pub(crate) on_stop: Rc<Vec<Hook>>,
     pub(crate) services: Rc<RefCell<Vec<Box<dyn AppServiceFactory>>>>,
     pub(crate) default: Option<Rc<HttpNewService>>,
     pub(crate) factory_ref: Rc<RefCell<Option<AppRoutingFactory>>>,
     pub(crate) external: RefCell<Vec<ResourceDef>>,
+    pub(crate) decoding: PathDecoding,
 }
 
 impl<T, B> ServiceFactory for AppInit<T, B>
@@ -109,7 +114,16 @@ where
         let rmap = Rc::new(rmap);
         rmap.finish(rmap.clone());
 
+        let hooks = self.on_start.clone();
+        let starting = if hooks.is_empty() {
+            None
+        } else {
+            Some(async move { lifecycle::run_hooks(hooks.iter()).await }.boxed_local())
+        };
+
         AppInitResult {
+            starting,
+            on_stop: self.on_stop.clone(),
             endpoint: None,
             endpoint_fut: self.endpoint.new_service(()),
             data: self.data.clone(),
@@ -123,6 +137,7 @@ where
             ),
             config,
             rmap,
+            decoding: self.decoding,
             _t: PhantomData,
         }
     }
@@ -133,6 +148,8 @@ pub struct AppInitResult<T, B>
 where
     T: ServiceFactory,
 {
+    starting: Option<LocalBoxFuture<'static, ()>>,
+    on_stop: Rc<Vec<Hook>>,
     endpoint: Option<T::Service>,
     #[pin]
     endpoint_fut: T::Future,
@@ -142,6 +159,7 @@ where
     data_factories: Vec<Box<dyn DataFactory>>,
     data_factories_fut: Vec<LocalBoxFuture<'static, Result<Box<dyn DataFactory>, ()>>>,
     extensions: Option<Extensions>,
+    decoding: PathDecoding,
     _t: PhantomData<B>,
 }
 
@@ -160,6 +178,14 @@ where
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.project();
 
+        // start hooks
+        if let Some(fut) = this.starting.as_mut() {
+            if fut.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+            *this.starting = None;
+        }
+
         // async data factories
         let mut idx = 0;
         while idx < this.data_factories_fut.len() {
@@ -189,12 +215,24 @@ where
                 f.create(&mut data);
             }
 
+            let stop_hook = if this.on_stop.is_empty() {
+                None
+            } else {
+                let hooks = this.on_stop.clone();
+                Some(lifecycle::register_stop_hook(Box::new(move || {
+                    async move { lifecycle::run_hooks(hooks.iter().rev()).await }
+                        .boxed_local()
+                })))
+            };
+
             Poll::Ready(Ok(AppInitService {
+                stop_hook,
                 service: this.endpoint.take().unwrap(),
                 rmap: this.rmap.clone(),
                 config: this.config.clone(),
                 data: Rc::new(data),
                 pool: HttpRequestPool::create(),
+                decoding: *this.decoding,
             }))
         } else {
             Poll::Pending
@@ -212,6 +250,8 @@ where
     config: AppConfig,
     data: Rc<Extensions>,
     pool: &'static HttpRequestPool,
+    stop_hook: Option<usize>,
+    decoding: PathDecoding,
 }
 
 impl<T, B> Service for AppInitService<T, B>
@@ -236,11 +276,12 @@ where
             inner.path.reset();
             inner.head = head;
             inner.payload = payload;
-            inner.app_data = self.data.clone();
+            inner.app_data.clear();
+            inner.app_data.push(self.data.clone());
             req
         } else {
             HttpRequest::new(
-                Path::new(Url::new(head.uri.clone())),
+                Path::new(Url::with_decoding(head.uri.clone(), self.decoding)),
                 head,
                 payload,
                 self.rmap.clone(),
@@ -259,6 +300,13 @@ where
 {
     fn drop(&mut self) {
         self.pool.clear();
+
+        // application instance is dropped before worker shutdown
+        if let Some(hook) = self.stop_hook.take().and_then(lifecycle::remove_stop_hook) {
+            if System::is_set() {
+                fiber::spawn(hook());
+            }
+        }
     }
 }
 
@@ -267,6 +315,18 @@ pub struct AppRoutingFactory {
     default: Rc<HttpNewService>,
 }
 
+impl AppRoutingFactory {
+    pub(crate) fn new(
+        services: Vec<(ResourceDef, HttpNewService, RefCell<Option<Guards>>)>,
+        default: Rc<HttpNewService>,
+    ) -> Self {
+        AppRoutingFactory {
+            services: Rc::new(services),
+            default,
+        }
+    }
+}
+
 impl ServiceFactory for AppRoutingFactory {
     type Config = ();
     type Request = ServiceRequest;
@@ -363,8 +423,10 @@ impl Future for AppRoutingFactoryResponse {
                 });
             Poll::Ready(Ok(AppRouting {
                 ready: None,
-                router: router.finish(),
-                default: self.default.take(),
+                router: Rc::new(RefCell::new(AppRouter {
+                    router: router.finish(),
+                    default: self.default.take(),
+                })),
             }))
         } else {
             Poll::Pending
@@ -373,9 +435,8 @@ impl Future for AppRoutingFactoryResponse {
 }
 
 pub struct AppRouting {
-    router: Router<HttpService, Guards>,
+    router: Rc<RefCell<AppRouter>>,
     ready: Option<(ServiceRequest, ResourceInfo)>,
-    default: Option<HttpService>,
 }
 
 impl Service for AppRouting {
@@ -393,6 +454,23 @@ impl Service for AppRouting {
     }
 
     fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
+        req.set_app_router(Rc::downgrade(&self.router));
+        self.router.borrow_mut().call(req)
+    }
+}
+
+/// App router, shared with requests for internal subrequests
+pub(crate) struct AppRouter {
+    router: Router<HttpService, Guards>,
+    default: Option<HttpService>,
+}
+
+impl AppRouter {
+    pub(crate) fn call(&mut self, mut req: ServiceRequest) -> BoxResponse {
+        if let Some(err) = req.match_info().decode_error() {
+            return ok(req.error_response(err)).boxed_local();
+        }
+
         let res = self.router.recognize_mut_checked(&mut req, |req, guards| {
             if let Some(ref guards) = guards {
                 for f in guards {
@@ -405,7 +483,11 @@ impl Service for AppRouting {
         });
 
         if let Some((srv, _info)) = res {
-            srv.call(req)
+            if let Some(err) = req.match_info().decode_error() {
+                ok(req.error_response(err)).boxed_local()
+            } else {
+                srv.call(req)
+            }
         } else if let Some(ref mut default) = self.default {
             default.call(req)
         } else {
@@ -0,0 +1,273 @@
+//! Batch request endpoint
+//!
+//! [`Batch`](struct.Batch.html) service accepts json array of request
+//! descriptors, dispatches them to the application router as internal
+//! subrequests and streams back json array of responses in the same order.
+//!
+//! ```rust
+//! use kayrx::web::{self, batch, App, HttpResponse};
+//!
+//! fn main() {
+//!     let app = App::new()
+//!         .service(batch::Batch::new("/batch").concurrency(8))
+//!         .route("/users/{id}", web::get().to(|| HttpResponse::Ok()));
+//! }
+//! ```
+//!
+//! Request body:
+//!
+//! ```json
+//! [
+//!     {"method": "GET", "path": "/users/1"},
+//!     {"method": "POST", "path": "/users", "body": {"name": "kayrx"}}
+//! ]
+//! ```
+//!
+//! Response body:
+//!
+//! ```json
+//! [
+//!     {"status": 200, "headers": {"content-type": "application/json"}, "body": {"id": 1}},
+//!     {"status": 201, "headers": {}, "body": "created"}
+//! ]
+//! ```
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use bytes::{Bytes, BytesMut};
+use futures_util::future::{ok, ready, FutureExt, LocalBoxFuture};
+use futures_util::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::http::error::{Error, ErrorBadRequest};
+use crate::http::header::{HeaderName, HeaderValue, CONTENT_TYPE};
+use crate::http::{Method, Response, StatusCode};
+use crate::router::ResourceDef;
+use crate::service::fn_service;
+use crate::web::config::AppService;
+use crate::web::extract::FromRequest;
+use crate::web::guard;
+use crate::web::request::HttpRequest;
+use crate::web::service::{HttpServiceFactory, ServiceRequest, ServiceResponse};
+use crate::web::types::Json;
+
+/// Default number of concurrently dispatched subrequests
+const CONCURRENCY: usize = 4;
+
+/// Default max number of subrequests in a batch
+const MAX_REQUESTS: usize = 32;
+
+/// Subrequest descriptor
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchRequest {
+    /// Request method, `GET` by default
+    #[serde(default = "default_method")]
+    pub method: String,
+    /// Absolute path with optional query string
+    pub path: String,
+    /// Request headers, headers of the batch request are used by default
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Json body of the request
+    #[serde(default)]
+    pub body: Option<Value>,
+}
+
+fn default_method() -> String {
+    "GET".to_owned()
+}
+
+/// Subrequest result
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchResponse {
+    /// Response status code
+    pub status: u16,
+    /// Response headers
+    pub headers: BTreeMap<String, String>,
+    /// Json body if response content type is json, string otherwise
+    pub body: Value,
+}
+
+/// Batch request service.
+///
+/// Service handles `POST` requests to the configured path. Subrequests are
+/// dispatched concurrently up to the concurrency limit, responses are sent
+/// as soon as all preceding responses are sent. Subrequests go through
+/// scope and resource middlewares of the target resources, application
+/// middlewares are applied once to the batch request itself.
+///
+/// Batch body is parsed with the `JsonConfig` of the application.
+pub struct Batch {
+    path: String,
+    concurrency: usize,
+    max_requests: usize,
+}
+
+impl Batch {
+    /// Create batch service for the path
+    pub fn new(path: &str) -> Self {
+        Batch {
+            path: path.to_owned(),
+            concurrency: CONCURRENCY,
+            max_requests: MAX_REQUESTS,
+        }
+    }
+
+    /// Set max number of concurrently dispatched subrequests, by default 4.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = std::cmp::max(concurrency, 1);
+        self
+    }
+
+    /// Set max number of subrequests in a batch, by default 32.
+    ///
+    /// Larger batches are rejected with `400 Bad Request` response.
+    pub fn max_requests(mut self, max: usize) -> Self {
+        self.max_requests = max;
+        self
+    }
+}
+
+impl HttpServiceFactory for Batch {
+    fn register(self, config: &mut AppService) {
+        let inner = Rc::new(self);
+
+        config.register_service(
+            ResourceDef::new(inner.path.as_str()),
+            Some(vec![Box::new(guard::Post())]),
+            fn_service(move |req: ServiceRequest| handle(req, inner.clone())),
+            None,
+        );
+    }
+}
+
+async fn handle(req: ServiceRequest, inner: Rc<Batch>) -> Result<ServiceResponse, Error> {
+    let (req, mut payload) = req.into_parts();
+
+    let items = match Json::<Vec<BatchRequest>>::from_request(&req, &mut payload).await {
+        Ok(items) => items.into_inner(),
+        Err(e) => return Ok(ServiceResponse::from_err(e, req)),
+    };
+    if items.len() > inner.max_requests {
+        let err = ErrorBadRequest(format!(
+            "Batch size is bigger ({}) than allowed ({})",
+            items.len(),
+            inner.max_requests
+        ));
+        return Ok(ServiceResponse::from_err(err, req));
+    }
+
+    let futs: Vec<_> = items.into_iter().map(|item| execute(&req, item)).collect();
+    let items = stream::iter(futs)
+        .buffered(inner.concurrency)
+        .enumerate()
+        .map(|(idx, item)| {
+            let mut buf = BytesMut::new();
+            if idx != 0 {
+                buf.extend_from_slice(b",");
+            }
+            buf.extend_from_slice(&serde_json::to_vec(&item).unwrap_or_default());
+            Ok::<_, Error>(buf.freeze())
+        });
+    let body = stream::once(ok(Bytes::from_static(b"[")))
+        .chain(items)
+        .chain(stream::once(ok(Bytes::from_static(b"]"))));
+
+    let res = Response::Ok()
+        .content_type("application/json")
+        .streaming(body);
+    Ok(ServiceResponse::new(req, res))
+}
+
+/// Dispatch subrequest and collect the response
+fn execute(req: &HttpRequest, item: BatchRequest) -> LocalBoxFuture<'static, BatchResponse> {
+    let sub = match prepare(req, item) {
+        Ok(sub) => sub,
+        Err(e) => return ready(error_response(e)).boxed_local(),
+    };
+
+    req.dispatch(Ok(sub))
+        .then(|res| async move {
+            match res {
+                Ok(res) => read_response(res).await,
+                Err(e) => error_response(e),
+            }
+        })
+        .boxed_local()
+}
+
+fn prepare(req: &HttpRequest, item: BatchRequest) -> Result<ServiceRequest, Error> {
+    let method = Method::from_bytes(item.method.as_bytes()).map_err(ErrorBadRequest)?;
+    let mut sub = ServiceRequest::new(req.child(&item.path, method)?);
+
+    for (name, value) in item.headers {
+        let name = HeaderName::from_bytes(name.as_bytes()).map_err(ErrorBadRequest)?;
+        let value = HeaderValue::from_str(&value).map_err(ErrorBadRequest)?;
+        sub.headers_mut().insert(name, value);
+    }
+    if let Some(body) = item.body {
+        let body = serde_json::to_vec(&body).map_err(ErrorBadRequest)?;
+        let mut payload = crate::http::h1::Payload::empty();
+        payload.unread_data(Bytes::from(body));
+        sub.headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        sub.set_payload(payload.into());
+    }
+    Ok(sub)
+}
+
+async fn read_response(mut res: ServiceResponse) -> BatchResponse {
+    let status = res.status().as_u16();
+    let headers = res
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|value| (name.as_str().to_owned(), value.to_owned()))
+        })
+        .collect();
+    let is_json = res
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|val| val.to_str().ok())
+        .and_then(|val| val.parse::<mime::Mime>().ok())
+        .map(|mime| mime.subtype() == mime::JSON || mime.suffix() == Some(mime::JSON))
+        .unwrap_or(false);
+
+    let mut stream = res.take_body();
+    let mut body = BytesMut::new();
+    while let Some(chunk) = stream.next().await {
+        match chunk {
+            Ok(chunk) => body.extend_from_slice(&chunk),
+            Err(e) => return error_response(e),
+        }
+    }
+
+    let body = if is_json {
+        serde_json::from_slice(&body)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&body).into_owned()))
+    } else {
+        Value::String(String::from_utf8_lossy(&body).into_owned())
+    };
+    BatchResponse {
+        status,
+        headers,
+        body,
+    }
+}
+
+fn error_response(err: Error) -> BatchResponse {
+    let status = err.as_response_error().status_code();
+    BatchResponse {
+        status: if status.is_success() {
+            StatusCode::INTERNAL_SERVER_ERROR.as_u16()
+        } else {
+            status.as_u16()
+        },
+        headers: BTreeMap::new(),
+        body: Value::String(err.to_string()),
+    }
+}
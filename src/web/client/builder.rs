@@ -8,8 +8,12 @@ use crate::http::client::{Connect, ConnectError, Connection, Connector};
 use crate::http::{header, error::HttpError, HeaderMap, HeaderName};
 use crate::service::Service;
 
+use crate::web::client::cache::{CacheConnector, HttpCache};
 use crate::web::client::connect::ConnectorWrapper;
+use crate::web::client::redirect::{RedirectConnector, RedirectPolicy};
+use crate::web::client::trace::TraceConnector;
 use crate::web::client::{Client, ClientConfig};
+use crate::web::trace::Propagation;
 
 /// An HTTP Client builder
 ///
@@ -18,8 +22,9 @@ use crate::web::client::{Client, ClientConfig};
 pub struct ClientBuilder {
     config: ClientConfig,
     default_headers: bool,
-    allow_redirects: bool,
-    max_redirects: usize,
+    redirect: RedirectPolicy,
+    cache: Option<HttpCache>,
+    propagation: Option<Propagation>,
 }
 
 impl Default for ClientBuilder {
@@ -32,8 +37,9 @@ impl ClientBuilder {
     pub fn new() -> Self {
         ClientBuilder {
             default_headers: true,
-            allow_redirects: true,
-            max_redirects: 10,
+            redirect: RedirectPolicy::default(),
+            cache: None,
+            propagation: None,
             config: ClientConfig {
                 headers: HeaderMap::new(),
                 timeout: Some(Duration::from_secs(5)),
@@ -56,6 +62,16 @@ impl ClientBuilder {
         self
     }
 
+    /// Enable client side http cache.
+    ///
+    /// Responses to `GET` requests are cached according to `Cache-Control`,
+    /// `Expires`, `ETag` and `Last-Modified` response headers, see
+    /// [`cache`](cache/index.html) module. By default responses are not cached.
+    pub fn cache(mut self, cache: HttpCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
     /// Set request timeout
     ///
     /// Request timeout is the total time before a response must be received.
@@ -75,7 +91,7 @@ impl ClientBuilder {
     ///
     /// Redirects are allowed by default.
     pub fn disable_redirects(mut self) -> Self {
-        self.allow_redirects = false;
+        self.redirect = self.redirect.max_redirects(0);
         self
     }
 
@@ -83,7 +99,16 @@ impl ClientBuilder {
     ///
     /// Max redirects is set to 10 by default.
     pub fn max_redirects(mut self, num: usize) -> Self {
-        self.max_redirects = num;
+        self.redirect = self.redirect.max_redirects(num);
+        self
+    }
+
+    /// Set redirect policy.
+    ///
+    /// Redirects are followed by the client for `send()` family of request
+    /// methods, websocket requests do not follow redirects.
+    pub fn redirect_policy(mut self, policy: RedirectPolicy) -> Self {
+        self.redirect = policy;
         self
     }
 
@@ -138,8 +163,35 @@ impl ClientBuilder {
         self.header(header::AUTHORIZATION, format!("Bearer {}", token))
     }
 
+    /// Propagate trace context of the current request.
+    ///
+    /// Requests sent while request with trace context is handled, see
+    /// [`trace`](../trace/index.html) module, carry headers of a child span.
+    /// Headers set explicitly on the request are kept.
+    pub fn trace_propagation(mut self, propagation: Propagation) -> Self {
+        self.propagation = Some(propagation);
+        self
+    }
+
     /// Finish build process and create `Client` instance.
-    pub fn finish(self) -> Client {
+    pub fn finish(mut self) -> Client {
+        // every request, including redirect hops, is a separate span
+        if let Some(propagation) = self.propagation.take() {
+            let connector = self.config.connector.into_inner();
+            self.config.connector =
+                RefCell::new(Box::new(TraceConnector::new(propagation, connector)));
+        }
+        if let Some(cache) = self.cache.take() {
+            let connector = self.config.connector.into_inner();
+            self.config.connector =
+                RefCell::new(Box::new(CacheConnector::new(cache, connector)));
+        }
+        // every hop of redirect goes through the cache
+        if self.redirect.is_enabled() {
+            let connector = self.config.connector.into_inner();
+            self.config.connector =
+                RefCell::new(Box::new(RedirectConnector::new(self.redirect, connector)));
+        }
         Client(Rc::new(self.config))
     }
 }
@@ -0,0 +1,593 @@
+//! Client side HTTP cache
+//!
+//! Caching follows RFC 9111 semantics for a private cache: responses to `GET`
+//! requests are stored if they carry freshness information or validators,
+//! fresh responses are served without contacting the server and stale ones
+//! are revalidated with `If-None-Match` / `If-Modified-Since` requests.
+//! Responses are selected by the request headers listed in `Vary`.
+//!
+//! ```rust
+//! use kayrx::web::client::Client;
+//! use kayrx::web::client::cache::{HttpCache, MemoryCacheStore};
+//!
+//! let client = Client::build()
+//!     .cache(HttpCache::new(MemoryCacheStore::new(1024)))
+//!     .finish();
+//! ```
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime};
+use std::{cmp, net};
+
+use bytes::{Bytes, BytesMut};
+use futures_core::Stream;
+use futures_util::future::{ready, FutureExt, LocalBoxFuture};
+
+use crate::codec::Framed2 as Framed;
+use crate::http::body::Body;
+use crate::http::client::{ConnectError, SendRequestError};
+use crate::http::error::PayloadError;
+use crate::http::h1::ClientCodec;
+use crate::http::header::{self, HeaderName, HeaderValue, HttpDate};
+use crate::http::{
+    HeaderMap, Method, Payload, RequestHead, ResponseHead, StatusCode, Uri, Version,
+};
+
+use crate::web::client::connect::{BoxedSocket, Connect};
+use crate::web::client::response::ClientResponse;
+
+/// Storage backend of the client cache.
+///
+/// Store keeps one response per url, responses with different `Vary`
+/// selection replace each other.
+pub trait CacheStore {
+    /// Get stored response
+    fn get(&self, key: &str) -> LocalBoxFuture<'static, Option<CachedResponse>>;
+
+    /// Store response
+    fn put(&self, key: &str, response: CachedResponse) -> LocalBoxFuture<'static, ()>;
+
+    /// Remove stored response
+    fn remove(&self, key: &str) -> LocalBoxFuture<'static, ()>;
+}
+
+/// Stored response
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    /// Response status
+    pub status: StatusCode,
+    /// Response version
+    pub version: Version,
+    /// Response headers
+    pub headers: HeaderMap,
+    /// Response body, as received from the server
+    pub body: Bytes,
+    /// Values of the request headers listed in response `Vary` header
+    pub vary: Vec<(HeaderName, Option<HeaderValue>)>,
+    /// Time the response was received or last validated
+    pub stored_at: SystemTime,
+}
+
+impl CachedResponse {
+    pub(crate) fn matches(&self, headers: &HeaderMap) -> bool {
+        self.vary
+            .iter()
+            .all(|(name, value)| headers.get(name) == value.as_ref())
+    }
+
+    /// Time since the response was generated by the origin server
+    pub(crate) fn age(&self, now: SystemTime) -> Duration {
+        let age = self
+            .headers
+            .get(&header::AGE)
+            .and_then(|val| val.to_str().ok())
+            .and_then(|val| val.parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or_else(|| Duration::from_secs(0));
+        age + now.duration_since(self.stored_at).unwrap_or_default()
+    }
+
+    pub(crate) fn is_fresh(&self, now: SystemTime) -> bool {
+        let cc = Directives::new(&self.headers);
+        if cc.has("no-cache") {
+            return false;
+        }
+        match freshness_lifetime(&self.headers, &cc) {
+            Some(lifetime) => lifetime > self.age(now),
+            None => false,
+        }
+    }
+
+    fn into_response(self, now: SystemTime) -> ClientResponse {
+        let mut head = ResponseHead::new(self.status);
+        head.version = self.version;
+        head.headers = self.headers.clone();
+        head.headers
+            .insert(header::AGE, HeaderValue::from(self.age(now).as_secs()));
+
+        let body = self.body;
+        let stream: crate::http::PayloadStream =
+            Box::pin(futures_util::stream::once(ready(Ok(body))));
+        ClientResponse::new(head, Payload::Stream(stream))
+    }
+}
+
+/// In-memory cache store.
+///
+/// Clones of the store share the same storage. When capacity is reached,
+/// the oldest entries are evicted.
+#[derive(Clone)]
+pub struct MemoryCacheStore {
+    inner: Rc<RefCell<MemoryInner>>,
+}
+
+struct MemoryInner {
+    capacity: usize,
+    entries: HashMap<String, CachedResponse>,
+    order: VecDeque<String>,
+}
+
+impl MemoryCacheStore {
+    /// Create store for `capacity` responses
+    pub fn new(capacity: usize) -> Self {
+        MemoryCacheStore {
+            inner: Rc::new(RefCell::new(MemoryInner {
+                capacity,
+                entries: HashMap::new(),
+                order: VecDeque::new(),
+            })),
+        }
+    }
+
+    /// Number of stored responses
+    pub fn len(&self) -> usize {
+        self.inner.borrow().entries.len()
+    }
+
+    /// Check if store is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl CacheStore for MemoryCacheStore {
+    fn get(&self, key: &str) -> LocalBoxFuture<'static, Option<CachedResponse>> {
+        ready(self.inner.borrow().entries.get(key).cloned()).boxed_local()
+    }
+
+    fn put(&self, key: &str, response: CachedResponse) -> LocalBoxFuture<'static, ()> {
+        let mut inner = self.inner.borrow_mut();
+        if inner.capacity > 0 {
+            if inner.entries.insert(key.to_owned(), response).is_none() {
+                inner.order.push_back(key.to_owned());
+            }
+            while inner.entries.len() > inner.capacity {
+                match inner.order.pop_front() {
+                    Some(key) => {
+                        inner.entries.remove(&key);
+                    }
+                    None => break,
+                }
+            }
+        }
+        ready(()).boxed_local()
+    }
+
+    fn remove(&self, key: &str) -> LocalBoxFuture<'static, ()> {
+        let mut inner = self.inner.borrow_mut();
+        if inner.entries.remove(key).is_some() {
+            inner.order.retain(|k| k != key);
+        }
+        ready(()).boxed_local()
+    }
+}
+
+/// Client cache configuration
+pub struct HttpCache {
+    store: Box<dyn CacheStore>,
+    max_body_size: usize,
+}
+
+impl HttpCache {
+    /// Create cache with provided store
+    pub fn new<S: CacheStore + 'static>(store: S) -> Self {
+        HttpCache {
+            store: Box::new(store),
+            max_body_size: 1_048_576,
+        }
+    }
+
+    /// Set max size of a cached response body. Larger responses are not
+    /// stored. By default max size is 1Mb.
+    pub fn max_body_size(mut self, size: usize) -> Self {
+        self.max_body_size = size;
+        self
+    }
+}
+
+/// Cache-Control directives
+pub(crate) struct Directives(Vec<(String, Option<String>)>);
+
+impl Directives {
+    pub(crate) fn new(headers: &HeaderMap) -> Self {
+        let mut directives = Vec::new();
+        for val in headers.get_all(&header::CACHE_CONTROL) {
+            if let Ok(val) = val.to_str() {
+                for item in val.split(',') {
+                    let mut parts = item.splitn(2, '=');
+                    let name = parts.next().unwrap_or("").trim().to_ascii_lowercase();
+                    if name.is_empty() {
+                        continue;
+                    }
+                    let value = parts.next().map(|v| v.trim().trim_matches('"').to_owned());
+                    directives.push((name, value));
+                }
+            }
+        }
+        Directives(directives)
+    }
+
+    pub(crate) fn has(&self, name: &str) -> bool {
+        self.0.iter().any(|(n, _)| n == name)
+    }
+
+    pub(crate) fn seconds(&self, name: &str) -> Option<Duration> {
+        self.0
+            .iter()
+            .find(|(n, _)| n == name)
+            .and_then(|(_, v)| v.as_ref())
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(Duration::from_secs)
+    }
+}
+
+fn http_date(headers: &HeaderMap, name: &HeaderName) -> Option<SystemTime> {
+    headers
+        .get(name)
+        .and_then(|val| val.to_str().ok())
+        .and_then(|val| HttpDate::from_str(val).ok())
+        .map(SystemTime::from)
+}
+
+pub(crate) fn freshness_lifetime(headers: &HeaderMap, cc: &Directives) -> Option<Duration> {
+    if let Some(max_age) = cc.seconds("max-age") {
+        return Some(max_age);
+    }
+    let date = http_date(headers, &header::DATE);
+    if headers.contains_key(&header::EXPIRES) {
+        // invalid `Expires` means already expired
+        let expires = http_date(headers, &header::EXPIRES);
+        return Some(match (expires, date) {
+            (Some(expires), Some(date)) => {
+                expires.duration_since(date).unwrap_or_default()
+            }
+            _ => Duration::from_secs(0),
+        });
+    }
+    // heuristic freshness, 10% of the time since last modification
+    if let (Some(date), Some(modified)) =
+        (date, http_date(headers, &header::LAST_MODIFIED))
+    {
+        let lifetime = date.duration_since(modified).unwrap_or_default() / 10;
+        return Some(cmp::min(lifetime, Duration::from_secs(86400)));
+    }
+    None
+}
+
+pub(crate) fn is_cacheable(head: &ResponseHead) -> bool {
+    match head.status {
+        StatusCode::OK
+        | StatusCode::NON_AUTHORITATIVE_INFORMATION
+        | StatusCode::NO_CONTENT
+        | StatusCode::MULTIPLE_CHOICES
+        | StatusCode::MOVED_PERMANENTLY
+        | StatusCode::NOT_FOUND
+        | StatusCode::GONE => (),
+        _ => return false,
+    }
+    let cc = Directives::new(&head.headers);
+    if cc.has("no-store") {
+        return false;
+    }
+    let vary_all = head
+        .headers
+        .get_all(&header::VARY)
+        .filter_map(|val| val.to_str().ok())
+        .any(|val| val.split(',').any(|v| v.trim() == "*"));
+    if vary_all {
+        return false;
+    }
+    freshness_lifetime(&head.headers, &cc).is_some()
+        || head.headers.contains_key(&header::ETAG)
+        || head.headers.contains_key(&header::LAST_MODIFIED)
+}
+
+pub(crate) fn vary(res: &HeaderMap, req: &HeaderMap) -> Vec<(HeaderName, Option<HeaderValue>)> {
+    res.get_all(&header::VARY)
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| val.split(','))
+        .filter_map(|name| HeaderName::from_str(name.trim()).ok())
+        .map(|name| {
+            let value = req.get(&name).cloned();
+            (name, value)
+        })
+        .collect()
+}
+
+enum Request {
+    Owned(RequestHead),
+    Rc(Rc<RequestHead>, Option<HeaderMap>),
+}
+
+impl Request {
+    fn head(&self) -> &RequestHead {
+        match self {
+            Request::Owned(ref head) => head,
+            Request::Rc(ref head, _) => &**head,
+        }
+    }
+
+    /// Request headers including extra headers
+    fn headers(&self) -> HeaderMap {
+        match self {
+            Request::Owned(head) => head.headers.clone(),
+            Request::Rc(head, extra) => {
+                let mut headers = head.headers.clone();
+                if let Some(extra) = extra {
+                    for (name, value) in extra.iter() {
+                        headers.insert(name.clone(), value.clone());
+                    }
+                }
+                headers
+            }
+        }
+    }
+
+    fn insert_header(&mut self, name: HeaderName, value: HeaderValue) {
+        match self {
+            Request::Owned(head) => {
+                head.headers.insert(name, value);
+            }
+            Request::Rc(_, extra) => {
+                extra
+                    .get_or_insert_with(HeaderMap::new)
+                    .insert(name, value);
+            }
+        }
+    }
+}
+
+/// Connector wrapper that serves and stores responses in the cache
+pub(crate) struct CacheConnector {
+    connector: Rc<RefCell<Box<dyn Connect>>>,
+    cache: Rc<HttpCache>,
+}
+
+impl CacheConnector {
+    pub(crate) fn new(cache: HttpCache, connector: Box<dyn Connect>) -> Self {
+        CacheConnector {
+            connector: Rc::new(RefCell::new(connector)),
+            cache: Rc::new(cache),
+        }
+    }
+
+    fn send(
+        &mut self,
+        mut req: Request,
+        body: Body,
+        addr: Option<net::SocketAddr>,
+    ) -> Pin<Box<dyn Future<Output = Result<ClientResponse, SendRequestError>>>> {
+        let connector = self.connector.clone();
+        let cache = self.cache.clone();
+        let method = req.head().method.clone();
+        let key = req.head().uri.to_string();
+
+        let send = move |req: Request, body: Body| {
+            let mut connector = connector.borrow_mut();
+            match req {
+                Request::Owned(head) => connector.send_request(head, body, addr),
+                Request::Rc(head, extra) => {
+                    connector.send_request_extra(head, extra, body, addr)
+                }
+            }
+        };
+
+        if method != Method::GET {
+            let fut = send(req, body);
+            if method == Method::HEAD
+                || method == Method::OPTIONS
+                || method == Method::TRACE
+            {
+                return fut;
+            }
+
+            // unsafe methods invalidate stored response
+            return Box::pin(async move {
+                let res = fut.await?;
+                if !res.status().is_server_error() && !res.status().is_client_error()
+                {
+                    cache.store.remove(&key).await;
+                }
+                Ok(res)
+            });
+        }
+
+        let req_headers = req.headers();
+        let req_cc = Directives::new(&req_headers);
+        if req_cc.has("no-store") {
+            return send(req, body);
+        }
+
+        Box::pin(async move {
+            let cached = cache
+                .store
+                .get(&key)
+                .await
+                .filter(|cached| cached.matches(&req_headers));
+
+            if let Some(ref cached) = cached {
+                let now = SystemTime::now();
+                if !req_cc.has("no-cache") && cached.is_fresh(now) {
+                    return Ok(cached.clone().into_response(now));
+                }
+
+                // revalidate stale response
+                if let Some(etag) = cached.headers.get(&header::ETAG) {
+                    req.insert_header(header::IF_NONE_MATCH, etag.clone());
+                } else if let Some(modified) = cached.headers.get(&header::LAST_MODIFIED) {
+                    req.insert_header(header::IF_MODIFIED_SINCE, modified.clone());
+                }
+            }
+
+            let mut res = send(req, body).await?;
+
+            if res.status() == StatusCode::NOT_MODIFIED {
+                if let Some(mut cached) = cached {
+                    for (name, value) in res.headers().iter() {
+                        if *name != header::CONTENT_LENGTH {
+                            cached.headers.insert(name.clone(), value.clone());
+                        }
+                    }
+                    cached.headers.remove(&header::AGE);
+                    cached.stored_at = SystemTime::now();
+                    cache.store.put(&key, cached.clone()).await;
+                    return Ok(cached.into_response(SystemTime::now()));
+                }
+                return Ok(res);
+            }
+
+            if is_cacheable(&res.head) {
+                let entry = CachedResponse {
+                    status: res.head.status,
+                    version: res.head.version,
+                    headers: res.head.headers.clone(),
+                    body: Bytes::new(),
+                    vary: vary(&res.head.headers, &req_headers),
+                    stored_at: SystemTime::now(),
+                };
+                let payload = std::mem::replace(&mut res.payload, Payload::None);
+                let body: crate::http::PayloadStream = Box::pin(CacheBody {
+                    payload,
+                    buf: BytesMut::new(),
+                    entry: Some((key, entry)),
+                    cache,
+                });
+                res.payload = Payload::Stream(body);
+            } else if cached.is_some() {
+                cache.store.remove(&key).await;
+            }
+            Ok(res)
+        })
+    }
+}
+
+impl Connect for CacheConnector {
+    fn warmup(
+        &mut self,
+        uri: Uri,
+    ) -> Pin<Box<dyn Future<Output = Result<(), ConnectError>>>> {
+        self.connector.borrow_mut().warmup(uri)
+    }
+
+    fn send_request(
+        &mut self,
+        head: RequestHead,
+        body: Body,
+        addr: Option<net::SocketAddr>,
+    ) -> Pin<Box<dyn Future<Output = Result<ClientResponse, SendRequestError>>>> {
+        self.send(Request::Owned(head), body, addr)
+    }
+
+    fn send_request_extra(
+        &mut self,
+        head: Rc<RequestHead>,
+        extra_headers: Option<HeaderMap>,
+        body: Body,
+        addr: Option<net::SocketAddr>,
+    ) -> Pin<Box<dyn Future<Output = Result<ClientResponse, SendRequestError>>>> {
+        self.send(Request::Rc(head, extra_headers), body, addr)
+    }
+
+    fn open_tunnel(
+        &mut self,
+        head: RequestHead,
+        addr: Option<net::SocketAddr>,
+    ) -> Pin<
+        Box<
+            dyn Future<
+                Output = Result<
+                    (ResponseHead, Framed<BoxedSocket, ClientCodec>),
+                    SendRequestError,
+                >,
+            >,
+        >,
+    > {
+        self.connector.borrow_mut().open_tunnel(head, addr)
+    }
+
+    fn open_tunnel_extra(
+        &mut self,
+        head: Rc<RequestHead>,
+        extra_headers: Option<HeaderMap>,
+        addr: Option<net::SocketAddr>,
+    ) -> Pin<
+        Box<
+            dyn Future<
+                Output = Result<
+                    (ResponseHead, Framed<BoxedSocket, ClientCodec>),
+                    SendRequestError,
+                >,
+            >,
+        >,
+    > {
+        self.connector
+            .borrow_mut()
+            .open_tunnel_extra(head, extra_headers, addr)
+    }
+}
+
+/// Response payload that stores the response once it is fully read
+struct CacheBody {
+    payload: Payload,
+    buf: BytesMut,
+    entry: Option<(String, CachedResponse)>,
+    cache: Rc<HttpCache>,
+}
+
+impl Stream for CacheBody {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        match Pin::new(&mut this.payload).poll_next(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                if this.entry.is_some() {
+                    if this.buf.len() + chunk.len() > this.cache.max_body_size {
+                        this.entry = None;
+                        this.buf = BytesMut::new();
+                    } else {
+                        this.buf.extend_from_slice(&chunk);
+                    }
+                }
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            Poll::Ready(None) => {
+                if let Some((key, mut entry)) = this.entry.take() {
+                    entry.body = std::mem::replace(&mut this.buf, BytesMut::new()).freeze();
+                    crate::fiber::spawn(this.cache.store.put(&key, entry));
+                }
+                Poll::Ready(None)
+            }
+            Poll::Ready(Some(Err(e))) => {
+                this.entry = None;
+                Poll::Ready(Some(Err(e)))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
@@ -11,7 +11,7 @@ use crate::http::client::{
     Connect as ClientConnect, ConnectError, Connection, SendRequestError,
 };
 use crate::http::h1::ClientCodec;
-use crate::http::HeaderMap;
+use crate::http::{HeaderMap, Uri};
 use crate::http::{RequestHead, RequestHeadType, ResponseHead};
 use crate::service::Service;
 
@@ -19,7 +19,20 @@ use crate::web::client::response::ClientResponse;
 
 pub(crate) struct ConnectorWrapper<T>(pub T);
 
+/// Stream isolation label of the request, stored in request head extensions
+pub(crate) struct Isolation(pub(crate) String);
+
+fn isolation(head: &RequestHead) -> Option<String> {
+    head.extensions().get::<Isolation>().map(|label| label.0.clone())
+}
+
 pub(crate) trait Connect {
+    /// Open connection to the host and return it to the pool
+    fn warmup(
+        &mut self,
+        uri: Uri,
+    ) -> Pin<Box<dyn Future<Output = Result<(), ConnectError>>>>;
+
     fn send_request(
         &mut self,
         head: RequestHead,
@@ -78,6 +91,22 @@ where
     <T::Response as Connection>::TunnelFuture: 'static,
     T::Future: 'static,
 {
+    fn warmup(
+        &mut self,
+        uri: Uri,
+    ) -> Pin<Box<dyn Future<Output = Result<(), ConnectError>>>> {
+        let fut = self.0.call(ClientConnect {
+            uri,
+            addr: None,
+            isolation: None,
+        });
+
+        Box::pin(async move {
+            fut.await?.release();
+            Ok(())
+        })
+    }
+
     fn send_request(
         &mut self,
         head: RequestHead,
@@ -88,6 +117,7 @@ where
         let fut = self.0.call(ClientConnect {
             uri: head.uri.clone(),
             addr,
+            isolation: isolation(&head),
         });
 
         Box::pin(async move {
@@ -112,6 +142,7 @@ where
         let fut = self.0.call(ClientConnect {
             uri: head.uri.clone(),
             addr,
+            isolation: isolation(&head),
         });
 
         Box::pin(async move {
@@ -144,6 +175,7 @@ where
         let fut = self.0.call(ClientConnect {
             uri: head.uri.clone(),
             addr,
+            isolation: isolation(&head),
         });
 
         Box::pin(async move {
@@ -177,6 +209,7 @@ where
         let fut = self.0.call(ClientConnect {
             uri: head.uri.clone(),
             addr,
+            isolation: isolation(&head),
         });
 
         Box::pin(async move {
@@ -24,27 +24,32 @@ use std::time::Duration;
 
 pub use crate::http::client::Connector;
 
+use crate::http::client::SendRequestError;
 use crate::http::{error::HttpError, HeaderMap, Method, Uri};
 use crate::http::RequestHead;
 
 mod builder;
+pub mod cache;
 mod connect;
 pub mod error;
 mod frozen;
+mod redirect;
 mod request;
 mod response;
 mod sender;
 pub mod test;
+mod trace;
 pub mod ws;
 
 pub use self::builder::ClientBuilder;
 pub use self::connect::BoxedSocket;
 pub use self::frozen::{FrozenClientRequest, FrozenSendBuilder};
+pub use self::redirect::RedirectPolicy;
 pub use self::request::ClientRequest;
 pub use self::response::{ClientResponse, JsonBody, MessageBody};
 pub use self::sender::SendClientRequest;
 
-use self::connect::{Connect, ConnectorWrapper};
+use self::connect::Connect;
 
 /// An HTTP Client
 ///
@@ -74,13 +79,7 @@ pub(crate) struct ClientConfig {
 
 impl Default for Client {
     fn default() -> Self {
-        Client(Rc::new(ClientConfig {
-            connector: RefCell::new(Box::new(ConnectorWrapper(
-                Connector::new().finish(),
-            ))),
-            headers: HeaderMap::new(),
-            timeout: Some(Duration::from_secs(5)),
-        }))
+        ClientBuilder::new().finish()
     }
 }
 
@@ -188,6 +187,47 @@ impl Client {
         self.request(Method::OPTIONS, url)
     }
 
+    /// Establish connections to the hosts in advance.
+    ///
+    /// Opens connection, including tls handshake, to every host and puts it
+    /// to the connection pool, so first requests do not pay connection
+    /// setup latency. Pool tops up idle connections to the
+    /// `Connector::min_idle_per_host()` limit in background.
+    ///
+    /// ```rust,no_run
+    /// use kayrx::web::client::{Client, Connector};
+    ///
+    /// #[kayrx::main]
+    /// async fn main() {
+    ///     let client = Client::build()
+    ///         .connector(Connector::new().min_idle_per_host(4).finish())
+    ///         .finish();
+    ///
+    ///     client
+    ///         .warmup(vec!["https://www.rust-lang.org"])
+    ///         .await
+    ///         .unwrap();
+    /// }
+    /// ```
+    pub async fn warmup<I, U>(&self, hosts: I) -> Result<(), SendRequestError>
+    where
+        I: IntoIterator<Item = U>,
+        Uri: TryFrom<U>,
+        <Uri as TryFrom<U>>::Error: Into<HttpError>,
+    {
+        let mut futs = Vec::new();
+        for host in hosts {
+            let uri =
+                Uri::try_from(host).map_err(|e| SendRequestError::Http(e.into()))?;
+            futs.push(self.0.connector.borrow_mut().warmup(uri));
+        }
+
+        for res in futures_util::future::join_all(futs).await {
+            res?;
+        }
+        Ok(())
+    }
+
     /// Construct WebSockets request.
     pub fn ws<U>(&self, url: U) -> ws::WebsocketsRequest
     where
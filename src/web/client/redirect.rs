@@ -0,0 +1,441 @@
+//! Redirect following
+use std::cell::RefCell;
+use std::future::Future;
+use std::net;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use crate::codec::Framed2 as Framed;
+use crate::http::body::Body;
+use crate::http::client::{ConnectError, SendRequestError};
+use crate::http::h1::ClientCodec;
+use crate::http::header::{self, HeaderName};
+use crate::http::uri::PathAndQuery;
+use crate::http::{HeaderMap, Method, RequestHead, ResponseHead, StatusCode, Uri, Version};
+
+use crate::web::client::connect::{BoxedSocket, Connect, Isolation};
+use crate::web::client::response::ClientResponse;
+
+/// Redirect policy of the client.
+///
+/// By default client follows up to 10 redirects, including redirects to
+/// other origins. `Authorization`, `Proxy-Authorization` and `Cookie`
+/// headers are removed if redirect leads to other origin or downgrades
+/// `https` to `http`.
+///
+/// `303 See Other` is followed with `GET` request, `301` and `302` change
+/// `POST` to `GET`, `307` and `308` keep method and body. Requests with
+/// streaming body can not be repeated, so `307` and `308` responses to
+/// such requests are returned as is.
+///
+/// ```rust
+/// use kayrx::web::client::{Client, RedirectPolicy};
+///
+/// let client = Client::build()
+///     .redirect_policy(RedirectPolicy::new().max_redirects(3).cross_origin(false))
+///     .finish();
+/// ```
+#[derive(Debug, Clone)]
+pub struct RedirectPolicy {
+    max_redirects: usize,
+    cross_origin: bool,
+    sensitive_headers: Vec<HeaderName>,
+}
+
+impl Default for RedirectPolicy {
+    fn default() -> Self {
+        RedirectPolicy {
+            max_redirects: 10,
+            cross_origin: true,
+            sensitive_headers: vec![
+                header::AUTHORIZATION,
+                header::PROXY_AUTHORIZATION,
+                header::COOKIE,
+            ],
+        }
+    }
+}
+
+impl RedirectPolicy {
+    /// Create default redirect policy
+    pub fn new() -> Self {
+        RedirectPolicy::default()
+    }
+
+    /// Policy that does not follow redirects
+    pub fn none() -> Self {
+        RedirectPolicy::default().max_redirects(0)
+    }
+
+    /// Set max number of redirects.
+    ///
+    /// Request fails with `SendRequestError::TooManyRedirects` error if
+    /// server redirects more times. Zero disables redirects.
+    pub fn max_redirects(mut self, num: usize) -> Self {
+        self.max_redirects = num;
+        self
+    }
+
+    /// Follow redirects to other origins.
+    ///
+    /// If disabled, redirect response to other origin is returned as is.
+    /// Enabled by default.
+    pub fn cross_origin(mut self, allow: bool) -> Self {
+        self.cross_origin = allow;
+        self
+    }
+
+    /// Add header that gets removed on redirect to other origin or on
+    /// `https` to `http` downgrade.
+    pub fn sensitive_header(mut self, name: HeaderName) -> Self {
+        if !self.sensitive_headers.contains(&name) {
+            self.sensitive_headers.push(name);
+        }
+        self
+    }
+
+    /// Check if policy follows redirects
+    pub fn is_enabled(&self) -> bool {
+        self.max_redirects != 0
+    }
+}
+
+/// Request state that is needed to repeat request to the new location
+struct Hop {
+    method: Method,
+    uri: Uri,
+    version: Version,
+    headers: HeaderMap,
+    isolation: Option<String>,
+    // `None` if body can not be repeated
+    body: Option<Body>,
+}
+
+impl Hop {
+    fn new(head: &RequestHead, extra: Option<&HeaderMap>, body: &Body) -> Self {
+        let mut headers = head.headers.clone();
+        if let Some(extra) = extra {
+            for (name, value) in extra.iter() {
+                headers.insert(name.clone(), value.clone());
+            }
+        }
+        let body = match body {
+            Body::None => Some(Body::None),
+            Body::Empty => Some(Body::Empty),
+            Body::Bytes(ref bytes) => Some(Body::Bytes(bytes.clone())),
+            Body::Message(_) => None,
+        };
+
+        Hop {
+            headers,
+            body,
+            method: head.method.clone(),
+            uri: head.uri.clone(),
+            version: head.version,
+            isolation: head
+                .extensions()
+                .get::<Isolation>()
+                .map(|label| label.0.clone()),
+        }
+    }
+
+    /// Move to the redirect location, returns `false` if policy does not
+    /// allow to follow redirect
+    fn redirect(&mut self, res: &ClientResponse, policy: &RedirectPolicy) -> bool {
+        let location = match res
+            .headers()
+            .get(header::LOCATION)
+            .and_then(|loc| loc.to_str().ok())
+            .and_then(|loc| resolve(&self.uri, loc))
+        {
+            Some(uri) => uri,
+            None => return false,
+        };
+
+        let cross_origin = origin(&self.uri) != origin(&location);
+        if cross_origin && !policy.cross_origin {
+            return false;
+        }
+
+        match res.status() {
+            StatusCode::SEE_OTHER => {
+                if self.method != Method::HEAD {
+                    self.method = Method::GET;
+                }
+                self.drop_body();
+            }
+            StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND => {
+                if self.method == Method::POST {
+                    self.method = Method::GET;
+                    self.drop_body();
+                }
+            }
+            StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT => {
+                if self.body.is_none() {
+                    return false;
+                }
+            }
+            _ => return false,
+        }
+
+        let downgrade = self.uri.scheme_str() == Some("https")
+            && location.scheme_str() == Some("http");
+        if cross_origin || downgrade {
+            for name in &policy.sensitive_headers {
+                self.headers.remove(name);
+            }
+        }
+        if cross_origin {
+            self.headers.remove(header::HOST);
+        }
+        self.uri = location;
+        true
+    }
+
+    fn drop_body(&mut self) {
+        self.body = Some(Body::Empty);
+        self.headers.remove(header::CONTENT_TYPE);
+        self.headers.remove(header::CONTENT_LENGTH);
+        self.headers.remove(header::CONTENT_ENCODING);
+        self.headers.remove(header::TRANSFER_ENCODING);
+    }
+
+    fn request(&self) -> (RequestHead, Body) {
+        let mut head = RequestHead::default();
+        head.method = self.method.clone();
+        head.uri = self.uri.clone();
+        head.version = self.version;
+        head.headers = self.headers.clone();
+        if let Some(ref label) = self.isolation {
+            head.extensions_mut().insert(Isolation(label.clone()));
+        }
+
+        let body = match self.body {
+            Some(Body::Bytes(ref bytes)) => Body::Bytes(bytes.clone()),
+            Some(Body::None) => Body::None,
+            _ => Body::Empty,
+        };
+        (head, body)
+    }
+}
+
+fn is_redirect(status: StatusCode) -> bool {
+    match status {
+        StatusCode::MOVED_PERMANENTLY
+        | StatusCode::FOUND
+        | StatusCode::SEE_OTHER
+        | StatusCode::TEMPORARY_REDIRECT
+        | StatusCode::PERMANENT_REDIRECT => true,
+        _ => false,
+    }
+}
+
+/// Scheme, host and port of the uri
+fn origin(uri: &Uri) -> (Option<&str>, Option<&str>, Option<u16>) {
+    let port = uri.port_u16().or_else(|| match uri.scheme_str() {
+        Some("https") | Some("wss") => Some(443),
+        Some("http") | Some("ws") => Some(80),
+        _ => None,
+    });
+    (uri.scheme_str(), uri.host(), port)
+}
+
+/// Resolve `Location` header value against request uri
+fn resolve(base: &Uri, location: &str) -> Option<Uri> {
+    let location = location.split('#').next().unwrap_or("");
+
+    // absolute uri
+    if let Some(idx) = location.find("://") {
+        if !location[..idx].contains(|c| c == '/' || c == '?') {
+            let uri = Uri::from_str(location).ok()?;
+            return if uri.host().is_some() { Some(uri) } else { None };
+        }
+    }
+    // scheme relative uri
+    if location.starts_with("//") {
+        return Uri::from_str(&format!("{}:{}", base.scheme_str()?, location)).ok();
+    }
+
+    let path = if location.starts_with('/') {
+        location.to_owned()
+    } else if location.starts_with('?') {
+        format!("{}{}", base.path(), location)
+    } else {
+        let dir = match base.path().rfind('/') {
+            Some(idx) => &base.path()[..=idx],
+            None => "/",
+        };
+        format!("{}{}", dir, location)
+    };
+
+    let mut parts = base.clone().into_parts();
+    parts.path_and_query = Some(PathAndQuery::from_str(&path).ok()?);
+    Uri::from_parts(parts).ok()
+}
+
+/// Connector wrapper that follows redirects according to the policy
+pub(crate) struct RedirectConnector {
+    connector: Rc<RefCell<Box<dyn Connect>>>,
+    policy: Rc<RedirectPolicy>,
+}
+
+impl RedirectConnector {
+    pub(crate) fn new(policy: RedirectPolicy, connector: Box<dyn Connect>) -> Self {
+        RedirectConnector {
+            connector: Rc::new(RefCell::new(connector)),
+            policy: Rc::new(policy),
+        }
+    }
+
+    fn send(
+        &mut self,
+        mut hop: Hop,
+        fut: Pin<Box<dyn Future<Output = Result<ClientResponse, SendRequestError>>>>,
+        addr: Option<net::SocketAddr>,
+    ) -> Pin<Box<dyn Future<Output = Result<ClientResponse, SendRequestError>>>> {
+        let connector = self.connector.clone();
+        let policy = self.policy.clone();
+
+        Box::pin(async move {
+            let mut res = fut.await?;
+            let mut hops = 0;
+
+            while is_redirect(res.status()) {
+                let prev = origin(&hop.uri).1.map(|host| host.to_owned());
+                if !hop.redirect(&res, &policy) {
+                    break;
+                }
+                if hops == policy.max_redirects {
+                    return Err(SendRequestError::TooManyRedirects);
+                }
+                hops += 1;
+                log::trace!("Following redirect to {}", hop.uri);
+
+                // explicit socket address applies only to the original host
+                let addr = if prev.as_ref().map(|h| h.as_str()) == hop.uri.host() {
+                    addr
+                } else {
+                    None
+                };
+                let (head, body) = hop.request();
+                let fut = connector.borrow_mut().send_request(head, body, addr);
+                res = fut.await?;
+            }
+            Ok(res)
+        })
+    }
+}
+
+impl Connect for RedirectConnector {
+    fn warmup(
+        &mut self,
+        uri: Uri,
+    ) -> Pin<Box<dyn Future<Output = Result<(), ConnectError>>>> {
+        self.connector.borrow_mut().warmup(uri)
+    }
+
+    fn send_request(
+        &mut self,
+        head: RequestHead,
+        body: Body,
+        addr: Option<net::SocketAddr>,
+    ) -> Pin<Box<dyn Future<Output = Result<ClientResponse, SendRequestError>>>> {
+        let hop = Hop::new(&head, None, &body);
+        let fut = self.connector.borrow_mut().send_request(head, body, addr);
+        self.send(hop, fut, addr)
+    }
+
+    fn send_request_extra(
+        &mut self,
+        head: Rc<RequestHead>,
+        extra_headers: Option<HeaderMap>,
+        body: Body,
+        addr: Option<net::SocketAddr>,
+    ) -> Pin<Box<dyn Future<Output = Result<ClientResponse, SendRequestError>>>> {
+        let hop = Hop::new(&head, extra_headers.as_ref(), &body);
+        let fut = self
+            .connector
+            .borrow_mut()
+            .send_request_extra(head, extra_headers, body, addr);
+        self.send(hop, fut, addr)
+    }
+
+    fn open_tunnel(
+        &mut self,
+        head: RequestHead,
+        addr: Option<net::SocketAddr>,
+    ) -> Pin<
+        Box<
+            dyn Future<
+                Output = Result<
+                    (ResponseHead, Framed<BoxedSocket, ClientCodec>),
+                    SendRequestError,
+                >,
+            >,
+        >,
+    > {
+        self.connector.borrow_mut().open_tunnel(head, addr)
+    }
+
+    fn open_tunnel_extra(
+        &mut self,
+        head: Rc<RequestHead>,
+        extra_headers: Option<HeaderMap>,
+        addr: Option<net::SocketAddr>,
+    ) -> Pin<
+        Box<
+            dyn Future<
+                Output = Result<
+                    (ResponseHead, Framed<BoxedSocket, ClientCodec>),
+                    SendRequestError,
+                >,
+            >,
+        >,
+    > {
+        self.connector
+            .borrow_mut()
+            .open_tunnel_extra(head, extra_headers, addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uri(s: &str) -> Uri {
+        Uri::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_resolve() {
+        let base = uri("http://example.com/a/b?q=1");
+        assert_eq!(
+            resolve(&base, "https://other.com/c").unwrap(),
+            uri("https://other.com/c")
+        );
+        assert_eq!(
+            resolve(&base, "//other.com/c").unwrap(),
+            uri("http://other.com/c")
+        );
+        assert_eq!(resolve(&base, "/c?x=2").unwrap(), uri("http://example.com/c?x=2"));
+        assert_eq!(resolve(&base, "c#frag").unwrap(), uri("http://example.com/a/c"));
+        assert_eq!(resolve(&base, "?x=2").unwrap(), uri("http://example.com/a/b?x=2"));
+    }
+
+    #[test]
+    fn test_origin() {
+        assert_eq!(
+            origin(&uri("http://example.com/")),
+            origin(&uri("http://example.com:80/a"))
+        );
+        assert_ne!(
+            origin(&uri("http://example.com/")),
+            origin(&uri("https://example.com/"))
+        );
+        assert_ne!(
+            origin(&uri("http://example.com/")),
+            origin(&uri("http://example.com:8080/"))
+        );
+    }
+}
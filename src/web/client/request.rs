@@ -22,6 +22,7 @@ use crate::http::error::{Error, HttpError};
 use crate::web::client::error::{FreezeRequestError, InvalidUrl};
 use crate::web::client::frozen::FrozenClientRequest;
 use crate::web::client::sender::{PrepForSendingError, RequestSender, SendClientRequest};
+use crate::web::client::connect::Isolation;
 use crate::web::client::ClientConfig;
 
 const HTTPS_ENCODING: &str = "br, gzip, deflate";
@@ -353,6 +354,16 @@ impl ClientRequest {
         self
     }
 
+    /// Set stream isolation label.
+    ///
+    /// Requests with different labels never share connections. If the
+    /// connector uses SOCKS5 proxy, label is sent as proxy credentials, so
+    /// proxies with stream isolation route them through different circuits.
+    pub fn isolation<T: Into<String>>(self, label: T) -> Self {
+        self.head.extensions_mut().insert(Isolation(label.into()));
+        self
+    }
+
     /// This method calls provided closure with builder reference if
     /// value is `true`.
     pub fn if_true<F>(self, value: bool, f: F) -> Self
@@ -0,0 +1,186 @@
+//! Trace context propagation
+use std::future::Future;
+use std::net;
+use std::pin::Pin;
+use std::rc::Rc;
+
+use crate::codec::Framed2 as Framed;
+use crate::http::body::Body;
+use crate::http::client::{ConnectError, SendRequestError};
+use crate::http::h1::ClientCodec;
+use crate::http::{HeaderMap, Method, RequestHead, ResponseHead, Uri};
+
+use crate::web::client::connect::{BoxedSocket, Connect};
+use crate::web::client::response::ClientResponse;
+use crate::web::trace::{Propagation, TraceContext};
+
+type ResponseFuture = Pin<Box<dyn Future<Output = Result<ClientResponse, SendRequestError>>>>;
+
+/// Connector wrapper that injects context of the current request,
+/// every outgoing request is a child span
+pub(crate) struct TraceConnector {
+    connector: Box<dyn Connect>,
+    propagation: Propagation,
+}
+
+impl TraceConnector {
+    pub(crate) fn new(propagation: Propagation, connector: Box<dyn Connect>) -> Self {
+        TraceConnector {
+            connector,
+            propagation,
+        }
+    }
+
+    /// Returns span of the request
+    fn inject(&self, head: &mut RequestHead) -> Option<TraceContext> {
+        let ctx = TraceContext::current()?.child();
+        ctx.inject(&mut head.headers, self.propagation);
+        Some(ctx)
+    }
+
+    /// Head is shared, headers go to extra headers
+    fn inject_extra(
+        &self,
+        head: &RequestHead,
+        extra_headers: &mut Option<HeaderMap>,
+    ) -> Option<TraceContext> {
+        let ctx = TraceContext::current()?.child();
+        let mut headers = HeaderMap::new();
+        ctx.inject(&mut headers, self.propagation);
+
+        let extra = extra_headers.get_or_insert_with(HeaderMap::new);
+        for (name, value) in headers.iter() {
+            if !head.headers.contains_key(name) && !extra.contains_key(name) {
+                extra.insert(name.clone(), value.clone());
+            }
+        }
+        Some(ctx)
+    }
+}
+
+/// Record client metrics and span of the request
+#[cfg(feature = "telemetry")]
+fn record(
+    ctx: Option<TraceContext>,
+    method: &Method,
+    uri: &Uri,
+    fut: ResponseFuture,
+) -> ResponseFuture {
+    use std::time::SystemTime;
+
+    use crate::telemetry::{self, SpanData, SpanKind};
+
+    let method = method.to_string();
+    let url = uri.to_string();
+    let start = SystemTime::now();
+
+    Box::pin(async move {
+        let res = fut.await;
+        let status = match res {
+            Ok(ref res) => res.status().as_str().to_owned(),
+            Err(_) => "error".to_owned(),
+        };
+        telemetry::counter(
+            "http.client.requests",
+            &[("method", &method), ("status", &status)],
+        )
+        .add(1);
+
+        if let Some(ctx) = ctx {
+            let error = match res {
+                Ok(ref res) => res.status().is_server_error(),
+                Err(_) => true,
+            };
+            telemetry::record_span(SpanData {
+                ctx,
+                name: method.clone(),
+                kind: SpanKind::Client,
+                start,
+                end: SystemTime::now(),
+                attributes: vec![
+                    ("http.method", method),
+                    ("http.url", url),
+                    ("http.status_code", status),
+                ],
+                error,
+            });
+        }
+        res
+    })
+}
+
+#[cfg(not(feature = "telemetry"))]
+fn record(_: Option<TraceContext>, _: &Method, _: &Uri, fut: ResponseFuture) -> ResponseFuture {
+    fut
+}
+
+impl Connect for TraceConnector {
+    fn warmup(
+        &mut self,
+        uri: Uri,
+    ) -> Pin<Box<dyn Future<Output = Result<(), ConnectError>>>> {
+        self.connector.warmup(uri)
+    }
+
+    fn send_request(
+        &mut self,
+        mut head: RequestHead,
+        body: Body,
+        addr: Option<net::SocketAddr>,
+    ) -> ResponseFuture {
+        let ctx = self.inject(&mut head);
+        let (method, uri) = (head.method.clone(), head.uri.clone());
+        record(ctx, &method, &uri, self.connector.send_request(head, body, addr))
+    }
+
+    fn send_request_extra(
+        &mut self,
+        head: Rc<RequestHead>,
+        mut extra_headers: Option<HeaderMap>,
+        body: Body,
+        addr: Option<net::SocketAddr>,
+    ) -> ResponseFuture {
+        let ctx = self.inject_extra(&head, &mut extra_headers);
+        let fut = self
+            .connector
+            .send_request_extra(head.clone(), extra_headers, body, addr);
+        record(ctx, &head.method, &head.uri, fut)
+    }
+
+    fn open_tunnel(
+        &mut self,
+        mut head: RequestHead,
+        addr: Option<net::SocketAddr>,
+    ) -> Pin<
+        Box<
+            dyn Future<
+                Output = Result<
+                    (ResponseHead, Framed<BoxedSocket, ClientCodec>),
+                    SendRequestError,
+                >,
+            >,
+        >,
+    > {
+        self.inject(&mut head);
+        self.connector.open_tunnel(head, addr)
+    }
+
+    fn open_tunnel_extra(
+        &mut self,
+        head: Rc<RequestHead>,
+        mut extra_headers: Option<HeaderMap>,
+        addr: Option<net::SocketAddr>,
+    ) -> Pin<
+        Box<
+            dyn Future<
+                Output = Result<
+                    (ResponseHead, Framed<BoxedSocket, ClientCodec>),
+                    SendRequestError,
+                >,
+            >,
+        >,
+    > {
+        self.inject_extra(&head, &mut extra_headers);
+        self.connector.open_tunnel_extra(head, extra_headers, addr)
+    }
+}
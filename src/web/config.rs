@@ -8,6 +8,7 @@ use crate::service::{boxed, IntoServiceFactory, ServiceFactory};
 use crate::web::data::{Data, DataFactory};
 use crate::web::error::Error;
 use crate::web::guard::Guard;
+use crate::web::info::ConnectionInfoConfig;
 use crate::web::resource::Resource;
 use crate::web::rmap::ResourceMap;
 use crate::web::route::Route;
@@ -130,11 +131,26 @@ struct AppConfigInner {
     secure: bool,
     host: String,
     addr: SocketAddr,
+    info: ConnectionInfoConfig,
 }
 
 impl AppConfig {
     pub(crate) fn new(secure: bool, addr: SocketAddr, host: String) -> Self {
-        AppConfig(Rc::new(AppConfigInner { secure, addr, host }))
+        AppConfig(Rc::new(AppConfigInner {
+            secure,
+            addr,
+            host,
+            info: ConnectionInfoConfig::default(),
+        }))
+    }
+
+    pub(crate) fn with_connection_info(self, info: ConnectionInfoConfig) -> Self {
+        AppConfig(Rc::new(AppConfigInner {
+            secure: self.0.secure,
+            addr: self.0.addr,
+            host: self.0.host.clone(),
+            info,
+        }))
     }
 
     /// Server host name.
@@ -157,6 +173,11 @@ impl AppConfig {
     pub fn local_addr(&self) -> SocketAddr {
         self.0.addr
     }
+
+    /// Scheme and host resolution configuration of `ConnectionInfo`
+    pub fn connection_info(&self) -> &ConnectionInfoConfig {
+        &self.0.info
+    }
 }
 
 impl Default for AppConfig {
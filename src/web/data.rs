@@ -100,8 +100,14 @@ impl<T> Clone for Data<T> {
     }
 }
 
+impl<T> From<Arc<T>> for Data<T> {
+    fn from(arc: Arc<T>) -> Self {
+        Data(arc)
+    }
+}
+
 impl<T: 'static> FromRequest for Data<T> {
-    type Config = ();
+    type Config = DataConfig;
     type Error = Error;
     type Future = Ready<Result<Self, Error>>;
 
@@ -115,13 +121,126 @@ impl<T: 'static> FromRequest for Data<T> {
                  Request path: {:?}",
                 req.path()
             );
-            err(ErrorInternalServerError(
-                "App data is not configured, to configure use App::data()",
-            ))
+            err(missing::<T>(req, "App data"))
         }
     }
 }
 
+/// Request-local data extractor.
+///
+/// Extracts a clone of `T` from request extensions, usually inserted by
+/// middleware with `HttpRequest::extensions_mut()`. If value of type `T`
+/// is not present, extractor fails with *Internal Server Error* response.
+///
+/// ```rust
+/// use kayrx::web::{HttpResponse, ReqData};
+///
+/// #[derive(Debug, Clone)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// async fn index(user: ReqData<User>) -> HttpResponse {
+///     HttpResponse::Ok().body(format!("Hello {}", user.name))
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReqData<T: Clone + 'static>(T);
+
+impl<T: Clone + 'static> ReqData<T> {
+    /// Unwrap to inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: Clone + 'static> Deref for ReqData<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Clone + 'static> FromRequest for ReqData<T> {
+    type Config = DataConfig;
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if let Some(st) = req.extensions().get::<T>() {
+            ok(ReqData(st.clone()))
+        } else {
+            log::debug!(
+                "Failed to construct request-local Data extractor. \
+                 Request path: {:?}",
+                req.path()
+            );
+            err(missing::<T>(req, "Request data"))
+        }
+    }
+}
+
+/// Error for missing data of type `T`
+fn missing<T: 'static>(req: &HttpRequest, kind: &str) -> Error {
+    let name = std::any::type_name::<T>();
+    let handler = req
+        .app_data::<DataConfig>()
+        .and_then(|c| c.ehandler.clone());
+
+    if let Some(handler) = handler {
+        (handler)(name, req)
+    } else {
+        ErrorInternalServerError(format!(
+            "{} of type `{}` is not configured",
+            kind, name
+        ))
+    }
+}
+
+/// `Data` and `ReqData` extractors configuration
+///
+/// Error handler receives type name of missing data.
+///
+/// ```rust
+/// use kayrx::http::error;
+/// use kayrx::web::{self, App, DataConfig, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .app_data(DataConfig::default().error_handler(|name, _| {
+///             error::InternalError::from_response(
+///                 name,
+///                 HttpResponse::ServiceUnavailable().finish(),
+///             )
+///             .into()
+///         }))
+///         .route("/", web::get().to(|_: web::Data<usize>| HttpResponse::Ok()));
+/// }
+/// ```
+#[derive(Clone)]
+pub struct DataConfig {
+    ehandler: Option<Arc<dyn Fn(&'static str, &HttpRequest) -> Error + Send + Sync>>,
+}
+
+impl DataConfig {
+    /// Set custom error handler
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&'static str, &HttpRequest) -> Error + Send + Sync + 'static,
+    {
+        self.ehandler = Some(Arc::new(f));
+        self
+    }
+}
+
+impl Default for DataConfig {
+    fn default() -> Self {
+        DataConfig { ehandler: None }
+    }
+}
+
 impl<T: 'static> DataFactory for Data<T> {
     fn create(&self, extensions: &mut Extensions) -> bool {
         if !extensions.contains::<Data<T>>() {
@@ -0,0 +1,267 @@
+//! Runtime reconfigurable routing
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::task::{Context, Poll};
+
+use futures_util::future::{ok, FutureExt, LocalBoxFuture, Ready};
+
+use crate::http::Response;
+use crate::service::{boxed, fn_service, Service, ServiceFactory};
+use crate::web::app_service::{AppRouting, AppRoutingFactory, AppRoutingFactoryResponse};
+use crate::web::config::{AppConfig, AppService, ServiceConfig};
+use crate::web::error::{Error, ErrorInternalServerError};
+use crate::web::scope::Scope;
+use crate::web::service::{HttpServiceFactory, ServiceRequest, ServiceResponse};
+
+type Configure = Arc<dyn Fn(&mut ServiceConfig) + Send + Sync>;
+
+/// Routing table of the dynamic router
+#[derive(Clone, Default)]
+pub struct DynamicRoutes {
+    scopes: Vec<(String, Configure)>,
+}
+
+impl DynamicRoutes {
+    /// Create empty routing table
+    pub fn new() -> Self {
+        DynamicRoutes::default()
+    }
+
+    /// Mount scope at the prefix.
+    ///
+    /// Scope is configured with `ServiceConfig`, same way as
+    /// `Scope::configure()`. Scope with the same prefix gets replaced.
+    pub fn mount<F>(&mut self, prefix: &str, f: F) -> &mut Self
+    where
+        F: Fn(&mut ServiceConfig) + Send + Sync + 'static,
+    {
+        let f: Configure = Arc::new(f);
+        match self.scopes.iter_mut().find(|item| item.0 == prefix) {
+            Some(item) => item.1 = f,
+            None => self.scopes.push((prefix.to_owned(), f)),
+        }
+        self
+    }
+
+    /// Unmount scope, returns `false` if prefix is not mounted
+    pub fn unmount(&mut self, prefix: &str) -> bool {
+        let len = self.scopes.len();
+        self.scopes.retain(|item| item.0 != prefix);
+        len != self.scopes.len()
+    }
+
+    /// Check if prefix is mounted
+    pub fn contains(&self, prefix: &str) -> bool {
+        self.scopes.iter().any(|item| item.0 == prefix)
+    }
+
+    /// Iterate over mounted prefixes in registration order
+    pub fn prefixes(&self) -> impl Iterator<Item = &str> {
+        self.scopes.iter().map(|item| item.0.as_str())
+    }
+}
+
+impl fmt::Debug for DynamicRoutes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.prefixes()).finish()
+    }
+}
+
+/// Routing table that could be changed at runtime.
+///
+/// Router is a handle to a routing table shared by all workers. Scopes
+/// could be mounted, unmounted or the whole table could be swapped at any
+/// time. Every change is atomic, workers rebuild their services on the
+/// next request and requests that are already in flight complete with
+/// the old table.
+///
+/// Router is used as a default service of an application or a scope,
+/// dynamic scopes are consulted only if none of the static routes match.
+/// Dynamic resources are not registered for url generation.
+///
+/// ```rust
+/// use kayrx::web::{self, App, DynamicRouter, HttpResponse};
+///
+/// let router = DynamicRouter::new();
+///
+/// let app = {
+///     let router = router.clone();
+///     move || {
+///         App::new()
+///             .service(web::resource("/").to(|| HttpResponse::Ok()))
+///             .default_service(router.clone())
+///     }
+/// };
+///
+/// // onboard tenant at runtime
+/// router.mount("/tenant-a", |cfg| {
+///     cfg.route("/index.html", web::get().to(|| HttpResponse::Ok()));
+/// });
+/// ```
+#[derive(Clone)]
+pub struct DynamicRouter(Arc<Shared>);
+
+struct Shared {
+    version: AtomicUsize,
+    routes: RwLock<Arc<DynamicRoutes>>,
+}
+
+impl Default for DynamicRouter {
+    fn default() -> Self {
+        DynamicRouter(Arc::new(Shared {
+            version: AtomicUsize::new(0),
+            routes: RwLock::new(Arc::new(DynamicRoutes::new())),
+        }))
+    }
+}
+
+impl fmt::Debug for DynamicRouter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DynamicRouter").field(&self.routes()).finish()
+    }
+}
+
+impl DynamicRouter {
+    /// Create router with empty routing table
+    pub fn new() -> Self {
+        DynamicRouter::default()
+    }
+
+    /// Mount scope at the prefix, scope with the same prefix gets replaced
+    pub fn mount<F>(&self, prefix: &str, f: F)
+    where
+        F: Fn(&mut ServiceConfig) + Send + Sync + 'static,
+    {
+        self.update(|routes| {
+            routes.mount(prefix, f);
+        })
+    }
+
+    /// Unmount scope, returns `false` if prefix is not mounted
+    pub fn unmount(&self, prefix: &str) -> bool {
+        self.update(|routes| routes.unmount(prefix))
+    }
+
+    /// Replace the whole routing table
+    pub fn swap(&self, routes: DynamicRoutes) -> Arc<DynamicRoutes> {
+        let mut table = self.0.routes.write().unwrap();
+        let old = std::mem::replace(&mut *table, Arc::new(routes));
+        self.0.version.fetch_add(1, Ordering::AcqRel);
+        old
+    }
+
+    /// Apply several changes to the routing table atomically
+    pub fn update<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut DynamicRoutes) -> R,
+    {
+        let mut table = self.0.routes.write().unwrap();
+        let mut routes = (**table).clone();
+        let res = f(&mut routes);
+        *table = Arc::new(routes);
+        self.0.version.fetch_add(1, Ordering::AcqRel);
+        res
+    }
+
+    /// Current routing table
+    pub fn routes(&self) -> Arc<DynamicRoutes> {
+        self.0.routes.read().unwrap().clone()
+    }
+
+    fn snapshot(&self) -> (usize, Arc<DynamicRoutes>) {
+        let table = self.0.routes.read().unwrap();
+        (self.0.version.load(Ordering::Acquire), table.clone())
+    }
+}
+
+impl ServiceFactory for DynamicRouter {
+    type Config = ();
+    type Request = ServiceRequest;
+    type Response = ServiceResponse;
+    type Error = Error;
+    type InitError = ();
+    type Service = DynamicRouterService;
+    type Future = Ready<Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        ok(DynamicRouterService {
+            router: self.clone(),
+            routing: Rc::new(RefCell::new(None)),
+        })
+    }
+}
+
+/// Per worker dynamic router service
+pub struct DynamicRouterService {
+    router: DynamicRouter,
+    routing: Rc<RefCell<Option<(usize, AppRouting)>>>,
+}
+
+impl Service for DynamicRouterService {
+    type Request = ServiceRequest;
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<ServiceResponse, Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let version = self.router.0.version.load(Ordering::Acquire);
+        if let Some((ver, ref mut routing)) = *self.routing.borrow_mut() {
+            if ver == version {
+                return routing.call(req);
+            }
+        }
+
+        // routing table has changed, build new services
+        let (version, routes) = self.router.snapshot();
+        let fut = build(&routes, req.app_config().clone());
+        let state = self.routing.clone();
+
+        async move {
+            let mut routing = fut
+                .await
+                .map_err(|_| ErrorInternalServerError("Can not build dynamic routes"))?;
+            let res = routing.call(req);
+
+            let mut state = state.borrow_mut();
+            let newer = match *state {
+                Some((ver, _)) => ver.wrapping_sub(version) as isize <= 0,
+                None => true,
+            };
+            if newer {
+                *state = Some((version, routing));
+            }
+            drop(state);
+
+            res.await
+        }
+        .boxed_local()
+    }
+}
+
+fn build(routes: &DynamicRoutes, config: AppConfig) -> AppRoutingFactoryResponse {
+    let default = Rc::new(boxed::factory(fn_service(|req: ServiceRequest| {
+        ok(req.into_response(Response::NotFound().finish()))
+    })));
+    let mut cfg = AppService::new(config, default.clone(), Rc::new(Vec::new()));
+    for (prefix, f) in &routes.scopes {
+        let f = f.clone();
+        Scope::new(prefix)
+            .configure(move |cfg| f(cfg))
+            .register(&mut cfg);
+    }
+
+    let services = cfg
+        .into_services()
+        .1
+        .into_iter()
+        .map(|(rdef, srv, guards, _)| (rdef, srv, RefCell::new(guards)))
+        .collect();
+    AppRoutingFactory::new(services, default).new_service(())
+}
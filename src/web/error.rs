@@ -1,11 +1,12 @@
 //! Error and Result module
 
-use std::result;
+use std::{fmt, result};
 use derive_more::{Display, From};
+use quick_xml::DeError as XmlError;
 use serde_json::error::Error as JsonError;
 use url::ParseError as UrlParseError;
 
-use crate::http::{StatusCode, Response as HttpResponse};
+use crate::http::{header, StatusCode, Response as HttpResponse};
 
 pub(crate) use crate::http::error::*;
 
@@ -13,6 +14,119 @@ pub(crate) use crate::http::error::*;
 /// for kayrx.
 pub type Result<T, E = Error> = result::Result<T, E>;
 
+/// Deserialize error with location of the value that failed.
+///
+/// Location is serde path of the field, i.e. `user.emails[1]`, json
+/// errors also carry line and column of the payload.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeserializeError {
+    path: Option<String>,
+    message: String,
+    position: Option<(usize, usize)>,
+}
+
+impl DeserializeError {
+    /// Create error from the serde error and path of failed field
+    pub fn new<E: fmt::Display>(err: E, path: Option<String>) -> Self {
+        DeserializeError {
+            path,
+            message: err.to_string(),
+            position: None,
+        }
+    }
+
+    /// Path of the field that failed, `None` if top level value failed
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_ref().map(|s| s.as_str())
+    }
+
+    /// Error message of the deserializer
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Line and column of the payload where error occurred
+    pub fn position(&self) -> Option<(usize, usize)> {
+        self.position
+    }
+}
+
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(ref path) = self.path {
+            write!(f, "{}: {}", path, self.message)
+        } else {
+            f.write_str(&self.message)
+        }
+    }
+}
+
+impl<E: fmt::Display> From<serde_path_to_error::Error<E>> for DeserializeError {
+    fn from(err: serde_path_to_error::Error<E>) -> Self {
+        let path = err.path().to_string();
+        let path = if path == "." { None } else { Some(path) };
+        DeserializeError::new(err.into_inner(), path)
+    }
+}
+
+impl From<serde::de::value::Error> for DeserializeError {
+    fn from(err: serde::de::value::Error) -> Self {
+        DeserializeError::new(err, None)
+    }
+}
+
+impl From<JsonError> for DeserializeError {
+    fn from(err: JsonError) -> Self {
+        let position = Some((err.line(), err.column()));
+        DeserializeError {
+            path: None,
+            message: err.to_string(),
+            position,
+        }
+    }
+}
+
+/// Deserialize json and keep path of the field that failed
+pub(crate) fn json_from_slice<T>(body: &[u8]) -> Result<T, DeserializeError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let mut de = serde_json::Deserializer::from_slice(body);
+    let res = serde_path_to_error::deserialize(&mut de).map_err(|err| {
+        let position = (err.inner().line(), err.inner().column());
+        let mut err = DeserializeError::from(err);
+        err.position = Some(position);
+        err
+    })?;
+    de.end()?;
+    Ok(res)
+}
+
+/// Common response of extractor errors.
+///
+/// Body is json object with `error` message, deserialize errors also
+/// contain `field` path and json errors `line` and `column`.
+pub(crate) fn extractor_error_response(
+    status: StatusCode,
+    err: &dyn fmt::Display,
+    de: Option<&DeserializeError>,
+) -> HttpResponse {
+    let mut body = serde_json::Map::new();
+    body.insert("error".to_owned(), err.to_string().into());
+    if let Some(de) = de {
+        if let Some(path) = de.path() {
+            body.insert("field".to_owned(), path.into());
+        }
+        if let Some((line, column)) = de.position() {
+            body.insert("line".to_owned(), line.into());
+            body.insert("column".to_owned(), column.into());
+        }
+    }
+    HttpResponse::build(status)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(serde_json::Value::Object(body).to_string())
+}
+
 /// Errors which can occur when attempting to generate resource uri.
 #[derive(Debug, PartialEq, Display, From)]
 pub enum UrlGenerationError {
@@ -30,6 +144,23 @@ pub enum UrlGenerationError {
 /// `InternalServerError` for `UrlGeneratorError`
 impl ResponseError for UrlGenerationError {}
 
+/// Errors which can occur when dispatching internal subrequest.
+#[derive(Debug, PartialEq, Display)]
+pub enum SubrequestError {
+    /// Path is not a valid absolute path
+    #[display(fmt = "Invalid subrequest path")]
+    InvalidPath,
+    /// Request is not dispatched by an application router
+    #[display(fmt = "Application router is not available")]
+    Unavailable,
+    /// Subrequest is polled while the router is dispatching a request
+    #[display(fmt = "Application router is busy")]
+    Busy,
+}
+
+/// `InternalServerError` for `SubrequestError`
+impl ResponseError for SubrequestError {}
+
 /// A set of errors that can occur during parsing urlencoded payloads
 #[derive(Debug, Display, From)]
 pub enum UrlencodedError {
@@ -63,6 +194,7 @@ impl ResponseError for UrlencodedError {
         match *self {
             UrlencodedError::Overflow { .. } => StatusCode::PAYLOAD_TOO_LARGE,
             UrlencodedError::UnknownLength => StatusCode::LENGTH_REQUIRED,
+            UrlencodedError::Payload(ref err) => err.status_code(),
             _ => StatusCode::BAD_REQUEST,
         }
     }
@@ -77,22 +209,137 @@ pub enum JsonPayloadError {
     /// Content type error
     #[display(fmt = "Content type error")]
     ContentType,
+    /// Nesting depth is bigger than allowed
+    #[display(fmt = "Json nesting depth is bigger than allowed")]
+    Depth,
     /// Deserialize error
     #[display(fmt = "Json deserialize error: {}", _0)]
-    Deserialize(JsonError),
+    Deserialize(DeserializeError),
     /// Payload error
     #[display(fmt = "Error that occur during reading payload: {}", _0)]
     Payload(PayloadError),
 }
 
+impl From<JsonError> for JsonPayloadError {
+    fn from(err: JsonError) -> Self {
+        JsonPayloadError::Deserialize(err.into())
+    }
+}
+
 /// Return `BadRequest` for `JsonPayloadError`
 impl ResponseError for JsonPayloadError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            JsonPayloadError::Overflow => StatusCode::PAYLOAD_TOO_LARGE,
+            JsonPayloadError::Payload(ref err) => err.status_code(),
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+
     fn error_response(&self) -> HttpResponse {
+        let de = match *self {
+            JsonPayloadError::Deserialize(ref e) => Some(e),
+            _ => None,
+        };
+        extractor_error_response(self.status_code(), self, de)
+    }
+}
+
+/// A set of errors that can occur during parsing and applying patch documents
+#[derive(Debug, Display, From)]
+pub enum PatchError {
+    /// Content type error
+    #[display(fmt = "Unsupported patch document content type")]
+    ContentType,
+    /// Payload error
+    #[display(fmt = "{}", _0)]
+    Payload(JsonPayloadError),
+    /// Patch document is malformed
+    #[display(fmt = "Invalid patch document: {}", _0)]
+    #[from(ignore)]
+    Invalid(String),
+    /// Patch can not be applied to the current state of the resource
+    #[display(fmt = "Patch can not be applied: {}", _0)]
+    #[from(ignore)]
+    Conflict(String),
+    /// Patched document does not deserialize into the resource type
+    #[display(fmt = "Patched document is invalid: {}", _0)]
+    Deserialize(JsonError),
+}
+
+/// Return `UnsupportedMediaType`, `BadRequest`, `Conflict` or
+/// `UnprocessableEntity` for `PatchError`
+impl ResponseError for PatchError {
+    fn status_code(&self) -> StatusCode {
         match *self {
-            JsonPayloadError::Overflow => {
-                HttpResponse::new(StatusCode::PAYLOAD_TOO_LARGE)
+            PatchError::ContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            PatchError::Payload(JsonPayloadError::Overflow) => {
+                StatusCode::PAYLOAD_TOO_LARGE
             }
-            _ => HttpResponse::new(StatusCode::BAD_REQUEST),
+            PatchError::Payload(_) | PatchError::Invalid(_) => StatusCode::BAD_REQUEST,
+            PatchError::Conflict(_) => StatusCode::CONFLICT,
+            PatchError::Deserialize(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+}
+
+/// A set of errors that can occur during parsing xml payloads
+#[derive(Debug, Display, From)]
+pub enum XmlPayloadError {
+    /// Payload size is bigger than allowed. (default: 32kB)
+    #[display(fmt = "Xml payload size is bigger than allowed")]
+    Overflow,
+    /// Content type error
+    #[display(fmt = "Content type error")]
+    ContentType,
+    /// Deserialize error
+    #[display(fmt = "Xml deserialize error: {}", _0)]
+    Deserialize(XmlError),
+    /// Payload error
+    #[display(fmt = "Error that occur during reading payload: {}", _0)]
+    Payload(PayloadError),
+}
+
+/// Return `BadRequest` for `XmlPayloadError`
+impl ResponseError for XmlPayloadError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            XmlPayloadError::Overflow => StatusCode::PAYLOAD_TOO_LARGE,
+            XmlPayloadError::Payload(ref err) => err.status_code(),
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// A set of errors that can occur during parsing protobuf payloads
+#[cfg(feature = "protobuf")]
+#[derive(Debug, Display, From)]
+pub enum ProtobufPayloadError {
+    /// Payload size is bigger than allowed. (default: 256kB)
+    #[display(fmt = "Protobuf payload size is bigger than allowed")]
+    Overflow,
+    /// Content type error
+    #[display(fmt = "Content type error")]
+    ContentType,
+    /// grpc-web framing error
+    #[display(fmt = "Malformed grpc-web frame")]
+    Framing,
+    /// Deserialize error
+    #[display(fmt = "Protobuf decode error: {}", _0)]
+    Deserialize(prost::DecodeError),
+    /// Payload error
+    #[display(fmt = "Error that occur during reading payload: {}", _0)]
+    Payload(PayloadError),
+}
+
+/// Return `BadRequest` for `ProtobufPayloadError`
+#[cfg(feature = "protobuf")]
+impl ResponseError for ProtobufPayloadError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            ProtobufPayloadError::Overflow => StatusCode::PAYLOAD_TOO_LARGE,
+            ProtobufPayloadError::Payload(ref err) => err.status_code(),
+            _ => StatusCode::BAD_REQUEST,
         }
     }
 }
@@ -102,7 +349,7 @@ impl ResponseError for JsonPayloadError {
 pub enum PathError {
     /// Deserialize error
     #[display(fmt = "Path deserialize error: {}", _0)]
-    Deserialize(serde::de::value::Error),
+    Deserialize(DeserializeError),
 }
 
 /// Return `BadRequest` for `PathError`
@@ -110,6 +357,18 @@ impl ResponseError for PathError {
     fn status_code(&self) -> StatusCode {
         StatusCode::BAD_REQUEST
     }
+
+    fn error_response(&self) -> HttpResponse {
+        let PathError::Deserialize(ref de) = *self;
+        extractor_error_response(self.status_code(), self, Some(de))
+    }
+}
+
+/// Return `BadRequest` for path decoding error
+impl ResponseError for crate::router::DecodeError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
 }
 
 /// A set of errors that can occur during parsing query strings
@@ -117,7 +376,7 @@ impl ResponseError for PathError {
 pub enum QueryPayloadError {
     /// Deserialize error
     #[display(fmt = "Query deserialize error: {}", _0)]
-    Deserialize(serde::de::value::Error),
+    Deserialize(DeserializeError),
 }
 
 /// Return `BadRequest` for `QueryPayloadError`
@@ -125,6 +384,29 @@ impl ResponseError for QueryPayloadError {
     fn status_code(&self) -> StatusCode {
         StatusCode::BAD_REQUEST
     }
+
+    fn error_response(&self) -> HttpResponse {
+        let QueryPayloadError::Deserialize(ref de) = *self;
+        extractor_error_response(self.status_code(), self, Some(de))
+    }
+}
+
+/// A set of errors that can occur during typed header extraction
+#[derive(Debug, Display)]
+pub enum HeaderError {
+    /// Header is missing
+    #[display(fmt = "Missing header: {}", _0)]
+    Missing(crate::http::header::HeaderName),
+    /// Header value can not be parsed
+    #[display(fmt = "Can not parse header: {}", _0)]
+    Invalid(crate::http::header::HeaderName),
+}
+
+/// Return `BadRequest` for `HeaderError`
+impl ResponseError for HeaderError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
 }
 
 /// Error type returned when reading body as lines.
@@ -150,6 +432,7 @@ impl ResponseError for ReadlinesError {
     fn status_code(&self) -> StatusCode {
         match *self {
             ReadlinesError::LimitOverflow => StatusCode::PAYLOAD_TOO_LARGE,
+            ReadlinesError::Payload(ref err) => err.status_code(),
             _ => StatusCode::BAD_REQUEST,
         }
     }
@@ -178,15 +461,46 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
 
+    #[test]
+    fn test_patch_error() {
+        let resp: HttpResponse = PatchError::ContentType.error_response();
+        assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        let resp: HttpResponse =
+            PatchError::Payload(JsonPayloadError::Overflow).error_response();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let resp: HttpResponse = PatchError::Invalid("op".to_owned()).error_response();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp: HttpResponse = PatchError::Conflict("/a".to_owned()).error_response();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+    }
+
+    #[test]
+    fn test_xml_payload_error() {
+        let resp: HttpResponse = XmlPayloadError::Overflow.error_response();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let resp: HttpResponse = XmlPayloadError::ContentType.error_response();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
     #[test]
     fn test_query_payload_error() {
         let resp: HttpResponse = QueryPayloadError::Deserialize(
-            serde_urlencoded::from_str::<i32>("bad query").unwrap_err(),
+            serde_urlencoded::from_str::<i32>("bad query").unwrap_err().into(),
         )
         .error_response();
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
 
+    #[test]
+    fn test_header_error() {
+        let resp: HttpResponse =
+            HeaderError::Missing(crate::http::header::ACCEPT).error_response();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp: HttpResponse =
+            HeaderError::Invalid(crate::http::header::ACCEPT).error_response();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
     #[test]
     fn test_readlines_error() {
         let resp: HttpResponse = ReadlinesError::LimitOverflow.error_response();
@@ -0,0 +1,182 @@
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
+
+use crate::http::error::Error;
+use crate::http::header::{self, HeaderMap, HeaderName, HeaderValue};
+use crate::http::{RequestHead, Response as HttpResponse};
+use crate::web::guard::Guard;
+
+/// Configuration for files under specific directory.
+///
+/// Registered with `Files::directory()`, it allows to restrict access to
+/// the directory with guards and HTTP Basic authentication and to add
+/// custom headers to served files.
+///
+/// ```rust
+/// use kayrx::web::{guard, App};
+/// use kayrx::web::files::{DirConfig, Files};
+///
+/// fn main() {
+///     let app = App::new().service(
+///         Files::new("/", "./dist")
+///             // hashed assets never change
+///             .directory(
+///                 "assets",
+///                 DirConfig::new()
+///                     .header("cache-control", "public, max-age=31536000, immutable"),
+///             )
+///             .directory(
+///                 "private",
+///                 DirConfig::new()
+///                     .guard(guard::Get())
+///                     .basic_auth("private", |user, password| {
+///                         user == "admin" && password == "secret"
+///                     }),
+///             ),
+///     );
+/// }
+/// ```
+pub struct DirConfig {
+    guards: Vec<Box<dyn Guard>>,
+    headers: HeaderMap,
+    auth: Option<BasicAuth>,
+}
+
+struct BasicAuth {
+    realm: String,
+    check: Box<dyn Fn(&str, &str) -> bool>,
+}
+
+impl Default for DirConfig {
+    fn default() -> Self {
+        DirConfig::new()
+    }
+}
+
+impl DirConfig {
+    /// Create empty directory configuration
+    pub fn new() -> Self {
+        DirConfig {
+            guards: Vec::new(),
+            headers: HeaderMap::new(),
+            auth: None,
+        }
+    }
+
+    /// Add guard, requests that do not pass it get `404 Not Found` response.
+    pub fn guard<G: Guard + 'static>(mut self, guard: G) -> Self {
+        self.guards.push(Box::new(guard));
+        self
+    }
+
+    /// Add header to every file served from the directory.
+    pub fn header<K, V>(mut self, key: K, value: V) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<Error>,
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<Error>,
+    {
+        #[allow(clippy::match_wild_err_arm)]
+        match HeaderName::try_from(key) {
+            Ok(key) => match HeaderValue::try_from(value) {
+                Ok(value) => self.headers.append(key, value),
+                Err(_) => panic!("Can not create header value"),
+            },
+            Err(_) => panic!("Can not create header name"),
+        }
+        self
+    }
+
+    /// Protect directory with HTTP Basic authentication.
+    ///
+    /// `check` receives user name and password from `Authorization` header.
+    /// Requests without valid credentials get `401 Unauthorized` response
+    /// with `WWW-Authenticate` challenge for the specified realm.
+    pub fn basic_auth<T, F>(mut self, realm: T, check: F) -> Self
+    where
+        T: Into<String>,
+        F: Fn(&str, &str) -> bool + 'static,
+    {
+        self.auth = Some(BasicAuth {
+            realm: realm.into(),
+            check: Box::new(check),
+        });
+        self
+    }
+
+    /// Check request against guards and authentication.
+    ///
+    /// Returns response that should be sent instead of the file.
+    pub(crate) fn check(&self, head: &RequestHead) -> Option<HttpResponse> {
+        if !self.guards.iter().all(|guard| guard.check(head)) {
+            return Some(HttpResponse::NotFound().finish());
+        }
+
+        if let Some(ref auth) = self.auth {
+            let authorized = head
+                .headers()
+                .get(header::AUTHORIZATION)
+                .and_then(|val| basic_credentials(val))
+                .map(|(user, password)| (auth.check)(&user, &password))
+                .unwrap_or(false);
+
+            if !authorized {
+                return Some(
+                    HttpResponse::Unauthorized()
+                        .header(
+                            header::WWW_AUTHENTICATE,
+                            format!("Basic realm=\"{}\"", auth.realm.replace('"', "")),
+                        )
+                        .finish(),
+                );
+            }
+        }
+        None
+    }
+
+    /// Add configured headers to the response
+    pub(crate) fn apply(&self, res: &mut HttpResponse) {
+        for key in self.headers.keys() {
+            res.headers_mut().remove(key);
+        }
+        for (key, value) in self.headers.iter() {
+            res.headers_mut().append(key.clone(), value.clone());
+        }
+    }
+}
+
+/// Parse credentials of `Authorization: Basic ...` header
+fn basic_credentials(value: &HeaderValue) -> Option<(String, String)> {
+    let value = value.to_str().ok()?;
+    let mut parts = value.splitn(2, ' ');
+    if !parts.next()?.eq_ignore_ascii_case("basic") {
+        return None;
+    }
+    let decoded = base64::decode(parts.next()?.trim()).ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    let mut credentials = decoded.splitn(2, ':');
+    let user = credentials.next()?.to_owned();
+    let password = credentials.next().unwrap_or("").to_owned();
+    Some((user, password))
+}
+
+/// Resolve configured directory relative to the service base directory.
+///
+/// Directory is canonicalized, so it could be compared with canonical file
+/// paths regardless of symlinks or case of the request path.
+pub(crate) fn resolve(base: &Path, dir: &str) -> PathBuf {
+    let dir = base.join(dir.trim_matches('/'));
+    match dir.canonicalize() {
+        Ok(dir) => dir,
+        Err(_) => {
+            log::error!("Specified path is not a directory: {:?}", dir);
+            dir
+        }
+    }
+}
+
+/// Check if file path is located in the directory
+pub(crate) fn matches(dir: &Path, path: &Path) -> bool {
+    path.starts_with(dir)
+}
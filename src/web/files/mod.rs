@@ -32,10 +32,12 @@ use mime_guess::from_ext;
 use percent_encoding::{utf8_percent_encode, CONTROLS};
 use v_htmlescape::escape as escape_html_entity;
 
+mod dir;
 mod error;
 mod named;
 mod range;
 
+pub use self::dir::DirConfig;
 pub use self::error::{FilesError, UriSegmentError};
 pub use self::named::NamedFile;
 pub use self::range::HttpRange;
@@ -231,13 +233,16 @@ type MimeOverride = dyn Fn(&mime::Name) -> DispositionType;
 /// Static files handling
 ///
 /// `Files` service must be registered with `App::service()` method.
+/// It serves files of the directory with guessed mime type, supports
+/// `Range` and `If-Range` requests, `ETag` and `Last-Modified`
+/// conditional responses and directory index files.
 ///
 /// ```rust
-/// use kayrx::web::{App, file as fs};
+/// use kayrx::web::{files::Files, App};
 ///
 /// fn main() {
 ///     let app = App::new()
-///         .service(fs::Files::new("/static", "."));
+///         .service(Files::new("/static", ".").index_file("index.html"));
 /// }
 /// ```
 pub struct Files {
@@ -251,6 +256,15 @@ pub struct Files {
     mime_override: Option<Rc<MimeOverride>>,
     file_flags: named::Flags,
     guards: Option<Rc<Box<dyn Guard>>>,
+    precompressed: bool,
+    fallback: Option<Rc<Fallback>>,
+    dirs: Vec<(PathBuf, Rc<DirConfig>)>,
+}
+
+/// Entrypoint served for unknown paths, see `Files::index_fallback()`
+struct Fallback {
+    file: String,
+    exclude: Vec<String>,
 }
 
 impl Clone for Files {
@@ -266,6 +280,9 @@ impl Clone for Files {
             path: self.path.clone(),
             mime_override: self.mime_override.clone(),
             guards: self.guards.clone(),
+            precompressed: self.precompressed,
+            fallback: self.fallback.clone(),
+            dirs: self.dirs.clone(),
         }
     }
 }
@@ -297,6 +314,9 @@ impl Files {
             mime_override: None,
             file_flags: named::Flags::default(),
             guards: None,
+            precompressed: false,
+            fallback: None,
+            dirs: Vec::new(),
         }
     }
 
@@ -371,6 +391,83 @@ impl Files {
         self
     }
 
+    /// Serve pre-compressed versions of files if they exist.
+    ///
+    /// For a requested file `app.js` service looks for `app.js.br` and
+    /// `app.js.gz` siblings and, if the client accepts the respective
+    /// encoding, serves the sibling with `Content-Encoding` header set.
+    /// Such responses are not compressed again by `Compress` middleware.
+    ///
+    /// By default pre-compressed files are not used.
+    #[inline]
+    pub fn use_precompressed(mut self, value: bool) -> Self {
+        self.precompressed = value;
+        self
+    }
+
+    /// Serve specified file for requests that do not match any file.
+    ///
+    /// Single page applications route on the client side with history API,
+    /// so every unknown path should load application entrypoint. Fallback
+    /// is served only for `GET` and `HEAD` requests with
+    /// `Cache-Control: no-cache` header, so clients always revalidate
+    /// the entrypoint. Paths that start with prefixes registered with
+    /// `Files::fallback_exclude()` are not affected.
+    ///
+    /// ```rust
+    /// use kayrx::web::App;
+    /// use kayrx::web::files::Files;
+    ///
+    /// fn main() {
+    ///     let app = App::new().service(
+    ///         Files::new("/", "./dist")
+    ///             .index_file("index.html")
+    ///             .index_fallback("index.html")
+    ///             .fallback_exclude("/api"),
+    ///     );
+    /// }
+    /// ```
+    pub fn index_fallback<T: Into<String>>(mut self, file: T) -> Self {
+        let exclude = self
+            .fallback
+            .take()
+            .map(|f| f.exclude.clone())
+            .unwrap_or_default();
+        self.fallback = Some(Rc::new(Fallback {
+            file: file.into(),
+            exclude,
+        }));
+        self
+    }
+
+    /// Do not serve index fallback for request paths with specified prefix.
+    ///
+    /// Has effect only if `Files::index_fallback()` is set.
+    pub fn fallback_exclude<T: Into<String>>(mut self, prefix: T) -> Self {
+        if let Some(fallback) = self.fallback.take() {
+            let mut exclude = fallback.exclude.clone();
+            exclude.push(prefix.into());
+            self.fallback = Some(Rc::new(Fallback {
+                file: fallback.file.clone(),
+                exclude,
+            }));
+        }
+        self
+    }
+
+    /// Set configuration for files under specified directory.
+    ///
+    /// Directory is relative to the service base directory. Configuration of
+    /// every matching directory applies to the request, in registration
+    /// order. Files are matched by their resolved location on disk, so a
+    /// symlink into the directory is subject to the same configuration.
+    /// See [`DirConfig`](struct.DirConfig.html) for details.
+    pub fn directory<T: Into<String>>(mut self, dir: T, config: DirConfig) -> Self {
+        let dir = dir::resolve(&self.directory, &dir.into());
+        self.dirs.push((dir, Rc::new(config)));
+        self
+    }
+
     /// Disable `Content-Disposition` header.
     ///
     /// By default Content-Disposition` header is enabled.
@@ -434,6 +531,9 @@ impl ServiceFactory for Files {
             mime_override: self.mime_override.clone(),
             file_flags: self.file_flags,
             guards: self.guards.clone(),
+            precompressed: self.precompressed,
+            fallback: self.fallback.clone(),
+            dirs: self.dirs.clone(),
         };
 
         if let Some(ref default) = *self.default.borrow() {
@@ -463,6 +563,9 @@ pub struct FilesService {
     mime_override: Option<Rc<MimeOverride>>,
     file_flags: named::Flags,
     guards: Option<Rc<Box<dyn Guard>>>,
+    precompressed: bool,
+    fallback: Option<Rc<Fallback>>,
+    dirs: Vec<(PathBuf, Rc<DirConfig>)>,
 }
 
 impl FilesService {
@@ -481,6 +584,133 @@ impl FilesService {
             Either::Left(ok(req.error_response(e)))
         }
     }
+
+    /// Path of the index fallback file, if it should be served for request
+    fn fallback_path(&self, req: &ServiceRequest) -> Option<PathBuf> {
+        let fallback = self.fallback.as_ref()?;
+        match *req.method() {
+            Method::HEAD | Method::GET => (),
+            _ => return None,
+        }
+        if fallback
+            .exclude
+            .iter()
+            .any(|prefix| req.path().starts_with(prefix.as_str()))
+        {
+            return None;
+        }
+        Some(self.directory.join(&fallback.file))
+    }
+
+    fn serve_file(
+        &mut self,
+        path: PathBuf,
+        req: ServiceRequest,
+        fallback: bool,
+        dirs: &[Rc<DirConfig>],
+    ) -> Either<
+        Ready<Result<ServiceResponse, Error>>,
+        LocalBoxFuture<'static, Result<ServiceResponse, Error>>,
+    > {
+        let named_file = if self.precompressed {
+            open_precompressed(&path, req.headers())
+        } else {
+            NamedFile::open(path)
+        };
+
+        match named_file {
+            Ok(mut named_file) => {
+                if let Some(ref mime_override) = self.mime_override {
+                    let new_disposition =
+                        mime_override(&named_file.content_type.type_());
+                    named_file.content_disposition.disposition = new_disposition;
+                }
+
+                named_file.flags = self.file_flags;
+                let (req, _) = req.into_parts();
+                Either::Left(ok(match named_file.into_response(&req) {
+                    Ok(mut item) => {
+                        if self.precompressed {
+                            item.headers_mut().append(
+                                header::VARY,
+                                header::HeaderValue::from_static("accept-encoding"),
+                            );
+                        }
+                        if fallback {
+                            item.headers_mut().insert(
+                                header::CACHE_CONTROL,
+                                header::HeaderValue::from_static("no-cache"),
+                            );
+                        }
+                        for dir in dirs {
+                            dir.apply(&mut item);
+                        }
+                        ServiceResponse::new(req, item)
+                    }
+                    Err(e) => ServiceResponse::from_err(e, req),
+                }))
+            }
+            Err(e) => self.handle_err(e, req),
+        }
+    }
+}
+
+/// Open pre-compressed sibling of the file if client accepts its encoding,
+/// otherwise open the file itself.
+fn open_precompressed(path: &Path, headers: &header::HeaderMap) -> io::Result<NamedFile> {
+    for encoding in accepted_encodings(headers) {
+        let ext = match encoding {
+            header::ContentEncoding::Br => "br",
+            header::ContentEncoding::Gzip => "gz",
+            _ => continue,
+        };
+        let mut sibling = path.as_os_str().to_owned();
+        sibling.push(".");
+        sibling.push(ext);
+        let sibling = PathBuf::from(sibling);
+
+        if sibling.is_file() {
+            // content type and disposition are derived from the original name
+            let mut named_file = NamedFile::from_file(File::open(&sibling)?, path)?;
+            named_file.precompressed = Some(encoding);
+            return Ok(named_file);
+        }
+    }
+    NamedFile::open(path)
+}
+
+/// Encodings with non-zero quality from `Accept-Encoding` header,
+/// most preferred first.
+fn accepted_encodings(headers: &header::HeaderMap) -> Vec<header::ContentEncoding> {
+    let mut encodings: Vec<(header::ContentEncoding, f32)> = headers
+        .get_all(header::ACCEPT_ENCODING)
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| val.split(','))
+        .filter_map(|item| {
+            let mut parts = item.split(';');
+            let encoding = header::ContentEncoding::from(parts.next()?.trim());
+            let quality = parts
+                .filter_map(|p| {
+                    let p = p.trim();
+                    if p.starts_with("q=") {
+                        p[2..].parse::<f32>().ok()
+                    } else {
+                        None
+                    }
+                })
+                .next()
+                .unwrap_or(1.0);
+            if quality > 0.0 {
+                Some((encoding, quality))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    // stable sort keeps order of equally preferred encodings
+    encodings.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(cmp::Ordering::Equal));
+    encodings.into_iter().map(|(enc, _)| enc).collect()
 }
 
 impl Service for FilesService {
@@ -522,9 +752,37 @@ impl Service for FilesService {
         };
 
         // full filepath
-        let path = match self.directory.join(&real_path.0).canonicalize() {
+        let path = self.directory.join(&real_path.0).canonicalize();
+
+        // per-directory access checks, missing files are matched by
+        // requested location
+        let dirs: Vec<_> = {
+            let file = match path {
+                Ok(ref path) => path.clone(),
+                Err(_) => self.directory.join(&real_path.0),
+            };
+            self.dirs
+                .iter()
+                .filter(|(dir, _)| dir::matches(dir, &file))
+                .map(|(_, config)| config.clone())
+                .collect()
+        };
+        for config in &dirs {
+            if let Some(res) = config.check(req.head()) {
+                return Either::Left(ok(req.into_response(res)));
+            }
+        }
+
+        let path = match path {
             Ok(path) => path,
-            Err(e) => return self.handle_err(e, req),
+            Err(e) => {
+                return match self.fallback_path(&req) {
+                    Some(path) if e.kind() == io::ErrorKind::NotFound => {
+                        self.serve_file(path, req, true, &[])
+                    }
+                    _ => self.handle_err(e, req),
+                }
+            }
         };
 
         if path.is_dir() {
@@ -539,25 +797,7 @@ impl Service for FilesService {
                     )));
                 }
 
-                let path = path.join(redir_index);
-
-                match NamedFile::open(path) {
-                    Ok(mut named_file) => {
-                        if let Some(ref mime_override) = self.mime_override {
-                            let new_disposition =
-                                mime_override(&named_file.content_type.type_());
-                            named_file.content_disposition.disposition = new_disposition;
-                        }
-
-                        named_file.flags = self.file_flags;
-                        let (req, _) = req.into_parts();
-                        Either::Left(ok(match named_file.into_response(&req) {
-                            Ok(item) => ServiceResponse::new(req, item),
-                            Err(e) => ServiceResponse::from_err(e, req),
-                        }))
-                    }
-                    Err(e) => self.handle_err(e, req),
-                }
+                self.serve_file(path.join(redir_index), req, false, &dirs)
             } else if self.show_index {
                 let dir = Directory::new(self.directory.clone(), path);
                 let (req, _) = req.into_parts();
@@ -573,25 +813,7 @@ impl Service for FilesService {
                 )))
             }
         } else {
-            match NamedFile::open(path) {
-                Ok(mut named_file) => {
-                    if let Some(ref mime_override) = self.mime_override {
-                        let new_disposition =
-                            mime_override(&named_file.content_type.type_());
-                        named_file.content_disposition.disposition = new_disposition;
-                    }
-
-                    named_file.flags = self.file_flags;
-                    let (req, _) = req.into_parts();
-                    match named_file.into_response(&req) {
-                        Ok(item) => {
-                            Either::Left(ok(ServiceResponse::new(req.clone(), item)))
-                        }
-                        Err(e) => Either::Left(ok(ServiceResponse::from_err(e, req))),
-                    }
-                }
-                Err(e) => self.handle_err(e, req),
-            }
+            self.serve_file(path, req, false, &dirs)
         }
     }
 }
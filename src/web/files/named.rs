@@ -48,6 +48,7 @@ pub struct NamedFile {
     pub(crate) content_type: mime::Mime,
     pub(crate) content_disposition: header::ContentDisposition,
     pub(crate) encoding: Option<ContentEncoding>,
+    pub(crate) precompressed: Option<ContentEncoding>,
 }
 
 impl NamedFile {
@@ -120,6 +121,7 @@ impl NamedFile {
             md,
             modified,
             encoding,
+            precompressed: None,
             status_code: StatusCode::OK,
             flags: Flags::default(),
         })
@@ -269,6 +271,9 @@ impl NamedFile {
             if let Some(current_encoding) = self.encoding {
                 resp.encoding(current_encoding);
             }
+            if let Some(precompressed) = self.precompressed {
+                resp.header(header::CONTENT_ENCODING, precompressed.as_str());
+            }
             let reader = ChunkedReadFile {
                 size: self.md.len(),
                 offset: 0,
@@ -336,21 +341,36 @@ impl NamedFile {
         if let Some(current_encoding) = self.encoding {
             resp.encoding(current_encoding);
         }
+        // file content is already encoded, skip runtime compression
+        if let Some(precompressed) = self.precompressed {
+            resp.header(header::CONTENT_ENCODING, precompressed.as_str());
+        }
 
         resp.if_some(last_modified, |lm, resp| {
             resp.set(header::LastModified(lm));
         })
-        .if_some(etag, |etag, resp| {
+        .if_some(etag.clone(), |etag, resp| {
             resp.set(header::ETag(etag));
         });
 
         resp.header(header::ACCEPT_RANGES, "bytes");
 
+        if precondition_failed {
+            return Ok(resp.status(StatusCode::PRECONDITION_FAILED).finish());
+        } else if not_modified {
+            return Ok(resp.status(StatusCode::NOT_MODIFIED).finish());
+        }
+
         let mut length = self.md.len();
         let mut offset = 0;
 
-        // check for range header
-        if let Some(ranges) = req.headers().get(&header::RANGE) {
+        // check for range header, ignore it if `If-Range` validator is stale
+        let ranges = if range_fresh(etag.as_ref(), last_modified.as_ref(), req) {
+            req.headers().get(&header::RANGE)
+        } else {
+            None
+        };
+        if let Some(ranges) = ranges {
             if let Ok(rangesheader) = ranges.to_str() {
                 if let Ok(rangesvec) = HttpRange::parse(rangesheader, length) {
                     length = rangesvec[0].length;
@@ -374,12 +394,6 @@ impl NamedFile {
             };
         };
 
-        if precondition_failed {
-            return Ok(resp.status(StatusCode::PRECONDITION_FAILED).finish());
-        } else if not_modified {
-            return Ok(resp.status(StatusCode::NOT_MODIFIED).finish());
-        }
-
         let reader = ChunkedReadFile {
             offset,
             size: length,
@@ -444,6 +458,34 @@ fn none_match(etag: Option<&header::EntityTag>, req: &HttpRequest) -> bool {
     }
 }
 
+/// Returns true if `req` doesn't have an `If-Range` header or its validator
+/// matches current representation, so `Range` header can be used.
+fn range_fresh(
+    etag: Option<&header::EntityTag>,
+    last_modified: Option<&header::HttpDate>,
+    req: &HttpRequest,
+) -> bool {
+    match req.get_header::<header::IfRange>() {
+        None => true,
+        Some(header::IfRange::EntityTag(ref tag)) => {
+            etag.map(|etag| tag.strong_eq(etag)).unwrap_or(false)
+        }
+        Some(header::IfRange::Date(ref since)) => {
+            // http date has one second resolution
+            let secs = |date: &header::HttpDate| {
+                SystemTime::from(*date)
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .ok()
+            };
+            match last_modified {
+                Some(lm) => secs(lm).is_some() && secs(lm) == secs(since),
+                None => false,
+            }
+        }
+    }
+}
+
 impl Responder for NamedFile {
     type Error = Error;
     type Future = Ready<Result<HttpResponse, Error>>;
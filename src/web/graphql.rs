@@ -0,0 +1,623 @@
+//! GraphQL integration
+//!
+//! Module provides transport for GraphQL servers, query execution is
+//! delegated to an [`Executor`](trait.Executor.html) implementation.
+//!
+//! * [`GraphQLRequest`](struct.GraphQLRequest.html) extractor accepts `GET`
+//!   requests with query parameters, `POST` requests with json or
+//!   `application/graphql` body and `multipart/form-data` uploads as
+//!   defined by the GraphQL multipart request spec.
+//! * [`GraphQLResponse`](struct.GraphQLResponse.html) responder sends
+//!   execution result as json.
+//! * [`GraphQL`](struct.GraphQL.html) service handles queries on the path and
+//!   optionally serves subscriptions over websocket with `graphql-ws`
+//!   protocol.
+//!
+//! ```rust
+//! use futures::future::{ready, FutureExt, LocalBoxFuture};
+//! use kayrx::web::graphql::{Executor, GraphQL, GraphQLRequest, GraphQLResponse};
+//! use kayrx::web::App;
+//! use serde_json::json;
+//!
+//! struct Hello;
+//!
+//! impl Executor for Hello {
+//!     fn execute(&self, req: GraphQLRequest) -> LocalBoxFuture<'static, GraphQLResponse> {
+//!         let res = if req.query.contains("hello") {
+//!             GraphQLResponse::data(json!({"hello": "world"}))
+//!         } else {
+//!             GraphQLResponse::error("Unknown field")
+//!         };
+//!         ready(res).boxed_local()
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let app = App::new().service(GraphQL::new("/graphql", Hello).subscriptions(true));
+//! }
+//! ```
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+use std::time::Duration;
+
+use bytes::{Bytes, BytesMut};
+use derive_more::{Display, From};
+use futures_util::future::{abortable, ok, ready, AbortHandle, Ready};
+use futures_util::future::{FutureExt, LocalBoxFuture};
+use futures_util::stream::{self, LocalBoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::http::error::{Error, PayloadError, ResponseError};
+use crate::http::header::{CONNECTION, UPGRADE};
+use crate::http::{HttpMessage, Method, Payload, Response, StatusCode};
+use crate::router::ResourceDef;
+use crate::service::fn_service;
+use crate::timer::delay_for;
+use crate::web::config::AppService;
+use crate::web::extract::FromRequest;
+use crate::web::guard;
+use crate::web::multipart::{Multipart, MultipartError};
+use crate::web::request::HttpRequest;
+use crate::web::responder::Responder;
+use crate::web::service::{HttpServiceFactory, ServiceRequest, ServiceResponse};
+use crate::web::types::payload::HttpMessageBody;
+use crate::web::ws::{self, Message, WsContext, WsSession};
+
+/// Websocket sub-protocol of subscriptions transport
+const PROTOCOL: &str = "graphql-ws";
+
+/// GraphQL executor
+pub trait Executor: 'static {
+    /// Execute query or mutation.
+    ///
+    /// Queries of `GET` requests are executed as well, executor should
+    /// reject mutations if `GraphQLRequest::is_get()` returns true.
+    fn execute(&self, req: GraphQLRequest) -> LocalBoxFuture<'static, GraphQLResponse>;
+
+    /// Execute subscription, every item of the stream is sent to the client.
+    ///
+    /// By default operation is executed once with `execute()`.
+    fn subscribe(&self, req: GraphQLRequest) -> LocalBoxStream<'static, GraphQLResponse> {
+        stream::once(self.execute(req)).boxed_local()
+    }
+}
+
+/// GraphQL operation
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphQLRequest {
+    /// Query document
+    pub query: String,
+    /// Name of the operation to execute
+    #[serde(default)]
+    pub operation_name: Option<String>,
+    /// Operation variables
+    #[serde(default)]
+    pub variables: Option<Value>,
+    /// Protocol extensions
+    #[serde(default)]
+    pub extensions: Option<Value>,
+    /// Uploaded files of multipart request
+    #[serde(skip)]
+    pub uploads: Vec<Upload>,
+    #[serde(skip)]
+    get: bool,
+}
+
+impl GraphQLRequest {
+    /// Create operation with query document
+    pub fn new<T: Into<String>>(query: T) -> Self {
+        GraphQLRequest {
+            query: query.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Check if operation is received with `GET` request
+    pub fn is_get(&self) -> bool {
+        self.get
+    }
+
+    /// Uploaded file for variable path, i.e. `variables.files.0`
+    pub fn upload(&self, path: &str) -> Option<&Upload> {
+        self.uploads.iter().find(|upload| upload.path == path)
+    }
+}
+
+/// File uploaded with multipart request.
+///
+/// Variable of the file is `null` in operation variables.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Upload {
+    /// Variable path of the file, i.e. `variables.file`
+    pub path: String,
+    /// File name provided by client
+    pub filename: Option<String>,
+    /// Content type of the file
+    pub content_type: String,
+    /// File content
+    pub data: Bytes,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GetParams {
+    query: String,
+    operation_name: Option<String>,
+    variables: Option<String>,
+    extensions: Option<String>,
+}
+
+/// Operation extractor configuration
+#[derive(Clone, Debug)]
+pub struct GraphQLConfig {
+    limit: usize,
+    upload_limit: usize,
+}
+
+impl GraphQLConfig {
+    /// Change max size of json or graphql body. By default max size is 256Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Change max size of multipart request. By default max size is 8Mb
+    pub fn upload_limit(mut self, limit: usize) -> Self {
+        self.upload_limit = limit;
+        self
+    }
+}
+
+impl Default for GraphQLConfig {
+    fn default() -> Self {
+        GraphQLConfig {
+            limit: 262_144,
+            upload_limit: 8_388_608,
+        }
+    }
+}
+
+/// A set of errors that can occur during GraphQL request extraction
+#[derive(Debug, Display, From)]
+pub enum GraphQLError {
+    /// Request is not a valid GraphQL request
+    #[display(fmt = "Invalid GraphQL request: {}", _0)]
+    #[from(ignore)]
+    Invalid(String),
+    /// Content type is not supported
+    #[display(fmt = "Content type error")]
+    ContentType,
+    /// Payload size is bigger than allowed
+    #[display(fmt = "GraphQL payload size is bigger than allowed")]
+    Overflow,
+    /// Payload error
+    #[display(fmt = "Error that occur during reading payload: {}", _0)]
+    Payload(PayloadError),
+    /// Multipart error
+    #[display(fmt = "Multipart error: {}", _0)]
+    Multipart(MultipartError),
+    /// Json error
+    #[display(fmt = "Json deserialize error: {}", _0)]
+    Json(serde_json::Error),
+}
+
+/// Return `BadRequest` for `GraphQLError`
+impl ResponseError for GraphQLError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            GraphQLError::ContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            GraphQLError::Overflow => StatusCode::PAYLOAD_TOO_LARGE,
+            GraphQLError::Payload(ref err) => err.status_code(),
+            GraphQLError::Multipart(ref err) => err.status_code(),
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+impl FromRequest for GraphQLRequest {
+    type Config = GraphQLConfig;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let cfg = req
+            .app_data::<GraphQLConfig>()
+            .cloned()
+            .unwrap_or_default();
+
+        if *req.method() == Method::GET {
+            let res = from_query(req.query_string()).map_err(Error::from);
+            return ready(res).boxed_local();
+        }
+
+        match req.content_type() {
+            "application/json" => {
+                let body = HttpMessageBody::new(req, payload).limit(cfg.limit);
+                async move {
+                    let body = body.await.map_err(payload_error)?;
+                    let op: GraphQLRequest =
+                        serde_json::from_slice(&body).map_err(GraphQLError::from)?;
+                    Ok(op)
+                }
+                .boxed_local()
+            }
+            "application/graphql" => {
+                let body = HttpMessageBody::new(req, payload).limit(cfg.limit);
+                async move {
+                    let body = body.await.map_err(payload_error)?;
+                    let query = String::from_utf8(body.to_vec()).map_err(|_| {
+                        GraphQLError::Invalid("Query is not valid utf-8".to_owned())
+                    })?;
+                    Ok(GraphQLRequest::new(query))
+                }
+                .boxed_local()
+            }
+            "multipart/form-data" => {
+                let mp = Multipart::new(req.headers(), payload.take());
+                async move { Ok(from_multipart(mp, cfg.upload_limit).await?) }.boxed_local()
+            }
+            _ => async { Err(GraphQLError::ContentType.into()) }.boxed_local(),
+        }
+    }
+}
+
+fn payload_error(err: PayloadError) -> GraphQLError {
+    match err {
+        PayloadError::Overflow => GraphQLError::Overflow,
+        err => GraphQLError::Payload(err),
+    }
+}
+
+fn from_query(query: &str) -> Result<GraphQLRequest, GraphQLError> {
+    let params: GetParams = serde_urlencoded::from_str(query)
+        .map_err(|e| GraphQLError::Invalid(e.to_string()))?;
+    let json = |val: Option<String>| -> Result<Option<Value>, GraphQLError> {
+        match val {
+            Some(ref val) if !val.is_empty() => Ok(Some(serde_json::from_str(val)?)),
+            _ => Ok(None),
+        }
+    };
+
+    Ok(GraphQLRequest {
+        query: params.query,
+        operation_name: params.operation_name,
+        variables: json(params.variables)?,
+        extensions: json(params.extensions)?,
+        uploads: Vec::new(),
+        get: true,
+    })
+}
+
+/// Parse GraphQL multipart request, `operations` and `map` fields go
+/// before files.
+async fn from_multipart(
+    mut mp: Multipart,
+    limit: usize,
+) -> Result<GraphQLRequest, GraphQLError> {
+    let mut size = 0;
+    let mut operations = None;
+    let mut map: Option<BTreeMap<String, Vec<String>>> = None;
+    let mut files = HashMap::new();
+
+    while let Some(field) = mp.next().await {
+        let mut field = field?;
+        let (name, filename) = match field.content_disposition() {
+            Some(cd) => (
+                cd.get_name().unwrap_or("").to_owned(),
+                cd.get_filename().map(|name| name.to_owned()),
+            ),
+            None => return Err(GraphQLError::Invalid("Field name is required".to_owned())),
+        };
+        let content_type = field.content_type().to_string();
+
+        let mut data = BytesMut::new();
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk?;
+            size += chunk.len();
+            if size > limit {
+                return Err(GraphQLError::Overflow);
+            }
+            data.extend_from_slice(&chunk);
+        }
+
+        match name.as_str() {
+            "operations" => {
+                operations = Some(serde_json::from_slice::<GraphQLRequest>(&data)?)
+            }
+            "map" => map = Some(serde_json::from_slice(&data)?),
+            _ => {
+                files.insert(
+                    name,
+                    Upload {
+                        path: String::new(),
+                        filename,
+                        content_type,
+                        data: data.freeze(),
+                    },
+                );
+            }
+        }
+    }
+
+    let mut op = operations
+        .ok_or_else(|| GraphQLError::Invalid("Operations field is required".to_owned()))?;
+    let map = map.ok_or_else(|| GraphQLError::Invalid("Map field is required".to_owned()))?;
+
+    for (key, paths) in map {
+        let file = files
+            .remove(&key)
+            .ok_or_else(|| GraphQLError::Invalid(format!("File {} is missing", key)))?;
+        for path in paths {
+            if !has_variable(&op, &path) {
+                return Err(GraphQLError::Invalid(format!("Invalid file path {}", path)));
+            }
+            op.uploads.push(Upload {
+                path,
+                ..file.clone()
+            });
+        }
+    }
+    Ok(op)
+}
+
+/// Check if object path, i.e. `variables.files.0`, exists in operation
+fn has_variable(op: &GraphQLRequest, path: &str) -> bool {
+    let mut parts = path.split('.');
+    if parts.next() != Some("variables") {
+        return false;
+    }
+    let mut value = match op.variables {
+        Some(ref value) => value,
+        None => return false,
+    };
+    for part in parts {
+        let next = match value {
+            Value::Object(ref map) => map.get(part),
+            Value::Array(ref items) => part.parse::<usize>().ok().and_then(|idx| items.get(idx)),
+            _ => None,
+        };
+        value = match next {
+            Some(next) => next,
+            None => return false,
+        };
+    }
+    true
+}
+
+/// Result of GraphQL operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphQLResponse(pub Value);
+
+impl GraphQLResponse {
+    /// Successful result with data
+    pub fn data(data: Value) -> Self {
+        GraphQLResponse(json!({ "data": data }))
+    }
+
+    /// Failed result with error message
+    pub fn error<T: Into<String>>(message: T) -> Self {
+        GraphQLResponse(json!({ "errors": [{ "message": message.into() }] }))
+    }
+
+    /// Check if result does not contain errors
+    pub fn is_ok(&self) -> bool {
+        self.0.get("errors").is_none()
+    }
+}
+
+impl Responder for GraphQLResponse {
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        ok(Response::Ok()
+            .content_type("application/json")
+            .body(self.0.to_string()))
+    }
+}
+
+/// GraphQL service.
+///
+/// Service executes operations of `GET` and `POST` requests on the path.
+/// If subscriptions are enabled, websocket upgrade requests start
+/// `graphql-ws` protocol session, every subscription of the session runs
+/// in a separate task until executor stream is finished or client stops it.
+pub struct GraphQL<E> {
+    path: String,
+    executor: Rc<E>,
+    subscriptions: bool,
+    keep_alive: Option<Duration>,
+}
+
+impl<E: Executor> GraphQL<E> {
+    /// Create service for the path
+    pub fn new(path: &str, executor: E) -> Self {
+        GraphQL {
+            path: path.to_owned(),
+            executor: Rc::new(executor),
+            subscriptions: false,
+            keep_alive: Some(Duration::from_secs(15)),
+        }
+    }
+
+    /// Enable websocket subscriptions transport
+    pub fn subscriptions(mut self, enabled: bool) -> Self {
+        self.subscriptions = enabled;
+        self
+    }
+
+    /// Set keep-alive interval of subscriptions session, by default 15 seconds.
+    ///
+    /// `None` disables keep-alive messages.
+    pub fn keep_alive(mut self, interval: Option<Duration>) -> Self {
+        self.keep_alive = interval;
+        self
+    }
+}
+
+impl<E: Executor> HttpServiceFactory for GraphQL<E> {
+    fn register(self, config: &mut AppService) {
+        let inner = Rc::new(self);
+
+        config.register_service(
+            ResourceDef::new(inner.path.as_str()),
+            Some(vec![Box::new(guard::Any(guard::Get()).or(guard::Post()))]),
+            fn_service(move |req: ServiceRequest| handle(req, inner.clone())),
+            None,
+        );
+    }
+}
+
+async fn handle<E: Executor>(
+    req: ServiceRequest,
+    inner: Rc<GraphQL<E>>,
+) -> Result<ServiceResponse, Error> {
+    let (req, mut payload) = req.into_parts();
+
+    if inner.subscriptions && is_upgrade(&req) {
+        let session = Session {
+            executor: inner.executor.clone(),
+            keep_alive: inner.keep_alive,
+            ops: HashMap::new(),
+            ka: None,
+        };
+        let res = ws::WebSocket::new(session, payload)
+            .protocols(vec![PROTOCOL])
+            .respond_to(&req)
+            .await?;
+        return Ok(ServiceResponse::new(req, res));
+    }
+
+    match GraphQLRequest::from_request(&req, &mut payload).await {
+        Ok(op) => {
+            let res = inner.executor.execute(op).await.respond_to(&req).await?;
+            Ok(ServiceResponse::new(req, res))
+        }
+        Err(e) => Ok(ServiceResponse::from_err(e, req)),
+    }
+}
+
+fn is_upgrade(req: &HttpRequest) -> bool {
+    let has = |name, value: &str| {
+        req.headers()
+            .get(name)
+            .and_then(|val| val.to_str().ok())
+            .map(|val| val.to_lowercase().contains(value))
+            .unwrap_or(false)
+    };
+    has(UPGRADE, "websocket") && has(CONNECTION, "upgrade")
+}
+
+/// Messages of `graphql-ws` protocol sent by client
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    ConnectionInit {
+        #[serde(default)]
+        #[allow(dead_code)]
+        payload: Option<Value>,
+    },
+    Start {
+        id: String,
+        payload: GraphQLRequest,
+    },
+    Stop {
+        id: String,
+    },
+    ConnectionTerminate,
+}
+
+struct Session<E> {
+    executor: Rc<E>,
+    keep_alive: Option<Duration>,
+    ops: HashMap<String, AbortHandle>,
+    ka: Option<AbortHandle>,
+}
+
+impl<E: Executor> Session<E> {
+    fn start(&mut self, id: String, op: GraphQLRequest, ctx: &mut WsContext) {
+        let sender = ctx.sender();
+        let mut stream = self.executor.subscribe(op);
+        let op_id = id.clone();
+
+        let (fut, handle) = abortable(async move {
+            while let Some(res) = stream.next().await {
+                let msg = json!({"type": "data", "id": op_id, "payload": res.0});
+                if sender.send(Message::Text(msg.to_string())).is_err() {
+                    return;
+                }
+            }
+            let msg = json!({"type": "complete", "id": op_id});
+            let _ = sender.send(Message::Text(msg.to_string()));
+        });
+        crate::fiber::spawn(fut.map(|_| ()));
+
+        // operation id could be reused after completion
+        if let Some(prev) = self.ops.insert(id, handle) {
+            prev.abort();
+        }
+    }
+
+    fn keep_alive(&mut self, ctx: &mut WsContext) {
+        ctx.text(json!({"type": "ka"}).to_string());
+
+        if let Some(interval) = self.keep_alive {
+            let sender = ctx.sender();
+            let (fut, handle) = abortable(async move {
+                loop {
+                    delay_for(interval).await;
+                    let msg = json!({"type": "ka"}).to_string();
+                    if sender.send(Message::Text(msg)).is_err() {
+                        return;
+                    }
+                }
+            });
+            crate::fiber::spawn(fut.map(|_| ()));
+            if let Some(prev) = self.ka.replace(handle) {
+                prev.abort();
+            }
+        }
+    }
+}
+
+impl<E: Executor> WsSession for Session<E> {
+    fn handle(&mut self, msg: Message, ctx: &mut WsContext) {
+        let text = match msg {
+            Message::Text(text) => text,
+            _ => return,
+        };
+
+        match serde_json::from_str::<ClientMessage>(&text) {
+            Ok(ClientMessage::ConnectionInit { .. }) => {
+                ctx.text(json!({"type": "connection_ack"}).to_string());
+                self.keep_alive(ctx);
+            }
+            Ok(ClientMessage::Start { id, payload }) => self.start(id, payload, ctx),
+            Ok(ClientMessage::Stop { id }) => {
+                if let Some(handle) = self.ops.remove(&id) {
+                    handle.abort();
+                    ctx.text(json!({"type": "complete", "id": id}).to_string());
+                }
+            }
+            Ok(ClientMessage::ConnectionTerminate) => ctx.close(None),
+            Err(e) => {
+                let msg = json!({
+                    "type": "connection_error",
+                    "payload": {"message": e.to_string()},
+                });
+                ctx.text(msg.to_string());
+            }
+        }
+    }
+
+    fn stopped(&mut self) {
+        for (_, handle) in self.ops.drain() {
+            handle.abort();
+        }
+        if let Some(handle) = self.ka.take() {
+            handle.abort();
+        }
+    }
+}
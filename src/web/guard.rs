@@ -27,9 +27,13 @@
 //! ```
 #![allow(non_snake_case)]
 use std::convert::TryFrom;
+use std::future::Future;
+
+use futures_util::future::{FutureExt, LocalBoxFuture};
 
 use crate::http::{self, header, uri::Uri};
 use crate::http::RequestHead;
+use crate::web::request::HttpRequest;
 
 /// Trait defines resource guards. Guards are used for route selection.
 ///
@@ -84,6 +88,69 @@ where
     }
 }
 
+/// Trait defines async route guards.
+///
+/// Async guard could inspect request asynchronously before the route is
+/// selected, i.e. introspect access token with an authorization server.
+/// Async guards of a route are checked only if all sync guards of the
+/// route passed. Returned future must not borrow the request, clone
+/// required parts of the request instead. Result of the check could be
+/// stored in request extensions for the handler.
+pub trait AsyncGuard {
+    /// Check if request matches predicate
+    fn check(&self, request: &HttpRequest) -> LocalBoxFuture<'static, bool>;
+}
+
+/// Create async guard object for supplied function.
+///
+/// ```rust
+/// use kayrx::web::{guard, self, App, HttpResponse};
+///
+/// async fn introspect(token: String) -> bool {
+///     // ask authorization server
+///     token == "secret"
+/// }
+///
+/// fn main() {
+///     App::new().service(web::resource("/index.html").route(
+///         web::route()
+///             .async_guard(guard::fn_async_guard(|req| {
+///                 let token = req
+///                     .headers()
+///                     .get("authorization")
+///                     .and_then(|val| val.to_str().ok())
+///                     .map(|val| val.to_owned());
+///                 async move {
+///                     match token {
+///                         Some(token) => introspect(token).await,
+///                         None => false,
+///                     }
+///                 }
+///             }))
+///             .to(|| HttpResponse::Ok()))
+///     );
+/// }
+/// ```
+pub fn fn_async_guard<F, R>(f: F) -> impl AsyncGuard
+where
+    F: Fn(&HttpRequest) -> R,
+    R: Future<Output = bool> + 'static,
+{
+    FnAsyncGuard(f)
+}
+
+struct FnAsyncGuard<F>(F);
+
+impl<F, R> AsyncGuard for FnAsyncGuard<F>
+where
+    F: Fn(&HttpRequest) -> R,
+    R: Future<Output = bool> + 'static,
+{
+    fn check(&self, req: &HttpRequest) -> LocalBoxFuture<'static, bool> {
+        (self.0)(req).boxed_local()
+    }
+}
+
 /// Return guard that matches if any of supplied guards.
 ///
 /// ```rust
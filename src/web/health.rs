@@ -0,0 +1,298 @@
+//! Liveness and readiness endpoints
+//!
+//! [`Health`](struct.Health.html) module serves `/healthz` and `/readyz`
+//! endpoints. Readiness is computed from the status of registered
+//! [`Dependency`](struct.Dependency.html) probes, service is ready when
+//! all critical dependencies are up.
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use kayrx::web::health::{Dependency, Health};
+//! use kayrx::web::{self, App, HttpResponse};
+//!
+//! fn main() {
+//!     let db = Dependency::http("db", "http://127.0.0.1:5984/_up")
+//!         .interval(Duration::from_secs(5));
+//!     let search = Dependency::http("search", "http://127.0.0.1:9200/").critical(false);
+//!
+//!     let db2 = db.clone();
+//!     let app = App::new()
+//!         .plug(Health::new().dependency(db).dependency(search))
+//!         .route("/orders", web::get().to(move || {
+//!             // fail fast while database is down
+//!             let res = db2.check().map(|_| HttpResponse::Ok().finish());
+//!             async move { res }
+//!         }));
+//! }
+//! ```
+use std::cell::RefCell;
+use std::fmt;
+use std::future::Future;
+use std::rc::Rc;
+use std::time::Duration;
+
+use futures_util::future::{FutureExt, LocalBoxFuture};
+use serde_json::{json, Map, Value};
+
+use crate::http::{Response as HttpResponse, StatusCode};
+use crate::krse::sync::watch;
+use crate::timer::{self, MissedTickBehavior};
+use crate::web::client::Client;
+use crate::web::error::{Error, ErrorServiceUnavailable};
+use crate::web::module::{Module, ModuleConfig};
+use crate::web::web::get;
+
+/// Status of a dependency
+#[derive(Clone, Debug, PartialEq)]
+pub enum Status {
+    /// Dependency is not probed yet
+    Unknown,
+    /// Last probe succeeded
+    Up,
+    /// Last probe failed with the error
+    Down(String),
+}
+
+impl Status {
+    /// Check if status is `Up`
+    pub fn is_up(&self) -> bool {
+        *self == Status::Up
+    }
+}
+
+type Probe = Box<dyn Fn() -> LocalBoxFuture<'static, Result<(), String>>>;
+
+struct Inner {
+    name: String,
+    probe: Probe,
+    interval: Duration,
+    timeout: Duration,
+    critical: bool,
+    tx: watch::Sender<Status>,
+    rx: watch::Receiver<Status>,
+    status: RefCell<Status>,
+}
+
+/// Periodically probed dependency of the service.
+///
+/// Dependency is probed by `Health` module background job of every
+/// application instance. Dependency is a cheap to clone handle, handlers
+/// could check its status or watch status changes.
+///
+/// By default dependency is probed every 10 seconds with 5 seconds
+/// timeout and it is critical.
+#[derive(Clone)]
+pub struct Dependency(Rc<Inner>);
+
+impl Dependency {
+    /// Create dependency with custom probe
+    pub fn new<F, R>(name: &str, probe: F) -> Self
+    where
+        F: Fn() -> R + 'static,
+        R: Future<Output = Result<(), String>> + 'static,
+    {
+        let (tx, rx) = watch::channel(Status::Unknown);
+        Dependency(Rc::new(Inner {
+            name: name.to_owned(),
+            probe: Box::new(move || probe().boxed_local()),
+            interval: Duration::from_secs(10),
+            timeout: Duration::from_secs(5),
+            critical: true,
+            tx,
+            rx,
+            status: RefCell::new(Status::Unknown),
+        }))
+    }
+
+    /// Create dependency that is probed with `GET` request to the url,
+    /// dependency is up if response status is successful
+    pub fn http(name: &str, url: &str) -> Self {
+        let client = Client::default();
+        let url = url.to_owned();
+        Dependency::new(name, move || {
+            client.get(&url).send().map(|res| match res {
+                Ok(res) if res.status().is_success() => Ok(()),
+                Ok(res) => Err(format!("Response status is {}", res.status())),
+                Err(e) => Err(e.to_string()),
+            })
+        })
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.0).expect("Dependency is shared")
+    }
+
+    /// Set probe interval.
+    ///
+    /// # Panics
+    ///
+    /// Panics if dependency is already cloned.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.inner_mut().interval = interval;
+        self
+    }
+
+    /// Set probe timeout, probe that is not completed within the timeout
+    /// fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if dependency is already cloned.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.inner_mut().timeout = timeout;
+        self
+    }
+
+    /// Set whether service is ready only if dependency is up.
+    ///
+    /// Status of non critical dependency is reported by `/readyz`, but it
+    /// does not affect readiness.
+    ///
+    /// # Panics
+    ///
+    /// Panics if dependency is already cloned.
+    pub fn critical(mut self, critical: bool) -> Self {
+        self.inner_mut().critical = critical;
+        self
+    }
+
+    /// Dependency name
+    pub fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    /// Current status
+    pub fn status(&self) -> Status {
+        self.0.status.borrow().clone()
+    }
+
+    /// Check if dependency is up
+    pub fn is_up(&self) -> bool {
+        self.0.status.borrow().is_up()
+    }
+
+    /// Returns `503 Service Unavailable` error if dependency is not up
+    pub fn check(&self) -> Result<(), Error> {
+        if self.is_up() {
+            Ok(())
+        } else {
+            Err(ErrorServiceUnavailable(format!(
+                "Dependency {} is not available",
+                self.0.name
+            )))
+        }
+    }
+
+    /// Receiver of status changes
+    pub fn watch(&self) -> watch::Receiver<Status> {
+        self.0.rx.clone()
+    }
+
+    /// Probe dependency and update status
+    async fn probe(&self) {
+        let status = match timer::timeout(self.0.timeout, (self.0.probe)()).await {
+            Ok(Ok(_)) => Status::Up,
+            Ok(Err(e)) => Status::Down(e),
+            Err(_) => Status::Down("Probe timed out".to_owned()),
+        };
+
+        if *self.0.status.borrow() != status {
+            match status {
+                Status::Down(ref e) => log::warn!("Dependency {} is down: {}", self.0.name, e),
+                _ => log::info!("Dependency {} is up", self.0.name),
+            }
+            *self.0.status.borrow_mut() = status.clone();
+            let _ = self.0.tx.broadcast(status);
+        }
+    }
+
+    async fn run(self) {
+        let mut ticks = timer::interval(self.0.interval);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        loop {
+            ticks.tick().await;
+            self.probe().await;
+        }
+    }
+}
+
+impl fmt::Debug for Dependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dependency")
+            .field("name", &self.0.name)
+            .field("critical", &self.0.critical)
+            .field("status", &*self.0.status.borrow())
+            .finish()
+    }
+}
+
+/// Health endpoints module.
+///
+/// * `GET /healthz` always responds with `200 OK`.
+/// * `GET /readyz` responds with `200 OK` if all critical dependencies
+///   are up and with `503 Service Unavailable` otherwise. Response body
+///   is json with dependency statuses.
+///
+/// Module registers its services at the application root, dependencies
+/// are probed while application is running.
+#[derive(Clone, Debug, Default)]
+pub struct Health {
+    dependencies: Vec<Dependency>,
+}
+
+impl Health {
+    /// Create health module without dependencies
+    pub fn new() -> Self {
+        Health::default()
+    }
+
+    /// Register dependency
+    pub fn dependency(mut self, dependency: Dependency) -> Self {
+        self.dependencies.push(dependency);
+        self
+    }
+}
+
+impl Module for Health {
+    fn name(&self) -> &str {
+        "health"
+    }
+
+    fn configure(&self, cfg: &mut ModuleConfig) {
+        for dep in &self.dependencies {
+            let dep = dep.clone();
+            cfg.background(move || dep.clone().run());
+        }
+
+        let deps = self.dependencies.clone();
+        cfg.route("/healthz", get().to(|| HttpResponse::Ok()));
+        cfg.route("/readyz", get().to(move || readiness(&deps)));
+    }
+}
+
+fn readiness(deps: &[Dependency]) -> HttpResponse {
+    let mut is_ready = true;
+    let mut statuses = Map::new();
+    for dep in deps {
+        let status = dep.status();
+        if dep.0.critical && !status.is_up() {
+            is_ready = false;
+        }
+        let value = match status {
+            Status::Unknown => json!({"status": "unknown"}),
+            Status::Up => json!({"status": "up"}),
+            Status::Down(e) => json!({"status": "down", "error": e}),
+        };
+        statuses.insert(dep.0.name.clone(), value);
+    }
+
+    let status = if is_ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    HttpResponse::build(status).json(json!({
+        "status": if is_ready { "ready" } else { "not ready" },
+        "dependencies": Value::Object(statuses),
+    }))
+}
@@ -2,11 +2,141 @@ use std::cell::Ref;
 
 use crate::web::dev::{AppConfig, RequestHead};
 use crate::http::header::{self, HeaderName};
+use crate::secure::tls::rust_tls::{Certificate, ServerSession, Session};
 
 const X_FORWARDED_FOR: &[u8] = b"x-forwarded-for";
 const X_FORWARDED_HOST: &[u8] = b"x-forwarded-host";
 const X_FORWARDED_PROTO: &[u8] = b"x-forwarded-proto";
 
+/// Source of the request scheme and host.
+///
+/// See [`ConnectionInfoConfig`](struct.ConnectionInfoConfig.html).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InfoSource {
+    /// `Forwarded` header
+    Forwarded,
+    /// `X-Forwarded-Proto` and `X-Forwarded-Host` headers
+    XForwarded,
+    /// `Host` header and request uri
+    Host,
+    /// Server name requested by the client with TLS SNI extension, scheme
+    /// is `https` for TLS connections
+    Sni,
+    /// Server configuration: server hostname and listener type.
+    /// Always resolves.
+    Config,
+}
+
+/// Configuration of `ConnectionInfo` scheme and host resolution.
+///
+/// Sources are checked in order, the first one that provides a value wins.
+/// Forwarding headers can be set by any client, so behind a reverse proxy
+/// only headers set by the proxy should be trusted, and servers that are
+/// exposed directly should not use them at all. Resolved values are used
+/// for url generation (`HttpRequest::url_for()`), redirects and
+/// canonicalization.
+///
+/// By default sources are `Forwarded`, `XForwarded`, `Host` and `Config`.
+///
+/// ```rust,no_run
+/// use kayrx::web::{self, App, HttpResponse, HttpServer};
+/// use kayrx::web::dev::{ConnectionInfoConfig, InfoSource};
+///
+/// #[kayrx::main]
+/// async fn main() -> std::io::Result<()> {
+///     HttpServer::new(|| App::new().service(web::resource("/").to(|| HttpResponse::Ok())))
+///         // trust only `Forwarded` header set by the proxy
+///         .connection_info(ConnectionInfoConfig::new(vec![
+///             InfoSource::Forwarded,
+///             InfoSource::Config,
+///         ]))
+///         .server_hostname("www.rust-lang.org")
+///         .bind("127.0.0.1:59090")?
+///         .run()
+///         .await
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionInfoConfig {
+    sources: Vec<InfoSource>,
+}
+
+impl Default for ConnectionInfoConfig {
+    fn default() -> Self {
+        ConnectionInfoConfig::new(vec![
+            InfoSource::Forwarded,
+            InfoSource::XForwarded,
+            InfoSource::Host,
+            InfoSource::Config,
+        ])
+    }
+}
+
+impl ConnectionInfoConfig {
+    /// Create configuration with ordered list of sources
+    pub fn new<I: IntoIterator<Item = InfoSource>>(sources: I) -> Self {
+        ConnectionInfoConfig {
+            sources: sources.into_iter().collect(),
+        }
+    }
+
+    /// Configured sources
+    pub fn sources(&self) -> &[InfoSource] {
+        &self.sources
+    }
+}
+
+/// TLS parameters of the connection.
+///
+/// Info is stored in request extensions of the requests that are received
+/// over TLS listeners of `HttpServer`.
+///
+/// ```rust
+/// use kayrx::web::{dev::TlsInfo, HttpRequest, HttpResponse};
+///
+/// async fn index(req: HttpRequest) -> HttpResponse {
+///     match req.extensions().get::<TlsInfo>() {
+///         Some(tls) if tls.peer_certificates().is_some() => HttpResponse::Ok().finish(),
+///         _ => HttpResponse::Forbidden().finish(),
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TlsInfo {
+    server_name: Option<String>,
+    alpn_protocol: Option<Vec<u8>>,
+    peer_certificates: Option<Vec<Certificate>>,
+}
+
+impl TlsInfo {
+    pub(crate) fn new(session: &ServerSession) -> Self {
+        TlsInfo {
+            server_name: session.get_sni_hostname().map(|name| name.to_owned()),
+            alpn_protocol: session.get_alpn_protocol().map(|proto| proto.to_vec()),
+            peer_certificates: session.get_peer_certificates(),
+        }
+    }
+
+    /// Server name requested by the client with SNI extension
+    pub fn server_name(&self) -> Option<&str> {
+        self.server_name.as_ref().map(|name| name.as_str())
+    }
+
+    /// Protocol negotiated with ALPN extension
+    pub fn alpn_protocol(&self) -> Option<&[u8]> {
+        self.alpn_protocol.as_ref().map(|proto| proto.as_slice())
+    }
+
+    /// Certificate chain presented by the client, end-entity certificate
+    /// is the first.
+    ///
+    /// Client certificates are requested if server config is created
+    /// with client certificate verifier, i.e. `AllowAnyAuthenticatedClient`.
+    pub fn peer_certificates(&self) -> Option<&[Certificate]> {
+        self.peer_certificates.as_ref().map(|certs| certs.as_slice())
+    }
+}
+
 /// `HttpRequest` connection information
 #[derive(Debug, Clone, Default)]
 pub struct ConnectionInfo {
@@ -20,12 +150,12 @@ impl ConnectionInfo {
     /// Create *ConnectionInfo* instance for a request.
     pub fn get<'a>(req: &'a RequestHead, cfg: &AppConfig) -> Ref<'a, Self> {
         if !req.extensions().contains::<ConnectionInfo>() {
-            req.extensions_mut().insert(ConnectionInfo::new(req, cfg));
+            let info = ConnectionInfo::new(req, cfg);
+            req.extensions_mut().insert(info);
         }
         Ref::map(req.extensions(), |e| e.get().unwrap())
     }
 
-    #[allow(clippy::cognitive_complexity)]
     fn new(req: &RequestHead, cfg: &AppConfig) -> ConnectionInfo {
         let mut host = None;
         let mut scheme = None;
@@ -33,6 +163,9 @@ impl ConnectionInfo {
         let mut peer = None;
 
         // load forwarded header
+        let mut fwd_for = None;
+        let mut fwd_proto = None;
+        let mut fwd_host = None;
         for hdr in req.headers.get_all(&header::FORWARDED) {
             if let Ok(val) = hdr.to_str() {
                 for pair in val.split(';') {
@@ -40,22 +173,11 @@ impl ConnectionInfo {
                         let mut items = el.trim().splitn(2, '=');
                         if let Some(name) = items.next() {
                             if let Some(val) = items.next() {
+                                let val = Some(val.trim());
                                 match &name.to_lowercase() as &str {
-                                    "for" => {
-                                        if remote.is_none() {
-                                            remote = Some(val.trim());
-                                        }
-                                    }
-                                    "proto" => {
-                                        if scheme.is_none() {
-                                            scheme = Some(val.trim());
-                                        }
-                                    }
-                                    "host" => {
-                                        if host.is_none() {
-                                            host = Some(val.trim());
-                                        }
-                                    }
+                                    "for" => fwd_for = fwd_for.or(val),
+                                    "proto" => fwd_proto = fwd_proto.or(val),
+                                    "host" => fwd_host = fwd_host.or(val),
                                     _ => (),
                                 }
                             }
@@ -65,57 +187,52 @@ impl ConnectionInfo {
             }
         }
 
-        // scheme
-        if scheme.is_none() {
-            if let Some(h) = req
-                .headers
-                .get(&HeaderName::from_lowercase(X_FORWARDED_PROTO).unwrap())
-            {
-                if let Ok(h) = h.to_str() {
-                    scheme = h.split(',').next().map(|v| v.trim());
-                }
-            }
-            if scheme.is_none() {
-                scheme = req.uri.scheme().map(|a| a.as_str());
-                if scheme.is_none() && cfg.secure() {
-                    scheme = Some("https")
-                }
-            }
-        }
+        let sni = req
+            .extensions()
+            .get::<TlsInfo>()
+            .map(|tls| tls.server_name.clone());
 
-        // host
-        if host.is_none() {
-            if let Some(h) = req
-                .headers
-                .get(&HeaderName::from_lowercase(X_FORWARDED_HOST).unwrap())
-            {
-                if let Ok(h) = h.to_str() {
-                    host = h.split(',').next().map(|v| v.trim());
+        for source in cfg.connection_info().sources() {
+            match source {
+                InfoSource::Forwarded => {
+                    scheme = scheme.or(fwd_proto);
+                    host = host.or(fwd_host);
                 }
-            }
-            if host.is_none() {
-                if let Some(h) = req.headers.get(&header::HOST) {
-                    host = h.to_str().ok();
+                InfoSource::XForwarded => {
+                    if scheme.is_none() {
+                        scheme = first_value(req, X_FORWARDED_PROTO);
+                    }
+                    if host.is_none() {
+                        host = first_value(req, X_FORWARDED_HOST);
+                    }
                 }
-                if host.is_none() {
-                    host = req.uri.authority().map(|a| a.as_str());
+                InfoSource::Host => {
+                    scheme = scheme.or_else(|| req.uri.scheme().map(|a| a.as_str()));
                     if host.is_none() {
-                        host = Some(cfg.host());
+                        host = req
+                            .headers
+                            .get(&header::HOST)
+                            .and_then(|h| h.to_str().ok());
+                    }
+                    host = host.or_else(|| req.uri.authority().map(|a| a.as_str()));
+                }
+                InfoSource::Sni => {
+                    if let Some(ref sni) = sni {
+                        scheme = scheme.or(Some("https"));
+                        host = host.or_else(|| sni.as_ref().map(|s| s.as_str()));
                     }
                 }
+                InfoSource::Config => {
+                    let secure = if cfg.secure() { "https" } else { "http" };
+                    scheme = scheme.or(Some(secure));
+                    host = host.or_else(|| Some(cfg.host()));
+                }
             }
         }
 
         // remote addr
         if remote.is_none() {
-            if let Some(h) = req
-                .headers
-                .get(&HeaderName::from_lowercase(X_FORWARDED_FOR).unwrap())
-            {
-                if let Ok(h) = h.to_str() {
-                    remote = h.split(',').next().map(|v| v.trim());
-                }
-            }
+            remote = fwd_for.or_else(|| first_value(req, X_FORWARDED_FOR));
             if remote.is_none() {
                 // get peeraddr from socketaddr
                 peer = req.peer_addr.map(|addr| format!("{}", addr));
@@ -132,11 +249,14 @@ impl ConnectionInfo {
 
     /// Scheme of the request.
     ///
-    /// Scheme is resolved through the following headers, in this order:
+    /// By default scheme is resolved through the following sources, in this order:
     ///
     /// - Forwarded
     /// - X-Forwarded-Proto
     /// - Uri
+    /// - Listener type
+    ///
+    /// Sources could be changed with `ConnectionInfoConfig`.
     #[inline]
     pub fn scheme(&self) -> &str {
         &self.scheme
@@ -144,13 +264,15 @@ impl ConnectionInfo {
 
     /// Hostname of the request.
     ///
-    /// Hostname is resolved through the following headers, in this order:
+    /// By default hostname is resolved through the following sources, in this order:
     ///
     /// - Forwarded
     /// - X-Forwarded-Host
     /// - Host
     /// - Uri
     /// - Server hostname
+    ///
+    /// Sources could be changed with `ConnectionInfoConfig`.
     pub fn host(&self) -> &str {
         &self.host
     }
@@ -180,6 +302,14 @@ impl ConnectionInfo {
     }
 }
 
+/// First value of the comma separated header
+fn first_value<'a>(req: &'a RequestHead, name: &[u8]) -> Option<&'a str> {
+    req.headers
+        .get(&HeaderName::from_lowercase(name).unwrap())
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.split(',').next().map(|v| v.trim()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -232,4 +362,42 @@ mod tests {
         let info = req.connection_info();
         assert_eq!(info.scheme(), "https");
     }
+
+    #[test]
+    fn test_sources() {
+        let cfg = AppConfig::default().with_connection_info(ConnectionInfoConfig::new(
+            vec![InfoSource::Forwarded, InfoSource::Config],
+        ));
+
+        let req = TestRequest::default()
+            .header(header::HOST, "rust-lang.org")
+            .header(X_FORWARDED_PROTO, "https")
+            .to_http_request();
+        let info = ConnectionInfo::new(req.head(), &cfg);
+        assert_eq!(info.scheme(), "http");
+        assert_eq!(info.host(), "localhost:8080");
+
+        let req = TestRequest::default()
+            .header(header::FORWARDED, "proto=https; host=rust-lang.org")
+            .to_http_request();
+        let info = ConnectionInfo::new(req.head(), &cfg);
+        assert_eq!(info.scheme(), "https");
+        assert_eq!(info.host(), "rust-lang.org");
+
+        let cfg = AppConfig::default().with_connection_info(ConnectionInfoConfig::new(
+            vec![InfoSource::Sni, InfoSource::Host],
+        ));
+        let req = TestRequest::default()
+            .header(header::HOST, "rust-lang.org")
+            .to_http_request();
+        req.head()
+            .extensions_mut()
+            .insert(TlsInfo {
+                server_name: Some("www.rust-lang.org".to_owned()),
+                ..TlsInfo::default()
+            });
+        let info = ConnectionInfo::new(req.head(), &cfg);
+        assert_eq!(info.scheme(), "https");
+        assert_eq!(info.host(), "www.rust-lang.org");
+    }
 }
\ No newline at end of file
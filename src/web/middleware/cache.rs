@@ -0,0 +1,412 @@
+//! `Middleware` for caching responses.
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime};
+
+use bytes::{Bytes, BytesMut};
+use futures_util::future::{ok, poll_fn, FutureExt, LocalBoxFuture, Ready};
+use futures_util::stream::{self, StreamExt};
+
+use crate::http::body::{Body, BodyStream, MessageBody, ResponseBody};
+use crate::http::error::Error;
+use crate::http::header::{self, HeaderValue};
+use crate::http::{Method, Response, StatusCode};
+use crate::service::{Service, Transform};
+use crate::web::client::cache::{
+    freshness_lifetime, is_cacheable, vary, CacheStore, CachedResponse, Directives,
+    MemoryCacheStore,
+};
+use crate::web::service::{ServiceRequest, ServiceResponse};
+
+/// `Middleware` for caching responses of `GET` requests.
+///
+/// Middleware acts as a shared cache in front of the application
+/// (RFC 9111): responses with freshness information are stored and served
+/// until they become stale, `Vary` selects stored response by request
+/// headers. Responses with `no-store` or `private` directives are not
+/// stored, successful unsafe requests invalidate stored response.
+///
+/// `stale-while-revalidate` and `stale-if-error` extensions (RFC 5861)
+/// are supported. Within the stale-while-revalidate window stale response
+/// is served immediately and request is processed in background to refresh
+/// the stored response. Within the stale-if-error window stale response is
+/// served if application responds with *500*, *502*, *503* or *504*
+/// status, so middleware can be used as a caching gateway in front
+/// of flaky upstreams.
+///
+/// Stores from `web::client::cache` are used for storage.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use kayrx::web::{self, middleware, App, HttpResponse};
+/// use kayrx::web::client::cache::MemoryCacheStore;
+///
+/// let app = App::new()
+///     .wrap(
+///         middleware::ResponseCache::with_store(MemoryCacheStore::new(1024))
+///             .stale_if_error(Duration::from_secs(300)),
+///     )
+///     .service(web::resource("/").to(|| HttpResponse::Ok()));
+/// ```
+pub struct ResponseCache<T = MemoryCacheStore> {
+    inner: Rc<Inner<T>>,
+}
+
+struct Inner<T> {
+    store: T,
+    max_body_size: usize,
+    stale_while_revalidate: Option<Duration>,
+    stale_if_error: Option<Duration>,
+    refreshing: RefCell<HashSet<String>>,
+}
+
+impl Default for ResponseCache<MemoryCacheStore> {
+    fn default() -> Self {
+        ResponseCache::with_store(MemoryCacheStore::new(1024))
+    }
+}
+
+impl ResponseCache<MemoryCacheStore> {
+    /// Construct `ResponseCache` middleware with in-memory store for 1024 responses.
+    pub fn new() -> Self {
+        ResponseCache::default()
+    }
+}
+
+impl<T: CacheStore> ResponseCache<T> {
+    /// Construct `ResponseCache` middleware with custom store.
+    pub fn with_store(store: T) -> Self {
+        ResponseCache {
+            inner: Rc::new(Inner {
+                store,
+                max_body_size: 1_048_576,
+                stale_while_revalidate: None,
+                stale_if_error: None,
+                refreshing: RefCell::new(HashSet::new()),
+            }),
+        }
+    }
+
+    /// Set max size of a cached response body. Larger responses are not
+    /// stored. By default max size is 1Mb.
+    pub fn max_body_size(mut self, size: usize) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .max_body_size = size;
+        self
+    }
+
+    /// Set default stale-while-revalidate window, used for responses
+    /// without `stale-while-revalidate` directive. By default window is not set.
+    pub fn stale_while_revalidate(mut self, window: Duration) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .stale_while_revalidate = Some(window);
+        self
+    }
+
+    /// Set default stale-if-error window, used for responses without
+    /// `stale-if-error` directive. By default window is not set.
+    pub fn stale_if_error(mut self, window: Duration) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .stale_if_error = Some(window);
+        self
+    }
+}
+
+impl<S, T, B> Transform<S> for ResponseCache<T>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    S::Future: 'static,
+    T: CacheStore + 'static,
+    B: MessageBody + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<Body>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ResponseCacheMiddleware<S, T>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(ResponseCacheMiddleware {
+            service: Rc::new(RefCell::new(service)),
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+#[doc(hidden)]
+pub struct ResponseCacheMiddleware<S, T> {
+    service: Rc<RefCell<S>>,
+    inner: Rc<Inner<T>>,
+}
+
+impl<S, T, B> Service for ResponseCacheMiddleware<S, T>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    S::Future: 'static,
+    T: CacheStore + 'static,
+    B: MessageBody + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<Body>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let inner = self.inner.clone();
+        let key = cache_key(&req);
+
+        match *req.method() {
+            Method::GET => (),
+            Method::HEAD | Method::OPTIONS | Method::TRACE => {
+                return self
+                    .service
+                    .call(req)
+                    .map(|res| res.map(into_boxed_body))
+                    .boxed_local();
+            }
+            _ => {
+                // unsafe methods invalidate stored response
+                let fut = self.service.call(req);
+                return async move {
+                    let res = fut.await?;
+                    let status = res.status();
+                    if !status.is_client_error() && !status.is_server_error() {
+                        inner.store.remove(&key).await;
+                    }
+                    Ok(into_boxed_body(res))
+                }
+                .boxed_local();
+            }
+        }
+
+        let req_cc = Directives::new(req.headers());
+        if req_cc.has("no-store") {
+            return self
+                .service
+                .call(req)
+                .map(|res| res.map(into_boxed_body))
+                .boxed_local();
+        }
+
+        let srv = self.service.clone();
+        async move {
+            let cached = inner
+                .store
+                .get(&key)
+                .await
+                .filter(|cached| cached.matches(req.headers()));
+
+            if let Some(ref cached) = cached {
+                let now = SystemTime::now();
+                if !req_cc.has("no-cache") {
+                    if cached.is_fresh(now) {
+                        return Ok(req.into_response(to_response(cached, now)));
+                    }
+
+                    let swr = inner.stale_while_revalidate;
+                    if is_usable_stale(cached, now, "stale-while-revalidate", swr) {
+                        // serve stale response, refresh it in background
+                        if inner.refreshing.borrow_mut().insert(key.clone()) {
+                            refresh(&srv, &inner, key, &req);
+                        }
+                        return Ok(req.into_response(to_response(cached, now)));
+                    }
+                }
+            }
+
+            let res = srv.borrow_mut().call(req).await?;
+
+            // serve stale response if application failed
+            match res.status() {
+                StatusCode::INTERNAL_SERVER_ERROR
+                | StatusCode::BAD_GATEWAY
+                | StatusCode::SERVICE_UNAVAILABLE
+                | StatusCode::GATEWAY_TIMEOUT => {
+                    let now = SystemTime::now();
+                    if let Some(ref cached) = cached {
+                        let sie = inner.stale_if_error;
+                        if is_usable_stale(cached, now, "stale-if-error", sie) {
+                            log::debug!("Serving stale response for {}", key);
+                            return Ok(res.into_response(to_response(cached, now)));
+                        }
+                    }
+                }
+                _ => (),
+            }
+
+            store(&inner, &key, res).await
+        }
+        .boxed_local()
+    }
+}
+
+/// Key of the stored response, virtual hosts share the store
+fn cache_key(req: &ServiceRequest) -> String {
+    let info = req.connection_info();
+    let path = req
+        .uri()
+        .path_and_query()
+        .map(|pq| pq.as_str())
+        .unwrap_or("/");
+    format!("{}://{}{}", info.scheme(), info.host(), path)
+}
+
+/// Process copy of the request in background and store the response.
+///
+/// Copy keeps match information and data containers collected so far,
+/// so it could be handled by the wrapped service.
+fn refresh<S, T, B>(
+    srv: &Rc<RefCell<S>>,
+    inner: &Rc<Inner<T>>,
+    key: String,
+    req: &ServiceRequest,
+) where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    S::Future: 'static,
+    T: CacheStore + 'static,
+    B: MessageBody + 'static,
+{
+    let copy = req
+        .uri()
+        .path_and_query()
+        .and_then(|pq| req.request().child(pq.as_str(), Method::GET).ok())
+        .and_then(|mut copy| {
+            let copy_inner = Rc::get_mut(&mut copy.0)?;
+            copy_inner.path = req.match_info().clone();
+            copy_inner.app_data = req.request().0.app_data.clone();
+            Some(ServiceRequest::new(copy))
+        });
+    let copy = match copy {
+        Some(copy) => copy,
+        None => {
+            inner.refreshing.borrow_mut().remove(&key);
+            return;
+        }
+    };
+
+    let fut = srv.borrow_mut().call(copy);
+    let inner = inner.clone();
+    crate::fiber::spawn(async move {
+        match fut.await {
+            Ok(res) => {
+                if let Err(e) = store(&inner, &key, res).await {
+                    log::trace!("Can not refresh {}: {}", key, e);
+                }
+            }
+            Err(e) => log::trace!("Can not refresh {}: {}", key, e),
+        }
+        inner.refreshing.borrow_mut().remove(&key);
+    });
+}
+
+/// Check if stale response may be served.
+///
+/// Window from the response's `directive` takes precedence over
+/// the configured default.
+fn is_usable_stale(
+    cached: &CachedResponse,
+    now: SystemTime,
+    directive: &str,
+    default: Option<Duration>,
+) -> bool {
+    let cc = Directives::new(&cached.headers);
+    if cc.has("no-cache") || cc.has("must-revalidate") || cc.has("proxy-revalidate") {
+        return false;
+    }
+    let lifetime = freshness_lifetime(&cached.headers, &cc).unwrap_or_default();
+    let staleness = cached.age(now).checked_sub(lifetime).unwrap_or_default();
+    match cc.seconds(directive).or(default) {
+        Some(window) => staleness <= window,
+        None => false,
+    }
+}
+
+fn to_response(cached: &CachedResponse, now: SystemTime) -> Response {
+    let mut res = Response::new(cached.status);
+    for (name, value) in cached.headers.iter() {
+        res.headers_mut().append(name.clone(), value.clone());
+    }
+    res.headers_mut()
+        .insert(header::AGE, HeaderValue::from(cached.age(now).as_secs()));
+    res.set_body(Body::Bytes(cached.body.clone()))
+}
+
+/// Store cacheable response, or drop stored response for the key
+async fn store<T: CacheStore, B: MessageBody + 'static>(
+    inner: &Inner<T>,
+    key: &str,
+    res: ServiceResponse<B>,
+) -> Result<ServiceResponse<Body>, Error> {
+    let cc = Directives::new(res.headers());
+    if !is_cacheable(res.response().head()) || cc.has("private") {
+        inner.store.remove(key).await;
+        return Ok(into_boxed_body(res));
+    }
+
+    let headers = res.headers().clone();
+    let (res, body) = buffer_response(res, inner.max_body_size).await?;
+    match body {
+        Some(body) => {
+            let cached = CachedResponse {
+                status: res.status(),
+                version: res.request().version(),
+                vary: vary(&headers, res.request().headers()),
+                headers,
+                body,
+                stored_at: SystemTime::now(),
+            };
+            inner.store.put(key, cached).await;
+        }
+        None => inner.store.remove(key).await,
+    }
+    Ok(res)
+}
+
+fn into_boxed_body<B: MessageBody + 'static>(
+    res: ServiceResponse<B>,
+) -> ServiceResponse<Body> {
+    res.map_body(|_, body| match body {
+        ResponseBody::Body(b) => ResponseBody::Other(Body::from_message(b)),
+        ResponseBody::Other(b) => ResponseBody::Other(b),
+    })
+}
+
+/// Read response body into memory. If body is bigger than `limit`,
+/// response is returned without body copy.
+async fn buffer_response<B: MessageBody + 'static>(
+    mut res: ServiceResponse<B>,
+    limit: usize,
+) -> Result<(ServiceResponse<Body>, Option<Bytes>), Error> {
+    let mut body = res.take_body();
+    let mut buf = BytesMut::new();
+
+    while let Some(item) = poll_fn(|cx| body.poll_next(cx)).await {
+        buf.extend_from_slice(&item?);
+        if buf.len() > limit {
+            // send already consumed part followed by the rest of the stream
+            let head = buf.freeze();
+            let stream = stream::once(async move { Ok::<_, Error>(head) }).chain(body);
+            let res = res.map_body(|_, _| {
+                ResponseBody::Other(Body::from_message(BodyStream::new(stream)))
+            });
+            return Ok((res, None));
+        }
+    }
+
+    let body = buf.freeze();
+    let res = res.map_body(|_, _| ResponseBody::Other(Body::Bytes(body.clone())));
+    Ok((res, Some(body)))
+}
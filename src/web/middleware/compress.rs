@@ -36,12 +36,29 @@ use crate::web::service::{ServiceRequest, ServiceResponse};
 ///         );
 /// }
 /// ```
-pub struct Compress(ContentEncoding);
+pub struct Compress {
+    encoding: ContentEncoding,
+    level: Option<u32>,
+}
 
 impl Compress {
     /// Create new `Compress` middleware with default encoding.
     pub fn new(encoding: ContentEncoding) -> Self {
-        Compress(encoding)
+        Compress {
+            encoding,
+            level: None,
+        }
+    }
+
+    /// Set compression level.
+    ///
+    /// Level is in `0..=9` range for gzip and deflate and in `0..=11`
+    /// range for brotli, larger values are clamped. Higher level produces
+    /// smaller responses at the cost of cpu time. By default fast compression
+    /// is used.
+    pub fn level(mut self, level: u32) -> Self {
+        self.level = Some(level);
+        self
     }
 }
 
@@ -66,7 +83,8 @@ where
     fn new_transform(&self, service: S) -> Self::Future {
         ok(CompressMiddleware {
             service,
-            encoding: self.0,
+            encoding: self.encoding,
+            level: self.level,
         })
     }
 }
@@ -74,6 +92,7 @@ where
 pub struct CompressMiddleware<S> {
     service: S,
     encoding: ContentEncoding,
+    level: Option<u32>,
 }
 
 impl<S, B> Service for CompressMiddleware<S>
@@ -104,6 +123,7 @@ where
 
         CompressResponse {
             encoding,
+            level: self.level,
             fut: self.service.call(req),
             _t: PhantomData,
         }
@@ -120,6 +140,7 @@ where
     #[pin]
     fut: S::Future,
     encoding: ContentEncoding,
+    level: Option<u32>,
     _t: PhantomData<B>,
 }
 
@@ -141,9 +162,10 @@ where
                     *this.encoding
                 };
 
-                Poll::Ready(Ok(
-                    resp.map_body(move |head, body| Encoder::response(enc, head, body))
-                ))
+                let level = *this.level;
+                Poll::Ready(Ok(resp.map_body(move |head, body| {
+                    Encoder::response_with_level(enc, level, head, body)
+                })))
             }
             Err(e) => Poll::Ready(Err(e)),
         }
@@ -191,11 +213,15 @@ impl AcceptEncoding {
         };
         let quality = match parts.len() {
             1 => encoding.quality(),
-            _ => match f64::from_str(parts[1]) {
+            _ => match f64::from_str(parts[1].trim_start_matches("q=")) {
                 Ok(q) => q,
                 Err(_) => 0.0,
             },
         };
+        // `q=0` means encoding is not acceptable
+        if quality <= 0.0 {
+            return None;
+        }
         Some(AcceptEncoding { encoding, quality })
     }
 
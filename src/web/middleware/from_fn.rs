@@ -0,0 +1,117 @@
+//! Middleware from async function
+use std::cell::RefCell;
+use std::future::Future;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use futures_util::future::{ok, FutureExt, LocalBoxFuture, Ready};
+
+use crate::http::error::Error;
+use crate::service::boxed::{self, BoxService};
+use crate::service::{Service, Transform};
+use crate::web::service::{ServiceRequest, ServiceResponse};
+
+/// Create middleware from async function.
+///
+/// Function receives request and `Next` handle, calling `next.call(req)`
+/// passes request to the rest of the service chain. Function could also
+/// return response without calling next service.
+///
+/// ```rust
+/// use kayrx::http::error::Error;
+/// use kayrx::http::header;
+/// use kayrx::web::dev::{Body, ServiceRequest, ServiceResponse};
+/// use kayrx::web::middleware::{from_fn, Next};
+/// use kayrx::web::{self, App, HttpResponse};
+///
+/// async fn version(
+///     req: ServiceRequest,
+///     next: Next<Body>,
+/// ) -> Result<ServiceResponse<Body>, Error> {
+///     let mut res = next.call(req).await?;
+///     res.headers_mut().insert(
+///         header::HeaderName::from_static("x-version"),
+///         header::HeaderValue::from_static("0.2"),
+///     );
+///     Ok(res)
+/// }
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(from_fn(version))
+///         .service(web::resource("/").to(|| HttpResponse::Ok()));
+/// }
+/// ```
+pub fn from_fn<F>(f: F) -> FromFn<F> {
+    FromFn { f }
+}
+
+/// Middleware transform, created by `from_fn()` function
+#[derive(Clone)]
+pub struct FromFn<F> {
+    f: F,
+}
+
+impl<S, F, Fut, B> Transform<S> for FromFn<F>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    S::Future: 'static,
+    F: Fn(ServiceRequest, Next<B>) -> Fut + Clone + 'static,
+    Fut: Future<Output = Result<ServiceResponse<B>, Error>> + 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = FromFnMiddleware<F, B>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(FromFnMiddleware {
+            f: self.f.clone(),
+            service: Rc::new(RefCell::new(boxed::service(service))),
+        })
+    }
+}
+
+/// Rest of the service chain
+pub struct Next<B> {
+    service: Rc<RefCell<BoxService<ServiceRequest, ServiceResponse<B>, Error>>>,
+}
+
+impl<B> Next<B> {
+    /// Call next service
+    pub async fn call(self, req: ServiceRequest) -> Result<ServiceResponse<B>, Error> {
+        let fut = self.service.borrow_mut().call(req);
+        fut.await
+    }
+}
+
+pub struct FromFnMiddleware<F, B> {
+    f: F,
+    service: Rc<RefCell<BoxService<ServiceRequest, ServiceResponse<B>, Error>>>,
+}
+
+impl<F, Fut, B> Service for FromFnMiddleware<F, B>
+where
+    F: Fn(ServiceRequest, Next<B>) -> Fut,
+    Fut: Future<Output = Result<ServiceResponse<B>, Error>> + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.borrow_mut().poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let next = Next {
+            service: self.service.clone(),
+        };
+        (self.f)(req, next).boxed_local()
+    }
+}
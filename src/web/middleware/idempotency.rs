@@ -0,0 +1,492 @@
+//! `Middleware` for idempotent processing of unsafe requests.
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use bytes::{Bytes, BytesMut};
+use futures_util::future::{ok, poll_fn, FutureExt, LocalBoxFuture, Ready};
+use futures_util::stream::{self, StreamExt};
+use parking_lot::Mutex;
+
+use crate::http::body::{Body, BodyStream, MessageBody, ResponseBody};
+use crate::http::error::Error;
+use crate::http::header::HeaderName;
+use crate::http::{HeaderMap, Method, Response, StatusCode};
+use crate::krse::sync::local::oneshot;
+use crate::service::{Service, Transform};
+use crate::timer::Instant;
+use crate::web::middleware::memory::MemoryBudget;
+use crate::web::service::{ServiceRequest, ServiceResponse};
+
+/// Response stored for an idempotency key.
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl CachedResponse {
+    /// Create cached response from its parts.
+    pub fn new(status: StatusCode, headers: HeaderMap, body: Bytes) -> Self {
+        CachedResponse {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    /// Response status code
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Response headers
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Response body
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    fn to_response(&self) -> Response {
+        let mut res = Response::new(self.status);
+        for (name, value) in self.headers.iter() {
+            res.headers_mut().append(name.clone(), value.clone());
+        }
+        res.set_body(Body::Bytes(self.body.clone()))
+    }
+}
+
+/// State of an idempotency key as seen by `IdempotencyStore::reserve()`.
+#[derive(Debug, Clone)]
+pub enum Reservation {
+    /// Key was unknown and is now reserved by the caller.
+    Acquired,
+    /// Key is reserved by a request that is still being processed.
+    InFlight,
+    /// Key was already processed, stored response must be replayed.
+    Completed(CachedResponse),
+}
+
+/// Storage backend for the `Idempotency` middleware.
+///
+/// Store could be shared between workers (in-memory store uses `Arc`)
+/// or be backed by an external service.
+pub trait IdempotencyStore {
+    /// Reserve key for processing. Reservation must expire after `ttl`.
+    fn reserve(&self, key: &str, ttl: Duration)
+        -> LocalBoxFuture<'static, Result<Reservation, Error>>;
+
+    /// Store response for reserved key.
+    fn complete(
+        &self,
+        key: &str,
+        res: CachedResponse,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<(), Error>>;
+
+    /// Drop reservation, subsequent requests with the same key are processed again.
+    fn release(&self, key: &str) -> LocalBoxFuture<'static, Result<(), Error>>;
+}
+
+enum Entry {
+    InFlight,
+    Completed(CachedResponse),
+}
+
+/// In-memory idempotency store.
+///
+/// Clones of the store share the same storage.
+#[derive(Clone, Default)]
+pub struct MemoryStore {
+    inner: Arc<Mutex<HashMap<String, (Instant, Entry)>>>,
+}
+
+impl MemoryStore {
+    /// Create new in-memory store
+    pub fn new() -> Self {
+        MemoryStore::default()
+    }
+
+    /// Remove all expired entries
+    pub fn purge(&self) {
+        let now = Instant::now();
+        self.inner.lock().retain(|_, (expires, _)| *expires > now);
+    }
+}
+
+impl IdempotencyStore for MemoryStore {
+    fn reserve(
+        &self,
+        key: &str,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<Reservation, Error>> {
+        let now = Instant::now();
+        let mut inner = self.inner.lock();
+
+        let res = match inner.get(key) {
+            Some((expires, Entry::InFlight)) if *expires > now => Reservation::InFlight,
+            Some((expires, Entry::Completed(res))) if *expires > now => {
+                Reservation::Completed(res.clone())
+            }
+            _ => {
+                inner.insert(key.to_owned(), (now + ttl, Entry::InFlight));
+                Reservation::Acquired
+            }
+        };
+        ok(res).boxed_local()
+    }
+
+    fn complete(
+        &self,
+        key: &str,
+        res: CachedResponse,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<(), Error>> {
+        self.inner
+            .lock()
+            .insert(key.to_owned(), (Instant::now() + ttl, Entry::Completed(res)));
+        ok(()).boxed_local()
+    }
+
+    fn release(&self, key: &str) -> LocalBoxFuture<'static, Result<(), Error>> {
+        self.inner.lock().remove(key);
+        ok(()).boxed_local()
+    }
+}
+
+/// `Middleware` implementing idempotency keys for unsafe methods.
+///
+/// First response for a key (header `Idempotency-Key` by default) is
+/// stored and replayed for retries of the same request. Concurrent
+/// duplicates within a worker wait for the first request to complete,
+/// duplicates that are in-flight on a different worker get
+/// *409 Conflict* response. Server errors are not stored.
+///
+/// Key is scoped by request method and path. Reservation of the request
+/// that is being processed expires after `in_flight_ttl` (1 minute by
+/// default), so key of a crashed worker becomes usable again. Reservation
+/// is released if request processing fails or is cancelled.
+///
+/// ```rust
+/// use kayrx::web::{self, middleware, App, HttpResponse};
+/// use kayrx::web::middleware::idempotency::{Idempotency, MemoryStore};
+///
+/// let store = MemoryStore::new();
+///
+/// let app = App::new()
+///     .wrap(Idempotency::with_store(store.clone()))
+///     .service(web::resource("/payments").route(web::post().to(|| HttpResponse::Created())));
+/// ```
+pub struct Idempotency<T = MemoryStore> {
+    inner: Rc<Inner<T>>,
+}
+
+struct Inner<T> {
+    store: T,
+    header: HeaderName,
+    ttl: Duration,
+    in_flight_ttl: Duration,
+    limit: usize,
+}
+
+impl Default for Idempotency<MemoryStore> {
+    fn default() -> Self {
+        Idempotency::with_store(MemoryStore::new())
+    }
+}
+
+impl Idempotency<MemoryStore> {
+    /// Construct `Idempotency` middleware with in-memory store.
+    pub fn new() -> Self {
+        Idempotency::default()
+    }
+}
+
+impl<T: IdempotencyStore> Idempotency<T> {
+    /// Construct `Idempotency` middleware with custom store.
+    pub fn with_store(store: T) -> Self {
+        Idempotency {
+            inner: Rc::new(Inner {
+                store,
+                header: HeaderName::from_static("idempotency-key"),
+                ttl: Duration::from_secs(24 * 60 * 60),
+                in_flight_ttl: Duration::from_secs(60),
+                limit: 262_144,
+            }),
+        }
+    }
+
+    /// Set name of the header carrying idempotency key.
+    ///
+    /// By default `Idempotency-Key` header is used.
+    pub fn header(mut self, name: HeaderName) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .header = name;
+        self
+    }
+
+    /// Set time to keep stored responses. By default responses are kept for 24 hours.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .ttl = ttl;
+        self
+    }
+
+    /// Set time to keep reservation of the request that is still being
+    /// processed. By default reservation is kept for 1 minute.
+    ///
+    /// Duplicates that arrive after reservation expired are processed again,
+    /// so ttl should be longer than max request processing time.
+    pub fn in_flight_ttl(mut self, ttl: Duration) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .in_flight_ttl = ttl;
+        self
+    }
+
+    /// Set max size of the response body that could be stored. By default max size is 256Kb
+    ///
+    /// Responses with bigger bodies are sent as is and the key is released.
+    pub fn limit(mut self, limit: usize) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .limit = limit;
+        self
+    }
+}
+
+impl<S, T, B> Transform<S> for Idempotency<T>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    S::Future: 'static,
+    T: IdempotencyStore + 'static,
+    B: MessageBody + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<Body>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = IdempotencyMiddleware<S, T>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(IdempotencyMiddleware {
+            service: Rc::new(RefCell::new(service)),
+            inner: self.inner.clone(),
+            waiters: Rc::new(RefCell::new(HashMap::new())),
+        })
+    }
+}
+
+type Waiters = Rc<RefCell<HashMap<String, Vec<oneshot::Sender<CachedResponse>>>>>;
+
+#[doc(hidden)]
+pub struct IdempotencyMiddleware<S, T> {
+    service: Rc<RefCell<S>>,
+    inner: Rc<Inner<T>>,
+    waiters: Waiters,
+}
+
+impl<S, T, B> Service for IdempotencyMiddleware<S, T>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    S::Future: 'static,
+    T: IdempotencyStore + 'static,
+    B: MessageBody + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<Body>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let key = match idempotency_key(&req, &self.inner.header) {
+            Some(key) => key,
+            None => {
+                return self
+                    .service
+                    .call(req)
+                    .map(|res| res.map(into_boxed_body))
+                    .boxed_local();
+            }
+        };
+
+        // duplicate of a request that is processed by this worker
+        if let Some(waiters) = self.waiters.borrow_mut().get_mut(&key) {
+            let (tx, rx) = oneshot::channel();
+            waiters.push(tx);
+            return async move {
+                match rx.await {
+                    Ok(cached) => Ok(req.into_response(cached.to_response())),
+                    Err(_) => Ok(req.into_response(Response::Conflict().finish())),
+                }
+            }
+            .boxed_local();
+        }
+        self.waiters.borrow_mut().insert(key.clone(), Vec::new());
+
+        let mut srv = self.service.clone();
+        let mut guard = KeyGuard {
+            key,
+            inner: self.inner.clone(),
+            waiters: self.waiters.clone(),
+            reserved: false,
+            done: false,
+        };
+
+        async move {
+            let inner = guard.inner.clone();
+            match inner.store.reserve(&guard.key, inner.in_flight_ttl).await? {
+                Reservation::Completed(cached) => {
+                    notify(&guard.waiters, &guard.key, &cached);
+                    guard.done = true;
+                    Ok(req.into_response(cached.to_response()))
+                }
+                Reservation::InFlight => {
+                    Ok(req.into_response(Response::Conflict().finish()))
+                }
+                Reservation::Acquired => {
+                    guard.reserved = true;
+                    let res = srv.call(req).await?;
+                    if res.status().is_server_error() {
+                        guard.release().await;
+                        return Ok(res.map_body(|_, body| into_body(body)));
+                    }
+
+                    let (res, cached) = buffer_response(res, inner.limit).await?;
+                    match cached {
+                        Some(cached) => {
+                            notify(&guard.waiters, &guard.key, &cached);
+                            guard.done = true;
+                            inner.store.complete(&guard.key, cached, inner.ttl).await?;
+                        }
+                        None => guard.release().await,
+                    }
+                    Ok(res)
+                }
+            }
+        }
+        .boxed_local()
+    }
+}
+
+/// Key of the request that is being processed.
+///
+/// If processing fails or the future is dropped, waiting duplicates get
+/// *409 Conflict* response and reservation is released.
+struct KeyGuard<T: IdempotencyStore> {
+    key: String,
+    inner: Rc<Inner<T>>,
+    waiters: Waiters,
+    reserved: bool,
+    done: bool,
+}
+
+impl<T: IdempotencyStore> KeyGuard<T> {
+    async fn release(&mut self) {
+        self.done = true;
+        self.waiters.borrow_mut().remove(&self.key);
+        if let Err(e) = self.inner.store.release(&self.key).await {
+            log::warn!("Can not release idempotency key: {}", e);
+        }
+    }
+}
+
+impl<T: IdempotencyStore> Drop for KeyGuard<T> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.waiters.borrow_mut().remove(&self.key);
+            if self.reserved {
+                let fut = self.inner.store.release(&self.key);
+                crate::fiber::spawn(async move {
+                    if let Err(e) = fut.await {
+                        log::warn!("Can not release idempotency key: {}", e);
+                    }
+                });
+            }
+        }
+    }
+}
+
+fn idempotency_key(req: &ServiceRequest, header: &HeaderName) -> Option<String> {
+    match *req.method() {
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE => return None,
+        _ => (),
+    }
+    let key = req.headers().get(header)?.to_str().ok()?;
+    if key.is_empty() {
+        None
+    } else {
+        Some(format!("{} {} {}", req.method(), req.path(), key))
+    }
+}
+
+fn notify(waiters: &Waiters, key: &str, cached: &CachedResponse) {
+    if let Some(waiters) = waiters.borrow_mut().remove(key) {
+        for tx in waiters {
+            let _ = tx.send(cached.clone());
+        }
+    }
+}
+
+fn into_body<B: MessageBody + 'static>(body: ResponseBody<B>) -> ResponseBody<Body> {
+    match body {
+        ResponseBody::Body(b) => ResponseBody::Other(Body::from_message(b)),
+        ResponseBody::Other(b) => ResponseBody::Other(b),
+    }
+}
+
+fn into_boxed_body<B: MessageBody + 'static>(
+    res: ServiceResponse<B>,
+) -> ServiceResponse<Body> {
+    res.map_body(|_, body| into_body(body))
+}
+
+/// Read response body into memory. If body is bigger than `limit` or
+/// request memory budget, response is returned without cached copy.
+async fn buffer_response<B: MessageBody + 'static>(
+    mut res: ServiceResponse<B>,
+    limit: usize,
+) -> Result<(ServiceResponse<Body>, Option<CachedResponse>), Error> {
+    let budget = MemoryBudget::of(res.request());
+    let mut body = res.take_body();
+    let mut buf = BytesMut::new();
+
+    while let Some(item) = poll_fn(|cx| body.poll_next(cx)).await {
+        let chunk = item?;
+        let exceeded = match budget {
+            Some(ref budget) => budget.charge(chunk.len()).is_err(),
+            None => false,
+        };
+        buf.extend_from_slice(&chunk);
+        if buf.len() > limit || exceeded {
+            // send already consumed part followed by the rest of the stream
+            let head = buf.freeze();
+            let stream = stream::once(async move { Ok::<_, Error>(head) }).chain(body);
+            let res = res.map_body(|_, _| {
+                ResponseBody::Other(Body::from_message(BodyStream::new(stream)))
+            });
+            return Ok((res, None));
+        }
+    }
+
+    let body = buf.freeze();
+    let cached = CachedResponse::new(res.status(), res.headers().clone(), body.clone());
+    let res = res.map_body(|_, _| ResponseBody::Other(Body::Bytes(body)));
+    Ok((res, Some(cached)))
+}
@@ -61,6 +61,10 @@ use crate::http::Response as HttpResponse;
 ///
 /// `%r`  First line of request
 ///
+/// `%m`  Request method
+///
+/// `%P`  The process ID of the child that serviced the request
+///
 /// `%s`  Response status code
 ///
 /// `%b`  Size of response in bytes, including HTTP headers
@@ -294,7 +298,7 @@ impl Format {
     /// Returns `None` if the format string syntax is incorrect.
     pub fn new(s: &str) -> Format {
         log::trace!("Access log format: {}", s);
-        let fmt = Regex::new(r"%(\{([A-Za-z0-9\-_]+)\}([ioe])|[atPrUsbTD]?)").unwrap();
+        let fmt = Regex::new(r"%(\{([A-Za-z0-9\-_]+)\}([ioe])|[atPrmUsbTD]?)").unwrap();
 
         let mut idx = 0;
         let mut results = Vec::new();
@@ -324,6 +328,8 @@ impl Format {
                     "a" => FormatText::RemoteAddr,
                     "t" => FormatText::RequestTime,
                     "r" => FormatText::RequestLine,
+                    "m" => FormatText::Method,
+                    "P" => FormatText::Pid,
                     "s" => FormatText::ResponseStatus,
                     "b" => FormatText::ResponseSize,
                     "U" => FormatText::UrlPath,
@@ -349,6 +355,8 @@ pub enum FormatText {
     Str(String),
     Percent,
     RequestLine,
+    Method,
+    Pid,
     RequestTime,
     ResponseStatus,
     ResponseSize,
@@ -372,6 +380,7 @@ impl FormatText {
             FormatText::Str(ref string) => fmt.write_str(string),
             FormatText::Percent => "%".fmt(fmt),
             FormatText::ResponseSize => size.fmt(fmt),
+            FormatText::Pid => std::process::id().fmt(fmt),
             FormatText::Time => {
                 let rt = OffsetDateTime::now() - entry_time;
                 let rt = rt.as_seconds_f64();
@@ -434,6 +443,7 @@ impl FormatText {
                     ))
                 };
             }
+            FormatText::Method => *self = FormatText::Str(req.method().to_string()),
             FormatText::UrlPath => *self = FormatText::Str(req.path().to_string()),
             FormatText::RequestTime => {
                 *self = FormatText::Str(now.format("%Y-%m-%dT%H:%M:%S"))
@@ -464,7 +474,7 @@ impl FormatText {
 }
 
 pub struct FormatDisplay<'a>(
-    &'a dyn Fn(&mut Formatter<'_>) -> Result<(), fmt::Error>,
+    pub &'a dyn Fn(&mut Formatter<'_>) -> Result<(), fmt::Error>,
 );
 
 impl<'a> fmt::Display for FormatDisplay<'a> {
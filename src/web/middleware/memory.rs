@@ -0,0 +1,207 @@
+//! `Middleware` for per-request memory accounting.
+use std::cell::Cell;
+use std::fmt;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use futures_util::future::{ok, Ready};
+
+use crate::http::error::{Error, ResponseError};
+use crate::http::{HttpMessage, Payload, StatusCode};
+use crate::service::{Service, Transform};
+use crate::web::extract::FromRequest;
+use crate::web::request::HttpRequest;
+use crate::web::service::{ServiceRequest, ServiceResponse};
+
+/// `Middleware` for capping memory buffered by a single request.
+///
+/// Middleware stores [`MemoryBudget`](struct.MemoryBudget.html) in request
+/// extensions. Bytes buffered by body extractors (`Bytes`, `String`,
+/// `Json`, `Form`, `Xml`, `Protobuf`), by multipart fields and by
+/// middlewares that buffer response bodies (`Redact`, `Idempotency`) are
+/// charged to the budget. When the cap is exceeded, body extractors fail
+/// with `413 Payload Too Large` and response buffering fails with
+/// `500 Internal Server Error`, so a single pathological request can not
+/// exhaust memory of the worker. Breaches are counted in
+/// `http.server.memory_limit` metric.
+///
+/// Budget is not shared between requests, per-extractor limits still apply.
+///
+/// ```rust
+/// use bytes::Bytes;
+/// use kayrx::web::{self, middleware, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         // 4Mb per request
+///         .wrap(middleware::MemoryLimit::new(4 * 1024 * 1024))
+///         .service(web::resource("/").to(|body: Bytes| async move {
+///             HttpResponse::Ok().body(body)
+///         }));
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct MemoryLimit {
+    limit: usize,
+}
+
+impl MemoryLimit {
+    /// Construct `MemoryLimit` middleware with max number of bytes
+    /// buffered per request.
+    pub fn new(limit: usize) -> MemoryLimit {
+        MemoryLimit { limit }
+    }
+}
+
+impl<S, B> Transform<S> for MemoryLimit
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = MemoryLimitMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(MemoryLimitMiddleware {
+            service,
+            limit: self.limit,
+        })
+    }
+}
+
+pub struct MemoryLimitMiddleware<S> {
+    service: S,
+    limit: usize,
+}
+
+impl<S, B> Service for MemoryLimitMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        req.extensions_mut().insert(MemoryBudget::new(self.limit));
+        self.service.call(req)
+    }
+}
+
+struct Inner {
+    limit: usize,
+    used: Cell<usize>,
+    exceeded: Cell<bool>,
+}
+
+/// Memory budget of the request.
+///
+/// Budget is stored in request extensions by
+/// [`MemoryLimit`](struct.MemoryLimit.html) middleware. Handlers and
+/// custom extractors can charge their own buffers as well. If the
+/// middleware is not registered, extracted budget is unlimited.
+#[derive(Clone)]
+pub struct MemoryBudget(Rc<Inner>);
+
+impl MemoryBudget {
+    fn new(limit: usize) -> Self {
+        MemoryBudget(Rc::new(Inner {
+            limit,
+            used: Cell::new(0),
+            exceeded: Cell::new(false),
+        }))
+    }
+
+    /// Budget of the request, if `MemoryLimit` middleware is registered
+    pub fn of(req: &HttpRequest) -> Option<MemoryBudget> {
+        req.extensions().get::<MemoryBudget>().cloned()
+    }
+
+    /// Charge buffered bytes to the budget.
+    ///
+    /// Bytes are not charged if the budget would be exceeded.
+    pub fn charge(&self, size: usize) -> Result<(), MemoryLimitError> {
+        let used = self.0.used.get().saturating_add(size);
+        if used > self.0.limit {
+            if !self.0.exceeded.replace(true) {
+                #[cfg(feature = "telemetry")]
+                crate::telemetry::counter("http.server.memory_limit", &[]).add(1);
+            }
+            Err(MemoryLimitError {
+                limit: self.0.limit,
+            })
+        } else {
+            self.0.used.set(used);
+            Ok(())
+        }
+    }
+
+    /// Return bytes of a released buffer to the budget
+    pub fn release(&self, size: usize) {
+        self.0.used.set(self.0.used.get().saturating_sub(size));
+    }
+
+    /// Number of currently charged bytes
+    pub fn used(&self) -> usize {
+        self.0.used.get()
+    }
+
+    /// Max number of bytes buffered by the request
+    pub fn limit(&self) -> usize {
+        self.0.limit
+    }
+
+    /// Check if the request tried to buffer more than the limit
+    pub fn is_exceeded(&self) -> bool {
+        self.0.exceeded.get()
+    }
+}
+
+impl fmt::Debug for MemoryBudget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryBudget")
+            .field("limit", &self.0.limit)
+            .field("used", &self.0.used.get())
+            .finish()
+    }
+}
+
+impl FromRequest for MemoryBudget {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ok(MemoryBudget::of(req).unwrap_or_else(|| MemoryBudget::new(usize::MAX)))
+    }
+}
+
+/// Request memory budget is exceeded.
+///
+/// Generates `500 Internal Server Error` response, body extractors report
+/// their own overflow errors instead.
+#[derive(Debug)]
+pub struct MemoryLimitError {
+    limit: usize,
+}
+
+impl fmt::Display for MemoryLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Request memory limit of {} bytes is exceeded", self.limit)
+    }
+}
+
+impl ResponseError for MemoryLimitError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
@@ -1,19 +1,35 @@
 //! Middlewares
 
+pub mod cache;
 mod compress;
 mod condition;
 mod cors;
 mod defaultheaders;
+mod from_fn;
 pub mod errhandlers;
+pub mod idempotency;
 mod logger;
+pub mod memory;
 mod normalize;
+pub mod quota;
+pub mod redact;
+pub mod server_timing;
+mod trace;
 
+pub use self::cache::ResponseCache;
 pub use self::cors::Cors;
 pub use self::compress::Compress;
 pub use self::condition::Condition;
 pub use self::defaultheaders::DefaultHeaders;
+pub use self::from_fn::{from_fn, FromFn, Next};
+pub use self::idempotency::Idempotency;
 pub use self::logger::Logger;
+pub use self::memory::MemoryLimit;
 pub use self::normalize::NormalizePath;
+pub use self::quota::Quotas;
+pub use self::redact::Redact;
+pub use self::server_timing::{ServerTiming, ServerTimingHeader};
+pub use self::trace::Trace;
 
 pub mod dev {
     pub use super::logger::{Format, FormatDisplay};
@@ -0,0 +1,446 @@
+//! `Middleware` for per-tenant request and traffic quotas.
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_util::future::{ok, FutureExt, LocalBoxFuture, Ready};
+use parking_lot::Mutex;
+
+use crate::http::body::{BodySize, MessageBody, ResponseBody};
+use crate::http::error::{Error, PayloadError};
+use crate::http::header::{HeaderName, HeaderValue, RETRY_AFTER};
+use crate::http::{HeaderMap, HttpMessage, Payload, Response};
+use crate::service::{Service, Transform};
+use crate::timer::Instant;
+use crate::web::service::{ServiceRequest, ServiceResponse};
+
+/// Quota of a tenant for a single accounting period.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quota {
+    requests: Option<u64>,
+    bytes: Option<u64>,
+    period: Duration,
+}
+
+impl Quota {
+    /// Create unlimited quota for the accounting period.
+    pub fn per(period: Duration) -> Self {
+        Quota {
+            requests: None,
+            bytes: None,
+            period,
+        }
+    }
+
+    /// Set max number of requests per period
+    pub fn requests(mut self, requests: u64) -> Self {
+        self.requests = Some(requests);
+        self
+    }
+
+    /// Set max number of transferred bytes (request and response bodies) per period
+    pub fn bytes(mut self, bytes: u64) -> Self {
+        self.bytes = Some(bytes);
+        self
+    }
+
+    /// Accounting period
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+}
+
+/// Usage of a tenant in the current accounting period.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Usage {
+    /// Number of requests
+    pub requests: u64,
+    /// Number of transferred bytes
+    pub bytes: u64,
+    /// Time left until the end of the period
+    pub reset: Duration,
+}
+
+/// Accounting backend for the `Quotas` middleware.
+///
+/// Store could be shared between workers (in-memory store uses `Arc`)
+/// or be backed by an external service, i.e. redis.
+pub trait QuotaStore {
+    /// Add requests and bytes to the tenant's usage in the current period
+    /// and return updated usage. New period starts when the current one
+    /// is over.
+    fn record(
+        &self,
+        tenant: &str,
+        quota: &Quota,
+        requests: u64,
+        bytes: u64,
+    ) -> LocalBoxFuture<'static, Result<Usage, Error>>;
+}
+
+struct Window {
+    start: Instant,
+    requests: u64,
+    bytes: u64,
+}
+
+/// In-memory quota store with fixed accounting windows.
+///
+/// Clones of the store share the same storage.
+#[derive(Clone, Default)]
+pub struct MemoryQuotaStore {
+    inner: Arc<Mutex<HashMap<String, Window>>>,
+}
+
+impl MemoryQuotaStore {
+    /// Create new in-memory store
+    pub fn new() -> Self {
+        MemoryQuotaStore::default()
+    }
+
+    /// Remove windows that started before `max_period`
+    pub fn purge(&self, max_period: Duration) {
+        let now = Instant::now();
+        self.inner
+            .lock()
+            .retain(|_, window| window.start + max_period > now);
+    }
+}
+
+impl QuotaStore for MemoryQuotaStore {
+    fn record(
+        &self,
+        tenant: &str,
+        quota: &Quota,
+        requests: u64,
+        bytes: u64,
+    ) -> LocalBoxFuture<'static, Result<Usage, Error>> {
+        let now = Instant::now();
+        let mut inner = self.inner.lock();
+
+        let window = inner.entry(tenant.to_owned()).or_insert(Window {
+            start: now,
+            requests: 0,
+            bytes: 0,
+        });
+        if window.start + quota.period <= now {
+            window.start = now;
+            window.requests = 0;
+            window.bytes = 0;
+        }
+        window.requests += requests;
+        window.bytes += bytes;
+
+        ok(Usage {
+            requests: window.requests,
+            bytes: window.bytes,
+            reset: (window.start + quota.period) - now,
+        })
+        .boxed_local()
+    }
+}
+
+/// `Middleware` for multi-tenant quotas.
+///
+/// Tenant is derived from the request by a user provided closure, requests
+/// without a tenant are not accounted. Each request and the size of request
+/// and response bodies are recorded in a [`QuotaStore`](trait.QuotaStore.html).
+/// Bodies are counted as they are streamed, so chunked uploads are accounted
+/// as well, their size is recorded once the body is consumed or dropped.
+/// Once the tenant exceeds its quota, requests are rejected with
+/// *429 Too Many Requests* until the end of the accounting period.
+///
+/// Usage is reported in `X-Quota-Limit`, `X-Quota-Remaining`,
+/// `X-Quota-Bytes-Limit`, `X-Quota-Bytes-Remaining` and `X-Quota-Reset`
+/// response headers.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use kayrx::web::{self, App, HttpResponse};
+/// use kayrx::web::middleware::quota::{Quota, Quotas};
+///
+/// let app = App::new()
+///     .wrap(
+///         Quotas::new(|req| {
+///             req.headers()
+///                 .get("x-api-key")
+///                 .and_then(|key| key.to_str().ok())
+///                 .map(|key| key.to_owned())
+///         })
+///         .quota(Quota::per(Duration::from_secs(3600)).requests(1000))
+///         .tenant_quota("premium", Quota::per(Duration::from_secs(3600)).requests(100_000)),
+///     )
+///     .service(web::resource("/api").to(|| HttpResponse::Ok()));
+/// ```
+pub struct Quotas<T = MemoryQuotaStore> {
+    inner: Rc<Inner<T>>,
+}
+
+struct Inner<T> {
+    store: T,
+    tenant: Box<dyn Fn(&ServiceRequest) -> Option<String>>,
+    quota: Quota,
+    tenants: HashMap<String, Quota>,
+}
+
+impl Quotas<MemoryQuotaStore> {
+    /// Construct `Quotas` middleware with in-memory store.
+    pub fn new<F>(tenant: F) -> Self
+    where
+        F: Fn(&ServiceRequest) -> Option<String> + 'static,
+    {
+        Quotas::with_store(MemoryQuotaStore::new(), tenant)
+    }
+}
+
+impl<T: QuotaStore> Quotas<T> {
+    /// Construct `Quotas` middleware with custom store.
+    pub fn with_store<F>(store: T, tenant: F) -> Self
+    where
+        F: Fn(&ServiceRequest) -> Option<String> + 'static,
+    {
+        Quotas {
+            inner: Rc::new(Inner {
+                store,
+                tenant: Box::new(tenant),
+                quota: Quota::per(Duration::from_secs(3600)),
+                tenants: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Set default quota. By default quota is unlimited with one hour period.
+    pub fn quota(mut self, quota: Quota) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .quota = quota;
+        self
+    }
+
+    /// Set quota for the specific tenant.
+    pub fn tenant_quota<N: Into<String>>(mut self, tenant: N, quota: Quota) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .tenants
+            .insert(tenant.into(), quota);
+        self
+    }
+}
+
+impl<S, T, B> Transform<S> for Quotas<T>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    S::Future: 'static,
+    T: QuotaStore + Clone + 'static,
+    B: MessageBody + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<QuotaBody<B, T>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = QuotasMiddleware<S, T>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(QuotasMiddleware {
+            service: Rc::new(RefCell::new(service)),
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+pub struct QuotasMiddleware<S, T> {
+    service: Rc<RefCell<S>>,
+    inner: Rc<Inner<T>>,
+}
+
+impl<S, T, B> Service for QuotasMiddleware<S, T>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    S::Future: 'static,
+    T: QuotaStore + Clone + 'static,
+    B: MessageBody + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<QuotaBody<B, T>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
+        let tenant = match (*self.inner.tenant)(&req) {
+            Some(tenant) => tenant,
+            None => {
+                return self
+                    .service
+                    .call(req)
+                    .map(|res| {
+                        res.map(|res| {
+                            res.map_body(|_, body| {
+                                ResponseBody::Body(QuotaBody {
+                                    body,
+                                    size: 0,
+                                    account: None,
+                                })
+                            })
+                        })
+                    })
+                    .boxed_local();
+            }
+        };
+
+        let quota = self
+            .inner
+            .tenants
+            .get(&tenant)
+            .copied()
+            .unwrap_or(self.inner.quota);
+
+        let mut srv = self.service.clone();
+        let inner = self.inner.clone();
+
+        async move {
+            let usage = inner.store.record(&tenant, &quota, 1, 0).await?;
+
+            if exceeded(&quota, &usage) {
+                let mut res = Response::TooManyRequests();
+                res.header(RETRY_AFTER, retry_after(&usage).to_string());
+                let mut res = res.finish();
+                usage_headers(res.headers_mut(), &quota, &usage);
+                return Ok(req.into_response(res.into_body()));
+            }
+
+            let payload = req.take_payload();
+            req.set_payload(Payload::Stream(Box::pin(QuotaPayload {
+                payload,
+                size: 0,
+                account: Some((inner.store.clone(), tenant.clone(), quota)),
+            })));
+
+            let mut res = srv.call(req).await?;
+            usage_headers(res.headers_mut(), &quota, &usage);
+
+            Ok(res.map_body(move |_, body| {
+                ResponseBody::Body(QuotaBody {
+                    body,
+                    size: 0,
+                    account: Some((inner.store.clone(), tenant, quota)),
+                })
+            }))
+        }
+        .boxed_local()
+    }
+}
+
+fn exceeded(quota: &Quota, usage: &Usage) -> bool {
+    quota.requests.map_or(false, |limit| usage.requests > limit)
+        || quota.bytes.map_or(false, |limit| usage.bytes > limit)
+}
+
+fn retry_after(usage: &Usage) -> u64 {
+    let reset = usage.reset;
+    if reset.subsec_nanos() > 0 {
+        reset.as_secs() + 1
+    } else {
+        reset.as_secs()
+    }
+}
+
+fn usage_headers(headers: &mut HeaderMap, quota: &Quota, usage: &Usage) {
+    let mut insert = |name: &'static str, value: u64| {
+        headers.insert(HeaderName::from_static(name), HeaderValue::from(value));
+    };
+    if let Some(limit) = quota.requests {
+        insert("x-quota-limit", limit);
+        insert("x-quota-remaining", limit.saturating_sub(usage.requests));
+    }
+    if let Some(limit) = quota.bytes {
+        insert("x-quota-bytes-limit", limit);
+        insert("x-quota-bytes-remaining", limit.saturating_sub(usage.bytes));
+    }
+    insert("x-quota-reset", retry_after(usage));
+}
+
+/// Response body that records number of sent bytes in the quota store
+pub struct QuotaBody<B, T: QuotaStore> {
+    body: ResponseBody<B>,
+    size: u64,
+    account: Option<(T, String, Quota)>,
+}
+
+impl<B, T: QuotaStore> Drop for QuotaBody<B, T> {
+    fn drop(&mut self) {
+        record_bytes(self.account.take(), self.size);
+    }
+}
+
+impl<B: MessageBody, T: QuotaStore> MessageBody for QuotaBody<B, T> {
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Error>>> {
+        match self.body.poll_next(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                self.size += chunk.len() as u64;
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            val => val,
+        }
+    }
+}
+
+/// Request payload that records number of received bytes in the quota store
+struct QuotaPayload<T: QuotaStore> {
+    payload: Payload,
+    size: u64,
+    account: Option<(T, String, Quota)>,
+}
+
+impl<T: QuotaStore> Unpin for QuotaPayload<T> {}
+
+impl<T: QuotaStore> Drop for QuotaPayload<T> {
+    fn drop(&mut self) {
+        record_bytes(self.account.take(), self.size);
+    }
+}
+
+impl<T: QuotaStore> Stream for QuotaPayload<T> {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        match Pin::new(&mut this.payload).poll_next(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                this.size += chunk.len() as u64;
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            val => val,
+        }
+    }
+}
+
+/// Record transferred bytes in background
+fn record_bytes<T: QuotaStore>(account: Option<(T, String, Quota)>, size: u64) {
+    if let Some((store, tenant, quota)) = account {
+        if size > 0 {
+            let fut = store.record(&tenant, &quota, 0, size);
+            crate::fiber::spawn(async move {
+                if let Err(e) = fut.await {
+                    log::error!("Can not record quota usage: {}", e);
+                }
+            });
+        }
+    }
+}
@@ -0,0 +1,377 @@
+//! `Middleware` for redaction of sensitive response fields.
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use bytes::{Bytes, BytesMut};
+use futures_util::future::{ok, poll_fn, FutureExt, LocalBoxFuture, Ready};
+use mime::Mime;
+use regex::bytes::{NoExpand, Regex};
+use serde_json::Value;
+
+use crate::http::body::{Body, MessageBody, ResponseBody};
+use crate::http::error::{Error, ErrorInternalServerError};
+use crate::http::header::{CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE};
+use crate::service::{Service, Transform};
+use crate::web::middleware::memory::MemoryBudget;
+use crate::web::service::{ServiceRequest, ServiceResponse};
+
+/// Locates and redacts sensitive data in the response body
+pub trait Matcher {
+    /// Check if matcher handles responses with the content type
+    fn applies(&self, mime: &Mime) -> bool;
+
+    /// Redact sensitive data.
+    ///
+    /// Returned error is sent to the client instead of the response,
+    /// so unprocessable bodies are never leaked.
+    fn redact(&self, body: Bytes) -> Result<Bytes, Error>;
+}
+
+/// Redacts JSON fields addressed by JSON pointers (RFC 6901).
+///
+/// `*` segment matches every member of an object or element of an array,
+/// i.e. `/users/*/ssn`. Pointed values are replaced with
+/// `"[REDACTED]"` string by default, missing fields are ignored.
+///
+/// Matcher applies to `application/json` and `+json` content types.
+pub struct JsonPointer {
+    paths: Vec<Vec<String>>,
+    replacement: Value,
+}
+
+impl JsonPointer {
+    /// Create matcher for the list of pointers
+    pub fn new<I, S>(pointers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let paths = pointers
+            .into_iter()
+            .map(|pointer| {
+                pointer
+                    .as_ref()
+                    .split('/')
+                    .skip(1)
+                    .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+                    .collect()
+            })
+            .collect();
+        JsonPointer {
+            paths,
+            replacement: Value::String("[REDACTED]".to_owned()),
+        }
+    }
+
+    /// Set replacement value, use `Value::Null` to clear fields.
+    pub fn replacement(mut self, value: Value) -> Self {
+        self.replacement = value;
+        self
+    }
+
+    fn redact_value(&self, value: &mut Value, path: &[String]) {
+        let (segment, rest) = match path.split_first() {
+            Some(item) => item,
+            None => {
+                *value = self.replacement.clone();
+                return;
+            }
+        };
+
+        match value {
+            Value::Object(ref mut map) => {
+                if segment == "*" {
+                    for item in map.values_mut() {
+                        self.redact_value(item, rest);
+                    }
+                } else if let Some(item) = map.get_mut(segment) {
+                    self.redact_value(item, rest);
+                }
+            }
+            Value::Array(ref mut items) => {
+                if segment == "*" {
+                    for item in items.iter_mut() {
+                        self.redact_value(item, rest);
+                    }
+                } else if let Some(item) =
+                    segment.parse::<usize>().ok().and_then(|idx| items.get_mut(idx))
+                {
+                    self.redact_value(item, rest);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+impl Matcher for JsonPointer {
+    fn applies(&self, mime: &Mime) -> bool {
+        mime.subtype() == mime::JSON || mime.suffix() == Some(mime::JSON)
+    }
+
+    fn redact(&self, body: Bytes) -> Result<Bytes, Error> {
+        if body.is_empty() {
+            return Ok(body);
+        }
+        let mut value: Value = serde_json::from_slice(&body)
+            .map_err(|_| ErrorInternalServerError("Can not redact response"))?;
+        for path in &self.paths {
+            self.redact_value(&mut value, path);
+        }
+        let body = serde_json::to_vec(&value)
+            .map_err(|_| ErrorInternalServerError("Can not redact response"))?;
+        Ok(Bytes::from(body))
+    }
+}
+
+/// Replaces all regex matches in text responses.
+///
+/// Matcher applies to `text/*`, JSON and XML content types. Matches are
+/// replaced with `[REDACTED]` by default.
+pub struct RegexMatcher {
+    regex: Regex,
+    replacement: String,
+}
+
+impl RegexMatcher {
+    /// Create matcher for the regex pattern.
+    ///
+    /// Panics if pattern is not a valid regex.
+    pub fn new(pattern: &str) -> Self {
+        RegexMatcher {
+            regex: Regex::new(pattern).expect("Wrong redaction pattern"),
+            replacement: "[REDACTED]".to_owned(),
+        }
+    }
+
+    /// Set replacement text
+    pub fn replacement<T: Into<String>>(mut self, text: T) -> Self {
+        self.replacement = text.into();
+        self
+    }
+}
+
+impl Matcher for RegexMatcher {
+    fn applies(&self, mime: &Mime) -> bool {
+        mime.type_() == mime::TEXT
+            || mime.subtype() == mime::JSON
+            || mime.suffix() == Some(mime::JSON)
+            || mime.subtype() == mime::XML
+            || mime.suffix() == Some(mime::XML)
+    }
+
+    fn redact(&self, body: Bytes) -> Result<Bytes, Error> {
+        if !self.regex.is_match(&body) {
+            return Ok(body);
+        }
+        let body = self
+            .regex
+            .replace_all(&body, NoExpand(self.replacement.as_bytes()))
+            .into_owned();
+        Ok(Bytes::from(body))
+    }
+}
+
+/// `Middleware` that redacts sensitive fields from responses.
+///
+/// Responses with content type handled by one of the matchers are read into
+/// memory and processed by matchers in registration order. Redaction could
+/// be restricted to a specific audience, i.e. selected by request header,
+/// other requests get the responses as is.
+///
+/// Middleware fails closed: responses that could not be processed, are
+/// bigger than the limit (1Mb by default) or carry `Content-Encoding` are
+/// replaced with *500 Internal Server Error*. Register compression
+/// middleware before this one, so it compresses already redacted bodies.
+///
+/// ```rust
+/// use kayrx::web::{self, App, HttpResponse};
+/// use kayrx::web::middleware::redact::{JsonPointer, Redact, RegexMatcher};
+///
+/// let app = App::new()
+///     .wrap(
+///         Redact::new()
+///             .matcher(JsonPointer::new(&["/ssn", "/cards/*/number"]))
+///             .matcher(RegexMatcher::new(r"\d{3}-\d{2}-\d{4}"))
+///             .audience(|req| {
+///                 req.headers()
+///                     .get("x-audience")
+///                     .map(|val| val != "internal")
+///                     .unwrap_or(true)
+///             }),
+///     )
+///     .service(web::resource("/").to(|| HttpResponse::Ok()));
+/// ```
+pub struct Redact {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    matchers: Vec<Box<dyn Matcher>>,
+    audience: Option<Box<dyn Fn(&ServiceRequest) -> bool>>,
+    limit: usize,
+}
+
+impl Default for Redact {
+    fn default() -> Self {
+        Redact {
+            inner: Rc::new(Inner {
+                matchers: Vec::new(),
+                audience: None,
+                limit: 1_048_576,
+            }),
+        }
+    }
+}
+
+impl Redact {
+    /// Construct `Redact` middleware without matchers.
+    pub fn new() -> Self {
+        Redact::default()
+    }
+
+    /// Register matcher
+    pub fn matcher<M: Matcher + 'static>(mut self, matcher: M) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .matchers
+            .push(Box::new(matcher));
+        self
+    }
+
+    /// Redact responses only for requests accepted by the predicate.
+    ///
+    /// By default responses are redacted for all requests.
+    pub fn audience<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&ServiceRequest) -> bool + 'static,
+    {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .audience = Some(Box::new(f));
+        self
+    }
+
+    /// Set max size of the processed response body. By default max size is 1Mb
+    pub fn limit(mut self, limit: usize) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .limit = limit;
+        self
+    }
+}
+
+impl<S, B> Transform<S> for Redact
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<Body>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RedactMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RedactMiddleware {
+            service,
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+pub struct RedactMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service for RedactMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<Body>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let redact = match self.inner.audience {
+            Some(ref audience) => audience(&req),
+            None => true,
+        };
+        let inner = self.inner.clone();
+        let fut = self.service.call(req);
+
+        async move {
+            let mut res = fut.await?;
+            if !redact {
+                return Ok(res.map_body(|_, body| into_body(body)));
+            }
+
+            let mime = res
+                .headers()
+                .get(&CONTENT_TYPE)
+                .and_then(|val| val.to_str().ok())
+                .and_then(|val| val.parse::<Mime>().ok());
+            let matchers: Vec<_> = match mime {
+                Some(ref mime) => inner
+                    .matchers
+                    .iter()
+                    .filter(|matcher| matcher.applies(mime))
+                    .collect(),
+                None => Vec::new(),
+            };
+            if matchers.is_empty() {
+                return Ok(res.map_body(|_, body| into_body(body)));
+            }
+
+            let encoded = res
+                .headers()
+                .get(&CONTENT_ENCODING)
+                .map(|val| val != "identity")
+                .unwrap_or(false);
+            if encoded {
+                return Err(ErrorInternalServerError("Can not redact encoded response"));
+            }
+
+            let budget = MemoryBudget::of(res.request());
+            let mut body = res.take_body();
+            let mut buf = BytesMut::new();
+            while let Some(item) = poll_fn(|cx| body.poll_next(cx)).await {
+                let chunk = item?;
+                if let Some(ref budget) = budget {
+                    budget.charge(chunk.len())?;
+                }
+                buf.extend_from_slice(&chunk);
+                if buf.len() > inner.limit {
+                    return Err(ErrorInternalServerError(
+                        "Response is too large to redact",
+                    ));
+                }
+            }
+
+            let mut body = buf.freeze();
+            for matcher in matchers {
+                body = matcher.redact(body)?;
+            }
+            res.headers_mut().remove(&CONTENT_LENGTH);
+            Ok(res.map_body(|_, _| ResponseBody::Other(Body::Bytes(body))))
+        }
+        .boxed_local()
+    }
+}
+
+fn into_body<B: MessageBody + 'static>(body: ResponseBody<B>) -> ResponseBody<Body> {
+    match body {
+        ResponseBody::Body(b) => ResponseBody::Other(Body::from_message(b)),
+        ResponseBody::Other(b) => ResponseBody::Other(b),
+    }
+}
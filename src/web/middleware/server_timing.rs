@@ -0,0 +1,268 @@
+//! Middleware for `Server-Timing` response header
+use std::cell::RefCell;
+use std::fmt::Write;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures_util::future::{ok, FutureExt, LocalBoxFuture, Ready};
+
+use crate::http::header::{HeaderName, HeaderValue};
+use crate::http::{error::Error, HttpMessage, Payload};
+use crate::service::{Service, Transform};
+use crate::web::extract::FromRequest;
+use crate::web::request::HttpRequest;
+use crate::web::service::{ServiceRequest, ServiceResponse};
+
+const SERVER_TIMING: &str = "server-timing";
+
+/// `Middleware` for emitting `Server-Timing` response header.
+///
+/// Handlers and inner middlewares record named metrics through the
+/// [`ServerTiming`](struct.ServerTiming.html) handle stored in request
+/// extensions. Recorded metrics are sent to the client in `Server-Timing`
+/// header (see [W3C Server Timing](https://www.w3.org/TR/server-timing/)),
+/// so backend phases are visible in browser developer tools. By default
+/// `total` metric with the duration of the whole request is added as well.
+///
+/// Metrics may reveal details of the backend, consider enabling this
+/// middleware only for trusted clients.
+///
+/// ```rust
+/// use kayrx::web::{self, middleware, App, HttpResponse};
+/// use kayrx::web::middleware::ServerTiming;
+///
+/// async fn index(timing: ServerTiming) -> HttpResponse {
+///     let guard = timing.start("db");
+///     // query database ...
+///     drop(guard);
+///
+///     timing.mark("cache-miss");
+///     HttpResponse::Ok().finish()
+/// }
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(middleware::ServerTimingHeader::new())
+///         .service(web::resource("/").to(index));
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ServerTimingHeader {
+    total: bool,
+}
+
+impl Default for ServerTimingHeader {
+    fn default() -> Self {
+        ServerTimingHeader { total: true }
+    }
+}
+
+impl ServerTimingHeader {
+    /// Construct `ServerTimingHeader` middleware.
+    pub fn new() -> ServerTimingHeader {
+        ServerTimingHeader::default()
+    }
+
+    /// Add `total` metric with the duration of the request. Enabled by default.
+    pub fn total(mut self, total: bool) -> Self {
+        self.total = total;
+        self
+    }
+}
+
+impl<S, B> Transform<S> for ServerTimingHeader
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ServerTimingMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(ServerTimingMiddleware {
+            service,
+            total: self.total,
+        })
+    }
+}
+
+pub struct ServerTimingMiddleware<S> {
+    service: S,
+    total: bool,
+}
+
+impl<S, B> Service for ServerTimingMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let start = Instant::now();
+        let total = self.total;
+        let timing = ServerTiming::default();
+        req.extensions_mut().insert(timing.clone());
+
+        let fut = self.service.call(req);
+
+        async move {
+            let mut res = fut.await?;
+
+            let total = if total { Some(start.elapsed()) } else { None };
+            if let Some(value) = timing.header_value(total) {
+                res.headers_mut()
+                    .append(HeaderName::from_static(SERVER_TIMING), value);
+            }
+            Ok(res)
+        }
+        .boxed_local()
+    }
+}
+
+struct Metric {
+    name: String,
+    dur: Option<Duration>,
+    desc: Option<String>,
+}
+
+/// Handle for recording `Server-Timing` metrics.
+///
+/// Handle is stored in request extensions by
+/// [`ServerTimingHeader`](struct.ServerTimingHeader.html) middleware and
+/// can be extracted in handlers. If the middleware is not registered,
+/// extracted handle is detached and recorded metrics are discarded.
+#[derive(Clone, Default)]
+pub struct ServerTiming(Rc<RefCell<Vec<Metric>>>);
+
+impl ServerTiming {
+    /// Record metric with duration
+    pub fn record<N: Into<String>>(&self, name: N, dur: Duration) {
+        self.push(name.into(), Some(dur), None);
+    }
+
+    /// Record metric with duration and description
+    pub fn record_with_desc<N, D>(&self, name: N, dur: Duration, desc: D)
+    where
+        N: Into<String>,
+        D: Into<String>,
+    {
+        self.push(name.into(), Some(dur), Some(desc.into()));
+    }
+
+    /// Record metric without duration, i.e. `cache-miss`
+    pub fn mark<N: Into<String>>(&self, name: N) {
+        self.push(name.into(), None, None);
+    }
+
+    /// Start measuring metric, duration is recorded when returned guard is dropped
+    pub fn start<N: Into<String>>(&self, name: N) -> TimingGuard {
+        TimingGuard {
+            timing: self.clone(),
+            name: Some(name.into()),
+            start: Instant::now(),
+        }
+    }
+
+    fn push(&self, name: String, dur: Option<Duration>, desc: Option<String>) {
+        self.0.borrow_mut().push(Metric { name, dur, desc });
+    }
+
+    fn header_value(&self, total: Option<Duration>) -> Option<HeaderValue> {
+        let metrics = self.0.borrow();
+        let mut value = String::new();
+
+        let total = total.map(|dur| Metric {
+            name: "total".to_owned(),
+            dur: Some(dur),
+            desc: None,
+        });
+        for metric in metrics.iter().chain(total.iter()) {
+            if !value.is_empty() {
+                value.push_str(", ");
+            }
+            // metric name must be a token
+            value.extend(metric.name.chars().map(|c| {
+                if c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c) {
+                    c
+                } else {
+                    '_'
+                }
+            }));
+            if let Some(ref desc) = metric.desc {
+                value.push_str(";desc=\"");
+                value.extend(
+                    desc.chars()
+                        .filter(|c| !c.is_control())
+                        .flat_map(|c| match c {
+                            '"' | '\\' => vec!['\\', c],
+                            c => vec![c],
+                        }),
+                );
+                value.push('"');
+            }
+            if let Some(dur) = metric.dur {
+                let _ = write!(value, ";dur={:.3}", dur.as_secs_f64() * 1000.0);
+            }
+        }
+
+        if value.is_empty() {
+            None
+        } else {
+            HeaderValue::from_str(&value).ok()
+        }
+    }
+}
+
+impl FromRequest for ServerTiming {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ok(req
+            .extensions()
+            .get::<ServerTiming>()
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+/// Records duration of the metric when dropped
+pub struct TimingGuard {
+    timing: ServerTiming,
+    name: Option<String>,
+    start: Instant,
+}
+
+impl TimingGuard {
+    /// Stop measuring and record the metric
+    pub fn stop(mut self) {
+        self.finish();
+    }
+
+    fn finish(&mut self) {
+        if let Some(name) = self.name.take() {
+            self.timing.record(name, self.start.elapsed());
+        }
+    }
+}
+
+impl Drop for TimingGuard {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
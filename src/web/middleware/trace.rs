@@ -0,0 +1,139 @@
+//! Middleware for distributed tracing context
+use std::task::{Context, Poll};
+
+use futures_util::future::{ok, FutureExt, LocalBoxFuture, Ready};
+
+use crate::http::error::Error;
+use crate::http::header::{HeaderName, HeaderValue};
+use crate::http::{HttpMessage, Payload};
+use crate::service::{Service, Transform};
+use crate::web::extract::FromRequest;
+use crate::web::request::HttpRequest;
+use crate::web::service::{ServiceRequest, ServiceResponse};
+use crate::web::trace::{TraceContext, TRACEPARENT};
+
+/// `Middleware` for extracting distributed tracing context.
+///
+/// Middleware reads W3C `traceparent`/`tracestate` or B3 headers of the
+/// request and starts server span as a child of the remote span, requests
+/// without context start new trace. Span is stored in request extensions,
+/// so handlers can extract `TraceContext`, and it is current while the
+/// request is handled, see [`trace`](../trace/index.html) module.
+///
+/// ```rust
+/// use kayrx::web::{self, middleware, App, HttpResponse};
+/// use kayrx::web::trace::TraceContext;
+///
+/// async fn index(ctx: TraceContext) -> HttpResponse {
+///     println!("trace {:032x}", ctx.trace_id());
+///     HttpResponse::Ok().finish()
+/// }
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(middleware::Trace::new().response_header(true))
+///         .service(web::resource("/").to(index));
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct Trace {
+    response_header: bool,
+}
+
+impl Trace {
+    /// Construct `Trace` middleware.
+    pub fn new() -> Trace {
+        Trace::default()
+    }
+
+    /// Send `traceparent` of the server span in response. Disabled by default.
+    pub fn response_header(mut self, enabled: bool) -> Self {
+        self.response_header = enabled;
+        self
+    }
+}
+
+impl<S, B> Transform<S> for Trace
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = TraceMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(TraceMiddleware {
+            service,
+            response_header: self.response_header,
+        })
+    }
+}
+
+pub struct TraceMiddleware<S> {
+    service: S,
+    response_header: bool,
+}
+
+impl<S, B> Service for TraceMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let ctx = TraceContext::from_headers(req.headers())
+            .map(|remote| remote.child())
+            .unwrap_or_else(TraceContext::new_root);
+        req.extensions_mut().insert(ctx.clone());
+
+        let header = if self.response_header {
+            HeaderValue::from_str(&ctx.traceparent()).ok()
+        } else {
+            None
+        };
+        // inner services may start work during `call()`
+        let fut = {
+            let _guard = ctx.enter();
+            self.service.call(req)
+        };
+
+        ctx.scope(async move {
+            let mut res = fut.await?;
+            if let Some(value) = header {
+                res.headers_mut()
+                    .insert(HeaderName::from_static(TRACEPARENT), value);
+            }
+            Ok(res)
+        })
+        .boxed_local()
+    }
+}
+
+impl FromRequest for TraceContext {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    /// Context stored by `Trace` middleware, new root context if the
+    /// middleware is not registered.
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ok(req
+            .extensions()
+            .get::<TraceContext>()
+            .cloned()
+            .unwrap_or_else(TraceContext::new_root))
+    }
+}
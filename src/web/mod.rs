@@ -58,45 +58,68 @@
 //! ## Package feature
 //!
 //! * `cookie` - enables http cookie support.
+//! * `protobuf` - enables protobuf and grpc-web extractor/responder.
 //! 
 
 mod app;
+mod admission;
 mod app_service;
 mod config;
 mod data;
+mod dynamic;
 mod extract;
 mod handler;
 mod info;
+mod module;
 mod request;
 mod resource;
-mod responder;
 mod rmap;
 mod route;
+mod route_table;
 mod scope;
 mod server;
+mod server_config;
 mod service;
 mod web;
 
+pub mod batch;
 pub mod client;
 pub mod error;
-pub mod file;
+pub mod files;
+pub mod graphql;
 pub mod guard;
+pub mod health;
 pub mod middleware;
 pub mod multipart;
+pub mod responder;
+pub mod seo;
+pub mod sse;
 pub mod test;
+pub mod trace;
 pub mod types;
+pub mod upload;
+pub mod ws;
+
+#[doc(hidden)]
+pub use self::files as file;
 
 pub use kayrx_macro::{connect, delete, get, post, head, options, patch, put, trace};
+pub use kayrx_macro::{routes, FromRequest, Responder};
+pub use self::admission::{Admission, QueueOrder};
 pub use self::app::App;
 pub use self::config::ServiceConfig;
-pub use self::data::Data;
+pub use self::data::{Data, DataConfig, ReqData};
+pub use self::dynamic::{DynamicRouter, DynamicRoutes};
 pub use self::extract::FromRequest;
+pub use self::module::{Module, ModuleConfig};
 pub use self::request::HttpRequest;
 pub use self::resource::Resource;
-pub use self::responder::{Either, Responder};
+pub use self::responder::{Attachment, Either, Redirect, Responder, Streaming};
 pub use self::route::Route;
+pub use self::route_table::RouteTable;
 pub use self::scope::Scope;
 pub use self::server::HttpServer;
+pub use self::server_config::{ServerConfig, ServerConfigError, TlsConfig};
 pub use self::service::WebService;
 pub use self::web::*;
 
@@ -110,16 +133,18 @@ pub mod dev {
     //! traits by adding a glob import to the top of kayrx heavy modules:
 
     pub use crate::http::body::{Body, BodySize, MessageBody, ResponseBody, SizedStream};
-    pub use crate::http::encoding::Decoder as Decompress;
+    pub use crate::http::encoding::{Decoder as Decompress, DecompressLimits};
     pub use crate::http::ResponseBuilder as HttpResponseBuilder;
     pub use crate::http::{ Extensions, Payload, PayloadStream, RequestHead, ResponseHead};
     pub use crate::server::Server;
     pub use crate::service::{Service, Transform};
-    pub use crate::router::{Path, ResourceDef, ResourcePath, Url};
+    pub use crate::router::{
+        DecodeError, EncodedSlash, Path, PathDecoding, ResourceDef, ResourcePath, Url,
+    };
     pub use super::config::{AppConfig, AppService};
     #[doc(hidden)]
     pub use super::handler::Factory;
-    pub use super::info::ConnectionInfo;
+    pub use super::info::{ConnectionInfo, ConnectionInfoConfig, InfoSource, TlsInfo};
     pub use super::rmap::ResourceMap;
     pub use super::service::{HttpServiceFactory, ServiceRequest, ServiceResponse, WebService};
     pub use super::types::form::UrlEncoded;
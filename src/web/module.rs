@@ -0,0 +1,155 @@
+//! Reusable application modules
+use std::future::Future;
+use std::ops;
+
+use futures_util::future::{FutureExt, LocalBoxFuture};
+
+use crate::server::lifecycle::{self, Hook};
+use crate::service::boxed::{BoxService, BoxServiceFactory};
+use crate::service::{Service, Transform};
+use crate::web::config::ServiceConfig;
+use crate::web::error::Error;
+use crate::web::scope::Scope;
+use crate::web::service::{ServiceRequest, ServiceResponse};
+
+pub(crate) type ModuleScope =
+    Scope<BoxServiceFactory<(), ServiceRequest, ServiceResponse, Error, ()>>;
+type Middleware = Box<dyn FnOnce(ModuleScope) -> ModuleScope>;
+type Job = Box<dyn Fn() -> LocalBoxFuture<'static, ()>>;
+
+/// Reusable bundle of routes, data, middleware and background jobs.
+///
+/// Module is registered with `App::plug()`. Every module has a unique name,
+/// registering two modules with the same name or mount prefix panics.
+///
+/// ```rust
+/// use kayrx::web::{self, middleware, App, HttpResponse, Module, ModuleConfig};
+///
+/// struct Health;
+///
+/// impl Module for Health {
+///     fn name(&self) -> &str {
+///         "health"
+///     }
+///
+///     fn prefix(&self) -> &str {
+///         "/health"
+///     }
+///
+///     fn configure(&self, cfg: &mut ModuleConfig) {
+///         cfg.route("/live", web::get().to(|| HttpResponse::Ok()));
+///         cfg.wrap(middleware::DefaultHeaders::new().header("cache-control", "no-store"));
+///     }
+/// }
+///
+/// let app = App::new().plug(Health);
+/// ```
+pub trait Module {
+    /// Unique module name
+    fn name(&self) -> &str;
+
+    /// Default mount prefix.
+    ///
+    /// Module without prefix registers its services at the application
+    /// root. Application could mount module at a different prefix with
+    /// `App::plug_at()`.
+    fn prefix(&self) -> &str {
+        ""
+    }
+
+    /// Register module services
+    fn configure(&self, cfg: &mut ModuleConfig);
+}
+
+/// Module configuration.
+///
+/// Module configuration extends [`ServiceConfig`](struct.ServiceConfig.html)
+/// with middleware, lifecycle hooks and background jobs.
+pub struct ModuleConfig {
+    pub(crate) config: ServiceConfig,
+    pub(crate) middleware: Vec<Middleware>,
+    pub(crate) on_start: Vec<Hook>,
+    pub(crate) on_stop: Vec<Hook>,
+    pub(crate) jobs: Vec<Job>,
+}
+
+impl ModuleConfig {
+    pub(crate) fn new() -> Self {
+        ModuleConfig {
+            config: ServiceConfig::new(),
+            middleware: Vec::new(),
+            on_start: Vec::new(),
+            on_stop: Vec::new(),
+            jobs: Vec::new(),
+        }
+    }
+
+    /// Register middleware for all module services.
+    ///
+    /// Module with middleware requires mount prefix. Middleware is applied
+    /// in registration order, same way as `Scope::wrap()`.
+    pub fn wrap<M>(&mut self, mw: M) -> &mut Self
+    where
+        M: Transform<
+                BoxService<ServiceRequest, ServiceResponse, Error>,
+                Request = ServiceRequest,
+                Response = ServiceResponse,
+                Error = Error,
+                InitError = (),
+            > + 'static,
+        M::Transform: 'static,
+        M::Future: 'static,
+        <M::Transform as Service>::Future: 'static,
+    {
+        self.middleware
+            .push(Box::new(move |scope: ModuleScope| scope.wrap(mw).boxed()));
+        self
+    }
+
+    /// Register async function that is executed on application start
+    pub fn on_start<F, R>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn() -> R + 'static,
+        R: Future<Output = ()> + 'static,
+    {
+        self.on_start.push(lifecycle::hook(f));
+        self
+    }
+
+    /// Register async function that is executed on application stop
+    pub fn on_stop<F, R>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn() -> R + 'static,
+        R: Future<Output = ()> + 'static,
+    {
+        self.on_stop.push(lifecycle::hook(f));
+        self
+    }
+
+    /// Register background job.
+    ///
+    /// Job is started for each application instance after start hooks,
+    /// and it is cancelled when application stops.
+    pub fn background<F, R>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn() -> R + 'static,
+        R: Future<Output = ()> + 'static,
+    {
+        self.jobs.push(Box::new(move || f().boxed_local()));
+        self
+    }
+}
+
+impl ops::Deref for ModuleConfig {
+    type Target = ServiceConfig;
+
+    fn deref(&self) -> &ServiceConfig {
+        &self.config
+    }
+}
+
+impl ops::DerefMut for ModuleConfig {
+    fn deref_mut(&mut self) -> &mut ServiceConfig {
+        &mut self.config
+    }
+}
@@ -32,10 +32,13 @@ pub enum MultipartError {
     NotConsumed,
 }
 
-/// Return `BadRequest` for `MultipartError`
+/// Return `BadRequest` for `MultipartError`, `PayloadTooLarge` for overflow
 impl ResponseError for MultipartError {
     fn status_code(&self) -> StatusCode {
-        StatusCode::BAD_REQUEST
+        match *self {
+            MultipartError::Payload(PayloadError::Overflow) => StatusCode::PAYLOAD_TOO_LARGE,
+            _ => StatusCode::BAD_REQUEST,
+        }
     }
 }
 
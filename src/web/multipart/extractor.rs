@@ -1,4 +1,5 @@
 //! Multipart payload support
+use crate::web::middleware::memory::MemoryBudget;
 use crate::web::{dev::Payload, FromRequest, HttpRequest};
 use futures_util::future::{ok, Ready};
 use crate::http::error::Error;
@@ -37,6 +38,6 @@ impl FromRequest for Multipart {
 
     #[inline]
     fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
-        ok(Multipart::new(req.headers(), payload.take()))
+        ok(Multipart::new(req.headers(), payload.take()).budget(MemoryBudget::of(req)))
     }
 }
\ No newline at end of file
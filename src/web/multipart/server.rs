@@ -14,6 +14,7 @@ use mime;
 
 use crate::krse::task::LocalWaker;
 use crate::web::error::{ParseError, PayloadError};
+use crate::web::middleware::memory::MemoryBudget;
 use crate::http::header::{
     self, ContentDisposition, HeaderMap, HeaderName, HeaderValue,
 };
@@ -83,6 +84,14 @@ impl Multipart {
         }
     }
 
+    /// Charge buffered payload to the request memory budget
+    pub(crate) fn budget(self, budget: Option<MemoryBudget>) -> Self {
+        if let Some(ref inner) = self.inner {
+            inner.borrow().payload.payload.borrow_mut().budget = budget;
+        }
+        self
+    }
+
     /// Extract boundary info from headers.
     pub fn boundary(headers: &HeaderMap) -> Result<String, MultipartError> {
         if let Some(content_type) = headers.get(&header::CONTENT_TYPE) {
@@ -114,6 +123,8 @@ impl Stream for Multipart {
     ) -> Poll<Option<Self::Item>> {
         if let Some(err) = self.error.take() {
             Poll::Ready(Some(Err(err)))
+        } else if self.inner.is_none() {
+            Poll::Ready(None)
         } else if self.safety.current() {
             let this = self.get_mut();
             let mut inner = this.inner.as_mut().unwrap().borrow_mut();
@@ -214,8 +225,9 @@ impl InnerMultipart {
                     if chunk.len() < boundary.len() {
                         continue;
                     }
-                    if &chunk[..2] == b"--"
-                        && &chunk[2..chunk.len() - 2] == boundary.as_bytes()
+                    if chunk.len() == boundary.len() + 4
+                        && &chunk[..2] == b"--"
+                        && &chunk[2..boundary.len() + 2] == boundary.as_bytes()
                     {
                         break;
                     } else {
@@ -721,6 +733,8 @@ pub struct PayloadBuffer {
     pub eof: bool,
     pub buf: BytesMut,
     stream: LocalBoxStream<'static, Result<Bytes, PayloadError>>,
+    budget: Option<MemoryBudget>,
+    charged: usize,
 }
 
 impl PayloadBuffer {
@@ -733,13 +747,26 @@ impl PayloadBuffer {
             eof: false,
             buf: BytesMut::new(),
             stream: stream.boxed_local(),
+            budget: None,
+            charged: 0,
         }
     }
 
     pub fn poll_stream(&mut self, cx: &mut Context) -> Result<(), PayloadError> {
         loop {
             match Pin::new(&mut self.stream).poll_next(cx) {
-                Poll::Ready(Some(Ok(data))) => self.buf.extend_from_slice(&data),
+                Poll::Ready(Some(Ok(data))) => {
+                    if let Some(ref budget) = self.budget {
+                        // consumed part of the buffer is returned to the budget
+                        budget.release(self.charged.saturating_sub(self.buf.len()));
+                        self.charged = cmp::min(self.charged, self.buf.len());
+                        budget
+                            .charge(data.len())
+                            .map_err(|_| PayloadError::Overflow)?;
+                        self.charged += data.len();
+                    }
+                    self.buf.extend_from_slice(&data)
+                }
                 Poll::Ready(Some(Err(e))) => return Err(e),
                 Poll::Ready(None) => {
                     self.eof = true;
@@ -804,3 +831,11 @@ impl PayloadBuffer {
         self.buf.extend_from_slice(&buf);
     }
 }
+
+impl Drop for PayloadBuffer {
+    fn drop(&mut self) {
+        if let Some(ref budget) = self.budget {
+            budget.release(self.charged);
+        }
+    }
+}
@@ -1,17 +1,21 @@
 use std::cell::{Ref, RefCell, RefMut};
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
 use std::{fmt, net};
 
+use crate::http::header::{CONTENT_LENGTH, TRANSFER_ENCODING};
 use crate::http::{HeaderMap, Method, Uri, Version};
 use crate::http::{error::Error, Extensions, HttpMessage, Message, Payload, RequestHead};
 use crate::router::{Path, Url};
-use futures_util::future::{ok, Ready};
+use futures_util::future::{ok, FutureExt, LocalBoxFuture, Ready};
+use smallvec::SmallVec;
 
+use crate::web::app_service::AppRouter;
 use crate::web::config::AppConfig;
-use crate::web::error::UrlGenerationError;
+use crate::web::error::{SubrequestError, UrlGenerationError};
 use crate::web::extract::FromRequest;
 use crate::web::info::ConnectionInfo;
 use crate::web::rmap::ResourceMap;
+use crate::web::service::{ServiceRequest, ServiceResponse};
 
 #[derive(Clone)]
 /// An HTTP Request
@@ -21,7 +25,8 @@ pub(crate) struct HttpRequestInner {
     pub(crate) head: Message<RequestHead>,
     pub(crate) path: Path<Url>,
     pub(crate) payload: Payload,
-    pub(crate) app_data: Rc<Extensions>,
+    pub(crate) app_data: SmallVec<[Rc<Extensions>; 4]>,
+    pub(crate) app_router: Option<Weak<RefCell<AppRouter>>>,
     rmap: Rc<ResourceMap>,
     config: AppConfig,
     pool: &'static HttpRequestPool,
@@ -44,7 +49,8 @@ impl HttpRequest {
             payload,
             rmap,
             config,
-            app_data,
+            app_data: smallvec::smallvec![app_data],
+            app_router: None,
             pool,
         }))
     }
@@ -136,6 +142,11 @@ impl HttpRequest {
 
     /// Generate url for named resource
     ///
+    /// Absolute url is built from the connection info of the request.
+    /// Elements are substituted into dynamic segments in order, characters
+    /// that would change structure of the url (`?`, `#`, spaces, etc) are
+    /// percent-encoded.
+    ///
     /// ```rust
     /// # use kayrx::web::{self, App, HttpRequest, HttpResponse};
     /// #
@@ -207,12 +218,106 @@ impl HttpRequest {
 
     /// Get an application data stored with `App::extension()` method during
     /// application configuration.
+    ///
+    /// Data of the innermost scope or resource takes precedence, data of
+    /// enclosing scopes and application is still accessible.
     pub fn app_data<T: 'static>(&self) -> Option<&T> {
-        if let Some(st) = self.0.app_data.get::<T>() {
-            Some(&st)
-        } else {
-            None
+        self.0
+            .app_data
+            .iter()
+            .rev()
+            .filter_map(|container| container.get::<T>())
+            .next()
+    }
+
+    /// Dispatch internal request to the application router.
+    ///
+    /// Subrequest does not make a socket round trip, it is routed as a
+    /// regular request with the given path and method, a copy of the
+    /// request headers and an empty body. Scope and resource middlewares
+    /// are applied, application level middlewares are not. Application
+    /// data is shared with the parent request.
+    ///
+    /// Subrequest is dispatched when the returned future is polled, so it
+    /// can not be awaited within `Service::call()` of a middleware.
+    ///
+    /// ```rust
+    /// use kayrx::http::Error;
+    /// use kayrx::http::Method;
+    /// use kayrx::web::{self, test, App, HttpRequest, HttpResponse};
+    ///
+    /// async fn page(req: HttpRequest) -> Result<HttpResponse, Error> {
+    ///     let header = req.subrequest("/fragments/header", Method::GET).await?;
+    ///     let body = test::read_body(header).await;
+    ///     Ok(HttpResponse::Ok().body(body))
+    /// }
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .route("/", web::get().to(page))
+    ///         .route("/fragments/header", web::get().to(|| HttpResponse::Ok()));
+    /// }
+    /// ```
+    pub fn subrequest(
+        &self,
+        path: &str,
+        method: Method,
+    ) -> LocalBoxFuture<'static, Result<ServiceResponse, Error>> {
+        self.dispatch(self.child(path, method).map(ServiceRequest::new))
+    }
+
+    /// Dispatch prepared subrequest to the application router
+    pub(crate) fn dispatch(
+        &self,
+        req: Result<ServiceRequest, SubrequestError>,
+    ) -> LocalBoxFuture<'static, Result<ServiceResponse, Error>> {
+        let router = self.0.app_router.as_ref().and_then(|r| r.upgrade());
+
+        async move {
+            let req = req?;
+            let router = router.ok_or(SubrequestError::Unavailable)?;
+            let fut = router
+                .try_borrow_mut()
+                .map_err(|_| SubrequestError::Busy)?
+                .call(req);
+            fut.await
+        }
+        .boxed_local()
+    }
+
+    /// Create request for the internal dispatch
+    pub(crate) fn child(&self, path: &str, method: Method) -> Result<HttpRequest, SubrequestError> {
+        if !path.starts_with('/') {
+            return Err(SubrequestError::InvalidPath);
         }
+        let uri = path
+            .parse::<Uri>()
+            .map_err(|_| SubrequestError::InvalidPath)?;
+
+        let (mut head, _) = crate::http::Request::new().into_parts();
+        head.uri = uri;
+        head.method = method;
+        head.version = self.version();
+        head.peer_addr = self.head().peer_addr;
+        head.headers = self.headers().clone();
+        head.headers.remove(CONTENT_LENGTH);
+        head.headers.remove(TRANSFER_ENCODING);
+
+        let path = Path::new(Url::with_decoding(
+            head.uri.clone(),
+            *self.match_info().get_ref().decoding(),
+        ));
+        let mut req = HttpRequest::new(
+            path,
+            head,
+            Payload::None,
+            self.0.rmap.clone(),
+            self.0.config.clone(),
+            self.0.app_data[0].clone(),
+            self.0.pool,
+        );
+        Rc::get_mut(&mut req.0).unwrap().app_router = self.0.app_router.clone();
+        Ok(req)
     }
 }
 
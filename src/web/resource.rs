@@ -6,12 +6,12 @@ use std::rc::Rc;
 use std::task::{Context, Poll};
 
 use crate::http::{error::Error, Extensions, Response};
-use crate::router::IntoPattern;
+use crate::router::{IntoPattern, PathDecoding};
 use crate::service::boxed::{self, BoxService, BoxServiceFactory};
 use crate::service::{
     apply, apply_fn_factory, IntoServiceFactory, Service, ServiceFactory, Transform,
 };
-use futures_util::future::{ok, Either, LocalBoxFuture, Ready};
+use futures_util::future::{ok, Either, FutureExt, LocalBoxFuture, Ready};
 
 use crate::web::data::Data;
 use crate::web::dev::{insert_slash, AppService, HttpServiceFactory, ResourceDef};
@@ -19,7 +19,7 @@ use crate::web::extract::FromRequest;
 use crate::web::guard::Guard;
 use crate::web::handler::Factory;
 use crate::web::responder::Responder;
-use crate::web::route::{CreateRouteService, Route, RouteService};
+use crate::web::route::{CreateRouteService, Route, RouteCandidate, RouteService};
 use crate::web::service::{ServiceRequest, ServiceResponse};
 
 type HttpService = BoxService<ServiceRequest, ServiceResponse, Error>;
@@ -56,6 +56,7 @@ pub struct Resource<T = ResourceEndpoint> {
     guards: Vec<Box<dyn Guard>>,
     default: Rc<RefCell<Option<Rc<HttpNewService>>>>,
     factory_ref: Rc<RefCell<Option<ResourceFactory>>>,
+    decoding: Option<PathDecoding>,
 }
 
 impl Resource {
@@ -71,6 +72,7 @@ impl Resource {
             guards: Vec::new(),
             data: None,
             default: Rc::new(RefCell::new(None)),
+            decoding: None,
         }
     }
 }
@@ -121,6 +123,33 @@ where
         self
     }
 
+    /// Override percent-decoding policy of resource parameters.
+    ///
+    /// Policy applies if application decodes path after matching, see
+    /// `App::path_decoding()`.
+    ///
+    /// ```rust
+    /// use kayrx::web::{self, dev::{EncodedSlash, PathDecoding}, App, HttpResponse};
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .path_decoding(PathDecoding::new().decode_after_match())
+    ///         .service(
+    ///             web::resource("/objects/{key}")
+    ///                 .path_decoding(
+    ///                     PathDecoding::new()
+    ///                         .decode_after_match()
+    ///                         .encoded_slash(EncodedSlash::Decode),
+    ///                 )
+    ///                 .route(web::get().to(|| HttpResponse::Ok())),
+    ///         );
+    /// }
+    /// ```
+    pub fn path_decoding(mut self, decoding: PathDecoding) -> Self {
+        self.decoding = Some(decoding);
+        self
+    }
+
     pub(crate) fn add_guards(mut self, guards: Vec<Box<dyn Guard>>) -> Self {
         self.guards.extend(guards);
         self
@@ -274,6 +303,7 @@ where
             default: self.default,
             data: self.data,
             factory_ref: self.factory_ref,
+            decoding: self.decoding,
         }
     }
 
@@ -336,6 +366,7 @@ where
             default: self.default,
             data: self.data,
             factory_ref: self.factory_ref,
+            decoding: self.decoding,
         }
     }
 
@@ -388,9 +419,8 @@ where
         if let Some(ref name) = self.name {
             *rdef.name_mut() = name.clone();
         }
-        // custom app data storage
-        if let Some(ref mut ext) = self.data {
-            config.set_service_data(ext);
+        if let Some(decoding) = self.decoding {
+            rdef.set_decoding(decoding);
         }
         config.register_service(rdef, guards, self, None)
     }
@@ -504,7 +534,7 @@ impl Future for CreateResourceService {
             Poll::Ready(Ok(ResourceService {
                 routes,
                 data: self.data.clone(),
-                default: self.default.take(),
+                default: self.default.take().map(|srv| Rc::new(RefCell::new(srv))),
             }))
         } else {
             Poll::Pending
@@ -515,7 +545,7 @@ impl Future for CreateResourceService {
 pub struct ResourceService {
     routes: Vec<RouteService>,
     data: Option<Rc<Extensions>>,
-    default: Option<HttpService>,
+    default: Option<Rc<RefCell<HttpService>>>,
 }
 
 impl Service for ResourceService {
@@ -532,16 +562,50 @@ impl Service for ResourceService {
     }
 
     fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
+        // routes with async guards are checked after sync guards,
+        // up to the first matched route without async guards
+        let mut candidates = Vec::new();
         for route in self.routes.iter_mut() {
             if route.check(&mut req) {
-                if let Some(ref data) = self.data {
-                    req.set_data_container(data.clone());
+                if !route.is_async() && candidates.is_empty() {
+                    if let Some(ref data) = self.data {
+                        req.add_data_container(data.clone());
+                    }
+                    return Either::Right(route.call(req));
+                }
+                candidates.push(route.candidate());
+                if !route.is_async() {
+                    break;
                 }
-                return Either::Right(route.call(req));
             }
         }
-        if let Some(ref mut default) = self.default {
-            Either::Right(default.call(req))
+        if !candidates.is_empty() {
+            let data = self.data.clone();
+            let default = self.default.clone();
+            return Either::Right(
+                async move {
+                    match RouteCandidate::call_first(candidates, req, data).await {
+                        Ok(res) => res,
+                        Err(req) => {
+                            if let Some(default) = default {
+                                let fut = default.borrow_mut().call(req);
+                                fut.await
+                            } else {
+                                let req = req.into_parts().0;
+                                Ok(ServiceResponse::new(
+                                    req,
+                                    Response::MethodNotAllowed().finish(),
+                                ))
+                            }
+                        }
+                    }
+                }
+                .boxed_local(),
+            );
+        }
+
+        if let Some(ref default) = self.default {
+            Either::Right(default.borrow_mut().call(req))
         } else {
             let req = req.into_parts().0;
             Either::Left(ok(ServiceResponse::new(
@@ -1,3 +1,4 @@
+//! `Responder` trait and its implementations
 use std::convert::TryFrom;
 use std::future::Future;
 use std::marker::PhantomData;
@@ -5,17 +6,26 @@ use std::pin::Pin;
 use std::task::{Context, Poll};
 
 use crate::http::error::InternalError;
+use crate::http::body::Body;
+use crate::http::header::{
+    Charset, ContentDisposition, DispositionParam, DispositionType, ExtendedValue,
+    LOCATION,
+};
 use crate::http::{
     header::IntoHeaderValue, Response, ResponseBuilder, HeaderMap, HeaderName, StatusCode,
 };
 use crate::http::error::{Error, HttpError};
 use bytes::{Bytes, BytesMut};
+use futures_core::Stream;
 use futures_util::future::{err, ok, Either as EitherFuture, Ready};
 use futures_util::ready;
 use pin_project::{pin_project, project};
 
+use crate::web::error::UrlGenerationError;
 use crate::web::request::HttpRequest;
 
+pub use crate::web::types::JsonLines;
+
 /// Trait implemented by types that can be converted to a http response.
 ///
 /// Types that implement this trait can be used as the return type of a handler.
@@ -332,6 +342,316 @@ impl<T: Responder> Future for CustomResponderFut<T> {
     }
 }
 
+/// File download responder.
+///
+/// Sets `Content-Disposition` header for the body, so browsers save it
+/// as a file instead of displaying it. Non-ASCII filenames are encoded
+/// according to RFC 5987 (`filename*=UTF-8''...`), with an ASCII
+/// `filename` fallback for older clients. Content type is inferred from
+/// the filename extension unless set explicitly.
+///
+/// ```rust
+/// use kayrx::web::{self, App, Attachment};
+///
+/// async fn report() -> Attachment<Vec<u8>> {
+///     let pdf: Vec<u8> = Vec::new();
+///     Attachment::new(pdf).filename("отчёт 2020.pdf")
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/report").route(web::get().to(report))
+///     );
+/// }
+/// ```
+pub struct Attachment<B> {
+    body: B,
+    filename: Option<String>,
+    content_type: Option<mime::Mime>,
+    inline: bool,
+}
+
+impl<B: Into<Body>> Attachment<B> {
+    /// Create attachment responder for the body
+    pub fn new(body: B) -> Self {
+        Attachment {
+            body,
+            filename: None,
+            content_type: None,
+            inline: false,
+        }
+    }
+
+    /// Set filename suggested to the client
+    pub fn filename<T: Into<String>>(mut self, filename: T) -> Self {
+        self.filename = Some(filename.into());
+        self
+    }
+
+    /// Set content type. By default content type is guessed from
+    /// the filename, or `application/octet-stream` is used.
+    pub fn content_type(mut self, content_type: mime::Mime) -> Self {
+        self.content_type = Some(content_type);
+        self
+    }
+
+    /// Use `inline` disposition, so the browser may display content
+    /// instead of downloading it. By default `attachment` disposition is used.
+    pub fn inline(mut self, inline: bool) -> Self {
+        self.inline = inline;
+        self
+    }
+
+    fn content_disposition(&self) -> ContentDisposition {
+        let mut parameters = Vec::new();
+        if let Some(ref filename) = self.filename {
+            // path separators and control characters are never sent to the client
+            let filename: String = filename
+                .chars()
+                .map(|c| match c {
+                    '/' | '\\' => '_',
+                    c if c.is_control() => '_',
+                    c => c,
+                })
+                .collect();
+
+            let fallback: String = filename
+                .chars()
+                .map(|c| if c.is_ascii() { c } else { '_' })
+                .collect();
+            parameters.push(DispositionParam::Filename(fallback));
+            if !filename.is_ascii() {
+                parameters.push(DispositionParam::FilenameExt(ExtendedValue {
+                    charset: Charset::Ext(String::from("UTF-8")),
+                    language_tag: None,
+                    value: filename.into_bytes(),
+                }));
+            }
+        }
+        ContentDisposition {
+            disposition: if self.inline {
+                DispositionType::Inline
+            } else {
+                DispositionType::Attachment
+            },
+            parameters,
+        }
+    }
+}
+
+impl<B: Into<Body>> Responder for Attachment<B> {
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        let cd = self.content_disposition();
+        let ct = match self.content_type {
+            Some(ct) => ct,
+            None => self
+                .filename
+                .as_ref()
+                .map(|name| mime_guess::from_path(name).first_or_octet_stream())
+                .unwrap_or(mime::APPLICATION_OCTET_STREAM),
+        };
+
+        ok(Response::build(StatusCode::OK)
+            .content_type(ct.to_string())
+            .set(cd)
+            .body(self.body))
+    }
+}
+
+/// Streaming body responder.
+///
+/// Response body is produced by the stream and sent to the client with
+/// chunked transfer encoding. Stream is polled only while connection
+/// write buffer has room, so slow clients pause the producer instead of
+/// buffering whole response in memory.
+///
+/// ```rust
+/// use bytes::Bytes;
+/// use futures::stream;
+/// use kayrx::web::{self, App, Streaming};
+///
+/// async fn numbers() -> Streaming<impl futures::Stream<Item = Result<Bytes, std::io::Error>>> {
+///     Streaming::new(stream::iter(
+///         (0..1000).map(|i| Ok(Bytes::from(format!("{}\n", i)))),
+///     ))
+///     .content_type("text/plain")
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(web::resource("/numbers").to(numbers));
+/// }
+/// ```
+pub struct Streaming<S> {
+    stream: S,
+    status: StatusCode,
+    content_type: Option<String>,
+}
+
+impl<S, E> Streaming<S>
+where
+    S: Stream<Item = Result<Bytes, E>> + 'static,
+    E: Into<Error> + 'static,
+{
+    /// Create streaming responder
+    pub fn new(stream: S) -> Self {
+        Streaming {
+            stream,
+            status: StatusCode::OK,
+            content_type: None,
+        }
+    }
+
+    /// Set response status code. By default `200 OK` is used.
+    pub fn status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Set content type. By default `application/octet-stream` is used.
+    pub fn content_type<T: Into<String>>(mut self, content_type: T) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+}
+
+impl<S, E> Responder for Streaming<S>
+where
+    S: Stream<Item = Result<Bytes, E>> + 'static,
+    E: Into<Error> + 'static,
+{
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        let content_type = self
+            .content_type
+            .unwrap_or_else(|| mime::APPLICATION_OCTET_STREAM.to_string());
+
+        ok(Response::build(self.status)
+            .content_type(content_type)
+            .streaming(self.stream))
+    }
+}
+
+/// Redirect responder.
+///
+/// Relative targets are resolved against the current request path and
+/// `Location` header contains path-absolute URL, `Host` header of the
+/// request is never used. Absolute targets are sent as is.
+///
+/// ```rust
+/// use kayrx::web::{self, App, Redirect};
+///
+/// async fn old_index() -> Redirect {
+///     Redirect::permanent("/index.html")
+/// }
+///
+/// async fn login() -> Redirect {
+///     // `/account/login?next=/` redirects to `/account/form?next=/`
+///     Redirect::see_other("form").preserve_query(true)
+/// }
+///
+/// fn main() {
+///     let app = App::new()
+///         .service(web::resource("/").to(old_index))
+///         .service(web::resource("/account/login").to(login));
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Redirect {
+    target: String,
+    status: StatusCode,
+    preserve_query: bool,
+}
+
+impl Redirect {
+    /// Temporary redirect, *307 Temporary Redirect* response.
+    ///
+    /// Client repeats request with the same method and body.
+    pub fn to<T: Into<String>>(target: T) -> Self {
+        Redirect::with_status(target, StatusCode::TEMPORARY_REDIRECT)
+    }
+
+    /// Permanent redirect, *308 Permanent Redirect* response.
+    pub fn permanent<T: Into<String>>(target: T) -> Self {
+        Redirect::with_status(target, StatusCode::PERMANENT_REDIRECT)
+    }
+
+    /// Redirect after form submission, *303 See Other* response.
+    ///
+    /// Client follows redirect with `GET` request.
+    pub fn see_other<T: Into<String>>(target: T) -> Self {
+        Redirect::with_status(target, StatusCode::SEE_OTHER)
+    }
+
+    /// Redirect with custom status code, i.e. *302 Found* or
+    /// *301 Moved Permanently*.
+    pub fn with_status<T: Into<String>>(target: T, status: StatusCode) -> Self {
+        Redirect {
+            target: target.into(),
+            status,
+            preserve_query: false,
+        }
+    }
+
+    /// Copy query string of the current request to the target.
+    ///
+    /// Query is not copied if target has its own query. By default
+    /// query is not preserved.
+    pub fn preserve_query(mut self, preserve: bool) -> Self {
+        self.preserve_query = preserve;
+        self
+    }
+
+    fn location(&self, req: &HttpRequest) -> Result<String, url::ParseError> {
+        // relative targets are resolved against placeholder origin,
+        // only path of the result is used
+        const BASE: &str = "http://redirect.invalid";
+
+        let (mut location, relative) = match url::Url::parse(&self.target) {
+            Ok(url) => (url, false),
+            Err(url::ParseError::RelativeUrlWithoutBase) => {
+                let base = url::Url::parse(BASE)?.join(req.path())?;
+                (base.join(&self.target)?, true)
+            }
+            Err(e) => return Err(e),
+        };
+
+        if self.preserve_query && location.query().is_none() {
+            let query = req.query_string();
+            if !query.is_empty() {
+                location.set_query(Some(query));
+            }
+        }
+
+        if !relative {
+            Ok(location.into_string())
+        } else if location.host_str() == url::Url::parse(BASE)?.host_str() {
+            Ok(location[url::Position::BeforePath..].to_owned())
+        } else {
+            // network-path reference, i.e. `//example.com/`
+            Ok(format!("//{}", &location[url::Position::BeforeUsername..]))
+        }
+    }
+}
+
+impl Responder for Redirect {
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        match self.location(req) {
+            Ok(location) => ok(Response::build(self.status)
+                .header(LOCATION, location)
+                .finish()),
+            Err(e) => err(UrlGenerationError::ParseError(e).into()),
+        }
+    }
+}
+
 /// Combines two different responder types into a single type
 ///
 /// ```rust
@@ -3,11 +3,24 @@ use std::rc::Rc;
 
 use crate::router::ResourceDef;
 use fxhash::FxHashMap;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
 use url::Url;
 
 use crate::web::error::UrlGenerationError;
 use crate::web::request::HttpRequest;
 
+/// Characters of the path elements that would change structure of the url
+const ELEMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
 #[derive(Clone, Debug)]
 pub struct ResourceMap {
     root: ResourceDef,
@@ -61,7 +74,9 @@ impl ResourceMap {
         I: AsRef<str>,
     {
         let mut path = String::new();
-        let mut elements = elements.into_iter();
+        let mut elements = elements
+            .into_iter()
+            .map(|el| utf8_percent_encode(el.as_ref(), ELEMENT).to_string());
 
         if self.patterns_for(name, &mut path, &mut elements)?.is_some() {
             if path.starts_with('/') {
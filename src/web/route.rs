@@ -1,15 +1,19 @@
+use std::cell::{Cell, RefCell};
 use std::future::Future;
 use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{Context, Poll};
 
-use crate::http::{Method, error::Error, Response as HttpResponse};
+use crate::http::header::RETRY_AFTER;
+use crate::http::{Extensions, Method, error::Error, Response as HttpResponse};
+use crate::krse::sync::Semaphore;
 use crate::service::{Service, ServiceFactory};
-use futures_util::future::{ready, FutureExt, LocalBoxFuture};
+use futures_util::future::{ok, ready, FutureExt, LocalBoxFuture};
 
 use crate::web::extract::FromRequest;
-use crate::web::guard::{self, Guard};
+use crate::web::guard::{self, AsyncGuard, Guard};
 use crate::web::handler::{Extract, Factory, Handler};
+use crate::web::request::HttpRequest;
 use crate::web::responder::Responder;
 use crate::web::service::{ServiceRequest, ServiceResponse};
 
@@ -41,6 +45,8 @@ type BoxedRouteNewService<Req, Res> = Box<
 pub struct Route {
     service: BoxedRouteNewService<ServiceRequest, ServiceResponse>,
     guards: Rc<Vec<Box<dyn Guard>>>,
+    async_guards: Rc<Vec<Box<dyn AsyncGuard>>>,
+    limit: Option<(usize, usize)>,
 }
 
 impl Route {
@@ -51,6 +57,8 @@ impl Route {
                 ready(HttpResponse::NotFound())
             })))),
             guards: Rc::new(Vec::new()),
+            async_guards: Rc::new(Vec::new()),
+            limit: None,
         }
     }
 
@@ -72,6 +80,8 @@ impl ServiceFactory for Route {
         CreateRouteService {
             fut: self.service.new_service(()),
             guards: self.guards.clone(),
+            async_guards: self.async_guards.clone(),
+            limit: self.limit,
         }
     }
 }
@@ -86,6 +96,8 @@ pub struct CreateRouteService {
     #[pin]
     fut: RouteFuture,
     guards: Rc<Vec<Box<dyn Guard>>>,
+    async_guards: Rc<Vec<Box<dyn AsyncGuard>>>,
+    limit: Option<(usize, usize)>,
 }
 
 impl Future for CreateRouteService {
@@ -95,18 +107,36 @@ impl Future for CreateRouteService {
         let this = self.project();
 
         match this.fut.poll(cx)? {
-            Poll::Ready(service) => Poll::Ready(Ok(RouteService {
-                service,
-                guards: this.guards.clone(),
-            })),
+            Poll::Ready(service) => {
+                let service: BoxedRouteService<_, _> = if let Some((permits, queue)) =
+                    *this.limit
+                {
+                    Box::new(LimitedRouteService {
+                        service: Rc::new(RefCell::new(service)),
+                        limit: Rc::new(ConcurrencyLimit {
+                            semaphore: Semaphore::new(permits),
+                            queue,
+                            queued: Cell::new(0),
+                        }),
+                    })
+                } else {
+                    service
+                };
+                Poll::Ready(Ok(RouteService {
+                    service: Rc::new(RefCell::new(service)),
+                    guards: this.guards.clone(),
+                    async_guards: this.async_guards.clone(),
+                }))
+            }
             Poll::Pending => Poll::Pending,
         }
     }
 }
 
 pub struct RouteService {
-    service: BoxedRouteService<ServiceRequest, ServiceResponse>,
+    service: Rc<RefCell<BoxedRouteService<ServiceRequest, ServiceResponse>>>,
     guards: Rc<Vec<Box<dyn Guard>>>,
+    async_guards: Rc<Vec<Box<dyn AsyncGuard>>>,
 }
 
 impl RouteService {
@@ -118,6 +148,57 @@ impl RouteService {
         }
         true
     }
+
+    /// Check if route has async guards
+    pub(crate) fn is_async(&self) -> bool {
+        !self.async_guards.is_empty()
+    }
+
+    /// Route that passed sync guards, async guards are checked later
+    pub(crate) fn candidate(&self) -> RouteCandidate {
+        RouteCandidate {
+            service: self.service.clone(),
+            guards: self.async_guards.clone(),
+        }
+    }
+}
+
+/// Route that passed sync guards
+pub(crate) struct RouteCandidate {
+    service: Rc<RefCell<BoxedRouteService<ServiceRequest, ServiceResponse>>>,
+    guards: Rc<Vec<Box<dyn AsyncGuard>>>,
+}
+
+impl RouteCandidate {
+    async fn check(&self, req: &HttpRequest) -> bool {
+        for f in self.guards.iter() {
+            if !f.check(req).await {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Select first candidate that passes async guards.
+    ///
+    /// Request is returned back if none of the candidates matched.
+    pub(crate) async fn call_first(
+        candidates: Vec<RouteCandidate>,
+        req: ServiceRequest,
+        data: Option<Rc<Extensions>>,
+    ) -> Result<Result<ServiceResponse, Error>, ServiceRequest> {
+        let mut req = req;
+        for candidate in candidates {
+            if candidate.check(req.request()).await {
+                if let Some(data) = data {
+                    req.add_data_container(data);
+                }
+                let fut = candidate.service.borrow_mut().call(req);
+                return Ok(fut.await);
+            }
+        }
+        Err(req)
+    }
 }
 
 impl Service for RouteService {
@@ -127,11 +208,11 @@ impl Service for RouteService {
     type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
 
     fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        self.service.poll_ready(cx)
+        self.service.borrow_mut().poll_ready(cx)
     }
 
     fn call(&mut self, req: ServiceRequest) -> Self::Future {
-        self.service.call(req).boxed_local()
+        self.service.borrow_mut().call(req).boxed_local()
     }
 }
 
@@ -175,6 +256,54 @@ impl Route {
         self
     }
 
+    /// Add async guard to the route.
+    ///
+    /// Async guards are checked after all sync guards of the route passed.
+    /// If async guard rejects the request, the next matching route of the
+    /// resource is tried, as with sync guards.
+    ///
+    /// ```rust
+    /// # use kayrx::web::{self, guard, App, HttpResponse};
+    /// # fn main() {
+    /// App::new().service(web::resource("/path").route(
+    ///     web::get()
+    ///         .async_guard(guard::fn_async_guard(|req| {
+    ///             let token = req.headers().get("x-token").cloned();
+    ///             async move { token.is_some() }
+    ///         }))
+    ///         .to(|| HttpResponse::Ok()))
+    /// );
+    /// # }
+    /// ```
+    pub fn async_guard<F: AsyncGuard + 'static>(mut self, f: F) -> Self {
+        Rc::get_mut(&mut self.async_guards)
+            .unwrap()
+            .push(Box::new(f));
+        self
+    }
+
+    /// Limit number of concurrently processed requests.
+    ///
+    /// At most `n` requests are handled by the route at the same time,
+    /// up to `queue` additional requests wait for a free slot in FIFO order.
+    /// If the queue is full, *503 Service Unavailable* response with
+    /// `Retry-After` header is returned. Limit is enforced per worker thread.
+    ///
+    /// ```rust
+    /// # use kayrx::web::{self, App, HttpResponse};
+    /// # fn main() {
+    /// App::new().service(web::resource("/report").route(
+    ///     web::get()
+    ///         .concurrency_limit(2, 10)
+    ///         .to(|| HttpResponse::Ok()))
+    /// );
+    /// # }
+    /// ```
+    pub fn concurrency_limit(mut self, n: usize, queue: usize) -> Self {
+        self.limit = Some((n, queue));
+        self
+    }
+
     /// Set handler function, use request extractors for parameters.
     ///
     /// ```rust
@@ -237,6 +366,92 @@ impl Route {
     }
 }
 
+struct ConcurrencyLimit {
+    semaphore: Semaphore,
+    queue: usize,
+    queued: Cell<usize>,
+}
+
+/// Returns permit to the semaphore on drop
+struct Permit(Rc<ConcurrencyLimit>);
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.0.semaphore.add_permits(1);
+    }
+}
+
+/// Tracks number of waiting requests
+struct Queued(Rc<ConcurrencyLimit>);
+
+impl Queued {
+    fn new(limit: Rc<ConcurrencyLimit>) -> Self {
+        limit.queued.set(limit.queued.get() + 1);
+        Queued(limit)
+    }
+}
+
+impl Drop for Queued {
+    fn drop(&mut self) {
+        self.0.queued.set(self.0.queued.get() - 1);
+    }
+}
+
+struct LimitedRouteService {
+    service: Rc<RefCell<BoxedRouteService<ServiceRequest, ServiceResponse>>>,
+    limit: Rc<ConcurrencyLimit>,
+}
+
+impl Service for LimitedRouteService {
+    type Request = ServiceRequest;
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.borrow_mut().poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        if let Ok(permit) = self.limit.semaphore.try_acquire() {
+            permit.forget();
+            let permit = Permit(self.limit.clone());
+            let fut = self.service.borrow_mut().call(req);
+            return async move {
+                let res = fut.await;
+                drop(permit);
+                res
+            }
+            .boxed_local();
+        }
+
+        if self.limit.queued.get() >= self.limit.queue {
+            return ok(req.into_response(
+                HttpResponse::ServiceUnavailable()
+                    .header(RETRY_AFTER, "1")
+                    .finish(),
+            ))
+            .boxed_local();
+        }
+
+        let queued = Queued::new(self.limit.clone());
+        let limit = self.limit.clone();
+        let service = self.service.clone();
+
+        async move {
+            limit.semaphore.acquire().await.forget();
+            drop(queued);
+            let permit = Permit(limit);
+
+            let fut = service.borrow_mut().call(req);
+            let res = fut.await;
+            drop(permit);
+            res
+        }
+        .boxed_local()
+    }
+}
+
 struct RouteNewService<T>
 where
     T: ServiceFactory<Request = ServiceRequest, Error = (Error, ServiceRequest)>,
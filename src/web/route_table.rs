@@ -0,0 +1,289 @@
+//! Compile-time route table, generated by `routes!` macro
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use futures_util::future::{ok, Either, FutureExt, LocalBoxFuture, Ready};
+
+use crate::http::Response;
+use crate::router::ResourceDef;
+use crate::service::boxed::{BoxService, BoxServiceFactory};
+use crate::service::{Service, ServiceFactory};
+use crate::web::config::AppService;
+use crate::web::error::Error;
+use crate::web::route::{Route, RouteCandidate, RouteService};
+use crate::web::service::{HttpServiceFactory, ServiceRequest, ServiceResponse};
+
+type HttpService = BoxService<ServiceRequest, ServiceResponse, Error>;
+type HttpNewService = BoxServiceFactory<(), ServiceRequest, ServiceResponse, Error, ()>;
+
+/// Hash function of the static routes table.
+///
+/// `routes!` macro uses the same function to find seed without collisions.
+#[doc(hidden)]
+pub fn hash(seed: u64, path: &str) -> u64 {
+    let mut h = 0xcbf2_9ce4_8422_2325 ^ seed;
+    for b in path.bytes() {
+        h ^= u64::from(b);
+        h = h.wrapping_mul(0x0100_0000_01b3);
+    }
+    h
+}
+
+/// Routing table built at compile time by `routes!` macro.
+///
+/// Paths without parameters are dispatched with perfect hash table, so
+/// lookup cost does not depend on the number of routes. Paths with
+/// parameters are grouped by first path segment, only group of the
+/// request's first segment and paths that start with parameter are
+/// matched. Static segments take precedence over parameters, within a
+/// group paths are matched in declaration order.
+///
+/// Table is registered as a catch-all service, requests that do not match
+/// any path are passed to the default service, so table should be
+/// registered after all other services.
+///
+/// ```rust
+/// use kayrx::web::{self, routes, App, HttpResponse};
+///
+/// async fn index() -> HttpResponse {
+///     HttpResponse::Ok().finish()
+/// }
+///
+/// async fn user(path: web::types::Path<String>) -> String {
+///     format!("user {}", path)
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(routes! {
+///         GET "/" => index,
+///         GET "/users/{name}" => user,
+///         POST "/users/{name}" => user,
+///     });
+/// }
+/// ```
+pub struct RouteTable {
+    inner: Rc<Inner>,
+    default: Option<Rc<HttpNewService>>,
+}
+
+struct Inner {
+    seed: u64,
+    slots: &'static [u16],
+    statics: Vec<(&'static str, Vec<Route>)>,
+    groups: &'static [(&'static str, usize, usize)],
+    dynamics: Vec<(ResourceDef, Vec<Route>)>,
+}
+
+impl RouteTable {
+    /// Create table from parts generated by `routes!` macro.
+    ///
+    /// `slots` maps hash slot to index of static path plus one, `groups`
+    /// are sorted first segments with ranges of dynamic paths, paths that
+    /// start with parameter follow the last group.
+    #[doc(hidden)]
+    pub fn from_parts(
+        seed: u64,
+        slots: &'static [u16],
+        statics: Vec<(&'static str, Vec<Route>)>,
+        groups: &'static [(&'static str, usize, usize)],
+        dynamics: Vec<(&'static str, Vec<Route>)>,
+    ) -> Self {
+        debug_assert!(slots.len().is_power_of_two());
+
+        RouteTable {
+            inner: Rc::new(Inner {
+                seed,
+                slots,
+                statics,
+                groups,
+                dynamics: dynamics
+                    .into_iter()
+                    .map(|(path, routes)| (ResourceDef::new(path), routes))
+                    .collect(),
+            }),
+            default: None,
+        }
+    }
+
+    /// Number of paths in the table
+    pub fn len(&self) -> usize {
+        self.inner.statics.len() + self.inner.dynamics.len()
+    }
+
+    /// Check if table is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Inner {
+    fn find_static(&self, path: &str) -> Option<usize> {
+        let slot = hash(self.seed, path) as usize & (self.slots.len() - 1);
+        match self.slots[slot] {
+            0 => None,
+            idx => {
+                let idx = idx as usize - 1;
+                if self.statics[idx].0 == path {
+                    Some(idx)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    fn dynamic_range(&self, path: &str) -> (usize, usize) {
+        let segment = path.trim_start_matches('/').split('/').next().unwrap_or("");
+        match self.groups.binary_search_by(|g| g.0.cmp(segment)) {
+            Ok(idx) => (self.groups[idx].1, self.groups[idx].2),
+            Err(_) => (0, 0),
+        }
+    }
+
+    fn wildcards(&self) -> usize {
+        self.groups.last().map(|g| g.2).unwrap_or(0)
+    }
+}
+
+impl HttpServiceFactory for RouteTable {
+    fn register(mut self, config: &mut AppService) {
+        self.default = Some(config.default_service());
+        config.register_service(ResourceDef::root_prefix(""), None, self, None)
+    }
+}
+
+impl ServiceFactory for RouteTable {
+    type Config = ();
+    type Request = ServiceRequest;
+    type Response = ServiceResponse;
+    type Error = Error;
+    type InitError = ();
+    type Service = RouteTableService;
+    type Future = LocalBoxFuture<'static, Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        let inner = self.inner.clone();
+        let default = self.default.as_ref().map(|f| f.new_service(()));
+
+        async move {
+            let mut statics = Vec::with_capacity(inner.statics.len());
+            for (_, routes) in &inner.statics {
+                statics.push(new_routes(routes).await?);
+            }
+            let mut dynamics = Vec::with_capacity(inner.dynamics.len());
+            for (_, routes) in &inner.dynamics {
+                dynamics.push(new_routes(routes).await?);
+            }
+            let default = match default {
+                Some(fut) => Some(fut.await?),
+                None => None,
+            };
+
+            Ok(RouteTableService {
+                inner,
+                statics,
+                dynamics,
+                default,
+            })
+        }
+        .boxed_local()
+    }
+}
+
+async fn new_routes(routes: &[Route]) -> Result<Vec<RouteService>, ()> {
+    let mut services = Vec::with_capacity(routes.len());
+    for route in routes {
+        services.push(route.new_service(()).await?);
+    }
+    Ok(services)
+}
+
+/// Route table service
+pub struct RouteTableService {
+    inner: Rc<Inner>,
+    statics: Vec<Vec<RouteService>>,
+    dynamics: Vec<Vec<RouteService>>,
+    default: Option<HttpService>,
+}
+
+impl RouteTableService {
+    fn find(&self, req: &mut ServiceRequest) -> Option<Result<usize, usize>> {
+        let path = req.match_info().path();
+        if let Some(idx) = self.inner.find_static(path) {
+            let len = path.len();
+            req.match_info_mut().skip(len as u16);
+            return Some(Ok(idx));
+        }
+
+        let (start, end) = self.inner.dynamic_range(path);
+        let wildcards = self.inner.wildcards();
+        (start..end)
+            .chain(wildcards..self.inner.dynamics.len())
+            .find(|idx| self.inner.dynamics[*idx].0.match_path(req.match_info_mut()))
+            .map(Err)
+    }
+}
+
+impl Service for RouteTableService {
+    type Request = ServiceRequest;
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = Either<
+        Ready<Result<ServiceResponse, Error>>,
+        LocalBoxFuture<'static, Result<ServiceResponse, Error>>,
+    >;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
+        let routes = match self.find(&mut req) {
+            Some(Ok(idx)) => &mut self.statics[idx],
+            Some(Err(idx)) => &mut self.dynamics[idx],
+            None => {
+                return if let Some(ref mut default) = self.default {
+                    Either::Right(default.call(req))
+                } else {
+                    let req = req.into_parts().0;
+                    Either::Left(ok(ServiceResponse::new(
+                        req,
+                        Response::NotFound().finish(),
+                    )))
+                };
+            }
+        };
+
+        let mut candidates = Vec::new();
+        for route in routes.iter_mut() {
+            if route.check(&mut req) {
+                if !route.is_async() && candidates.is_empty() {
+                    return Either::Right(route.call(req));
+                }
+                candidates.push(route.candidate());
+                if !route.is_async() {
+                    break;
+                }
+            }
+        }
+        if !candidates.is_empty() {
+            return Either::Right(
+                async move {
+                    match RouteCandidate::call_first(candidates, req, None).await {
+                        Ok(res) => res,
+                        Err(req) => Ok(ServiceResponse::new(
+                            req.into_parts().0,
+                            Response::MethodNotAllowed().finish(),
+                        )),
+                    }
+                }
+                .boxed_local(),
+            );
+        }
+        let req = req.into_parts().0;
+        Either::Left(ok(ServiceResponse::new(
+            req,
+            Response::MethodNotAllowed().finish(),
+        )))
+    }
+}
@@ -150,6 +150,8 @@ where
     /// Set or override application data.
     ///
     /// This method overrides data stored with [`App::app_data()`](#method.app_data)
+    /// for routes of the scope. Data of enclosing scopes and application
+    /// that is not overridden remains accessible.
     pub fn app_data<U: 'static>(mut self, data: U) -> Self {
         if self.data.is_none() {
             self.data = Some(Extensions::new());
@@ -399,6 +401,34 @@ where
     }
 }
 
+impl<T> Scope<T>
+where
+    T: ServiceFactory<
+            Config = (),
+            Request = ServiceRequest,
+            Response = ServiceResponse,
+            Error = Error,
+            InitError = (),
+        > + 'static,
+    T::Service: 'static,
+    T::Future: 'static,
+    <T::Service as Service>::Future: 'static,
+{
+    /// Erase scope endpoint type
+    pub(crate) fn boxed(self) -> Scope<HttpNewService> {
+        Scope {
+            endpoint: boxed::factory(self.endpoint),
+            rdef: self.rdef,
+            data: self.data,
+            guards: self.guards,
+            services: self.services,
+            default: self.default,
+            external: self.external,
+            factory_ref: self.factory_ref,
+        }
+    }
+}
+
 impl<T> HttpServiceFactory for Scope<T>
 where
     T: ServiceFactory<
@@ -428,10 +458,6 @@ where
             rmap.add(&mut rdef, None);
         }
 
-        // custom app data storage
-        if let Some(ref mut ext) = self.data {
-            config.set_service_data(ext);
-        }
 
         // complete scope pipeline creation
         *self.factory_ref.borrow_mut() = Some(ScopeFactory {
@@ -616,8 +642,11 @@ impl Service for ScopeService {
         });
 
         if let Some((srv, _info)) = res {
+            if let Some(err) = req.match_info().decode_error() {
+                return Either::Right(ok(req.error_response(err)));
+            }
             if let Some(ref data) = self.data {
-                req.set_data_container(data.clone());
+                req.add_data_container(data.clone());
             }
             Either::Left(srv.call(req))
         } else if let Some(ref mut default) = self.default {
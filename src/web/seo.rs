@@ -0,0 +1,463 @@
+//! Robots.txt and sitemap.xml service
+//!
+//! `Seo` service serves `/robots.txt` and a dynamically generated
+//! `/sitemap.xml`. Sitemap entries are produced by a user provided async
+//! stream and are serialized to xml incrementally. If base url of the site
+//! is configured, generated sitemap is cached for a configurable amount of
+//! time, cached copy is also stored gzip compressed for clients that
+//! accept it.
+//!
+//! ```rust
+//! use futures::stream;
+//! use kayrx::web::{self, seo, App};
+//!
+//! fn main() {
+//!     let app = App::new().service(
+//!         seo::Seo::new()
+//!             .base_url("https://example.com")
+//!             .robots("User-agent: *\nDisallow: /admin/\n")
+//!             .sitemap(|| {
+//!                 stream::iter(vec![
+//!                     seo::SitemapUrl::new("/"),
+//!                     seo::SitemapUrl::new("/about").priority(0.8),
+//!                 ])
+//!             }),
+//!     );
+//! }
+//! ```
+use std::cell::RefCell;
+use std::fmt::Write as FmtWrite;
+use std::io::Write;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use bytes::{Bytes, BytesMut};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures_core::Stream;
+use futures_util::future::ok;
+use futures_util::stream::LocalBoxStream;
+use futures_util::StreamExt;
+
+use crate::http::header::{
+    ContentEncoding, ACCEPT_ENCODING, CACHE_CONTROL, CONTENT_ENCODING, VARY,
+};
+use crate::http::{Method, Response};
+use crate::router::ResourceDef;
+use crate::service::fn_service;
+use crate::timer::Instant;
+use crate::web::config::AppService;
+use crate::web::error::Error;
+use crate::web::guard::{self, Guard};
+use crate::web::request::HttpRequest;
+use crate::web::service::{HttpServiceFactory, ServiceRequest};
+
+const SITEMAP_HEADER: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                              <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n";
+const SITEMAP_FOOTER: &str = "</urlset>\n";
+
+/// How frequently the page is likely to change
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangeFreq {
+    Always,
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+    Never,
+}
+
+impl ChangeFreq {
+    fn as_str(self) -> &'static str {
+        match self {
+            ChangeFreq::Always => "always",
+            ChangeFreq::Hourly => "hourly",
+            ChangeFreq::Daily => "daily",
+            ChangeFreq::Weekly => "weekly",
+            ChangeFreq::Monthly => "monthly",
+            ChangeFreq::Yearly => "yearly",
+            ChangeFreq::Never => "never",
+        }
+    }
+}
+
+/// Sitemap entry
+#[derive(Debug, Clone)]
+pub struct SitemapUrl {
+    loc: String,
+    lastmod: Option<String>,
+    changefreq: Option<ChangeFreq>,
+    priority: Option<f32>,
+}
+
+impl SitemapUrl {
+    /// Create sitemap entry.
+    ///
+    /// Relative locations are resolved against configured base url or
+    /// canonical url of the request.
+    pub fn new<T: Into<String>>(loc: T) -> Self {
+        SitemapUrl {
+            loc: loc.into(),
+            lastmod: None,
+            changefreq: None,
+            priority: None,
+        }
+    }
+
+    /// Set date of last modification, in W3C datetime format (`2020-01-31`)
+    pub fn lastmod<T: Into<String>>(mut self, lastmod: T) -> Self {
+        self.lastmod = Some(lastmod.into());
+        self
+    }
+
+    /// Set change frequency
+    pub fn changefreq(mut self, changefreq: ChangeFreq) -> Self {
+        self.changefreq = Some(changefreq);
+        self
+    }
+
+    /// Set priority of the url relative to other urls, from 0.0 to 1.0
+    pub fn priority(mut self, priority: f32) -> Self {
+        self.priority = Some(priority.max(0.0).min(1.0));
+        self
+    }
+
+    fn write_xml(&self, base: &str, buf: &mut String) {
+        buf.push_str("  <url>\n    <loc>");
+        if self.loc.starts_with('/') {
+            escape_xml(base, buf);
+        }
+        escape_xml(&self.loc, buf);
+        buf.push_str("</loc>\n");
+        if let Some(ref lastmod) = self.lastmod {
+            buf.push_str("    <lastmod>");
+            escape_xml(lastmod, buf);
+            buf.push_str("</lastmod>\n");
+        }
+        if let Some(changefreq) = self.changefreq {
+            let _ = writeln!(buf, "    <changefreq>{}</changefreq>", changefreq.as_str());
+        }
+        if let Some(priority) = self.priority {
+            let _ = writeln!(buf, "    <priority>{:.1}</priority>", priority);
+        }
+        buf.push_str("  </url>\n");
+    }
+}
+
+fn escape_xml(s: &str, buf: &mut String) {
+    for ch in s.chars() {
+        match ch {
+            '&' => buf.push_str("&amp;"),
+            '<' => buf.push_str("&lt;"),
+            '>' => buf.push_str("&gt;"),
+            '"' => buf.push_str("&quot;"),
+            '\'' => buf.push_str("&apos;"),
+            _ => buf.push(ch),
+        }
+    }
+}
+
+/// Canonical absolute url for the path.
+///
+/// Scheme and host are resolved from request's connection info.
+pub fn canonical_url(req: &HttpRequest, path: &str) -> String {
+    let info = req.connection_info();
+    format!("{}://{}{}", info.scheme(), info.host(), path)
+}
+
+type SitemapSource = Rc<dyn Fn() -> LocalBoxStream<'static, SitemapUrl>>;
+
+struct Cached {
+    expires: Instant,
+    body: Bytes,
+    gzip: Bytes,
+}
+
+struct Inner {
+    base: Option<String>,
+    robots: String,
+    sitemap: Option<SitemapSource>,
+    ttl: Duration,
+    cache: RefCell<Option<Cached>>,
+}
+
+/// Robots.txt and sitemap.xml service
+pub struct Seo {
+    base: Option<String>,
+    robots: String,
+    sitemap: Option<SitemapSource>,
+    ttl: Duration,
+}
+
+impl Seo {
+    /// Create new service. By default robots.txt allows everything and
+    /// sitemap.xml is not served.
+    pub fn new() -> Self {
+        Seo {
+            base: None,
+            robots: "User-agent: *\nAllow: /\n".to_owned(),
+            sitemap: None,
+            ttl: Duration::from_secs(3600),
+        }
+    }
+
+    /// Set scheme and host of the site, i.e. `https://example.com`.
+    ///
+    /// Base url is used for sitemap urls. If it is not set, urls are built
+    /// from connection info of each request and sitemap is not cached,
+    /// otherwise a request with forged `Host` header could poison the
+    /// cached copy.
+    pub fn base_url<T: Into<String>>(mut self, url: T) -> Self {
+        let url = url.into();
+        self.base = Some(url.trim_end_matches('/').to_owned());
+        self
+    }
+
+    /// Set content of robots.txt.
+    ///
+    /// If sitemap is configured, `Sitemap:` line with canonical sitemap url
+    /// is appended.
+    pub fn robots<T: Into<String>>(mut self, robots: T) -> Self {
+        self.robots = robots.into();
+        self
+    }
+
+    /// Set source of sitemap entries.
+    pub fn sitemap<F, S>(mut self, source: F) -> Self
+    where
+        F: Fn() -> S + 'static,
+        S: Stream<Item = SitemapUrl> + 'static,
+    {
+        self.sitemap = Some(Rc::new(move || source().boxed_local()));
+        self
+    }
+
+    /// Set time to cache generated sitemap. By default sitemap is cached for 1 hour.
+    ///
+    /// Sitemap is cached only if base url is configured.
+    pub fn cache_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+}
+
+impl Default for Seo {
+    fn default() -> Self {
+        Seo::new()
+    }
+}
+
+impl HttpServiceFactory for Seo {
+    fn register(self, config: &mut AppService) {
+        let inner = Rc::new(Inner {
+            base: self.base,
+            robots: self.robots,
+            sitemap: self.sitemap,
+            ttl: self.ttl,
+            cache: RefCell::new(None),
+        });
+
+        let guards = || -> Option<Vec<Box<dyn Guard>>> {
+            Some(vec![Box::new(guard::Any(guard::Get()).or(guard::Head()))])
+        };
+
+        let robots = inner.clone();
+        config.register_service(
+            ResourceDef::new("/robots.txt"),
+            guards(),
+            fn_service(move |req: ServiceRequest| {
+                let res = robots_txt(&req, &robots);
+                ok::<_, Error>(req.into_response(res))
+            }),
+            None,
+        );
+
+        if inner.sitemap.is_some() {
+            config.register_service(
+                ResourceDef::new("/sitemap.xml"),
+                guards(),
+                fn_service(move |req: ServiceRequest| {
+                    let res = sitemap_xml(&req, &inner);
+                    ok::<_, Error>(req.into_response(res))
+                }),
+                None,
+            );
+        }
+    }
+}
+
+fn robots_txt(req: &ServiceRequest, inner: &Inner) -> Response {
+    let mut body = inner.robots.clone();
+    if inner.sitemap.is_some() {
+        if !body.is_empty() && !body.ends_with('\n') {
+            body.push('\n');
+        }
+        let _ = writeln!(body, "Sitemap: {}/sitemap.xml", base_url(req, inner));
+    }
+    Response::Ok()
+        .content_type("text/plain; charset=utf-8")
+        .body(body)
+}
+
+/// Configured base url or scheme and host of the request
+fn base_url(req: &ServiceRequest, inner: &Inner) -> String {
+    match inner.base {
+        Some(ref base) => base.clone(),
+        None => {
+            let info = req.connection_info();
+            format!("{}://{}", info.scheme(), info.host())
+        }
+    }
+}
+
+/// Check if `Accept-Encoding` header value allows gzip, `gzip;q=0` rejects it
+fn accepts_gzip(value: &str) -> bool {
+    let mut wildcard = false;
+    for item in value.split(',') {
+        let mut parts = item.split(';');
+        let name = parts.next().unwrap_or("").trim();
+        let quality = parts
+            .map(|param| param.trim())
+            .find(|param| param.starts_with("q="))
+            .map(|param| param[2..].trim().parse::<f32>().unwrap_or(0.0))
+            .unwrap_or(1.0);
+
+        if name.eq_ignore_ascii_case("gzip") {
+            return quality > 0.0;
+        } else if name == "*" {
+            wildcard = quality > 0.0;
+        }
+    }
+    wildcard
+}
+
+fn sitemap_xml(req: &ServiceRequest, inner: &Rc<Inner>) -> Response {
+    let gzip = req
+        .headers()
+        .get(&ACCEPT_ENCODING)
+        .and_then(|val| val.to_str().ok())
+        .map(accepts_gzip)
+        .unwrap_or(false);
+
+    let mut res = Response::Ok();
+    res.content_type("application/xml; charset=utf-8")
+        .header(VARY, "accept-encoding")
+        .header(CACHE_CONTROL, format!("max-age={}", inner.ttl.as_secs()));
+
+    if let Some(ref cached) = *inner.cache.borrow() {
+        if cached.expires > Instant::now() {
+            if gzip {
+                res.header(CONTENT_ENCODING, ContentEncoding::Gzip.as_str());
+                return res.body(cached.gzip.clone());
+            } else {
+                return res.body(cached.body.clone());
+            }
+        }
+    }
+
+    if *req.method() == Method::HEAD {
+        return res.finish();
+    }
+
+    let base = base_url(req, inner);
+    let source = inner.sitemap.as_ref().unwrap();
+
+    res.streaming(SitemapStream {
+        base,
+        stream: Some((source)()),
+        started: false,
+        capture: BytesMut::new(),
+        inner: inner.clone(),
+    })
+}
+
+/// Stream of sitemap xml chunks, completed document is stored in the cache
+struct SitemapStream {
+    base: String,
+    stream: Option<LocalBoxStream<'static, SitemapUrl>>,
+    started: bool,
+    capture: BytesMut,
+    inner: Rc<Inner>,
+}
+
+impl SitemapStream {
+    fn chunk(&mut self, data: String) -> Bytes {
+        let data = Bytes::from(data);
+        if self.inner.base.is_some() {
+            self.capture.extend_from_slice(&data);
+        }
+        data
+    }
+
+    fn complete(&mut self) {
+        // urls of the sitemap are built from request's host
+        if self.inner.base.is_none() {
+            return;
+        }
+
+        let body = self.capture.split().freeze();
+        let mut enc = GzEncoder::new(Vec::new(), Compression::default());
+        let gzip = enc
+            .write_all(&body)
+            .and_then(|_| enc.finish())
+            .map(Bytes::from);
+
+        match gzip {
+            Ok(gzip) => {
+                *self.inner.cache.borrow_mut() = Some(Cached {
+                    expires: Instant::now() + self.inner.ttl,
+                    body,
+                    gzip,
+                })
+            }
+            Err(e) => log::error!("Can not compress sitemap: {}", e),
+        }
+    }
+}
+
+impl Stream for SitemapStream {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if !this.started {
+            this.started = true;
+            return Poll::Ready(Some(Ok(this.chunk(SITEMAP_HEADER.to_owned()))));
+        }
+
+        let stream = match this.stream {
+            Some(ref mut stream) => stream,
+            None => return Poll::Ready(None),
+        };
+
+        let mut buf = String::new();
+        loop {
+            match Pin::new(&mut *stream).poll_next(cx) {
+                Poll::Ready(Some(url)) => {
+                    url.write_xml(&this.base, &mut buf);
+                    if buf.len() >= 8192 {
+                        break;
+                    }
+                }
+                Poll::Ready(None) => {
+                    this.stream = None;
+                    buf.push_str(SITEMAP_FOOTER);
+                    let chunk = this.chunk(buf);
+                    this.complete();
+                    return Poll::Ready(Some(Ok(chunk)));
+                }
+                Poll::Pending => {
+                    if buf.is_empty() {
+                        return Poll::Pending;
+                    }
+                    break;
+                }
+            }
+        }
+        Poll::Ready(Some(Ok(this.chunk(buf))))
+    }
+}
@@ -1,16 +1,27 @@
+use std::future::Future;
 use std::marker::PhantomData;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use std::{fmt, io, net};
 use net2::TcpBuilder;
+use futures_core::Stream;
 use futures_util::future::ok;
 
 use crate::http::{body::MessageBody, error::Error, HttpService, KeepAlive, Request, Response};
-use crate::server::{Server, ServerBuilder};
+use crate::http::h2::Reason;
+use crate::http::{ConnLimits, H1Timeouts, H2Settings, WriteRate};
+use crate::server::{AcceptGate, Server, ServerBuilder, ShutdownSignal, Supervisor};
 use crate::service::{map_config, IntoServiceFactory, Service, ServiceFactory};
 use crate::http::Protocol;
+use crate::fiber::diagnostics::{self, TlsSummary};
 use crate::service::pipeline_factory;
+use crate::krse::net::TcpStream;
 use crate::secure::tls::ServerConfig as RustlsServerConfig;
+use crate::secure::tls::TlsStream;
+use crate::web::admission::{Admission, AdmissionFactory};
 use crate::web::config::AppConfig;
+use crate::web::info::{ConnectionInfoConfig, TlsInfo};
+use crate::web::server_config::{self, ServerConfig, ServerConfigError};
 
 struct Socket {
     scheme: &'static str,
@@ -22,6 +33,14 @@ struct Config {
     keep_alive: KeepAlive,
     client_timeout: u64,
     client_shutdown: u64,
+    conn_limits: ConnLimits,
+    h2_settings: H2Settings,
+    h1_timeouts: H1Timeouts,
+    write_rate: Option<WriteRate>,
+    write_buffer: Option<(usize, usize)>,
+    read_buffer: Option<(usize, usize)>,
+    admission: Option<Admission>,
+    info: ConnectionInfoConfig,
 }
 
 /// An HTTP Server.
@@ -79,6 +98,14 @@ where
                 keep_alive: KeepAlive::Timeout(5),
                 client_timeout: 5000,
                 client_shutdown: 5000,
+                conn_limits: ConnLimits::default(),
+                h2_settings: H2Settings::default(),
+                h1_timeouts: H1Timeouts::default(),
+                write_rate: None,
+                write_buffer: None,
+                read_buffer: None,
+                admission: None,
+                info: ConnectionInfoConfig::default(),
             })),
             backlog: 1024,
             sockets: Vec::new(),
@@ -87,6 +114,80 @@ where
         }
     }
 
+    /// Create new http server with application factory and configuration.
+    ///
+    /// Configuration is validated, tls certificates are loaded and all
+    /// listeners are bound, so configuration errors are reported at startup.
+    ///
+    /// ```rust,no_run
+    /// use kayrx::web::{self, App, HttpResponse, HttpServer, ServerConfig};
+    ///
+    /// #[kayrx::main]
+    /// async fn main() -> std::io::Result<()> {
+    ///     let cfg: ServerConfig = serde_json::from_str(
+    ///         r#"{"bind": ["127.0.0.1:59090"], "workers": 2}"#,
+    ///     )?;
+    ///
+    ///     HttpServer::from_config(
+    ///         || App::new().service(web::resource("/").to(|| HttpResponse::Ok())),
+    ///         cfg,
+    ///     )
+    ///     .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?
+    ///     .run()
+    ///     .await
+    /// }
+    /// ```
+    pub fn from_config(factory: F, cfg: ServerConfig) -> Result<Self, ServerConfigError> {
+        cfg.validate()?;
+        let tls = match cfg.tls {
+            Some(ref tls) => Some(tls.load()?),
+            None => None,
+        };
+
+        let mut srv = HttpServer::new(factory);
+        if let Some(num) = cfg.workers {
+            srv = srv.workers(num);
+        }
+        if let Some(num) = cfg.backlog {
+            srv = srv.backlog(num);
+        }
+        if let Some(num) = cfg.maxconn {
+            srv = srv.maxconn(num);
+        }
+        if let Some(num) = cfg.maxconnrate {
+            srv = srv.maxconnrate(num);
+        }
+        if let Some(val) = cfg.keep_alive {
+            srv = srv.keep_alive(if val == 0 { KeepAlive::Disabled } else { val.into() });
+        }
+        if let Some(val) = cfg.client_timeout {
+            srv = srv.client_timeout(val);
+        }
+        if let Some(val) = cfg.client_shutdown {
+            srv = srv.client_shutdown(val);
+        }
+        if let Some(sec) = cfg.shutdown_timeout {
+            srv = srv.shutdown_timeout(sec);
+        }
+        if let Some(ref host) = cfg.hostname {
+            srv = srv.server_hostname(host);
+        }
+
+        for addr in &cfg.bind {
+            let addrs = server_config::resolve(addr)?;
+            srv = srv.bind(&addrs[..]).map_err(ServerConfigError::Bind)?;
+        }
+        if let (Some(tls), Some(config)) = (cfg.tls, tls) {
+            for addr in &tls.bind {
+                let addrs = server_config::resolve(addr)?;
+                srv = srv
+                    .bind_rustls(&addrs[..], config.clone())
+                    .map_err(ServerConfigError::Bind)?;
+            }
+        }
+        Ok(srv)
+    }
+
     /// Set number of workers to start.
     ///
     /// By default http server uses number of available logical cpu as threads
@@ -169,6 +270,149 @@ where
         self
     }
 
+    /// Set max lifetime of the connection.
+    ///
+    /// After lifetime connection is closed gracefully, so long-lived
+    /// keep-alive clients reconnect and get rebalanced across instances.
+    /// See [`HttpServiceBuilder::conn_lifetime()`](../http/struct.HttpServiceBuilder.html#method.conn_lifetime).
+    ///
+    /// By default lifetime is not limited.
+    pub fn conn_lifetime(self, dur: Duration) -> Self {
+        self.config.lock().unwrap().conn_limits.lifetime = Some(dur);
+        self
+    }
+
+    /// Set max idle period of the connection.
+    ///
+    /// Connection without in-flight requests for this period is closed.
+    ///
+    /// By default idle period is not limited.
+    pub fn conn_max_idle(self, dur: Duration) -> Self {
+        self.config.lock().unwrap().conn_limits.max_idle = Some(dur);
+        self
+    }
+
+    /// Set jitter of connection lifetime limits.
+    ///
+    /// Limits of each connection are shortened by random part of the
+    /// jitter to prevent synchronized closes. By default jitter is set to 0.1.
+    pub fn conn_jitter(self, jitter: f64) -> Self {
+        self.config.lock().unwrap().conn_limits.jitter = jitter.max(0.0).min(1.0);
+        self
+    }
+
+    /// Set timeout for reading request head.
+    ///
+    /// Client that does not send complete request head within the timeout
+    /// gets 408 (Request Time-out) response and connection is closed.
+    /// See [`HttpServiceBuilder::request_header_timeout()`](../http/struct.HttpServiceBuilder.html#method.request_header_timeout).
+    ///
+    /// By default only the first request is limited by client timeout.
+    pub fn request_header_timeout(self, timeout: Duration) -> Self {
+        self.config.lock().unwrap().h1_timeouts.header = Some(timeout);
+        self
+    }
+
+    /// Set timeout for flushing response data.
+    ///
+    /// Connection is dropped if buffered response data makes no progress
+    /// within the timeout.
+    /// See [`HttpServiceBuilder::response_flush_timeout()`](../http/struct.HttpServiceBuilder.html#method.response_flush_timeout).
+    ///
+    /// By default flush timeout is not set.
+    pub fn response_flush_timeout(self, timeout: Duration) -> Self {
+        self.config.lock().unwrap().h1_timeouts.flush = Some(timeout);
+        self
+    }
+
+    /// Set minimal rate of response writes to the client.
+    ///
+    /// Connection of the client that receives less than `bytes_per_sec` on
+    /// average during `period` while response data waits for it is dropped.
+    /// See [`HttpServiceBuilder::min_write_rate()`](../http/struct.HttpServiceBuilder.html#method.min_write_rate).
+    ///
+    /// By default write rate is not limited.
+    pub fn min_write_rate(self, bytes_per_sec: u64, period: Duration) -> Self {
+        self.config.lock().unwrap().write_rate = Some(WriteRate {
+            bytes_per_sec,
+            period,
+        });
+        self
+    }
+
+    /// Set watermarks of the response write buffer.
+    ///
+    /// Streaming response body is paused when buffered data reaches `high`
+    /// watermark and resumed below `low` watermark.
+    /// See [`HttpServiceBuilder::write_buffer()`](../http/struct.HttpServiceBuilder.html#method.write_buffer).
+    ///
+    /// By default both watermarks are 32Kb.
+    pub fn write_buffer(self, high: usize, low: usize) -> Self {
+        self.config.lock().unwrap().write_buffer = Some((high, std::cmp::min(low, high)));
+        self
+    }
+
+    /// Set min and max size of the socket read chunk.
+    ///
+    /// Read chunk grows under sustained throughput and shrinks when
+    /// connection is idle.
+    /// See [`HttpServiceBuilder::read_buffer()`](../http/struct.HttpServiceBuilder.html#method.read_buffer).
+    ///
+    /// By default min is 4Kb and max is 32Kb.
+    pub fn read_buffer(self, min: usize, max: usize) -> Self {
+        self.config.lock().unwrap().read_buffer = Some((min, std::cmp::max(min, max)));
+        self
+    }
+
+    /// Set max number of concurrent http/2 streams per connection.
+    ///
+    /// Requests over the limit are rejected, so a single client could not
+    /// monopolize the worker. See
+    /// [`HttpServiceBuilder::h2_max_concurrent_streams()`](../http/struct.HttpServiceBuilder.html#method.h2_max_concurrent_streams).
+    ///
+    /// By default number of streams is not limited.
+    pub fn h2_max_concurrent_streams(self, max: u32) -> Self {
+        self.config.lock().unwrap().h2_settings.max_concurrent_streams = Some(max);
+        self
+    }
+
+    /// Set error for http/2 streams over the max concurrent streams limit.
+    ///
+    /// `Reason::REFUSED_STREAM` resets the stream, `Reason::ENHANCE_YOUR_CALM`
+    /// closes the connection. By default `Reason::REFUSED_STREAM` is used.
+    pub fn h2_overflow_reason(self, reason: Reason) -> Self {
+        self.config.lock().unwrap().h2_settings.overflow_reason = reason;
+        self
+    }
+
+    /// Set initial receive window size (in octets) of http/2 streams.
+    ///
+    /// By default window size is 65,535.
+    pub fn h2_initial_window_size(self, size: u32) -> Self {
+        self.config.lock().unwrap().h2_settings.initial_window_size = Some(size);
+        self
+    }
+
+    /// Set initial receive window size (in octets) of http/2 connection.
+    ///
+    /// By default window size is 65,535.
+    pub fn h2_initial_connection_window_size(self, size: u32) -> Self {
+        self.config.lock().unwrap().h2_settings.initial_connection_window_size = Some(size);
+        self
+    }
+
+    /// Set server level admission control.
+    ///
+    /// Bounds the number of requests concurrently processed by each worker,
+    /// see [`Admission`](struct.Admission.html). By default admission control
+    /// is disabled.
+    ///
+    /// This method should be called before `bind()` method call.
+    pub fn admission(self, admission: Admission) -> Self {
+        self.config.lock().unwrap().admission = Some(admission);
+        self
+    }
+
     /// Set server host name.
     ///
     /// Host name is used by application router as a hostname for url generation.
@@ -181,6 +425,17 @@ where
         self
     }
 
+    /// Set scheme and host resolution configuration of `ConnectionInfo`.
+    ///
+    /// By default scheme and host are resolved from forwarding headers,
+    /// `Host` header and server configuration. Check
+    /// [ConnectionInfoConfig](./dev/struct.ConnectionInfoConfig.html)
+    /// documentation for more information.
+    pub fn connection_info(self, cfg: ConnectionInfoConfig) -> Self {
+        self.config.lock().unwrap().info = cfg;
+        self
+    }
+
     /// Stop kayrx system.
     pub fn system_exit(mut self) -> Self {
         self.builder = self.builder.system_exit();
@@ -193,6 +448,14 @@ where
         self
     }
 
+    /// Log diagnostics snapshot line on start.
+    ///
+    /// See [`Runtime::diagnostics()`](../fiber/struct.Runtime.html#method.diagnostics).
+    pub fn startup_banner(mut self) -> Self {
+        self.builder = self.builder.startup_banner();
+        self
+    }
+
     /// Timeout for graceful workers shutdown.
     ///
     /// After receiving a stop signal, workers have this much time to finish
@@ -205,6 +468,95 @@ where
         self
     }
 
+    /// Register async function that is executed once on server start.
+    ///
+    /// Start hooks are executed in registration order, before workers get
+    /// started. Use `App::on_start()` for per worker initialization.
+    pub fn on_start<H, R>(mut self, f: H) -> Self
+    where
+        H: Fn() -> R + 'static,
+        R: Future<Output = ()> + 'static,
+    {
+        self.builder = self.builder.on_start(f);
+        self
+    }
+
+    /// Register async function that is executed once on server stop.
+    ///
+    /// Stop hooks are executed in reverse registration order, after all
+    /// workers and their application stop hooks are completed.
+    pub fn on_stop<H, R>(mut self, f: H) -> Self
+    where
+        H: Fn() -> R + 'static,
+        R: Future<Output = ()> + 'static,
+    {
+        self.builder = self.builder.on_stop(f);
+        self
+    }
+
+    /// Spawn background task attached to the server lifecycle.
+    ///
+    /// Task runs on the server's system thread. On graceful shutdown server
+    /// waits for background tasks, tasks that are still running after
+    /// background timeout get cancelled.
+    ///
+    /// ```rust,no_run
+    /// use kayrx::web::{self, App, HttpResponse, HttpServer};
+    ///
+    /// #[kayrx::main]
+    /// async fn main() -> std::io::Result<()> {
+    ///     let srv = HttpServer::new(
+    ///         || App::new().service(web::resource("/").to(|| HttpResponse::Ok())));
+    ///     let shutdown = srv.shutdown_signal();
+    ///
+    ///     srv.spawn_background(async move {
+    ///             // refresh caches until server stops
+    ///             shutdown.await;
+    ///         })
+    ///         .bind("127.0.0.1:59090")?
+    ///         .run()
+    ///         .await
+    /// }
+    /// ```
+    pub fn spawn_background<T>(mut self, fut: T) -> Self
+    where
+        T: Future<Output = ()> + 'static,
+    {
+        self.builder = self.builder.spawn_background(fut);
+        self
+    }
+
+    /// Timeout for background tasks completion in seconds.
+    ///
+    /// By default background tasks timeout sets to 5 seconds.
+    pub fn background_timeout(mut self, sec: u64) -> Self {
+        self.builder = self.builder.background_timeout(sec);
+        self
+    }
+
+    /// Get future that resolves when server starts shutdown process
+    pub fn shutdown_signal(&self) -> ShutdownSignal {
+        self.builder.shutdown_signal()
+    }
+
+    /// Enable worker supervision.
+    ///
+    /// Worker re-creates its application instances after too many panics in
+    /// request processing. See [`Supervisor`](../server/struct.Supervisor.html)
+    /// for details.
+    pub fn supervisor(mut self, supervisor: Supervisor) -> Self {
+        self.builder = self.builder.supervisor(supervisor);
+        self
+    }
+
+    /// Pause accepting connections while server is overloaded.
+    ///
+    /// See [`AcceptGate`](../server/struct.AcceptGate.html) for details.
+    pub fn accept_gate(mut self, gate: AcceptGate) -> Self {
+        self.builder = self.builder.accept_gate(gate);
+        self
+    }
+
     /// Get addresses of bound sockets.
     pub fn addrs(&self) -> Vec<net::SocketAddr> {
         self.sockets.iter().map(|s| s.addr).collect()
@@ -242,22 +594,104 @@ where
                     false,
                     addr,
                     c.host.clone().unwrap_or_else(|| format!("{}", addr)),
-                );
+                )
+                .with_connection_info(c.info.clone());
 
                 HttpService::build()
                     .keep_alive(c.keep_alive)
                     .client_timeout(c.client_timeout)
+                    .conn_limits(c.conn_limits)
+                    .h2_settings(c.h2_settings)
+                    .h1_timeouts(c.h1_timeouts)
+                    .write_rate(c.write_rate)
+                    .write_buffer_limits(c.write_buffer)
+                    .read_buffer_limits(c.read_buffer)
                     .local_addr(addr)
-                    .finish(map_config(factory(), move |_| cfg.clone()))
+                    .finish(AdmissionFactory::new(
+                        c.admission.clone(),
+                        map_config(factory(), move |_| cfg.clone()),
+                    ))
                     .tcp()
             },
         )?;
         Ok(self)
     }
 
+    /// Accept connections from the stream of tcp connections.
+    ///
+    /// Connections could come from custom acceptors, i.e. transparent
+    /// proxy sockets, userspace tcp stacks or test harnesses. Accepting
+    /// stops when stream ends.
+    ///
+    /// ```rust,no_run
+    /// use std::{io, net, thread};
+    /// use futures::channel::mpsc;
+    /// use kayrx::web::{self, App, HttpResponse, HttpServer};
+    ///
+    /// #[kayrx::main]
+    /// async fn main() -> io::Result<()> {
+    ///     let (tx, rx) = mpsc::unbounded::<io::Result<net::TcpStream>>();
+    ///
+    ///     // custom acceptor
+    ///     let lst = net::TcpListener::bind("127.0.0.1:59090")?;
+    ///     thread::spawn(move || {
+    ///         for conn in lst.incoming() {
+    ///             let _ = tx.unbounded_send(conn);
+    ///         }
+    ///     });
+    ///
+    ///     HttpServer::new(|| App::new().service(web::resource("/").to(|| HttpResponse::Ok())))
+    ///         .listen_from_stream(rx)?
+    ///         .run()
+    ///         .await
+    /// }
+    /// ```
+    pub fn listen_from_stream<St>(mut self, stream: St) -> io::Result<Self>
+    where
+        St: Stream<Item = io::Result<net::TcpStream>> + 'static,
+    {
+        let cfg = self.config.clone();
+        let factory = self.factory.clone();
+        let addr = net::SocketAddr::new(
+            net::IpAddr::V4(net::Ipv4Addr::new(127, 0, 0, 1)),
+            8080,
+        );
+        self.sockets.push(Socket {
+            addr,
+            scheme: "http",
+        });
+
+        let name = format!("kayrx-service-stream-{}", self.sockets.len());
+        self.builder = self.builder.listen_stream(name, addr, stream, move || {
+            let c = cfg.lock().unwrap();
+            let cfg = AppConfig::new(
+                false,
+                addr,
+                c.host.clone().unwrap_or_else(|| format!("{}", addr)),
+            )
+            .with_connection_info(c.info.clone());
+
+            HttpService::build()
+                .keep_alive(c.keep_alive)
+                .client_timeout(c.client_timeout)
+                .conn_limits(c.conn_limits)
+                .h2_settings(c.h2_settings)
+                .h1_timeouts(c.h1_timeouts)
+                .write_rate(c.write_rate)
+                .write_buffer_limits(c.write_buffer)
+                .read_buffer_limits(c.read_buffer)
+                .finish(AdmissionFactory::new(
+                    c.admission.clone(),
+                    map_config(factory(), move |_| cfg.clone()),
+                ))
+                .tcp()
+        })?;
+        Ok(self)
+    }
+
     /// Use listener for accepting incoming tls connection requests
     ///
-    /// This method sets alpn protocols to "h2" and "http/1.1"
+    /// See [`bind_rustls()`](#method.bind_rustls).
     pub fn listen_rustls(
         self,
         lst: net::TcpListener,
@@ -278,6 +712,21 @@ where
             addr,
             scheme: "https",
         });
+        diagnostics::register_tls(
+            addr.to_string(),
+            TlsSummary {
+                alpn_protocols: if config.alpn_protocols.is_empty() {
+                    vec!["h2".to_owned(), "http/1.1".to_owned()]
+                } else {
+                    config
+                        .alpn_protocols
+                        .iter()
+                        .map(|proto| String::from_utf8_lossy(proto).into_owned())
+                        .collect()
+                },
+                versions: config.versions.iter().map(|v| format!("{:?}", v)).collect(),
+            },
+        );
 
         self.builder = self.builder.listen(
             format!("kayrx-service-{}", addr),
@@ -288,12 +737,25 @@ where
                     true,
                     addr,
                     c.host.clone().unwrap_or_else(|| format!("{}", addr)),
-                );
+                )
+                .with_connection_info(c.info.clone());
                 HttpService::build()
                     .keep_alive(c.keep_alive)
                     .client_timeout(c.client_timeout)
+                    .conn_limits(c.conn_limits)
+                    .h2_settings(c.h2_settings)
+                    .h1_timeouts(c.h1_timeouts)
+                    .write_rate(c.write_rate)
+                    .write_buffer_limits(c.write_buffer)
+                    .read_buffer_limits(c.read_buffer)
                     .client_disconnect(c.client_shutdown)
-                    .finish(map_config(factory(), move |_| cfg.clone()))
+                    .on_connect(|io: &TlsStream<TcpStream>| {
+                        TlsInfo::new(io.get_ref().1)
+                    })
+                    .finish(AdmissionFactory::new(
+                        c.admission.clone(),
+                        map_config(factory(), move |_| cfg.clone()),
+                    ))
                     .rustls(config.clone())
             },
         )?;
@@ -347,7 +809,42 @@ where
 
     /// Start listening for incoming tls connections.
     ///
-    /// This method sets alpn protocols to "h2" and "http/1.1"
+    /// Alpn protocols are set to "h2" and "http/1.1" unless they are set in
+    /// the config, negotiated protocol selects http/2 or http/1 dispatcher.
+    /// Use [`SniResolver`](../secure/tls/struct.SniResolver.html) as cert
+    /// resolver of the config to serve per host certificates, wrap it with
+    /// [`CertResolver`](../secure/tls/struct.CertResolver.html) to replace
+    /// certificates without restart. Server name,
+    /// alpn protocol and client certificates of the connection are available
+    /// to handlers as [`TlsInfo`](dev/struct.TlsInfo.html) request extension.
+    ///
+    /// ```rust,no_run
+    /// use std::sync::Arc;
+    /// use kayrx::secure::tls::{rust_tls, SniResolver};
+    /// use kayrx::web::{App, HttpServer};
+    /// # fn load(_: &str) -> (Vec<rust_tls::Certificate>, rust_tls::PrivateKey) { unimplemented!() }
+    ///
+    /// #[kayrx::main]
+    /// async fn main() -> std::io::Result<()> {
+    ///     let mut resolver = SniResolver::new();
+    ///     let (certs, key) = load("example.com");
+    ///     resolver.add("example.com", certs, &key).unwrap();
+    ///     let (certs, key) = load("wildcard.example.org");
+    ///     resolver.add("*.example.org", certs, &key).unwrap();
+    ///
+    ///     // require client certificates
+    ///     let mut roots = rust_tls::RootCertStore::empty();
+    ///     roots.add(&load("ca").0[0]).unwrap();
+    ///     let mut config =
+    ///         rust_tls::ServerConfig::new(rust_tls::AllowAnyAuthenticatedClient::new(roots));
+    ///     config.cert_resolver = Arc::new(resolver);
+    ///
+    ///     HttpServer::new(|| App::new())
+    ///         .bind_rustls("0.0.0.0:443", config)?
+    ///         .run()
+    ///         .await
+    /// }
+    /// ```
     pub fn bind_rustls<A: net::ToSocketAddrs>(
         mut self,
         addr: A,
@@ -362,11 +859,16 @@ where
 
     /// Start listening for unix domain connections on existing listener.
     ///
-    /// This method is available with `uds` feature.
+    /// Listener could be inherited from systemd socket activation.
+    /// Connections are served with http/1.
     pub fn listen_uds(
         mut self,
         lst: std::os::unix::net::UnixListener,
-    ) -> io::Result<Self> {
+    ) -> io::Result<Self>
+    where
+        // queued requests hold application service until admitted
+        S::Service: 'static,
+    {
         use crate::krse::net::UnixStream;
 
         let cfg = self.config.clone();
@@ -388,12 +890,22 @@ where
                 false,
                 socket_addr,
                 c.host.clone().unwrap_or_else(|| format!("{}", socket_addr)),
-            );
+            )
+            .with_connection_info(c.info.clone());
             pipeline_factory(|io: UnixStream| ok((io, Protocol::Http1, None))).and_then(
                 HttpService::build()
                     .keep_alive(c.keep_alive)
                     .client_timeout(c.client_timeout)
-                    .finish(map_config(factory(), move |_| config.clone())),
+                    .conn_limits(c.conn_limits)
+                    .h2_settings(c.h2_settings)
+                    .h1_timeouts(c.h1_timeouts)
+                    .write_rate(c.write_rate)
+                    .write_buffer_limits(c.write_buffer)
+                    .read_buffer_limits(c.read_buffer)
+                    .finish(AdmissionFactory::new(
+                        c.admission.clone(),
+                        map_config(factory(), move |_| config.clone()),
+                    )),
             )
         })?;
         Ok(self)
@@ -401,10 +913,13 @@ where
 
     /// Start listening for incoming unix domain connections.
     ///
-    /// This method is available with `uds` feature.
+    /// Existing socket file is removed. Connections are served with http/1,
+    /// clients could connect with `Connector::unix_socket()`.
     pub fn bind_uds<A>(mut self, addr: A) -> io::Result<Self>
     where
         A: AsRef<std::path::Path>,
+        // queued requests hold application service until admitted
+        S::Service: 'static,
     {
         use crate::krse::net::UnixStream;
 
@@ -428,13 +943,23 @@ where
                     false,
                     socket_addr,
                     c.host.clone().unwrap_or_else(|| format!("{}", socket_addr)),
-                );
+                )
+                .with_connection_info(c.info.clone());
                 pipeline_factory(|io: UnixStream| ok((io, Protocol::Http1, None)))
                     .and_then(
                         HttpService::build()
                             .keep_alive(c.keep_alive)
                             .client_timeout(c.client_timeout)
-                            .finish(map_config(factory(), move |_| config.clone())),
+                            .conn_limits(c.conn_limits)
+                            .h2_settings(c.h2_settings)
+                            .h1_timeouts(c.h1_timeouts)
+                            .write_rate(c.write_rate)
+                            .write_buffer_limits(c.write_buffer)
+                            .read_buffer_limits(c.read_buffer)
+                            .finish(AdmissionFactory::new(
+                                c.admission.clone(),
+                                map_config(factory(), move |_| config.clone()),
+                            )),
                     )
             },
         )?;
@@ -450,7 +975,6 @@ where
     S::Error: Into<Error>,
     S::InitError: fmt::Debug,
     S::Response: Into<Response<B>>,
-    S::Service: 'static,
     B: MessageBody,
 {
     /// Start listening for incoming connections.
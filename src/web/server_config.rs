@@ -0,0 +1,186 @@
+//! Config-driven http server construction
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::path::PathBuf;
+
+use derive_more::Display;
+use rust_tls::internal::pemfile::{certs, pkcs8_private_keys, rsa_private_keys};
+use rust_tls::NoClientAuth;
+use serde::Deserialize;
+
+use crate::secure::tls::ServerConfig as RustlsServerConfig;
+
+/// Http server configuration.
+///
+/// Configuration could be deserialized from any serde supported format,
+/// all fields except addresses are optional, missing values keep
+/// `HttpServer` defaults.
+///
+/// ```rust
+/// use kayrx::web::ServerConfig;
+///
+/// let cfg: ServerConfig = serde_json::from_str(r#"{
+///     "bind": ["127.0.0.1:8080"],
+///     "workers": 4,
+///     "keep_alive": 75,
+///     "client_timeout": 3000
+/// }"#).unwrap();
+/// assert!(cfg.validate().is_ok());
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Addresses of plain http listeners
+    pub bind: Vec<String>,
+    /// Tls listeners configuration
+    pub tls: Option<TlsConfig>,
+    /// Number of workers
+    pub workers: Option<usize>,
+    /// Maximum number of pending connections
+    pub backlog: Option<i32>,
+    /// Maximum per-worker number of concurrent connections
+    pub maxconn: Option<usize>,
+    /// Maximum per-worker number of concurrent tls handshakes
+    pub maxconnrate: Option<usize>,
+    /// Keep-alive in seconds, `0` disables keep-alive
+    pub keep_alive: Option<usize>,
+    /// Client request header timeout in milliseconds
+    pub client_timeout: Option<u64>,
+    /// Connection shutdown timeout in milliseconds
+    pub client_shutdown: Option<u64>,
+    /// Graceful workers shutdown timeout in seconds
+    pub shutdown_timeout: Option<u64>,
+    /// Server host name used for url generation
+    pub hostname: Option<String>,
+}
+
+/// Tls listeners configuration
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    /// Addresses of https listeners
+    pub bind: Vec<String>,
+    /// Path to PEM encoded certificate chain
+    pub cert: PathBuf,
+    /// Path to PEM encoded PKCS8 or RSA private key
+    pub key: PathBuf,
+}
+
+/// Errors which can occur when http server is constructed from config
+#[derive(Debug, Display)]
+pub enum ServerConfigError {
+    /// No listener addresses
+    #[display(fmt = "No listener addresses configured")]
+    NoAddress,
+    /// Invalid listener address
+    #[display(fmt = "Invalid listener address {:?}: {}", _0, _1)]
+    Address(String, io::Error),
+    /// Invalid numeric setting
+    #[display(fmt = "Invalid value of {:?}: {}", _0, _1)]
+    Invalid(&'static str, String),
+    /// Can not load certificate chain
+    #[display(fmt = "Can not load certificate {:?}: {}", _0, _1)]
+    Certificate(PathBuf, String),
+    /// Can not load private key
+    #[display(fmt = "Can not load private key {:?}: {}", _0, _1)]
+    PrivateKey(PathBuf, String),
+    /// Can not bind listener
+    #[display(fmt = "Can not bind listener: {}", _0)]
+    Bind(io::Error),
+}
+
+impl std::error::Error for ServerConfigError {}
+
+impl ServerConfig {
+    /// Validate configuration.
+    ///
+    /// Checks settings values and resolves listener addresses, tls
+    /// certificates are checked during server construction.
+    pub fn validate(&self) -> Result<(), ServerConfigError> {
+        let tls_bind = self.tls.as_ref().map(|tls| tls.bind.len()).unwrap_or(0);
+        if self.bind.is_empty() && tls_bind == 0 {
+            return Err(ServerConfigError::NoAddress);
+        }
+        if let Some(ref tls) = self.tls {
+            if tls.bind.is_empty() {
+                return Err(ServerConfigError::Invalid(
+                    "tls.bind",
+                    "no addresses".to_owned(),
+                ));
+            }
+        }
+        if self.workers == Some(0) {
+            return Err(ServerConfigError::Invalid(
+                "workers",
+                "must be greater than 0".to_owned(),
+            ));
+        }
+        if let Some(backlog) = self.backlog {
+            if backlog <= 0 {
+                return Err(ServerConfigError::Invalid(
+                    "backlog",
+                    "must be greater than 0".to_owned(),
+                ));
+            }
+        }
+        if self.maxconn == Some(0) {
+            return Err(ServerConfigError::Invalid(
+                "maxconn",
+                "must be greater than 0".to_owned(),
+            ));
+        }
+        if self.maxconnrate == Some(0) {
+            return Err(ServerConfigError::Invalid(
+                "maxconnrate",
+                "must be greater than 0".to_owned(),
+            ));
+        }
+
+        let tls = self.tls.iter().flat_map(|tls| tls.bind.iter());
+        for addr in self.bind.iter().chain(tls) {
+            resolve(addr)?;
+        }
+        Ok(())
+    }
+}
+
+impl TlsConfig {
+    /// Load certificate chain and private key
+    pub fn load(&self) -> Result<RustlsServerConfig, ServerConfigError> {
+        let cert_err = |e: String| ServerConfigError::Certificate(self.cert.clone(), e);
+        let key_err = |e: String| ServerConfigError::PrivateKey(self.key.clone(), e);
+
+        let file = File::open(&self.cert).map_err(|e| cert_err(e.to_string()))?;
+        let chain = certs(&mut BufReader::new(file))
+            .map_err(|_| cert_err("invalid PEM".to_owned()))?;
+        if chain.is_empty() {
+            return Err(cert_err("no certificates found".to_owned()));
+        }
+
+        let file = File::open(&self.key).map_err(|e| key_err(e.to_string()))?;
+        let mut keys = pkcs8_private_keys(&mut BufReader::new(file))
+            .map_err(|_| key_err("invalid PEM".to_owned()))?;
+        if keys.is_empty() {
+            let file = File::open(&self.key).map_err(|e| key_err(e.to_string()))?;
+            keys = rsa_private_keys(&mut BufReader::new(file))
+                .map_err(|_| key_err("invalid PEM".to_owned()))?;
+        }
+        if keys.is_empty() {
+            return Err(key_err("no private keys found".to_owned()));
+        }
+
+        let mut config = RustlsServerConfig::new(NoClientAuth::new());
+        config
+            .set_single_cert(chain, keys.remove(0))
+            .map_err(|e| key_err(e.to_string()))?;
+        Ok(config)
+    }
+}
+
+/// Resolve listener address
+pub(crate) fn resolve(addr: &str) -> Result<Vec<SocketAddr>, ServerConfigError> {
+    addr.to_socket_addrs()
+        .map(|addrs| addrs.collect())
+        .map_err(|e| ServerConfigError::Address(addr.to_owned(), e))
+}
@@ -1,5 +1,5 @@
-use std::cell::{Ref, RefMut};
-use std::rc::Rc;
+use std::cell::{Ref, RefCell, RefMut};
+use std::rc::{Rc, Weak};
 use std::{fmt, net};
 
 use crate::http::body::{Body, MessageBody, ResponseBody};
@@ -10,7 +10,9 @@ use crate::http::{
 };
 use crate::router::{IntoPattern, Path, Resource, ResourceDef, Url};
 use crate::service::{IntoServiceFactory, ServiceFactory};
+use futures_util::future::LocalBoxFuture;
 
+use crate::web::app_service::AppRouter;
 use crate::web::config::{AppConfig, AppService};
 use crate::web::data::Data;
 use crate::web::dev::insert_slash;
@@ -108,6 +110,12 @@ impl ServiceRequest {
         ServiceResponse::new(self.0, res.into_body())
     }
 
+    /// Reference to the inner http request
+    #[inline]
+    pub(crate) fn request(&self) -> &HttpRequest {
+        &self.0
+    }
+
     /// This method returns reference to the request head
     #[inline]
     pub fn head(&self) -> &RequestHead {
@@ -217,11 +225,18 @@ impl ServiceRequest {
     /// Get an application data stored with `App::data()` method during
     /// application configuration.
     pub fn app_data<T: 'static>(&self) -> Option<Data<T>> {
-        if let Some(st) = (self.0).0.app_data.get::<Data<T>>() {
-            Some(st.clone())
-        } else {
-            None
-        }
+        self.0.app_data::<Data<T>>().cloned()
+    }
+
+    /// Dispatch internal request to the application router.
+    ///
+    /// See [`HttpRequest::subrequest()`](struct.HttpRequest.html#method.subrequest)
+    pub fn subrequest(
+        &self,
+        path: &str,
+        method: Method,
+    ) -> LocalBoxFuture<'static, Result<ServiceResponse, Error>> {
+        self.0.subrequest(path, method)
     }
 
     /// Set request payload.
@@ -230,9 +245,17 @@ impl ServiceRequest {
     }
 
     #[doc(hidden)]
-    /// Set new app data container
-    pub fn set_data_container(&mut self, extensions: Rc<Extensions>) {
-        Rc::get_mut(&mut (self.0).0).unwrap().app_data = extensions;
+    /// Add app data container of the nested scope or resource
+    pub fn add_data_container(&mut self, extensions: Rc<Extensions>) {
+        Rc::get_mut(&mut (self.0).0)
+            .unwrap()
+            .app_data
+            .push(extensions);
+    }
+
+    /// Set router of the application that handles the request
+    pub(crate) fn set_app_router(&mut self, router: Weak<RefCell<AppRouter>>) {
+        Rc::get_mut(&mut (self.0).0).unwrap().app_router = Some(router);
     }
 }
 
@@ -0,0 +1,252 @@
+//! Server-Sent Events
+//!
+//! [`Sse`](struct.Sse.html) responder sends a stream of events to the
+//! client with `text/event-stream` content type. While the stream is idle,
+//! responder sends comment keep-alives, so proxies and load balancers do
+//! not drop the connection.
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use futures::stream;
+//! use kayrx::web::{self, sse, App};
+//!
+//! async fn events() -> sse::Sse<impl futures::Stream<Item = Result<sse::Event, std::io::Error>>> {
+//!     sse::Sse::new(stream::iter(vec![
+//!         Ok(sse::Event::data("hello").event("greeting").id("1")),
+//!         Ok(sse::Event::data("line 1\nline 2").id("2")),
+//!     ]))
+//!     .keep_alive(Duration::from_secs(10))
+//! }
+//!
+//! fn main() {
+//!     let app = App::new().service(web::resource("/events").to(events));
+//! }
+//! ```
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use bytes::{BufMut, Bytes, BytesMut};
+use futures_core::Stream;
+use futures_util::future::{ok, Ready};
+use pin_project::pin_project;
+
+use crate::http::error::Error;
+use crate::http::header::CACHE_CONTROL;
+use crate::http::{Response, StatusCode};
+use crate::timer::{delay_for, Delay};
+use crate::web::request::HttpRequest;
+use crate::web::responder::Responder;
+
+/// Default keep-alive interval
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// Max size of events batched into one chunk
+const MAX_CHUNK: usize = 16_384;
+
+/// Single server-sent event
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Event {
+    id: Option<String>,
+    event: Option<String>,
+    data: Option<String>,
+    retry: Option<Duration>,
+}
+
+impl Event {
+    /// Create event with data.
+    ///
+    /// Multi-line data is sent as multiple `data` fields, client joins
+    /// them back with line feeds.
+    pub fn data<T: Into<String>>(data: T) -> Event {
+        Event {
+            data: Some(data.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Create event with json serialized data
+    pub fn json<T: serde::Serialize>(data: &T) -> Result<Event, serde_json::Error> {
+        Ok(Event::data(serde_json::to_string(data)?))
+    }
+
+    /// Set event id, client sends last received id in the
+    /// `Last-Event-ID` header on reconnect.
+    pub fn id<T: Into<String>>(mut self, id: T) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Set event type, by default client dispatches `message` event.
+    pub fn event<T: Into<String>>(mut self, event: T) -> Self {
+        self.event = Some(event.into());
+        self
+    }
+
+    /// Set client reconnection delay
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    fn encode(&self, buf: &mut BytesMut) {
+        if let Some(ref id) = self.id {
+            field(buf, "id", id);
+        }
+        if let Some(ref event) = self.event {
+            field(buf, "event", event);
+        }
+        if let Some(retry) = self.retry {
+            field(buf, "retry", &retry.as_millis().to_string());
+        }
+        if let Some(ref data) = self.data {
+            for line in data.split('\n') {
+                buf.put_slice(b"data: ");
+                buf.put_slice(line.trim_end_matches('\r').as_bytes());
+                buf.put_slice(b"\n");
+            }
+        }
+        buf.put_slice(b"\n");
+    }
+}
+
+/// Single line field, line breaks would start a new field
+fn field(buf: &mut BytesMut, name: &str, value: &str) {
+    buf.put_slice(name.as_bytes());
+    buf.put_slice(b": ");
+    for part in value.split(|c| c == '\n' || c == '\r') {
+        buf.put_slice(part.as_bytes());
+    }
+    buf.put_slice(b"\n");
+}
+
+/// Server-sent events responder.
+///
+/// Every item of the stream is sent as a separate event. If the stream does
+/// not produce events for keep-alive interval, comment line is sent instead.
+/// Response is finished when the stream ends, error of the stream drops
+/// the connection.
+pub struct Sse<S> {
+    stream: S,
+    keep_alive: Option<Duration>,
+    retry: Option<Duration>,
+}
+
+impl<S, E> Sse<S>
+where
+    S: Stream<Item = Result<Event, E>> + 'static,
+    E: Into<Error> + 'static,
+{
+    /// Create responder for the stream of events
+    pub fn new(stream: S) -> Self {
+        Sse {
+            stream,
+            keep_alive: Some(KEEP_ALIVE),
+            retry: None,
+        }
+    }
+
+    /// Set keep-alive interval, by default 15 seconds.
+    pub fn keep_alive(mut self, interval: Duration) -> Self {
+        self.keep_alive = Some(interval);
+        self
+    }
+
+    /// Disable keep-alive comments
+    pub fn disable_keep_alive(mut self) -> Self {
+        self.keep_alive = None;
+        self
+    }
+
+    /// Set client reconnection delay, it is sent before the first event.
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+}
+
+impl<S, E> Responder for Sse<S>
+where
+    S: Stream<Item = Result<Event, E>> + 'static,
+    E: Into<Error> + 'static,
+{
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        let mut buf = BytesMut::new();
+        if let Some(retry) = self.retry {
+            field(&mut buf, "retry", &retry.as_millis().to_string());
+            buf.put_slice(b"\n");
+        }
+
+        ok(Response::build(StatusCode::OK)
+            .content_type("text/event-stream")
+            .header(CACHE_CONTROL, "no-cache")
+            .streaming(SseStream {
+                stream: self.stream,
+                keep_alive: self.keep_alive,
+                delay: self.keep_alive.map(delay_for),
+                buf,
+                done: false,
+            }))
+    }
+}
+
+#[pin_project]
+struct SseStream<S> {
+    #[pin]
+    stream: S,
+    keep_alive: Option<Duration>,
+    delay: Option<Delay>,
+    buf: BytesMut,
+    done: bool,
+}
+
+impl<S, E> Stream for SseStream<S>
+where
+    S: Stream<Item = Result<Event, E>>,
+    E: Into<Error>,
+{
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        if *this.done {
+            return Poll::Ready(None);
+        }
+
+        // send available events in one chunk
+        while this.buf.len() < MAX_CHUNK {
+            match this.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(event))) => event.encode(this.buf),
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e.into()))),
+                Poll::Ready(None) => {
+                    *this.done = true;
+                    return if this.buf.is_empty() {
+                        Poll::Ready(None)
+                    } else {
+                        Poll::Ready(Some(Ok(this.buf.split().freeze())))
+                    };
+                }
+                Poll::Pending => break,
+            }
+        }
+
+        if let (Some(delay), Some(interval)) = (this.delay.as_mut(), *this.keep_alive) {
+            if !this.buf.is_empty() {
+                delay.reset_after(interval);
+            } else if Pin::new(&mut *delay).poll(cx).is_ready() {
+                delay.reset_after(interval);
+                return Poll::Ready(Some(Ok(Bytes::from_static(b":\n\n"))));
+            }
+        }
+
+        if this.buf.is_empty() {
+            Poll::Pending
+        } else {
+            Poll::Ready(Some(Ok(this.buf.split().freeze())))
+        }
+    }
+}
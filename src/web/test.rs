@@ -5,7 +5,7 @@ use std::rc::Rc;
 use std::sync::mpsc;
 use std::{fmt, net, thread, time};
 use bytes::{Bytes, BytesMut};
-use futures_util::future::ok;
+use futures_util::future::{ok, poll_fn};
 use futures_util::StreamExt;
 use futures_core::stream::Stream;
 use net2::TcpBuilder;
@@ -20,7 +20,7 @@ use crate::codec::Framed2 as Framed;
 use crate::http::header::{ContentType, Header, HeaderName, IntoHeaderValue};	
 use crate::http::{error::HttpError, Method, StatusCode, Uri, Version};
 use crate::http::test::TestRequest as HttpTestRequest;
-use crate::http::{Extensions, HttpService, Request};
+use crate::http::{Extensions, HttpService, Protocol, Request};
 use crate::websocket;
 use crate::router::{Path, ResourceDef, Url};
 use crate::{timer::delay_for, fiber::System};
@@ -211,6 +211,50 @@ where
     bytes.freeze()
 }
 
+/// Helper function that returns a deserialized body of a `ServiceResponse`
+///
+/// ```rust
+/// use kayrx::web::{App, test, self, HttpResponse};
+/// use kayrx::http::StatusCode;
+/// use serde::{Serialize, Deserialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// pub struct Person {
+///     id: String,
+///     name: String
+/// }
+///
+/// #[kayrx::test]
+/// async fn test_get_person() {
+///     let mut app = test::init_service(
+///         App::new().route("/people", web::get().to(|| async {
+///             HttpResponse::Ok().json(Person { id: "1".into(), name: "User name".into() })
+///         }))
+///     ).await;
+///
+///     let req = test::TestRequest::with_uri("/people").to_request();
+///     let resp = test::call_service(&mut app, req).await;
+///     assert_eq!(resp.status(), StatusCode::OK);
+///
+///     let result: Person = test::read_body_json(resp).await;
+///     assert_eq!(result.id, "1");
+/// }
+/// ```
+pub async fn read_body_json<T, B>(res: ServiceResponse<B>) -> T
+where
+    B: MessageBody,
+    T: DeserializeOwned,
+{
+    let body = read_body(res).await;
+
+    serde_json::from_slice(&body).unwrap_or_else(|e| {
+        panic!(
+            "read_body_json failed during deserialization: {}, body: {:?}",
+            e, body
+        )
+    })
+}
+
 pub async fn load_stream<S>(mut stream: S) -> Result<Bytes, Error>
 where
     S: Stream<Item = Result<Bytes, Error>> + Unpin,
@@ -615,12 +659,12 @@ where
     <S::Service as Service>::Future: 'static,
     B: MessageBody + 'static,
 {
-    let (tx, rx) = mpsc::channel();
-
     let ssl = match cfg.stream {
         StreamType::Tcp => false,
         StreamType::Rustls(_) => true,
+        StreamType::Memory(size) => return start_memory(cfg.client_timeout, size, factory),
     };
+    let (tx, rx) = mpsc::channel();
 
     // run server in separate thread
     thread::spawn(move || {
@@ -686,6 +730,8 @@ where
                         .rustls(config.clone())
                 }),
             },
+
+            StreamType::Memory(_) => unreachable!(),
         }
         .unwrap()
         .start();
@@ -712,7 +758,75 @@ where
         addr,
         client,
         system,
-        server,
+        server: Some(server),
+    }
+}
+
+/// Start test server with in-memory transport
+fn start_memory<F, I, S, B>(ctimeout: u64, size: usize, factory: F) -> TestServer
+where
+    F: Fn() -> I + Send + Clone + 'static,
+    I: IntoServiceFactory<S>,
+    S: ServiceFactory<Config = AppConfig, Request = Request> + 'static,
+    S::Error: Into<Error> + 'static,
+    S::InitError: fmt::Debug,
+    S::Response: Into<HttpResponse<B>> + 'static,
+    <S::Service as Service>::Future: 'static,
+    B: MessageBody + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+    let (connector, mut listener) = crate::connect::memory(size);
+    let addr = net::SocketAddr::new(net::IpAddr::V4(net::Ipv4Addr::new(127, 0, 0, 1)), 8080);
+
+    // run server in separate thread
+    thread::spawn(move || {
+        let sys = System::new("kayrx-test-server");
+        let cfg = AppConfig::new(false, addr, format!("{}", addr));
+        let factory = HttpService::build()
+            .client_timeout(ctimeout)
+            .finish(map_config(factory(), move |_| cfg.clone()));
+
+        crate::fiber::spawn(async move {
+            let mut srv = match factory.new_service(()).await {
+                Ok(srv) => srv,
+                Err(_) => {
+                    log::error!("Can not create test server service");
+                    return;
+                }
+            };
+            while let Some(io) = listener.next().await {
+                if poll_fn(|cx| srv.poll_ready(cx)).await.is_err() {
+                    break;
+                }
+                let fut = srv.call((io, Protocol::Http1, None));
+                crate::fiber::spawn(async move {
+                    let _ = fut.await;
+                });
+            }
+        });
+
+        tx.send(System::current()).unwrap();
+        sys.run()
+    });
+
+    let system = rx.recv().unwrap();
+
+    let client = {
+        let connector = Connector::new()
+            .connector(connector)
+            .conn_lifetime(time::Duration::from_secs(0))
+            .timeout(time::Duration::from_millis(30000))
+            .finish();
+
+        Client::build().connector(connector).finish()
+    };
+
+    TestServer {
+        ssl: false,
+        addr,
+        client,
+        system,
+        server: None,
     }
 }
 
@@ -734,6 +848,7 @@ enum HttpVer {
 enum StreamType {
     Tcp,
     Rustls(rust_tls::ServerConfig),
+    Memory(usize),
 }
 
 impl Default for TestServerConfig {
@@ -775,6 +890,16 @@ impl TestServerConfig {
         self
     }
 
+    /// Start server with in-memory transport.
+    ///
+    /// Client connects to the server over `DuplexStream`s instead of tcp
+    /// sockets, each direction buffers up to 64Kb. Memory server supports
+    /// http/1.1 only, tls and protocol settings are ignored.
+    pub fn memory(mut self) -> Self {
+        self.stream = StreamType::Memory(65_536);
+        self
+    }
+
     /// Set server client timeout in milliseconds for first request.
     pub fn client_timeout(mut self, val: u64) -> Self {
         self.client_timeout = val;
@@ -798,7 +923,7 @@ pub struct TestServer {
     client: crate::web::client::Client,
     system: crate::fiber::System,
     ssl: bool,
-    server: Server,
+    server: Option<Server>,
 }
 
 impl TestServer {
@@ -889,7 +1014,9 @@ impl TestServer {
 
     /// Gracefully stop http server
     pub async fn stop(self) {
-        self.server.stop(true).await;
+        if let Some(ref server) = self.server {
+            server.stop(true).await;
+        }
         self.system.stop();
         delay_for(time::Duration::from_millis(100)).await;
     }
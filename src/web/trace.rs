@@ -0,0 +1,329 @@
+//! Distributed tracing context propagation.
+//!
+//! [`TraceContext`](struct.TraceContext.html) identifies the current span
+//! of a distributed trace. [`middleware::Trace`](../middleware/struct.Trace.html)
+//! extracts it from W3C `traceparent`/`tracestate` or B3 headers of incoming
+//! requests and makes it current while the request is handled. Clients
+//! built with `ClientBuilder::trace_propagation()` inject context of the
+//! current request into outgoing requests, so traces continue across
+//! services.
+//!
+//! ```rust
+//! use kayrx::web::client::Client;
+//! use kayrx::web::trace::Propagation;
+//! use kayrx::web::{self, middleware, App, HttpResponse};
+//!
+//! async fn index(client: web::Data<Client>) -> HttpResponse {
+//!     // request carries `traceparent` header with the trace id
+//!     // of the incoming request
+//!     let _ = client.get("http://backend.local/").send().await;
+//!     HttpResponse::Ok().finish()
+//! }
+//!
+//! fn main() {
+//!     let app = App::new()
+//!         .data(Client::build().trace_propagation(Propagation::W3C).finish())
+//!         .wrap(middleware::Trace::new())
+//!         .service(web::resource("/").to(index));
+//! }
+//! ```
+//!
+//! Context is stored per thread while the request future is polled,
+//! futures spawned with `fiber::spawn` should be wrapped with
+//! `TraceContext::scope()` to keep it.
+use std::cell::RefCell;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::http::header::{HeaderMap, HeaderName, HeaderValue};
+
+pub(crate) const TRACEPARENT: &str = "traceparent";
+const TRACESTATE: &str = "tracestate";
+const B3: &str = "b3";
+const X_B3_TRACEID: &str = "x-b3-traceid";
+const X_B3_SPANID: &str = "x-b3-spanid";
+const X_B3_SAMPLED: &str = "x-b3-sampled";
+
+thread_local! {
+    static CURRENT: RefCell<Option<TraceContext>> = RefCell::new(None);
+}
+
+/// Trace context headers injected into outgoing requests
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Propagation {
+    /// W3C `traceparent` and `tracestate` headers
+    W3C,
+    /// B3 single `b3` header
+    B3,
+    /// Both W3C and B3 headers
+    All,
+}
+
+/// Span of a distributed trace
+#[derive(Clone, PartialEq)]
+pub struct TraceContext {
+    trace_id: u128,
+    span_id: u64,
+    parent_id: Option<u64>,
+    sampled: bool,
+    state: Option<String>,
+}
+
+impl TraceContext {
+    /// Start new sampled trace
+    pub fn new_root() -> TraceContext {
+        TraceContext {
+            trace_id: non_zero(rand::random),
+            span_id: non_zero(rand::random),
+            parent_id: None,
+            sampled: true,
+            state: None,
+        }
+    }
+
+    /// Extract remote context from request headers.
+    ///
+    /// W3C `traceparent` header takes precedence over B3 `b3` header
+    /// and `X-B3-*` headers.
+    pub fn from_headers(headers: &HeaderMap) -> Option<TraceContext> {
+        if let Some(value) = headers.get(TRACEPARENT) {
+            let mut ctx = TraceContext::parse_traceparent(value.to_str().ok()?)?;
+            ctx.state = headers
+                .get(TRACESTATE)
+                .and_then(|v| v.to_str().ok())
+                .filter(|v| !v.trim().is_empty())
+                .map(|v| v.to_owned());
+            return Some(ctx);
+        }
+        if let Some(value) = headers.get(B3) {
+            return TraceContext::parse_b3(value.to_str().ok()?);
+        }
+
+        let trace_id = headers.get(X_B3_TRACEID)?.to_str().ok()?;
+        let span_id = headers.get(X_B3_SPANID)?.to_str().ok()?;
+        let sampled = headers
+            .get(X_B3_SAMPLED)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v == "1" || v == "true")
+            .unwrap_or(true);
+        Some(TraceContext {
+            trace_id: parse_b3_trace_id(trace_id)?,
+            span_id: parse_hex_u64(span_id)?,
+            parent_id: None,
+            sampled,
+            state: None,
+        })
+    }
+
+    /// Parse W3C `traceparent` header value
+    pub fn parse_traceparent(value: &str) -> Option<TraceContext> {
+        let mut parts = value.trim().split('-');
+        let version = parts.next()?;
+        let trace_id = parts.next()?;
+        let span_id = parts.next()?;
+        let flags = parts.next()?;
+
+        // future versions may append fields
+        if version.len() != 2
+            || version == "ff"
+            || (version == "00" && parts.next().is_some())
+            || trace_id.len() != 32
+            || span_id.len() != 16
+            || flags.len() != 2
+        {
+            return None;
+        }
+        u8::from_str_radix(version, 16).ok()?;
+        let flags = u8::from_str_radix(flags, 16).ok()?;
+
+        Some(TraceContext {
+            trace_id: parse_hex_u128(trace_id)?,
+            span_id: parse_hex_u64(span_id)?,
+            parent_id: None,
+            sampled: flags & 1 == 1,
+            state: None,
+        })
+    }
+
+    /// Parse B3 single header value, `{trace_id}-{span_id}-{sampled}`
+    pub fn parse_b3(value: &str) -> Option<TraceContext> {
+        let mut parts = value.trim().split('-');
+        let trace_id = parse_b3_trace_id(parts.next()?)?;
+        let span_id = parse_hex_u64(parts.next()?)?;
+        let sampled = match parts.next() {
+            None | Some("1") | Some("d") => true,
+            Some("0") => false,
+            Some(_) => return None,
+        };
+        Some(TraceContext {
+            trace_id,
+            span_id,
+            parent_id: None,
+            sampled,
+            state: None,
+        })
+    }
+
+    /// Create child span of the same trace
+    pub fn child(&self) -> TraceContext {
+        TraceContext {
+            trace_id: self.trace_id,
+            span_id: non_zero(rand::random),
+            parent_id: Some(self.span_id),
+            sampled: self.sampled,
+            state: self.state.clone(),
+        }
+    }
+
+    /// Trace id
+    pub fn trace_id(&self) -> u128 {
+        self.trace_id
+    }
+
+    /// Span id
+    pub fn span_id(&self) -> u64 {
+        self.span_id
+    }
+
+    /// Id of the parent span, `None` for root spans
+    pub fn parent_id(&self) -> Option<u64> {
+        self.parent_id
+    }
+
+    /// Check if trace is sampled
+    pub fn is_sampled(&self) -> bool {
+        self.sampled
+    }
+
+    /// Vendor specific `tracestate` value
+    pub fn trace_state(&self) -> Option<&str> {
+        self.state.as_ref().map(|s| s.as_str())
+    }
+
+    /// W3C `traceparent` header value
+    pub fn traceparent(&self) -> String {
+        format!(
+            "00-{:032x}-{:016x}-{:02x}",
+            self.trace_id, self.span_id, self.sampled as u8
+        )
+    }
+
+    /// B3 single header value
+    pub fn b3(&self) -> String {
+        format!(
+            "{:032x}-{:016x}-{}",
+            self.trace_id, self.span_id, self.sampled as u8
+        )
+    }
+
+    /// Insert context headers, headers that are already set are kept
+    pub(crate) fn inject(&self, headers: &mut HeaderMap, propagation: Propagation) {
+        let mut set = |name: &'static str, value: &str| {
+            let name = HeaderName::from_static(name);
+            if !headers.contains_key(&name) {
+                if let Ok(value) = HeaderValue::from_str(value) {
+                    headers.insert(name, value);
+                }
+            }
+        };
+
+        if propagation != Propagation::B3 {
+            set(TRACEPARENT, &self.traceparent());
+            if let Some(ref state) = self.state {
+                set(TRACESTATE, state);
+            }
+        }
+        if propagation != Propagation::W3C {
+            set(B3, &self.b3());
+        }
+    }
+
+    /// Context of the request that is currently handled
+    pub fn current() -> Option<TraceContext> {
+        CURRENT.with(|current| current.borrow().clone())
+    }
+
+    /// Make context current while the future is polled
+    pub fn scope<F: Future>(self, fut: F) -> Scoped<F> {
+        Scoped { ctx: self, fut }
+    }
+
+    /// Make context current until returned guard is dropped
+    pub(crate) fn enter(&self) -> Entered {
+        let prev = CURRENT.with(|current| current.replace(Some(self.clone())));
+        Entered { prev }
+    }
+}
+
+/// Restores previous context on drop
+pub(crate) struct Entered {
+    prev: Option<TraceContext>,
+}
+
+impl Drop for Entered {
+    fn drop(&mut self) {
+        let prev = self.prev.take();
+        CURRENT.with(|current| *current.borrow_mut() = prev);
+    }
+}
+
+impl fmt::Debug for TraceContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TraceContext")
+            .field("trace_id", &format_args!("{:032x}", self.trace_id))
+            .field("span_id", &format_args!("{:016x}", self.span_id))
+            .field("sampled", &self.sampled)
+            .finish()
+    }
+}
+
+/// Future with current trace context, see `TraceContext::scope()`
+#[pin_project::pin_project]
+pub struct Scoped<F> {
+    ctx: TraceContext,
+    #[pin]
+    fut: F,
+}
+
+impl<F: Future> Future for Scoped<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let this = self.project();
+        let _guard = this.ctx.enter();
+        this.fut.poll(cx)
+    }
+}
+
+fn non_zero<T: Default + PartialEq>(f: fn() -> T) -> T {
+    loop {
+        let val = f();
+        if val != T::default() {
+            return val;
+        }
+    }
+}
+
+fn parse_hex_u128(s: &str) -> Option<u128> {
+    if !s.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)) {
+        return None;
+    }
+    u128::from_str_radix(s, 16).ok().filter(|id| *id != 0)
+}
+
+fn parse_hex_u64(s: &str) -> Option<u64> {
+    if s.len() != 16 {
+        return None;
+    }
+    parse_hex_u128(s).map(|id| id as u64)
+}
+
+/// B3 trace id is 64 or 128 bit
+fn parse_b3_trace_id(s: &str) -> Option<u128> {
+    if s.len() != 16 && s.len() != 32 {
+        return None;
+    }
+    parse_hex_u128(s)
+}
@@ -0,0 +1,122 @@
+//! Streaming CSV responder
+
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+
+use bytes::BytesMut;
+use futures_util::future::{ok, Ready};
+use serde::Serialize;
+
+use crate::http::error::ErrorInternalServerError;
+use crate::http::{Response, StatusCode};
+use crate::web::error::Error;
+use crate::web::request::HttpRequest;
+use crate::web::responder::Responder;
+use crate::web::types::jsonlines::SerializeStream;
+
+/// Streaming CSV responder.
+///
+/// Serializes a stream of serde values into `text/csv` body, one record per
+/// row. If records are structs, header row is generated from field names
+/// unless disabled with `CsvStream::has_headers(false)`. Like
+/// [`JsonLines`](struct.JsonLines.html), records are serialized on demand
+/// and sent in chunks.
+///
+/// ```rust
+/// use futures::stream;
+/// use kayrx::web::{self, types, App, Error};
+/// use serde_derive::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Row {
+///     id: u64,
+///     name: &'static str,
+/// }
+///
+/// async fn export() -> types::CsvStream<impl futures::Stream<Item = Result<Row, Error>>> {
+///     types::CsvStream::new(stream::iter((0..1000).map(|id| Ok(Row { id, name: "row" }))))
+///         .delimiter(b';')
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/export.csv").route(web::get().to(export))
+///     );
+/// }
+/// ```
+pub struct CsvStream<S> {
+    stream: S,
+    delimiter: u8,
+    has_headers: bool,
+}
+
+impl<S> CsvStream<S> {
+    /// Create new csv responder from a stream of records
+    pub fn new(stream: S) -> Self {
+        CsvStream {
+            stream,
+            delimiter: b',',
+            has_headers: true,
+        }
+    }
+
+    /// Set field delimiter. By default `,` is used.
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Enable or disable header row. Enabled by default.
+    pub fn has_headers(mut self, yes: bool) -> Self {
+        self.has_headers = yes;
+        self
+    }
+}
+
+impl<S, T, E> Responder for CsvStream<S>
+where
+    S: futures_core::Stream<Item = Result<T, E>> + 'static,
+    T: Serialize + 'static,
+    E: Into<Error> + 'static,
+{
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        let out = SharedBuf::default();
+        let mut writer = ::csv::WriterBuilder::new()
+            .delimiter(self.delimiter)
+            .has_headers(self.has_headers)
+            .from_writer(out.clone());
+
+        let stream = SerializeStream::new(self.stream, move |item: &T, buf: &mut BytesMut| {
+            writer.serialize(item).map_err(ErrorInternalServerError)?;
+            writer.flush()?;
+            buf.extend_from_slice(&out.0.borrow_mut().split());
+            Ok(())
+        });
+
+        ok(Response::build(StatusCode::OK)
+            .content_type("text/csv; charset=utf-8")
+            .streaming(stream))
+    }
+}
+
+/// `CsvStream` under the `csv::Stream` name
+pub use self::CsvStream as Stream;
+
+/// Writer target shared between csv writer and the body stream
+#[derive(Clone, Default)]
+struct SharedBuf(Rc<RefCell<BytesMut>>);
+
+impl io::Write for SharedBuf {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
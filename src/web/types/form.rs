@@ -16,11 +16,13 @@ use crate::http::{error::Error, HttpMessage, Payload, Response};
 use crate::web::dev::Decompress;
 use crate::web::error::UrlencodedError;
 use crate::web::extract::FromRequest;
+use crate::web::middleware::memory::MemoryBudget;
 use crate::http::{
     header::{ContentType, CONTENT_LENGTH},
     StatusCode,
 };
 use crate::web::request::HttpRequest;
+use crate::web::types::PayloadConfig;
 use crate::web::responder::Responder;
 
 /// Form data helper (`application/x-www-form-urlencoded`)
@@ -121,7 +123,7 @@ where
         let (limit, err) = req
             .app_data::<FormConfig>()
             .map(|c| (c.limit, c.ehandler.clone()))
-            .unwrap_or((16384, None));
+            .unwrap_or_else(|| (PayloadConfig::limit_for(req, 16384), None));
 
         UrlEncoded::new(req, payload)
             .limit(limit)
@@ -242,6 +244,7 @@ pub struct UrlEncoded<U> {
     stream: Option<Decompress<Payload>>,
     limit: usize,
     length: Option<usize>,
+    budget: Option<MemoryBudget>,
     encoding: &'static Encoding,
     err: Option<UrlencodedError>,
     fut: Option<LocalBoxFuture<'static, Result<U, UrlencodedError>>>,
@@ -272,13 +275,14 @@ impl<U> UrlEncoded<U> {
             }
         };
 
-        let payload = Decompress::from_headers(payload.take(), req.headers());
+        let payload = crate::web::types::payload::decompress(payload.take(), req);
 
         UrlEncoded {
             encoding,
             stream: Some(payload),
             limit: 32_768,
             length: len,
+            budget: MemoryBudget::of(req),
             fut: None,
             err: None,
         }
@@ -291,6 +295,7 @@ impl<U> UrlEncoded<U> {
             fut: None,
             err: Some(e),
             length: None,
+            budget: None,
             encoding: UTF_8,
         }
     }
@@ -319,6 +324,7 @@ where
 
         // payload size
         let limit = self.limit;
+        let budget = self.budget.take();
         if let Some(len) = self.length.take() {
             if len > limit {
                 return Poll::Ready(Err(UrlencodedError::Overflow { size: len, limit }));
@@ -341,6 +347,14 @@ where
                             limit,
                         });
                     } else {
+                        if let Some(ref budget) = budget {
+                            budget.charge(chunk.len()).map_err(|_| {
+                                UrlencodedError::Overflow {
+                                    size: budget.used() + chunk.len(),
+                                    limit: budget.limit(),
+                                }
+                            })?;
+                        }
                         body.extend_from_slice(&chunk);
                     }
                 }
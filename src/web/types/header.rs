@@ -0,0 +1,182 @@
+//! Typed header extractor
+
+use std::sync::Arc;
+use std::{fmt, ops};
+
+use futures_util::future::{err, ok, Ready};
+
+use crate::http::error::Error;
+use crate::http::header::Header as TypedHeader;
+use crate::web::dev::Payload;
+use crate::web::error::HeaderError;
+use crate::web::extract::FromRequest;
+use crate::web::request::HttpRequest;
+
+/// Extract and parse a single typed header.
+///
+/// Missing or malformed header results in *400 Bad Request* response.
+/// `Header<Option<T>>` extracts optional header: missing header is `None`,
+/// while malformed header is still rejected. Note that `Option<Header<T>>`
+/// silently ignores malformed headers.
+///
+/// [**HeaderConfig**](struct.HeaderConfig.html) allows to configure extraction process.
+///
+/// ## Example
+///
+/// ```rust
+/// use kayrx::http::header::{ContentType, IfMatch};
+/// use kayrx::web::{self, types, App};
+///
+/// async fn index(
+///     ct: types::Header<ContentType>,
+///     if_match: types::Header<Option<IfMatch>>,
+/// ) -> String {
+///     format!("{} {:?}", ct.0, if_match.0)
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/index.html").route(web::post().to(index)),
+///     );
+/// }
+/// ```
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+pub struct Header<T>(pub T);
+
+impl<T> Header<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for Header<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for Header<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Header<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Header<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: TypedHeader> FromRequest for Header<T> {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+    type Config = HeaderConfig;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        match parse::<T>(req) {
+            Ok(Some(hdr)) => ok(Header(hdr)),
+            Ok(None) => err(error(req, HeaderError::Missing(T::name()))),
+            Err(e) => err(error(req, e)),
+        }
+    }
+}
+
+impl<T: TypedHeader> FromRequest for Header<Option<T>> {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+    type Config = HeaderConfig;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        match parse::<T>(req) {
+            Ok(hdr) => ok(Header(hdr)),
+            Err(e) => err(error(req, e)),
+        }
+    }
+}
+
+fn parse<T: TypedHeader>(req: &HttpRequest) -> Result<Option<T>, HeaderError> {
+    if !req.headers().contains_key(T::name()) {
+        return Ok(None);
+    }
+    T::parse(req).map(Some).map_err(|_| {
+        log::debug!(
+            "Failed during Header extractor parsing. \
+             Request path: {:?}",
+            req.path()
+        );
+        HeaderError::Invalid(T::name())
+    })
+}
+
+fn error(req: &HttpRequest, e: HeaderError) -> Error {
+    let error_handler = req
+        .app_data::<HeaderConfig>()
+        .map(|c| c.ehandler.clone())
+        .unwrap_or(None);
+
+    if let Some(error_handler) = error_handler {
+        (error_handler)(e, req)
+    } else {
+        e.into()
+    }
+}
+
+/// Header extractor configuration
+///
+/// ## Example
+///
+/// ```rust
+/// use kayrx::http::error::InternalError;
+/// use kayrx::http::header::ContentType;
+/// use kayrx::web::{self, types, App, FromRequest, HttpResponse};
+///
+/// async fn index(ct: types::Header<ContentType>) -> String {
+///     format!("{}", ct.0)
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/index.html").app_data(
+///             // change header extractor configuration
+///             types::Header::<ContentType>::configure(|cfg| {
+///                 cfg.error_handler(|err, req| {  // <- create custom error response
+///                     InternalError::from_response(
+///                         err, HttpResponse::Conflict().finish()).into()
+///                 })
+///             }))
+///             .route(web::post().to(index))
+///     );
+/// }
+/// ```
+#[derive(Clone)]
+pub struct HeaderConfig {
+    ehandler: Option<Arc<dyn Fn(HeaderError, &HttpRequest) -> Error + Send + Sync>>,
+}
+
+impl HeaderConfig {
+    /// Set custom error handler
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(HeaderError, &HttpRequest) -> Error + Send + Sync + 'static,
+    {
+        self.ehandler = Some(Arc::new(f));
+        self
+    }
+}
+
+impl Default for HeaderConfig {
+    fn default() -> Self {
+        HeaderConfig { ehandler: None }
+    }
+}
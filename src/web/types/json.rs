@@ -3,12 +3,14 @@
 
 use std::future::Future;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{mpsc, Arc};
 use std::task::{Context, Poll};
-use std::{fmt, ops};
+use std::{fmt, io, ops};
 
-use bytes::BytesMut;
+use bytes::{Bytes, BytesMut};
+use futures_core::Stream;
 use futures_util::future::{err, ok, FutureExt, LocalBoxFuture, Ready};
+use futures_util::task::AtomicWaker;
 use futures_util::StreamExt;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
@@ -16,11 +18,14 @@ use serde_json;
 
 use crate::http::{header::CONTENT_LENGTH, StatusCode};
 use crate::http::{HttpMessage, Payload, Response};
+use crate::util::threadpool::{self, CpuFuture};
 
 use crate::web::dev::Decompress;
-use crate::web::error::{Error, JsonPayloadError};
+use crate::web::error::{json_from_slice, Error, JsonPayloadError};
 use crate::web::extract::FromRequest;
+use crate::web::middleware::memory::MemoryBudget;
 use crate::web::request::HttpRequest;
+use crate::web::types::PayloadConfig;
 use crate::web::responder::Responder;
 
 /// Json helper
@@ -137,6 +142,164 @@ impl<T: Serialize> Responder for Json<T> {
     }
 }
 
+impl<T> Json<T>
+where
+    T: Serialize + Send + 'static,
+{
+    /// Create json responder that serializes value incrementally.
+    ///
+    /// Value is serialized on the thread pool into chunks of limited size
+    /// (64Kb by default) which are sent to the client as soon as they are
+    /// ready, so large responses never occupy one contiguous buffer.
+    /// Serializer waits while the client is not reading, at most two
+    /// chunks are buffered.
+    ///
+    /// ```rust
+    /// use kayrx::web::types::{Json, JsonStreaming};
+    ///
+    /// async fn index() -> JsonStreaming<Vec<u64>> {
+    ///     Json::streaming((0..1_000_000).collect())
+    /// }
+    /// # fn main() {}
+    /// ```
+    pub fn streaming(value: T) -> JsonStreaming<T> {
+        JsonStreaming {
+            value,
+            chunk_size: 65_536,
+        }
+    }
+}
+
+/// Json responder that serializes value in chunks, see `Json::streaming()`
+pub struct JsonStreaming<T> {
+    value: T,
+    chunk_size: usize,
+}
+
+impl<T> JsonStreaming<T> {
+    /// Change size of the serialized chunk. By default size is 64Kb
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        self.chunk_size = size;
+        self
+    }
+}
+
+impl<T> Responder for JsonStreaming<T>
+where
+    T: Serialize + Send + 'static,
+{
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        let (tx, rx) = mpsc::sync_channel(2);
+        let waker = Arc::new(AtomicWaker::new());
+        let mut writer = ChunkWriter {
+            tx,
+            waker: waker.clone(),
+            buf: BytesMut::with_capacity(self.chunk_size),
+            chunk_size: self.chunk_size,
+        };
+        let value = self.value;
+
+        let task = threadpool::run(move || {
+            let res = serde_json::to_writer(&mut writer, &value)
+                .map_err(io::Error::from)
+                .and_then(|_| writer.flush_chunk());
+            if let Err(e) = res {
+                // client is gone or serialization failed
+                writer.send(Err(e))
+            } else {
+                Ok(())
+            }
+        });
+
+        ok(Response::build(StatusCode::OK)
+            .content_type("application/json")
+            .streaming(ChunkStream {
+                rx,
+                waker,
+                task: Some(task),
+            }))
+    }
+}
+
+/// Serializer output, sends chunks to the response body
+struct ChunkWriter {
+    tx: mpsc::SyncSender<Result<Bytes, io::Error>>,
+    waker: Arc<AtomicWaker>,
+    buf: BytesMut,
+    chunk_size: usize,
+}
+
+impl ChunkWriter {
+    fn send(&self, item: Result<Bytes, io::Error>) -> io::Result<()> {
+        self.tx
+            .send(item)
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "Response is dropped"))?;
+        self.waker.wake();
+        Ok(())
+    }
+
+    fn flush_chunk(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            Ok(())
+        } else {
+            let chunk = self.buf.split().freeze();
+            self.buf.reserve(self.chunk_size);
+            self.send(Ok(chunk))
+        }
+    }
+}
+
+impl io::Write for ChunkWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        if self.buf.len() >= self.chunk_size {
+            self.flush_chunk()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Response body of streaming json
+struct ChunkStream {
+    rx: mpsc::Receiver<Result<Bytes, io::Error>>,
+    waker: Arc<AtomicWaker>,
+    // serializer task is canceled if it did not start before drop,
+    // task completion wakes the stream after the last chunk is sent
+    task: Option<CpuFuture<(), io::Error>>,
+}
+
+impl Stream for ChunkStream {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        this.waker.register(cx.waker());
+        loop {
+            match this.rx.try_recv() {
+                Ok(item) => return Poll::Ready(Some(item.map_err(Error::from))),
+                Err(mpsc::TryRecvError::Disconnected) => return Poll::Ready(None),
+                Err(mpsc::TryRecvError::Empty) => match this.task {
+                    Some(ref mut task) => {
+                        if Pin::new(task).poll(cx).is_pending() {
+                            return Poll::Pending;
+                        }
+                        // writer is dropped, drain remaining chunks
+                        this.task = None;
+                    }
+                    None => return Poll::Ready(None),
+                },
+            }
+        }
+    }
+}
+
 /// Json extractor. Allow to extract typed information from request's
 /// payload.
 ///
@@ -180,13 +343,18 @@ where
     #[inline]
     fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
         let req2 = req.clone();
-        let (limit, err, ctype) = req
-            .app_data::<Self::Config>()
-            .map(|c| (c.limit, c.ehandler.clone(), c.content_type.clone()))
-            .unwrap_or((32768, None, None));
+        let tmp;
+        let cfg = if let Some(cfg) = req.app_data::<Self::Config>() {
+            cfg
+        } else {
+            tmp = JsonConfig::default().limit(PayloadConfig::limit_for(req, 32768));
+            &tmp
+        };
+        let err = cfg.ehandler.clone();
 
-        JsonBody::new(req, payload, ctype)
-            .limit(limit)
+        JsonBody::with_config(req, payload, cfg.content_type.clone(), cfg.strict)
+            .limit(cfg.limit)
+            .max_depth(cfg.max_depth)
             .map(move |res| match res {
                 Err(e) => {
                     log::debug!(
@@ -232,6 +400,7 @@ where
 ///                        .content_type(|mime| {  // <- accept text/plain content type
 ///                            mime.type_() == mime::TEXT && mime.subtype() == mime::PLAIN
 ///                        })
+///                        .max_depth(16)  // <- limit nesting of arrays and objects
 ///                        .error_handler(|err, req| {  // <- create custom error response
 ///                           error::InternalError::from_response(
 ///                               err, HttpResponse::Conflict().finish()).into()
@@ -246,6 +415,8 @@ pub struct JsonConfig {
     limit: usize,
     ehandler: Option<Arc<dyn Fn(JsonPayloadError, &HttpRequest) -> Error + Send + Sync>>,
     content_type: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
+    strict: bool,
+    max_depth: Option<usize>,
 }
 
 impl JsonConfig {
@@ -255,7 +426,10 @@ impl JsonConfig {
         self
     }
 
-    /// Set custom error handler
+    /// Set custom error handler.
+    ///
+    /// Handler maps extraction errors to custom responses, i.e. structured
+    /// *422 Unprocessable Entity* response for deserialization errors.
     pub fn error_handler<F>(mut self, f: F) -> Self
     where
         F: Fn(JsonPayloadError, &HttpRequest) -> Error + Send + Sync + 'static,
@@ -272,6 +446,25 @@ impl JsonConfig {
         self.content_type = Some(Arc::new(predicate));
         self
     }
+
+    /// Require json content type of the request. By default it is required.
+    ///
+    /// If disabled, payload is parsed as json regardless of `Content-Type`
+    /// header, for clients that do not set it.
+    pub fn strict_content_type(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Set max nesting depth of json arrays and objects.
+    ///
+    /// Payload is checked before deserialization, deeper documents are
+    /// rejected with `JsonPayloadError::Depth` error. By default only
+    /// recursion limit of the json parser applies.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
 }
 
 impl Default for JsonConfig {
@@ -280,6 +473,8 @@ impl Default for JsonConfig {
             limit: 32768,
             ehandler: None,
             content_type: None,
+            strict: true,
+            max_depth: None,
         }
     }
 }
@@ -294,7 +489,9 @@ impl Default for JsonConfig {
 /// * content length is greater than 256k
 pub struct JsonBody<U> {
     limit: usize,
+    max_depth: Option<usize>,
     length: Option<usize>,
+    budget: Option<MemoryBudget>,
     stream: Option<Decompress<Payload>>,
     err: Option<JsonPayloadError>,
     fut: Option<LocalBoxFuture<'static, Result<U, JsonPayloadError>>>,
@@ -309,6 +506,15 @@ where
         req: &HttpRequest,
         payload: &mut Payload,
         ctype: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
+    ) -> Self {
+        JsonBody::with_config(req, payload, ctype, true)
+    }
+
+    fn with_config(
+        req: &HttpRequest,
+        payload: &mut Payload,
+        ctype: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
+        strict: bool,
     ) -> Self {
         // check content-type
         let json = if let Ok(Some(mime)) = req.mime_type() {
@@ -319,10 +525,12 @@ where
             false
         };
 
-        if !json {
+        if !json && strict {
             return JsonBody {
                 limit: 262_144,
+                max_depth: None,
                 length: None,
+                budget: None,
                 stream: None,
                 fut: None,
                 err: Some(JsonPayloadError::ContentType),
@@ -335,11 +543,13 @@ where
             .and_then(|l| l.to_str().ok())
             .and_then(|s| s.parse::<usize>().ok());
 
-        let payload = Decompress::from_headers(payload.take(), req.headers());
+        let payload = crate::web::types::payload::decompress(payload.take(), req);
 
         JsonBody {
             limit: 262_144,
+            max_depth: None,
             length: len,
+            budget: MemoryBudget::of(req),
             stream: Some(payload),
             fut: None,
             err: None,
@@ -351,6 +561,44 @@ where
         self.limit = limit;
         self
     }
+
+    /// Set max nesting depth of json arrays and objects
+    pub fn max_depth(mut self, depth: Option<usize>) -> Self {
+        self.max_depth = depth;
+        self
+    }
+}
+
+/// Check nesting depth of json document without parsing it
+fn check_depth(body: &[u8], max: usize) -> bool {
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for &b in body {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if b == b'\\' {
+                escaped = true;
+            } else if b == b'"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                depth += 1;
+                if depth > max {
+                    return false;
+                }
+            }
+            b']' | b'}' => depth -= 1,
+            _ => (),
+        }
+    }
+    true
 }
 
 impl<U> Future for JsonBody<U>
@@ -369,6 +617,8 @@ where
         }
 
         let limit = self.limit;
+        let budget = self.budget.take();
+        let max_depth = self.max_depth;
         if let Some(len) = self.length.take() {
             if len > limit {
                 return Poll::Ready(Err(JsonPayloadError::Overflow));
@@ -385,10 +635,20 @@ where
                     if (body.len() + chunk.len()) > limit {
                         return Err(JsonPayloadError::Overflow);
                     } else {
+                        if let Some(ref budget) = budget {
+                            budget
+                                .charge(chunk.len())
+                                .map_err(|_| JsonPayloadError::Overflow)?;
+                        }
                         body.extend_from_slice(&chunk);
                     }
                 }
-                Ok(serde_json::from_slice::<U>(&body)?)
+                if let Some(max) = max_depth {
+                    if !check_depth(&body, max) {
+                        return Err(JsonPayloadError::Depth);
+                    }
+                }
+                Ok(json_from_slice::<U>(&body)?)
             }
             .boxed_local(),
         );
@@ -0,0 +1,152 @@
+//! Streaming NDJSON responder
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::{Bytes, BytesMut};
+use futures_core::Stream;
+use futures_util::future::{ok, Ready};
+use pin_project::pin_project;
+use serde::Serialize;
+
+use crate::http::{Response, StatusCode};
+use crate::web::error::Error;
+use crate::web::request::HttpRequest;
+use crate::web::responder::Responder;
+
+/// Default size of the chunk sent to the peer
+const FLUSH_SIZE: usize = 8192;
+
+/// Newline delimited json responder.
+///
+/// Serializes a stream of serde values into `application/x-ndjson` body,
+/// one json document per line. Items are serialized only when the connection
+/// is ready to accept more data, so a complete result set never has to be
+/// loaded into memory. Serialized items are sent in chunks of up to 8Kb,
+/// buffered data is flushed as soon as the source stream has no ready items.
+///
+/// ```rust
+/// use futures::stream;
+/// use kayrx::web::{self, types, App, Error};
+/// use serde_derive::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Row {
+///     id: u64,
+/// }
+///
+/// async fn export() -> types::JsonLines<impl futures::Stream<Item = Result<Row, Error>>> {
+///     types::JsonLines(stream::iter((0..1000).map(|id| Ok(Row { id }))))
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/export").route(web::get().to(export))
+///     );
+/// }
+/// ```
+pub struct JsonLines<S>(pub S);
+
+impl<S> JsonLines<S> {
+    /// Deconstruct to an inner stream
+    pub fn into_inner(self) -> S {
+        self.0
+    }
+}
+
+impl<S, T, E> Responder for JsonLines<S>
+where
+    S: Stream<Item = Result<T, E>> + 'static,
+    T: Serialize + 'static,
+    E: Into<Error> + 'static,
+{
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        let stream = SerializeStream::new(self.0, |item: &T, buf: &mut BytesMut| {
+            buf.extend_from_slice(&serde_json::to_vec(item)?);
+            buf.extend_from_slice(b"\n");
+            Ok(())
+        });
+
+        ok(Response::build(StatusCode::OK)
+            .content_type("application/x-ndjson")
+            .streaming(stream))
+    }
+}
+
+/// Stream that serializes items of the inner stream into chunks of bytes
+#[pin_project]
+pub(crate) struct SerializeStream<S, F> {
+    #[pin]
+    stream: S,
+    encode: F,
+    buf: BytesMut,
+    done: bool,
+}
+
+impl<S, F, T, E> SerializeStream<S, F>
+where
+    S: Stream<Item = Result<T, E>>,
+    F: FnMut(&T, &mut BytesMut) -> Result<(), Error>,
+    E: Into<Error>,
+{
+    pub(crate) fn new(stream: S, encode: F) -> Self {
+        SerializeStream {
+            stream,
+            encode,
+            buf: BytesMut::new(),
+            done: false,
+        }
+    }
+}
+
+impl<S, F, T, E> Stream for SerializeStream<S, F>
+where
+    S: Stream<Item = Result<T, E>>,
+    F: FnMut(&T, &mut BytesMut) -> Result<(), Error>,
+    E: Into<Error>,
+{
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        loop {
+            if *this.done {
+                return if this.buf.is_empty() {
+                    Poll::Ready(None)
+                } else {
+                    Poll::Ready(Some(Ok(this.buf.split().freeze())))
+                };
+            }
+
+            match this.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(item))) => {
+                    if let Err(e) = (this.encode)(&item, this.buf) {
+                        *this.done = true;
+                        this.buf.clear();
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                    if this.buf.len() >= FLUSH_SIZE {
+                        return Poll::Ready(Some(Ok(this.buf.split().freeze())));
+                    }
+                }
+                Poll::Ready(Some(Err(e))) => {
+                    *this.done = true;
+                    this.buf.clear();
+                    return Poll::Ready(Some(Err(e.into())));
+                }
+                Poll::Ready(None) => *this.done = true,
+                Poll::Pending => {
+                    return if this.buf.is_empty() {
+                        Poll::Pending
+                    } else {
+                        Poll::Ready(Some(Ok(this.buf.split().freeze())))
+                    };
+                }
+            }
+        }
+    }
+}
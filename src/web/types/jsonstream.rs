@@ -0,0 +1,307 @@
+//! Streaming json extractor (NDJSON / json-seq)
+
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use bytes::BytesMut;
+use futures_core::Stream;
+use futures_util::future::{err, ok, Ready};
+use serde::de::DeserializeOwned;
+
+use crate::http::{HttpMessage, Payload};
+use crate::web::dev::Decompress;
+use crate::web::error::{Error, JsonPayloadError};
+use crate::web::extract::FromRequest;
+use crate::web::request::HttpRequest;
+
+/// Record separator used by `application/json-seq` (RFC 7464)
+const RS: u8 = 0x1E;
+
+/// Json stream extractor. Incrementally parses newline-delimited json
+/// (`application/x-ndjson`, `application/jsonl`) or json text sequences
+/// (`application/json-seq`) from request's payload.
+///
+/// Each item is deserialized as soon as it is complete, so the whole body
+/// never has to be buffered. Size of each item is limited,
+/// [**JsonStreamConfig**](struct.JsonStreamConfig.html) allows to configure
+/// extraction process.
+///
+/// ## Example
+///
+/// ```rust
+/// use futures::StreamExt;
+/// use kayrx::web::{self, types, App, Error};
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Event {
+///     name: String,
+/// }
+///
+/// async fn ingest(mut events: types::JsonStream<Event>) -> Result<String, Error> {
+///     let mut count = 0;
+///     while let Some(event) = events.next().await {
+///         let _event = event?;
+///         count += 1;
+///     }
+///     Ok(format!("Ingested {} events", count))
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/ingest").route(web::post().to(ingest))
+///     );
+/// }
+/// ```
+pub struct JsonStream<T> {
+    stream: Option<Decompress<Payload>>,
+    buf: BytesMut,
+    checked: usize,
+    limit: usize,
+    separator: u8,
+    eof: bool,
+    _t: PhantomData<T>,
+}
+
+// The stream does not ever project Pin to T
+impl<T> Unpin for JsonStream<T> {}
+
+impl<T> JsonStream<T>
+where
+    T: DeserializeOwned,
+{
+    /// Create `JsonStream` for request.
+    ///
+    /// Returns `JsonPayloadError::ContentType` if request's content type is
+    /// not one of supported json stream content types.
+    pub fn new(
+        req: &HttpRequest,
+        payload: &mut Payload,
+        ctype: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
+    ) -> Result<Self, JsonPayloadError> {
+        let separator = match req.mime_type() {
+            Ok(Some(mime)) => {
+                if mime.type_() == mime::APPLICATION && mime.subtype() == "json-seq" {
+                    RS
+                } else if (mime.type_() == mime::APPLICATION
+                    && (mime.subtype() == "x-ndjson"
+                        || mime.subtype() == "ndjson"
+                        || mime.subtype() == "jsonl"
+                        || mime.subtype() == "x-json-stream"))
+                    || ctype.as_ref().map_or(false, |predicate| predicate(mime))
+                {
+                    b'\n'
+                } else {
+                    return Err(JsonPayloadError::ContentType);
+                }
+            }
+            _ => return Err(JsonPayloadError::ContentType),
+        };
+
+        Ok(JsonStream {
+            stream: Some(crate::web::types::payload::decompress(payload.take(), req)),
+            buf: BytesMut::with_capacity(8192),
+            checked: 0,
+            limit: 65_536,
+            separator,
+            eof: false,
+            _t: PhantomData,
+        })
+    }
+
+    /// Change max size of a single item. By default max size is 64Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    fn parse(&mut self, len: usize) -> Option<Result<T, JsonPayloadError>> {
+        let item = self.buf.split_to(len);
+        let item = trim(&item);
+        if item.is_empty() {
+            None
+        } else if item.len() > self.limit {
+            Some(Err(JsonPayloadError::Overflow))
+        } else {
+            Some(serde_json::from_slice(item).map_err(JsonPayloadError::from))
+        }
+    }
+}
+
+impl<T> Stream for JsonStream<T>
+where
+    T: DeserializeOwned,
+{
+    type Item = Result<T, JsonPayloadError>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            // stream is terminated after an error
+            if this.stream.is_none() {
+                return Poll::Ready(None);
+            }
+
+            // check if there is a complete item in the buffer
+            if let Some(idx) = memchr::memchr(this.separator, &this.buf[this.checked..]) {
+                let len = this.checked + idx + 1;
+                this.checked = 0;
+                match this.parse(len) {
+                    Some(Err(e)) => {
+                        this.stream = None;
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                    Some(item) => return Poll::Ready(Some(item)),
+                    None => continue,
+                }
+            }
+            this.checked = this.buf.len();
+
+            if this.buf.len() > this.limit + 1 {
+                this.stream = None;
+                return Poll::Ready(Some(Err(JsonPayloadError::Overflow)));
+            }
+
+            if this.eof {
+                this.stream = None;
+                let len = this.buf.len();
+                return Poll::Ready(this.parse(len));
+            }
+
+            match Pin::new(this.stream.as_mut().unwrap()).poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => this.buf.extend_from_slice(&chunk),
+                Poll::Ready(Some(Err(e))) => {
+                    this.stream = None;
+                    return Poll::Ready(Some(Err(e.into())));
+                }
+                Poll::Ready(None) => this.eof = true,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+fn trim(mut item: &[u8]) -> &[u8] {
+    while let Some((first, rest)) = item.split_first() {
+        if first.is_ascii_whitespace() || *first == RS {
+            item = rest;
+        } else {
+            break;
+        }
+    }
+    while let Some((last, rest)) = item.split_last() {
+        if last.is_ascii_whitespace() || *last == RS {
+            item = rest;
+        } else {
+            break;
+        }
+    }
+    item
+}
+
+impl<T> FromRequest for JsonStream<T>
+where
+    T: DeserializeOwned + 'static,
+{
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+    type Config = JsonStreamConfig;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let (limit, ehandler, ctype) = req
+            .app_data::<Self::Config>()
+            .map(|c| (c.limit, c.ehandler.clone(), c.content_type.clone()))
+            .unwrap_or((65_536, None, None));
+
+        match JsonStream::new(req, payload, ctype) {
+            Ok(stream) => ok(stream.limit(limit)),
+            Err(e) => {
+                log::debug!(
+                    "Failed to create json stream from payload. \
+                     Request path: {}",
+                    req.path()
+                );
+                if let Some(ehandler) = ehandler {
+                    err((*ehandler)(e, req))
+                } else {
+                    err(e.into())
+                }
+            }
+        }
+    }
+}
+
+/// Json stream extractor configuration
+///
+/// ```rust
+/// use kayrx::web::{self, types, App};
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Event {
+///     name: String,
+/// }
+///
+/// async fn ingest(events: types::JsonStream<Event>) -> &'static str {
+///     "Ok"
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/ingest")
+///             // max size of a single event is 1kb
+///             .app_data(types::JsonStreamConfig::default().limit(1024))
+///             .route(web::post().to(ingest))
+///     );
+/// }
+/// ```
+#[derive(Clone)]
+pub struct JsonStreamConfig {
+    limit: usize,
+    ehandler: Option<Arc<dyn Fn(JsonPayloadError, &HttpRequest) -> Error + Send + Sync>>,
+    content_type: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
+}
+
+impl JsonStreamConfig {
+    /// Change max size of a single item. By default max size is 64Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Set custom error handler for content type errors
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(JsonPayloadError, &HttpRequest) -> Error + Send + Sync + 'static,
+    {
+        self.ehandler = Some(Arc::new(f));
+        self
+    }
+
+    /// Set predicate for additional allowed content types.
+    /// Items of such payloads are separated by new line.
+    pub fn content_type<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(mime::Mime) -> bool + Send + Sync + 'static,
+    {
+        self.content_type = Some(Arc::new(predicate));
+        self
+    }
+}
+
+impl Default for JsonStreamConfig {
+    fn default() -> Self {
+        JsonStreamConfig {
+            limit: 65_536,
+            ehandler: None,
+            content_type: None,
+        }
+    }
+}
@@ -1,15 +1,33 @@
 //! Web Helper types
 
+pub mod csv;
+mod header;
 pub(crate) mod form;
 pub(crate) mod json;
+pub(crate) mod jsonlines;
+mod jsonstream;
+mod patch;
 mod path;
+mod precondition;
 pub(crate) mod payload;
+#[cfg(feature = "protobuf")]
+mod protobuf;
 mod query;
 pub(crate) mod readlines;
+mod xml;
 
+pub use self::csv::CsvStream;
 pub use self::form::{Form, FormConfig};
-pub use self::json::{Json, JsonConfig};
+pub use self::header::{Header, HeaderConfig};
+pub use self::json::{Json, JsonConfig, JsonStreaming};
+pub use self::jsonlines::JsonLines;
+pub use self::jsonstream::{JsonStream, JsonStreamConfig};
+pub use self::patch::{JsonPatch, MergePatch, PatchConfig, PatchOperation};
 pub use self::path::{Path, PathConfig};
+pub use self::precondition::{Precondition, PreconditionFailed};
 pub use self::payload::{Payload, PayloadConfig};
+#[cfg(feature = "protobuf")]
+pub use self::protobuf::{Protobuf, ProtobufBody, ProtobufConfig};
 pub use self::query::{Query, QueryConfig};
-pub use self::readlines::Readlines;
\ No newline at end of file
+pub use self::readlines::Readlines;
+pub use self::xml::{Xml, XmlBody, XmlConfig};
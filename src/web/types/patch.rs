@@ -0,0 +1,506 @@
+//! Json patch (RFC 6902) and json merge patch (RFC 7396) extractors
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::{fmt, ops};
+
+use futures_util::future::{err, FutureExt, LocalBoxFuture};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::http::{HttpMessage, Payload};
+use crate::web::error::{Error, PatchError};
+use crate::web::extract::FromRequest;
+use crate::web::request::HttpRequest;
+use crate::web::types::json::JsonBody;
+
+/// Single json patch operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    /// Add value at the path
+    Add { path: String, value: Value },
+    /// Remove value at the path
+    Remove { path: String },
+    /// Replace existing value at the path
+    Replace { path: String, value: Value },
+    /// Move value from one location to another
+    Move { from: String, path: String },
+    /// Copy value from one location to another
+    Copy { from: String, path: String },
+    /// Check that value at the path is equal to the given value
+    Test { path: String, value: Value },
+}
+
+/// Json patch extractor.
+///
+/// Extracts RFC 6902 patch document from `application/json-patch+json`
+/// payload. Operations and their json pointers are validated during
+/// extraction, malformed documents are rejected with `400 Bad Request`,
+/// other content types with `415 Unsupported Media Type`.
+///
+/// Patch is applied atomically, if any operation fails the document
+/// is left untouched and `PatchError::Conflict` is returned.
+///
+/// [**PatchConfig**](struct.PatchConfig.html) allows to configure
+/// extraction process.
+///
+/// ## Example
+///
+/// ```rust
+/// use kayrx::web::{self, types, App, Error};
+/// use serde_derive::{Deserialize, Serialize};
+///
+/// #[derive(Deserialize, Serialize)]
+/// struct User {
+///     name: String,
+///     email: String,
+/// }
+///
+/// async fn patch_user(patch: types::JsonPatch) -> Result<types::Json<User>, Error> {
+///     let user = User { name: "John".to_owned(), email: "john@example.com".to_owned() };
+///     Ok(types::Json(patch.apply_to(&user)?))
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/user").route(web::patch().to(patch_user))
+///     );
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPatch(pub Vec<PatchOperation>);
+
+impl JsonPatch {
+    /// Parse and validate patch document
+    pub fn from_value(value: Value) -> Result<Self, PatchError> {
+        let ops: Vec<PatchOperation> = serde_json::from_value(value)
+            .map_err(|e| PatchError::Invalid(e.to_string()))?;
+
+        for op in &ops {
+            match op {
+                PatchOperation::Add { path, .. }
+                | PatchOperation::Remove { path }
+                | PatchOperation::Replace { path, .. }
+                | PatchOperation::Test { path, .. } => {
+                    parse_pointer(path)?;
+                }
+                PatchOperation::Move { from, path } => {
+                    let from_tokens = parse_pointer(from)?;
+                    let path_tokens = parse_pointer(path)?;
+                    if path_tokens.len() > from_tokens.len()
+                        && path_tokens.starts_with(&from_tokens)
+                    {
+                        return Err(PatchError::Invalid(format!(
+                            "can not move {} into its own child {}",
+                            from, path
+                        )));
+                    }
+                }
+                PatchOperation::Copy { from, path } => {
+                    parse_pointer(from)?;
+                    parse_pointer(path)?;
+                }
+            }
+        }
+        Ok(JsonPatch(ops))
+    }
+
+    /// Deconstruct to a list of operations
+    pub fn into_inner(self) -> Vec<PatchOperation> {
+        self.0
+    }
+
+    /// Apply patch to the json document
+    pub fn apply(&self, doc: &mut Value) -> Result<(), PatchError> {
+        let mut patched = doc.clone();
+        for op in &self.0 {
+            apply_operation(&mut patched, op)?;
+        }
+        *doc = patched;
+        Ok(())
+    }
+
+    /// Apply patch to the serializable value, and deserialize result back
+    pub fn apply_to<T>(&self, base: &T) -> Result<T, PatchError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let mut doc = serde_json::to_value(base)?;
+        self.apply(&mut doc)?;
+        Ok(serde_json::from_value(doc)?)
+    }
+}
+
+impl ops::Deref for JsonPatch {
+    type Target = [PatchOperation];
+
+    fn deref(&self) -> &[PatchOperation] {
+        &self.0
+    }
+}
+
+impl FromRequest for JsonPatch {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+    type Config = PatchConfig;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        read_patch(req, payload, "json-patch", |value| JsonPatch::from_value(value))
+    }
+}
+
+/// Json merge patch extractor.
+///
+/// Extracts RFC 7396 merge patch from `application/merge-patch+json`
+/// payload and applies it onto a base value provided by the handler.
+/// Members set to `null` are removed, objects are merged recursively,
+/// all other values are replaced.
+///
+/// [**PatchConfig**](struct.PatchConfig.html) allows to configure
+/// extraction process.
+///
+/// ## Example
+///
+/// ```rust
+/// use kayrx::web::{self, types, App, Error};
+/// use serde_derive::{Deserialize, Serialize};
+///
+/// #[derive(Deserialize, Serialize)]
+/// struct User {
+///     name: String,
+///     email: Option<String>,
+/// }
+///
+/// async fn patch_user(patch: types::MergePatch<User>) -> Result<types::Json<User>, Error> {
+///     let user = User { name: "John".to_owned(), email: None };
+///     Ok(types::Json(patch.apply(&user)?))
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/user").route(web::patch().to(patch_user))
+///     );
+/// }
+/// ```
+pub struct MergePatch<T> {
+    patch: Value,
+    _t: PhantomData<T>,
+}
+
+impl<T> MergePatch<T> {
+    /// Create merge patch from json value
+    pub fn new(patch: Value) -> Self {
+        MergePatch {
+            patch,
+            _t: PhantomData,
+        }
+    }
+
+    /// Deconstruct to the patch document
+    pub fn into_inner(self) -> Value {
+        self.patch
+    }
+
+    /// Apply patch to the json document
+    pub fn apply_value(&self, doc: &mut Value) {
+        merge(doc, &self.patch)
+    }
+}
+
+impl<T> MergePatch<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Apply patch onto the base value and deserialize result
+    pub fn apply(&self, base: &T) -> Result<T, PatchError> {
+        let mut doc = serde_json::to_value(base)?;
+        merge(&mut doc, &self.patch);
+        Ok(serde_json::from_value(doc)?)
+    }
+}
+
+impl<T> ops::Deref for MergePatch<T> {
+    type Target = Value;
+
+    fn deref(&self) -> &Value {
+        &self.patch
+    }
+}
+
+impl<T> fmt::Debug for MergePatch<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MergePatch: {:?}", self.patch)
+    }
+}
+
+impl<T: 'static> FromRequest for MergePatch<T> {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+    type Config = PatchConfig;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        read_patch(req, payload, "merge-patch", |value| Ok(MergePatch::new(value)))
+    }
+}
+
+/// Patch extractors configuration
+///
+/// ```rust
+/// use kayrx::web::{self, types, App};
+///
+/// async fn index(patch: types::JsonPatch) -> String {
+///     format!("{} operations", patch.len())
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/index.html")
+///             // max patch document size is 4kb
+///             .app_data(types::PatchConfig::default().limit(4096))
+///             .route(web::patch().to(index))
+///     );
+/// }
+/// ```
+#[derive(Clone)]
+pub struct PatchConfig {
+    limit: usize,
+    ehandler: Option<Arc<dyn Fn(PatchError, &HttpRequest) -> Error + Send + Sync>>,
+}
+
+impl PatchConfig {
+    /// Change max size of payload. By default max size is 32Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Set custom error handler
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(PatchError, &HttpRequest) -> Error + Send + Sync + 'static,
+    {
+        self.ehandler = Some(Arc::new(f));
+        self
+    }
+}
+
+impl Default for PatchConfig {
+    fn default() -> Self {
+        PatchConfig {
+            limit: 32768,
+            ehandler: None,
+        }
+    }
+}
+
+fn read_patch<T, F>(
+    req: &HttpRequest,
+    payload: &mut Payload,
+    subtype: &'static str,
+    f: F,
+) -> LocalBoxFuture<'static, Result<T, Error>>
+where
+    T: 'static,
+    F: FnOnce(Value) -> Result<T, PatchError> + 'static,
+{
+    let req2 = req.clone();
+    let (limit, ehandler) = req
+        .app_data::<PatchConfig>()
+        .map(|c| (c.limit, c.ehandler.clone()))
+        .unwrap_or((32768, None));
+
+    let handle = move |e: PatchError| {
+        log::debug!(
+            "Failed to extract patch document from payload. \
+             Request path: {}",
+            req2.path()
+        );
+        if let Some(ehandler) = ehandler {
+            (*ehandler)(e, &req2)
+        } else {
+            e.into()
+        }
+    };
+
+    // check content-type
+    let valid = if let Ok(Some(mime)) = req.mime_type() {
+        mime.type_() == mime::APPLICATION
+            && mime.subtype() == subtype
+            && mime.suffix() == Some(mime::JSON)
+    } else {
+        false
+    };
+    if !valid {
+        return err(handle(PatchError::ContentType)).boxed_local();
+    }
+
+    JsonBody::<Value>::new(req, payload, None)
+        .limit(limit)
+        .map(move |res| res.map_err(PatchError::from).and_then(f).map_err(handle))
+        .boxed_local()
+}
+
+/// Parse json pointer (RFC 6901) into reference tokens
+fn parse_pointer(pointer: &str) -> Result<Vec<String>, PatchError> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    if !pointer.starts_with('/') {
+        return Err(PatchError::Invalid(format!("invalid json pointer {}", pointer)));
+    }
+
+    let mut tokens = Vec::new();
+    for token in pointer[1..].split('/') {
+        let mut unescaped = String::with_capacity(token.len());
+        let mut chars = token.chars();
+        while let Some(ch) = chars.next() {
+            if ch == '~' {
+                match chars.next() {
+                    Some('0') => unescaped.push('~'),
+                    Some('1') => unescaped.push('/'),
+                    _ => {
+                        return Err(PatchError::Invalid(format!(
+                            "invalid json pointer {}",
+                            pointer
+                        )))
+                    }
+                }
+            } else {
+                unescaped.push(ch);
+            }
+        }
+        tokens.push(unescaped);
+    }
+    Ok(tokens)
+}
+
+/// Parse array index, `-` is allowed only if `append` is true
+fn parse_index(token: &str, len: usize, append: bool) -> Option<usize> {
+    if append && token == "-" {
+        return Some(len);
+    }
+    // leading zeros are not allowed
+    if token.is_empty() || (token.len() > 1 && token.starts_with('0')) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+fn lookup<'a>(doc: &'a mut Value, tokens: &[String]) -> Option<&'a mut Value> {
+    let mut target = doc;
+    for token in tokens {
+        target = match target {
+            Value::Object(map) => map.get_mut(token)?,
+            Value::Array(arr) => {
+                let idx = parse_index(token, arr.len(), false)?;
+                arr.get_mut(idx)?
+            }
+            _ => return None,
+        };
+    }
+    Some(target)
+}
+
+fn conflict(pointer: &str) -> PatchError {
+    PatchError::Conflict(format!("path {} does not exist", pointer))
+}
+
+fn add(doc: &mut Value, pointer: &str, value: Value) -> Result<(), PatchError> {
+    let tokens = parse_pointer(pointer)?;
+    let (last, parent) = match tokens.split_last() {
+        Some(item) => item,
+        None => {
+            *doc = value;
+            return Ok(());
+        }
+    };
+
+    match lookup(doc, parent) {
+        Some(Value::Object(map)) => {
+            map.insert(last.clone(), value);
+            Ok(())
+        }
+        Some(Value::Array(arr)) => match parse_index(last, arr.len(), true) {
+            Some(idx) if idx <= arr.len() => {
+                arr.insert(idx, value);
+                Ok(())
+            }
+            _ => Err(conflict(pointer)),
+        },
+        _ => Err(conflict(pointer)),
+    }
+}
+
+fn remove(doc: &mut Value, pointer: &str) -> Result<Value, PatchError> {
+    let tokens = parse_pointer(pointer)?;
+    let (last, parent) = match tokens.split_last() {
+        Some(item) => item,
+        None => return Err(PatchError::Conflict("can not remove root".to_owned())),
+    };
+
+    match lookup(doc, parent) {
+        Some(Value::Object(map)) => map.remove(last).ok_or_else(|| conflict(pointer)),
+        Some(Value::Array(arr)) => match parse_index(last, arr.len(), false) {
+            Some(idx) if idx < arr.len() => Ok(arr.remove(idx)),
+            _ => Err(conflict(pointer)),
+        },
+        _ => Err(conflict(pointer)),
+    }
+}
+
+fn apply_operation(doc: &mut Value, op: &PatchOperation) -> Result<(), PatchError> {
+    match op {
+        PatchOperation::Add { path, value } => add(doc, path, value.clone()),
+        PatchOperation::Remove { path } => remove(doc, path).map(|_| ()),
+        PatchOperation::Replace { path, value } => {
+            let tokens = parse_pointer(path)?;
+            let target = lookup(doc, &tokens).ok_or_else(|| conflict(path))?;
+            *target = value.clone();
+            Ok(())
+        }
+        PatchOperation::Move { from, path } => {
+            if from != path {
+                let value = remove(doc, from)?;
+                add(doc, path, value)?;
+            }
+            Ok(())
+        }
+        PatchOperation::Copy { from, path } => {
+            let tokens = parse_pointer(from)?;
+            let value = lookup(doc, &tokens).ok_or_else(|| conflict(from))?.clone();
+            add(doc, path, value)
+        }
+        PatchOperation::Test { path, value } => {
+            let tokens = parse_pointer(path)?;
+            match lookup(doc, &tokens) {
+                Some(target) if target == value => Ok(()),
+                Some(_) => Err(PatchError::Conflict(format!("test failed for {}", path))),
+                None => Err(conflict(path)),
+            }
+        }
+    }
+}
+
+/// Merge patch into the target document (RFC 7396)
+fn merge(target: &mut Value, patch: &Value) {
+    if let Value::Object(patch) = patch {
+        if !target.is_object() {
+            *target = Value::Object(Map::new());
+        }
+        if let Value::Object(map) = target {
+            for (key, value) in patch {
+                if value.is_null() {
+                    map.remove(key);
+                } else {
+                    merge(map.entry(key.as_str()).or_insert(Value::Null), value);
+                }
+            }
+        }
+    } else {
+        *target = patch.clone();
+    }
+}
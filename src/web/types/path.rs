@@ -9,7 +9,7 @@ use futures_util::future::{ready, Ready};
 use serde::de;
 
 use crate::web::dev::Payload;
-use crate::web::error::PathError;
+use crate::web::error::{DeserializeError, PathError};
 use crate::web::request::HttpRequest;
 use crate::web::FromRequest;
 
@@ -173,8 +173,9 @@ where
             .unwrap_or(None);
 
         ready(
-            de::Deserialize::deserialize(PathDeserializer::new(req.match_info()))
+            serde_path_to_error::deserialize(PathDeserializer::new(req.match_info()))
                 .map(|inner| Path { inner })
+                .map_err(DeserializeError::from)
                 .map_err(move |e| {
                     log::debug!(
                         "Failed during Path extractor deserialization. \
@@ -5,6 +5,7 @@ use std::str;
 use std::task::{Context, Poll};
 
 use crate::http::error::{Error, ErrorBadRequest, PayloadError};
+use crate::http::encoding::DecompressLimits;
 use crate::http::HttpMessage;
 use bytes::{Bytes, BytesMut};
 use encoding_rs::UTF_8;
@@ -15,9 +16,25 @@ use mime::Mime;
 
 use crate::web::dev;
 use crate::web::extract::FromRequest;
+use crate::web::middleware::memory::MemoryBudget;
 use crate::http::header;
 use crate::web::request::HttpRequest;
 
+/// Create decompressing stream for request payload.
+///
+/// `DecompressLimits` registered with `App::app_data()` are applied
+/// to compressed payloads, otherwise default limits are used.
+pub(crate) fn decompress<S>(payload: S, req: &HttpRequest) -> dev::Decompress<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>>,
+{
+    let limits = req
+        .app_data::<DecompressLimits>()
+        .copied()
+        .unwrap_or_default();
+    dev::Decompress::from_headers(payload, req.headers()).limits(limits)
+}
+
 /// Payload extractor returns request 's payload stream.
 ///
 /// ## Example
@@ -237,10 +254,36 @@ impl FromRequest for String {
     }
 }
 /// Payload configuration for request's payload.
+///
+/// Configuration registered with `App::app_data()`, `Scope::app_data()` or
+/// `Resource::app_data()` limits payload of `Bytes` and `String` extractors.
+/// Its size limit also applies to `Json` and `Form` extractors without their
+/// own configuration. Oversized payload is rejected with
+/// *413 Payload Too Large* response.
+///
+/// ```rust
+/// use kayrx::web::{self, types, App};
+///
+/// fn main() {
+///     let app = App::new()
+///         // 1Mb limit for all extractors of the application
+///         .app_data(types::PayloadConfig::new(1_048_576))
+///         .service(
+///             web::resource("/upload")
+///                 // text only, up to 64Kb
+///                 .app_data(
+///                     types::PayloadConfig::new(65_536)
+///                         .mimetype(mime::TEXT_PLAIN)
+///                         .mimetype(mime::TEXT_CSV),
+///                 )
+///                 .route(web::post().to(|body: String| async move { body })),
+///         );
+/// }
+/// ```
 #[derive(Clone)]
 pub struct PayloadConfig {
     limit: usize,
-    mimetype: Option<Mime>,
+    mimetypes: Vec<Mime>,
 }
 
 impl PayloadConfig {
@@ -257,19 +300,33 @@ impl PayloadConfig {
         self
     }
 
-    /// Set required mime-type of the request. By default mime type is not
+    /// Add allowed mime-type of the request. By default mime type is not
     /// enforced.
+    ///
+    /// Mime-type parameters, i.e. charset, are ignored by the check.
     pub fn mimetype(mut self, mt: Mime) -> Self {
-        self.mimetype = Some(mt);
+        self.mimetypes.push(mt);
         self
     }
 
+    /// Size limit for extractors with own configuration,
+    /// `default` is used if payload config is not registered
+    pub(crate) fn limit_for(req: &HttpRequest, default: usize) -> usize {
+        req.app_data::<PayloadConfig>()
+            .map(|cfg| cfg.limit)
+            .unwrap_or(default)
+    }
+
     fn check_mimetype(&self, req: &HttpRequest) -> Result<(), Error> {
         // check content-type
-        if let Some(ref mt) = self.mimetype {
+        if !self.mimetypes.is_empty() {
             match req.mime_type() {
                 Ok(Some(ref req_mt)) => {
-                    if mt != req_mt {
+                    if !self
+                        .mimetypes
+                        .iter()
+                        .any(|mt| mt.essence_str() == req_mt.essence_str())
+                    {
                         return Err(ErrorBadRequest("Unexpected Content-Type"));
                     }
                 }
@@ -289,7 +346,7 @@ impl Default for PayloadConfig {
     fn default() -> Self {
         PayloadConfig {
             limit: 262_144,
-            mimetype: None,
+            mimetypes: Vec::new(),
         }
     }
 }
@@ -304,6 +361,7 @@ impl Default for PayloadConfig {
 pub struct HttpMessageBody {
     limit: usize,
     length: Option<usize>,
+    budget: Option<MemoryBudget>,
     stream: Option<dev::Decompress<dev::Payload>>,
     err: Option<PayloadError>,
     fut: Option<LocalBoxFuture<'static, Result<Bytes, PayloadError>>>,
@@ -325,12 +383,13 @@ impl HttpMessageBody {
             }
         }
 
-        let stream = Some(dev::Decompress::from_headers(payload.take(), req.headers()));
+        let stream = Some(decompress(payload.take(), req));
 
         HttpMessageBody {
             stream,
             limit: 262_144,
             length: len,
+            budget: MemoryBudget::of(req),
             fut: None,
             err: None,
         }
@@ -349,6 +408,7 @@ impl HttpMessageBody {
             fut: None,
             err: Some(e),
             length: None,
+            budget: None,
         }
     }
 }
@@ -373,6 +433,7 @@ impl Future for HttpMessageBody {
 
         // future
         let limit = self.limit;
+        let budget = self.budget.take();
         let mut stream = self.stream.take().unwrap();
         self.fut = Some(
             async move {
@@ -383,6 +444,11 @@ impl Future for HttpMessageBody {
                     if body.len() + chunk.len() > limit {
                         return Err(PayloadError::Overflow);
                     } else {
+                        if let Some(ref budget) = budget {
+                            budget
+                                .charge(chunk.len())
+                                .map_err(|_| PayloadError::Overflow)?;
+                        }
                         body.extend_from_slice(&chunk);
                     }
                 }
@@ -0,0 +1,196 @@
+//! Conditional request helpers for optimistic concurrency control
+
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use futures_util::future::{ok, Ready};
+
+use crate::http::error::{Error, ResponseError};
+use crate::http::header::{EntityTag, ETag, IfMatch, IfUnmodifiedSince};
+use crate::http::{HttpMessage, Payload, Response, StatusCode};
+use crate::web::extract::FromRequest;
+use crate::web::request::HttpRequest;
+use crate::web::responder::Responder;
+
+/// Validators sent by the client with a state changing request.
+///
+/// Extracts `If-Match` and `If-Unmodified-Since` headers, so `PUT`/`PATCH`/`DELETE`
+/// handlers can implement lost-update protection: the handler loads current
+/// state of the resource and calls [`check`](#method.check) with its etag
+/// and modification time before applying the change.
+///
+/// Evaluation follows RFC 7232 section 6: `If-Unmodified-Since` is ignored
+/// when `If-Match` is present, `If-Match` validators are compared with
+/// strong comparison.
+///
+/// ## Example
+///
+/// ```rust
+/// use kayrx::http::header::EntityTag;
+/// use kayrx::web::{self, types, App, Error, HttpResponse};
+///
+/// async fn update(cond: types::Precondition, body: String) -> Result<HttpResponse, Error> {
+///     let current = EntityTag::strong("v1".to_owned());
+///     cond.check(Some(&current), None)?;
+///
+///     // store body ...
+///     Ok(HttpResponse::NoContent().finish())
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/doc").route(web::put().to(update))
+///     );
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Precondition {
+    if_match: Option<IfMatch>,
+    if_unmodified_since: Option<SystemTime>,
+}
+
+impl Precondition {
+    /// Read validators from request headers.
+    ///
+    /// Headers that can not be parsed are ignored.
+    pub fn from_req(req: &HttpRequest) -> Self {
+        Precondition {
+            if_match: req.get_header::<IfMatch>(),
+            if_unmodified_since: req
+                .get_header::<IfUnmodifiedSince>()
+                .map(|IfUnmodifiedSince(date)| date.into()),
+        }
+    }
+
+    /// `If-Match` header value, if present
+    pub fn if_match(&self) -> Option<&IfMatch> {
+        self.if_match.as_ref()
+    }
+
+    /// `If-Unmodified-Since` header value, if present
+    pub fn if_unmodified_since(&self) -> Option<SystemTime> {
+        self.if_unmodified_since
+    }
+
+    /// Returns true if the request carries any validator
+    pub fn is_conditional(&self) -> bool {
+        self.if_match.is_some() || self.if_unmodified_since.is_some()
+    }
+
+    /// Evaluate `If-Match` against current etag of the resource.
+    ///
+    /// `None` etag means the resource does not exist. Returns true if
+    /// header is not present.
+    pub fn match_etag(&self, etag: Option<&EntityTag>) -> bool {
+        match self.if_match {
+            None => true,
+            Some(IfMatch::Any) => etag.is_some(),
+            Some(IfMatch::Items(ref items)) => match etag {
+                Some(etag) => items.iter().any(|item| item.strong_eq(etag)),
+                None => false,
+            },
+        }
+    }
+
+    /// Evaluate `If-Unmodified-Since` against last modification time of
+    /// the resource. Returns true if header is not present or the resource
+    /// modification time is unknown.
+    pub fn unmodified_since(&self, last_modified: Option<SystemTime>) -> bool {
+        match (self.if_unmodified_since, last_modified) {
+            (Some(since), Some(modified)) => {
+                // http dates have one second resolution
+                match (since.duration_since(UNIX_EPOCH), modified.duration_since(UNIX_EPOCH)) {
+                    (Ok(since), Ok(modified)) => modified.as_secs() <= since.as_secs(),
+                    _ => true,
+                }
+            }
+            _ => true,
+        }
+    }
+
+    /// Evaluate all preconditions against current state of the resource.
+    ///
+    /// Returns `PreconditionFailed` error carrying current etag if the
+    /// request must not be applied.
+    pub fn check(
+        &self,
+        etag: Option<&EntityTag>,
+        last_modified: Option<SystemTime>,
+    ) -> Result<(), PreconditionFailed> {
+        let passed = if self.if_match.is_some() {
+            self.match_etag(etag)
+        } else {
+            self.unmodified_since(last_modified)
+        };
+
+        if passed {
+            Ok(())
+        } else {
+            Err(PreconditionFailed {
+                etag: etag.cloned(),
+            })
+        }
+    }
+}
+
+impl FromRequest for Precondition {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ok(Precondition::from_req(req))
+    }
+}
+
+/// `412 Precondition Failed` response.
+///
+/// Can be returned from a handler directly or used as an error.
+/// Current etag of the resource is sent to the client, if known.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PreconditionFailed {
+    etag: Option<EntityTag>,
+}
+
+impl PreconditionFailed {
+    /// Create response without etag
+    pub fn new() -> Self {
+        PreconditionFailed::default()
+    }
+
+    /// Set current etag of the resource
+    pub fn etag(mut self, etag: EntityTag) -> Self {
+        self.etag = Some(etag);
+        self
+    }
+}
+
+impl fmt::Display for PreconditionFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Precondition failed")
+    }
+}
+
+impl ResponseError for PreconditionFailed {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::PRECONDITION_FAILED
+    }
+
+    fn error_response(&self) -> Response {
+        let mut res = Response::build(StatusCode::PRECONDITION_FAILED);
+        if let Some(ref etag) = self.etag {
+            res.set(ETag(etag.clone()));
+        }
+        res.finish()
+    }
+}
+
+impl Responder for PreconditionFailed {
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        ok(self.error_response())
+    }
+}
@@ -0,0 +1,477 @@
+//! Protobuf extractor/responder with grpc-web framing support
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::{fmt, ops};
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use futures_util::future::{err, ok, FutureExt, LocalBoxFuture, Ready};
+use futures_util::StreamExt;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use prost::Message;
+
+use crate::http::error::{ErrorInternalServerError, InternalError, ResponseError};
+use crate::http::{header::CONTENT_LENGTH, StatusCode};
+use crate::http::{HttpMessage, Payload, Response};
+
+use crate::web::dev::Decompress;
+use crate::web::error::{Error, ProtobufPayloadError};
+use crate::web::extract::FromRequest;
+use crate::web::middleware::memory::MemoryBudget;
+use crate::web::request::HttpRequest;
+use crate::web::responder::Responder;
+
+/// grpc-web frame flag of a trailers frame
+const TRAILERS_FLAG: u8 = 0x80;
+
+/// grpc status codes
+const GRPC_RESOURCE_EXHAUSTED: u8 = 8;
+const GRPC_INTERNAL: u8 = 13;
+
+/// Characters that must be percent-encoded in `grpc-message`
+const GRPC_MESSAGE: &AsciiSet = &CONTROLS.add(b'%');
+
+/// Wire format of the protobuf payload
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    /// Plain protobuf message
+    Proto,
+    /// Length prefixed grpc-web frames
+    GrpcWeb,
+    /// Base64 encoded grpc-web frames
+    GrpcWebText,
+}
+
+impl Format {
+    fn from_req(req: &HttpRequest) -> Option<Format> {
+        let mime = match req.mime_type() {
+            Ok(Some(mime)) => mime,
+            _ => return None,
+        };
+        if mime.type_() != mime::APPLICATION {
+            return None;
+        }
+        match mime.subtype().as_str() {
+            "protobuf" | "x-protobuf" => Some(Format::Proto),
+            "grpc-web" => match mime.suffix() {
+                None => Some(Format::GrpcWeb),
+                Some(suffix) if suffix == "proto" => Some(Format::GrpcWeb),
+                _ => None,
+            },
+            "grpc-web-text" => match mime.suffix() {
+                None => Some(Format::GrpcWebText),
+                Some(suffix) if suffix == "proto" => Some(Format::GrpcWebText),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Format::Proto => "application/protobuf",
+            Format::GrpcWeb => "application/grpc-web+proto",
+            Format::GrpcWebText => "application/grpc-web-text+proto",
+        }
+    }
+
+    /// grpc-web-text frames are base64 encoded
+    fn encode(self, frames: Bytes) -> Bytes {
+        match self {
+            Format::GrpcWebText => Bytes::from(base64::encode(&frames)),
+            _ => frames,
+        }
+    }
+}
+
+/// Protobuf helper
+///
+/// Protobuf can be used for extracting typed information from request's
+/// payload and for protobuf response generation. The type `T` must
+/// implement `prost::Message`.
+///
+/// Besides plain `application/protobuf` (and `application/x-protobuf`)
+/// payloads, [gRPC-web](https://github.com/grpc/grpc/blob/master/doc/PROTOCOL-WEB.md)
+/// requests (`application/grpc-web+proto` and `application/grpc-web-text+proto`)
+/// are supported, so browser grpc-web clients can call handlers directly.
+/// Responder uses the same wire format as the request: grpc-web responses
+/// are framed and carry `grpc-status` trailers. Decode errors of grpc-web
+/// requests are reported the way grpc-web clients expect: `200 OK` response
+/// with `grpc-status` and `grpc-message` trailers.
+///
+/// [**ProtobufConfig**](struct.ProtobufConfig.html) allows to configure
+/// extraction process.
+///
+/// ## Example
+///
+/// ```rust
+/// use kayrx::web::{self, types, App};
+///
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// pub struct HelloRequest {
+///     #[prost(string, tag = "1")]
+///     pub name: String,
+/// }
+///
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// pub struct HelloReply {
+///     #[prost(string, tag = "1")]
+///     pub message: String,
+/// }
+///
+/// async fn say_hello(req: types::Protobuf<HelloRequest>) -> types::Protobuf<HelloReply> {
+///     types::Protobuf(HelloReply {
+///         message: format!("Hello {}!", req.name),
+///     })
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/helloworld.Greeter/SayHello")
+///             .route(web::post().to(say_hello))
+///     );
+/// }
+/// ```
+pub struct Protobuf<T>(pub T);
+
+impl<T> Protobuf<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for Protobuf<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for Protobuf<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> fmt::Debug for Protobuf<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Protobuf: {:?}", self.0)
+    }
+}
+
+impl<T: Message> Responder for Protobuf<T> {
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        let format = Format::from_req(req).unwrap_or(Format::Proto);
+
+        let mut msg = BytesMut::with_capacity(self.0.encoded_len());
+        if let Err(e) = self.0.encode(&mut msg) {
+            return err(ErrorInternalServerError(e));
+        }
+
+        let body = match format {
+            Format::Proto => msg.freeze(),
+            Format::GrpcWeb | Format::GrpcWebText => {
+                let mut buf = BytesMut::with_capacity(msg.len() + 40);
+                buf.put_u8(0);
+                buf.put_u32(msg.len() as u32);
+                buf.extend_from_slice(&msg);
+                grpc_web_trailers(&mut buf, 0, "");
+                format.encode(buf.freeze())
+            }
+        };
+
+        ok(Response::build(StatusCode::OK)
+            .content_type(format.content_type())
+            .body(body))
+    }
+}
+
+/// Append trailers frame
+fn grpc_web_trailers(buf: &mut BytesMut, status: u8, message: &str) {
+    let trailers = format!("grpc-status:{}\r\ngrpc-message:{}\r\n", status, message);
+
+    buf.put_u8(TRAILERS_FLAG);
+    buf.put_u32(trailers.len() as u32);
+    buf.extend_from_slice(trailers.as_bytes());
+}
+
+/// Trailers-only grpc-web response for the payload error.
+///
+/// grpc-web clients do not check http status, so error is reported
+/// with `grpc-status` in `200 OK` response.
+fn grpc_web_error(err: ProtobufPayloadError, format: Format) -> Error {
+    let status = match err {
+        ProtobufPayloadError::Overflow => GRPC_RESOURCE_EXHAUSTED,
+        ProtobufPayloadError::Payload(ref e)
+            if e.status_code() == StatusCode::PAYLOAD_TOO_LARGE =>
+        {
+            GRPC_RESOURCE_EXHAUSTED
+        }
+        _ => GRPC_INTERNAL,
+    };
+    let message = utf8_percent_encode(&err.to_string(), GRPC_MESSAGE).to_string();
+
+    let mut buf = BytesMut::with_capacity(message.len() + 40);
+    grpc_web_trailers(&mut buf, status, &message);
+    let res = Response::build(StatusCode::OK)
+        .content_type(format.content_type())
+        .header("grpc-status", status.to_string())
+        .header("grpc-message", message)
+        .body(format.encode(buf.freeze()));
+    InternalError::from_response(err, res).into()
+}
+
+/// Extract message from the first data frame of grpc-web payload
+fn grpc_web_message(mut body: Bytes) -> Result<Bytes, ProtobufPayloadError> {
+    if body.len() < 5 || body[0] & TRAILERS_FLAG != 0 {
+        return Err(ProtobufPayloadError::Framing);
+    }
+    if body[0] != 0 {
+        // compressed messages are not supported
+        return Err(ProtobufPayloadError::Framing);
+    }
+    body.advance(1);
+    let len = body.get_u32() as usize;
+    if body.len() < len {
+        return Err(ProtobufPayloadError::Framing);
+    }
+    Ok(body.split_to(len))
+}
+
+/// Protobuf extractor. Allow to extract typed information from request's
+/// payload.
+///
+/// [**ProtobufConfig**](struct.ProtobufConfig.html) allows to configure
+/// extraction process.
+impl<T> FromRequest for Protobuf<T>
+where
+    T: Message + Default + 'static,
+{
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+    type Config = ProtobufConfig;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let req2 = req.clone();
+        let format = Format::from_req(req);
+        let (limit, err) = req
+            .app_data::<Self::Config>()
+            .map(|c| (c.limit, c.ehandler.clone()))
+            .unwrap_or((262_144, None));
+
+        ProtobufBody::new(req, payload)
+            .limit(limit)
+            .map(move |res| match res {
+                Err(e) => {
+                    log::debug!(
+                        "Failed to decode Protobuf from payload. \
+                         Request path: {}",
+                        req2.path()
+                    );
+                    match (err, format) {
+                        (Some(err), _) => Err((*err)(e, &req2)),
+                        (None, Some(format)) if format != Format::Proto => {
+                            Err(grpc_web_error(e, format))
+                        }
+                        (None, _) => Err(e.into()),
+                    }
+                }
+                Ok(data) => Ok(Protobuf(data)),
+            })
+            .boxed_local()
+    }
+}
+
+/// Protobuf extractor configuration
+///
+/// ```rust
+/// use kayrx::web::{self, types, App};
+///
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// pub struct Upload {
+///     #[prost(bytes, tag = "1")]
+///     pub data: Vec<u8>,
+/// }
+///
+/// async fn index(upload: types::Protobuf<Upload>) -> String {
+///     format!("Received {} bytes", upload.data.len())
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/upload")
+///             // max payload size is 1mb
+///             .app_data(types::ProtobufConfig::default().limit(1_048_576))
+///             .route(web::post().to(index))
+///     );
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ProtobufConfig {
+    limit: usize,
+    ehandler:
+        Option<Arc<dyn Fn(ProtobufPayloadError, &HttpRequest) -> Error + Send + Sync>>,
+}
+
+impl ProtobufConfig {
+    /// Change max size of payload. By default max size is 256Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Set custom error handler
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(ProtobufPayloadError, &HttpRequest) -> Error + Send + Sync + 'static,
+    {
+        self.ehandler = Some(Arc::new(f));
+        self
+    }
+}
+
+impl Default for ProtobufConfig {
+    fn default() -> Self {
+        ProtobufConfig {
+            limit: 262_144,
+            ehandler: None,
+        }
+    }
+}
+
+/// Request's payload protobuf parser, it resolves to a decoded `T` value.
+///
+/// Returns error:
+///
+/// * content type is not `application/protobuf`, `application/x-protobuf`,
+///   `application/grpc-web+proto` or `application/grpc-web-text+proto`
+/// * content length is greater than 256k
+/// * grpc-web payload is not properly framed
+pub struct ProtobufBody<U> {
+    limit: usize,
+    length: Option<usize>,
+    budget: Option<MemoryBudget>,
+    format: Format,
+    stream: Option<Decompress<Payload>>,
+    err: Option<ProtobufPayloadError>,
+    fut: Option<LocalBoxFuture<'static, Result<U, ProtobufPayloadError>>>,
+}
+
+impl<U> ProtobufBody<U>
+where
+    U: Message + Default + 'static,
+{
+    /// Create `ProtobufBody` for request.
+    pub fn new(req: &HttpRequest, payload: &mut Payload) -> Self {
+        let format = match Format::from_req(req) {
+            Some(format) => format,
+            None => {
+                return ProtobufBody {
+                    limit: 262_144,
+                    length: None,
+                    budget: None,
+                    format: Format::Proto,
+                    stream: None,
+                    fut: None,
+                    err: Some(ProtobufPayloadError::ContentType),
+                }
+            }
+        };
+
+        let len = req
+            .headers()
+            .get(&CONTENT_LENGTH)
+            .and_then(|l| l.to_str().ok())
+            .and_then(|s| s.parse::<usize>().ok());
+
+        let payload = crate::web::types::payload::decompress(payload.take(), req);
+
+        ProtobufBody {
+            limit: 262_144,
+            length: len,
+            budget: MemoryBudget::of(req),
+            format,
+            stream: Some(payload),
+            fut: None,
+            err: None,
+        }
+    }
+
+    /// Change max size of payload. By default max size is 256Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+impl<U> Future for ProtobufBody<U>
+where
+    U: Message + Default + 'static,
+{
+    type Output = Result<U, ProtobufPayloadError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(ref mut fut) = self.fut {
+            return Pin::new(fut).poll(cx);
+        }
+
+        if let Some(err) = self.err.take() {
+            return Poll::Ready(Err(err));
+        }
+
+        let limit = self.limit;
+        let budget = self.budget.take();
+        if let Some(len) = self.length.take() {
+            if len > limit {
+                return Poll::Ready(Err(ProtobufPayloadError::Overflow));
+            }
+        }
+        let format = self.format;
+        let mut stream = self.stream.take().unwrap();
+
+        self.fut = Some(
+            async move {
+                let mut body = BytesMut::with_capacity(8192);
+
+                while let Some(item) = stream.next().await {
+                    let chunk = item?;
+                    if (body.len() + chunk.len()) > limit {
+                        return Err(ProtobufPayloadError::Overflow);
+                    } else {
+                        if let Some(ref budget) = budget {
+                            budget
+                                .charge(chunk.len())
+                                .map_err(|_| ProtobufPayloadError::Overflow)?;
+                        }
+                        body.extend_from_slice(&chunk);
+                    }
+                }
+
+                let msg = match format {
+                    Format::Proto => body.freeze(),
+                    Format::GrpcWeb => grpc_web_message(body.freeze())?,
+                    Format::GrpcWebText => {
+                        let body = base64::decode(&body)
+                            .map_err(|_| ProtobufPayloadError::Framing)?;
+                        grpc_web_message(Bytes::from(body))?
+                    }
+                };
+                Ok(U::decode(msg)?)
+            }
+            .boxed_local(),
+        );
+
+        self.poll(cx)
+    }
+}
@@ -9,7 +9,7 @@ use serde::de;
 use serde_urlencoded;
 
 use crate::web::dev::Payload;
-use crate::web::error::QueryPayloadError;
+use crate::web::error::{DeserializeError, QueryPayloadError};
 use crate::web::extract::FromRequest;
 use crate::web::request::HttpRequest;
 
@@ -66,12 +66,18 @@ impl<T> Query<T> {
     where
         T: de::DeserializeOwned,
     {
-        serde_urlencoded::from_str::<T>(query_str)
+        deserialize_query::<T>(query_str)
             .map(|val| Ok(Query(val)))
             .unwrap_or_else(move |e| Err(QueryPayloadError::Deserialize(e)))
     }
 }
 
+/// Deserialize query string and keep path of the field that failed
+fn deserialize_query<T: de::DeserializeOwned>(query: &str) -> Result<T, DeserializeError> {
+    let de = serde_urlencoded::Deserializer::new(url::form_urlencoded::parse(query.as_bytes()));
+    serde_path_to_error::deserialize(de).map_err(DeserializeError::from)
+}
+
 impl<T> ops::Deref for Query<T> {
     type Target = T;
 
@@ -146,7 +152,7 @@ where
             .map(|c| c.ehandler.clone())
             .unwrap_or(None);
 
-        serde_urlencoded::from_str::<T>(req.query_string())
+        deserialize_query::<T>(req.query_string())
             .map(|val| ok(Query(val)))
             .unwrap_or_else(move |e| {
                 let e = QueryPayloadError::Deserialize(e);
@@ -0,0 +1,411 @@
+//! Xml extractor/responder
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::{fmt, ops};
+
+use bytes::BytesMut;
+use futures_util::future::{err, ok, FutureExt, LocalBoxFuture, Ready};
+use futures_util::StreamExt;
+use quick_xml::events::Event;
+use quick_xml::{Reader, Writer};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::http::error::ErrorInternalServerError;
+use crate::http::{header::CONTENT_LENGTH, StatusCode};
+use crate::http::{HttpMessage, Payload, Response};
+
+use crate::web::dev::Decompress;
+use crate::web::error::{Error, XmlPayloadError};
+use crate::web::extract::FromRequest;
+use crate::web::middleware::memory::MemoryBudget;
+use crate::web::request::HttpRequest;
+use crate::web::responder::Responder;
+
+const XML_DECL: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>";
+
+/// Xml helper
+///
+/// Xml can be used for two different purpose. First is for xml response
+/// generation and second is for extracting typed information from request's
+/// payload.
+///
+/// To extract typed information from request's body, the type `T` must
+/// implement the `Deserialize` trait from *serde*. Accepted content types
+/// are `application/xml`, `text/xml` and any `+xml` suffixed type.
+///
+/// [**XmlConfig**](struct.XmlConfig.html) allows to configure extraction
+/// process and output formatting.
+///
+/// ## Example
+///
+/// ```rust
+/// use kayrx::web::{self, types, App};
+/// use serde_derive::{Deserialize, Serialize};
+///
+/// #[derive(Deserialize, Serialize)]
+/// struct Order {
+///     id: u64,
+///     item: String,
+/// }
+///
+/// /// deserialize `Order` from request's body and echo it back
+/// async fn index(order: types::Xml<Order>) -> types::Xml<Order> {
+///     order
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///        web::resource("/order").route(
+///            web::post().to(index))
+///     );
+/// }
+/// ```
+pub struct Xml<T>(pub T);
+
+impl<T> Xml<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for Xml<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for Xml<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> fmt::Debug for Xml<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Xml: {:?}", self.0)
+    }
+}
+
+impl<T> fmt::Display for Xml<T>
+where
+    T: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl<T: Serialize> Responder for Xml<T> {
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        let indent = req.app_data::<XmlConfig>().and_then(|c| c.indent);
+
+        let body = match quick_xml::se::to_string(&self.0) {
+            Ok(body) => body,
+            Err(e) => return err(ErrorInternalServerError(e)),
+        };
+        let body = match indent {
+            Some(indent) => match pretty(&body, indent) {
+                Ok(body) => body,
+                Err(e) => return err(ErrorInternalServerError(e)),
+            },
+            None => format!("{}{}", XML_DECL, body),
+        };
+
+        ok(Response::build(StatusCode::OK)
+            .content_type("application/xml; charset=utf-8")
+            .body(body))
+    }
+}
+
+/// Re-format compact xml document with indentation
+fn pretty(body: &str, indent: usize) -> Result<String, quick_xml::Error> {
+    let mut reader = Reader::from_str(body);
+    reader.trim_text(true);
+
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', indent);
+    writer.write(XML_DECL.as_bytes())?;
+    writer.write(b"\n")?;
+
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event(&mut buf)? {
+            Event::Eof => break,
+            ev => {
+                writer.write_event(ev)?;
+            }
+        }
+        buf.clear();
+    }
+
+    let mut out = writer.into_inner();
+    out.push(b'\n');
+    Ok(String::from_utf8_lossy(&out).into_owned())
+}
+
+/// Xml extractor. Allow to extract typed information from request's
+/// payload.
+///
+/// To extract typed information from request's body, the type `T` must
+/// implement the `Deserialize` trait from *serde*.
+///
+/// [**XmlConfig**](struct.XmlConfig.html) allows to configure extraction
+/// process.
+impl<T> FromRequest for Xml<T>
+where
+    T: DeserializeOwned + 'static,
+{
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+    type Config = XmlConfig;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let req2 = req.clone();
+        let (limit, err, ctype) = req
+            .app_data::<Self::Config>()
+            .map(|c| (c.limit, c.ehandler.clone(), c.content_type.clone()))
+            .unwrap_or((32768, None, None));
+
+        XmlBody::new(req, payload, ctype)
+            .limit(limit)
+            .map(move |res| match res {
+                Err(e) => {
+                    log::debug!(
+                        "Failed to deserialize Xml from payload. \
+                         Request path: {}",
+                        req2.path()
+                    );
+                    if let Some(err) = err {
+                        Err((*err)(e, &req2))
+                    } else {
+                        Err(e.into())
+                    }
+                }
+                Ok(data) => Ok(Xml(data)),
+            })
+            .boxed_local()
+    }
+}
+
+/// Xml extractor and responder configuration
+///
+/// ```rust
+/// use kayrx::web::{self, error, types, App, FromRequest, HttpResponse};
+/// use serde_derive::{Deserialize, Serialize};
+///
+/// #[derive(Deserialize, Serialize)]
+/// struct Envelope {
+///     body: String,
+/// }
+///
+/// async fn index(info: types::Xml<Envelope>) -> types::Xml<Envelope> {
+///     info
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/soap")
+///             .app_data(
+///                 // max payload size is 4kb, responses are indented by 2 spaces
+///                 types::XmlConfig::default()
+///                     .limit(4096)
+///                     .pretty(2)
+///                     .error_handler(|err, req| {
+///                         error::InternalError::from_response(
+///                             err, HttpResponse::Conflict().finish()).into()
+///                     }))
+///             .route(web::post().to(index))
+///     );
+/// }
+/// ```
+#[derive(Clone)]
+pub struct XmlConfig {
+    limit: usize,
+    indent: Option<usize>,
+    ehandler: Option<Arc<dyn Fn(XmlPayloadError, &HttpRequest) -> Error + Send + Sync>>,
+    content_type: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
+}
+
+impl XmlConfig {
+    /// Change max size of payload. By default max size is 32Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Indent responses by `indent` spaces. By default responses are compact.
+    pub fn pretty(mut self, indent: usize) -> Self {
+        self.indent = Some(indent);
+        self
+    }
+
+    /// Produce compact responses
+    pub fn compact(mut self) -> Self {
+        self.indent = None;
+        self
+    }
+
+    /// Set custom error handler
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(XmlPayloadError, &HttpRequest) -> Error + Send + Sync + 'static,
+    {
+        self.ehandler = Some(Arc::new(f));
+        self
+    }
+
+    /// Set predicate for additional allowed content types
+    pub fn content_type<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(mime::Mime) -> bool + Send + Sync + 'static,
+    {
+        self.content_type = Some(Arc::new(predicate));
+        self
+    }
+}
+
+impl Default for XmlConfig {
+    fn default() -> Self {
+        XmlConfig {
+            limit: 32768,
+            indent: None,
+            ehandler: None,
+            content_type: None,
+        }
+    }
+}
+
+/// Request's payload xml parser, it resolves to a deserialized `T` value.
+///
+/// Returns error:
+///
+/// * content type is not `application/xml`, `text/xml` or `+xml`
+///   (unless specified in [`XmlConfig`](struct.XmlConfig.html))
+/// * content length is greater than 256k
+pub struct XmlBody<U> {
+    limit: usize,
+    length: Option<usize>,
+    budget: Option<MemoryBudget>,
+    stream: Option<Decompress<Payload>>,
+    err: Option<XmlPayloadError>,
+    fut: Option<LocalBoxFuture<'static, Result<U, XmlPayloadError>>>,
+}
+
+impl<U> XmlBody<U>
+where
+    U: DeserializeOwned + 'static,
+{
+    /// Create `XmlBody` for request.
+    pub fn new(
+        req: &HttpRequest,
+        payload: &mut Payload,
+        ctype: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
+    ) -> Self {
+        // check content-type
+        let xml = if let Ok(Some(mime)) = req.mime_type() {
+            ((mime.type_() == mime::APPLICATION || mime.type_() == mime::TEXT)
+                && mime.subtype() == mime::XML)
+                || mime.suffix() == Some(mime::XML)
+                || ctype.as_ref().map_or(false, |predicate| predicate(mime))
+        } else {
+            false
+        };
+
+        if !xml {
+            return XmlBody {
+                limit: 262_144,
+                length: None,
+                budget: None,
+                stream: None,
+                fut: None,
+                err: Some(XmlPayloadError::ContentType),
+            };
+        }
+
+        let len = req
+            .headers()
+            .get(&CONTENT_LENGTH)
+            .and_then(|l| l.to_str().ok())
+            .and_then(|s| s.parse::<usize>().ok());
+
+        let payload = crate::web::types::payload::decompress(payload.take(), req);
+
+        XmlBody {
+            limit: 262_144,
+            length: len,
+            budget: MemoryBudget::of(req),
+            stream: Some(payload),
+            fut: None,
+            err: None,
+        }
+    }
+
+    /// Change max size of payload. By default max size is 256Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+impl<U> Future for XmlBody<U>
+where
+    U: DeserializeOwned + 'static,
+{
+    type Output = Result<U, XmlPayloadError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(ref mut fut) = self.fut {
+            return Pin::new(fut).poll(cx);
+        }
+
+        if let Some(err) = self.err.take() {
+            return Poll::Ready(Err(err));
+        }
+
+        let limit = self.limit;
+        let budget = self.budget.take();
+        if let Some(len) = self.length.take() {
+            if len > limit {
+                return Poll::Ready(Err(XmlPayloadError::Overflow));
+            }
+        }
+        let mut stream = self.stream.take().unwrap();
+
+        self.fut = Some(
+            async move {
+                let mut body = BytesMut::with_capacity(8192);
+
+                while let Some(item) = stream.next().await {
+                    let chunk = item?;
+                    if (body.len() + chunk.len()) > limit {
+                        return Err(XmlPayloadError::Overflow);
+                    } else {
+                        if let Some(ref budget) = budget {
+                            budget
+                                .charge(chunk.len())
+                                .map_err(|_| XmlPayloadError::Overflow)?;
+                        }
+                        body.extend_from_slice(&chunk);
+                    }
+                }
+                Ok(quick_xml::de::from_reader::<_, U>(&body[..])?)
+            }
+            .boxed_local(),
+        );
+
+        self.poll(cx)
+    }
+}
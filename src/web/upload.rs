@@ -0,0 +1,646 @@
+//! Resumable uploads
+//!
+//! `Upload` service implements [tus](https://tus.io/protocols/resumable-upload.html)
+//! resumable upload protocol version 1.0.0 with `creation`, `expiration`,
+//! `checksum` and `termination` extensions. Clients create an upload with
+//! `POST` request and then send its content with one or more `PATCH`
+//! requests, if connection breaks, upload is resumed from the offset
+//! reported by `HEAD` request.
+//!
+//! Upload content and state are kept by `UploadStorage` implementation.
+//! Storage is shared by all workers, `MemoryStorage` and `DiskStorage` are
+//! provided. Incomplete uploads are removed from storage once they expire.
+//!
+//! ```rust
+//! use kayrx::web::{upload, App};
+//!
+//! fn main() {
+//!     let storage = upload::DiskStorage::new("/tmp/uploads").unwrap();
+//!
+//!     let app = App::new().service(
+//!         upload::Upload::new("/files", storage).max_size(1 << 30),
+//!     );
+//! }
+//! ```
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::future::Future;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bytes::{Bytes, BytesMut};
+use futures_channel::mpsc;
+use futures_core::Stream;
+use futures_util::StreamExt;
+use rand::Rng;
+
+use crate::http::header::{self, HeaderValue, HttpDate};
+use crate::http::{HttpMessage, Method, Response, StatusCode};
+use crate::router::ResourceDef;
+use crate::service::fn_service;
+use crate::timer::{DelayQueue, Instant};
+use crate::web::config::AppService;
+use crate::web::error::{BlockingError, Error};
+use crate::web::service::{HttpServiceFactory, ServiceRequest, ServiceResponse};
+
+const TUS_VERSION: &str = "1.0.0";
+const TUS_EXTENSIONS: &str = "creation,expiration,checksum,termination";
+const OFFSET_CONTENT_TYPE: &str = "application/offset+octet-stream";
+
+/// Size of the buffer flushed to storage while `PATCH` body is received
+const FLUSH_SIZE: usize = 1_048_576;
+
+/// State of the upload
+#[derive(Debug, Clone, PartialEq)]
+pub struct UploadInfo {
+    /// Total size of the upload
+    pub length: u64,
+    /// Number of bytes received so far
+    pub offset: u64,
+    /// Raw value of `Upload-Metadata` header
+    pub metadata: Option<String>,
+    /// Time after which incomplete upload is removed
+    pub expires: Option<SystemTime>,
+}
+
+impl UploadInfo {
+    /// Check if all content of the upload is received
+    pub fn is_complete(&self) -> bool {
+        self.offset >= self.length
+    }
+
+    fn is_expired(&self) -> bool {
+        !self.is_complete() && self.expires.map(|t| t <= SystemTime::now()).unwrap_or(false)
+    }
+}
+
+/// Storage of upload content and state.
+///
+/// Storage is shared between workers, its methods are called on the
+/// thread pool so they are allowed to block.
+pub trait UploadStorage: Send + Sync + 'static {
+    /// Create new empty upload
+    fn create(&self, id: &str, info: &UploadInfo) -> io::Result<()>;
+
+    /// Get state of the upload, `None` if upload does not exist
+    fn info(&self, id: &str) -> io::Result<Option<UploadInfo>>;
+
+    /// Append data to the upload.
+    ///
+    /// `offset` is expected offset of the upload, storage must return
+    /// error of `InvalidInput` kind if it does not match actual offset.
+    fn append(&self, id: &str, offset: u64, data: &[u8]) -> io::Result<()>;
+
+    /// Remove upload
+    fn remove(&self, id: &str) -> io::Result<()>;
+}
+
+/// Storage that keeps uploads in memory
+#[derive(Clone, Default)]
+pub struct MemoryStorage {
+    uploads: Arc<Mutex<HashMap<String, (UploadInfo, Vec<u8>)>>>,
+}
+
+impl MemoryStorage {
+    /// Create empty storage
+    pub fn new() -> Self {
+        MemoryStorage::default()
+    }
+
+    /// Get content of the upload received so far
+    pub fn content(&self, id: &str) -> Option<Vec<u8>> {
+        let uploads = self.uploads.lock().unwrap();
+        uploads.get(id).map(|(_, data)| data.clone())
+    }
+}
+
+impl UploadStorage for MemoryStorage {
+    fn create(&self, id: &str, info: &UploadInfo) -> io::Result<()> {
+        let mut uploads = self.uploads.lock().unwrap();
+        uploads.insert(id.to_owned(), (info.clone(), Vec::new()));
+        Ok(())
+    }
+
+    fn info(&self, id: &str) -> io::Result<Option<UploadInfo>> {
+        let uploads = self.uploads.lock().unwrap();
+        Ok(uploads.get(id).map(|(info, _)| info.clone()))
+    }
+
+    fn append(&self, id: &str, offset: u64, data: &[u8]) -> io::Result<()> {
+        let mut uploads = self.uploads.lock().unwrap();
+        let (info, content) = uploads
+            .get_mut(id)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        if info.offset != offset {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Offset mismatch"));
+        }
+        content.extend_from_slice(data);
+        info.offset += data.len() as u64;
+        Ok(())
+    }
+
+    fn remove(&self, id: &str) -> io::Result<()> {
+        self.uploads.lock().unwrap().remove(id);
+        Ok(())
+    }
+}
+
+/// Storage that keeps uploads in a directory.
+///
+/// Content of the upload is stored in a file named after upload id,
+/// upload state is stored next to it in a file with `.info` extension.
+#[derive(Debug, Clone)]
+pub struct DiskStorage {
+    dir: PathBuf,
+}
+
+impl DiskStorage {
+    /// Create storage in the directory, directory is created if it does
+    /// not exist
+    pub fn new<T: Into<PathBuf>>(dir: T) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(DiskStorage { dir })
+    }
+
+    /// Path of the file with upload content
+    pub fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(id)
+    }
+
+    fn info_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.info", id))
+    }
+}
+
+impl UploadStorage for DiskStorage {
+    fn create(&self, id: &str, info: &UploadInfo) -> io::Result<()> {
+        let expires = info
+            .expires
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs().to_string())
+            .unwrap_or_default();
+        fs::write(
+            self.info_path(id),
+            format!(
+                "{}\n{}\n{}",
+                info.length,
+                expires,
+                info.metadata.as_ref().map(|s| s.as_str()).unwrap_or("")
+            ),
+        )?;
+        fs::File::create(self.path(id))?;
+        Ok(())
+    }
+
+    fn info(&self, id: &str) -> io::Result<Option<UploadInfo>> {
+        let raw = match fs::read_to_string(self.info_path(id)) {
+            Ok(raw) => raw,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "Malformed upload info");
+
+        let mut lines = raw.splitn(3, '\n');
+        let length = lines
+            .next()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(invalid)?;
+        let expires = match lines.next().ok_or_else(invalid)? {
+            "" => None,
+            secs => Some(UNIX_EPOCH + Duration::from_secs(secs.parse().map_err(|_| invalid())?)),
+        };
+        let metadata = lines.next().filter(|s| !s.is_empty()).map(|s| s.to_owned());
+        let offset = fs::metadata(self.path(id))?.len();
+
+        Ok(Some(UploadInfo {
+            length,
+            offset,
+            metadata,
+            expires,
+        }))
+    }
+
+    fn append(&self, id: &str, offset: u64, data: &[u8]) -> io::Result<()> {
+        let mut file = OpenOptions::new().append(true).open(self.path(id))?;
+        if file.metadata()?.len() != offset {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Offset mismatch"));
+        }
+        file.write_all(data)
+    }
+
+    fn remove(&self, id: &str) -> io::Result<()> {
+        let _ = fs::remove_file(self.info_path(id));
+        match fs::remove_file(self.path(id)) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            res => res,
+        }
+    }
+}
+
+/// Resumable upload service, see [module documentation](index.html)
+pub struct Upload {
+    path: String,
+    storage: Arc<dyn UploadStorage>,
+    max_size: Option<u64>,
+    expiration: Duration,
+}
+
+impl Upload {
+    /// Create upload service at specified path with storage
+    pub fn new<S: UploadStorage>(path: &str, storage: S) -> Self {
+        Upload {
+            path: path.trim_end_matches('/').to_owned(),
+            storage: Arc::new(storage),
+            max_size: None,
+            expiration: Duration::from_secs(86_400),
+        }
+    }
+
+    /// Set maximum size of the upload. By default size is not limited.
+    pub fn max_size(mut self, size: u64) -> Self {
+        self.max_size = Some(size);
+        self
+    }
+
+    /// Set time after which incomplete upload is removed.
+    ///
+    /// By default uploads expire in 24 hours.
+    pub fn expiration(mut self, timeout: Duration) -> Self {
+        self.expiration = timeout;
+        self
+    }
+}
+
+struct Inner {
+    storage: Arc<dyn UploadStorage>,
+    max_size: Option<u64>,
+    expiration: Duration,
+    expiry: RefCell<Option<mpsc::UnboundedSender<(String, Instant)>>>,
+}
+
+impl HttpServiceFactory for Upload {
+    fn register(self, config: &mut AppService) {
+        let inner = Rc::new(Inner {
+            storage: self.storage,
+            max_size: self.max_size,
+            expiration: self.expiration,
+            expiry: RefCell::new(None),
+        });
+
+        let base = inner.clone();
+        config.register_service(
+            ResourceDef::new(self.path.as_str()),
+            None,
+            fn_service(move |req: ServiceRequest| handle(base.clone(), req)),
+            None,
+        );
+        config.register_service(
+            ResourceDef::new(format!("{}/{{id}}", self.path).as_str()),
+            None,
+            fn_service(move |req: ServiceRequest| handle(inner.clone(), req)),
+            None,
+        );
+    }
+}
+
+async fn handle(inner: Rc<Inner>, req: ServiceRequest) -> Result<ServiceResponse, Error> {
+    let id = req.match_info().get("id").map(|id| id.to_owned());
+
+    if *req.method() == Method::OPTIONS {
+        let mut res = tus_response(StatusCode::NO_CONTENT);
+        res.header("Tus-Version", TUS_VERSION)
+            .header("Tus-Extension", TUS_EXTENSIONS)
+            .header("Tus-Checksum-Algorithm", "sha1");
+        if let Some(max_size) = inner.max_size {
+            res.header("Tus-Max-Size", max_size.to_string());
+        }
+        return Ok(req.into_response(res.finish()));
+    }
+
+    let version = req
+        .headers()
+        .get("tus-resumable")
+        .and_then(|val| val.to_str().ok());
+    if version != Some(TUS_VERSION) {
+        return Ok(req.into_response(
+            tus_response(StatusCode::PRECONDITION_FAILED)
+                .header("Tus-Version", TUS_VERSION)
+                .finish(),
+        ));
+    }
+
+    let res = match (id, req.method().clone()) {
+        (None, Method::POST) => create(&inner, &req).await,
+        (Some(ref id), _) if !is_valid_id(id) => Ok(tus_response(StatusCode::NOT_FOUND).finish()),
+        (Some(id), Method::HEAD) => status(&inner, id).await,
+        (Some(id), Method::PATCH) => return patch(inner, req, id).await,
+        (Some(id), Method::DELETE) => terminate(&inner, id).await,
+        _ => Ok(tus_response(StatusCode::METHOD_NOT_ALLOWED).finish()),
+    };
+    Ok(match res {
+        Ok(res) => req.into_response(res),
+        Err(e) => req.error_response(e),
+    })
+}
+
+/// `POST` request, creates new upload
+async fn create(inner: &Rc<Inner>, req: &ServiceRequest) -> Result<Response, Error> {
+    let length = match header_u64(req, "upload-length") {
+        Some(length) => length,
+        None => return Ok(tus_response(StatusCode::BAD_REQUEST).finish()),
+    };
+    if inner.max_size.map(|max| length > max).unwrap_or(false) {
+        return Ok(tus_response(StatusCode::PAYLOAD_TOO_LARGE).finish());
+    }
+
+    let id = format!("{:032x}", rand::thread_rng().gen::<u128>());
+    let expires = SystemTime::now() + inner.expiration;
+    let info = UploadInfo {
+        length,
+        offset: 0,
+        metadata: req
+            .headers()
+            .get("upload-metadata")
+            .and_then(|val| val.to_str().ok())
+            .map(|val| val.to_owned()),
+        expires: Some(expires),
+    };
+
+    let storage = inner.storage.clone();
+    let upload_id = id.clone();
+    crate::web::block(move || storage.create(&upload_id, &info))
+        .await
+        .map_err(storage_error)?;
+    schedule_expiry(inner, id.clone(), inner.expiration);
+
+    Ok(tus_response(StatusCode::CREATED)
+        .header(
+            header::LOCATION,
+            format!("{}/{}", req.path().trim_end_matches('/'), id),
+        )
+        .header("Upload-Expires", HttpDate::from(expires).to_string())
+        .finish())
+}
+
+/// `HEAD` request, reports upload offset
+async fn status(inner: &Rc<Inner>, id: String) -> Result<Response, Error> {
+    let info = match load(inner, id).await? {
+        Some(info) => info,
+        None => return Ok(tus_response(StatusCode::NOT_FOUND).finish()),
+    };
+
+    let mut res = tus_response(StatusCode::OK);
+    res.header(header::CACHE_CONTROL, "no-store")
+        .header("Upload-Offset", info.offset.to_string())
+        .header("Upload-Length", info.length.to_string());
+    if let Some(metadata) = info.metadata {
+        res.header("Upload-Metadata", metadata);
+    }
+    if let Some(expires) = info.expires {
+        res.header("Upload-Expires", HttpDate::from(expires).to_string());
+    }
+    Ok(res.finish())
+}
+
+/// `DELETE` request, terminates upload
+async fn terminate(inner: &Rc<Inner>, id: String) -> Result<Response, Error> {
+    if load(inner, id.clone()).await?.is_none() {
+        return Ok(tus_response(StatusCode::NOT_FOUND).finish());
+    }
+    let storage = inner.storage.clone();
+    crate::web::block(move || storage.remove(&id))
+        .await
+        .map_err(storage_error)?;
+    Ok(tus_response(StatusCode::NO_CONTENT).finish())
+}
+
+/// `PATCH` request, appends content to the upload
+async fn patch(
+    inner: Rc<Inner>,
+    mut req: ServiceRequest,
+    id: String,
+) -> Result<ServiceResponse, Error> {
+    let content_type = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|val| val.to_str().ok());
+    if content_type != Some(OFFSET_CONTENT_TYPE) {
+        return Ok(req.into_response(tus_response(StatusCode::UNSUPPORTED_MEDIA_TYPE).finish()));
+    }
+    let offset = match header_u64(&req, "upload-offset") {
+        Some(offset) => offset,
+        None => return Ok(req.into_response(tus_response(StatusCode::BAD_REQUEST).finish())),
+    };
+    let checksum = match req.headers().get("upload-checksum") {
+        Some(val) => match parse_checksum(val) {
+            Some(checksum) => Some(checksum),
+            None => {
+                return Ok(req.into_response(tus_response(StatusCode::BAD_REQUEST).finish()))
+            }
+        },
+        None => None,
+    };
+
+    let info = match load(&inner, id.clone()).await {
+        Ok(Some(info)) => info,
+        Ok(None) => return Ok(req.into_response(tus_response(StatusCode::NOT_FOUND).finish())),
+        Err(e) => return Ok(req.error_response(e)),
+    };
+    if info.offset != offset {
+        return Ok(req.into_response(tus_response(StatusCode::CONFLICT).finish()));
+    }
+
+    // content is written to storage while it is received, unless checksum
+    // has to be verified before the chunk is accepted
+    let remaining = info.length.saturating_sub(offset);
+    let mut payload = req.take_payload();
+    let mut received = 0u64;
+    let mut written = offset;
+    let mut buf = BytesMut::new();
+    let mut hasher = sha1::Sha1::new();
+
+    while let Some(chunk) = payload.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                log::debug!("Upload {}: payload error: {}", id, e);
+                break;
+            }
+        };
+        received += chunk.len() as u64;
+        if received > remaining {
+            return Ok(req.into_response(tus_response(StatusCode::PAYLOAD_TOO_LARGE).finish()));
+        }
+        if checksum.is_some() {
+            hasher.update(&chunk);
+        }
+        buf.extend_from_slice(&chunk);
+
+        if checksum.is_none() && buf.len() >= FLUSH_SIZE {
+            let data = buf.split().freeze();
+            if let Err(e) = append(&inner, &id, written, data.clone()).await {
+                return Ok(req.error_response(e));
+            }
+            written += data.len() as u64;
+        }
+    }
+
+    if let Some(expected) = checksum {
+        if hasher.digest().bytes()[..] != expected[..] {
+            // 460 Checksum Mismatch, chunk is discarded
+            let status = StatusCode::from_u16(460).unwrap();
+            return Ok(req.into_response(tus_response(status).finish()));
+        }
+    }
+
+    if !buf.is_empty() {
+        let data = buf.freeze();
+        if let Err(e) = append(&inner, &id, written, data.clone()).await {
+            return Ok(req.error_response(e));
+        }
+        written += data.len() as u64;
+    }
+
+    let mut res = tus_response(StatusCode::NO_CONTENT);
+    res.header("Upload-Offset", written.to_string());
+    if let Some(expires) = info.expires {
+        res.header("Upload-Expires", HttpDate::from(expires).to_string());
+    }
+    Ok(req.into_response(res.finish()))
+}
+
+async fn append(inner: &Rc<Inner>, id: &str, offset: u64, data: Bytes) -> Result<(), Error> {
+    let storage = inner.storage.clone();
+    let id = id.to_owned();
+    crate::web::block(move || storage.append(&id, offset, &data))
+        .await
+        .map_err(storage_error)
+}
+
+/// Load upload state, expired uploads are reported as missing
+async fn load(inner: &Rc<Inner>, id: String) -> Result<Option<UploadInfo>, Error> {
+    let storage = inner.storage.clone();
+    let info = crate::web::block(move || storage.info(&id))
+        .await
+        .map_err(storage_error)?;
+    Ok(info.filter(|info| !info.is_expired()))
+}
+
+fn storage_error(err: BlockingError<io::Error>) -> Error {
+    match err {
+        BlockingError::Error(err) => match err.kind() {
+            io::ErrorKind::NotFound => Response::NotFound().finish().into(),
+            io::ErrorKind::InvalidInput => Response::Conflict().finish().into(),
+            _ => err.into(),
+        },
+        BlockingError::Canceled => Response::InternalServerError().finish().into(),
+    }
+}
+
+fn tus_response(status: StatusCode) -> crate::http::ResponseBuilder {
+    let mut res = Response::build(status);
+    res.header("Tus-Resumable", HeaderValue::from_static(TUS_VERSION));
+    res
+}
+
+fn header_u64(req: &ServiceRequest, name: &str) -> Option<u64> {
+    req.headers()
+        .get(name)
+        .and_then(|val| val.to_str().ok())
+        .and_then(|val| val.trim().parse().ok())
+}
+
+/// Parse `Upload-Checksum` header, only `sha1` algorithm is supported
+fn parse_checksum(val: &HeaderValue) -> Option<Vec<u8>> {
+    let val = val.to_str().ok()?;
+    let mut parts = val.trim().splitn(2, ' ');
+    if parts.next()? != "sha1" {
+        return None;
+    }
+    base64::decode(parts.next()?.trim()).ok()
+}
+
+/// Upload ids are generated by the service, anything else is not looked up
+fn is_valid_id(id: &str) -> bool {
+    id.len() == 32 && id.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Register upload for removal once it expires.
+///
+/// Expiry queue is owned by a task spawned on the worker at first use.
+fn schedule_expiry(inner: &Rc<Inner>, id: String, timeout: Duration) {
+    let mut expiry = inner.expiry.borrow_mut();
+    if expiry.is_none() {
+        let (tx, rx) = mpsc::unbounded();
+        crate::fiber::spawn(Expiry {
+            rx: Some(rx),
+            queue: DelayQueue::new(),
+            storage: inner.storage.clone(),
+        });
+        *expiry = Some(tx);
+    }
+    let _ = expiry
+        .as_ref()
+        .unwrap()
+        .unbounded_send((id, Instant::now() + timeout));
+}
+
+/// Removes incomplete uploads once they expire
+struct Expiry {
+    rx: Option<mpsc::UnboundedReceiver<(String, Instant)>>,
+    queue: DelayQueue<String>,
+    storage: Arc<dyn UploadStorage>,
+}
+
+impl Future for Expiry {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = &mut *self;
+
+        if let Some(ref mut rx) = this.rx {
+            loop {
+                match Pin::new(&mut *rx).poll_next(cx) {
+                    Poll::Ready(Some((id, deadline))) => {
+                        this.queue.insert_at(id, deadline);
+                    }
+                    Poll::Ready(None) => {
+                        // service is dropped, worker is stopping
+                        this.rx = None;
+                        return Poll::Ready(());
+                    }
+                    Poll::Pending => break,
+                }
+            }
+        }
+
+        while let Poll::Ready(Some(item)) = this.queue.poll_expired(cx) {
+            let id = match item {
+                Ok(expired) => expired.into_inner(),
+                Err(e) => {
+                    log::error!("Upload expiry timer error: {}", e);
+                    continue;
+                }
+            };
+            let storage = this.storage.clone();
+            crate::fiber::spawn(async move {
+                let res = crate::web::block(move || match storage.info(&id)? {
+                    Some(ref info) if !info.is_complete() => storage.remove(&id),
+                    _ => Ok(()),
+                })
+                .await;
+                if let Err(e) = res {
+                    log::error!("Can not remove expired upload: {:?}", e);
+                }
+            });
+        }
+        Poll::Pending
+    }
+}
@@ -0,0 +1,431 @@
+//! Websocket support for web handlers.
+//!
+//! Handler performs upgrade handshake and returns streaming response, the
+//! response body drives `WsSession`: incoming frames are decoded from the
+//! request payload, outgoing messages are encoded to the response body.
+//!
+//! Session receives complete messages, fragmented messages are assembled.
+//! Ping frames are answered automatically, close frame from the peer is
+//! echoed back and closes the connection.
+//!
+//! ```rust
+//! use kayrx::web::{self, types, ws, App, Error, HttpRequest, HttpResponse};
+//!
+//! struct Echo;
+//!
+//! impl ws::WsSession for Echo {
+//!     fn handle(&mut self, msg: ws::Message, ctx: &mut ws::WsContext) {
+//!         match msg {
+//!             ws::Message::Text(text) => ctx.text(text),
+//!             ws::Message::Binary(bin) => ctx.binary(bin),
+//!             _ => (),
+//!         }
+//!     }
+//! }
+//!
+//! async fn index(req: HttpRequest, stream: types::Payload) -> Result<HttpResponse, Error> {
+//!     ws::start(Echo, &req, stream)
+//! }
+//!
+//! fn main() {
+//!     let app = App::new().service(web::resource("/ws/").route(web::get().to(index)));
+//! }
+//! ```
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::{Bytes, BytesMut};
+use futures_channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use futures_core::Stream;
+use futures_util::future::{ok, Ready};
+
+use crate::codec::{Decoder, Encoder};
+use crate::http::error::{Error, PayloadError};
+use crate::http::header;
+use crate::http::Response;
+use crate::web::request::HttpRequest;
+use crate::web::responder::Responder;
+use crate::websocket::{self, Codec, Frame, Item};
+
+pub use crate::websocket::{CloseCode, CloseReason, HandshakeError, Message, ProtocolError};
+
+/// Websocket session handler
+pub trait WsSession: 'static {
+    /// Method is called when connection is established
+    fn started(&mut self, _: &mut WsContext) {}
+
+    /// Handle incoming message
+    fn handle(&mut self, msg: Message, ctx: &mut WsContext);
+
+    /// Method is called when connection is closed
+    fn stopped(&mut self) {}
+}
+
+/// Perform websocket handshake and start session.
+///
+/// Returns handshake error response if request is not a valid websocket
+/// upgrade request.
+pub fn start<T, S>(session: T, req: &HttpRequest, stream: S) -> Result<Response, Error>
+where
+    T: WsSession,
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin + 'static,
+{
+    WebSocket::new(session, stream).response(req)
+}
+
+/// Websocket responder
+///
+/// Handshake is performed when responder is converted to a response.
+pub struct WebSocket<T, S> {
+    session: T,
+    stream: S,
+    max_size: usize,
+    write_buffer: usize,
+    protocols: Vec<String>,
+}
+
+impl<T, S> WebSocket<T, S>
+where
+    T: WsSession,
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin + 'static,
+{
+    /// Create websocket responder for session and request payload
+    pub fn new(session: T, stream: S) -> Self {
+        WebSocket {
+            session,
+            stream,
+            max_size: 65_536,
+            write_buffer: 32_768,
+            protocols: Vec::new(),
+        }
+    }
+
+    /// Set max size of incoming message. By default max size is 64Kb.
+    ///
+    /// Limit applies to the assembled fragmented messages as well.
+    pub fn max_size(mut self, size: usize) -> Self {
+        self.max_size = size;
+        self
+    }
+
+    /// Set max size of outgoing data encoded at once. By default 32Kb.
+    ///
+    /// Messages over the limit stay queued until the connection sends
+    /// encoded data, incoming frames are not read while messages are queued.
+    /// So session that responds to the peer faster than the peer reads
+    /// is slowed down instead of buffering responses in memory.
+    pub fn write_buffer(mut self, size: usize) -> Self {
+        self.write_buffer = size;
+        self
+    }
+
+    /// Set supported sub-protocols.
+    ///
+    /// First protocol from the request's `Sec-WebSocket-Protocol` header that
+    /// is also supported by server is selected.
+    pub fn protocols<I, P>(mut self, protocols: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<String>,
+    {
+        self.protocols = protocols.into_iter().map(|p| p.into()).collect();
+        self
+    }
+
+    fn response(self, req: &HttpRequest) -> Result<Response, Error> {
+        let mut res = websocket::handshake(req.head())?;
+
+        if let Some(protocol) = self.select_protocol(req) {
+            res.header(header::SEC_WEBSOCKET_PROTOCOL, protocol);
+        }
+
+        let (tx, rx) = unbounded();
+        Ok(res.streaming(WsStream {
+            session: self.session,
+            ctx: WsContext {
+                queue: VecDeque::new(),
+                closed: false,
+                tx,
+            },
+            rx,
+            stream: Some(self.stream),
+            buf: BytesMut::new(),
+            codec: Codec::new().max_size(self.max_size),
+            max_size: self.max_size,
+            write_buffer: self.write_buffer,
+            cont: None,
+            started: false,
+            stopped: false,
+        }))
+    }
+
+    fn select_protocol(&self, req: &HttpRequest) -> Option<String> {
+        let requested = req
+            .headers()
+            .get(header::SEC_WEBSOCKET_PROTOCOL)
+            .and_then(|val| val.to_str().ok())?;
+        requested
+            .split(',')
+            .map(|p| p.trim())
+            .find(|p| self.protocols.iter().any(|s| s == p))
+            .map(|p| p.to_owned())
+    }
+}
+
+impl<T, S> Responder for WebSocket<T, S>
+where
+    T: WsSession,
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin + 'static,
+{
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        ok(self.response(req).unwrap_or_else(Response::from_error))
+    }
+}
+
+/// Websocket session context
+pub struct WsContext {
+    queue: VecDeque<Message>,
+    closed: bool,
+    tx: UnboundedSender<Message>,
+}
+
+impl WsContext {
+    /// Send text message
+    pub fn text<T: Into<String>>(&mut self, text: T) {
+        self.write(Message::Text(text.into()));
+    }
+
+    /// Send binary message
+    pub fn binary<B: Into<Bytes>>(&mut self, data: B) {
+        self.write(Message::Binary(data.into()));
+    }
+
+    /// Send ping message
+    pub fn ping(&mut self, msg: &[u8]) {
+        self.write(Message::Ping(Bytes::copy_from_slice(msg)));
+    }
+
+    /// Send pong message
+    pub fn pong(&mut self, msg: &[u8]) {
+        self.write(Message::Pong(Bytes::copy_from_slice(msg)));
+    }
+
+    /// Send close message and close connection
+    pub fn close(&mut self, reason: Option<CloseReason>) {
+        self.write(Message::Close(reason));
+    }
+
+    /// Check if close message is sent
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Get sender handle, sender could be used from other tasks
+    pub fn sender(&self) -> WsSender {
+        WsSender(self.tx.clone())
+    }
+
+    fn write(&mut self, msg: Message) {
+        if !self.closed {
+            if let Message::Close(_) = msg {
+                self.closed = true;
+            }
+            self.queue.push_back(msg);
+        }
+    }
+}
+
+/// Sender handle for websocket session
+#[derive(Clone)]
+pub struct WsSender(UnboundedSender<Message>);
+
+impl WsSender {
+    /// Send message to the peer.
+    ///
+    /// Returns message back if session is stopped.
+    pub fn send(&self, msg: Message) -> Result<(), Message> {
+        self.0.unbounded_send(msg).map_err(|e| e.into_inner())
+    }
+
+    /// Check if session is stopped
+    pub fn is_closed(&self) -> bool {
+        self.0.is_closed()
+    }
+}
+
+struct WsStream<T, S> {
+    session: T,
+    ctx: WsContext,
+    rx: UnboundedReceiver<Message>,
+    stream: Option<S>,
+    buf: BytesMut,
+    codec: Codec,
+    max_size: usize,
+    write_buffer: usize,
+    cont: Option<(bool, BytesMut)>,
+    started: bool,
+    stopped: bool,
+}
+
+impl<T, S> Unpin for WsStream<T, S> {}
+
+impl<T, S> WsStream<T, S>
+where
+    T: WsSession,
+{
+    fn handle_frame(&mut self, frame: Frame) {
+        let msg = match frame {
+            Frame::Text(data) => match String::from_utf8(data.to_vec()) {
+                Ok(text) => Message::Text(text),
+                Err(_) => return self.ctx.close(Some(CloseCode::Invalid.into())),
+            },
+            Frame::Binary(data) => Message::Binary(data),
+            Frame::Ping(data) => {
+                self.ctx.pong(&data);
+                Message::Ping(data)
+            }
+            Frame::Pong(data) => Message::Pong(data),
+            Frame::Close(reason) => {
+                self.session
+                    .handle(Message::Close(reason.clone()), &mut self.ctx);
+                return self.ctx.close(reason);
+            }
+            Frame::Continuation(item) => match self.continuation(item) {
+                Some(msg) => msg,
+                None => return,
+            },
+        };
+        self.session.handle(msg, &mut self.ctx);
+    }
+
+    fn continuation(&mut self, item: Item) -> Option<Message> {
+        let (data, last) = match item {
+            Item::FirstText(data) => {
+                self.cont = Some((true, BytesMut::new()));
+                (data, false)
+            }
+            Item::FirstBinary(data) => {
+                self.cont = Some((false, BytesMut::new()));
+                (data, false)
+            }
+            Item::Continue(data) => (data, false),
+            Item::Last(data) => (data, true),
+        };
+
+        let (is_text, buf) = match self.cont {
+            Some((is_text, ref mut buf)) => (is_text, buf),
+            None => {
+                self.ctx.close(Some(CloseCode::Protocol.into()));
+                return None;
+            }
+        };
+        if buf.len() + data.len() > self.max_size {
+            self.cont = None;
+            self.ctx.close(Some(CloseCode::Size.into()));
+            return None;
+        }
+        buf.extend_from_slice(&data);
+
+        if !last {
+            return None;
+        }
+        let data = self.cont.take().unwrap().1.freeze();
+        if is_text {
+            match String::from_utf8(data.to_vec()) {
+                Ok(text) => Some(Message::Text(text)),
+                Err(_) => {
+                    self.ctx.close(Some(CloseCode::Invalid.into()));
+                    None
+                }
+            }
+        } else {
+            Some(Message::Binary(data))
+        }
+    }
+}
+
+impl<T, S> Stream for WsStream<T, S>
+where
+    T: WsSession,
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+{
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.stopped {
+            return Poll::Ready(None);
+        }
+        if !this.started {
+            this.started = true;
+            this.session.started(&mut this.ctx);
+        }
+
+        // read incoming frames, unless outgoing messages are queued
+        while !this.ctx.closed && this.ctx.queue.is_empty() {
+            let stream = match this.stream {
+                Some(ref mut stream) => stream,
+                None => break,
+            };
+            match Pin::new(stream).poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => {
+                    this.buf.extend_from_slice(&chunk);
+                    loop {
+                        match this.codec.decode(&mut this.buf) {
+                            Ok(Some(frame)) => this.handle_frame(frame),
+                            Ok(None) => break,
+                            Err(e) => {
+                                log::debug!("Websocket protocol error: {}", e);
+                                let code = match e {
+                                    ProtocolError::Overflow => CloseCode::Size,
+                                    _ => CloseCode::Protocol,
+                                };
+                                this.ctx.close(Some(code.into()));
+                                break;
+                            }
+                        }
+                        if this.ctx.closed {
+                            break;
+                        }
+                    }
+                }
+                Poll::Ready(Some(Err(e))) => {
+                    log::debug!("Websocket payload error: {}", e);
+                    this.stream = None;
+                }
+                Poll::Ready(None) => this.stream = None,
+                Poll::Pending => break,
+            }
+        }
+
+        let mut buf = BytesMut::new();
+        while buf.len() < this.write_buffer {
+            if let Some(msg) = this.ctx.queue.pop_front() {
+                if let Err(e) = this.codec.encode(msg, &mut buf) {
+                    log::debug!("Websocket encoding error: {}", e);
+                }
+            } else if let Poll::Ready(Some(msg)) = Pin::new(&mut this.rx).poll_next(cx) {
+                // messages from other tasks
+                this.ctx.write(msg);
+            } else {
+                break;
+            }
+        }
+        if !buf.is_empty() {
+            return Poll::Ready(Some(Ok(buf.freeze())));
+        }
+
+        if this.ctx.closed || this.stream.is_none() {
+            this.stopped = true;
+            this.rx.close();
+            this.session.stopped();
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
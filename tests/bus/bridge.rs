@@ -0,0 +1,172 @@
+use std::cell::{Cell, RefCell};
+use std::io;
+use std::rc::Rc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::future::{poll_fn, ready, FutureExt, LocalBoxFuture};
+use kayrx::bus::{Bridge, Bus, BusBridge};
+use kayrx::krse::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use kayrx::timer::delay_for;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Order {
+    id: u32,
+}
+
+#[derive(Default)]
+struct Broker {
+    connects: Cell<usize>,
+    fail_connects: Cell<usize>,
+    fail_publishes: Cell<usize>,
+    topics: RefCell<Vec<String>>,
+    published: RefCell<Vec<(String, Bytes)>>,
+    incoming: RefCell<Option<UnboundedReceiver<(String, Bytes)>>>,
+}
+
+#[derive(Clone)]
+struct MockBridge(Rc<Broker>);
+
+impl MockBridge {
+    fn new() -> (Self, UnboundedSender<(String, Bytes)>) {
+        let (tx, rx) = unbounded_channel();
+        let broker = Broker::default();
+        *broker.incoming.borrow_mut() = Some(rx);
+        (MockBridge(Rc::new(broker)), tx)
+    }
+}
+
+fn fail(counter: &Cell<usize>) -> bool {
+    if counter.get() > 0 {
+        counter.set(counter.get() - 1);
+        true
+    } else {
+        false
+    }
+}
+
+impl BusBridge for MockBridge {
+    fn connect(&self, topics: &[String]) -> LocalBoxFuture<'static, Result<(), io::Error>> {
+        self.0.connects.set(self.0.connects.get() + 1);
+        *self.0.topics.borrow_mut() = topics.to_vec();
+        if fail(&self.0.fail_connects) {
+            ready(Err(io::Error::new(io::ErrorKind::Other, "connect"))).boxed_local()
+        } else {
+            ready(Ok(())).boxed_local()
+        }
+    }
+
+    fn publish(
+        &self,
+        topic: &str,
+        payload: Bytes,
+    ) -> LocalBoxFuture<'static, Result<(), io::Error>> {
+        if fail(&self.0.fail_publishes) {
+            return ready(Err(io::Error::new(io::ErrorKind::Other, "publish"))).boxed_local();
+        }
+        self.0.published.borrow_mut().push((topic.to_owned(), payload));
+        ready(Ok(())).boxed_local()
+    }
+
+    fn recv(&self) -> LocalBoxFuture<'static, Result<Option<(String, Bytes)>, io::Error>> {
+        let broker = self.0.clone();
+        poll_fn(move |cx| {
+            broker
+                .incoming
+                .borrow_mut()
+                .as_mut()
+                .unwrap()
+                .poll_recv(cx)
+                .map(Ok)
+        })
+        .boxed_local()
+    }
+}
+
+async fn wait_for<F: Fn() -> bool>(f: F) {
+    for _ in 0..100 {
+        if f() {
+            return;
+        }
+        delay_for(Duration::from_millis(10)).await;
+    }
+    panic!("condition is not met");
+}
+
+#[kayrx::test]
+async fn test_bridge() {
+    let bus = Bus::new();
+    let mut orders = bus.subscribe_to::<Order>("orders");
+    let (bridge, tx) = MockBridge::new();
+    let broker = bridge.0.clone();
+
+    let handle = Bridge::new(bus.clone(), bridge)
+        .topic::<Order>("orders")
+        .start();
+    wait_for(|| broker.connects.get() == 1).await;
+    assert_eq!(&*broker.topics.borrow(), &["orders".to_owned()]);
+
+    // local to broker
+    bus.publish_to("orders", Order { id: 1 });
+    wait_for(|| broker.published.borrow().len() == 1).await;
+    assert_eq!(
+        broker.published.borrow()[0],
+        ("orders".to_owned(), Bytes::from_static(br#"{"id":1}"#))
+    );
+    assert_eq!(orders.recv().await, Some(Order { id: 1 }));
+
+    // broker to local, not sent back
+    tx.send(("orders".to_owned(), Bytes::from_static(br#"{"id":2}"#)))
+        .unwrap();
+    assert_eq!(orders.recv().await, Some(Order { id: 2 }));
+    delay_for(Duration::from_millis(50)).await;
+    assert_eq!(broker.published.borrow().len(), 1);
+
+    handle.stop();
+}
+
+#[kayrx::test]
+async fn test_reconnect() {
+    let bus = Bus::new();
+    let (bridge, _tx) = MockBridge::new();
+    let broker = bridge.0.clone();
+    broker.fail_connects.set(2);
+
+    let handle = Bridge::new(bus.clone(), bridge)
+        .topic::<Order>("orders")
+        .reconnect(Duration::from_millis(5), Duration::from_millis(20))
+        .start();
+    wait_for(|| broker.connects.get() == 3).await;
+
+    // failed message is sent again after reconnect
+    broker.fail_publishes.set(1);
+    bus.publish_to("orders", Order { id: 1 });
+    wait_for(|| broker.published.borrow().len() == 1).await;
+    assert_eq!(broker.connects.get(), 4);
+
+    handle.stop();
+}
+
+#[kayrx::test]
+async fn test_at_least_once() {
+    let bus = Bus::with_capacity(1);
+    let mut orders = bus.subscribe_to::<Order>("orders");
+    let (bridge, tx) = MockBridge::new();
+
+    let handle = Bridge::new(bus.clone(), bridge)
+        .topic::<Order>("orders")
+        .start();
+
+    // subscriber's queue is full, driver waits instead of dropping
+    for id in 0..3 {
+        tx.send(("orders".to_owned(), format!(r#"{{"id":{}}}"#, id).into()))
+            .unwrap();
+    }
+    for id in 0..3 {
+        assert_eq!(orders.recv().await, Some(Order { id }));
+    }
+    assert_eq!(bus.stats()["orders"].dropped, 0);
+
+    handle.stop();
+}
@@ -0,0 +1,88 @@
+mod bridge;
+
+use futures::StreamExt;
+use kayrx::bus::{Bus, TopicStats};
+use kayrx::web::test::{call_service, init_service, TestRequest};
+use kayrx::web::{self, App, HttpResponse};
+
+#[derive(Clone, Debug, PartialEq)]
+struct Event(u32);
+
+#[kayrx::test]
+async fn test_typed_topic() {
+    let bus = Bus::new();
+    let mut sub1 = bus.subscribe::<Event>();
+    let mut sub2 = bus.subscribe::<Event>();
+    let mut other = bus.subscribe::<u32>();
+
+    assert_eq!(bus.publish(Event(1)), 2);
+    assert_eq!(bus.publish(Event(2)), 2);
+    assert_eq!(sub1.recv().await, Some(Event(1)));
+    assert_eq!(sub1.next().await, Some(Event(2)));
+    assert_eq!(sub2.recv().await, Some(Event(1)));
+    assert_eq!(sub2.recv().await, Some(Event(2)));
+    assert_eq!(other.try_recv(), None);
+
+    drop(sub2);
+    assert_eq!(bus.publish(Event(3)), 1);
+    assert_eq!(sub1.recv().await, Some(Event(3)));
+}
+
+#[kayrx::test]
+async fn test_named_topic() {
+    let bus = Bus::new();
+    let mut orders = bus.subscribe_to::<Event>("orders");
+    let mut typed = bus.subscribe::<Event>();
+
+    assert_eq!(bus.publish_to("orders", Event(1)), 1);
+    assert_eq!(bus.publish_to("users", Event(2)), 0);
+    // same name, different type
+    assert_eq!(bus.publish_to("orders", 3u32), 0);
+
+    assert_eq!(orders.recv().await, Some(Event(1)));
+    assert_eq!(orders.try_recv(), None);
+    assert_eq!(typed.try_recv(), None);
+}
+
+#[kayrx::test]
+async fn test_bounded_queue() {
+    let bus = Bus::with_capacity(2);
+    let mut slow = bus.subscribe_to::<Event>("events");
+
+    for i in 0..4 {
+        bus.publish_to("events", Event(i));
+    }
+    assert_eq!(slow.try_recv(), Some(Event(0)));
+    assert_eq!(slow.try_recv(), Some(Event(1)));
+    assert_eq!(slow.try_recv(), None);
+
+    assert_eq!(
+        bus.stats()["events"],
+        TopicStats {
+            subscribers: 1,
+            published: 4,
+            delivered: 2,
+            dropped: 2,
+        }
+    );
+}
+
+#[kayrx::test]
+async fn test_app_data() {
+    let bus = Bus::new();
+    let mut events = bus.subscribe::<Event>();
+
+    let mut srv = init_service(App::new().data(bus.clone()).route(
+        "/",
+        web::post().to(|bus: web::Data<Bus>| async move {
+            bus.publish(Event(10));
+            HttpResponse::Ok().finish()
+        }),
+    ))
+    .await;
+
+    let req = TestRequest::post().uri("/").to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert!(resp.status().is_success());
+    assert_eq!(events.recv().await, Some(Event(10)));
+}
@@ -0,0 +1,133 @@
+use std::cell::{Cell, RefCell};
+use std::io;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::future::{ok, FutureExt, LocalBoxFuture};
+use kayrx::coord::{FileLease, HttpLease, Lease, Singleton};
+use kayrx::timer::delay_for;
+use kayrx::web::{self, test, types::Json, App, HttpResponse};
+use serde_json::Value;
+
+/// Lease shared by instances of the same test
+struct MockLease {
+    id: usize,
+    holder: Rc<Cell<Option<usize>>>,
+}
+
+impl Lease for MockLease {
+    fn acquire(&self, _: Duration) -> LocalBoxFuture<'static, Result<bool, io::Error>> {
+        let res = match self.holder.get() {
+            Some(id) => id == self.id,
+            None => {
+                self.holder.set(Some(self.id));
+                true
+            }
+        };
+        ok(res).boxed_local()
+    }
+
+    fn release(&self) -> LocalBoxFuture<'static, Result<(), io::Error>> {
+        if self.holder.get() == Some(self.id) {
+            self.holder.set(None);
+        }
+        ok(()).boxed_local()
+    }
+}
+
+#[kayrx::test]
+async fn test_singleton() {
+    let holder = Rc::new(Cell::new(None));
+    let runs = Rc::new(RefCell::new(Vec::new()));
+
+    let handles: Vec<_> = (0..2)
+        .map(|id| {
+            let lease = MockLease {
+                id,
+                holder: holder.clone(),
+            };
+            let runs = runs.clone();
+            Singleton::new(lease, Duration::from_millis(50), move || {
+                runs.borrow_mut().push(id);
+                async {}
+            })
+            .start()
+        })
+        .collect();
+
+    delay_for(Duration::from_millis(275)).await;
+    assert!(runs.borrow().len() >= 3);
+    assert!(runs.borrow().iter().all(|id| *id == 0));
+
+    // another instance takes over after leader is stopped
+    handles[0].stop().await;
+    runs.borrow_mut().clear();
+    delay_for(Duration::from_millis(125)).await;
+    assert!(!runs.borrow().is_empty());
+    assert!(runs.borrow().iter().all(|id| *id == 1));
+
+    handles[1].stop().await;
+    assert_eq!(holder.get(), None);
+}
+
+#[kayrx::test]
+async fn test_file_lease() {
+    let path = std::env::temp_dir().join(format!("kayrx-lease-{}", std::process::id()));
+    let lease1 = FileLease::new(&path);
+    let lease2 = FileLease::new(&path);
+    let ttl = Duration::from_secs(1);
+
+    assert!(lease1.acquire(ttl).await.unwrap());
+    assert!(lease1.acquire(ttl).await.unwrap());
+    assert!(!lease2.acquire(ttl).await.unwrap());
+
+    lease1.release().await.unwrap();
+    assert!(lease2.acquire(ttl).await.unwrap());
+    assert!(!lease1.acquire(ttl).await.unwrap());
+
+    lease2.release().await.unwrap();
+    let _ = std::fs::remove_file(&path);
+}
+
+#[kayrx::test]
+async fn test_http_lease() {
+    let holder: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+
+    let srv = test::start(move || {
+        let (holder1, holder2) = (holder.clone(), holder.clone());
+        App::new().service(
+            web::resource("/lease")
+                .route(web::put().to(move |body: Json<Value>| {
+                    let mut holder = holder1.lock().unwrap();
+                    let id = body["holder"].as_str().unwrap().to_owned();
+                    assert_eq!(body["ttl"], 3000);
+                    let res = match *holder {
+                        Some(ref current) if *current != id => HttpResponse::Conflict().finish(),
+                        _ => {
+                            *holder = Some(id);
+                            HttpResponse::Ok().finish()
+                        }
+                    };
+                    async move { res }
+                }))
+                .route(web::delete().to(move |body: Json<Value>| {
+                    let mut holder = holder2.lock().unwrap();
+                    if holder.as_ref().map(|s| s.as_str()) == body["holder"].as_str() {
+                        *holder = None;
+                    }
+                    async { HttpResponse::Ok().finish() }
+                })),
+        )
+    });
+
+    let lease1 = HttpLease::new(&srv.url("/lease")).holder("node-1");
+    let lease2 = HttpLease::new(&srv.url("/lease")).holder("node-2");
+    let ttl = Duration::from_secs(3);
+
+    assert!(lease1.acquire(ttl).await.unwrap());
+    assert!(!lease2.acquire(ttl).await.unwrap());
+    lease1.release().await.unwrap();
+    assert!(lease2.acquire(ttl).await.unwrap());
+    assert!(!lease1.acquire(ttl).await.unwrap());
+}
@@ -0,0 +1,35 @@
+use std::thread;
+use std::time::Duration;
+
+use futures::future::join;
+use kayrx::fiber::{spawn_blocking, System};
+
+#[kayrx::test]
+async fn test_spawn_blocking() {
+    let main = thread::current().id();
+    let (res, id) = spawn_blocking(move || (42, thread::current().id())).await;
+    assert_eq!(res, 42);
+    assert_ne!(id, main);
+}
+
+#[kayrx::test]
+#[should_panic(expected = "blocking failure")]
+async fn test_spawn_blocking_panic() {
+    spawn_blocking(|| panic!("blocking failure")).await
+}
+
+#[test]
+fn test_max_blocking_threads() {
+    System::builder()
+        .max_blocking_threads(1)
+        .blocking_keep_alive(Duration::from_millis(100))
+        .build()
+        .block_on(async {
+            let job = || {
+                thread::sleep(Duration::from_millis(20));
+                thread::current().id()
+            };
+            let (id1, id2) = join(spawn_blocking(job), spawn_blocking(job)).await;
+            assert_eq!(id1, id2);
+        });
+}
@@ -0,0 +1,51 @@
+use std::sync::mpsc;
+use std::time::Duration;
+use std::{net, thread};
+
+use kayrx::fiber::{Runtime, System};
+use kayrx::http::client::Connector;
+use kayrx::web::{self, App, HttpServer};
+
+#[test]
+fn test_diagnostics() {
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let sys = System::new("diagnostics");
+        let tcp = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = tcp.local_addr().unwrap();
+
+        let _ = Connector::new().limit(7).finish();
+        HttpServer::new(|| App::new().service(web::resource("/").to(|| async { "ok" })))
+            .workers(1)
+            .disable_signals()
+            .shutdown_timeout(3)
+            .startup_banner()
+            .listen(tcp)
+            .unwrap()
+            .run();
+        tx.send((System::current(), addr)).unwrap();
+        sys.run()
+    });
+    let (sys, addr) = rx.recv().unwrap();
+    thread::sleep(Duration::from_millis(100));
+
+    let diag = Runtime::diagnostics();
+    assert_eq!(diag.version, env!("CARGO_PKG_VERSION"));
+    assert!(diag.runtime.is_some());
+    let srv = diag
+        .servers
+        .iter()
+        .find(|srv| srv.sockets.iter().any(|sock| sock.addr == addr.to_string()))
+        .unwrap();
+    assert_eq!(srv.workers, 1);
+    assert_eq!(srv.shutdown_timeout, Duration::from_secs(3));
+    assert!(srv.sockets[0].tls.is_none());
+    assert!(diag.clients.iter().any(|client| client.limit == 7));
+    assert!(diag.to_string().contains(&addr.to_string()));
+
+    let json = serde_json::to_value(&diag).unwrap();
+    assert!(json["servers"].is_array());
+
+    sys.stop();
+}
@@ -0,0 +1,2 @@
+mod blocking;
+mod diagnostics;
@@ -0,0 +1,72 @@
+use kayrx::fuzz::{self, DecodeError};
+
+#[test]
+fn test_h1_request() {
+    let data = b"GET /a HTTP/1.1\r\n\r\n\
+                 POST /b HTTP/1.1\r\ncontent-length: 4\r\n\r\ndata\
+                 POST /c HTTP/1.1\r\ntransfer-encoding: chunked\r\n\r\n4\r\ndata\r\n0\r\n\r\n";
+    assert_eq!(fuzz::h1_request(data).unwrap(), 3);
+
+    // incomplete message
+    assert_eq!(fuzz::h1_request(b"GET /a HTTP/1.1\r\n").unwrap(), 0);
+}
+
+#[test]
+fn test_h1_request_malformed() {
+    match fuzz::h1_request(b"GET /a HT/11\r\n\r\n") {
+        Err(DecodeError::Parse(_)) => (),
+        res => panic!("{:?}", res),
+    }
+    match fuzz::h1_request(
+        b"GET /a HTTP/1.1\r\ntransfer-encoding: chunked\r\n\r\nfffffffffffffffff\r\n",
+    ) {
+        Err(DecodeError::Payload(_)) => (),
+        res => panic!("{:?}", res),
+    }
+}
+
+#[test]
+fn test_h1_response() {
+    let data = b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok\
+                 HTTP/1.1 204 No Content\r\n\r\n";
+    assert_eq!(fuzz::h1_response(data).unwrap(), 2);
+}
+
+#[test]
+fn test_ws_frames() {
+    assert_eq!(fuzz::ws_frames(&[0x81, 0x00, 0x82, 0x00], false).unwrap(), 2);
+    assert_eq!(fuzz::ws_frames(&[0x81, 0x80, 0, 0, 0, 0], true).unwrap(), 1);
+
+    match fuzz::ws_frames(&[0x81, 0x00], true) {
+        Err(DecodeError::Protocol(_)) => (),
+        res => panic!("{:?}", res),
+    }
+    match fuzz::ws_frames(&[0x8f, 0x00], false) {
+        Err(DecodeError::Protocol(_)) => (),
+        res => panic!("{:?}", res),
+    }
+}
+
+#[test]
+fn test_multipart() {
+    let data = b"--abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+                 Content-Disposition: form-data; name=\"file\"; filename=\"fn.txt\"\r\n\
+                 Content-Type: text/plain; charset=utf-8\r\n\r\n\
+                 test\r\n\
+                 --abbc761f78ff4d7cb7573b5a23f96ef0--\r\n";
+    assert_eq!(
+        fuzz::multipart("abbc761f78ff4d7cb7573b5a23f96ef0", data).unwrap(),
+        1
+    );
+}
+
+#[test]
+fn test_multipart_malformed() {
+    // short line in preamble must not panic
+    assert!(fuzz::multipart("a", b"--\n").is_err());
+    match fuzz::multipart("a\r\n", b"") {
+        Err(DecodeError::Multipart(_)) => (),
+        res => panic!("{:?}", res),
+    }
+    assert!(fuzz::multipart("abc", b"--abc\r\nbroken\r\n").is_err());
+}
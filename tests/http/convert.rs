@@ -0,0 +1,62 @@
+use std::net;
+
+use kayrx::http::body::Body;
+use kayrx::http::header::{HeaderValue, CONTENT_TYPE};
+use kayrx::http::{Method, Payload, PayloadStream, Request, Response, StatusCode};
+
+#[test]
+fn test_request_conversion() {
+    let addr: net::SocketAddr = "127.0.0.1:8080".parse().unwrap();
+    let mut req = http::Request::builder()
+        .method(Method::POST)
+        .uri("/index.html?q=1")
+        .header(CONTENT_TYPE, "text/plain")
+        .header("x-test", "1")
+        .header("x-test", "2")
+        .body(Payload::<PayloadStream>::None)
+        .unwrap();
+    req.extensions_mut().insert(10u32);
+    req.extensions_mut().insert(addr);
+
+    let req = Request::from(req);
+    assert_eq!(*req.method(), Method::POST);
+    assert_eq!(req.uri().query(), Some("q=1"));
+    assert_eq!(req.peer_addr(), Some(addr));
+    assert_eq!(req.head().headers.get_all("x-test").count(), 2);
+    assert_eq!(
+        req.head().extensions().get::<http::Extensions>().unwrap().get::<u32>(),
+        Some(&10)
+    );
+
+    let req = http::Request::from(req);
+    assert_eq!(*req.method(), Method::POST);
+    assert_eq!(req.uri().path(), "/index.html");
+    assert_eq!(req.headers()[CONTENT_TYPE], "text/plain");
+    assert_eq!(req.headers().get_all("x-test").iter().count(), 2);
+    assert_eq!(req.extensions().get::<u32>(), Some(&10));
+    assert_eq!(req.extensions().get::<net::SocketAddr>(), Some(&addr));
+}
+
+#[test]
+fn test_response_conversion() {
+    let mut res = http::Response::builder()
+        .status(StatusCode::CREATED)
+        .header(CONTENT_TYPE, "text/plain")
+        .body(Body::from("test"))
+        .unwrap();
+    res.extensions_mut().insert(10u32);
+
+    let res = Response::from(res);
+    assert_eq!(res.status(), StatusCode::CREATED);
+    assert_eq!(res.body().get_ref(), b"test");
+    assert_eq!(
+        res.headers().get(CONTENT_TYPE).unwrap(),
+        HeaderValue::from_static("text/plain")
+    );
+
+    let res = http::Response::from(res);
+    assert_eq!(res.status(), StatusCode::CREATED);
+    assert_eq!(res.headers()[CONTENT_TYPE], "text/plain");
+    assert_eq!(res.extensions().get::<u32>(), Some(&10));
+    assert_eq!(res.body().get_ref(), b"test");
+}
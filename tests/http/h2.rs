@@ -0,0 +1,158 @@
+use std::sync::mpsc;
+use std::time::Duration;
+use std::{net, thread};
+
+use bytes::Bytes;
+use kayrx::fiber::System;
+use kayrx::http::h2::{client, Reason};
+use kayrx::http::error::Error;
+use kayrx::http::header::{HeaderValue, ACCEPT, CONTENT_TYPE};
+use kayrx::http::{HeaderMap, HttpService, Request, Response};
+use kayrx::krse::net::TcpStream;
+use kayrx::service::fn_service;
+use kayrx::timer::delay_for;
+
+fn start(max: u32, reason: Reason) -> (System, net::SocketAddr) {
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let sys = System::new("test");
+        let tcp = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = tcp.local_addr().unwrap();
+
+        kayrx::server::new()
+            .workers(1)
+            .disable_signals()
+            .listen("test", tcp, move || {
+                HttpService::build()
+                    .h2_max_concurrent_streams(max)
+                    .h2_overflow_reason(reason)
+                    .h2_initial_window_size(1_000_000)
+                    .h2(fn_service(|_: Request| async {
+                        delay_for(Duration::from_millis(200)).await;
+                        Ok::<_, Error>(Response::Ok().finish())
+                    }))
+                    .tcp()
+            })
+            .unwrap()
+            .start();
+        tx.send((System::current(), addr)).unwrap();
+        sys.run()
+    });
+    rx.recv().unwrap()
+}
+
+fn request() -> http::Request<()> {
+    http::Request::get("http://localhost/").body(()).unwrap()
+}
+
+#[kayrx::test]
+async fn test_max_concurrent_streams_refused() {
+    let (sys, addr) = start(1, Reason::REFUSED_STREAM);
+
+    let io = TcpStream::connect(addr).await.unwrap();
+    let (mut h2, conn) = client::handshake(io).await.unwrap();
+
+    // both streams are sent before server settings are received
+    let (first, _) = h2.send_request(request(), true).unwrap();
+    let (second, _) = h2.send_request(request(), true).unwrap();
+    kayrx::fiber::spawn(async move {
+        let _ = conn.await;
+    });
+
+    let err = second.await.unwrap_err();
+    assert_eq!(err.reason(), Some(Reason::REFUSED_STREAM));
+
+    // connection is still usable
+    let res = first.await.unwrap();
+    assert!(res.status().is_success());
+
+    let mut h2 = h2.ready().await.unwrap();
+    let (res, _) = h2.send_request(request(), true).unwrap();
+    assert!(res.await.unwrap().status().is_success());
+
+    sys.stop();
+}
+
+#[kayrx::test]
+async fn test_max_concurrent_streams_calm() {
+    let (sys, addr) = start(1, Reason::ENHANCE_YOUR_CALM);
+
+    let io = TcpStream::connect(addr).await.unwrap();
+    let (mut h2, conn) = client::handshake(io).await.unwrap();
+
+    let (_first, _) = h2.send_request(request(), true).unwrap();
+    let (second, _) = h2.send_request(request(), true).unwrap();
+    kayrx::fiber::spawn(async move {
+        let _ = conn.await;
+    });
+
+    let err = second.await.unwrap_err();
+    assert_eq!(err.reason(), Some(Reason::ENHANCE_YOUR_CALM));
+
+    sys.stop();
+}
+
+#[kayrx::test]
+async fn test_server_push() {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let sys = System::new("test");
+        let tcp = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = tcp.local_addr().unwrap();
+
+        kayrx::server::new()
+            .workers(1)
+            .disable_signals()
+            .listen("test", tcp, move || {
+                HttpService::build()
+                    .h2(fn_service(|req: Request| async move {
+                        let res = if req.path() == "/" {
+                            let mut headers = HeaderMap::new();
+                            headers.insert(ACCEPT, HeaderValue::from_static("text/css"));
+                            Response::Ok()
+                                .push_promise("/app.css", headers)
+                                .priority(256)
+                                .body("index")
+                        } else {
+                            let accept = req.head().headers.get(ACCEPT).unwrap().clone();
+                            Response::Ok().header(CONTENT_TYPE, accept).body("css")
+                        };
+                        Ok::<_, Error>(res)
+                    }))
+                    .tcp()
+            })
+            .unwrap()
+            .start();
+        tx.send((System::current(), addr)).unwrap();
+        sys.run()
+    });
+    let (sys, addr) = rx.recv().unwrap();
+
+    let io = TcpStream::connect(addr).await.unwrap();
+    let (mut h2, conn) = client::handshake(io).await.unwrap();
+    kayrx::fiber::spawn(async move {
+        let _ = conn.await;
+    });
+
+    let (mut res, _) = h2.send_request(request(), true).unwrap();
+    let mut pushes = res.push_promises();
+    let res = res.await.unwrap();
+    assert!(res.status().is_success());
+    let mut body = res.into_body();
+    assert_eq!(body.data().await.unwrap().unwrap(), Bytes::from_static(b"index"));
+
+    // promised request is dispatched by the server
+    let (req, pushed) = pushes.push_promise().await.unwrap().unwrap().into_parts();
+    assert_eq!(req.uri().path(), "/app.css");
+    assert_eq!(req.headers().get(ACCEPT).unwrap(), "text/css");
+
+    let res = pushed.await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.headers().get(CONTENT_TYPE).unwrap(), "text/css");
+    let mut body = res.into_body();
+    assert_eq!(body.data().await.unwrap().unwrap(), Bytes::from_static(b"css"));
+    assert!(pushes.push_promise().await.is_none());
+
+    sys.stop();
+}
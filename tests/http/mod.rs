@@ -1,3 +1,5 @@
 mod h1;
+mod h2;
 mod config;
-mod body;
\ No newline at end of file
+mod body;
+mod convert;
\ No newline at end of file
@@ -0,0 +1,46 @@
+use std::io::SeekFrom;
+use std::path::PathBuf;
+
+use kayrx::krse::fs::{self, File};
+use kayrx::krse::io::{AsyncReadExt, AsyncWriteExt};
+
+fn temp_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("kayrx-fs-{}-{}", name, std::process::id()))
+}
+
+#[kayrx::test]
+async fn test_file() {
+    let dir = temp_dir("file").join("nested");
+    fs::create_dir_all(&dir).await.unwrap();
+    let path = dir.join("data.txt");
+
+    let mut file = File::create(&path).await.unwrap();
+    file.write_all(b"hello world").await.unwrap();
+    file.sync_all().await.unwrap();
+    assert_eq!(file.metadata().await.unwrap().len(), 11);
+    drop(file);
+
+    let mut file = File::open(&path).await.unwrap();
+    assert_eq!(file.seek(SeekFrom::Start(6)).await.unwrap(), 6);
+    let mut buf = [0u8; 5];
+    let n = file.read(&mut buf).await.unwrap();
+    assert_eq!(&buf[..n], &b"world"[..n]);
+
+    fs::remove_dir_all(temp_dir("file")).await.unwrap();
+}
+
+#[kayrx::test]
+async fn test_helpers() {
+    let dir = temp_dir("helpers");
+    fs::create_dir_all(&dir).await.unwrap();
+    let path = dir.join("data.txt");
+
+    fs::write(&path, "kayrx").await.unwrap();
+    assert_eq!(fs::read(&path).await.unwrap(), b"kayrx");
+    assert_eq!(fs::read_to_string(&path).await.unwrap(), "kayrx");
+    assert!(fs::metadata(&path).await.unwrap().is_file());
+
+    fs::remove_file(&path).await.unwrap();
+    assert!(fs::read(&path).await.is_err());
+    fs::remove_dir(&dir).await.unwrap();
+}
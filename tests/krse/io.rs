@@ -0,0 +1,45 @@
+use kayrx::krse::io::{self, AsyncReadExt, AsyncWriteExt};
+
+#[kayrx::test]
+async fn test_duplex() {
+    let (mut client, mut server) = io::duplex(64);
+
+    client.write_all(b"ping").await.unwrap();
+    let mut buf = [0u8; 4];
+    server.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"ping");
+
+    server.write_all(b"pong").await.unwrap();
+    client.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"pong");
+}
+
+#[kayrx::test]
+async fn test_duplex_capacity() {
+    let (mut client, mut server) = io::duplex(2);
+
+    let mut buf = [0u8; 5];
+    let (res1, res2) = futures::future::join(
+        client.write_all(b"hello"),
+        server.read_exact(&mut buf),
+    )
+    .await;
+    res1.unwrap();
+    res2.unwrap();
+    assert_eq!(&buf, b"hello");
+}
+
+#[kayrx::test]
+async fn test_duplex_close() {
+    let (mut client, mut server) = io::duplex(64);
+
+    client.write_all(b"bye").await.unwrap();
+    drop(client);
+
+    let mut buf = Vec::new();
+    server.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, b"bye");
+
+    let err = server.write_all(b"ping").await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::BrokenPipe);
+}
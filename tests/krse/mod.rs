@@ -1 +1,3 @@
+mod fs;
+mod io;
 mod sync;
@@ -0,0 +1,67 @@
+use futures::StreamExt;
+use kayrx::krse::sync::broadcast::{self, RecvError, TryRecvError};
+
+#[kayrx::test]
+async fn test_broadcast_every_receiver() {
+    let (tx, mut rx1) = broadcast::channel(16);
+    let mut rx2 = tx.subscribe();
+    assert_eq!(tx.receiver_count(), 2);
+
+    assert_eq!(tx.send(1).unwrap(), 2);
+    assert_eq!(tx.send(2).unwrap(), 2);
+
+    assert_eq!(rx1.recv().await.unwrap(), 1);
+    assert_eq!(rx1.recv().await.unwrap(), 2);
+    assert_eq!(rx2.recv().await.unwrap(), 1);
+    assert_eq!(rx2.recv().await.unwrap(), 2);
+
+    // late subscriber sees values sent after subscription only
+    let mut rx3 = tx.subscribe();
+    match rx3.try_recv() {
+        Err(TryRecvError::Empty) => (),
+        _ => panic!(),
+    }
+    tx.send(3).unwrap();
+    assert_eq!(rx3.recv().await.unwrap(), 3);
+}
+
+#[kayrx::test]
+async fn test_broadcast_lagged() {
+    let (tx, mut rx) = broadcast::channel(2);
+
+    tx.send(1).unwrap();
+    tx.send(2).unwrap();
+    tx.send(3).unwrap();
+
+    match rx.recv().await {
+        Err(RecvError::Lagged(1)) => (),
+        _ => panic!(),
+    }
+    assert_eq!(rx.recv().await.unwrap(), 2);
+    assert_eq!(rx.recv().await.unwrap(), 3);
+
+    drop(tx);
+    match rx.recv().await {
+        Err(RecvError::Closed) => (),
+        _ => panic!(),
+    }
+}
+
+#[kayrx::test]
+async fn test_broadcast_stream() {
+    let (tx, mut rx) = broadcast::channel(2);
+    tx.send(1).unwrap();
+    tx.send(2).unwrap();
+    tx.send(3).unwrap();
+
+    assert!(match rx.next().await {
+        Some(Err(RecvError::Lagged(1))) => true,
+        _ => false,
+    });
+    assert_eq!(rx.next().await.unwrap().unwrap(), 2);
+    assert_eq!(rx.next().await.unwrap().unwrap(), 3);
+
+    // stream ends once all senders are dropped
+    drop(tx);
+    assert!(rx.next().await.is_none());
+}
@@ -1 +1,2 @@
-mod local;
\ No newline at end of file
+mod broadcast;
+mod local;
@@ -1,6 +1,16 @@
+mod bus;
+mod coord;
+mod fiber;
+mod fuzz;
 mod http;
 mod krse;
+mod secure;
 mod service;
+#[cfg(feature = "sim")]
+mod sim;
+#[cfg(feature = "telemetry")]
+mod telemetry;
+mod timer;
 mod util;
 mod web;
 mod webui;
\ No newline at end of file
@@ -0,0 +1,212 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::channel::mpsc;
+use kayrx::secure::tls::rust_tls::internal::pemfile;
+use kayrx::secure::tls::rust_tls::{
+    Certificate, ClientConfig, ClientSession, PrivateKey, RootCertStore, ServerCertVerified,
+    ServerCertVerifier, ServerConfig, ServerSession, Session, TLSError, NoClientAuth,
+    ResolvesServerCert,
+};
+use kayrx::secure::tls::webpki::DNSNameRef;
+use kayrx::secure::tls::{CertResolver, SniResolver};
+use kayrx::timer::delay_for;
+
+fn certs(name: &str) -> Vec<Certificate> {
+    let path = format!("{}/tests/certs/{}-cert.pem", env!("CARGO_MANIFEST_DIR"), name);
+    pemfile::certs(&mut BufReader::new(File::open(path).unwrap())).unwrap()
+}
+
+fn key(name: &str) -> PrivateKey {
+    let path = format!("{}/tests/certs/{}-key.pem", env!("CARGO_MANIFEST_DIR"), name);
+    pemfile::pkcs8_private_keys(&mut BufReader::new(File::open(path).unwrap()))
+        .unwrap()
+        .remove(0)
+}
+
+/// Accept any server certificate, tests check presented certificate
+struct NoVerify;
+
+impl ServerCertVerifier for NoVerify {
+    fn verify_server_cert(
+        &self,
+        _: &RootCertStore,
+        _: &[Certificate],
+        _: DNSNameRef<'_>,
+        _: &[u8],
+    ) -> Result<ServerCertVerified, TLSError> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+fn transfer(from: &mut dyn Session, to: &mut dyn Session) {
+    let mut buf = Vec::new();
+    while from.wants_write() {
+        from.write_tls(&mut buf).unwrap();
+    }
+    let mut rd = &buf[..];
+    while !rd.is_empty() {
+        to.read_tls(&mut rd).unwrap();
+    }
+}
+
+/// Complete in-memory handshake, returns certificate presented by the
+/// server
+fn handshake<R>(resolver: R, name: Option<&str>) -> Result<Certificate, TLSError>
+where
+    R: ResolvesServerCert + 'static,
+{
+    let mut server_cfg = ServerConfig::new(NoClientAuth::new());
+    server_cfg.cert_resolver = Arc::new(resolver);
+    let mut server = ServerSession::new(&Arc::new(server_cfg));
+
+    let mut client_cfg = ClientConfig::new();
+    client_cfg
+        .dangerous()
+        .set_certificate_verifier(Arc::new(NoVerify));
+    client_cfg.enable_sni = name.is_some();
+    let name = DNSNameRef::try_from_ascii_str(name.unwrap_or("localhost")).unwrap();
+    let mut client = ClientSession::new(&Arc::new(client_cfg), name);
+
+    while client.is_handshaking() || server.is_handshaking() {
+        transfer(&mut client, &mut server);
+        server.process_new_packets()?;
+        transfer(&mut server, &mut client);
+        client.process_new_packets()?;
+    }
+    Ok(client.get_peer_certificates().unwrap().remove(0))
+}
+
+fn sni_resolver() -> SniResolver {
+    let mut resolver = SniResolver::new();
+    resolver
+        .add("localhost", certs("localhost"), &key("localhost"))
+        .unwrap();
+    resolver
+        .add("*.example.com", certs("wildcard"), &key("wildcard"))
+        .unwrap();
+    resolver
+}
+
+#[test]
+fn test_sni_resolver() {
+    let localhost = certs("localhost").remove(0);
+    let wildcard = certs("wildcard").remove(0);
+
+    assert_eq!(handshake(sni_resolver(), Some("localhost")).unwrap(), localhost);
+    assert_eq!(handshake(sni_resolver(), Some("LocalHost")).unwrap(), localhost);
+    assert_eq!(
+        handshake(sni_resolver(), Some("www.example.com")).unwrap(),
+        wildcard
+    );
+
+    // wildcard matches single label only, no default certificate
+    assert!(handshake(sni_resolver(), Some("a.b.example.com")).is_err());
+    assert!(handshake(sni_resolver(), Some("example.org")).is_err());
+    assert!(handshake(sni_resolver(), None).is_err());
+}
+
+#[test]
+fn test_sni_resolver_default() {
+    let mut resolver = sni_resolver();
+    resolver
+        .set_default(certs("localhost"), &key("localhost"))
+        .unwrap();
+    let localhost = certs("localhost").remove(0);
+    let wildcard = certs("wildcard").remove(0);
+
+    assert_eq!(
+        handshake(resolver.clone(), Some("a.b.example.com")).unwrap(),
+        localhost
+    );
+    assert_eq!(handshake(resolver.clone(), None).unwrap(), localhost);
+    assert_eq!(
+        handshake(resolver, Some("api.example.com")).unwrap(),
+        wildcard
+    );
+}
+
+fn default_resolver(name: &str) -> SniResolver {
+    let mut resolver = SniResolver::new();
+    resolver.set_default(certs(name), &key(name)).unwrap();
+    resolver
+}
+
+#[test]
+fn test_cert_resolver_set() {
+    let resolver = CertResolver::new(default_resolver("localhost"));
+
+    assert_eq!(
+        handshake(resolver.clone(), Some("localhost")).unwrap(),
+        certs("localhost").remove(0)
+    );
+
+    // following handshakes use new certificate
+    resolver.set(default_resolver("wildcard"));
+    assert_eq!(
+        handshake(resolver.clone(), Some("localhost")).unwrap(),
+        certs("wildcard").remove(0)
+    );
+    assert_eq!(
+        handshake(resolver, Some("localhost")).unwrap(),
+        certs("wildcard").remove(0)
+    );
+}
+
+#[kayrx::test]
+async fn test_cert_resolver_watch() {
+    let resolver = CertResolver::new(default_resolver("localhost"));
+    let (tx, rx) = mpsc::unbounded();
+    resolver.watch(rx);
+
+    tx.unbounded_send(default_resolver("wildcard")).unwrap();
+    delay_for(Duration::from_millis(10)).await;
+    assert_eq!(
+        handshake(resolver.clone(), Some("localhost")).unwrap(),
+        certs("wildcard").remove(0)
+    );
+
+    tx.unbounded_send(default_resolver("localhost")).unwrap();
+    delay_for(Duration::from_millis(10)).await;
+    assert_eq!(
+        handshake(resolver, Some("localhost")).unwrap(),
+        certs("localhost").remove(0)
+    );
+}
+
+#[kayrx::test]
+async fn test_cert_resolver_watch_files() {
+    let dir = std::env::temp_dir().join("kayrx-tls-watch-files");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let src = format!("{}/tests/certs", env!("CARGO_MANIFEST_DIR"));
+    let (cert, key) = (dir.join("cert.pem"), dir.join("key.pem"));
+    std::fs::copy(format!("{}/localhost-cert.pem", src), &cert).unwrap();
+    std::fs::copy(format!("{}/localhost-key.pem", src), &key).unwrap();
+
+    let resolver = CertResolver::from_files(&cert, &key).unwrap();
+    resolver.watch_files(&cert, &key, Duration::from_millis(5));
+
+    // empty certificate file is rejected, current certificate stays in use
+    delay_for(Duration::from_millis(20)).await;
+    std::fs::write(&cert, b"").unwrap();
+    let err = CertResolver::from_files(&cert, &key).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    delay_for(Duration::from_millis(30)).await;
+    assert_eq!(
+        handshake(resolver.clone(), Some("localhost")).unwrap(),
+        certs("localhost").remove(0)
+    );
+
+    std::fs::copy(format!("{}/wildcard-cert.pem", src), &cert).unwrap();
+    std::fs::copy(format!("{}/wildcard-key.pem", src), &key).unwrap();
+    delay_for(Duration::from_millis(30)).await;
+    assert_eq!(
+        handshake(resolver, Some("localhost")).unwrap(),
+        certs("wildcard").remove(0)
+    );
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -4,4 +4,6 @@ mod apply;
 mod fn_service;
 mod map_err;
 mod map;
-mod then;
\ No newline at end of file
+mod then;
+#[cfg(feature = "tower")]
+mod tower;
@@ -0,0 +1,91 @@
+use futures_util::future::{lazy, ok, Ready};
+use std::task::{Context, Poll};
+use kayrx::service::tower::{from_tower, into_tower, layer};
+use kayrx::service::{Service, ServiceFactory, Transform};
+
+#[derive(Clone)]
+struct Double;
+
+impl tower_service::Service<u32> for Double {
+    type Response = u32;
+    type Error = ();
+    type Future = Ready<Result<u32, ()>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), ()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: u32) -> Self::Future {
+        ok(req * 2)
+    }
+}
+
+struct AddOne<S>(S);
+
+impl<S: tower_service::Service<u32, Response = u32>> tower_service::Service<u32> for AddOne<S> {
+    type Response = u32;
+    type Error = S::Error;
+    type Future = futures_util::future::MapOk<S::Future, fn(u32) -> u32>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.0.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: u32) -> Self::Future {
+        use futures_util::TryFutureExt;
+        self.0.call(req).map_ok((|res| res + 1) as fn(u32) -> u32)
+    }
+}
+
+#[derive(Clone)]
+struct AddOneLayer;
+
+struct Triple;
+
+impl Service for Triple {
+    type Request = u32;
+    type Response = u32;
+    type Error = ();
+    type Future = Ready<Result<u32, ()>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), ()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: u32) -> Self::Future {
+        ok(req * 3)
+    }
+}
+
+impl<S> tower_layer::Layer<S> for AddOneLayer {
+    type Service = AddOne<S>;
+
+    fn layer(&self, service: S) -> AddOne<S> {
+        AddOne(service)
+    }
+}
+
+#[kayrx::test]
+async fn test_from_tower() {
+    let mut srv = from_tower(Double);
+    let res = lazy(|cx| srv.poll_ready(cx)).await;
+    assert_eq!(res, Poll::Ready(Ok(())));
+    assert_eq!(srv.call(2).await, Ok(4));
+
+    let mut srv = from_tower(Double).new_service(()).await.unwrap();
+    assert_eq!(srv.call(3).await, Ok(6));
+}
+
+#[kayrx::test]
+async fn test_into_tower() {
+    let mut srv = into_tower(Triple);
+    let res = lazy(|cx| tower_service::Service::poll_ready(&mut srv, cx)).await;
+    assert_eq!(res, Poll::Ready(Ok(())));
+    assert_eq!(tower_service::Service::call(&mut srv, 1).await, Ok(3));
+}
+
+#[kayrx::test]
+async fn test_layer() {
+    let mut srv = layer(AddOneLayer).new_transform(Triple).await.unwrap();
+    assert_eq!(srv.call(2).await, Ok(7));
+}
@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use kayrx::krse::io::{AsyncReadExt, AsyncWriteExt};
+use kayrx::sim::{self, Link, Network};
+use kayrx::timer::{delay_for, timeout, Instant};
+
+#[test]
+fn test_paused_clock() {
+    sim::run(async {
+        let start = Instant::now();
+        delay_for(Duration::from_secs(3600)).await;
+        assert_eq!(start.elapsed(), Duration::from_secs(3600));
+
+        let res = timeout(Duration::from_secs(1), delay_for(Duration::from_secs(2))).await;
+        assert!(res.is_err());
+        assert_eq!(start.elapsed(), Duration::from_secs(3601));
+    });
+}
+
+#[test]
+fn test_latency() {
+    sim::run(async {
+        let net = Network::new(0);
+        net.link("a", "b", Link::new().latency(Duration::from_millis(10)));
+
+        let mut listener = net.bind("b").unwrap();
+        let start = Instant::now();
+        let mut client = net.connect("a", "b").await.unwrap();
+        let mut server = listener.next().await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_millis(20));
+
+        client.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        server.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+        assert_eq!(start.elapsed(), Duration::from_millis(30));
+
+        drop(client);
+        let mut rest = Vec::new();
+        server.read_to_end(&mut rest).await.unwrap();
+        assert!(rest.is_empty());
+    });
+}
+
+#[test]
+fn test_loss_is_deterministic() {
+    fn transfer(seed: u64) -> Duration {
+        sim::run(async move {
+            let net = Network::new(seed);
+            net.default_link(
+                Link::new()
+                    .latency(Duration::from_millis(5))
+                    .jitter(Duration::from_millis(5))
+                    .loss(0.3),
+            );
+            let mut listener = net.bind("server").unwrap();
+            let start = Instant::now();
+            let mut client = net.connect("client", "server").await.unwrap();
+            let mut server = listener.next().await.unwrap();
+
+            for _ in 0..10 {
+                client.write_all(b"data").await.unwrap();
+            }
+            let mut buf = [0u8; 40];
+            server.read_exact(&mut buf).await.unwrap();
+            start.elapsed()
+        })
+    }
+
+    assert_eq!(transfer(42), transfer(42));
+}
+
+#[test]
+fn test_partition() {
+    sim::run(async {
+        let net = Network::new(0);
+        let mut listener = net.bind("b").unwrap();
+        let mut client = net.connect("a", "b").await.unwrap();
+        let mut server = listener.next().await.unwrap();
+
+        net.partition("a", "b");
+        let mut buf = [0u8; 1];
+        let err = server.read(&mut buf).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::ConnectionReset);
+        assert!(client.write_all(b"x").await.is_err());
+
+        let err = net.connect("a", "b").await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+
+        net.heal("a", "b");
+        assert!(net.connect("a", "b").await.is_ok());
+        assert!(net.connect("a", "c").await.is_err());
+    });
+}
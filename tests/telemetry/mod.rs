@@ -0,0 +1,102 @@
+use std::sync::{Arc, Mutex};
+
+use serde_json::Value;
+
+use kayrx::telemetry::{counter, histogram, HttpMetrics, Telemetry};
+use kayrx::web::test::{self, call_service, init_service, TestRequest};
+use kayrx::web::{self, middleware, types::Json, App, HttpRequest, HttpResponse};
+
+type Received = Arc<Mutex<Vec<(String, Value)>>>;
+
+fn collector(received: Received) -> test::TestServer {
+    test::start(move || {
+        let received = received.clone();
+        App::new().default_service(web::to(move |req: HttpRequest, body: Json<Value>| {
+            received
+                .lock()
+                .unwrap()
+                .push((req.path().to_owned(), body.into_inner()));
+            HttpResponse::Ok()
+        }))
+    })
+}
+
+fn find<'a>(received: &'a [(String, Value)], path: &str) -> Vec<&'a Value> {
+    received
+        .iter()
+        .filter(|(p, _)| p == path)
+        .map(|(_, body)| body)
+        .collect()
+}
+
+// registry and spans queue are global, so everything is checked by one test
+#[kayrx::test]
+async fn test_export() {
+    let received = Received::default();
+    let srv = collector(received.clone());
+
+    counter("test.jobs", &[("queue", "default")]).add(3);
+    histogram("test.latency", &[]).record(12.0);
+
+    let exporter = Telemetry::new(&srv.url("/"))
+        .service_name("test-service")
+        .start();
+    exporter.flush().await;
+
+    {
+        let received = received.lock().unwrap();
+        let metrics = find(&received, "/v1/metrics");
+        assert_eq!(metrics.len(), 1);
+
+        let resource = &metrics[0]["resourceMetrics"][0];
+        assert!(resource["resource"]["attributes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|attr| attr["key"] == "service.name"
+                && attr["value"]["stringValue"] == "test-service"));
+
+        let list = resource["scopeMetrics"][0]["metrics"].as_array().unwrap();
+        let jobs = list.iter().find(|m| m["name"] == "test.jobs").unwrap();
+        assert_eq!(jobs["sum"]["dataPoints"][0]["asInt"], "3");
+        assert_eq!(jobs["sum"]["isMonotonic"], true);
+        let latency = list.iter().find(|m| m["name"] == "test.latency").unwrap();
+        assert_eq!(latency["histogram"]["dataPoints"][0]["count"], "1");
+    }
+    received.lock().unwrap().clear();
+
+    let mut app = init_service(
+        App::new()
+            .wrap(HttpMetrics)
+            .wrap(middleware::Trace::new())
+            .service(web::resource("/").to(|| HttpResponse::Ok())),
+    )
+    .await;
+    let req = TestRequest::default()
+        .header(
+            "traceparent",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        )
+        .to_request();
+    let _ = call_service(&mut app, req).await;
+
+    exporter.shutdown().await;
+    // following calls complete immediately
+    exporter.shutdown().await;
+
+    let received = received.lock().unwrap();
+    let traces = find(&received, "/v1/traces");
+    assert_eq!(traces.len(), 1);
+    let span = &traces[0]["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+    assert_eq!(span["traceId"], "4bf92f3577b34da6a3ce929d0e0e4736");
+    assert_eq!(span["parentSpanId"], "00f067aa0ba902b7");
+    assert_eq!(span["kind"], 2);
+    assert_eq!(span["name"], "GET");
+
+    let metrics = find(&received, "/v1/metrics");
+    assert_eq!(metrics.len(), 1);
+    let list = metrics[0]["resourceMetrics"][0]["scopeMetrics"][0]["metrics"]
+        .as_array()
+        .unwrap();
+    assert!(list.iter().any(|m| m["name"] == "http.server.requests"));
+}
@@ -0,0 +1,32 @@
+use std::time::Duration;
+
+use kayrx::timer::{delay_for, delay_until, Instant};
+
+#[kayrx::test]
+async fn test_delay_reset_after() {
+    let mut delay = delay_for(Duration::from_secs(60));
+    let start = Instant::now();
+
+    delay.reset_after(Duration::from_millis(10));
+    assert!(delay.deadline() < start + Duration::from_secs(1));
+    (&mut delay).await;
+    assert!(delay.is_elapsed());
+
+    // too long durations are capped
+    delay.reset_after(Duration::from_secs(u64::max_value()));
+    assert!(!delay.is_elapsed());
+    assert!(delay.deadline() <= Instant::far_future());
+}
+
+#[kayrx::test]
+async fn test_far_future() {
+    let now = Instant::now();
+    let far = Instant::far_future();
+    assert!(far.saturating_duration_since(now) > Duration::from_secs(86400 * 365));
+    assert_eq!(now.saturating_duration_since(far), Duration::from_secs(0));
+    assert!(far.checked_add(Duration::from_secs(1)).is_some());
+
+    let mut delay = delay_until(far);
+    let res = kayrx::timer::timeout(Duration::from_millis(10), &mut delay).await;
+    assert!(res.is_err());
+}
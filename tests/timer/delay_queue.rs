@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use futures::future::poll_fn;
+use kayrx::timer::{delay_for, ClosePolicy, DelayQueue, Instant};
+
+#[kayrx::test]
+async fn test_pop_expired_drains_without_waiting() {
+    let mut queue = DelayQueue::with_capacity(4);
+    assert!(queue.capacity() >= 4);
+    assert!(queue.peek().is_none());
+
+    let start = Instant::now();
+    queue.insert(1, Duration::from_millis(0));
+    queue.insert(2, Duration::from_millis(5));
+    queue.insert(3, Duration::from_secs(60));
+    assert_eq!(queue.len(), 3);
+    assert!(queue.peek().unwrap() < start + Duration::from_secs(1));
+
+    delay_for(Duration::from_millis(20)).await;
+
+    let mut expired = Vec::new();
+    while let Some(item) = queue.pop_expired() {
+        expired.push(item.into_inner());
+    }
+    expired.sort();
+    assert_eq!(expired, vec![1, 2]);
+    assert_eq!(queue.len(), 1);
+    assert!(queue.pop_expired().is_none());
+    assert!(queue.peek().unwrap() >= start + Duration::from_secs(59));
+
+    // polling keeps working after the queue was drained
+    queue.insert(4, Duration::from_millis(1));
+    let item = poll_fn(|cx| queue.poll_expired(cx)).await.unwrap().unwrap();
+    assert_eq!(item.into_inner(), 4);
+}
+
+#[kayrx::test]
+async fn test_close_drains_immediately() {
+    let mut queue = DelayQueue::new();
+    queue.insert(3, Duration::from_secs(3600));
+    queue.insert(1, Duration::from_millis(0));
+    queue.insert(2, Duration::from_secs(60));
+    assert!(!queue.is_closed());
+
+    queue.close();
+    assert!(queue.is_closed());
+
+    let start = Instant::now();
+    let mut items = Vec::new();
+    while let Some(item) = poll_fn(|cx| queue.poll_expired(cx)).await {
+        items.push(item.unwrap().into_inner());
+    }
+    assert_eq!(items, vec![1, 2, 3]);
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert!(queue.is_empty());
+}
+
+#[kayrx::test]
+async fn test_close_wait_keeps_deadlines() {
+    let mut queue = DelayQueue::new();
+    queue.insert(1, Duration::from_millis(10));
+    queue.insert(2, Duration::from_secs(60));
+    queue.close_with(ClosePolicy::Wait);
+
+    let item = poll_fn(|cx| queue.poll_expired(cx)).await.unwrap().unwrap();
+    assert_eq!(item.into_inner(), 1);
+    assert!(queue.pop_expired().is_none());
+    assert_eq!(queue.len(), 1);
+}
+
+#[kayrx::test]
+#[should_panic(expected = "insert into closed DelayQueue")]
+async fn test_insert_into_closed() {
+    let mut queue = DelayQueue::new();
+    queue.close();
+    queue.insert(1, Duration::from_secs(1));
+}
@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use kayrx::timer::{delay_for, interval, Instant, MissedTickBehavior};
+
+#[kayrx::test]
+async fn test_interval_reset() {
+    let mut interval = interval(Duration::from_millis(50));
+    let start = interval.tick().await;
+
+    delay_for(Duration::from_millis(30)).await;
+    interval.reset();
+    let next = interval.tick().await;
+    assert!(next >= start + Duration::from_millis(80));
+
+    let at = Instant::now() + Duration::from_millis(10);
+    interval.reset_at(at);
+    assert_eq!(interval.tick().await, at);
+    assert_eq!(interval.tick().await, at + Duration::from_millis(50));
+}
+
+#[kayrx::test]
+async fn test_interval_set_period() {
+    let mut interval = interval(Duration::from_millis(20));
+    let first = interval.tick().await;
+
+    interval.set_period(Duration::from_millis(40));
+    assert_eq!(interval.period(), Duration::from_millis(40));
+
+    // already scheduled tick keeps the previous period
+    let second = interval.tick().await;
+    assert_eq!(second, first + Duration::from_millis(20));
+    let third = interval.tick().await;
+    assert_eq!(third, second + Duration::from_millis(40));
+}
+
+async fn stalled_ticks(behavior: MissedTickBehavior) -> (Instant, Instant, Instant) {
+    let mut interval = interval(Duration::from_millis(20));
+    interval.set_missed_tick_behavior(behavior);
+    assert_eq!(interval.missed_tick_behavior(), behavior);
+    let start = interval.tick().await;
+
+    // block the task, so ticks at 20ms and 40ms are missed
+    std::thread::sleep(Duration::from_millis(50));
+    let first = interval.tick().await;
+    let second = interval.tick().await;
+    (start, first, second)
+}
+
+#[kayrx::test]
+async fn test_interval_missed_tick_burst() {
+    let (start, first, second) = stalled_ticks(MissedTickBehavior::Burst).await;
+    assert_eq!(first, start + Duration::from_millis(20));
+    assert_eq!(second, start + Duration::from_millis(40));
+}
+
+#[kayrx::test]
+async fn test_interval_missed_tick_skip() {
+    let (start, first, second) = stalled_ticks(MissedTickBehavior::Skip).await;
+    assert_eq!(first, start + Duration::from_millis(20));
+    assert_eq!(second, start + Duration::from_millis(60));
+}
+
+#[kayrx::test]
+async fn test_interval_missed_tick_delay() {
+    let (start, first, second) = stalled_ticks(MissedTickBehavior::Delay).await;
+    assert_eq!(first, start + Duration::from_millis(20));
+    assert!(second >= start + Duration::from_millis(70));
+}
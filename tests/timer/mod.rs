@@ -0,0 +1,6 @@
+mod delay;
+mod delay_queue;
+mod interval;
+mod recent;
+mod stats;
+mod stream;
@@ -0,0 +1,19 @@
+use std::time::Duration;
+
+use kayrx::timer::{delay_for, recent, Instant};
+
+#[kayrx::test]
+async fn test_recent() {
+    let start = Instant::now();
+    delay_for(Duration::from_millis(20)).await;
+
+    let cached = recent();
+    assert!(cached >= start + Duration::from_millis(20));
+    assert!(cached <= Instant::now());
+}
+
+#[test]
+fn test_recent_outside_runtime() {
+    let start = Instant::now();
+    assert!(recent() >= start);
+}
@@ -0,0 +1,23 @@
+use std::time::Duration;
+
+use kayrx::timer::{delay_for, stats};
+
+#[kayrx::test]
+async fn test_timer_stats() {
+    let before = stats().unwrap();
+
+    let _long = delay_for(Duration::from_secs(60));
+    delay_for(Duration::from_millis(10)).await;
+
+    let stats = stats().unwrap();
+    assert!(stats.fired() > before.fired());
+    assert!(stats.entries() >= 1);
+    assert!(stats.mean_lateness() <= stats.max_lateness());
+    assert_eq!(stats.level_occupancy().len(), 6);
+    assert!(stats.level_occupancy().iter().sum::<u32>() >= 1);
+}
+
+#[test]
+fn test_timer_stats_outside_runtime() {
+    assert!(stats().is_none());
+}
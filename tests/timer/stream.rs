@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+use futures::channel::mpsc;
+use futures::stream::{self, StreamExt};
+use kayrx::timer::{delay_for, StreamTimeoutExt};
+
+#[kayrx::test]
+async fn test_stream_timeout() {
+    let (tx, rx) = mpsc::unbounded();
+    let mut items = rx.timeout(Duration::from_millis(50));
+
+    kayrx::fiber::spawn(async move {
+        for (item, gap) in vec![(1, 10), (2, 30), (3, 120)] {
+            delay_for(Duration::from_millis(gap)).await;
+            tx.unbounded_send(item).unwrap();
+        }
+    });
+
+    // deadline resets on every item
+    assert_eq!(items.next().await.unwrap().unwrap(), 1);
+    assert_eq!(items.next().await.unwrap().unwrap(), 2);
+
+    // single error per gap, stream continues after it
+    assert!(items.next().await.unwrap().is_err());
+    assert_eq!(items.next().await.unwrap().unwrap(), 3);
+    assert!(items.next().await.is_none());
+}
+
+#[kayrx::test]
+async fn test_stream_timeout_ready_items() {
+    let items: Vec<_> = stream::iter(vec![1, 2, 3])
+        .timeout(Duration::from_millis(10))
+        .map(|item| item.unwrap())
+        .collect()
+        .await;
+    assert_eq!(items, vec![1, 2, 3]);
+}
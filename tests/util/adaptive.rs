@@ -0,0 +1,85 @@
+use std::task::{Context, Poll};
+use std::time::Duration;
+use kayrx::timer;
+use kayrx::util::adaptive::*;
+use kayrx::service::{Service, Transform};
+use futures::future::{ok, FutureExt, LocalBoxFuture};
+
+#[derive(Debug, PartialEq)]
+enum SrvError {
+    Overloaded,
+}
+
+impl From<Overloaded> for SrvError {
+    fn from(_: Overloaded) -> Self {
+        SrvError::Overloaded
+    }
+}
+
+struct SleepService(Duration);
+
+impl Service for SleepService {
+    type Request = ();
+    type Response = ();
+    type Error = SrvError;
+    type Future = LocalBoxFuture<'static, Result<(), SrvError>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _: ()) -> Self::Future {
+        timer::delay_for(self.0)
+            .then(|_| ok::<_, SrvError>(()))
+            .boxed_local()
+    }
+}
+
+#[kayrx::test]
+async fn test_reject_over_limit() {
+    let mut srv = AdaptiveConcurrencyService::new(
+        AdaptiveConcurrency::new(Aimd::default()).initial_limit(1),
+        SleepService(Duration::from_millis(20)),
+    );
+    assert_eq!(srv.limit(), 1);
+
+    let res1 = srv.call(());
+    assert_eq!(srv.in_flight(), 1);
+    assert_eq!(srv.call(()).await, Err(SrvError::Overloaded));
+
+    assert_eq!(res1.await, Ok(()));
+    assert_eq!(srv.in_flight(), 0);
+    // fully utilized limit grows
+    assert_eq!(srv.limit(), 2);
+}
+
+#[kayrx::test]
+async fn test_aimd_backoff() {
+    let mut srv = AdaptiveConcurrency::new(
+        Aimd::default()
+            .backoff(0.5)
+            .threshold(Duration::from_millis(5)),
+    )
+    .initial_limit(8)
+    .min_limit(3)
+    .new_transform(SleepService(Duration::from_millis(20)))
+    .await
+    .unwrap();
+
+    srv.call(()).await.unwrap();
+    assert_eq!(srv.limit(), 4);
+    srv.call(()).await.unwrap();
+    assert_eq!(srv.limit(), 3);
+}
+
+#[test]
+fn test_gradient() {
+    let mut alg = Gradient::default().smoothing(1.0);
+
+    let rtt = Duration::from_millis(10);
+    assert_eq!(alg.update(16, rtt, 16, true), 20);
+    // under-utilized limit is not changed
+    assert_eq!(alg.update(20, rtt, 2, true), 20);
+    // latency spike reduces limit
+    assert!(alg.update(20, rtt * 10, 20, true) < 20);
+}
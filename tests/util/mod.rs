@@ -1,4 +1,6 @@
+mod adaptive;
 mod inflight;
 mod order;
+mod spool;
 mod time;
 mod timeout;
\ No newline at end of file
@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+use kayrx::util::spool::*;
+use futures::StreamExt;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("kayrx-spool-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn payload(size: usize) -> Vec<u8> {
+    (0..size).map(|i| (i % 251) as u8).collect()
+}
+
+#[kayrx::test]
+async fn test_in_memory() {
+    let mut buf = SpoolBuffer::new(1024);
+    assert!(buf.is_empty());
+
+    buf.write(b"hello ").await.unwrap();
+    buf.write(b"world").await.unwrap();
+    assert_eq!(buf.len(), 11);
+    assert!(!buf.is_spilled());
+    assert_eq!(&buf.into_bytes().await.unwrap()[..], b"hello world");
+}
+
+#[kayrx::test]
+async fn test_spill() {
+    let dir = temp_dir("spill");
+    let data = payload(300_000);
+
+    let mut buf = SpoolBuffer::new(1024).dir(&dir);
+    for chunk in data.chunks(1000) {
+        buf.write(chunk).await.unwrap();
+    }
+    assert!(buf.is_spilled());
+    assert_eq!(buf.len(), data.len() as u64);
+    assert_eq!(&buf.into_bytes().await.unwrap()[..], &data[..]);
+
+    // file is removed with the buffer
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[kayrx::test]
+async fn test_stream() {
+    let data = payload(100_000);
+
+    let mut buf = SpoolBuffer::new(10_000);
+    buf.write(&data[..50_000]).await.unwrap();
+    buf.write(&data[50_000..]).await.unwrap();
+    assert!(buf.is_spilled());
+
+    let mut stream = buf.into_stream();
+    let mut res = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.unwrap();
+        assert!(!chunk.is_empty());
+        res.extend_from_slice(&chunk);
+    }
+    assert_eq!(res, data);
+}
+
+#[kayrx::test]
+async fn test_missing_dir() {
+    let dir = std::env::temp_dir().join(format!("kayrx-spool-missing-{}", std::process::id()));
+    let mut buf = SpoolBuffer::new(10).dir(dir);
+    buf.write(b"short").await.unwrap();
+    assert!(buf.write(b" and long").await.is_err());
+}
@@ -0,0 +1,159 @@
+use std::io::{Read, Write};
+use std::net::{self, SocketAddr, TcpStream};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use kayrx::fiber::System;
+use kayrx::timer::delay_for;
+use kayrx::web::{self, Admission, App, HttpResponse, HttpServer, QueueOrder};
+
+/// Start server with single worker, request `/0` holds its slot for 300ms
+fn start(admission: Admission) -> (System, SocketAddr, Arc<Mutex<Vec<u32>>>) {
+    let order = Arc::new(Mutex::new(Vec::new()));
+    let (tx, rx) = mpsc::channel();
+
+    let order2 = order.clone();
+    thread::spawn(move || {
+        let sys = System::new("test");
+        let tcp = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = tcp.local_addr().unwrap();
+
+        HttpServer::new(move || {
+            let order = order2.clone();
+            App::new().service(web::resource("/{n}").to(move |n: web::types::Path<u32>| {
+                order.lock().unwrap().push(*n);
+                async move {
+                    if *n == 0 {
+                        delay_for(Duration::from_millis(300)).await;
+                    }
+                    HttpResponse::Ok().finish()
+                }
+            }))
+        })
+        .workers(1)
+        .disable_signals()
+        .admission(admission)
+        .listen(tcp)
+        .unwrap()
+        .run();
+        tx.send((System::current(), addr)).unwrap();
+        sys.run()
+    });
+    let (sys, addr) = rx.recv().unwrap();
+    (sys, addr, order)
+}
+
+/// Send request on a new connection, returns response status line
+fn get(addr: SocketAddr, n: u32) -> thread::JoinHandle<String> {
+    let handle = thread::spawn(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        write!(stream, "GET /{} HTTP/1.1\r\nHost: localhost\r\n\r\n", n).unwrap();
+        let mut buf = [0; 1024];
+        let len = stream.read(&mut buf).unwrap();
+        let res = String::from_utf8_lossy(&buf[..len]).to_string();
+        res.lines().next().unwrap_or("").to_owned()
+    });
+    thread::sleep(Duration::from_millis(50));
+    handle
+}
+
+const OK: &str = "HTTP/1.1 200 OK";
+const UNAVAILABLE: &str = "HTTP/1.1 503 Service Unavailable";
+
+#[test]
+fn test_admission_fifo() {
+    let (sys, addr, order) = start(
+        Admission::new(1)
+            .queue(2, QueueOrder::Fifo)
+            .max_delay(Duration::from_secs(5)),
+    );
+
+    let reqs: Vec<_> = (0..3).map(|n| get(addr, n)).collect();
+    for req in reqs {
+        assert_eq!(req.join().unwrap(), OK);
+    }
+    assert_eq!(*order.lock().unwrap(), vec![0, 1, 2]);
+
+    sys.stop();
+}
+
+#[test]
+fn test_admission_lifo() {
+    let (sys, addr, order) = start(
+        Admission::new(1)
+            .queue(2, QueueOrder::Lifo)
+            .max_delay(Duration::from_secs(5)),
+    );
+
+    let reqs: Vec<_> = (0..3).map(|n| get(addr, n)).collect();
+    for req in reqs {
+        assert_eq!(req.join().unwrap(), OK);
+    }
+    assert_eq!(*order.lock().unwrap(), vec![0, 2, 1]);
+
+    sys.stop();
+}
+
+#[test]
+fn test_admission_queue_overflow() {
+    // fifo queue rejects new request
+    let (sys, addr, order) = start(
+        Admission::new(1)
+            .queue(1, QueueOrder::Fifo)
+            .max_delay(Duration::from_secs(5)),
+    );
+
+    let reqs: Vec<_> = (0..3).map(|n| get(addr, n)).collect();
+    let res: Vec<_> = reqs.into_iter().map(|req| req.join().unwrap()).collect();
+    assert_eq!(res, vec![OK, OK, UNAVAILABLE]);
+    assert_eq!(*order.lock().unwrap(), vec![0, 1]);
+    sys.stop();
+
+    // lifo queue rejects oldest queued request
+    let (sys, addr, order) = start(
+        Admission::new(1)
+            .queue(1, QueueOrder::Lifo)
+            .max_delay(Duration::from_secs(5)),
+    );
+
+    let reqs: Vec<_> = (0..3).map(|n| get(addr, n)).collect();
+    let res: Vec<_> = reqs.into_iter().map(|req| req.join().unwrap()).collect();
+    assert_eq!(res, vec![OK, UNAVAILABLE, OK]);
+    assert_eq!(*order.lock().unwrap(), vec![0, 2]);
+    sys.stop();
+}
+
+#[test]
+fn test_admission_release() {
+    let (sys, addr, order) = start(
+        Admission::new(1)
+            .queue(1, QueueOrder::Fifo)
+            .max_delay(Duration::from_millis(100)),
+    );
+
+    // queued request waits longer than max delay
+    let reqs: Vec<_> = (0..2).map(|n| get(addr, n)).collect();
+    let res: Vec<_> = reqs.into_iter().map(|req| req.join().unwrap()).collect();
+    assert_eq!(res, vec![OK, UNAVAILABLE]);
+
+    // slot of the completed request and of the timed out waiter are released
+    let reqs: Vec<_> = (1..3).map(|n| get(addr, n)).collect();
+    for req in reqs {
+        assert_eq!(req.join().unwrap(), OK);
+    }
+    assert_eq!(*order.lock().unwrap(), vec![0, 1, 2]);
+
+    // requests within the limit are not queued
+    let (sys2, addr, _) = start(Admission::new(2));
+    let reqs: Vec<_> = (0..2).map(|n| get(addr, n)).collect();
+    for req in reqs {
+        assert_eq!(req.join().unwrap(), OK);
+    }
+
+    sys.stop();
+    sys2.stop();
+}
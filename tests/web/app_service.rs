@@ -29,4 +29,39 @@ async fn test_drop_data() {
         let _ = app.call(req).await.unwrap();
     }
     assert!(data.load(Ordering::Relaxed));
-}
\ No newline at end of file
+}
+
+#[kayrx::test]
+async fn test_lifecycle_hooks() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let (e1, e2, e3, e4) = (events.clone(), events.clone(), events.clone(), events.clone());
+    let srv = init_service(
+        App::new()
+            .on_start(move || {
+                let events = e1.clone();
+                async move { events.borrow_mut().push("start1") }
+            })
+            .on_start(move || {
+                let events = e2.clone();
+                async move { events.borrow_mut().push("start2") }
+            })
+            .on_stop(move || {
+                let events = e3.clone();
+                async move { events.borrow_mut().push("stop1") }
+            })
+            .on_stop(move || {
+                let events = e4.clone();
+                async move { events.borrow_mut().push("stop2") }
+            })
+            .service(web::resource("/").to(|| HttpResponse::Ok())),
+    )
+    .await;
+    assert_eq!(*events.borrow(), vec!["start1", "start2"]);
+
+    drop(srv);
+    kayrx::timer::delay_for(std::time::Duration::from_millis(50)).await;
+    assert_eq!(*events.borrow(), vec!["start1", "start2", "stop2", "stop1"]);
+}
@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use kayrx::http::header::CONTENT_TYPE;
+use kayrx::http::StatusCode;
+use kayrx::timer::delay_for;
+use kayrx::web::batch::Batch;
+use kayrx::web::test::{self, TestRequest};
+use kayrx::web::{self, types, App, HttpRequest, HttpResponse};
+use serde_json::{json, Value};
+
+fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(Batch::new("/batch").concurrency(2).max_requests(4))
+        .route(
+            "/slow/{ms}",
+            web::get().to(|req: HttpRequest| async move {
+                let ms: u64 = req.match_info().query("ms").parse().unwrap();
+                delay_for(Duration::from_millis(ms)).await;
+                HttpResponse::Ok().body(ms.to_string())
+            }),
+        )
+        .route(
+            "/echo",
+            web::post().to(|req: HttpRequest, body: types::Json<Value>| async move {
+                HttpResponse::Created().json(json!({
+                    "body": body.into_inner(),
+                    "x-id": req.headers().get("x-id").map(|v| v.to_str().unwrap()),
+                }))
+            }),
+        );
+}
+
+#[kayrx::test]
+async fn test_batch() {
+    let mut srv = test::init_service(App::new().configure(config)).await;
+
+    let req = TestRequest::post()
+        .uri("/batch")
+        .set_json(&json!([
+            {"path": "/slow/50"},
+            {"path": "/slow/1"},
+            {"method": "POST", "path": "/echo", "headers": {"x-id": "7"}, "body": {"a": 1}},
+            {"path": "/missing"},
+        ]))
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), "application/json");
+
+    let body: Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+    let items = body.as_array().unwrap();
+    assert_eq!(items.len(), 4);
+
+    // order of the batch is preserved
+    assert_eq!(items[0]["status"], 200);
+    assert_eq!(items[0]["body"], "50");
+    assert_eq!(items[1]["body"], "1");
+    assert_eq!(items[2]["status"], 201);
+    assert_eq!(items[2]["body"], json!({"body": {"a": 1}, "x-id": "7"}));
+    assert_eq!(items[3]["status"], 404);
+}
+
+#[kayrx::test]
+async fn test_batch_errors() {
+    let mut srv = test::init_service(App::new().configure(config)).await;
+
+    let req = TestRequest::post()
+        .uri("/batch")
+        .set_json(&Value::Array(vec![json!({"path": "/slow/1"}); 5]))
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let req = TestRequest::post()
+        .uri("/batch")
+        .set_json(&json!([
+            {"path": "slow/1"},
+            {"method": "GE T", "path": "/slow/1"},
+        ]))
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body: Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+    assert_eq!(body[0]["status"], 500);
+    assert_eq!(body[1]["status"], 400);
+
+    let req = TestRequest::get().uri("/batch").to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
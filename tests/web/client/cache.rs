@@ -0,0 +1,38 @@
+use std::time::SystemTime;
+
+use bytes::Bytes;
+use kayrx::http::{HeaderMap, StatusCode, Version};
+use kayrx::web::client::cache::{CacheStore, CachedResponse, MemoryCacheStore};
+
+fn response(body: &'static [u8]) -> CachedResponse {
+    CachedResponse {
+        status: StatusCode::OK,
+        version: Version::HTTP_11,
+        headers: HeaderMap::new(),
+        body: Bytes::from_static(body),
+        vary: Vec::new(),
+        stored_at: SystemTime::now(),
+    }
+}
+
+#[kayrx::test]
+async fn test_memory_store() {
+    let store = MemoryCacheStore::new(2);
+    assert!(store.is_empty());
+
+    store.put("/a", response(b"a")).await;
+    store.put("/b", response(b"b")).await;
+    store.put("/a", response(b"a2")).await;
+    assert_eq!(store.len(), 2);
+    assert_eq!(store.get("/a").await.unwrap().body, Bytes::from_static(b"a2"));
+
+    // oldest entry is evicted
+    store.put("/c", response(b"c")).await;
+    assert_eq!(store.len(), 2);
+    assert!(store.get("/a").await.is_none());
+    assert!(store.get("/b").await.is_some());
+
+    store.remove("/b").await;
+    assert!(store.get("/b").await.is_none());
+    assert_eq!(store.len(), 1);
+}
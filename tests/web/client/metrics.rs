@@ -0,0 +1,72 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use kayrx::http::client::{Connector, HostStats, Metrics};
+use kayrx::web::client::Client;
+use kayrx::web::{self, test, App, HttpResponse};
+
+#[derive(Clone, Default)]
+struct Events(Rc<RefCell<Vec<String>>>);
+
+impl Events {
+    fn count(&self, name: &str) -> usize {
+        self.0.borrow().iter().filter(|ev| ev.as_str() == name).count()
+    }
+}
+
+impl Metrics for Events {
+    fn dns(&self, _: &str, _: Duration) {
+        self.0.borrow_mut().push("dns".to_owned());
+    }
+
+    fn connect(&self, _: &str, _: Duration) {
+        self.0.borrow_mut().push("connect".to_owned());
+    }
+
+    fn first_byte(&self, _: &str, _: Duration) {
+        self.0.borrow_mut().push("first_byte".to_owned());
+    }
+
+    fn pool_hit(&self, _: &str) {
+        self.0.borrow_mut().push("hit".to_owned());
+    }
+
+    fn pool_miss(&self, _: &str) {
+        self.0.borrow_mut().push("miss".to_owned());
+    }
+}
+
+#[kayrx::test]
+async fn test_metrics() {
+    let srv = test::start(|| {
+        App::new().service(
+            web::resource("/").to(|| async { HttpResponse::Ok().body("ok") }),
+        )
+    });
+
+    let events = Events::default();
+    let connector = Connector::new().metrics(events.clone());
+    let stats = connector.stats();
+    let client = Client::build().connector(connector.finish()).finish();
+
+    let host = format!("localhost:{}", srv.addr().port());
+    let url = format!("http://{}/", host);
+    for _ in 0..2 {
+        let mut res = client.get(url.as_str()).send().await.unwrap();
+        assert!(res.status().is_success());
+        assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"ok"));
+    }
+
+    // second request reuses connection
+    assert_eq!(events.count("dns"), 1);
+    assert_eq!(events.count("connect"), 1);
+    assert_eq!(events.count("miss"), 1);
+    assert_eq!(events.count("hit"), 1);
+    assert_eq!(events.count("first_byte"), 2);
+
+    assert_eq!(stats.acquired(), 0);
+    assert_eq!(stats.host(&host), HostStats { idle: 1, opening: 0 });
+    assert_eq!(stats.hosts().len(), 1);
+}
@@ -1,2 +1,10 @@
+mod cache;
+mod metrics;
+mod pinning;
+mod pool;
+mod proxy;
+mod redirect;
 mod response;
-mod ws;
\ No newline at end of file
+mod socks;
+mod uds;
+mod ws;
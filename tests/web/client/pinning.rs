@@ -0,0 +1,87 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+
+use kayrx::http::client::{ConnectError, Connector, SendRequestError};
+use kayrx::secure::tls::rust_tls::internal::pemfile;
+use kayrx::secure::tls::rust_tls::{ClientConfig, NoClientAuth, ServerConfig};
+use kayrx::web::client::Client;
+use kayrx::web::{self, test, App, HttpResponse};
+
+/// base64 sha256 digests of certificates `SubjectPublicKeyInfo`
+const LOCALHOST_PIN: &str = "jZsRwzZNmrKVD2UtjCH7NhdY96ZlJb8nX9DJ0bE+xOg=";
+const WILDCARD_PIN: &str = "X9NKbIni/m5OO6OVVNLL6lq8m9nJvbkva5CC35ttcEQ=";
+
+fn cert_path(name: &str) -> String {
+    format!("{}/tests/certs/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+fn pin(val: &str) -> [u8; 32] {
+    let mut pin = [0u8; 32];
+    pin.copy_from_slice(&base64::decode(val).unwrap());
+    pin
+}
+
+fn start_server() -> test::TestServer {
+    let certs = pemfile::certs(&mut BufReader::new(
+        File::open(cert_path("localhost-cert.pem")).unwrap(),
+    ))
+    .unwrap();
+    let key = pemfile::pkcs8_private_keys(&mut BufReader::new(
+        File::open(cert_path("localhost-key.pem")).unwrap(),
+    ))
+    .unwrap()
+    .remove(0);
+    let mut config = ServerConfig::new(NoClientAuth::new());
+    config.set_single_cert(certs, key).unwrap();
+
+    test::start_with(test::config().rustls(config).h1(), || {
+        App::new().service(web::resource("/").to(|| HttpResponse::Ok()))
+    })
+}
+
+fn client(pins: Vec<[u8; 32]>) -> Client {
+    let mut config = ClientConfig::new();
+    config
+        .root_store
+        .add_pem_file(&mut BufReader::new(File::open(cert_path("ca.pem")).unwrap()))
+        .unwrap();
+    let connector = Connector::new()
+        .rustls(Arc::new(config))
+        .pin_server_cert("localhost", pins)
+        .finish();
+    Client::build().connector(connector).finish()
+}
+
+#[kayrx::test]
+async fn test_pin_match() {
+    let srv = start_server();
+    let url = format!("https://localhost:{}/", srv.addr().port());
+
+    let res = client(vec![pin(WILDCARD_PIN), pin(LOCALHOST_PIN)])
+        .get(&url)
+        .address(srv.addr())
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+}
+
+#[kayrx::test]
+async fn test_pin_mismatch() {
+    let srv = start_server();
+    let url = format!("https://localhost:{}/", srv.addr().port());
+
+    let err = client(vec![pin(WILDCARD_PIN)])
+        .get(&url)
+        .address(srv.addr())
+        .send()
+        .await
+        .unwrap_err();
+    match err {
+        SendRequestError::Connect(ConnectError::PinMismatch(host)) => {
+            assert_eq!(host, "localhost")
+        }
+        err => panic!("unexpected error: {:?}", err),
+    }
+}
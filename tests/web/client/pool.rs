@@ -0,0 +1,196 @@
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::net::{self, Shutdown, SocketAddr, TcpStream};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use bytes::Bytes;
+use kayrx::http::client::{Connector, HostStats, Metrics};
+use kayrx::secure::tls::rust_tls::internal::pemfile;
+use kayrx::secure::tls::rust_tls::{ClientConfig, NoClientAuth, ServerConfig};
+use kayrx::timer::delay_for;
+use kayrx::web::client::Client;
+use kayrx::web::{self, test, App, HttpResponse};
+
+#[derive(Clone, Default)]
+struct Events(Rc<RefCell<Vec<&'static str>>>);
+
+impl Events {
+    fn count(&self, name: &str) -> usize {
+        self.0.borrow().iter().filter(|ev| **ev == name).count()
+    }
+}
+
+impl Metrics for Events {
+    fn pool_hit(&self, _: &str) {
+        self.0.borrow_mut().push("hit");
+    }
+
+    fn pool_miss(&self, _: &str) {
+        self.0.borrow_mut().push("miss");
+    }
+}
+
+#[kayrx::test]
+async fn test_warmup_min_idle() {
+    let srv = test::start(|| {
+        App::new().service(
+            web::resource("/").to(|| async { HttpResponse::Ok().body("ok") }),
+        )
+    });
+
+    let events = Events::default();
+    let connector = Connector::new().min_idle_per_host(2).metrics(events.clone());
+    let stats = connector.stats();
+    let client = Client::build().connector(connector.finish()).finish();
+
+    let host = format!("localhost:{}", srv.addr().port());
+    let url = format!("http://{}/", host);
+    client.warmup(vec![url.as_str()]).await.unwrap();
+
+    // warmed up connection is idle, pool tops up idle connections in background
+    assert!(stats.host(&host).idle >= 1);
+    for _ in 0..100 {
+        let host = stats.host(&host);
+        if host.idle >= 2 && host.opening == 0 {
+            break;
+        }
+        delay_for(Duration::from_millis(10)).await;
+    }
+    let idle = stats.host(&host).idle;
+    assert!(idle >= 2);
+
+    // requests use idle connections
+    let misses = events.count("miss");
+    for _ in 0..2 {
+        let mut res = client.get(url.as_str()).send().await.unwrap();
+        assert!(res.status().is_success());
+        assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"ok"));
+    }
+    assert_eq!(events.count("hit"), 2);
+    assert_eq!(events.count("miss"), misses);
+    assert_eq!(stats.host(&host), HostStats { idle, opening: 0 });
+}
+
+fn cert_path(name: &str) -> String {
+    format!("{}/tests/certs/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+/// Tcp proxy, traffic of established connections could be silently dropped
+struct Proxy {
+    addr: SocketAddr,
+    conns: Arc<Mutex<Vec<Arc<AtomicBool>>>>,
+}
+
+impl Proxy {
+    fn start(target: SocketAddr) -> Proxy {
+        let lst = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = lst.local_addr().unwrap();
+        let conns = Arc::new(Mutex::new(Vec::new()));
+
+        let conns2 = conns.clone();
+        thread::spawn(move || {
+            for stream in lst.incoming() {
+                let client = stream.unwrap();
+                let server = TcpStream::connect(target).unwrap();
+                let dropped = Arc::new(AtomicBool::new(false));
+                conns2.lock().unwrap().push(dropped.clone());
+
+                Proxy::relay(&client, &server, dropped.clone());
+                Proxy::relay(&server, &client, dropped);
+            }
+        });
+        Proxy { addr, conns }
+    }
+
+    fn relay(from: &TcpStream, to: &TcpStream, dropped: Arc<AtomicBool>) {
+        let mut from = from.try_clone().unwrap();
+        let mut to = to.try_clone().unwrap();
+        thread::spawn(move || {
+            let mut buf = [0; 4096];
+            loop {
+                match io::Read::read(&mut from, &mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => {
+                        if !dropped.load(Ordering::SeqCst)
+                            && io::Write::write_all(&mut to, &buf[..n]).is_err()
+                        {
+                            break;
+                        }
+                    }
+                }
+            }
+            let _ = to.shutdown(Shutdown::Write);
+        });
+    }
+
+    /// Drop all traffic of currently established connections
+    fn black_hole(&self) {
+        for conn in self.conns.lock().unwrap().iter() {
+            conn.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+#[kayrx::test]
+async fn test_h2_keep_alive() {
+    let certs = pemfile::certs(&mut BufReader::new(
+        File::open(cert_path("localhost-cert.pem")).unwrap(),
+    ))
+    .unwrap();
+    let key = pemfile::pkcs8_private_keys(&mut BufReader::new(
+        File::open(cert_path("localhost-key.pem")).unwrap(),
+    ))
+    .unwrap()
+    .remove(0);
+    let mut config = ServerConfig::new(NoClientAuth::new());
+    config.set_single_cert(certs, key).unwrap();
+
+    let srv = test::start_with(test::config().rustls(config).h2(), || {
+        App::new().service(
+            web::resource("/").to(|| async { HttpResponse::Ok().body("ok") }),
+        )
+    });
+    let proxy = Proxy::start(srv.addr());
+
+    let mut config = ClientConfig::new();
+    config
+        .root_store
+        .add_pem_file(&mut BufReader::new(File::open(cert_path("ca.pem")).unwrap()))
+        .unwrap();
+    config.set_protocols(&[b"h2".to_vec()]);
+    let events = Events::default();
+    let connector = Connector::new()
+        .rustls(Arc::new(config))
+        .h2_keep_alive(Duration::from_millis(100), Duration::from_millis(100))
+        .timeout(Duration::from_secs(5))
+        .metrics(events.clone());
+    let client = Client::build()
+        .connector(connector.finish())
+        .timeout(Duration::from_secs(5))
+        .finish();
+    let url = format!("https://localhost:{}/", srv.addr().port());
+
+    // acknowledged pings keep connection in the pool
+    let mut res = client.get(url.as_str()).address(proxy.addr).send().await.unwrap();
+    assert_eq!(res.version(), kayrx::http::Version::HTTP_2);
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"ok"));
+    delay_for(Duration::from_millis(500)).await;
+
+    let mut res = client.get(url.as_str()).address(proxy.addr).send().await.unwrap();
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"ok"));
+    assert_eq!(events.count("miss"), 1);
+    assert_eq!(events.count("hit"), 1);
+
+    // unacknowledged ping closes connection, new one is opened
+    proxy.black_hole();
+    delay_for(Duration::from_millis(500)).await;
+
+    let mut res = client.get(url.as_str()).address(proxy.addr).send().await.unwrap();
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"ok"));
+    assert_eq!(events.count("miss"), 2);
+}
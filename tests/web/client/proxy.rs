@@ -0,0 +1,99 @@
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
+use bytes::Bytes;
+use kayrx::http::client::{Connector, Proxy};
+use kayrx::http::header;
+use kayrx::web::client::Client;
+use kayrx::web::{self, test, App, HttpRequest};
+
+async fn echo(req: HttpRequest) -> String {
+    let auth = req
+        .headers()
+        .get(header::PROXY_AUTHORIZATION)
+        .map(|v| v.to_str().unwrap().to_owned())
+        .unwrap_or_default();
+    format!("{} {}", req.uri(), auth)
+}
+
+#[kayrx::test]
+async fn test_http_proxy_forward() {
+    let srv = test::start(|| App::new().default_service(web::to(echo)));
+
+    let proxy = Proxy::http(&srv.url("/"))
+        .unwrap()
+        .basic_auth("user", "pass");
+    let client = Client::build()
+        .connector(Connector::new().proxy(proxy).finish())
+        .finish();
+
+    let mut res = client
+        .get("http://example.invalid/path?q=1")
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let body = res.body().await.unwrap();
+    assert_eq!(
+        body,
+        Bytes::from_static(b"http://example.invalid/path?q=1 Basic dXNlcjpwYXNz")
+    );
+}
+
+#[kayrx::test]
+async fn test_no_proxy() {
+    let srv = test::start(|| App::new().default_service(web::to(echo)));
+
+    // proxy is not reachable, request must go directly
+    let proxy = Proxy::all("http://127.0.0.1:1")
+        .unwrap()
+        .no_proxy(vec!["localhost", "127.0.0.1"]);
+    let client = Client::build()
+        .connector(Connector::new().proxy(proxy).finish())
+        .finish();
+
+    let mut res = client.get(srv.url("/direct")).send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"/direct "));
+}
+
+#[kayrx::test]
+async fn test_https_proxy_tunnel() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let handle = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut req = Vec::new();
+        let mut buf = [0u8; 1024];
+        while !req.ends_with(b"\r\n\r\n") {
+            let n = stream.read(&mut buf).unwrap();
+            assert!(n > 0);
+            req.extend_from_slice(&buf[..n]);
+        }
+        stream
+            .write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n")
+            .unwrap();
+        String::from_utf8(req).unwrap()
+    });
+
+    let proxy = Proxy::https(&format!("http://user:pass@{}", addr)).unwrap();
+    let client = Client::build()
+        .connector(Connector::new().proxy(proxy).finish())
+        .finish();
+
+    assert!(client.get("https://example.invalid/").send().await.is_err());
+
+    let req = handle.join().unwrap();
+    assert!(req.starts_with("CONNECT example.invalid:443 HTTP/1.1\r\n"));
+    assert!(req.contains("proxy-authorization: Basic dXNlcjpwYXNz\r\n"));
+}
+
+#[test]
+fn test_proxy_url() {
+    assert!(Proxy::all("proxy.local:3128").is_err());
+    assert!(Proxy::all("ftp://proxy.local").is_err());
+    assert!(Proxy::all("socks5://127.0.0.1:1080").is_ok());
+    assert!(Proxy::https("http://proxy.local:3128").is_ok());
+}
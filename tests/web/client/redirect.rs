@@ -0,0 +1,136 @@
+use bytes::Bytes;
+use kayrx::http::client::SendRequestError;
+use kayrx::http::{header, Method, StatusCode};
+use kayrx::web::client::{Client, RedirectPolicy};
+use kayrx::web::{self, test, App, HttpRequest, HttpResponse, ServiceConfig};
+
+// redirecting handlers read request body, so connection could be reused
+// by the next hop
+fn config(cfg: &mut ServiceConfig) {
+    cfg.route(
+        "/found",
+        web::to(|| {
+            HttpResponse::Found()
+                .header(header::LOCATION, "/target")
+                .finish()
+        }),
+    )
+    .route(
+        "/see-other",
+        web::to(|_: Bytes| {
+            HttpResponse::SeeOther()
+                .header(header::LOCATION, "target")
+                .finish()
+        }),
+    )
+    .route(
+        "/temporary",
+        web::to(|_: Bytes| {
+            HttpResponse::TemporaryRedirect()
+                .header(header::LOCATION, "/target")
+                .finish()
+        }),
+    )
+    .route(
+        "/loop",
+        web::to(|| {
+            HttpResponse::Found()
+                .header(header::LOCATION, "/loop")
+                .finish()
+        }),
+    )
+    .route(
+        "/target",
+        web::to(|req: HttpRequest, body: Bytes| {
+            let auth = req
+                .headers()
+                .get(header::AUTHORIZATION)
+                .map(|v| v.to_str().unwrap().to_owned())
+                .unwrap_or_default();
+            let body = format!("{} {} {:?}", req.method(), auth, body);
+            async move { body }
+        }),
+    );
+}
+
+#[kayrx::test]
+async fn test_follow_redirects() {
+    let srv = test::start(|| App::new().configure(config));
+    let client = Client::default();
+
+    let mut res = client
+        .get(srv.url("/found"))
+        .header(header::AUTHORIZATION, "token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    // same origin, credentials are kept
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"GET token b\"\""));
+
+    // 303 changes method to GET and drops body
+    let mut res = client.post(srv.url("/see-other")).send_body("data").await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"GET  b\"\""));
+
+    // 307 keeps method and body
+    let mut res = client
+        .request(Method::PUT, srv.url("/temporary"))
+        .send_body("data")
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"PUT  b\"data\""));
+}
+
+#[kayrx::test]
+async fn test_redirect_limits() {
+    let srv = test::start(|| App::new().configure(config));
+
+    let client = Client::build().max_redirects(3).finish();
+    match client.get(srv.url("/loop")).send().await {
+        Err(SendRequestError::TooManyRedirects) => (),
+        res => panic!("unexpected result: {:?}", res.map(|r| r.status())),
+    }
+
+    let client = Client::build().disable_redirects().finish();
+    let res = client.get(srv.url("/found")).send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::FOUND);
+
+    let client = Client::build()
+        .redirect_policy(RedirectPolicy::none())
+        .finish();
+    let res = client.get(srv.url("/found")).send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::FOUND);
+}
+
+#[kayrx::test]
+async fn test_cross_origin_redirect() {
+    let target = test::start(|| App::new().configure(config));
+    let location = target.url("/target");
+    let srv = test::start(move || {
+        let location = location.clone();
+        App::new().service(web::resource("/").to(move || {
+            HttpResponse::Found()
+                .header(header::LOCATION, location.as_str())
+                .finish()
+        }))
+    });
+
+    // credentials are not sent to other origin
+    let client = Client::default();
+    let mut res = client
+        .get(srv.url("/"))
+        .header(header::AUTHORIZATION, "token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"GET  b\"\""));
+
+    let client = Client::build()
+        .redirect_policy(RedirectPolicy::new().cross_origin(false))
+        .finish();
+    let res = client.get(srv.url("/")).send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::FOUND);
+}
@@ -0,0 +1,94 @@
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use kayrx::http::client::Connector;
+use kayrx::web::client::Client;
+use kayrx::web::{self, test, App, HttpResponse};
+
+/// Start SOCKS5 proxy that records username and requested address
+/// of each connection and tunnels it to `target`
+fn start_socks5(target: SocketAddr) -> (SocketAddr, Arc<Mutex<Vec<(String, String)>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let log2 = log.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buf = [0u8; 256];
+
+            stream.read_exact(&mut buf[..3]).unwrap();
+            assert_eq!(&buf[..2], &[5, 1]);
+            let method = buf[2];
+            stream.write_all(&[5, method]).unwrap();
+
+            let mut user = String::new();
+            if method == 2 {
+                stream.read_exact(&mut buf[..2]).unwrap();
+                let len = buf[1] as usize;
+                stream.read_exact(&mut buf[..len]).unwrap();
+                user = String::from_utf8_lossy(&buf[..len]).to_string();
+                stream.read_exact(&mut buf[..1]).unwrap();
+                let len = buf[0] as usize;
+                stream.read_exact(&mut buf[..len]).unwrap();
+                stream.write_all(&[1, 0]).unwrap();
+            }
+
+            stream.read_exact(&mut buf[..5]).unwrap();
+            assert_eq!(&buf[..4], &[5, 1, 0, 3]);
+            let len = buf[4] as usize;
+            stream.read_exact(&mut buf[..len + 2]).unwrap();
+            let host = String::from_utf8_lossy(&buf[..len]).to_string();
+            let port = u16::from_be_bytes([buf[len], buf[len + 1]]);
+            log2.lock().unwrap().push((user, format!("{}:{}", host, port)));
+
+            let mut server = TcpStream::connect(target).unwrap();
+            stream.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]).unwrap();
+
+            let mut client = stream.try_clone().unwrap();
+            let mut server2 = server.try_clone().unwrap();
+            thread::spawn(move || io::copy(&mut client, &mut server2));
+            thread::spawn(move || io::copy(&mut server, &mut stream));
+        }
+    });
+    (addr, log)
+}
+
+#[kayrx::test]
+async fn test_socks5_isolation() {
+    let srv = test::start(|| App::new().default_service(web::to(|| HttpResponse::Ok())));
+    let (proxy, log) = start_socks5(srv.addr());
+
+    let client = Client::build()
+        .connector(Connector::new().socks5_proxy(proxy).finish())
+        .finish();
+    let url = format!("http://localhost:{}/", srv.addr().port());
+
+    for label in &["a", "b", "a", ""] {
+        let req = client.get(url.as_str());
+        let req = if label.is_empty() {
+            req
+        } else {
+            req.isolation(*label)
+        };
+        let mut res = req.send().await.unwrap();
+        assert!(res.status().is_success());
+        res.body().await.unwrap();
+    }
+
+    // connections with different labels are not shared, label is
+    // sent as proxy credentials, host name is resolved by proxy
+    let target = format!("localhost:{}", srv.addr().port());
+    let log = log.lock().unwrap().clone();
+    assert_eq!(
+        log,
+        vec![
+            ("a".to_owned(), target.clone()),
+            ("b".to_owned(), target.clone()),
+            ("".to_owned(), target),
+        ]
+    );
+}
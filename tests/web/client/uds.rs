@@ -0,0 +1,47 @@
+use std::sync::mpsc;
+use std::thread;
+
+use kayrx::fiber::System;
+use kayrx::http::client::Connector;
+use kayrx::web::client::Client;
+use kayrx::web::{self, App, HttpRequest, HttpServer};
+
+#[kayrx::test]
+async fn test_unix_socket() {
+    let path = std::env::temp_dir().join(format!("kayrx-uds-{}.sock", std::process::id()));
+    let (tx, rx) = mpsc::channel();
+
+    let path2 = path.clone();
+    thread::spawn(move || {
+        let sys = System::new("test");
+        HttpServer::new(|| {
+            App::new().service(web::resource("/info").to(|req: HttpRequest| {
+                let host = req.connection_info().host().to_owned();
+                async move { host }
+            }))
+        })
+        .workers(1)
+        .disable_signals()
+        .bind_uds(&path2)
+        .unwrap()
+        .run();
+        tx.send(System::current()).unwrap();
+        sys.run()
+    });
+    let sys = rx.recv().unwrap();
+
+    let client = Client::build()
+        .connector(Connector::new().unix_socket(&path).finish())
+        .finish();
+
+    // logical host is kept for host header
+    for _ in 0..2 {
+        let mut res = client.get("http://sidecar/info").send().await.unwrap();
+        assert!(res.status().is_success());
+        let body = res.body().await.unwrap();
+        assert_eq!(body, "sidecar");
+    }
+
+    sys.stop();
+    let _ = std::fs::remove_file(&path);
+}
@@ -2,7 +2,8 @@ use kayrx::service::Service;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
-use kayrx::web::Data;
+use kayrx::http::error;
+use kayrx::web::{Data, DataConfig, FromRequest, ReqData};
 use kayrx::http::StatusCode;
 use kayrx::web::test::{self, init_service, TestRequest};
 use kayrx::web::{self, App};
@@ -142,4 +143,54 @@ async fn test_data_drop() {
     srv.stop().await;
 
     assert_eq!(num.load(Ordering::SeqCst), 0);
-}
\ No newline at end of file
+}
+#[kayrx::test]
+async fn test_data_config_error_handler() {
+    let mut srv = init_service(
+        App::new()
+            .app_data(DataConfig::default().error_handler(|name, _| {
+                assert!(name.contains("usize"));
+                error::InternalError::from_response(
+                    name,
+                    HttpResponse::ServiceUnavailable().finish(),
+                )
+                .into()
+            }))
+            .service(web::resource("/").to(|_: web::Data<usize>| HttpResponse::Ok())),
+    )
+    .await;
+
+    let req = TestRequest::default().to_request();
+    let resp = srv.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[kayrx::test]
+async fn test_data_from_arc() {
+    let state = Arc::new(7usize);
+    let mut srv = init_service(
+        App::new()
+            .app_data(Data::from(state.clone()))
+            .service(web::resource("/").to(|data: web::Data<usize>| {
+                assert_eq!(**data, 7);
+                HttpResponse::Ok()
+            })),
+    )
+    .await;
+
+    let req = TestRequest::default().to_request();
+    let resp = srv.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[kayrx::test]
+async fn test_req_data_extractor() {
+    let req = TestRequest::default().to_http_request();
+    req.extensions_mut().insert(String::from("user"));
+    let data = ReqData::<String>::extract(&req).await.unwrap();
+    assert_eq!(data.as_str(), "user");
+
+    let req = TestRequest::default().to_http_request();
+    let res = ReqData::<u64>::extract(&req).await;
+    assert!(res.is_err());
+}
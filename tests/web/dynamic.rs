@@ -0,0 +1,72 @@
+use kayrx::http::{Response as HttpResponse, StatusCode};
+use kayrx::service::Service;
+use kayrx::web::test::{init_service, read_body, TestRequest};
+use kayrx::web::{self, App, DynamicRouter, DynamicRoutes};
+
+#[kayrx::test]
+async fn test_mount_unmount() {
+    let router = DynamicRouter::new();
+    let mut srv = init_service(
+        App::new()
+            .service(web::resource("/").to(|| HttpResponse::Ok()))
+            .default_service(router.clone()),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("/tenant/index.html").to_request();
+    let resp = srv.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    router.mount("/tenant", |cfg| {
+        cfg.route("/index.html", web::get().to(|| async { "tenant" }));
+    });
+    assert!(router.routes().contains("/tenant"));
+
+    let req = TestRequest::with_uri("/tenant/index.html").to_request();
+    let resp = srv.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(read_body(resp).await, "tenant");
+
+    // static routes are not affected
+    let req = TestRequest::with_uri("/").to_request();
+    let resp = srv.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    assert!(router.unmount("/tenant"));
+    assert!(!router.unmount("/tenant"));
+    let req = TestRequest::with_uri("/tenant/index.html").to_request();
+    let resp = srv.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[kayrx::test]
+async fn test_swap() {
+    let router = DynamicRouter::new();
+    router.mount("/a", |cfg| {
+        cfg.route("/", web::get().to(|| async { "a" }));
+    });
+    let mut srv = init_service(App::new().default_service(router.clone())).await;
+
+    let req = TestRequest::with_uri("/a/").to_request();
+    assert_eq!(read_body(srv.call(req).await.unwrap()).await, "a");
+
+    let mut routes = DynamicRoutes::new();
+    routes
+        .mount("/b", |cfg| {
+            cfg.route("/", web::get().to(|| async { "b" }));
+        })
+        .mount("/c", |cfg| {
+            cfg.route("/", web::get().to(|| async { "c" }));
+        });
+    let old = router.swap(routes);
+    assert_eq!(old.prefixes().collect::<Vec<_>>(), vec!["/a"]);
+
+    let req = TestRequest::with_uri("/a/").to_request();
+    let resp = srv.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let req = TestRequest::with_uri("/b/").to_request();
+    assert_eq!(read_body(srv.call(req).await.unwrap()).await, "b");
+    let req = TestRequest::with_uri("/c/").to_request();
+    assert_eq!(read_body(srv.call(req).await.unwrap()).await, "c");
+}
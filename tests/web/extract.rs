@@ -90,4 +90,95 @@ async fn test_result() {
         .await
         .unwrap();
     assert!(r.is_err());
-}
\ No newline at end of file
+}
+#[derive(Debug, Default, PartialEq)]
+struct Token(String);
+
+impl FromRequest for Token {
+    type Error = Error;
+    type Future = futures::future::Ready<Result<Token, Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, _: &mut dev::Payload) -> Self::Future {
+        futures::future::ready(
+            req.headers()
+                .get("x-token")
+                .and_then(|val| val.to_str().ok())
+                .map(|val| Token(val.to_owned()))
+                .ok_or_else(|| kayrx::http::error::ErrorUnauthorized("no token")),
+        )
+    }
+}
+
+fn conflict(_: Error, _: &HttpRequest) -> Error {
+    kayrx::http::error::ErrorConflict("conflict")
+}
+
+#[derive(FromRequest)]
+struct Params {
+    query: Query<Info>,
+    #[from_request(default)]
+    token: Token,
+    req: HttpRequest,
+}
+
+#[derive(FromRequest)]
+struct Strict(
+    Option<Query<Info>>,
+    #[from_request(map_err = "conflict")] Token,
+);
+
+#[kayrx::test]
+async fn test_derive_from_request() {
+    let (req, mut pl) = TestRequest::with_uri("/?hello=world")
+        .header("x-token", "secret")
+        .to_http_parts();
+    let params = Params::from_request(&req, &mut pl).await.unwrap();
+    assert_eq!(params.query.hello, "world");
+    assert_eq!(params.token, Token("secret".to_owned()));
+    assert_eq!(params.req.path(), "/");
+
+    let (req, mut pl) = TestRequest::with_uri("/?hello=world").to_http_parts();
+    let params = Params::from_request(&req, &mut pl).await.unwrap();
+    assert_eq!(params.token, Token::default());
+
+    let (req, mut pl) = TestRequest::with_uri("/?bye=world").to_http_parts();
+    assert!(Params::from_request(&req, &mut pl).await.is_err());
+
+    let (req, mut pl) = TestRequest::with_uri("/").to_http_parts();
+    let err = Strict::from_request(&req, &mut pl).await.err().unwrap();
+    assert_eq!(
+        err.as_response_error().status_code(),
+        kayrx::http::StatusCode::CONFLICT
+    );
+
+    let (req, mut pl) = TestRequest::default()
+        .header("x-token", "secret")
+        .to_http_parts();
+    let strict = Strict::from_request(&req, &mut pl).await.unwrap();
+    assert!(strict.0.is_none());
+    assert_eq!(strict.1, Token("secret".to_owned()));
+}
+
+#[kayrx::test]
+async fn test_tuple_arity() {
+    let (req, mut pl) = TestRequest::with_uri("/?hello=world").to_http_parts();
+    let r = <(
+        Query<Info>,
+        HttpRequest,
+        (),
+        (),
+        (),
+        (),
+        (),
+        (),
+        (),
+        (),
+        (),
+        Option<Query<Info>>,
+    )>::from_request(&req, &mut pl)
+    .await
+    .unwrap();
+    assert_eq!(r.0.hello, "world");
+    assert_eq!(r.11.unwrap().hello, "world");
+}
@@ -12,7 +12,7 @@ use kayrx::web::middleware::Compress;
 use kayrx::web::test::{self, TestRequest};
 use kayrx::web::{self, App, Responder};
 use kayrx::service::ServiceFactory;
-use kayrx::web::file::*;
+use kayrx::web::files::*;
 use futures::future::ok;
 use kayrx::web::dev::ServiceRequest;
 
@@ -642,119 +642,105 @@ async fn test_default_handler_file_missing() {
     assert_eq!(bytes, Bytes::from_static(b"default content"));
 }
 
-//     #[kayrx::test]
-//     async fn test_serve_index() {
-//         let st = Files::new(".").index_file("test.binary");
-//         let req = TestRequest::default().uri("/tests").finish();
-
-//         let resp = st.handle(&req).respond_to(&req).unwrap();
-//         let resp = resp.as_msg();
-//         assert_eq!(resp.status(), StatusCode::OK);
-//         assert_eq!(
-//             resp.headers()
-//                 .get(header::CONTENT_TYPE)
-//                 .expect("content type"),
-//             "application/octet-stream"
-//         );
-//         assert_eq!(
-//             resp.headers()
-//                 .get(header::CONTENT_DISPOSITION)
-//                 .expect("content disposition"),
-//             "attachment; filename=\"test.binary\""
-//         );
-
-//         let req = TestRequest::default().uri("/tests/").finish();
-//         let resp = st.handle(&req).respond_to(&req).unwrap();
-//         let resp = resp.as_msg();
-//         assert_eq!(resp.status(), StatusCode::OK);
-//         assert_eq!(
-//             resp.headers().get(header::CONTENT_TYPE).unwrap(),
-//             "application/octet-stream"
-//         );
-//         assert_eq!(
-//             resp.headers().get(header::CONTENT_DISPOSITION).unwrap(),
-//             "attachment; filename=\"test.binary\""
-//         );
-
-//         // nonexistent index file
-//         let req = TestRequest::default().uri("/tests/unknown").finish();
-//         let resp = st.handle(&req).respond_to(&req).unwrap();
-//         let resp = resp.as_msg();
-//         assert_eq!(resp.status(), StatusCode::NOT_FOUND);
-
-//         let req = TestRequest::default().uri("/tests/unknown/").finish();
-//         let resp = st.handle(&req).respond_to(&req).unwrap();
-//         let resp = resp.as_msg();
-//         assert_eq!(resp.status(), StatusCode::NOT_FOUND);
-//     }
-
-//     #[kayrx::test]
-//     async fn test_serve_index_nested() {
-//         let st = Files::new(".").index_file("mod.rs");
-//         let req = TestRequest::default().uri("/src/client").finish();
-//         let resp = st.handle(&req).respond_to(&req).unwrap();
-//         let resp = resp.as_msg();
-//         assert_eq!(resp.status(), StatusCode::OK);
-//         assert_eq!(
-//             resp.headers().get(header::CONTENT_TYPE).unwrap(),
-//             "text/x-rust"
-//         );
-//         assert_eq!(
-//             resp.headers().get(header::CONTENT_DISPOSITION).unwrap(),
-//             "inline; filename=\"mod.rs\""
-//         );
-//     }
-
-//     #[kayrx::test]
-//     fn integration_serve_index() {
-//         let mut srv = test::TestServer::with_factory(|| {
-//             App::new().handler(
-//                 "test",
-//                 Files::new(".").index_file("Cargo.toml"),
-//             )
-//         });
-
-//         let request = srv.get().uri(srv.url("/test")).finish().unwrap();
-//         let response = srv.execute(request.send()).unwrap();
-//         assert_eq!(response.status(), StatusCode::OK);
-//         let bytes = srv.execute(response.body()).unwrap();
-//         let data = Bytes::from(fs::read("Cargo.toml").unwrap());
-//         assert_eq!(bytes, data);
-
-//         let request = srv.get().uri(srv.url("/test/")).finish().unwrap();
-//         let response = srv.execute(request.send()).unwrap();
-//         assert_eq!(response.status(), StatusCode::OK);
-//         let bytes = srv.execute(response.body()).unwrap();
-//         let data = Bytes::from(fs::read("Cargo.toml").unwrap());
-//         assert_eq!(bytes, data);
-
-//         // nonexistent index file
-//         let request = srv.get().uri(srv.url("/test/unknown")).finish().unwrap();
-//         let response = srv.execute(request.send()).unwrap();
-//         assert_eq!(response.status(), StatusCode::NOT_FOUND);
-
-//         let request = srv.get().uri(srv.url("/test/unknown/")).finish().unwrap();
-//         let response = srv.execute(request.send()).unwrap();
-//         assert_eq!(response.status(), StatusCode::NOT_FOUND);
-//     }
-
-//     #[kayrx::test]
-//     fn integration_percent_encoded() {
-//         let mut srv = test::TestServer::with_factory(|| {
-//             App::new().handler(
-//                 "test",
-//                 Files::new(".").index_file("Cargo.toml"),
-//             )
-//         });
-
-//         let request = srv
-//             .get()
-//             .uri(srv.url("/test/%43argo.toml"))
-//             .finish()
-//             .unwrap();
-//         let response = srv.execute(request.send()).unwrap();
-//         assert_eq!(response.status(), StatusCode::OK);
-//     }
+#[kayrx::test]
+async fn test_serve_index() {
+    let mut srv = test::init_service(
+        App::new().service(Files::new("/test", ".").index_file("Cargo.toml")),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("/test").to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get(header::CONTENT_TYPE).unwrap(), "text/x-toml");
+    let bytes = test::read_body(resp).await;
+    assert_eq!(bytes, Bytes::from(fs::read("Cargo.toml").unwrap()));
+
+    let req = TestRequest::with_uri("/test/").to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // nonexistent index file
+    let req = TestRequest::with_uri("/test/unknown").to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let req = TestRequest::with_uri("/test/unknown/").to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[kayrx::test]
+async fn test_serve_index_nested() {
+    let mut srv = test::init_service(
+        App::new().service(Files::new("/", ".").index_file("mod.rs")),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("/src/web").to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get(header::CONTENT_TYPE).unwrap(), "text/x-rust");
+    assert_eq!(
+        resp.headers().get(header::CONTENT_DISPOSITION).unwrap(),
+        "inline; filename=\"mod.rs\""
+    );
+}
+
+#[kayrx::test]
+async fn test_percent_encoded() {
+    let mut srv = test::init_service(
+        App::new().service(Files::new("/test", ".").index_file("Cargo.toml")),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("/test/%43argo.toml").to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[kayrx::test]
+async fn test_conditional_range() {
+    let mut srv = test::init_service(App::new().service(Files::new("/", "."))).await;
+
+    let req = TestRequest::with_uri("/Cargo.toml").to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    let etag = resp.headers().get(header::ETAG).unwrap().clone();
+    let last_modified = resp.headers().get(header::LAST_MODIFIED).unwrap().clone();
+
+    // not modified takes precedence over range
+    let req = TestRequest::with_uri("/Cargo.toml")
+        .header(header::IF_NONE_MATCH, etag.clone())
+        .header(header::RANGE, "bytes=0-9")
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+
+    // fresh if-range validators
+    let req = TestRequest::with_uri("/Cargo.toml")
+        .header(header::IF_RANGE, etag)
+        .header(header::RANGE, "bytes=0-9")
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(test::read_body(resp).await.len(), 10);
+
+    let req = TestRequest::with_uri("/Cargo.toml")
+        .header(header::IF_RANGE, last_modified)
+        .header(header::RANGE, "bytes=0-9")
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+
+    // stale validator, full content is returned
+    let req = TestRequest::with_uri("/Cargo.toml")
+        .header(header::IF_RANGE, "\"stale\"")
+        .header(header::RANGE, "bytes=0-9")
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().get(header::CONTENT_RANGE).is_none());
+}
 
 #[kayrx::test]
 async fn test_path_buf() {
@@ -786,4 +772,196 @@ async fn test_path_buf() {
         PathBufWrp::get_pathbuf("/seg1/../seg2/").unwrap().0,
         PathBuf::from_iter(vec!["seg2"])
     );
-}
\ No newline at end of file
+}
+#[kayrx::test]
+async fn test_precompressed() {
+    let dir = std::env::temp_dir().join("kayrx-files-precompressed");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("app.css"), b"plain").unwrap();
+    fs::write(dir.join("app.css.br"), b"brotli").unwrap();
+    fs::write(dir.join("app.css.gz"), b"gzip").unwrap();
+
+    let mut srv = test::init_service(
+        App::new()
+            .wrap(Compress::default())
+            .service(Files::new("/", &dir).use_precompressed(true)),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("/app.css")
+        .header(header::ACCEPT_ENCODING, "gzip, br")
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get(header::CONTENT_ENCODING).unwrap(), "gzip");
+    assert_eq!(
+        resp.headers().get(header::CONTENT_TYPE).unwrap(),
+        "text/css"
+    );
+    assert_eq!(resp.headers().get(header::VARY).unwrap(), "accept-encoding");
+    assert_eq!(test::read_body(resp).await, Bytes::from_static(b"gzip"));
+
+    let req = TestRequest::with_uri("/app.css")
+        .header(header::ACCEPT_ENCODING, "gzip;q=0.5, br")
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.headers().get(header::CONTENT_ENCODING).unwrap(), "br");
+    assert_eq!(test::read_body(resp).await, Bytes::from_static(b"brotli"));
+
+    let req = TestRequest::with_uri("/app.css")
+        .header(header::ACCEPT_ENCODING, "br;q=0")
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert!(resp.headers().get(header::CONTENT_ENCODING).is_none());
+    assert_eq!(test::read_body(resp).await, Bytes::from_static(b"plain"));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[kayrx::test]
+async fn test_index_fallback() {
+    let dir = std::env::temp_dir().join("kayrx-files-fallback");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("index.html"), b"<app>").unwrap();
+    fs::write(dir.join("app.css"), b"body {}").unwrap();
+
+    let mut srv = test::init_service(
+        App::new().service(
+            Files::new("/", &dir)
+                .index_file("index.html")
+                .index_fallback("index.html")
+                .fallback_exclude("/api"),
+        ),
+    )
+    .await;
+
+    // existing files are served as is
+    let req = TestRequest::with_uri("/app.css").to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().get(header::CACHE_CONTROL).is_none());
+    assert_eq!(test::read_body(resp).await, Bytes::from_static(b"body {}"));
+
+    // client side routes get the entrypoint
+    let req = TestRequest::with_uri("/users/42/profile").to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get(header::CONTENT_TYPE).unwrap(), "text/html");
+    assert_eq!(resp.headers().get(header::CACHE_CONTROL).unwrap(), "no-cache");
+    assert_eq!(test::read_body(resp).await, Bytes::from_static(b"<app>"));
+
+    // excluded prefixes and non-GET requests are not affected
+    let req = TestRequest::with_uri("/api/users").to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let req = TestRequest::with_uri("/users")
+        .method(Method::POST)
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[kayrx::test]
+async fn test_directory_config() {
+    let dir = std::env::temp_dir().join("kayrx-files-dirconfig");
+    fs::create_dir_all(dir.join("assets")).unwrap();
+    fs::create_dir_all(dir.join("private")).unwrap();
+    fs::write(dir.join("assets/app.1f3a.css"), b"body {}").unwrap();
+    fs::write(dir.join("private/report.txt"), b"secret").unwrap();
+
+    let mut srv = test::init_service(
+        App::new().service(
+            Files::new("/", &dir)
+                .directory(
+                    "assets",
+                    DirConfig::new()
+                        .header(header::CACHE_CONTROL, "public, max-age=31536000, immutable"),
+                )
+                .directory(
+                    "/private/",
+                    DirConfig::new().basic_auth("reports", |user, password| {
+                        user == "admin" && password == "pass"
+                    }),
+                ),
+        ),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("/assets/app.1f3a.css").to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers().get(header::CACHE_CONTROL).unwrap(),
+        "public, max-age=31536000, immutable"
+    );
+
+    let req = TestRequest::with_uri("/private/report.txt").to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        resp.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+        "Basic realm=\"reports\""
+    );
+
+    let req = TestRequest::with_uri("/private/report.txt")
+        .header(header::AUTHORIZATION, "Basic YWRtaW46d3Jvbmc=")
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    let req = TestRequest::with_uri("/private/report.txt")
+        .header(header::AUTHORIZATION, "Basic YWRtaW46cGFzcw==")
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().get(header::CACHE_CONTROL).is_none());
+    assert_eq!(test::read_body(resp).await, Bytes::from_static(b"secret"));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[cfg(unix)]
+#[kayrx::test]
+async fn test_directory_config_resolved_path() {
+    let dir = std::env::temp_dir().join("kayrx-files-dirconfig-resolved");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("public")).unwrap();
+    fs::create_dir_all(dir.join("private")).unwrap();
+    fs::write(dir.join("private/report.txt"), b"secret").unwrap();
+    std::os::unix::fs::symlink(dir.join("private"), dir.join("public/reports")).unwrap();
+
+    let mut srv = test::init_service(
+        App::new().service(Files::new("/", &dir).directory(
+            "private",
+            DirConfig::new().basic_auth("reports", |user, password| {
+                user == "admin" && password == "pass"
+            }),
+        )),
+    )
+    .await;
+
+    // symlink into the protected directory
+    let req = TestRequest::with_uri("/public/reports/report.txt").to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    let req = TestRequest::with_uri("/public/reports/report.txt")
+        .header(header::AUTHORIZATION, "Basic YWRtaW46cGFzcw==")
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // case variant either resolves to the protected file or does not exist
+    let req = TestRequest::with_uri("/PRIVATE/report.txt").to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_ne!(resp.status(), StatusCode::OK);
+
+    let req = TestRequest::with_uri("/Private/../private/report.txt").to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    let _ = fs::remove_dir_all(&dir);
+}
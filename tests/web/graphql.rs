@@ -0,0 +1,212 @@
+use bytes::Bytes;
+use futures::future::{ready, FutureExt, LocalBoxFuture};
+use futures::stream::{self, LocalBoxStream, StreamExt};
+use futures::SinkExt;
+use kayrx::http::{header, StatusCode};
+use kayrx::web::graphql::{Executor, GraphQL, GraphQLRequest, GraphQLResponse};
+use kayrx::web::test::{self, read_body_json, TestRequest};
+use kayrx::web::ws::Message;
+use kayrx::web::App;
+use kayrx::websocket::Frame;
+use serde_json::{json, Value};
+
+struct Echo;
+
+impl Executor for Echo {
+    fn execute(&self, req: GraphQLRequest) -> LocalBoxFuture<'static, GraphQLResponse> {
+        let uploads: Vec<_> = req
+            .uploads
+            .iter()
+            .map(|upload| {
+                json!({
+                    "path": upload.path,
+                    "filename": upload.filename,
+                    "data": String::from_utf8_lossy(&upload.data),
+                })
+            })
+            .collect();
+        ready(GraphQLResponse::data(json!({
+            "query": req.query,
+            "operationName": req.operation_name,
+            "variables": req.variables,
+            "get": req.is_get(),
+            "uploads": uploads,
+        })))
+        .boxed_local()
+    }
+
+    fn subscribe(&self, _: GraphQLRequest) -> LocalBoxStream<'static, GraphQLResponse> {
+        stream::iter(vec![1, 2])
+            .map(|tick| GraphQLResponse::data(json!({ "tick": tick })))
+            .boxed_local()
+    }
+}
+
+#[kayrx::test]
+async fn test_query() {
+    let mut srv = test::init_service(App::new().service(GraphQL::new("/graphql", Echo))).await;
+
+    let req = TestRequest::with_uri(
+        "/graphql?query=%7Bhello%7D&operationName=Op&variables=%7B%22a%22%3A1%7D",
+    )
+    .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers().get(header::CONTENT_TYPE).unwrap(),
+        "application/json"
+    );
+    let body: Value = read_body_json(resp).await;
+    assert_eq!(body["data"]["query"], "{hello}");
+    assert_eq!(body["data"]["operationName"], "Op");
+    assert_eq!(body["data"]["variables"], json!({"a": 1}));
+    assert_eq!(body["data"]["get"], true);
+
+    let req = TestRequest::post()
+        .uri("/graphql")
+        .set_json(&json!({"query": "{hello}", "variables": {"b": 2}}))
+        .to_request();
+    let body: Value = read_body_json(test::call_service(&mut srv, req).await).await;
+    assert_eq!(body["data"]["variables"], json!({"b": 2}));
+    assert_eq!(body["data"]["get"], false);
+
+    let req = TestRequest::post()
+        .uri("/graphql")
+        .header(header::CONTENT_TYPE, "application/graphql")
+        .set_payload("{hello}")
+        .to_request();
+    let body: Value = read_body_json(test::call_service(&mut srv, req).await).await;
+    assert_eq!(body["data"]["query"], "{hello}");
+
+    let req = TestRequest::post()
+        .uri("/graphql")
+        .header(header::CONTENT_TYPE, "text/plain")
+        .set_payload("{hello}")
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    let req = TestRequest::post()
+        .uri("/graphql")
+        .set_json(&json!({"variables": {}}))
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[kayrx::test]
+async fn test_multipart() {
+    let mut srv = test::init_service(App::new().service(GraphQL::new("/graphql", Echo))).await;
+
+    let body = "--abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+         Content-Disposition: form-data; name=\"operations\"\r\n\r\n\
+         {\"query\": \"mutation($file: Upload!) { upload(file: $file) }\", \"variables\": {\"file\": null}}\r\n\
+         --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+         Content-Disposition: form-data; name=\"map\"\r\n\r\n\
+         {\"0\": [\"variables.file\"]}\r\n\
+         --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+         Content-Disposition: form-data; name=\"0\"; filename=\"a.txt\"\r\n\
+         Content-Type: text/plain\r\n\r\n\
+         content\r\n\
+         --abbc761f78ff4d7cb7573b5a23f96ef0--\r\n";
+    let req = TestRequest::post()
+        .uri("/graphql")
+        .header(
+            header::CONTENT_TYPE,
+            "multipart/form-data; boundary=abbc761f78ff4d7cb7573b5a23f96ef0",
+        )
+        .set_payload(Bytes::from_static(body.as_bytes()))
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body: Value = read_body_json(resp).await;
+    assert_eq!(
+        body["data"]["uploads"],
+        json!([{"path": "variables.file", "filename": "a.txt", "data": "content"}])
+    );
+
+    // file path must point to a variable
+    let body = body_with_map("{\"0\": [\"variables.other\"]}");
+    let req = TestRequest::post()
+        .uri("/graphql")
+        .header(
+            header::CONTENT_TYPE,
+            "multipart/form-data; boundary=abbc761f78ff4d7cb7573b5a23f96ef0",
+        )
+        .set_payload(body)
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+fn body_with_map(map: &str) -> String {
+    format!(
+        "--abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+         Content-Disposition: form-data; name=\"operations\"\r\n\r\n\
+         {{\"query\": \"mutation\", \"variables\": {{\"file\": null}}}}\r\n\
+         --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+         Content-Disposition: form-data; name=\"map\"\r\n\r\n\
+         {}\r\n\
+         --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+         Content-Disposition: form-data; name=\"0\"; filename=\"a.txt\"\r\n\r\n\
+         content\r\n\
+         --abbc761f78ff4d7cb7573b5a23f96ef0--\r\n",
+        map
+    )
+}
+
+async fn next_message<S>(framed: &mut S) -> Value
+where
+    S: futures::Stream<Item = Result<Frame, kayrx::websocket::ProtocolError>> + Unpin,
+{
+    match framed.next().await.unwrap().unwrap() {
+        Frame::Text(text) => serde_json::from_slice(&text).unwrap(),
+        frame => panic!("unexpected frame: {:?}", frame),
+    }
+}
+
+#[kayrx::test]
+async fn test_subscriptions() {
+    let mut srv = test::start(|| {
+        App::new().service(
+            GraphQL::new("/graphql", Echo)
+                .subscriptions(true)
+                .keep_alive(None),
+        )
+    });
+
+    let mut framed = srv.ws_at("/graphql").await.unwrap();
+    framed
+        .send(Message::Text(json!({"type": "connection_init"}).to_string()))
+        .await
+        .unwrap();
+    assert_eq!(next_message(&mut framed).await, json!({"type": "connection_ack"}));
+    assert_eq!(next_message(&mut framed).await, json!({"type": "ka"}));
+
+    let start = json!({
+        "type": "start",
+        "id": "1",
+        "payload": {"query": "subscription { tick }"},
+    });
+    framed.send(Message::Text(start.to_string())).await.unwrap();
+    for tick in 1..3 {
+        assert_eq!(
+            next_message(&mut framed).await,
+            json!({"type": "data", "id": "1", "payload": {"data": {"tick": tick}}})
+        );
+    }
+    assert_eq!(
+        next_message(&mut framed).await,
+        json!({"type": "complete", "id": "1"})
+    );
+
+    framed
+        .send(Message::Text("not json".to_owned()))
+        .await
+        .unwrap();
+    assert_eq!(
+        next_message(&mut framed).await["type"],
+        "connection_error"
+    );
+}
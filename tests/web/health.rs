@@ -0,0 +1,78 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use kayrx::http::{Response as HttpResponse, StatusCode};
+use kayrx::timer::delay_for;
+use kayrx::web::health::{Dependency, Health, Status};
+use kayrx::web::test::{call_service, init_service, read_body_json, TestRequest};
+use kayrx::web::{self, App};
+use serde_json::{json, Value};
+
+#[kayrx::test]
+async fn test_readiness() {
+    let db_up = Rc::new(Cell::new(true));
+    let up = db_up.clone();
+    let db = Dependency::new("db", move || {
+        let res = if up.get() { Ok(()) } else { Err("refused".to_owned()) };
+        async move { res }
+    })
+    .interval(Duration::from_millis(20));
+    let cache = Dependency::new("cache", || async { Err("refused".to_owned()) })
+        .interval(Duration::from_millis(20))
+        .critical(false);
+
+    let mut changes = db.watch();
+    assert_eq!(changes.recv().await, Some(Status::Unknown));
+
+    let db2 = db.clone();
+    let mut srv = init_service(
+        App::new()
+            .plug(Health::new().dependency(db.clone()).dependency(cache))
+            .route(
+                "/orders",
+                web::get().to(move || {
+                    let res = db2.check().map(|_| HttpResponse::Ok().finish());
+                    async move { res }
+                }),
+            ),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("/healthz").to_request();
+    let res = call_service(&mut srv, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    assert_eq!(changes.recv().await, Some(Status::Up));
+    let req = TestRequest::with_uri("/readyz").to_request();
+    let res = call_service(&mut srv, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: Value = read_body_json(res).await;
+    assert_eq!(
+        body,
+        json!({
+            "status": "ready",
+            "dependencies": {
+                "db": {"status": "up"},
+                "cache": {"status": "down", "error": "refused"},
+            }
+        })
+    );
+
+    // critical dependency is down
+    db_up.set(false);
+    assert_eq!(changes.recv().await, Some(Status::Down("refused".to_owned())));
+    assert!(!db.is_up());
+    let req = TestRequest::with_uri("/readyz").to_request();
+    let res = call_service(&mut srv, req).await;
+    assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let req = TestRequest::with_uri("/orders").to_request();
+    let res = call_service(&mut srv, req).await;
+    assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    db_up.set(true);
+    delay_for(Duration::from_millis(100)).await;
+    let req = TestRequest::with_uri("/orders").to_request();
+    let res = call_service(&mut srv, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
@@ -0,0 +1,157 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use kayrx::http::StatusCode;
+use kayrx::timer::delay_for;
+use kayrx::web::client::cache::MemoryCacheStore;
+use kayrx::web::middleware::ResponseCache;
+use kayrx::web::test::{call_service, init_service, read_body, TestRequest};
+use kayrx::web::{self, App, HttpResponse};
+
+#[kayrx::test]
+async fn test_fresh_response() {
+    let counter = Rc::new(Cell::new(0));
+    let counter2 = counter.clone();
+    let mut srv = init_service(
+        App::new()
+            .wrap(ResponseCache::new())
+            .service(web::resource("/").to(move || {
+                counter2.set(counter2.get() + 1);
+                let res = HttpResponse::Ok()
+                    .header("cache-control", "max-age=60")
+                    .body(counter2.get().to_string());
+                async move { res }
+            })),
+    )
+    .await;
+
+    for _ in 0..2 {
+        let resp = call_service(&mut srv, TestRequest::get().uri("/").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"1"));
+    }
+    assert_eq!(counter.get(), 1);
+
+    // request no-cache bypasses stored response
+    let req = TestRequest::get()
+        .uri("/")
+        .header("cache-control", "no-cache")
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(read_body(resp).await, Bytes::from_static(b"2"));
+
+    // successful unsafe request invalidates stored response
+    let resp = call_service(&mut srv, TestRequest::post().uri("/").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let resp = call_service(&mut srv, TestRequest::get().uri("/").to_request()).await;
+    assert_eq!(read_body(resp).await, Bytes::from_static(b"4"));
+}
+
+#[kayrx::test]
+async fn test_private_response() {
+    let counter = Rc::new(Cell::new(0));
+    let counter2 = counter.clone();
+    let mut srv = init_service(
+        App::new()
+            .wrap(ResponseCache::with_store(MemoryCacheStore::new(16)))
+            .service(web::resource("/").to(move || {
+                counter2.set(counter2.get() + 1);
+                let res = HttpResponse::Ok()
+                    .header("cache-control", "private, max-age=60")
+                    .finish();
+                async move { res }
+            })),
+    )
+    .await;
+
+    for _ in 0..2 {
+        let resp = call_service(&mut srv, TestRequest::get().uri("/").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+    assert_eq!(counter.get(), 2);
+}
+
+#[kayrx::test]
+async fn test_stale_while_revalidate() {
+    let counter = Rc::new(Cell::new(0));
+    let counter2 = counter.clone();
+    let mut srv = init_service(
+        App::new()
+            .wrap(ResponseCache::new())
+            .service(web::resource("/").to(move || {
+                counter2.set(counter2.get() + 1);
+                let res = HttpResponse::Ok()
+                    .header("cache-control", "max-age=0, stale-while-revalidate=60")
+                    .body(counter2.get().to_string());
+                async move { res }
+            })),
+    )
+    .await;
+
+    let resp = call_service(&mut srv, TestRequest::get().uri("/").to_request()).await;
+    assert_eq!(read_body(resp).await, Bytes::from_static(b"1"));
+
+    // stale response is served, application is called in background
+    let resp = call_service(&mut srv, TestRequest::get().uri("/").to_request()).await;
+    assert_eq!(read_body(resp).await, Bytes::from_static(b"1"));
+    delay_for(Duration::from_millis(50)).await;
+    assert_eq!(counter.get(), 2);
+
+    // refreshed response
+    let resp = call_service(&mut srv, TestRequest::get().uri("/").to_request()).await;
+    assert_eq!(read_body(resp).await, Bytes::from_static(b"2"));
+}
+
+#[kayrx::test]
+async fn test_stale_if_error() {
+    let counter = Rc::new(Cell::new(0));
+    let counter2 = counter.clone();
+    let mut srv = init_service(
+        App::new()
+            .wrap(ResponseCache::new().stale_if_error(Duration::from_secs(60)))
+            .service(web::resource("/").to(move || {
+                counter2.set(counter2.get() + 1);
+                let res = if counter2.get() == 1 {
+                    HttpResponse::Ok()
+                        .header("cache-control", "max-age=0")
+                        .body("cached")
+                } else {
+                    HttpResponse::ServiceUnavailable().finish()
+                };
+                async move { res }
+            })),
+    )
+    .await;
+
+    let resp = call_service(&mut srv, TestRequest::get().uri("/").to_request()).await;
+    assert_eq!(read_body(resp).await, Bytes::from_static(b"cached"));
+
+    let resp = call_service(&mut srv, TestRequest::get().uri("/").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(read_body(resp).await, Bytes::from_static(b"cached"));
+    assert_eq!(counter.get(), 2);
+}
+
+#[kayrx::test]
+async fn test_key_includes_host() {
+    let mut srv = init_service(
+        App::new()
+            .wrap(ResponseCache::with_store(MemoryCacheStore::new(16)))
+            .service(web::resource("/").to(|req: web::HttpRequest| {
+                let host = req.connection_info().host().to_owned();
+                let res = HttpResponse::Ok()
+                    .header("cache-control", "max-age=60")
+                    .body(host);
+                async move { res }
+            })),
+    )
+    .await;
+
+    for host in &["one.example", "two.example", "one.example"] {
+        let req = TestRequest::get().uri("/").header("host", *host).to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(read_body(resp).await, Bytes::from(host.as_bytes()));
+    }
+}
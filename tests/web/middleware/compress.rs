@@ -0,0 +1,56 @@
+use std::io::Read;
+
+use bytes::Bytes;
+use flate2::read::GzDecoder;
+use kayrx::http::header::{ACCEPT_ENCODING, CONTENT_ENCODING};
+use kayrx::web::middleware::Compress;
+use kayrx::web::test::{call_service, init_service, read_body, TestRequest};
+use kayrx::web::{self, App, HttpResponse};
+
+const BODY: &str = "compressible body compressible body compressible body \
+                    compressible body compressible body compressible body";
+
+#[kayrx::test]
+async fn test_accept_encoding_quality() {
+    let mut srv = init_service(
+        App::new()
+            .wrap(Compress::default())
+            .service(web::resource("/").to(|| async { HttpResponse::Ok().body(BODY) })),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("/")
+        .header(ACCEPT_ENCODING, "br;q=0.1, gzip;q=0.8")
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.headers().get(CONTENT_ENCODING).unwrap(), "gzip");
+
+    let req = TestRequest::with_uri("/")
+        .header(ACCEPT_ENCODING, "gzip;q=0")
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert!(resp.headers().get(CONTENT_ENCODING).is_none());
+    assert_eq!(read_body(resp).await, Bytes::from_static(BODY.as_bytes()));
+}
+
+#[kayrx::test]
+async fn test_compression_level() {
+    let mut srv = init_service(
+        App::new()
+            .wrap(Compress::default().level(9))
+            .service(web::resource("/").to(|| async { HttpResponse::Ok().body(BODY) })),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("/")
+        .header(ACCEPT_ENCODING, "gzip")
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.headers().get(CONTENT_ENCODING).unwrap(), "gzip");
+
+    let body = read_body(resp).await;
+    assert!(body.len() < BODY.len());
+    let mut decoded = String::new();
+    GzDecoder::new(&body[..]).read_to_string(&mut decoded).unwrap();
+    assert_eq!(decoded, BODY);
+}
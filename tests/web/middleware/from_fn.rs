@@ -0,0 +1,63 @@
+use kayrx::http::error::Error;
+use kayrx::http::header::{HeaderValue, CONTENT_TYPE};
+use kayrx::http::{Response as HttpResponse, StatusCode};
+use kayrx::service::{Service, Transform};
+use kayrx::web::dev::{Body, ServiceRequest, ServiceResponse};
+use kayrx::web::middleware::{from_fn, Next};
+use kayrx::web::test::{self, ok_service, TestRequest};
+use kayrx::web::{self, App};
+
+async fn set_header(
+    req: ServiceRequest,
+    next: Next<Body>,
+) -> Result<ServiceResponse<Body>, Error> {
+    let mut res = next.call(req).await?;
+    res.headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("0001"));
+    Ok(res)
+}
+
+async fn forbid(
+    req: ServiceRequest,
+    next: Next<Body>,
+) -> Result<ServiceResponse<Body>, Error> {
+    if req.path() == "/secret" {
+        Ok(req.into_response(HttpResponse::Forbidden().finish()))
+    } else {
+        next.call(req).await
+    }
+}
+
+#[kayrx::test]
+async fn test_from_fn() {
+    let mut mw = from_fn(set_header)
+        .new_transform(ok_service())
+        .await
+        .unwrap();
+
+    let req = TestRequest::default().to_srv_request();
+    let resp = mw.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), "0001");
+}
+
+#[kayrx::test]
+async fn test_from_fn_short_circuit() {
+    let mut srv = test::init_service(
+        App::new()
+            .wrap(from_fn(set_header))
+            .wrap(from_fn(forbid))
+            .service(web::resource("/{name}").to(|| HttpResponse::Ok())),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("/index").to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), "0001");
+
+    let req = TestRequest::with_uri("/secret").to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    assert!(resp.headers().get(CONTENT_TYPE).is_none());
+}
@@ -0,0 +1,197 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::future::{ok, FutureExt};
+use kayrx::http::error::Error;
+use kayrx::http::{Method, StatusCode};
+use kayrx::http::Response as HttpResponse;
+use kayrx::service::{IntoService, Service, Transform};
+use kayrx::web::dev::ServiceRequest;
+use kayrx::web::middleware::idempotency::{Idempotency, MemoryStore};
+use kayrx::timer::{delay_for, timeout};
+use kayrx::web::test::{self, TestRequest};
+
+#[kayrx::test]
+async fn test_replay_stored_response() {
+    let counter = Rc::new(Cell::new(0));
+    let counter2 = counter.clone();
+    let srv = move |req: ServiceRequest| {
+        counter2.set(counter2.get() + 1);
+        ok(req.into_response(
+            HttpResponse::Created().body(format!("{}", counter2.get())),
+        ))
+    };
+
+    let mut mw = Idempotency::new()
+        .new_transform(srv.into_service())
+        .await
+        .unwrap();
+
+    for _ in 0..2 {
+        let req = TestRequest::post()
+            .uri("/payments")
+            .header("idempotency-key", "abc")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert_eq!(test::read_body(resp).await, Bytes::from_static(b"1"));
+    }
+    assert_eq!(counter.get(), 1);
+
+    let req = TestRequest::post()
+        .uri("/payments")
+        .header("idempotency-key", "xyz")
+        .to_srv_request();
+    let resp = mw.call(req).await.unwrap();
+    assert_eq!(test::read_body(resp).await, Bytes::from_static(b"2"));
+}
+
+#[kayrx::test]
+async fn test_safe_methods_and_missing_key() {
+    let counter = Rc::new(Cell::new(0));
+    let counter2 = counter.clone();
+    let srv = move |req: ServiceRequest| {
+        counter2.set(counter2.get() + 1);
+        ok(req.into_response(HttpResponse::Ok().finish()))
+    };
+
+    let mut mw = Idempotency::new()
+        .new_transform(srv.into_service())
+        .await
+        .unwrap();
+
+    let req = TestRequest::default()
+        .method(Method::GET)
+        .header("idempotency-key", "abc")
+        .to_srv_request();
+    let _ = mw.call(req).await.unwrap();
+    let req = TestRequest::default()
+        .method(Method::GET)
+        .header("idempotency-key", "abc")
+        .to_srv_request();
+    let _ = mw.call(req).await.unwrap();
+    let _ = mw.call(TestRequest::post().to_srv_request()).await.unwrap();
+    assert_eq!(counter.get(), 3);
+}
+
+#[kayrx::test]
+async fn test_in_flight_on_other_worker() {
+    let store = MemoryStore::new();
+    let mut mw = Idempotency::with_store(store.clone())
+        .new_transform(test::ok_service())
+        .await
+        .unwrap();
+
+    // reservation made by another worker
+    let _ = kayrx::web::middleware::idempotency::IdempotencyStore::reserve(
+        &store,
+        "POST / abc",
+        std::time::Duration::from_secs(10),
+    )
+    .await;
+
+    let req = TestRequest::post()
+        .header("idempotency-key", "abc")
+        .to_srv_request();
+    let resp = mw.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+}
+
+#[kayrx::test]
+async fn test_server_errors_not_stored() {
+    let counter = Rc::new(Cell::new(0));
+    let counter2 = counter.clone();
+    let srv = move |req: ServiceRequest| {
+        counter2.set(counter2.get() + 1);
+        ok(req.into_response(HttpResponse::InternalServerError().finish()))
+    };
+
+    let mut mw = Idempotency::new()
+        .new_transform(srv.into_service())
+        .await
+        .unwrap();
+
+    for _ in 0..2 {
+        let req = TestRequest::post()
+            .header("idempotency-key", "abc")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    assert_eq!(counter.get(), 2);
+}
+
+#[kayrx::test]
+async fn test_cancelled_request_releases_key() {
+    let counter = Rc::new(Cell::new(0));
+    let counter2 = counter.clone();
+    let srv = move |req: ServiceRequest| {
+        counter2.set(counter2.get() + 1);
+        let first = counter2.get() == 1;
+        async move {
+            if first {
+                delay_for(Duration::from_secs(60)).await;
+            }
+            Ok::<_, Error>(req.into_response(HttpResponse::Created().finish()))
+        }
+    };
+
+    let mut mw = Idempotency::new()
+        .new_transform(srv.into_service())
+        .await
+        .unwrap();
+    let req = || {
+        TestRequest::post()
+            .header("idempotency-key", "abc")
+            .to_srv_request()
+    };
+
+    let first = mw.call(req());
+    let duplicate = mw.call(req());
+    assert!(timeout(Duration::from_millis(10), first).await.is_err());
+
+    // waiting duplicate is rejected, key is processed again
+    let resp = duplicate.await.unwrap();
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    delay_for(Duration::from_millis(10)).await;
+
+    let resp = mw.call(req()).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    assert_eq!(counter.get(), 2);
+}
+
+#[kayrx::test]
+async fn test_in_flight_ttl() {
+    let store = MemoryStore::new();
+    let srv = |req: ServiceRequest| async move {
+        delay_for(Duration::from_secs(60)).await;
+        Ok::<_, Error>(req.into_response(HttpResponse::Created().finish()))
+    };
+    let mut mw1 = Idempotency::with_store(store.clone())
+        .in_flight_ttl(Duration::from_millis(50))
+        .new_transform(srv.into_service())
+        .await
+        .unwrap();
+    let mut mw2 = Idempotency::with_store(store)
+        .new_transform(test::ok_service())
+        .await
+        .unwrap();
+    let req = || {
+        TestRequest::post()
+            .header("idempotency-key", "abc")
+            .to_srv_request()
+    };
+
+    // request is stuck on the first worker
+    kayrx::fiber::spawn(mw1.call(req()).map(|_| ()));
+    delay_for(Duration::from_millis(10)).await;
+    let resp = mw2.call(req()).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+    // reservation expires
+    delay_for(Duration::from_millis(100)).await;
+    let resp = mw2.call(req()).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+}
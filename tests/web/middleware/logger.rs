@@ -40,7 +40,7 @@ async fn test_url_path() {
     .uri("/test/route/yeah")
     .to_srv_request();
 
-    let now = OffsetDateTime::now();
+    let now = OffsetDateTime::now_utc();
     for unit in &mut format.0 {
         unit.render_request(now, &req);
     }
@@ -71,7 +71,7 @@ async fn test_default_format() {
     )
     .to_srv_request();
 
-    let now = OffsetDateTime::now();
+    let now = OffsetDateTime::now_utc();
     for unit in &mut format.0 {
         unit.render_request(now, &req);
     }
@@ -81,7 +81,7 @@ async fn test_default_format() {
         unit.render_response(&resp);
     }
 
-    let entry_time = OffsetDateTime::now();
+    let entry_time = OffsetDateTime::now_utc();
     let render = |fmt: &mut Formatter<'_>| {
         for unit in &format.0 {
             unit.render(fmt, 1024, entry_time)?;
@@ -99,7 +99,7 @@ async fn test_request_time_format() {
     let mut format = Format::new("%t");
     let req = TestRequest::default().to_srv_request();
 
-    let now = OffsetDateTime::now();
+    let now = OffsetDateTime::now_utc();
     for unit in &mut format.0 {
         unit.render_request(now, &req);
     }
@@ -117,4 +117,32 @@ async fn test_request_time_format() {
     };
     let s = format!("{}", FormatDisplay(&render));
     assert!(s.contains(&format!("{}", now.format("%Y-%m-%dT%H:%M:%S"))));
-}
\ No newline at end of file
+}
+#[kayrx::test]
+async fn test_method_and_pid_format() {
+    let mut format = Format::new("%m %U %s %b %Dms pid=%P");
+    let req = TestRequest::with_uri("/items")
+        .method(kayrx::http::Method::DELETE)
+        .to_srv_request();
+
+    let now = OffsetDateTime::now_utc();
+    for unit in &mut format.0 {
+        unit.render_request(now, &req);
+    }
+
+    let resp = HttpResponse::build(StatusCode::NO_CONTENT).finish();
+    for unit in &mut format.0 {
+        unit.render_response(&resp);
+    }
+
+    let render = |fmt: &mut Formatter<'_>| {
+        for unit in &format.0 {
+            unit.render(fmt, 0, now)?;
+        }
+        Ok(())
+    };
+    let s = format!("{}", FormatDisplay(&render));
+    assert!(s.starts_with("DELETE /items 204 0 "));
+    assert!(s.contains("ms pid="));
+    assert!(s.ends_with(&std::process::id().to_string()));
+}
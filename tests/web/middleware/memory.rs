@@ -0,0 +1,110 @@
+use bytes::Bytes;
+use kayrx::http::StatusCode;
+use kayrx::service::Service;
+use kayrx::web::middleware::memory::{MemoryBudget, MemoryLimitError};
+use kayrx::web::middleware::redact::{JsonPointer, Redact};
+use kayrx::web::middleware::MemoryLimit;
+use kayrx::web::test::{call_service, init_service, read_body, TestRequest};
+use kayrx::web::{self, types, App, HttpResponse};
+
+#[kayrx::test]
+async fn test_body_extractors() {
+    let mut srv = init_service(
+        App::new()
+            .wrap(MemoryLimit::new(1024))
+            .service(web::resource("/bytes").to(|body: Bytes| async move {
+                HttpResponse::Ok().body(body)
+            }))
+            .service(web::resource("/json").to(|body: types::Json<Vec<u32>>| {
+                async move { HttpResponse::Ok().body(body.len().to_string()) }
+            })),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("/bytes")
+        .set_payload(vec![b'x'; 1024])
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // budget is per request
+    let req = TestRequest::with_uri("/bytes")
+        .set_payload(vec![b'x'; 1024])
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let req = TestRequest::with_uri("/bytes")
+        .set_payload(vec![b'x'; 1025])
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    // below json limit, above memory limit
+    let body = serde_json::to_vec(&vec![1u32; 1024]).unwrap();
+    let req = TestRequest::with_uri("/json")
+        .header("content-type", "application/json")
+        .set_payload(body)
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[kayrx::test]
+async fn test_budget_extractor() {
+    let mut srv = init_service(
+        App::new()
+            .wrap(MemoryLimit::new(100))
+            .service(web::resource("/").to(|body: Bytes, budget: MemoryBudget| {
+                async move {
+                    assert_eq!(budget.used(), body.len());
+                    assert_eq!(budget.limit(), 100);
+                    assert!(budget.charge(100 - body.len()).is_ok());
+                    assert!(budget.charge(1).is_err());
+                    assert!(budget.is_exceeded());
+                    budget.release(10);
+                    assert!(budget.charge(10).is_ok());
+                    HttpResponse::Ok().finish()
+                }
+            })),
+    )
+    .await;
+
+    let req = TestRequest::default().set_payload("0123456789").to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[kayrx::test]
+async fn test_response_buffering() {
+    let mut srv = init_service(
+        App::new()
+            .wrap(Redact::new().matcher(JsonPointer::new(&["/ssn"])))
+            .wrap(MemoryLimit::new(64))
+            .service(web::resource("/").to(|| async {
+                HttpResponse::Ok()
+                    .content_type("application/json")
+                    .body(format!(r#"{{"ssn":"1","data":"{}"}}"#, "x".repeat(100)))
+            }))
+            .service(web::resource("/small").to(|| async {
+                HttpResponse::Ok()
+                    .content_type("application/json")
+                    .body(r#"{"ssn":"1"}"#)
+            })),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("/small").to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = serde_json::from_slice(&read_body(resp).await).unwrap();
+    assert_eq!(body, serde_json::json!({"ssn": "[REDACTED]"}));
+
+    let req = TestRequest::default().to_request();
+    let err = srv.call(req).await.err().unwrap();
+    assert!(err.as_error::<MemoryLimitError>().is_some());
+    assert_eq!(
+        err.as_response_error().status_code(),
+        StatusCode::INTERNAL_SERVER_ERROR
+    );
+}
@@ -1,6 +1,15 @@
+mod cache;
+mod compress;
 mod condition;
 mod cors;
 mod defaultheaders;
 mod errhandlers;
-// mod logger;
-mod normalize;
\ No newline at end of file
+mod from_fn;
+mod idempotency;
+mod logger;
+mod memory;
+mod normalize;
+mod quota;
+mod redact;
+mod server_timing;
+mod trace;
@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::future::ok;
+use kayrx::http::header::{CONTENT_LENGTH, RETRY_AFTER};
+use kayrx::http::Response as HttpResponse;
+use kayrx::http::StatusCode;
+use kayrx::service::{IntoService, Service, Transform};
+use kayrx::web::dev::ServiceRequest;
+use kayrx::web::middleware::quota::{MemoryQuotaStore, Quota, QuotaStore, Quotas};
+use kayrx::web::test::{self, TestRequest};
+use kayrx::web::{self, App};
+
+fn tenant(req: &ServiceRequest) -> Option<String> {
+    req.headers()
+        .get("x-tenant")
+        .and_then(|val| val.to_str().ok())
+        .map(|val| val.to_owned())
+}
+
+#[kayrx::test]
+async fn test_request_quota() {
+    let srv = |req: ServiceRequest| ok(req.into_response(HttpResponse::Ok().finish()));
+    let mut mw = Quotas::new(tenant)
+        .quota(Quota::per(Duration::from_secs(60)).requests(2))
+        .tenant_quota("premium", Quota::per(Duration::from_secs(60)).requests(3))
+        .new_transform(srv.into_service())
+        .await
+        .unwrap();
+
+    for remaining in &["1", "0"] {
+        let req = TestRequest::default().header("x-tenant", "free").to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get("x-quota-limit").unwrap(), "2");
+        assert_eq!(resp.headers().get("x-quota-remaining").unwrap(), *remaining);
+        assert!(resp.headers().contains_key("x-quota-reset"));
+    }
+
+    let req = TestRequest::default().header("x-tenant", "free").to_srv_request();
+    let resp = mw.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(resp.headers().contains_key(RETRY_AFTER));
+
+    // per-tenant quota
+    let req = TestRequest::default().header("x-tenant", "premium").to_srv_request();
+    let resp = mw.call(req).await.unwrap();
+    assert_eq!(resp.headers().get("x-quota-remaining").unwrap(), "2");
+
+    // requests without tenant are not accounted
+    let req = TestRequest::default().to_srv_request();
+    let resp = mw.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(!resp.headers().contains_key("x-quota-limit"));
+}
+
+#[kayrx::test]
+async fn test_bytes_quota() {
+    let store = MemoryQuotaStore::new();
+    let quota = Quota::per(Duration::from_secs(60)).bytes(10);
+
+    let srv = |req: ServiceRequest| {
+        ok(req.into_response(HttpResponse::Ok().body("0123456789ab")))
+    };
+    let mut mw = Quotas::with_store(store.clone(), tenant)
+        .quota(quota)
+        .new_transform(srv.into_service())
+        .await
+        .unwrap();
+
+    let req = TestRequest::default().header("x-tenant", "t1").to_srv_request();
+    let resp = mw.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("x-quota-bytes-remaining").unwrap(), "10");
+    assert_eq!(test::read_body(resp).await, Bytes::from_static(b"0123456789ab"));
+
+    // response bytes are recorded in background
+    kayrx::timer::delay_for(Duration::from_millis(10)).await;
+    let usage = store.record("t1", &quota, 0, 0).await.unwrap();
+    assert_eq!(usage.requests, 1);
+    assert_eq!(usage.bytes, 12);
+
+    let req = TestRequest::default().header("x-tenant", "t1").to_srv_request();
+    let resp = mw.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[kayrx::test]
+async fn test_bytes_quota_request_body() {
+    let store = MemoryQuotaStore::new();
+    let quota = Quota::per(Duration::from_secs(60)).bytes(100);
+    let mut srv = test::init_service(
+        App::new()
+            .wrap(Quotas::with_store(store.clone(), tenant).quota(quota))
+            .service(web::resource("/").to(|body: Bytes| {
+                assert_eq!(body.len(), 64);
+                HttpResponse::Ok()
+            })),
+    )
+    .await;
+
+    // body without content-length, i.e. chunked upload
+    let req = TestRequest::post()
+        .header("x-tenant", "t1")
+        .set_payload(Bytes::from(vec![b'x'; 64]))
+        .to_request();
+    assert!(!req.head().headers.contains_key(CONTENT_LENGTH));
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    drop(resp);
+
+    kayrx::timer::delay_for(Duration::from_millis(10)).await;
+    let usage = store.record("t1", &quota, 0, 0).await.unwrap();
+    assert_eq!(usage.bytes, 64);
+}
+
+#[kayrx::test]
+async fn test_quota_period() {
+    let store = MemoryQuotaStore::new();
+    let quota = Quota::per(Duration::from_millis(50)).requests(1);
+
+    let usage = store.record("t1", &quota, 1, 0).await.unwrap();
+    assert_eq!(usage.requests, 1);
+    let usage = store.record("t1", &quota, 1, 0).await.unwrap();
+    assert_eq!(usage.requests, 2);
+
+    kayrx::timer::delay_for(Duration::from_millis(60)).await;
+    let usage = store.record("t1", &quota, 1, 0).await.unwrap();
+    assert_eq!(usage.requests, 1);
+}
@@ -0,0 +1,100 @@
+use bytes::Bytes;
+use futures::future::ok;
+use kayrx::http::Response as HttpResponse;
+use kayrx::http::StatusCode;
+use kayrx::service::{IntoService, Service, Transform};
+use kayrx::web::dev::ServiceRequest;
+use kayrx::web::middleware::redact::{JsonPointer, Redact, RegexMatcher};
+use kayrx::web::test::{self, TestRequest};
+
+#[kayrx::test]
+async fn test_json_pointer() {
+    let srv = |req: ServiceRequest| {
+        ok(req.into_response(
+            HttpResponse::Ok()
+                .content_type("application/json")
+                .body(r#"{"name":"a","ssn":"1","cards":[{"number":"2"},{"number":"3"}]}"#),
+        ))
+    };
+    let mut mw = Redact::new()
+        .matcher(JsonPointer::new(&["/ssn", "/cards/*/number", "/missing"]))
+        .new_transform(srv.into_service())
+        .await
+        .unwrap();
+
+    let resp = mw.call(TestRequest::default().to_srv_request()).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = test::read_body(resp).await;
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "name": "a",
+            "ssn": "[REDACTED]",
+            "cards": [{"number": "[REDACTED]"}, {"number": "[REDACTED]"}]
+        })
+    );
+}
+
+#[kayrx::test]
+async fn test_regex_audience() {
+    let srv = |req: ServiceRequest| {
+        ok(req.into_response(
+            HttpResponse::Ok()
+                .content_type("text/html")
+                .body("<p>ssn: 123-45-6789</p>"),
+        ))
+    };
+    let mut mw = Redact::new()
+        .matcher(RegexMatcher::new(r"\d{3}-\d{2}-\d{4}").replacement("***"))
+        .audience(|req| !req.headers().contains_key("x-internal"))
+        .new_transform(srv.into_service())
+        .await
+        .unwrap();
+
+    let resp = mw.call(TestRequest::default().to_srv_request()).await.unwrap();
+    assert_eq!(
+        test::read_body(resp).await,
+        Bytes::from_static(b"<p>ssn: ***</p>")
+    );
+
+    let req = TestRequest::default()
+        .header("x-internal", "1")
+        .to_srv_request();
+    let resp = mw.call(req).await.unwrap();
+    assert_eq!(
+        test::read_body(resp).await,
+        Bytes::from_static(b"<p>ssn: 123-45-6789</p>")
+    );
+}
+
+#[kayrx::test]
+async fn test_fail_closed() {
+    let srv = |req: ServiceRequest| {
+        ok(req.into_response(
+            HttpResponse::Ok()
+                .content_type("application/json")
+                .body("not a json"),
+        ))
+    };
+    let mut mw = Redact::new()
+        .matcher(JsonPointer::new(&["/ssn"]))
+        .new_transform(srv.into_service())
+        .await
+        .unwrap();
+
+    let resp = mw.call(TestRequest::default().to_srv_request()).await;
+    assert!(resp.is_err());
+
+    // other content types are not processed
+    let srv = |req: ServiceRequest| {
+        ok(req.into_response(HttpResponse::Ok().content_type("image/png").body("png")))
+    };
+    let mut mw = Redact::new()
+        .matcher(JsonPointer::new(&["/ssn"]))
+        .new_transform(srv.into_service())
+        .await
+        .unwrap();
+    let resp = mw.call(TestRequest::default().to_srv_request()).await.unwrap();
+    assert_eq!(test::read_body(resp).await, Bytes::from_static(b"png"));
+}
@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use kayrx::web::middleware::{ServerTiming, ServerTimingHeader};
+use kayrx::web::test::{call_service, init_service, TestRequest};
+use kayrx::web::{self, App, HttpResponse};
+
+async fn index(timing: ServerTiming) -> HttpResponse {
+    timing.record("db", Duration::from_millis(12));
+    timing.record_with_desc("cache", Duration::from_micros(500), "redis \"main\"");
+    timing.mark("miss");
+    timing.start("render").stop();
+    HttpResponse::Ok().finish()
+}
+
+#[kayrx::test]
+async fn test_server_timing() {
+    let mut srv = init_service(
+        App::new()
+            .wrap(ServerTimingHeader::new())
+            .service(web::resource("/").to(index)),
+    )
+    .await;
+
+    let req = TestRequest::default().to_request();
+    let resp = call_service(&mut srv, req).await;
+    let value = resp
+        .headers()
+        .get("server-timing")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_owned();
+
+    let metrics: Vec<_> = value.split(", ").collect();
+    assert_eq!(metrics.len(), 5);
+    assert_eq!(metrics[0], "db;dur=12.000");
+    assert_eq!(metrics[1], "cache;desc=\"redis \\\"main\\\"\";dur=0.500");
+    assert_eq!(metrics[2], "miss");
+    assert!(metrics[3].starts_with("render;dur="));
+    assert!(metrics[4].starts_with("total;dur="));
+}
+
+#[kayrx::test]
+async fn test_server_timing_without_total() {
+    let mut srv = init_service(
+        App::new()
+            .wrap(ServerTimingHeader::new().total(false))
+            .service(web::resource("/").to(|| async { HttpResponse::Ok().finish() }))
+            .service(web::resource("/mark").to(|timing: ServerTiming| {
+                async move {
+                    timing.mark("bad name");
+                    HttpResponse::Ok().finish()
+                }
+            })),
+    )
+    .await;
+
+    let req = TestRequest::default().to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert!(resp.headers().get("server-timing").is_none());
+
+    let req = TestRequest::with_uri("/mark").to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.headers().get("server-timing").unwrap(), "bad_name");
+}
+
+#[kayrx::test]
+async fn test_detached_handle() {
+    let mut srv = init_service(App::new().service(web::resource("/").to(index))).await;
+
+    let req = TestRequest::default().to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert!(resp.status().is_success());
+    assert!(resp.headers().get("server-timing").is_none());
+}
@@ -0,0 +1,119 @@
+use bytes::Bytes;
+use kayrx::web::client::Client;
+use kayrx::web::middleware::Trace;
+use kayrx::web::test::{self, call_service, init_service, read_body, TestRequest};
+use kayrx::web::trace::{Propagation, TraceContext};
+use kayrx::web::{self, App, HttpRequest, HttpResponse};
+
+const TRACEPARENT: &str = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+
+async fn index(ctx: TraceContext) -> HttpResponse {
+    assert_eq!(TraceContext::current(), Some(ctx.clone()));
+    HttpResponse::Ok().body(format!(
+        "{:032x} {:016x}",
+        ctx.trace_id(),
+        ctx.parent_id().unwrap_or(0)
+    ))
+}
+
+#[test]
+fn test_parse_traceparent() {
+    let ctx = TraceContext::parse_traceparent(TRACEPARENT).unwrap();
+    assert_eq!(ctx.trace_id(), 0x0af7651916cd43dd8448eb211c80319c);
+    assert_eq!(ctx.span_id(), 0xb7ad6b7169203331);
+    assert!(ctx.is_sampled());
+    assert_eq!(ctx.traceparent(), TRACEPARENT);
+
+    let child = ctx.child();
+    assert_eq!(child.trace_id(), ctx.trace_id());
+    assert_eq!(child.parent_id(), Some(ctx.span_id()));
+    assert_ne!(child.span_id(), ctx.span_id());
+
+    // zero ids, bad version, extra fields in version 00
+    assert!(TraceContext::parse_traceparent(
+        "00-00000000000000000000000000000000-b7ad6b7169203331-01"
+    )
+    .is_none());
+    assert!(TraceContext::parse_traceparent(
+        "ff-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01"
+    )
+    .is_none());
+    assert!(TraceContext::parse_traceparent(&format!("{}-00", TRACEPARENT)).is_none());
+    assert!(TraceContext::parse_traceparent(
+        "01-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-00-future"
+    )
+    .is_some());
+
+    let ctx = TraceContext::parse_b3("80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-0")
+        .unwrap();
+    assert!(!ctx.is_sampled());
+    assert_eq!(ctx.b3(), "80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-0");
+    assert!(TraceContext::parse_b3("a3ce929d0e0e4736-00f067aa0ba902b7").is_some());
+}
+
+#[kayrx::test]
+async fn test_trace_middleware() {
+    let mut srv = init_service(
+        App::new()
+            .wrap(Trace::new().response_header(true))
+            .service(web::resource("/").to(index)),
+    )
+    .await;
+
+    let req = TestRequest::default()
+        .header("traceparent", TRACEPARENT)
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    let header = resp.headers().get("traceparent").unwrap().to_str().unwrap();
+    let ctx = TraceContext::parse_traceparent(header).unwrap();
+    assert_eq!(ctx.trace_id(), 0x0af7651916cd43dd8448eb211c80319c);
+    assert_ne!(ctx.span_id(), 0xb7ad6b7169203331);
+    assert_eq!(
+        read_body(resp).await,
+        Bytes::from_static(b"0af7651916cd43dd8448eb211c80319c b7ad6b7169203331")
+    );
+
+    // new trace without incoming context
+    let req = TestRequest::default().to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert!(resp.headers().contains_key("traceparent"));
+    assert_eq!(TraceContext::current(), None);
+}
+
+#[kayrx::test]
+async fn test_client_propagation() {
+    let srv = test::start(|| {
+        App::new().default_service(web::to(|req: HttpRequest| {
+            let value = |name| {
+                req.headers()
+                    .get(name)
+                    .map(|v| v.to_str().unwrap().to_owned())
+                    .unwrap_or_default()
+            };
+            HttpResponse::Ok().body(format!("{} {}", value("traceparent"), value("b3")))
+        }))
+    });
+
+    let client = Client::build()
+        .trace_propagation(Propagation::All)
+        .finish();
+    let ctx = TraceContext::parse_traceparent(TRACEPARENT).unwrap();
+    let url = srv.url("/");
+    let mut res = ctx
+        .scope(async { client.get(url).send().await })
+        .await
+        .unwrap();
+    let body = res.body().await.unwrap();
+    let body = std::str::from_utf8(&body).unwrap();
+    let mut parts = body.split(' ');
+
+    let sent = TraceContext::parse_traceparent(parts.next().unwrap()).unwrap();
+    assert_eq!(sent.trace_id(), 0x0af7651916cd43dd8448eb211c80319c);
+    assert_ne!(sent.span_id(), 0xb7ad6b7169203331);
+    let b3 = TraceContext::parse_b3(parts.next().unwrap()).unwrap();
+    assert_eq!(b3.span_id(), sent.span_id());
+
+    // no current context, no headers
+    let mut res = client.get(srv.url("/")).send().await.unwrap();
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b" "));
+}
@@ -1,19 +1,33 @@
+mod admission;
 mod app_service;
+mod batch;
 // mod app;
 mod client;
 // mod config;
 mod data;
+mod dynamic;
 mod extract;
-mod file;
+mod files;
+mod graphql;
+mod health;
 mod middleware;
+mod module;
 mod multipart;
+mod path_decoding;
 // mod request;
 // mod resource;
 mod responder;
 mod route;
+mod route_table;
+mod server;
 mod service;
 mod scope;
+mod seo;
+mod sse;
+mod server_config;
 mod test;
 mod types;
+mod upload;
+mod ws;
 
 
@@ -0,0 +1,81 @@
+use kayrx::http::{header, Response as HttpResponse, StatusCode};
+use kayrx::service::Service;
+use kayrx::web::test::{init_service, read_body, TestRequest};
+use kayrx::web::{self, middleware, App, Module, ModuleConfig};
+
+struct Users;
+
+impl Module for Users {
+    fn name(&self) -> &str {
+        "users"
+    }
+
+    fn prefix(&self) -> &str {
+        "/users"
+    }
+
+    fn configure(&self, cfg: &mut ModuleConfig) {
+        cfg.data(10usize);
+        cfg.route(
+            "/count",
+            web::get().to(|count: web::Data<usize>| async move { count.to_string() }),
+        );
+        cfg.wrap(middleware::DefaultHeaders::new().header(header::CACHE_CONTROL, "no-store"));
+    }
+}
+
+struct Root;
+
+impl Module for Root {
+    fn name(&self) -> &str {
+        "root"
+    }
+
+    fn configure(&self, cfg: &mut ModuleConfig) {
+        cfg.route("/ping", web::get().to(|| HttpResponse::Ok()));
+    }
+}
+
+#[kayrx::test]
+async fn test_plug() {
+    let mut srv = init_service(App::new().plug(Users).plug(Root)).await;
+
+    let req = TestRequest::with_uri("/users/count").to_request();
+    let resp = srv.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers().get(header::CACHE_CONTROL).unwrap(),
+        "no-store"
+    );
+    assert_eq!(read_body(resp).await, "10");
+
+    let req = TestRequest::with_uri("/ping").to_request();
+    let resp = srv.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().get(header::CACHE_CONTROL).is_none());
+}
+
+#[kayrx::test]
+async fn test_plug_at() {
+    let mut srv = init_service(App::new().plug_at("/v2", Users)).await;
+
+    let req = TestRequest::with_uri("/v2/count").to_request();
+    let resp = srv.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let req = TestRequest::with_uri("/users/count").to_request();
+    let resp = srv.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[test]
+#[should_panic(expected = "already registered")]
+fn test_duplicate_name() {
+    let _ = App::new().plug(Users).plug_at("/v2", Users);
+}
+
+#[test]
+#[should_panic(expected = "already used")]
+fn test_duplicate_prefix() {
+    let _ = App::new().plug(Users).plug_at("/users", Root);
+}
@@ -0,0 +1,94 @@
+use kayrx::http::StatusCode;
+use kayrx::web::dev::{EncodedSlash, PathDecoding};
+use kayrx::web::test::{call_service, init_service, read_body, TestRequest};
+use kayrx::web::{self, types, App, HttpResponse};
+
+async fn echo(path: types::Path<String>) -> HttpResponse {
+    HttpResponse::Ok().body(path.into_inner())
+}
+
+#[kayrx::test]
+async fn test_default_decoding() {
+    let mut srv = init_service(
+        App::new().service(web::resource("/files/{name}").route(web::get().to(echo))),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("/files/a%20b").to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(read_body(resp).await, "a b");
+
+    // encoded slash is not a segment separator
+    let req = TestRequest::with_uri("/files/a%2Fb").to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(read_body(resp).await, "a%2Fb");
+
+    let req = TestRequest::with_uri("/files/%FF").to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[kayrx::test]
+async fn test_reject_encoded_slash() {
+    let mut srv = init_service(
+        App::new()
+            .path_decoding(PathDecoding::new().encoded_slash(EncodedSlash::Reject))
+            .service(web::resource("/files/{name}").route(web::get().to(echo))),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("/files/a%2Fb").to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let req = TestRequest::with_uri("/files/a%2fb").to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let req = TestRequest::with_uri("/files/ab").to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[kayrx::test]
+async fn test_decode_after_match() {
+    let decoding = PathDecoding::new().decode_after_match();
+    let mut srv = init_service(
+        App::new()
+            .path_decoding(decoding)
+            .service(web::resource("/files/{name}").route(web::get().to(echo)))
+            .service(
+                web::resource("/objects/{key}")
+                    .path_decoding(decoding.encoded_slash(EncodedSlash::Decode))
+                    .route(web::get().to(echo)),
+            )
+            .service(
+                web::scope("/strict").service(
+                    web::resource("/{key}")
+                        .path_decoding(decoding.encoded_slash(EncodedSlash::Reject))
+                        .route(web::get().to(echo)),
+                ),
+            ),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("/files/a%20b%2Fc").to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(read_body(resp).await, "a b%2Fc");
+
+    let req = TestRequest::with_uri("/objects/a%2Fb").to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(read_body(resp).await, "a/b");
+
+    let req = TestRequest::with_uri("/strict/a%2Fb").to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let req = TestRequest::with_uri("/files/%FF").to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
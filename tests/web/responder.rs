@@ -199,4 +199,238 @@
             res.headers().get(CONTENT_TYPE).unwrap(),
             HeaderValue::from_static("json")
         );
-    }
\ No newline at end of file
+    }
+    #[kayrx::test]
+    async fn test_attachment_responder() {
+        use kayrx::http::header::CONTENT_DISPOSITION;
+
+        let req = TestRequest::default().to_http_request();
+        let res = Attachment::new("%PDF")
+            .filename("report.pdf")
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.body().bin_ref(), b"%PDF");
+        assert_eq!(
+            res.headers().get(CONTENT_TYPE).unwrap(),
+            HeaderValue::from_static("application/pdf")
+        );
+        assert_eq!(
+            res.headers().get(CONTENT_DISPOSITION).unwrap(),
+            HeaderValue::from_static("attachment; filename=\"report.pdf\"")
+        );
+
+        let res = Attachment::new(Bytes::from_static(b"data"))
+            .filename("отчёт \"1\".csv")
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(
+            res.headers().get(CONTENT_DISPOSITION).unwrap(),
+            HeaderValue::from_static(
+                "attachment; filename=\"_____ \\\"1\\\".csv\"; \
+                 filename*=UTF-8''%D0%BE%D1%82%D1%87%D1%91%D1%82%20%221%22.csv"
+            )
+        );
+
+        let res = Attachment::new("<svg/>")
+            .filename("../logo.svg")
+            .content_type(mime::IMAGE_SVG)
+            .inline(true)
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(
+            res.headers().get(CONTENT_TYPE).unwrap(),
+            HeaderValue::from_static("image/svg+xml")
+        );
+        assert_eq!(
+            res.headers().get(CONTENT_DISPOSITION).unwrap(),
+            HeaderValue::from_static("inline; filename=\".._logo.svg\"")
+        );
+
+        let res = Attachment::new(vec![0u8; 4]).respond_to(&req).await.unwrap();
+        assert_eq!(
+            res.headers().get(CONTENT_TYPE).unwrap(),
+            HeaderValue::from_static("application/octet-stream")
+        );
+        assert_eq!(
+            res.headers().get(CONTENT_DISPOSITION).unwrap(),
+            HeaderValue::from_static("attachment")
+        );
+    }
+
+    #[kayrx::test]
+    async fn test_redirect_responder() {
+        use kayrx::http::header::LOCATION;
+
+        let req = TestRequest::with_uri("/account/login?next=%2F")
+            .header("host", "example.com")
+            .to_http_request();
+
+        let res = Redirect::to("/index.html").respond_to(&req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(
+            res.headers().get(LOCATION).unwrap(),
+            HeaderValue::from_static("/index.html")
+        );
+
+        let res = Redirect::see_other("form")
+            .preserve_query(true)
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            res.headers().get(LOCATION).unwrap(),
+            HeaderValue::from_static("/account/form?next=%2F")
+        );
+
+        let res = Redirect::permanent("../?a=1")
+            .preserve_query(true)
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(
+            res.headers().get(LOCATION).unwrap(),
+            HeaderValue::from_static("/?a=1")
+        );
+
+        let res = Redirect::with_status("https://kayrx.rs/", StatusCode::FOUND)
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::FOUND);
+        assert_eq!(
+            res.headers().get(LOCATION).unwrap(),
+            HeaderValue::from_static("https://kayrx.rs/")
+        );
+
+        // spoofed host does not leak into location
+        let req = TestRequest::with_uri("/account/login")
+            .header("host", "evil.example")
+            .to_http_request();
+        let res = Redirect::to("form").respond_to(&req).await.unwrap();
+        assert_eq!(
+            res.headers().get(LOCATION).unwrap(),
+            HeaderValue::from_static("/account/form")
+        );
+    }
+
+#[derive(Debug, derive_more::Display, Responder)]
+#[responder(error, content_type = "text/plain")]
+enum ApiError {
+    #[display(fmt = "not found")]
+    #[responder(status = 404)]
+    NotFound,
+    #[display(fmt = "conflict: {}", _0)]
+    #[responder(status = 409, content_type = "text/html")]
+    Conflict(String),
+    #[display(fmt = "internal")]
+    Internal { code: u32 },
+}
+
+#[derive(serde::Serialize, Responder)]
+#[responder(json, status = 201)]
+struct Created {
+    id: u32,
+}
+
+#[derive(derive_more::Display, Responder)]
+#[display(fmt = "hello {}", _0)]
+struct Hello(&'static str);
+
+#[kayrx::test]
+async fn test_derive_responder() {
+    let req = TestRequest::default().to_http_request();
+
+    let resp = ApiError::NotFound.respond_to(&req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    assert_eq!(resp.body().bin_ref(), b"not found");
+    assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), "text/plain");
+
+    let resp = ApiError::Conflict("id".to_owned())
+        .respond_to(&req)
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    assert_eq!(resp.body().bin_ref(), b"conflict: id");
+    assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), "text/html");
+
+    let err: Error = ApiError::Internal { code: 1 }.into();
+    assert_eq!(
+        err.as_response_error().status_code(),
+        StatusCode::INTERNAL_SERVER_ERROR
+    );
+
+    let resp = Created { id: 1 }.respond_to(&req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    assert_eq!(resp.body().bin_ref(), b"{\"id\":1}");
+    assert_eq!(
+        resp.headers().get(CONTENT_TYPE).unwrap(),
+        "application/json"
+    );
+
+    let resp = Hello("world").respond_to(&req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.body().bin_ref(), b"hello world");
+    assert_eq!(
+        resp.headers().get(CONTENT_TYPE).unwrap(),
+        "text/plain; charset=utf-8"
+    );
+}
+
+#[kayrx::test]
+async fn test_json_streaming_responder() {
+    use kayrx::web::test::{call_service, read_body};
+    use kayrx::web::types::{Json, JsonStreaming};
+
+    let mut srv = init_service(App::new().service(web::resource("/").to(
+        || async {
+            let items: Vec<u32> = (0..10_000).collect();
+            let res: JsonStreaming<Vec<u32>> = Json::streaming(items).chunk_size(1024);
+            res
+        },
+    )))
+    .await;
+
+    let req = TestRequest::with_uri("/").to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers().get(CONTENT_TYPE).unwrap(),
+        "application/json"
+    );
+
+    let body = read_body(resp).await;
+    let items: Vec<u32> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(items.len(), 10_000);
+    assert_eq!(items[9_999], 9_999);
+}
+
+#[kayrx::test]
+async fn test_streaming_responder() {
+    use futures::stream;
+    use kayrx::web::test::{call_service, read_body};
+    use kayrx::web::Streaming;
+
+    let mut srv = init_service(App::new().service(web::resource("/").to(|| async {
+        Streaming::new(stream::iter(
+            (0..3).map(|i| Ok::<_, Error>(Bytes::from(format!("chunk{};", i)))),
+        ))
+        .status(StatusCode::ACCEPTED)
+        .content_type("text/plain")
+    })))
+    .await;
+
+    let req = TestRequest::with_uri("/").to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+    assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), "text/plain");
+    assert_eq!(
+        read_body(resp).await,
+        Bytes::from_static(b"chunk0;chunk1;chunk2;")
+    );
+}
@@ -85,4 +85,104 @@ async fn test_route() {
 
     let body = read_body(resp).await;
     assert_eq!(body, Bytes::from_static(b"{\"name\":\"test\"}"));
-}
\ No newline at end of file
+}
+#[kayrx::test]
+async fn test_route_concurrency_limit() {
+    use kayrx::service::Service;
+
+    let mut srv = init_service(
+        App::new().service(
+            web::resource("/report").route(web::get().concurrency_limit(1, 1).to(|| {
+                async {
+                    delay_for(Duration::from_millis(50)).await;
+                    HttpResponse::Ok()
+                }
+            })),
+        ),
+    )
+    .await;
+
+    let req = || TestRequest::with_uri("/report").to_request();
+    let fut1 = srv.call(req());
+    let fut2 = srv.call(req());
+    let fut3 = srv.call(req());
+
+    let resp = fut3.await.unwrap();
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(resp.headers().get("retry-after").unwrap(), "1");
+
+    assert_eq!(fut1.await.unwrap().status(), StatusCode::OK);
+    assert_eq!(fut2.await.unwrap().status(), StatusCode::OK);
+
+    // queue is drained
+    let resp = call_service(&mut srv, req()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[kayrx::test]
+async fn test_route_async_guard() {
+    use kayrx::web::{guard, HttpRequest};
+
+    struct Tenant(String);
+
+    let mut srv = init_service(
+        App::new().service(
+            web::resource("/test")
+                .route(
+                    web::get()
+                        .async_guard(guard::fn_async_guard(|req| {
+                            let req = req.clone();
+                            async move {
+                                delay_for(Duration::from_millis(10)).await;
+                                let tenant = req
+                                    .headers()
+                                    .get("x-token")
+                                    .and_then(|val| val.to_str().ok())
+                                    .filter(|val| val.starts_with("tenant-"))
+                                    .map(|val| val[7..].to_owned());
+                                match tenant {
+                                    Some(tenant) => {
+                                        req.extensions_mut().insert(Tenant(tenant));
+                                        true
+                                    }
+                                    None => false,
+                                }
+                            }
+                        }))
+                        .to(|req: HttpRequest| {
+                            async move {
+                                let tenant = req.extensions().get::<Tenant>().unwrap().0.clone();
+                                HttpResponse::Ok().body(tenant)
+                            }
+                        }),
+                )
+                .route(web::get().to(|| HttpResponse::Unauthorized()))
+                .route(
+                    web::post()
+                        .async_guard(guard::fn_async_guard(|_| async { false }))
+                        .to(|| HttpResponse::Ok()),
+                ),
+        ),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("/test")
+        .header("x-token", "tenant-a")
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(read_body(resp).await, Bytes::from_static(b"a"));
+
+    // rejected by async guard, next route is selected
+    let req = TestRequest::with_uri("/test")
+        .header("x-token", "invalid")
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    let req = TestRequest::with_uri("/test")
+        .method(Method::POST)
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+}
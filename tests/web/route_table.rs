@@ -0,0 +1,158 @@
+use kayrx::http::{Method, Response as HttpResponse, StatusCode};
+use kayrx::service::Service;
+use kayrx::web::test::{init_service, read_body, TestRequest};
+use kayrx::web::{self, routes, App, HttpRequest};
+
+async fn index() -> &'static str {
+    "index"
+}
+
+async fn users() -> &'static str {
+    "users"
+}
+
+async fn create_user() -> HttpResponse {
+    HttpResponse::Created().finish()
+}
+
+async fn user(req: HttpRequest) -> String {
+    format!("user {}", req.match_info().get("id").unwrap())
+}
+
+async fn user_me() -> &'static str {
+    "me"
+}
+
+async fn file(req: HttpRequest) -> String {
+    format!("file {}", req.match_info().get("name").unwrap())
+}
+
+#[kayrx::test]
+async fn test_routes_static() {
+    let mut srv = init_service(App::new().service(routes! {
+        GET "/" => index,
+        GET "/users" => users,
+        POST "/users" => create_user,
+        GET "/about" => || async { "about" },
+    }))
+    .await;
+
+    let req = TestRequest::with_uri("/").to_request();
+    assert_eq!(read_body(srv.call(req).await.unwrap()).await, "index");
+
+    let req = TestRequest::with_uri("/users").to_request();
+    assert_eq!(read_body(srv.call(req).await.unwrap()).await, "users");
+
+    let req = TestRequest::with_uri("/about").to_request();
+    assert_eq!(read_body(srv.call(req).await.unwrap()).await, "about");
+
+    let req = TestRequest::with_uri("/users")
+        .method(Method::POST)
+        .to_request();
+    let resp = srv.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::CREATED);
+
+    let req = TestRequest::with_uri("/users")
+        .method(Method::DELETE)
+        .to_request();
+    let resp = srv.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+    let req = TestRequest::with_uri("/users/").to_request();
+    let resp = srv.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[kayrx::test]
+async fn test_routes_dynamic() {
+    let mut srv = init_service(App::new().service(routes! {
+        GET "/users/{id}" => user,
+        GET "/users/me" => user_me,
+        GET "/{name}.txt" => file,
+    }))
+    .await;
+
+    let req = TestRequest::with_uri("/users/10").to_request();
+    assert_eq!(read_body(srv.call(req).await.unwrap()).await, "user 10");
+
+    // static path takes precedence
+    let req = TestRequest::with_uri("/users/me").to_request();
+    assert_eq!(read_body(srv.call(req).await.unwrap()).await, "me");
+
+    let req = TestRequest::with_uri("/readme.txt").to_request();
+    assert_eq!(read_body(srv.call(req).await.unwrap()).await, "file readme");
+
+    let req = TestRequest::with_uri("/users/10")
+        .method(Method::POST)
+        .to_request();
+    let resp = srv.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+    let req = TestRequest::with_uri("/posts/10").to_request();
+    let resp = srv.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[kayrx::test]
+async fn test_routes_many() {
+    let table = routes! {
+        GET "/a" => || async { "a" },
+        GET "/b" => || async { "b" },
+        GET "/c" => || async { "c" },
+        GET "/d" => || async { "d" },
+        GET "/e" => || async { "e" },
+        GET "/f" => || async { "f" },
+        GET "/g" => || async { "g" },
+        GET "/h" => || async { "h" },
+        GET "/i" => || async { "i" },
+        GET "/j" => || async { "j" },
+        GET "/k/{id}" => || async { "k" },
+    };
+    assert_eq!(table.len(), 11);
+
+    let mut srv = init_service(App::new().service(table)).await;
+    for path in &["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"] {
+        let req = TestRequest::with_uri(&format!("/{}", path)).to_request();
+        assert_eq!(read_body(srv.call(req).await.unwrap()).await, path.as_bytes());
+    }
+    let req = TestRequest::with_uri("/k/1").to_request();
+    assert_eq!(read_body(srv.call(req).await.unwrap()).await, "k");
+}
+
+#[kayrx::test]
+async fn test_routes_default_service() {
+    let mut srv = init_service(
+        App::new()
+            .service(web::resource("/resource").to(|| async { "resource" }))
+            .service(routes! {
+                GET "/table" => || async { "table" },
+            })
+            .default_service(web::to(|| HttpResponse::Gone())),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("/resource").to_request();
+    assert_eq!(read_body(srv.call(req).await.unwrap()).await, "resource");
+
+    let req = TestRequest::with_uri("/table").to_request();
+    assert_eq!(read_body(srv.call(req).await.unwrap()).await, "table");
+
+    let req = TestRequest::with_uri("/unknown").to_request();
+    let resp = srv.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::GONE);
+}
+
+#[kayrx::test]
+async fn test_routes_in_scope() {
+    let mut srv = init_service(App::new().service(web::scope("/api").service(routes! {
+        GET "/users" => users,
+        GET "/users/{id}" => user,
+    })))
+    .await;
+
+    let req = TestRequest::with_uri("/api/users").to_request();
+    assert_eq!(read_body(srv.call(req).await.unwrap()).await, "users");
+
+    let req = TestRequest::with_uri("/api/users/5").to_request();
+    assert_eq!(read_body(srv.call(req).await.unwrap()).await, "user 5");
+}
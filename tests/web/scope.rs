@@ -475,6 +475,82 @@ async fn test_override_app_data() {
     assert_eq!(resp.status(), StatusCode::OK);
 }
 
+#[kayrx::test]
+async fn test_nested_scope_data() {
+    let mut srv = init_service(App::new().app_data(1u8).data(1usize).service(
+        web::scope("app").data(10usize).data(10u32).service(
+            web::scope("nested").data(100u32).route(
+                "/t",
+                web::get().to(
+                    |req: HttpRequest, size: web::Data<usize>, num: web::Data<u32>| {
+                        // innermost data wins, outer data is still visible
+                        assert_eq!(**size, 10);
+                        assert_eq!(**num, 100);
+                        assert_eq!(req.app_data::<u8>(), Some(&1));
+                        HttpResponse::Ok()
+                    },
+                ),
+            ),
+        ),
+    ))
+    .await;
+
+    let req = TestRequest::with_uri("/app/nested/t").to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[kayrx::test]
+async fn test_middleware_order() {
+    let mut srv = init_service(
+        App::new()
+            .wrap_fn(|req, srv| {
+                let fut = srv.call(req);
+                async move {
+                    let mut res = fut.await?;
+                    let value = format!(
+                        "app,{}",
+                        res.headers().get("x-order").unwrap().to_str().unwrap()
+                    );
+                    res.headers_mut().insert(
+                        header::HeaderName::from_static("x-order"),
+                        HeaderValue::from_str(&value).unwrap(),
+                    );
+                    Ok(res)
+                }
+            })
+            .service(
+                web::scope("app")
+                    .wrap_fn(|req, srv| {
+                        let fut = srv.call(req);
+                        async move {
+                            let mut res = fut.await?;
+                            res.headers_mut().insert(
+                                header::HeaderName::from_static("x-order"),
+                                HeaderValue::from_static("scope"),
+                            );
+                            Ok(res)
+                        }
+                    })
+                    .route("/test", web::get().to(|| HttpResponse::Ok())),
+            )
+            .route(
+                "/other",
+                web::get().to(|| HttpResponse::Ok().header("x-order", "none").finish()),
+            ),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("/app/test").to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.headers().get("x-order").unwrap(), "app,scope");
+
+    // scope middleware is not applied outside of the scope
+    let req = TestRequest::with_uri("/other").to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.headers().get("x-order").unwrap(), "app,none");
+}
+
 #[kayrx::test]
 async fn test_scope_config() {
     let mut srv =
@@ -558,4 +634,82 @@ async fn test_url_for_nested() {
         body,
         Bytes::from_static(b"http://localhost:8080/a/b/c/12345")
     );
-}
\ No newline at end of file
+}
+#[kayrx::test]
+async fn test_url_for_encoded() {
+    let mut srv = init_service(App::new().service(web::scope("/{tenant}").service(
+        web::resource("/user/{id}").name("user_detail").route(web::get().to(
+            |req: HttpRequest| {
+                async move {
+                    HttpResponse::Ok().body(format!(
+                        "{}",
+                        req.url_for("user_detail", &["a b", "1?x=#2"]).unwrap()
+                    ))
+                }
+            },
+        )),
+    )))
+    .await;
+
+    let req = TestRequest::with_uri("/t/user/1").to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = read_body(resp).await;
+    assert_eq!(
+        body,
+        Bytes::from_static(b"http://localhost:8080/a%20b/user/1%3Fx=%232")
+    );
+}
+
+#[kayrx::test]
+async fn test_subrequest() {
+    let mut srv = init_service(
+        App::new()
+            .service(
+                web::scope("/fragments")
+                    .wrap(DefaultHeaders::new().header(
+                        header::HeaderName::from_static("x-fragment"),
+                        HeaderValue::from_static("1"),
+                    ))
+                    .route(
+                        "/{name}",
+                        web::get().to(|req: HttpRequest| {
+                            let name = req.match_info().get("name").unwrap().to_owned();
+                            HttpResponse::Ok().body(name)
+                        }),
+                    ),
+            )
+            .route(
+                "/page",
+                web::get().to(|req: HttpRequest| async move {
+                    let mut body = Vec::new();
+                    for name in &["header", "footer"] {
+                        let res = req
+                            .subrequest(&format!("/fragments/{}", name), Method::GET)
+                            .await?;
+                        assert_eq!(res.headers().get("x-fragment").unwrap(), "1");
+                        body.extend_from_slice(&read_body(res).await);
+                    }
+                    Ok::<_, kayrx::http::error::Error>(HttpResponse::Ok().body(body))
+                }),
+            )
+            .route(
+                "/invalid",
+                web::get().to(|req: HttpRequest| async move {
+                    req.subrequest("fragments", Method::GET).await?;
+                    Ok::<_, kayrx::http::error::Error>(HttpResponse::Ok().finish())
+                }),
+            ),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("/page").to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().get("x-fragment").is_none());
+    assert_eq!(read_body(resp).await, Bytes::from_static(b"headerfooter"));
+
+    let req = TestRequest::with_uri("/invalid").to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
@@ -0,0 +1,183 @@
+use std::io::Read;
+
+use bytes::Bytes;
+use flate2::read::GzDecoder;
+use futures::stream;
+use kayrx::http::header::{CONTENT_ENCODING, CONTENT_TYPE};
+use kayrx::http::StatusCode;
+use kayrx::web::seo::{self, ChangeFreq, Seo, SitemapUrl};
+use kayrx::web::test::{self, TestRequest};
+use kayrx::web::App;
+
+#[kayrx::test]
+async fn test_robots_txt() {
+    let mut srv = test::init_service(App::new().service(
+        Seo::new()
+            .robots("User-agent: *\nDisallow: /admin/")
+            .sitemap(|| stream::iter(vec![SitemapUrl::new("/")])),
+    ))
+    .await;
+
+    let req = TestRequest::with_uri("/robots.txt")
+        .header("host", "example.com")
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers().get(CONTENT_TYPE).unwrap(),
+        "text/plain; charset=utf-8"
+    );
+    assert_eq!(
+        test::read_body(resp).await,
+        Bytes::from_static(
+            b"User-agent: *\nDisallow: /admin/\nSitemap: http://example.com/sitemap.xml\n"
+        )
+    );
+}
+
+#[kayrx::test]
+async fn test_sitemap_not_configured() {
+    let mut srv = test::init_service(App::new().service(Seo::new())).await;
+
+    let req = TestRequest::with_uri("/robots.txt").to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(
+        test::read_body(resp).await,
+        Bytes::from_static(b"User-agent: *\nAllow: /\n")
+    );
+
+    let req = TestRequest::with_uri("/sitemap.xml").to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[kayrx::test]
+async fn test_sitemap_xml() {
+    let mut srv = test::init_service(App::new().service(Seo::new().sitemap(|| {
+        stream::iter(vec![
+            SitemapUrl::new("/")
+                .changefreq(ChangeFreq::Daily)
+                .priority(1.0),
+            SitemapUrl::new("/search?q=a&b")
+                .lastmod("2020-01-31"),
+            SitemapUrl::new("https://cdn.example.com/file"),
+        ])
+    })))
+    .await;
+
+    let req = TestRequest::with_uri("/sitemap.xml")
+        .header("host", "example.com")
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = test::read_body(resp).await;
+    let body = std::str::from_utf8(&body).unwrap();
+    assert!(body.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset"));
+    assert!(body.contains(
+        "  <url>\n    <loc>http://example.com/</loc>\n    \
+         <changefreq>daily</changefreq>\n    <priority>1.0</priority>\n  </url>\n"
+    ));
+    assert!(body.contains("<loc>http://example.com/search?q=a&amp;b</loc>"));
+    assert!(body.contains("<lastmod>2020-01-31</lastmod>"));
+    assert!(body.contains("<loc>https://cdn.example.com/file</loc>"));
+    assert!(body.ends_with("</urlset>\n"));
+}
+
+#[kayrx::test]
+async fn test_sitemap_cached_gzip() {
+    let mut srv = test::init_service(
+        App::new().service(
+            Seo::new()
+                .base_url("http://example.com/")
+                .sitemap(|| stream::iter(vec![SitemapUrl::new("/a")])),
+        ),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("/sitemap.xml")
+        .header("host", "example.com")
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    let plain = test::read_body(resp).await;
+
+    let req = TestRequest::with_uri("/sitemap.xml")
+        .header("host", "example.com")
+        .header("accept-encoding", "gzip, deflate")
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.headers().get(CONTENT_ENCODING).unwrap(), "gzip");
+    let body = test::read_body(resp).await;
+
+    let mut dec = GzDecoder::new(&body[..]);
+    let mut unzipped = Vec::new();
+    dec.read_to_end(&mut unzipped).unwrap();
+    assert_eq!(Bytes::from(unzipped), plain);
+
+    // gzip is not acceptable
+    let req = TestRequest::with_uri("/sitemap.xml")
+        .header("accept-encoding", "gzip;q=0, *")
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert!(resp.headers().get(CONTENT_ENCODING).is_none());
+    assert_eq!(test::read_body(resp).await, plain);
+
+    let req = TestRequest::with_uri("/sitemap.xml")
+        .header("accept-encoding", "br, *;q=0.5")
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.headers().get(CONTENT_ENCODING).unwrap(), "gzip");
+}
+
+#[kayrx::test]
+async fn test_sitemap_host() {
+    let sitemap = || stream::iter(vec![SitemapUrl::new("/a")]);
+    let mut srv = test::init_service(App::new().service(Seo::new().sitemap(sitemap))).await;
+
+    // without base url sitemap is built for each request
+    for host in &["example.com", "evil.com"] {
+        let req = TestRequest::with_uri("/sitemap.xml")
+            .header("host", *host)
+            .header("accept-encoding", "gzip")
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert!(resp.headers().get(CONTENT_ENCODING).is_none());
+        let body = test::read_body(resp).await;
+        let loc = format!("<loc>http://{}/a</loc>", host);
+        assert!(std::str::from_utf8(&body).unwrap().contains(&loc));
+    }
+
+    // configured base url ignores host header
+    let mut srv = test::init_service(
+        App::new().service(Seo::new().base_url("https://example.com").sitemap(sitemap)),
+    )
+    .await;
+    let req = TestRequest::with_uri("/sitemap.xml")
+        .header("host", "evil.com")
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    let body = test::read_body(resp).await;
+    assert!(std::str::from_utf8(&body)
+        .unwrap()
+        .contains("<loc>https://example.com/a</loc>"));
+
+    let req = TestRequest::with_uri("/robots.txt")
+        .header("host", "evil.com")
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(
+        test::read_body(resp).await,
+        Bytes::from_static(b"User-agent: *\nAllow: /\nSitemap: https://example.com/sitemap.xml\n")
+    );
+}
+
+#[kayrx::test]
+async fn test_canonical_url() {
+    let req = TestRequest::default()
+        .header("host", "example.com")
+        .header("x-forwarded-proto", "https")
+        .to_http_request();
+    assert_eq!(
+        seo::canonical_url(&req, "/posts/1"),
+        "https://example.com/posts/1"
+    );
+}
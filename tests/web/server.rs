@@ -0,0 +1,636 @@
+use std::io::{Read, Write};
+use std::net::{self, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+use kayrx::fiber::System;
+use kayrx::server::{AcceptGate, Supervisor};
+use kayrx::timer::delay_for;
+use kayrx::web::dev::{ConnectionInfoConfig, InfoSource};
+use kayrx::web::{self, test, App, HttpRequest, HttpServer};
+
+#[kayrx::test]
+async fn test_graceful_shutdown_closes_idle_connections() {
+    let srv = test::start(|| App::new().service(web::resource("/").to(|| async { "ok" })));
+
+    let mut stream = TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+    let mut buf = [0; 1024];
+    let n = stream.read(&mut buf).unwrap();
+    assert!(buf[..n].starts_with(b"HTTP/1.1 200 OK\r\n"));
+
+    // idle keep-alive connection does not hold shutdown for `shutdown_timeout`
+    let start = Instant::now();
+    srv.stop().await;
+    assert!(start.elapsed() < Duration::from_secs(10));
+
+    // connection is closed by server
+    assert_eq!(stream.read(&mut buf).unwrap_or(0), 0);
+}
+
+#[test]
+fn test_connection_info_config() {
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let sys = System::new("test");
+        let tcp = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = tcp.local_addr().unwrap();
+
+        HttpServer::new(|| {
+            App::new().service(web::resource("/").to(|req: HttpRequest| {
+                let info = req.connection_info();
+                let body = format!("{}://{}", info.scheme(), info.host());
+                async move { body }
+            }))
+        })
+        .workers(1)
+        .disable_signals()
+        .server_hostname("www.rust-lang.org")
+        .connection_info(ConnectionInfoConfig::new(vec![
+            InfoSource::Forwarded,
+            InfoSource::Config,
+        ]))
+        .listen(tcp)
+        .unwrap()
+        .run();
+        tx.send((System::current(), addr)).unwrap();
+        sys.run()
+    });
+    let (sys, addr) = rx.recv().unwrap();
+
+    let request = |headers: &str| {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        write!(
+            stream,
+            "GET / HTTP/1.1\r\nHost: localhost\r\n{}Connection: close\r\n\r\n",
+            headers
+        )
+        .unwrap();
+        let mut res = String::new();
+        stream.read_to_string(&mut res).unwrap();
+        assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+        res.split("\r\n\r\n").nth(1).unwrap().to_owned()
+    };
+
+    // untrusted headers are ignored, server config is used
+    assert_eq!(
+        request("X-Forwarded-Proto: https\r\nX-Forwarded-Host: rust-lang.org\r\n"),
+        "http://www.rust-lang.org"
+    );
+    assert_eq!(
+        request("Forwarded: proto=https; host=rust-lang.org\r\n"),
+        "https://rust-lang.org"
+    );
+
+    sys.stop();
+}
+
+#[test]
+fn test_supervisor_restart() {
+    let (tx, rx) = mpsc::channel();
+    let (ev_tx, ev_rx) = mpsc::channel();
+    let ev_tx = Mutex::new(ev_tx);
+    let apps = Arc::new(AtomicUsize::new(0));
+
+    let apps2 = apps.clone();
+    thread::spawn(move || {
+        let sys = System::new("test");
+        let tcp = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = tcp.local_addr().unwrap();
+
+        HttpServer::new(move || {
+            apps2.fetch_add(1, Ordering::SeqCst);
+            App::new()
+                .service(web::resource("/").to(|| async { "ok" }))
+                .service(web::resource("/panic").to(|| async {
+                    panic!("request processing failed");
+                    #[allow(unreachable_code)]
+                    "ok"
+                }))
+        })
+        .workers(1)
+        .disable_signals()
+        .supervisor(
+            Supervisor::new()
+                .threshold(2)
+                .backoff(Duration::from_millis(100), Duration::from_secs(1))
+                .on_restart(move |event| {
+                    let _ = ev_tx.lock().unwrap().send(event.clone());
+                }),
+        )
+        .listen(tcp)
+        .unwrap()
+        .run();
+        tx.send((System::current(), addr)).unwrap();
+        sys.run()
+    });
+    let (sys, addr) = rx.recv().unwrap();
+
+    let request = |path: &str| {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nConnection: close\r\n\r\n", path).unwrap();
+        let mut res = String::new();
+        let _ = stream.read_to_string(&mut res);
+        res
+    };
+
+    // single failure is below threshold
+    assert_eq!(request("/panic"), "");
+    assert!(request("/").starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(ev_rx.recv_timeout(Duration::from_millis(200)).is_err());
+
+    // second failure triggers delayed restart of application
+    assert_eq!(request("/panic"), "");
+    let event = ev_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(event.worker, 0);
+    assert_eq!(event.failures, 2);
+    assert_eq!(event.restarts, 1);
+    assert_eq!(event.delay, Duration::from_millis(100));
+
+    assert!(request("/").starts_with("HTTP/1.1 200 OK\r\n"));
+    assert_eq!(apps.load(Ordering::SeqCst), 2);
+
+    sys.stop();
+}
+
+#[test]
+fn test_background_tasks() {
+    struct Cancelled(mpsc::Sender<&'static str>);
+
+    impl Drop for Cancelled {
+        fn drop(&mut self) {
+            let _ = self.0.send("cancelled");
+        }
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let (ev_tx, ev_rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let sys = System::new("test");
+        let tcp = net::TcpListener::bind("127.0.0.1:0").unwrap();
+
+        let srv =
+            HttpServer::new(|| App::new().service(web::resource("/").to(|| async { "ok" })));
+        let shutdown = srv.shutdown_signal();
+        let ev_tx2 = ev_tx.clone();
+        let ev_tx3 = ev_tx.clone();
+        let srv = srv
+            .workers(1)
+            .disable_signals()
+            .background_timeout(1)
+            // task stops gracefully on shutdown signal
+            .spawn_background(async move {
+                let _ = ev_tx2.send("started");
+                shutdown.await;
+                let _ = ev_tx2.send("stopped");
+            })
+            // task ignores shutdown signal and gets cancelled on timeout
+            .spawn_background(async move {
+                let _guard = Cancelled(ev_tx3);
+                delay_for(Duration::from_secs(60)).await;
+            })
+            .listen(tcp)
+            .unwrap()
+            .run();
+
+        // task registered at runtime
+        srv.spawn_background(async move {
+            let _ = ev_tx.send("runtime");
+        });
+        tx.send((System::current(), srv)).unwrap();
+        sys.run()
+    });
+    let (sys, srv) = rx.recv().unwrap();
+
+    let mut events = vec![
+        ev_rx.recv_timeout(Duration::from_secs(5)).unwrap(),
+        ev_rx.recv_timeout(Duration::from_secs(5)).unwrap(),
+    ];
+    events.sort();
+    assert_eq!(events, vec!["runtime", "started"]);
+
+    // server waits for background tasks up to background timeout
+    let start = Instant::now();
+    futures::executor::block_on(srv.stop(true));
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(900));
+    assert!(elapsed < Duration::from_secs(5));
+
+    assert_eq!(ev_rx.try_recv().unwrap(), "stopped");
+    assert_eq!(
+        ev_rx.recv_timeout(Duration::from_secs(5)).unwrap(),
+        "cancelled"
+    );
+
+    sys.stop();
+}
+
+fn request(stream: &mut TcpStream) -> std::io::Result<usize> {
+    stream.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")?;
+    let mut buf = [0; 1024];
+    let n = stream.read(&mut buf)?;
+    assert!(buf[..n].starts_with(b"HTTP/1.1 200 OK\r\n"));
+    Ok(n)
+}
+
+#[test]
+fn test_listen_from_stream() {
+    let (tx, rx) = mpsc::channel();
+    let (conn_tx, conn_rx) = futures::channel::mpsc::unbounded();
+
+    thread::spawn(move || {
+        let sys = System::new("test");
+        HttpServer::new(|| App::new().service(web::resource("/").to(|| async { "ok" })))
+            .workers(1)
+            .disable_signals()
+            .listen_from_stream(conn_rx)
+            .unwrap()
+            .run();
+        tx.send(System::current()).unwrap();
+        sys.run()
+    });
+    let sys = rx.recv().unwrap();
+
+    // connections are accepted by the test instead of the server
+    let lst = net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = lst.local_addr().unwrap();
+    let mut stream = TcpStream::connect(addr).unwrap();
+    conn_tx.unbounded_send(lst.accept().map(|(io, _)| io)).unwrap();
+
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    request(&mut stream).unwrap();
+    request(&mut stream).unwrap();
+
+    sys.stop();
+}
+
+#[test]
+fn test_accept_gate() {
+    let overloaded = Arc::new(AtomicBool::new(false));
+    let workers = Arc::new(AtomicUsize::new(0));
+    let (tx, rx) = mpsc::channel();
+
+    let (overloaded2, workers2) = (overloaded.clone(), workers.clone());
+    thread::spawn(move || {
+        let sys = System::new("test");
+        let tcp = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = tcp.local_addr().unwrap();
+
+        let gate = AcceptGate::new(move |load| {
+            workers2.store(load.workers(), Ordering::SeqCst);
+            overloaded2.load(Ordering::SeqCst)
+        })
+        .interval(Duration::from_millis(10));
+
+        HttpServer::new(|| App::new().service(web::resource("/").to(|| async { "ok" })))
+            .workers(1)
+            .disable_signals()
+            .accept_gate(gate)
+            .listen(tcp)
+            .unwrap()
+            .run();
+        tx.send((System::current(), addr)).unwrap();
+        sys.run()
+    });
+    let (sys, addr) = rx.recv().unwrap();
+
+    // wait for the first load check
+    for _ in 0..100 {
+        if workers.load(Ordering::SeqCst) != 0 {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(workers.load(Ordering::SeqCst), 1);
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    request(&mut stream).unwrap();
+
+    // new connection waits in the backlog
+    overloaded.store(true, Ordering::SeqCst);
+    thread::sleep(Duration::from_millis(200));
+    let mut stream2 = TcpStream::connect(addr).unwrap();
+    stream2
+        .set_read_timeout(Some(Duration::from_millis(300)))
+        .unwrap();
+    assert!(request(&mut stream2).is_err());
+
+    // established connection is still served
+    request(&mut stream).unwrap();
+
+    overloaded.store(false, Ordering::SeqCst);
+    stream2
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    let mut buf = [0; 1024];
+    let n = stream2.read(&mut buf).unwrap();
+    assert!(buf[..n].starts_with(b"HTTP/1.1 200 OK\r\n"));
+
+    sys.stop();
+}
+
+fn start_limited(
+    lifetime: Option<Duration>,
+    max_idle: Option<Duration>,
+) -> (System, net::SocketAddr) {
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let sys = System::new("test");
+        let tcp = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = tcp.local_addr().unwrap();
+
+        let mut srv =
+            HttpServer::new(|| App::new().service(web::resource("/").to(|| async { "ok" })))
+                .workers(1)
+                .disable_signals()
+                .keep_alive(30)
+                .conn_jitter(0.0);
+        if let Some(dur) = lifetime {
+            srv = srv.conn_lifetime(dur);
+        }
+        if let Some(dur) = max_idle {
+            srv = srv.conn_max_idle(dur);
+        }
+        srv.listen(tcp).unwrap().run();
+        tx.send((System::current(), addr)).unwrap();
+        sys.run()
+    });
+    rx.recv().unwrap()
+}
+
+fn read_response(stream: &mut TcpStream) -> String {
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+    let mut buf = [0; 1024];
+    let n = stream.read(&mut buf).unwrap();
+    assert!(buf[..n].starts_with(b"HTTP/1.1 200 OK\r\n"));
+    String::from_utf8_lossy(&buf[..n]).to_lowercase()
+}
+
+#[test]
+fn test_conn_lifetime() {
+    let (sys, addr) = start_limited(Some(Duration::from_millis(300)), None);
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    assert!(!read_response(&mut stream).contains("connection: close"));
+
+    // idle connection is closed once lifetime is reached
+    let start = Instant::now();
+    let mut buf = [0; 1024];
+    assert_eq!(stream.read(&mut buf).unwrap_or(0), 0);
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    // new connection gets its own lifetime
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    assert!(!read_response(&mut stream).contains("connection: close"));
+
+    sys.stop();
+}
+
+#[test]
+fn test_conn_max_idle() {
+    let (sys, addr) = start_limited(None, Some(Duration::from_millis(200)));
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    read_response(&mut stream);
+
+    // idle connection is closed before keep-alive timeout
+    let start = Instant::now();
+    let mut buf = [0; 1024];
+    assert_eq!(stream.read(&mut buf).unwrap_or(0), 0);
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    sys.stop();
+}
+
+#[test]
+fn test_min_write_rate() {
+    const SIZE: usize = 32 * 1024 * 1024;
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let sys = System::new("test");
+        let tcp = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = tcp.local_addr().unwrap();
+
+        HttpServer::new(|| {
+            App::new().service(
+                web::resource("/")
+                    .to(|| async { web::HttpResponse::Ok().body(vec![b'x'; SIZE]) }),
+            )
+        })
+        .workers(1)
+        .disable_signals()
+        .min_write_rate(1024 * 1024, Duration::from_millis(200))
+        .listen(tcp)
+        .unwrap()
+        .run();
+        tx.send((System::current(), addr)).unwrap();
+        sys.run()
+    });
+    let (sys, addr) = rx.recv().unwrap();
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+
+    // stalled client is dropped before the whole response is sent
+    thread::sleep(Duration::from_secs(1));
+    let mut buf = Vec::new();
+    let _ = stream.read_to_end(&mut buf);
+    assert!(buf.len() < SIZE);
+
+    sys.stop();
+}
+
+#[test]
+fn test_write_buffer_backpressure() {
+    let produced = Arc::new(AtomicUsize::new(0));
+    let (tx, rx) = mpsc::channel();
+
+    let counter = produced.clone();
+    thread::spawn(move || {
+        let sys = System::new("test");
+        let tcp = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = tcp.local_addr().unwrap();
+
+        HttpServer::new(move || {
+            let counter = counter.clone();
+            App::new().service(web::resource("/").to(move || {
+                let counter = counter.clone();
+                async move {
+                    // endless body, produced only when dispatcher polls it
+                    web::HttpResponse::Ok().streaming(futures::stream::repeat(()).map(
+                        move |_| {
+                            counter.fetch_add(1024, Ordering::Relaxed);
+                            Ok::<_, std::io::Error>(bytes::Bytes::from_static(&[b'x'; 1024]))
+                        },
+                    ))
+                }
+            }))
+        })
+        .workers(1)
+        .disable_signals()
+        .write_buffer(65_536, 16_384)
+        .listen(tcp)
+        .unwrap()
+        .run();
+        tx.send((System::current(), addr)).unwrap();
+        sys.run()
+    });
+    let (sys, addr) = rx.recv().unwrap();
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+
+    // client does not read, producer is bounded by socket and write buffers
+    thread::sleep(Duration::from_millis(500));
+    let first = produced.load(Ordering::Relaxed);
+    thread::sleep(Duration::from_millis(500));
+    assert_eq!(produced.load(Ordering::Relaxed), first);
+    assert!(first < 32 * 1024 * 1024);
+
+    // reading resumes the producer
+    let mut buf = [0; 65_536];
+    for _ in 0..64 {
+        stream.read_exact(&mut buf).unwrap();
+    }
+    thread::sleep(Duration::from_millis(100));
+    assert!(produced.load(Ordering::Relaxed) > first);
+
+    sys.stop();
+}
+
+#[test]
+fn test_read_buffer() {
+    const SIZE: usize = 200_000;
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let sys = System::new("test");
+        let tcp = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = tcp.local_addr().unwrap();
+
+        HttpServer::new(|| {
+            App::new().service(
+                web::resource("/")
+                    .to(|body: bytes::Bytes| async move { body.len().to_string() }),
+            )
+        })
+        .workers(1)
+        .disable_signals()
+        .read_buffer(16, 1024)
+        .listen(tcp)
+        .unwrap()
+        .run();
+        tx.send((System::current(), addr)).unwrap();
+        sys.run()
+    });
+    let (sys, addr) = rx.recv().unwrap();
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+
+    // read chunk grows for the large body and shrinks while connection is idle
+    for _ in 0..2 {
+        stream
+            .write_all(
+                format!(
+                    "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n",
+                    SIZE
+                )
+                .as_bytes(),
+            )
+            .unwrap();
+        stream.write_all(&[b'x'; SIZE]).unwrap();
+
+        let mut buf = [0; 1024];
+        let n = stream.read(&mut buf).unwrap();
+        let res = String::from_utf8_lossy(&buf[..n]);
+        assert!(res.starts_with("HTTP/1.1 200 OK"));
+        assert!(res.ends_with("200000"));
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    sys.stop();
+}
+
+#[test]
+fn test_request_header_timeout() {
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let sys = System::new("test");
+        let tcp = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = tcp.local_addr().unwrap();
+
+        HttpServer::new(|| App::new().service(web::resource("/").to(|| async { "ok" })))
+            .workers(1)
+            .disable_signals()
+            .keep_alive(30)
+            .request_header_timeout(Duration::from_millis(200))
+            .listen(tcp)
+            .unwrap()
+            .run();
+        tx.send((System::current(), addr)).unwrap();
+        sys.run()
+    });
+    let (sys, addr) = rx.recv().unwrap();
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    read_response(&mut stream);
+
+    // second request head is never completed
+    stream.write_all(b"GET / HTTP/1.1\r\nHost: ").unwrap();
+    let start = Instant::now();
+    let mut buf = [0; 1024];
+    let n = stream.read(&mut buf).unwrap();
+    assert!(buf[..n].starts_with(b"HTTP/1.1 408"));
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(stream.read(&mut buf).unwrap_or(0), 0);
+
+    sys.stop();
+}
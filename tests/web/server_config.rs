@@ -0,0 +1,63 @@
+use kayrx::web::{ServerConfig, ServerConfigError};
+
+#[test]
+fn test_deserialize() {
+    let cfg: ServerConfig = serde_json::from_str(
+        r#"{
+            "bind": ["127.0.0.1:8080", "127.0.0.1:8081"],
+            "tls": {"bind": ["127.0.0.1:8443"], "cert": "cert.pem", "key": "key.pem"},
+            "workers": 2,
+            "keep_alive": 0,
+            "shutdown_timeout": 10
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(cfg.bind.len(), 2);
+    assert_eq!(cfg.workers, Some(2));
+    assert_eq!(cfg.keep_alive, Some(0));
+    assert_eq!(cfg.client_timeout, None);
+    assert_eq!(cfg.tls.as_ref().unwrap().bind, vec!["127.0.0.1:8443".to_owned()]);
+    assert!(cfg.validate().is_ok());
+
+    assert!(serde_json::from_str::<ServerConfig>(r#"{"unknown": 1}"#).is_err());
+}
+
+#[test]
+fn test_validate() {
+    let cfg = ServerConfig::default();
+    match cfg.validate() {
+        Err(ServerConfigError::NoAddress) => (),
+        _ => panic!(),
+    }
+
+    let cfg = ServerConfig {
+        bind: vec!["127.0.0.1:8080".to_owned()],
+        workers: Some(0),
+        ..Default::default()
+    };
+    match cfg.validate() {
+        Err(ServerConfigError::Invalid(name, _)) => assert_eq!(name, "workers"),
+        _ => panic!(),
+    }
+
+    let cfg = ServerConfig {
+        bind: vec!["127.0.0.1".to_owned()],
+        ..Default::default()
+    };
+    match cfg.validate() {
+        Err(ServerConfigError::Address(addr, _)) => assert_eq!(addr, "127.0.0.1"),
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn test_tls_load_error() {
+    let cfg: ServerConfig = serde_json::from_str(
+        r#"{"tls": {"bind": ["127.0.0.1:8443"], "cert": "missing.pem", "key": "missing.pem"}}"#,
+    )
+    .unwrap();
+    match cfg.tls.unwrap().load() {
+        Err(ServerConfigError::Certificate(..)) => (),
+        _ => panic!(),
+    }
+}
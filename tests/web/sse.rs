@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::stream;
+use kayrx::http::header::{CACHE_CONTROL, CONTENT_TYPE};
+use kayrx::http::error::Error;
+use kayrx::http::StatusCode;
+use kayrx::timer::delay_for;
+use kayrx::web::sse::{Event, Sse};
+use kayrx::web::test::{self, TestRequest};
+use kayrx::web::{self, App};
+
+#[kayrx::test]
+async fn test_events() {
+    let mut srv = test::init_service(App::new().service(web::resource("/").to(|| async {
+        Sse::new(stream::iter(vec![
+            Ok::<_, Error>(Event::data("hello").event("greeting").id("1")),
+            Ok(Event::data("line 1\nline 2").id("2\n")),
+            Ok(Event::json(&vec![1, 2]).unwrap().retry(Duration::from_secs(3))),
+        ]))
+        .retry(Duration::from_millis(500))
+    })))
+    .await;
+
+    let req = TestRequest::default().to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), "text/event-stream");
+    assert_eq!(resp.headers().get(CACHE_CONTROL).unwrap(), "no-cache");
+    assert_eq!(
+        test::read_body(resp).await,
+        Bytes::from_static(
+            b"retry: 500\n\n\
+              id: 1\nevent: greeting\ndata: hello\n\n\
+              id: 2\ndata: line 1\ndata: line 2\n\n\
+              retry: 3000\ndata: [1,2]\n\n"
+        )
+    );
+}
+
+#[kayrx::test]
+async fn test_keep_alive() {
+    let mut srv = test::init_service(App::new().service(web::resource("/").to(|| async {
+        let events = stream::once(async {
+            delay_for(Duration::from_millis(250)).await;
+            Ok::<_, Error>(Event::data("late"))
+        });
+        Sse::new(events).keep_alive(Duration::from_millis(100))
+    })))
+    .await;
+
+    let req = TestRequest::default().to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    let body = test::read_body(resp).await;
+    assert_eq!(body, Bytes::from_static(b":\n\n:\n\ndata: late\n\n"));
+}
+
+#[kayrx::test]
+async fn test_disable_keep_alive() {
+    let mut srv = test::init_service(App::new().service(web::resource("/").to(|| async {
+        let events = stream::once(async {
+            delay_for(Duration::from_millis(100)).await;
+            Ok::<_, Error>(Event::data("late"))
+        });
+        Sse::new(events)
+            .keep_alive(Duration::from_millis(20))
+            .disable_keep_alive()
+    })))
+    .await;
+
+    let req = TestRequest::default().to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    let body = test::read_body(resp).await;
+    assert_eq!(body, Bytes::from_static(b"data: late\n\n"));
+}
@@ -172,6 +172,32 @@ async fn test_request_response_json() {
     assert_eq!(&result.name, "User name");
 }
 
+#[kayrx::test]
+async fn test_read_body_json() {
+    let mut app = init_service(App::new().service(web::resource("/people").route(
+        web::post().to(|person: types::Json<Person>| {
+            async { HttpResponse::Created().json(person.into_inner()) }
+        }),
+    )))
+    .await;
+
+    let payload = Person {
+        id: "12345".to_string(),
+        name: "User name".to_string(),
+    };
+
+    let req = TestRequest::post()
+        .uri("/people")
+        .set_json(&payload)
+        .to_request();
+    let resp = call_service(&mut app, req).await;
+    assert_eq!(resp.status(), kayrx::http::StatusCode::CREATED);
+
+    let result: Person = read_body_json(resp).await;
+    assert_eq!(&result.id, "12345");
+    assert_eq!(&result.name, "User name");
+}
+
 #[kayrx::test]
 async fn test_async_with_block() {
     async fn async_with_block() -> Result<HttpResponse, Error> {
@@ -212,4 +238,17 @@ async fn test_server_data() {
     let req = TestRequest::post().uri("/index.html").to_request();
     let res = app.call(req).await.unwrap();
     assert!(res.status().is_success());
-}
\ No newline at end of file
+}
+#[kayrx::test]
+async fn test_memory_server() {
+    let srv = start_with(config().memory(), || {
+        App::new().service(
+            web::resource("/").route(web::post().to(|body: String| async move { body })),
+        )
+    });
+
+    let mut res = srv.post("/").send_body("in memory").await.unwrap();
+    assert!(res.status().is_success());
+    let body = res.body().await.unwrap();
+    assert_eq!(body, "in memory");
+}
@@ -0,0 +1,117 @@
+use std::io::Write;
+use std::time::Duration;
+
+use bytes::Bytes;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use kayrx::http::{header, StatusCode};
+use kayrx::web::dev::DecompressLimits;
+use kayrx::web::test::{call_service, init_service, read_body, TestRequest};
+use kayrx::web::types::PayloadConfig;
+use kayrx::web::{self, App, HttpResponse};
+
+fn gzip(data: &[u8]) -> Bytes {
+    let mut enc = GzEncoder::new(Vec::new(), Compression::best());
+    enc.write_all(data).unwrap();
+    Bytes::from(enc.finish().unwrap())
+}
+
+#[kayrx::test]
+async fn test_decompression_limits() {
+    let mut srv = init_service(
+        App::new()
+            .app_data(PayloadConfig::new(4 * 1024 * 1024))
+            .app_data(
+                DecompressLimits::new()
+                    .max_size(1024 * 1024)
+                    .max_ratio(100)
+                    .timeout(Duration::from_secs(5)),
+            )
+            .service(web::resource("/").to(|body: Bytes| {
+                async move { HttpResponse::Ok().body(body.len().to_string()) }
+            })),
+    )
+    .await;
+
+    // regular compressed payload
+    let text: Vec<u8> = (0..20_000u32)
+        .flat_map(|i| i.to_string().into_bytes())
+        .collect();
+    let req = TestRequest::post()
+        .uri("/")
+        .header(header::CONTENT_ENCODING, "gzip")
+        .set_payload(gzip(&text))
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(read_body(resp).await, Bytes::from(text.len().to_string()));
+
+    // highly compressible payload exceeds ratio limit
+    let req = TestRequest::post()
+        .uri("/")
+        .header(header::CONTENT_ENCODING, "gzip")
+        .set_payload(gzip(&vec![0u8; 512 * 1024]))
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    // decoded payload exceeds size limit
+    let random: Vec<u8> = (0..2 * 1024 * 1024u32)
+        .map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8)
+        .collect();
+    let req = TestRequest::post()
+        .uri("/")
+        .header(header::CONTENT_ENCODING, "gzip")
+        .set_payload(gzip(&random))
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[kayrx::test]
+async fn test_decompression_default_limits() {
+    let mut srv = init_service(
+        App::new()
+            .app_data(PayloadConfig::new(32 * 1024 * 1024))
+            .service(web::resource("/").to(|body: Bytes| {
+                async move { HttpResponse::Ok().body(body.len().to_string()) }
+            })),
+    )
+    .await;
+
+    // default limits allow 16Mb of decoded payload
+    let req = TestRequest::post()
+        .uri("/")
+        .header(header::CONTENT_ENCODING, "gzip")
+        .set_payload(gzip(&vec![0u8; 16 * 1024 * 1024]))
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let req = TestRequest::post()
+        .uri("/")
+        .header(header::CONTENT_ENCODING, "gzip")
+        .set_payload(gzip(&vec![0u8; 16 * 1024 * 1024 + 1]))
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    // unlimited decompression
+    let mut srv = init_service(
+        App::new()
+            .app_data(PayloadConfig::new(32 * 1024 * 1024))
+            .app_data(DecompressLimits::unlimited())
+            .service(web::resource("/").to(|body: Bytes| {
+                async move { HttpResponse::Ok().body(body.len().to_string()) }
+            })),
+    )
+    .await;
+    let req = TestRequest::post()
+        .uri("/")
+        .header(header::CONTENT_ENCODING, "gzip")
+        .set_payload(gzip(&vec![0u8; 16 * 1024 * 1024 + 1]))
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(read_body(resp).await, Bytes::from((16 * 1024 * 1024 + 1).to_string()));
+}
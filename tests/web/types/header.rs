@@ -0,0 +1,70 @@
+use kayrx::http::header::{self, ContentType, IfModifiedSince};
+use kayrx::http::error::InternalError;
+use kayrx::http::StatusCode;
+use kayrx::web::test::TestRequest;
+use kayrx::web::types::{Header, HeaderConfig};
+use kayrx::web::{FromRequest, HttpResponse};
+
+#[kayrx::test]
+async fn test_header() {
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/json")
+        .to_http_parts();
+    let ct = Header::<ContentType>::from_request(&req, &mut pl)
+        .await
+        .unwrap();
+    assert_eq!(ct.into_inner(), ContentType::json());
+
+    // missing header
+    let (req, mut pl) = TestRequest::default().to_http_parts();
+    let err = Header::<ContentType>::from_request(&req, &mut pl)
+        .await
+        .unwrap_err();
+    assert_eq!(err.as_response_error().status_code(), StatusCode::BAD_REQUEST);
+
+    // malformed header
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "garbage")
+        .to_http_parts();
+    let err = Header::<ContentType>::from_request(&req, &mut pl)
+        .await
+        .unwrap_err();
+    assert_eq!(err.as_response_error().status_code(), StatusCode::BAD_REQUEST);
+}
+
+#[kayrx::test]
+async fn test_optional_header() {
+    let (req, mut pl) = TestRequest::default().to_http_parts();
+    let hdr = Header::<Option<IfModifiedSince>>::from_request(&req, &mut pl)
+        .await
+        .unwrap();
+    assert!(hdr.is_none());
+
+    let (req, mut pl) = TestRequest::default()
+        .header(header::IF_MODIFIED_SINCE, "Sun, 07 Nov 1994 08:48:37 GMT")
+        .to_http_parts();
+    let hdr = Header::<Option<IfModifiedSince>>::from_request(&req, &mut pl)
+        .await
+        .unwrap();
+    assert!(hdr.is_some());
+
+    let (req, mut pl) = TestRequest::default()
+        .header(header::IF_MODIFIED_SINCE, "yesterday")
+        .to_http_parts();
+    assert!(Header::<Option<IfModifiedSince>>::from_request(&req, &mut pl)
+        .await
+        .is_err());
+}
+
+#[kayrx::test]
+async fn test_error_handler() {
+    let (req, mut pl) = TestRequest::default()
+        .app_data(HeaderConfig::default().error_handler(|err, _| {
+            InternalError::from_response(err, HttpResponse::Conflict().finish()).into()
+        }))
+        .to_http_parts();
+    let err = Header::<ContentType>::from_request(&req, &mut pl)
+        .await
+        .unwrap_err();
+    assert_eq!(err.as_response_error().status_code(), StatusCode::CONFLICT);
+}
@@ -0,0 +1,95 @@
+use bytes::Bytes;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use kayrx::http::error::InternalError;
+use kayrx::http::header::CONTENT_TYPE;
+use kayrx::http::StatusCode;
+use kayrx::web::error::JsonPayloadError;
+use kayrx::web::test::{self, TestRequest};
+use kayrx::web::types::{Json, JsonConfig};
+use kayrx::web::{self, App, HttpResponse};
+
+#[derive(Deserialize)]
+struct Info {
+    name: String,
+}
+
+#[kayrx::test]
+async fn test_json_config() {
+    let mut srv = test::init_service(
+        App::new()
+            .app_data(
+                JsonConfig::default()
+                    .strict_content_type(false)
+                    .max_depth(2)
+                    .error_handler(|err, _| {
+                        let status = match err {
+                            JsonPayloadError::Deserialize(_) => {
+                                StatusCode::UNPROCESSABLE_ENTITY
+                            }
+                            _ => StatusCode::BAD_REQUEST,
+                        };
+                        let res = HttpResponse::build(status)
+                            .json(json!({ "message": err.to_string() }));
+                        InternalError::from_response(err, res).into()
+                    }),
+            )
+            .service(
+                web::resource("/").to(|info: Json<Info>| async move { info.name.clone() }),
+            )
+            .service(
+                web::resource("/any").to(|val: Json<Value>| async move { val.to_string() }),
+            ),
+    )
+    .await;
+
+    // content type is not required
+    let req = TestRequest::post()
+        .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(test::read_body(resp).await, Bytes::from_static(b"test"));
+
+    // custom error response
+    let req = TestRequest::post()
+        .header(CONTENT_TYPE, "application/json")
+        .set_payload(Bytes::from_static(b"{\"name\": 1}"))
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+    assert!(body["message"].as_str().unwrap().contains("deserialize"));
+
+    // nesting depth, brackets in strings are ignored
+    let req = TestRequest::post()
+        .uri("/any")
+        .set_payload(Bytes::from_static(b"{\"a\": [\"[[[{\"]}"))
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let req = TestRequest::post()
+        .uri("/any")
+        .set_payload(Bytes::from_static(b"{\"a\": [[1]]}"))
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[kayrx::test]
+async fn test_json_strict_content_type() {
+    let mut srv = test::init_service(
+        App::new().service(
+            web::resource("/").to(|info: Json<Info>| async move { info.name.clone() }),
+        ),
+    )
+    .await;
+
+    let req = TestRequest::post()
+        .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
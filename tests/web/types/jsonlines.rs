@@ -0,0 +1,62 @@
+use bytes::Bytes;
+use futures::stream;
+use serde::Serialize;
+
+use kayrx::http::header::CONTENT_TYPE;
+use kayrx::http::error::Error;
+use kayrx::web::test::{load_stream, TestRequest};
+use kayrx::web::types::*;
+use kayrx::web::{responder, Responder};
+
+#[derive(Serialize)]
+struct Row {
+    id: u32,
+    name: &'static str,
+}
+
+fn rows() -> impl futures::Stream<Item = Result<Row, Error>> {
+    stream::iter(vec![
+        Ok(Row { id: 1, name: "a" }),
+        Ok(Row { id: 2, name: "b" }),
+    ])
+}
+
+#[kayrx::test]
+async fn test_json_lines() {
+    let req = TestRequest::default().to_http_request();
+    let mut resp = JsonLines(rows()).respond_to(&req).await.unwrap();
+    assert_eq!(
+        resp.headers().get(CONTENT_TYPE).unwrap(),
+        "application/x-ndjson"
+    );
+    let body = load_stream(resp.take_body()).await.unwrap();
+    assert_eq!(
+        body,
+        Bytes::from_static(b"{\"id\":1,\"name\":\"a\"}\n{\"id\":2,\"name\":\"b\"}\n")
+    );
+
+    let mut resp = responder::JsonLines(rows()).respond_to(&req).await.unwrap();
+    let body = load_stream(resp.take_body()).await.unwrap();
+    assert_eq!(body.iter().filter(|b| **b == b'\n').count(), 2);
+}
+
+#[kayrx::test]
+async fn test_csv_stream() {
+    let req = TestRequest::default().to_http_request();
+    let mut resp = CsvStream::new(rows()).respond_to(&req).await.unwrap();
+    assert_eq!(
+        resp.headers().get(CONTENT_TYPE).unwrap(),
+        "text/csv; charset=utf-8"
+    );
+    let body = load_stream(resp.take_body()).await.unwrap();
+    assert_eq!(body, Bytes::from_static(b"id,name\n1,a\n2,b\n"));
+
+    let mut resp = csv::Stream::new(rows())
+        .has_headers(false)
+        .delimiter(b';')
+        .respond_to(&req)
+        .await
+        .unwrap();
+    let body = load_stream(resp.take_body()).await.unwrap();
+    assert_eq!(body, Bytes::from_static(b"1;a\n2;b\n"));
+}
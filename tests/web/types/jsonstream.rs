@@ -0,0 +1,63 @@
+use futures::stream::StreamExt;
+use serde::Deserialize;
+
+use kayrx::http::header;
+use kayrx::krse::Bytes;
+use kayrx::web::error::JsonPayloadError;
+use kayrx::web::test::TestRequest;
+use kayrx::web::types::*;
+use kayrx::web::FromRequest;
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Event {
+    id: u32,
+}
+
+#[kayrx::test]
+async fn test_ndjson() {
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/x-ndjson")
+        .set_payload(Bytes::from_static(b"{\"id\": 1}\n\n{\"id\": 2}\r\n{\"id\": 3}"))
+        .to_http_parts();
+
+    let stream = JsonStream::<Event>::from_request(&req, &mut pl).await.unwrap();
+    let items: Vec<_> = stream.map(|item| item.unwrap()).collect().await;
+    assert_eq!(items, vec![Event { id: 1 }, Event { id: 2 }, Event { id: 3 }]);
+}
+
+#[kayrx::test]
+async fn test_json_seq() {
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/json-seq")
+        .set_payload(Bytes::from_static(b"\x1e{\"id\": 1}\n\x1e{\"id\": 2}\n"))
+        .to_http_parts();
+
+    let stream = JsonStream::<Event>::from_request(&req, &mut pl).await.unwrap();
+    let items: Vec<_> = stream.map(|item| item.unwrap()).collect().await;
+    assert_eq!(items, vec![Event { id: 1 }, Event { id: 2 }]);
+}
+
+#[kayrx::test]
+async fn test_item_limit() {
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/x-ndjson")
+        .set_payload(Bytes::from_static(b"{\"id\": 1}\n{\"id\":      2}\n{\"id\": 3}"))
+        .app_data(JsonStreamConfig::default().limit(10))
+        .to_http_parts();
+
+    let mut stream = JsonStream::<Event>::from_request(&req, &mut pl).await.unwrap();
+    assert_eq!(stream.next().await.unwrap().unwrap(), Event { id: 1 });
+    match stream.next().await {
+        Some(Err(JsonPayloadError::Overflow)) => (),
+        _ => panic!("expected overflow"),
+    }
+    assert!(stream.next().await.is_none());
+}
+
+#[kayrx::test]
+async fn test_content_type() {
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/json")
+        .to_http_parts();
+    assert!(JsonStream::<Event>::from_request(&req, &mut pl).await.is_err());
+}
@@ -1,6 +1,17 @@
+mod decompress;
 // mod form;
+mod header;
 // mod json;
+mod json_config;
+mod jsonlines;
+mod jsonstream;
 mod path;
+mod payload_config;
 // mod payload;
 mod query;
 mod readlines;
+mod xml;
+#[cfg(feature = "protobuf")]
+mod protobuf;
+mod precondition;
+mod patch;
@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use kayrx::http::header;
+use kayrx::http::StatusCode;
+use kayrx::krse::Bytes;
+use kayrx::web::error::PatchError;
+use kayrx::web::test::TestRequest;
+use kayrx::web::types::*;
+use kayrx::web::FromRequest;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct User {
+    name: String,
+    email: Option<String>,
+    tags: Vec<String>,
+}
+
+fn user() -> User {
+    User {
+        name: "john".to_owned(),
+        email: Some("john@example.com".to_owned()),
+        tags: vec!["a".to_owned()],
+    }
+}
+
+#[kayrx::test]
+async fn test_json_patch_extract() {
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/json-patch+json")
+        .set_payload(Bytes::from_static(
+            br#"[{"op": "replace", "path": "/name", "value": "jane"},
+                 {"op": "add", "path": "/tags/-", "value": "b"},
+                 {"op": "test", "path": "/tags/0", "value": "a"}]"#,
+        ))
+        .to_http_parts();
+    let patch = JsonPatch::from_request(&req, &mut pl).await.unwrap();
+    assert_eq!(patch.len(), 3);
+
+    let patched = patch.apply_to(&user()).unwrap();
+    assert_eq!(patched.name, "jane");
+    assert_eq!(patched.tags, vec!["a".to_owned(), "b".to_owned()]);
+}
+
+#[kayrx::test]
+async fn test_json_patch_errors() {
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/json")
+        .set_payload(Bytes::from_static(b"[]"))
+        .to_http_parts();
+    let res = JsonPatch::from_request(&req, &mut pl).await;
+    let resp = res.err().unwrap().as_response_error().error_response();
+    assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/json-patch+json")
+        .set_payload(Bytes::from_static(br#"[{"op": "add", "path": "/a"}]"#))
+        .to_http_parts();
+    let res = JsonPatch::from_request(&req, &mut pl).await;
+    let resp = res.err().unwrap().as_response_error().error_response();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/json-patch+json")
+        .set_payload(Bytes::from_static(
+            br#"[{"op": "remove", "path": "name"}]"#,
+        ))
+        .to_http_parts();
+    assert!(JsonPatch::from_request(&req, &mut pl).await.is_err());
+
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/json-patch+json")
+        .set_payload(Bytes::from_static(
+            br#"[{"op": "move", "from": "/a", "path": "/a/b"}]"#,
+        ))
+        .to_http_parts();
+    assert!(JsonPatch::from_request(&req, &mut pl).await.is_err());
+}
+
+#[test]
+fn test_json_patch_apply() {
+    let mut doc = json!({"a": {"b~c": [1, 2]}, "d": "x"});
+    let patch = JsonPatch::from_value(json!([
+        {"op": "copy", "from": "/a/b~0c/1", "path": "/a/b~0c/0"},
+        {"op": "move", "from": "/d", "path": "/e"},
+        {"op": "remove", "path": "/a/b~0c/2"},
+        {"op": "add", "path": "/f~1g", "value": null},
+    ]))
+    .unwrap();
+    patch.apply(&mut doc).unwrap();
+    assert_eq!(doc, json!({"a": {"b~c": [2, 1]}, "e": "x", "f/g": null}));
+
+    // failed patch leaves document untouched
+    let patch = JsonPatch::from_value(json!([
+        {"op": "remove", "path": "/e"},
+        {"op": "test", "path": "/a/b~0c/0", "value": 5},
+    ]))
+    .unwrap();
+    match patch.apply(&mut doc) {
+        Err(PatchError::Conflict(_)) => (),
+        _ => panic!(),
+    }
+    assert_eq!(doc, json!({"a": {"b~c": [2, 1]}, "e": "x", "f/g": null}));
+
+    let patch =
+        JsonPatch::from_value(json!([{"op": "replace", "path": "/a/x", "value": 1}]))
+            .unwrap();
+    assert!(patch.apply(&mut doc).is_err());
+    let patch =
+        JsonPatch::from_value(json!([{"op": "add", "path": "/a/b~0c/05", "value": 1}]))
+            .unwrap();
+    assert!(patch.apply(&mut doc).is_err());
+}
+
+#[kayrx::test]
+async fn test_merge_patch() {
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/merge-patch+json")
+        .set_payload(Bytes::from_static(
+            br#"{"name": "jane", "email": null, "extra": {"a": 1}}"#,
+        ))
+        .to_http_parts();
+    let patch = MergePatch::<User>::from_request(&req, &mut pl).await.unwrap();
+
+    let patched = patch.apply(&user()).unwrap();
+    assert_eq!(
+        patched,
+        User {
+            name: "jane".to_owned(),
+            email: None,
+            tags: vec!["a".to_owned()],
+        }
+    );
+
+    let mut doc = json!({"a": "b", "c": {"d": "e", "f": "g"}});
+    patch.apply_value(&mut doc);
+    assert_eq!(
+        doc,
+        json!({"a": "b", "c": {"d": "e", "f": "g"}, "name": "jane", "extra": {"a": 1}})
+    );
+
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/merge-patch+json")
+        .set_payload(Bytes::from_static(br#"{"tags": "none"}"#))
+        .to_http_parts();
+    let patch = MergePatch::<User>::from_request(&req, &mut pl).await.unwrap();
+    match patch.apply(&user()) {
+        Err(PatchError::Deserialize(_)) => (),
+        _ => panic!(),
+    }
+
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/json-patch+json")
+        .set_payload(Bytes::from_static(b"{}"))
+        .to_http_parts();
+    assert!(MergePatch::<User>::from_request(&req, &mut pl).await.is_err());
+}
@@ -15,7 +15,7 @@ struct MyStruct {
     value: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Debug, Deserialize)]
 struct Test2 {
     key: String,
     value: u32,
@@ -126,4 +126,32 @@ async fn test_custom_err_handler() {
     let res: HttpResponse = s.into();
 
     assert_eq!(res.status(), http::StatusCode::CONFLICT);
-}
\ No newline at end of file
+}
+#[kayrx::test]
+async fn test_error_field_path() {
+    let mut req = TestRequest::with_uri("/name/user1/")
+        .app_data(PathConfig::default().error_handler(|err, _| {
+            match err {
+                kayrx::web::error::PathError::Deserialize(ref e) => {
+                    assert_eq!(e.path(), Some("value"));
+                }
+            }
+            err.into()
+        }))
+        .to_srv_request();
+
+    let resource = ResourceDef::new("/{key}/{value}/");
+    resource.match_path(req.match_info_mut());
+
+    let (req, mut pl) = req.into_parts();
+    let s = Path::<Test2>::from_request(&req, &mut pl)
+        .await
+        .unwrap_err();
+    let res: HttpResponse = s.into();
+
+    assert_eq!(res.status(), http::StatusCode::BAD_REQUEST);
+    assert_eq!(
+        res.headers().get(http::header::CONTENT_TYPE).unwrap(),
+        "application/json"
+    );
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use bytes::Bytes;
+use kayrx::http::header::CONTENT_TYPE;
+use kayrx::http::StatusCode;
+use kayrx::web::test::{self, TestRequest};
+use kayrx::web::types::{Form, Json, PayloadConfig};
+use kayrx::web::{self, App};
+
+#[kayrx::test]
+async fn test_payload_limit() {
+    let mut srv = test::init_service(
+        App::new()
+            .app_data(PayloadConfig::new(16))
+            .service(web::resource("/bytes").to(|body: Bytes| async move { body }))
+            .service(web::resource("/text").to(|body: String| async move { body }))
+            .service(
+                web::resource("/json")
+                    .to(|body: Json<Vec<u32>>| async move { body.len().to_string() }),
+            )
+            .service(web::resource("/form").to(
+                |body: Form<HashMap<String, String>>| async move { body.len().to_string() },
+            ))
+            .service(
+                web::resource("/large")
+                    .app_data(PayloadConfig::new(1024))
+                    .to(|body: Bytes| async move { body }),
+            ),
+    )
+    .await;
+
+    let req = TestRequest::post()
+        .uri("/bytes")
+        .set_payload(Bytes::from_static(b"0123456789"))
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    for path in &["/bytes", "/text"] {
+        let req = TestRequest::post()
+            .uri(path)
+            .set_payload(Bytes::from_static(b"0123456789abcdefghij"))
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    let req = TestRequest::post()
+        .uri("/json")
+        .header(CONTENT_TYPE, "application/json")
+        .set_payload(Bytes::from_static(b"[1, 2, 3, 4, 5, 6, 7, 8, 9]"))
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    let req = TestRequest::post()
+        .uri("/form")
+        .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .set_payload(Bytes::from_static(b"name=value&other=value"))
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    // per-resource config
+    let req = TestRequest::post()
+        .uri("/large")
+        .set_payload(Bytes::from_static(b"0123456789abcdefghij"))
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[kayrx::test]
+async fn test_payload_mimetypes() {
+    let mut srv = test::init_service(
+        App::new().service(
+            web::resource("/")
+                .app_data(
+                    PayloadConfig::default()
+                        .mimetype(mime::TEXT_PLAIN)
+                        .mimetype(mime::TEXT_CSV),
+                )
+                .to(|body: String| async move { body }),
+        ),
+    )
+    .await;
+
+    for ctype in &["text/plain; charset=utf-8", "text/csv"] {
+        let req = TestRequest::post()
+            .header(CONTENT_TYPE, *ctype)
+            .set_payload(Bytes::from_static(b"a,b"))
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    let req = TestRequest::post()
+        .header(CONTENT_TYPE, "application/json")
+        .set_payload(Bytes::from_static(b"[]"))
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let req = TestRequest::post()
+        .set_payload(Bytes::from_static(b"[]"))
+        .to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,88 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use kayrx::http::header::{self, EntityTag, HttpDate};
+use kayrx::http::StatusCode;
+use kayrx::http::error::ResponseError;
+use kayrx::web::test::TestRequest;
+use kayrx::web::types::*;
+use kayrx::web::{FromRequest, Responder};
+
+fn time(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+#[kayrx::test]
+async fn test_if_match() {
+    let v1 = EntityTag::strong("v1".to_owned());
+    let v2 = EntityTag::strong("v2".to_owned());
+
+    let (req, mut pl) = TestRequest::default()
+        .header(header::IF_MATCH, "\"v1\", \"v3\"")
+        .to_http_parts();
+    let cond = Precondition::from_request(&req, &mut pl).await.unwrap();
+    assert!(cond.is_conditional());
+    assert!(cond.check(Some(&v1), None).is_ok());
+    assert_eq!(cond.check(Some(&v2), None), Err(PreconditionFailed::new().etag(v2.clone())));
+    assert!(cond.check(None, None).is_err());
+
+    // weak etags never match
+    let (req, mut pl) = TestRequest::default()
+        .header(header::IF_MATCH, "W/\"v1\"")
+        .to_http_parts();
+    let cond = Precondition::from_request(&req, &mut pl).await.unwrap();
+    assert!(cond.check(Some(&v1), None).is_err());
+
+    let (req, mut pl) = TestRequest::default()
+        .header(header::IF_MATCH, "*")
+        .to_http_parts();
+    let cond = Precondition::from_request(&req, &mut pl).await.unwrap();
+    assert!(cond.check(Some(&v2), None).is_ok());
+    assert!(cond.check(None, None).is_err());
+}
+
+#[kayrx::test]
+async fn test_if_unmodified_since() {
+    let since = HttpDate::from(time(1_000_000));
+    let (req, mut pl) = TestRequest::default()
+        .header(header::IF_UNMODIFIED_SINCE, since)
+        .to_http_parts();
+    let cond = Precondition::from_request(&req, &mut pl).await.unwrap();
+    assert!(cond.check(None, Some(time(999_999))).is_ok());
+    assert!(cond.check(None, Some(time(1_000_000) + Duration::from_millis(500))).is_ok());
+    assert!(cond.check(None, Some(time(1_000_001))).is_err());
+    assert!(cond.check(None, None).is_ok());
+
+    // If-Unmodified-Since is ignored if If-Match is present
+    let since = HttpDate::from(time(1_000_000));
+    let (req, mut pl) = TestRequest::default()
+        .header(header::IF_MATCH, "*")
+        .header(header::IF_UNMODIFIED_SINCE, since)
+        .to_http_parts();
+    let cond = Precondition::from_request(&req, &mut pl).await.unwrap();
+    let v1 = EntityTag::strong("v1".to_owned());
+    assert!(cond.check(Some(&v1), Some(time(2_000_000))).is_ok());
+}
+
+#[kayrx::test]
+async fn test_unconditional() {
+    let (req, mut pl) = TestRequest::default().to_http_parts();
+    let cond = Precondition::from_request(&req, &mut pl).await.unwrap();
+    assert!(!cond.is_conditional());
+    assert!(cond.check(None, None).is_ok());
+}
+
+#[kayrx::test]
+async fn test_precondition_failed() {
+    let req = TestRequest::default().to_http_request();
+    let res = PreconditionFailed::new()
+        .etag(EntityTag::strong("v2".to_owned()))
+        .respond_to(&req)
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+    assert_eq!(res.headers().get(header::ETAG).unwrap(), "\"v2\"");
+
+    let res = PreconditionFailed::new().error_response();
+    assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+    assert!(res.headers().get(header::ETAG).is_none());
+}
@@ -0,0 +1,170 @@
+use bytes::{BufMut, BytesMut};
+use prost::Message;
+
+use kayrx::http::{header, StatusCode};
+use kayrx::krse::Bytes;
+use kayrx::web::test::{call_service, init_service, load_stream, read_body, TestRequest};
+use kayrx::web::types::*;
+use kayrx::web::{self, App, FromRequest, Responder};
+
+#[derive(Clone, PartialEq, Message)]
+struct Hello {
+    #[prost(string, tag = "1")]
+    name: String,
+}
+
+fn hello() -> Hello {
+    Hello {
+        name: "kayrx".to_string(),
+    }
+}
+
+fn encoded() -> Bytes {
+    let msg = hello();
+    let mut buf = BytesMut::with_capacity(msg.encoded_len());
+    msg.encode(&mut buf).unwrap();
+    buf.freeze()
+}
+
+fn framed() -> Bytes {
+    let msg = encoded();
+    let mut buf = BytesMut::new();
+    buf.put_u8(0);
+    buf.put_u32(msg.len() as u32);
+    buf.extend_from_slice(&msg);
+    buf.freeze()
+}
+
+#[kayrx::test]
+async fn test_extract_proto() {
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/x-protobuf")
+        .set_payload(encoded())
+        .to_http_parts();
+    let s = Protobuf::<Hello>::from_request(&req, &mut pl).await.unwrap();
+    assert_eq!(s.into_inner(), hello());
+
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/json")
+        .set_payload(encoded())
+        .to_http_parts();
+    let s = Protobuf::<Hello>::from_request(&req, &mut pl).await;
+    assert!(format!("{}", s.err().unwrap()).contains("Content type error"));
+
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/protobuf")
+        .set_payload(encoded())
+        .app_data(ProtobufConfig::default().limit(2))
+        .to_http_parts();
+    let s = Protobuf::<Hello>::from_request(&req, &mut pl).await;
+    assert!(format!("{}", s.err().unwrap())
+        .contains("Protobuf payload size is bigger than allowed"));
+}
+
+#[kayrx::test]
+async fn test_extract_grpc_web() {
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/grpc-web+proto")
+        .set_payload(framed())
+        .to_http_parts();
+    let s = Protobuf::<Hello>::from_request(&req, &mut pl).await.unwrap();
+    assert_eq!(s.name, "kayrx");
+
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/grpc-web-text")
+        .set_payload(base64::encode(&framed()))
+        .to_http_parts();
+    let s = Protobuf::<Hello>::from_request(&req, &mut pl).await.unwrap();
+    assert_eq!(s.name, "kayrx");
+
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/grpc-web+proto")
+        .set_payload(encoded())
+        .to_http_parts();
+    let s = Protobuf::<Hello>::from_request(&req, &mut pl).await;
+    assert!(format!("{}", s.err().unwrap()).contains("Malformed grpc-web frame"));
+}
+
+#[kayrx::test]
+async fn test_responder() {
+    let req = TestRequest::default().to_http_request();
+    let mut resp = Protobuf(hello()).respond_to(&req).await.unwrap();
+    assert_eq!(
+        resp.headers().get(header::CONTENT_TYPE).unwrap(),
+        "application/protobuf"
+    );
+    assert_eq!(load_stream(resp.take_body()).await.unwrap(), encoded());
+
+    let req = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/grpc-web+proto")
+        .to_http_request();
+    let mut resp = Protobuf(hello()).respond_to(&req).await.unwrap();
+    assert_eq!(
+        resp.headers().get(header::CONTENT_TYPE).unwrap(),
+        "application/grpc-web+proto"
+    );
+    let body = load_stream(resp.take_body()).await.unwrap();
+    let msg = framed();
+    assert_eq!(&body[..msg.len()], &msg[..]);
+    assert_eq!(body[msg.len()], 0x80);
+    assert_eq!(
+        &body[msg.len() + 5..],
+        &b"grpc-status:0\r\ngrpc-message:\r\n"[..]
+    );
+}
+
+#[kayrx::test]
+async fn test_grpc_web_errors() {
+    let mut srv = init_service(
+        App::new()
+            .app_data(ProtobufConfig::default().limit(8))
+            .service(web::resource("/").to(|msg: Protobuf<Hello>| async move { msg })),
+    )
+    .await;
+
+    // malformed frame, status is reported with trailers
+    let req = TestRequest::post()
+        .uri("/")
+        .header(header::CONTENT_TYPE, "application/grpc-web+proto")
+        .set_payload(encoded())
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers().get(header::CONTENT_TYPE).unwrap(),
+        "application/grpc-web+proto"
+    );
+    assert_eq!(resp.headers().get("grpc-status").unwrap(), "13");
+    assert_eq!(
+        resp.headers().get("grpc-message").unwrap(),
+        "Malformed grpc-web frame"
+    );
+    let body = read_body(resp).await;
+    let trailers = b"grpc-status:13\r\ngrpc-message:Malformed grpc-web frame\r\n";
+    assert_eq!(body[0], 0x80);
+    assert_eq!(&body[1..5], &(trailers.len() as u32).to_be_bytes());
+    assert_eq!(&body[5..], &trailers[..]);
+
+    // payload overflow
+    let req = TestRequest::post()
+        .uri("/")
+        .header(header::CONTENT_TYPE, "application/grpc-web-text+proto")
+        .set_payload(base64::encode(&framed()))
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("grpc-status").unwrap(), "8");
+    let body = base64::decode(&read_body(resp).await).unwrap();
+    assert_eq!(body[0], 0x80);
+    assert!(body[5..].starts_with(b"grpc-status:8\r\n"));
+
+    // plain protobuf requests keep http status
+    let req = TestRequest::post()
+        .uri("/")
+        .header(header::CONTENT_TYPE, "application/protobuf")
+        .set_payload(framed())
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    assert!(resp.headers().get("grpc-status").is_none());
+}
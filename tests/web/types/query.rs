@@ -69,4 +69,31 @@
                 .status(),
             StatusCode::UNPROCESSABLE_ENTITY
         );
-    }
\ No newline at end of file
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct Page {
+        #[allow(dead_code)]
+        page: u32,
+    }
+
+    #[kayrx::test]
+    async fn test_error_field_path() {
+        use kayrx::http::error::ResponseError;
+
+        let err = Query::<Page>::from_query("page=first").unwrap_err();
+        match err {
+            kayrx::web::error::QueryPayloadError::Deserialize(ref e) => {
+                assert_eq!(e.path(), Some("page"));
+            }
+        }
+        assert!(err.to_string().contains("page: invalid digit"));
+
+        let resp = err.error_response();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            resp.headers().get(kayrx::http::header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+    }
+
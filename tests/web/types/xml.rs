@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+
+use kayrx::http::header;
+use kayrx::http::StatusCode;
+use kayrx::krse::Bytes;
+use kayrx::web::error::XmlPayloadError;
+use kayrx::web::test::{load_stream, TestRequest};
+use kayrx::web::types::*;
+use kayrx::web::{FromRequest, Responder};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Order {
+    id: u32,
+    item: String,
+}
+
+fn order() -> Order {
+    Order {
+        id: 1,
+        item: "pen".to_string(),
+    }
+}
+
+#[kayrx::test]
+async fn test_extract() {
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/xml")
+        .set_payload(Bytes::from_static(
+            b"<Order><id>1</id><item>pen</item></Order>",
+        ))
+        .to_http_parts();
+    let s = Xml::<Order>::from_request(&req, &mut pl).await.unwrap();
+    assert_eq!(s.into_inner(), order());
+
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "text/xml; charset=utf-8")
+        .set_payload(Bytes::from_static(
+            b"<Order><id>1</id><item>pen</item></Order>",
+        ))
+        .to_http_parts();
+    let s = Xml::<Order>::from_request(&req, &mut pl).await.unwrap();
+    assert_eq!(s.item, "pen");
+
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/soap+xml")
+        .set_payload(Bytes::from_static(
+            b"<Order><id>1</id><item>pen</item></Order>",
+        ))
+        .to_http_parts();
+    assert!(Xml::<Order>::from_request(&req, &mut pl).await.is_ok());
+}
+
+#[kayrx::test]
+async fn test_extract_errors() {
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/json")
+        .set_payload(Bytes::from_static(b"<Order/>"))
+        .to_http_parts();
+    let s = Xml::<Order>::from_request(&req, &mut pl).await;
+    assert!(format!("{}", s.err().unwrap()).contains("Content type error"));
+
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/xml")
+        .header(header::CONTENT_LENGTH, "41")
+        .set_payload(Bytes::from_static(
+            b"<Order><id>1</id><item>pen</item></Order>",
+        ))
+        .app_data(XmlConfig::default().limit(10))
+        .to_http_parts();
+    let s = Xml::<Order>::from_request(&req, &mut pl).await;
+    assert!(format!("{}", s.err().unwrap())
+        .contains("Xml payload size is bigger than allowed"));
+
+    let (req, mut pl) = TestRequest::default()
+        .header(header::CONTENT_TYPE, "application/xml")
+        .set_payload(Bytes::from_static(b"<Order>"))
+        .app_data(
+            XmlConfig::default()
+                .error_handler(|_, _| XmlPayloadError::ContentType.into()),
+        )
+        .to_http_parts();
+    let s = Xml::<Order>::from_request(&req, &mut pl).await;
+    assert!(format!("{}", s.err().unwrap()).contains("Content type error"));
+}
+
+#[kayrx::test]
+async fn test_responder() {
+    let req = TestRequest::default().to_http_request();
+    let mut resp = Xml(order()).respond_to(&req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers().get(header::CONTENT_TYPE).unwrap(),
+        "application/xml; charset=utf-8"
+    );
+
+    let body = load_stream(resp.take_body()).await.unwrap();
+    let body = std::str::from_utf8(&body).unwrap();
+    assert!(body.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?><Order"));
+    assert!(!body.contains('\n'));
+    let de: Order = quick_xml::de::from_str(body).unwrap();
+    assert_eq!(de, order());
+}
+
+#[kayrx::test]
+async fn test_responder_pretty() {
+    let req = TestRequest::default()
+        .app_data(XmlConfig::default().pretty(2))
+        .to_http_request();
+    let mut resp = Xml(order()).respond_to(&req).await.unwrap();
+
+    let body = load_stream(resp.take_body()).await.unwrap();
+    let body = std::str::from_utf8(&body).unwrap();
+    assert!(body.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"));
+    assert!(body.ends_with('\n'));
+    let de: Order = quick_xml::de::from_str(body).unwrap();
+    assert_eq!(de, order());
+}
@@ -0,0 +1,132 @@
+use bytes::Bytes;
+use kayrx::http::{header, Method, StatusCode};
+use kayrx::web::test::{call_service, init_service, TestRequest};
+use kayrx::web::upload::{MemoryStorage, Upload};
+use kayrx::web::App;
+
+#[kayrx::test]
+async fn test_upload() {
+    let storage = MemoryStorage::new();
+    let mut srv = init_service(
+        App::new().service(Upload::new("/files", storage.clone()).max_size(1024)),
+    )
+    .await;
+
+    // discovery
+    let req = TestRequest::with_uri("/files")
+        .method(Method::OPTIONS)
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    assert_eq!(resp.headers().get("tus-version").unwrap(), "1.0.0");
+    assert_eq!(resp.headers().get("tus-max-size").unwrap(), "1024");
+
+    // protocol version is required
+    let req = TestRequest::post()
+        .uri("/files")
+        .header("upload-length", "11")
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+
+    let req = TestRequest::post()
+        .uri("/files")
+        .header("tus-resumable", "1.0.0")
+        .header("upload-length", "4096")
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    // creation
+    let req = TestRequest::post()
+        .uri("/files")
+        .header("tus-resumable", "1.0.0")
+        .header("upload-length", "11")
+        .header("upload-metadata", "filename aGVsbG8udHh0")
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    assert!(resp.headers().contains_key("upload-expires"));
+    let location = resp
+        .headers()
+        .get(header::LOCATION)
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_owned();
+    assert!(location.starts_with("/files/"));
+    let id = location.trim_start_matches("/files/").to_owned();
+
+    // first chunk
+    let req = TestRequest::with_uri(&location)
+        .method(Method::PATCH)
+        .header("tus-resumable", "1.0.0")
+        .header("upload-offset", "0")
+        .header(header::CONTENT_TYPE, "application/offset+octet-stream")
+        .set_payload(Bytes::from_static(b"hello"))
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    assert_eq!(resp.headers().get("upload-offset").unwrap(), "5");
+
+    // stale offset
+    let req = TestRequest::with_uri(&location)
+        .method(Method::PATCH)
+        .header("tus-resumable", "1.0.0")
+        .header("upload-offset", "0")
+        .header(header::CONTENT_TYPE, "application/offset+octet-stream")
+        .set_payload(Bytes::from_static(b" world"))
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+    // resume
+    let req = TestRequest::with_uri(&location)
+        .method(Method::HEAD)
+        .header("tus-resumable", "1.0.0")
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("upload-offset").unwrap(), "5");
+    assert_eq!(resp.headers().get("upload-length").unwrap(), "11");
+    assert_eq!(
+        resp.headers().get("upload-metadata").unwrap(),
+        "filename aGVsbG8udHh0"
+    );
+
+    // checksum mismatch, chunk is discarded
+    let req = TestRequest::with_uri(&location)
+        .method(Method::PATCH)
+        .header("tus-resumable", "1.0.0")
+        .header("upload-offset", "5")
+        .header("upload-checksum", "sha1 AAAAAAAAAAAAAAAAAAAAAAAAAAA=")
+        .header(header::CONTENT_TYPE, "application/offset+octet-stream")
+        .set_payload(Bytes::from_static(b" world"))
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status().as_u16(), 460);
+    assert_eq!(storage.content(&id).unwrap(), b"hello".to_vec());
+
+    // sha1(" world")
+    let req = TestRequest::with_uri(&location)
+        .method(Method::PATCH)
+        .header("tus-resumable", "1.0.0")
+        .header("upload-offset", "5")
+        .header("upload-checksum", "sha1 P4InJqDJ+1VmGOnLl/tkL372LW8=")
+        .header(header::CONTENT_TYPE, "application/offset+octet-stream")
+        .set_payload(Bytes::from_static(b" world"))
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    assert_eq!(resp.headers().get("upload-offset").unwrap(), "11");
+    assert_eq!(storage.content(&id).unwrap(), b"hello world".to_vec());
+
+    // termination
+    let req = TestRequest::with_uri(&location)
+        .method(Method::DELETE)
+        .header("tus-resumable", "1.0.0")
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    assert!(storage.content(&id).is_none());
+}
@@ -0,0 +1,100 @@
+use bytes::{Bytes, BytesMut};
+use kayrx::codec::{Decoder, Encoder};
+use kayrx::http::{header, StatusCode};
+use kayrx::web::test::{call_service, init_service, read_body, TestRequest};
+use kayrx::web::ws::{self, CloseCode, Message, WsContext, WsSession};
+use kayrx::web::{self, types, App, HttpRequest};
+use kayrx::websocket::{Codec, Frame};
+
+struct Echo;
+
+impl WsSession for Echo {
+    fn started(&mut self, ctx: &mut WsContext) {
+        ctx.text("welcome");
+    }
+
+    fn handle(&mut self, msg: Message, ctx: &mut WsContext) {
+        match msg {
+            Message::Text(text) => ctx.text(text),
+            Message::Binary(bin) => ctx.binary(bin),
+            _ => (),
+        }
+    }
+}
+
+fn ws_request(payload: Bytes) -> TestRequest {
+    TestRequest::default()
+        .header(header::UPGRADE, "websocket")
+        .header(header::CONNECTION, "upgrade")
+        .header(header::SEC_WEBSOCKET_VERSION, "13")
+        .header(header::SEC_WEBSOCKET_KEY, "13")
+        .set_payload(payload)
+}
+
+#[kayrx::test]
+async fn test_ws_session() {
+    let mut srv = init_service(App::new().service(web::resource("/").to(
+        |req: HttpRequest, stream: types::Payload| async move {
+            ws::start(Echo, &req, stream)
+        },
+    )))
+    .await;
+
+    let mut codec = Codec::new().client_mode();
+    let mut buf = BytesMut::new();
+    codec.encode(Message::Text("hello".into()), &mut buf).unwrap();
+    codec
+        .encode(Message::Ping(Bytes::from_static(b"ping")), &mut buf)
+        .unwrap();
+    codec
+        .encode(Message::Close(Some(CloseCode::Normal.into())), &mut buf)
+        .unwrap();
+
+    let req = ws_request(buf.freeze()).to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::SWITCHING_PROTOCOLS);
+
+    let mut body = BytesMut::from(&read_body(resp).await[..]);
+    let mut codec = Codec::new().client_mode();
+    assert_eq!(
+        codec.decode(&mut body).unwrap().unwrap(),
+        Frame::Text(Bytes::from_static(b"welcome"))
+    );
+    assert_eq!(
+        codec.decode(&mut body).unwrap().unwrap(),
+        Frame::Text(Bytes::from_static(b"hello"))
+    );
+    assert_eq!(
+        codec.decode(&mut body).unwrap().unwrap(),
+        Frame::Pong(Bytes::from_static(b"ping"))
+    );
+    assert_eq!(
+        codec.decode(&mut body).unwrap().unwrap(),
+        Frame::Close(Some(CloseCode::Normal.into()))
+    );
+    assert!(codec.decode(&mut body).unwrap().is_none());
+}
+
+#[kayrx::test]
+async fn test_ws_responder() {
+    let mut srv = init_service(App::new().service(web::resource("/").to(
+        |stream: types::Payload| async move {
+            ws::WebSocket::new(Echo, stream).protocols(vec!["graphql-ws", "chat"])
+        },
+    )))
+    .await;
+
+    let req = TestRequest::default().to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let req = ws_request(Bytes::new())
+        .header(header::SEC_WEBSOCKET_PROTOCOL, "chat, superchat")
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::SWITCHING_PROTOCOLS);
+    assert_eq!(
+        resp.headers().get(header::SEC_WEBSOCKET_PROTOCOL).unwrap(),
+        "chat"
+    );
+}